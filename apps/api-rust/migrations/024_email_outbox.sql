@@ -0,0 +1,35 @@
+-- Migration 024: Outbound email outbox
+-- Every email the platform sends is first written here and delivered by the
+-- in-process dispatcher, which retries transient failures with backoff.
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    template_id UUID REFERENCES email_templates(id) ON DELETE SET NULL,
+    category VARCHAR(64) NOT NULL DEFAULT 'custom',
+    to_email VARCHAR(255) NOT NULL,
+    subject VARCHAR(998) NOT NULL,
+    body_text TEXT NOT NULL,
+    body_html TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sending', 'sent', 'failed')),
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 5,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    sent_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_due ON email_outbox (next_attempt_at)
+    WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_email_outbox_tenant ON email_outbox (tenant_id, created_at DESC);
+
+-- RLS (not forced: the dispatcher runs outside any tenant context as table owner)
+ALTER TABLE email_outbox ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS email_outbox_tenant_isolation ON email_outbox;
+CREATE POLICY email_outbox_tenant_isolation ON email_outbox
+    USING (tenant_id::TEXT = current_setting('app.current_tenant', true));
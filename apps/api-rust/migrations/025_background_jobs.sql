@@ -0,0 +1,34 @@
+-- Migration 025: Durable background jobs
+-- Jobs are claimed with FOR UPDATE SKIP LOCKED so several API instances can
+-- share the queue. Periodic work is enqueued once per time slot using
+-- unique_key, which makes the schedule idempotent across instances/restarts.
+
+CREATE TABLE IF NOT EXISTS background_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID REFERENCES tenants(id),
+    job_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'dead')),
+    priority SMALLINT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 5,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    locked_by VARCHAR(100),
+    last_error TEXT,
+    unique_key VARCHAR(255),
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_background_jobs_unique_key ON background_jobs (unique_key);
+CREATE INDEX IF NOT EXISTS idx_background_jobs_due ON background_jobs (priority DESC, run_at)
+    WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS idx_background_jobs_running ON background_jobs (locked_at)
+    WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_background_jobs_type ON background_jobs (job_type, created_at DESC);
+
+-- System table: only the in-process runner reads it, and tenant_id is
+-- informational for tenant-scoped jobs, so no RLS policy is attached.
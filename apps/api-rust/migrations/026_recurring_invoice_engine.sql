@@ -0,0 +1,17 @@
+-- Migration 026: Recurring invoice generation
+-- anchor_date keeps month-end schedules stable (Jan 31 -> Feb 28 -> Mar 31),
+-- and the (recurring_invoice_id, issued_date) index makes issuance idempotent:
+-- each period of a template can produce at most one invoice.
+
+ALTER TABLE recurring_invoices ADD COLUMN IF NOT EXISTS anchor_date DATE;
+UPDATE recurring_invoices SET anchor_date = next_issue_date WHERE anchor_date IS NULL;
+ALTER TABLE recurring_invoices ALTER COLUMN anchor_date SET NOT NULL;
+
+ALTER TABLE recurring_invoices ADD COLUMN IF NOT EXISTS payment_terms_days INT NOT NULL DEFAULT 30
+    CHECK (payment_terms_days >= 0);
+
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS recurring_invoice_id UUID REFERENCES recurring_invoices(id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_invoices_recurring_period
+    ON invoices (recurring_invoice_id, issued_date)
+    WHERE recurring_invoice_id IS NOT NULL;
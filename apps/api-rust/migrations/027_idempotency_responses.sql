@@ -0,0 +1,24 @@
+-- Migration 027: Idempotency keys replay full responses
+-- Keys are scoped to the tenant and user that sent them. request_hash covers
+-- method, path and body, so reusing a key for a different request is
+-- rejected. A row with NULL response_status is in flight; locked_at lets a
+-- key abandoned by a crashed request be reclaimed.
+
+-- Keys only live for 24 hours and the old middleware never stored usable
+-- responses, so existing rows are safe to drop.
+DELETE FROM idempotency_keys;
+
+ALTER TABLE idempotency_keys DROP CONSTRAINT IF EXISTS idempotency_keys_tenant_id_key_key;
+DROP INDEX IF EXISTS idx_idempotency_keys_lookup;
+
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS user_id UUID NOT NULL;
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS request_method VARCHAR(10) NOT NULL;
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS response_headers JSONB;
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS locked_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
+ALTER TABLE idempotency_keys ALTER COLUMN request_hash SET NOT NULL;
+ALTER TABLE idempotency_keys DROP COLUMN IF EXISTS response_body;
+ALTER TABLE idempotency_keys ADD COLUMN response_body BYTEA;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_idempotency_keys_scope
+    ON idempotency_keys (tenant_id, user_id, key);
@@ -0,0 +1,45 @@
+-- Migration 028: Stripe webhook event log
+-- Every verified delivery is stored by Stripe event id before it is applied,
+-- so redeliveries are recognised and any event can be replayed from its
+-- stored payload. The table is cross-tenant (tenant_id is resolved while
+-- processing) and is only touched by the webhook path, hence no RLS.
+
+CREATE TABLE IF NOT EXISTS stripe_events (
+    id VARCHAR(255) PRIMARY KEY,
+    event_type VARCHAR(100) NOT NULL,
+    object_id VARCHAR(255),
+    tenant_id UUID REFERENCES tenants(id),
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'received'
+        CHECK (status IN ('received', 'processed', 'ignored', 'failed')),
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    stripe_created_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_stripe_events_object ON stripe_events (object_id);
+CREATE INDEX IF NOT EXISTS idx_stripe_events_failed ON stripe_events (received_at)
+    WHERE status = 'failed';
+
+-- Card payments: refunds and disputes are tracked against the original payment.
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS refunded_cents BIGINT NOT NULL DEFAULT 0
+    CHECK (refunded_cents >= 0);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS stripe_dispute_id VARCHAR(255);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS dispute_status VARCHAR(30);
+
+ALTER TABLE payment_events DROP CONSTRAINT IF EXISTS payment_events_event_type_check;
+ALTER TABLE payment_events ADD CONSTRAINT payment_events_event_type_check CHECK (event_type IN (
+    'charge_succeeded', 'charge_failed', 'refund', 'dispute',
+    'subscription_created', 'subscription_updated', 'subscription_cancelled',
+    'invoice_paid', 'invoice_payment_failed'
+));
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_events_stripe_event
+    ON payment_events (stripe_event_id) WHERE stripe_event_id IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_billing_invoices_stripe
+    ON billing_invoices (stripe_invoice_id) WHERE stripe_invoice_id IS NOT NULL;
+DROP INDEX IF EXISTS idx_subscriptions_stripe;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_subscriptions_stripe
+    ON subscriptions (stripe_subscription_id) WHERE stripe_subscription_id IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Migration 029: usage enforcement
+-- Meters and plan entitlements are upserted per subscription / feature when
+-- a plan is applied, so each needs a natural key.
+
+DELETE FROM usage_meters a USING usage_meters b
+    WHERE a.subscription_id = b.subscription_id AND a.meter_type = b.meter_type
+      AND a.created_at < b.created_at;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_meters_subscription_type
+    ON usage_meters (subscription_id, meter_type);
+
+DELETE FROM entitlements a USING entitlements b
+    WHERE a.tenant_id = b.tenant_id AND a.feature_key = b.feature_key
+      AND a.created_at < b.created_at;
+DROP INDEX IF EXISTS idx_entitlements_feature;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_entitlements_feature
+    ON entitlements (tenant_id, feature_key);
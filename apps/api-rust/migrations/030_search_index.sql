@@ -0,0 +1,130 @@
+-- Migration 030: Search indexing
+-- Row changes on searchable tables are queued in search_outbox by trigger and
+-- pushed to the configured engine by the `search.sync` job. When Typesense is
+-- not configured the engine is search_documents, searched with its stored
+-- tsvector, with pg_trgm word similarity on titles and skills for typo
+-- tolerance.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- ==========================================
+-- 1. Change queue
+-- ==========================================
+
+-- Written by triggers and drained by the sync job across all tenants, hence
+-- no RLS.
+CREATE TABLE IF NOT EXISTS search_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL,
+    entity VARCHAR(32) NOT NULL,
+    entity_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_outbox_created ON search_outbox (created_at);
+
+-- Queue the row (or the row it belongs to) for reindexing.
+-- TG_ARGV[0] is the entity, TG_ARGV[1] the column holding its id (default `id`).
+CREATE OR REPLACE FUNCTION search_enqueue() RETURNS TRIGGER AS $$
+DECLARE
+    rec JSONB;
+    id_column TEXT := COALESCE(TG_ARGV[1], 'id');
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        rec := to_jsonb(OLD);
+    ELSE
+        rec := to_jsonb(NEW);
+    END IF;
+    IF rec->>id_column IS NOT NULL THEN
+        INSERT INTO search_outbox (tenant_id, entity, entity_id)
+        VALUES ((rec->>'tenant_id')::UUID, TG_ARGV[0], (rec->>id_column)::UUID);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS search_enqueue_clients ON clients;
+CREATE TRIGGER search_enqueue_clients AFTER INSERT OR UPDATE OR DELETE ON clients
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('client');
+
+DROP TRIGGER IF EXISTS search_enqueue_client_contacts ON client_contacts;
+CREATE TRIGGER search_enqueue_client_contacts AFTER INSERT OR UPDATE OR DELETE ON client_contacts
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('client', 'client_id');
+
+DROP TRIGGER IF EXISTS search_enqueue_documents ON documents;
+CREATE TRIGGER search_enqueue_documents AFTER INSERT OR UPDATE OR DELETE ON documents
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('document');
+
+DROP TRIGGER IF EXISTS search_enqueue_invoices ON invoices;
+CREATE TRIGGER search_enqueue_invoices AFTER INSERT OR UPDATE OR DELETE ON invoices
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('invoice');
+
+DROP TRIGGER IF EXISTS search_enqueue_candidate_profiles ON candidate_profiles;
+CREATE TRIGGER search_enqueue_candidate_profiles AFTER INSERT OR UPDATE OR DELETE ON candidate_profiles
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('candidate');
+
+DROP TRIGGER IF EXISTS search_enqueue_candidate_skills ON candidate_skills;
+CREATE TRIGGER search_enqueue_candidate_skills AFTER INSERT OR UPDATE OR DELETE ON candidate_skills
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('candidate', 'candidate_id');
+
+DROP TRIGGER IF EXISTS search_enqueue_job_posts ON job_posts;
+CREATE TRIGGER search_enqueue_job_posts AFTER INSERT OR UPDATE OR DELETE ON job_posts
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('job');
+
+DROP TRIGGER IF EXISTS search_enqueue_applications ON applications;
+CREATE TRIGGER search_enqueue_applications AFTER INSERT OR UPDATE OR DELETE ON applications
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('application');
+
+DROP TRIGGER IF EXISTS search_enqueue_candidate_notes ON candidate_notes;
+CREATE TRIGGER search_enqueue_candidate_notes AFTER INSERT OR UPDATE OR DELETE ON candidate_notes
+    FOR EACH ROW EXECUTE FUNCTION search_enqueue('note');
+
+-- ==========================================
+-- 2. Postgres search index
+-- ==========================================
+
+CREATE TABLE IF NOT EXISTS search_documents (
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    entity VARCHAR(32) NOT NULL,
+    entity_id UUID NOT NULL,
+    title TEXT NOT NULL,
+    subtitle TEXT,
+    body TEXT NOT NULL DEFAULT '',
+    skills TEXT[] NOT NULL DEFAULT '{}',
+    location TEXT,
+    stage VARCHAR(64),
+    -- Set for documents only one user may see (private notes).
+    owner_id UUID,
+    url TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', title), 'A') ||
+        setweight(to_tsvector('english', COALESCE(subtitle, '')), 'B') ||
+        setweight(to_tsvector('english', body), 'C')
+    ) STORED,
+    PRIMARY KEY (entity, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_documents_vector ON search_documents USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_search_documents_tenant ON search_documents (tenant_id, entity);
+
+ALTER TABLE search_documents ENABLE ROW LEVEL SECURITY;
+ALTER TABLE search_documents FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS search_documents_tenant_isolation ON search_documents;
+CREATE POLICY search_documents_tenant_isolation ON search_documents
+    USING (tenant_id::text = current_setting('app.current_tenant', true));
+
+DROP POLICY IF EXISTS search_documents_tenant_insert ON search_documents;
+CREATE POLICY search_documents_tenant_insert ON search_documents
+    FOR INSERT WITH CHECK (tenant_id::text = current_setting('app.current_tenant', true));
+
+-- Index everything that already exists on the next sync.
+INSERT INTO search_outbox (tenant_id, entity, entity_id)
+SELECT tenant_id, 'client', id FROM clients
+UNION ALL SELECT tenant_id, 'document', id FROM documents
+UNION ALL SELECT tenant_id, 'invoice', id FROM invoices
+UNION ALL SELECT tenant_id, 'candidate', id FROM candidate_profiles
+UNION ALL SELECT tenant_id, 'job', id FROM job_posts
+UNION ALL SELECT tenant_id, 'application', id FROM applications
+UNION ALL SELECT tenant_id, 'note', id FROM candidate_notes;
@@ -0,0 +1,40 @@
+-- Migration 031: Hiring pipelines
+-- Jobs follow a pipeline template, optionally adjusted per job. Stage
+-- changes are checked against the job's pipeline: allowed next stages,
+-- scorecards required before leaving a stage, and terminal stages.
+
+ALTER TABLE job_posts
+    ADD COLUMN IF NOT EXISTS pipeline_template_id UUID REFERENCES pipeline_templates(id);
+
+-- Per-stage patches applied on top of the template, keyed by stage, e.g.
+-- {"technical": {"required_scorecards": 2}, "phone_screen": {"enabled": false}}.
+ALTER TABLE job_posts
+    ADD COLUMN IF NOT EXISTS pipeline_overrides JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_job_posts_pipeline_template ON job_posts(pipeline_template_id);
+
+-- Templates saved before stages had a shape cannot drive transitions; stop
+-- them being picked up as the default pipeline for new jobs.
+UPDATE pipeline_templates SET is_default = false
+WHERE is_default AND NOT (
+    jsonb_typeof(stages) = 'array' AND jsonb_array_length(stages) > 0
+    AND NOT EXISTS (
+        SELECT 1 FROM jsonb_array_elements(stages) s
+        WHERE jsonb_typeof(s) <> 'object'
+           OR NOT (s->>'key' = ANY (ARRAY['applied','screening','phone_screen','technical','onsite','offer','hired']))
+    )
+);
+
+-- At most one default template per tenant; keep the newest where there are several.
+UPDATE pipeline_templates t SET is_default = false
+WHERE t.is_default AND EXISTS (
+    SELECT 1 FROM pipeline_templates o
+    WHERE o.tenant_id = t.tenant_id AND o.is_default AND o.id <> t.id
+      AND (COALESCE(o.created_at, '-infinity'), o.id) > (COALESCE(t.created_at, '-infinity'), t.id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_pipeline_templates_one_default
+    ON pipeline_templates(tenant_id) WHERE is_default;
+
+CREATE INDEX IF NOT EXISTS idx_scorecards_application_stage
+    ON scorecards(application_id, interview_stage) WHERE submitted_at IS NOT NULL;
@@ -0,0 +1,37 @@
+-- Migration 032: Offer negotiations and offer letters
+-- A sent offer can be negotiated: the candidate counters, a recruiter
+-- accepts, rejects or revises, and every step is appended to the
+-- negotiation's rounds. Offer letters are rendered from a template and
+-- stored with the candidate's documents.
+
+-- One negotiation per offer. Rows written before offers existed have no
+-- offer and keep their denormalized names.
+ALTER TABLE offer_negotiations
+    ADD COLUMN IF NOT EXISTS offer_id UUID REFERENCES offers(id) ON DELETE CASCADE;
+ALTER TABLE offer_negotiations ALTER COLUMN candidate_name DROP NOT NULL;
+ALTER TABLE offer_negotiations ALTER COLUMN job_title DROP NOT NULL;
+
+-- status: counter_offer (awaiting a recruiter), revised_offer (awaiting the
+-- candidate), final_offer (awaiting the candidate, no further counters),
+-- accepted, declined, expired.
+-- rounds: [{round, proposed_by, action, base_salary_cents, equity_pct,
+--           signing_bonus_cents, start_date, notes, user_id, created_at}]
+CREATE UNIQUE INDEX IF NOT EXISTS idx_offer_negotiations_offer
+    ON offer_negotiations(offer_id) WHERE offer_id IS NOT NULL;
+
+ALTER TABLE offer_negotiations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE offer_negotiations FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'offer_negotiations' AND policyname = 'offer_negotiations_tenant_isolation') THEN
+        CREATE POLICY offer_negotiations_tenant_isolation ON offer_negotiations FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
+
+ALTER TABLE offers
+    ADD COLUMN IF NOT EXISTS letter_document_id UUID
+        REFERENCES candidate_documents(id) ON DELETE SET NULL;
+
+ALTER TABLE candidate_documents DROP CONSTRAINT IF EXISTS candidate_documents_document_type_check;
+ALTER TABLE candidate_documents ADD CONSTRAINT candidate_documents_document_type_check
+    CHECK (document_type IN ('resume','cover_letter','portfolio','certificate','offer_letter','other'));
@@ -0,0 +1,28 @@
+-- Migration 033: Multi-step approval chains
+-- Requests copy their workflow's steps when raised and advance
+-- current_step as each step reaches its quorum. Offers and job postings
+-- linked to a request cannot be sent or published until it is approved.
+
+-- steps: [{name, role, user_ids, quorum}], quorum a count or "all".
+ALTER TABLE approval_requests
+    ADD COLUMN IF NOT EXISTS entity_type VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS entity_id UUID,
+    ADD COLUMN IF NOT EXISTS steps JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN IF NOT EXISTS step_started_at TIMESTAMPTZ DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS last_reminded_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS resolved_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_approval_requests_entity
+    ON approval_requests(tenant_id, entity_type, entity_id, created_at DESC);
+
+-- At most one open request per entity.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_approval_requests_one_pending
+    ON approval_requests(tenant_id, entity_type, entity_id)
+    WHERE status = 'pending' AND entity_id IS NOT NULL;
+
+-- One decision per approver per step.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_approval_decisions_once
+    ON approval_decisions(request_id, step_number, approver_id);
+
+CREATE INDEX IF NOT EXISTS idx_approval_workflows_type
+    ON approval_workflows(tenant_id, workflow_type) WHERE is_active;
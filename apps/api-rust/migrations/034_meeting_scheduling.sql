@@ -0,0 +1,33 @@
+-- Migration 034: Slot finding and candidate self-scheduling
+-- Availability blocks are expanded into concrete times and intersected
+-- across a meeting's participants. A scheduling link lets a candidate
+-- pick one of the free slots without signing in; picking one accepts the
+-- meeting.
+
+CREATE TABLE IF NOT EXISTS meeting_scheduling_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    meeting_id UUID NOT NULL REFERENCES meeting_requests(id) ON DELETE CASCADE,
+    -- SHA-256 of the token handed out; the token itself is not stored.
+    token_hash VARCHAR(128) NOT NULL UNIQUE,
+    created_by UUID NOT NULL REFERENCES users(id),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE meeting_scheduling_links ENABLE ROW LEVEL SECURITY;
+ALTER TABLE meeting_scheduling_links FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'meeting_scheduling_links' AND policyname = 'meeting_scheduling_links_tenant_isolation') THEN
+        CREATE POLICY meeting_scheduling_links_tenant_isolation ON meeting_scheduling_links FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_meeting_scheduling_links_meeting
+    ON meeting_scheduling_links(meeting_id);
+
+-- Busy times: accepted meetings by start.
+CREATE INDEX IF NOT EXISTS idx_meeting_requests_accepted_time
+    ON meeting_requests(tenant_id, accepted_time) WHERE status = 'accepted';
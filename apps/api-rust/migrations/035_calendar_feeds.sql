@@ -0,0 +1,23 @@
+-- Migration 035: Calendar feeds
+-- Each user may have one subscribe URL serving their upcoming meetings as
+-- an iCalendar feed. Creating a new one replaces the old.
+
+CREATE TABLE IF NOT EXISTS calendar_feed_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    -- SHA-256 of the token in the feed URL.
+    token_hash VARCHAR(128) NOT NULL UNIQUE,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE calendar_feed_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE calendar_feed_tokens FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'calendar_feed_tokens' AND policyname = 'calendar_feed_tokens_tenant_isolation') THEN
+        CREATE POLICY calendar_feed_tokens_tenant_isolation ON calendar_feed_tokens FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
+
@@ -0,0 +1,13 @@
+-- Migration 036: Bill clients from unbilled time and expenses
+--
+-- Expenses are stamped with the invoice that billed them, like time entries
+-- already are, and invoice lines can point back at the expense they bill.
+ALTER TABLE expenses ADD COLUMN IF NOT EXISTS invoice_id UUID REFERENCES invoices(id);
+ALTER TABLE invoice_line_items ADD COLUMN IF NOT EXISTS expense_id UUID REFERENCES expenses(id);
+
+CREATE INDEX IF NOT EXISTS idx_expenses_invoice ON expenses(invoice_id);
+-- Unbilled work for a client is looked up by date range.
+CREATE INDEX IF NOT EXISTS idx_time_entries_unbilled ON time_entries(tenant_id, client_id, date)
+    WHERE invoice_id IS NULL AND is_billable;
+CREATE INDEX IF NOT EXISTS idx_expenses_unbilled ON expenses(tenant_id, client_id, date)
+    WHERE invoice_id IS NULL AND is_reimbursable;
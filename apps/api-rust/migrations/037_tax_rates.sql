@@ -0,0 +1,60 @@
+-- Migration 037: Tax rates
+-- Tenants configure the taxes their invoices carry. Tax is computed per
+-- line item and rounded to whole cents there, so an invoice's tax is the
+-- exact sum of its lines and of its per-rate breakdown in invoice_taxes.
+
+CREATE TABLE IF NOT EXISTS tax_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    name VARCHAR(100) NOT NULL,
+    -- Matched against the client's address state; NULL applies everywhere.
+    jurisdiction VARCHAR(100),
+    -- Parts per million of the taxable amount: 8.875% is 88750.
+    rate_ppm INT NOT NULL CHECK (rate_ppm >= 0 AND rate_ppm <= 1000000),
+    -- Compound taxes also apply to the taxes ordered before them.
+    is_compound BOOLEAN NOT NULL DEFAULT false,
+    priority INT NOT NULL DEFAULT 0,
+    exempt_service_types TEXT[] NOT NULL DEFAULT '{}',
+    exempt_client_ids UUID[] NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tax_rates_tenant ON tax_rates(tenant_id, priority) WHERE is_active;
+
+-- The tax an invoice carries for each rate applied to it, as computed.
+CREATE TABLE IF NOT EXISTS invoice_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    tax_rate_id UUID REFERENCES tax_rates(id) ON DELETE SET NULL,
+    name VARCHAR(100) NOT NULL,
+    jurisdiction VARCHAR(100),
+    rate_ppm INT NOT NULL,
+    is_compound BOOLEAN NOT NULL DEFAULT false,
+    taxable_cents BIGINT NOT NULL DEFAULT 0,
+    tax_cents BIGINT NOT NULL DEFAULT 0,
+    sort_order INT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoice_taxes_invoice ON invoice_taxes(invoice_id);
+
+ALTER TABLE invoice_line_items
+    ADD COLUMN IF NOT EXISTS service_type VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS tax_cents BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE tax_rates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tax_rates FORCE ROW LEVEL SECURITY;
+ALTER TABLE invoice_taxes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invoice_taxes FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'tax_rates' AND policyname = 'tax_rates_tenant_isolation') THEN
+        CREATE POLICY tax_rates_tenant_isolation ON tax_rates FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'invoice_taxes' AND policyname = 'invoice_taxes_tenant_isolation') THEN
+        CREATE POLICY invoice_taxes_tenant_isolation ON invoice_taxes FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
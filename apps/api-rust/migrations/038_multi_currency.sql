@@ -0,0 +1,38 @@
+-- Migration 038: Multi-currency
+-- Each tenant keeps its books in a base currency and formats amounts for a
+-- locale. Amounts stay in integer minor units of their own currency (yen
+-- for JPY, fils for KWD); reports convert them into the base currency with
+-- the exchange rate in effect on the day the money moved.
+
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS base_currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    ADD COLUMN IF NOT EXISTS locale VARCHAR(10) NOT NULL DEFAULT 'en-US';
+
+-- One unit of from_currency buys `rate` units of to_currency on rate_date.
+-- A rate stays in effect until a later-dated one replaces it.
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    rate_date DATE NOT NULL,
+    from_currency VARCHAR(3) NOT NULL,
+    to_currency VARCHAR(3) NOT NULL,
+    rate NUMERIC(24, 12) NOT NULL CHECK (rate > 0),
+    source VARCHAR(50) NOT NULL DEFAULT 'manual',
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (from_currency <> to_currency),
+    UNIQUE (tenant_id, from_currency, to_currency, rate_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_exchange_rates_lookup
+    ON exchange_rates(tenant_id, from_currency, to_currency, rate_date DESC);
+
+ALTER TABLE exchange_rates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE exchange_rates FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'exchange_rates' AND policyname = 'exchange_rates_tenant_isolation') THEN
+        CREATE POLICY exchange_rates_tenant_isolation ON exchange_rates FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
@@ -0,0 +1,55 @@
+-- Migration 039: Overdue invoices and dunning
+-- An hourly job moves sent and viewed invoices past their due date to
+-- overdue, then works through the tenant's dunning steps: each step fires
+-- once per invoice when it is the given number of days past due, sending
+-- a reminder and/or adding a late fee line to the invoice.
+
+CREATE TABLE IF NOT EXISTS dunning_steps (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    days_past_due INT NOT NULL CHECK (days_past_due >= 1),
+    send_reminder BOOLEAN NOT NULL DEFAULT true,
+    -- Flat fee in minor units of the tenant's base currency.
+    late_fee_cents BIGINT NOT NULL DEFAULT 0 CHECK (late_fee_cents >= 0),
+    -- Parts per million of the balance due: 1.5% is 15000.
+    late_fee_ppm INT NOT NULL DEFAULT 0 CHECK (late_fee_ppm >= 0 AND late_fee_ppm <= 1000000),
+    -- Added to the reminder email.
+    message TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, days_past_due)
+);
+
+-- What each step did to an invoice. Steps that came due together are
+-- recorded as skipped behind the latest one, which alone is applied.
+CREATE TABLE IF NOT EXISTS invoice_dunning_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    dunning_step_id UUID REFERENCES dunning_steps(id) ON DELETE SET NULL,
+    days_past_due INT NOT NULL,
+    skipped BOOLEAN NOT NULL DEFAULT false,
+    late_fee_cents BIGINT NOT NULL DEFAULT 0,
+    email_outbox_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (invoice_id, dunning_step_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_due ON invoices (tenant_id, due_date)
+    WHERE status IN ('sent', 'viewed', 'overdue') AND deleted_at IS NULL;
+
+ALTER TABLE dunning_steps ENABLE ROW LEVEL SECURITY;
+ALTER TABLE dunning_steps FORCE ROW LEVEL SECURITY;
+ALTER TABLE invoice_dunning_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invoice_dunning_events FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'dunning_steps' AND policyname = 'dunning_steps_tenant_isolation') THEN
+        CREATE POLICY dunning_steps_tenant_isolation ON dunning_steps FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'invoice_dunning_events' AND policyname = 'invoice_dunning_events_tenant_isolation') THEN
+        CREATE POLICY invoice_dunning_events_tenant_isolation ON invoice_dunning_events FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
@@ -0,0 +1,110 @@
+-- Migration 040: Payment allocation, refunds and credit notes
+-- A payment belongs to a client and is allocated to one or more of its
+-- invoices; what is left unallocated is credit the client can apply to
+-- later invoices. Credit notes add credit the same way. Allocations are
+-- signed and never edited: taking money back off an invoice (a refund, or
+-- voiding the invoice) adds a negative allocation. A refund is itself a
+-- payment with a negative amount, pointing at the payment refunded.
+
+-- Cancelled invoices are void; partly paid ones are partial.
+ALTER TABLE invoices DROP CONSTRAINT IF EXISTS chk_invoices_status;
+ALTER TABLE invoices DROP CONSTRAINT IF EXISTS invoices_status_check;
+UPDATE invoices SET status = 'void', updated_at = NOW() WHERE status = 'cancelled';
+UPDATE invoices SET status = 'partial', updated_at = NOW()
+    WHERE status IN ('sent', 'viewed') AND amount_paid_cents > 0 AND amount_paid_cents < total_cents;
+ALTER TABLE invoices ADD CONSTRAINT invoices_status_check
+    CHECK (status IN ('draft', 'sent', 'viewed', 'partial', 'overdue', 'paid', 'void'));
+
+-- The part of amount_paid_cents settled by credit notes rather than money.
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS credited_cents BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE payments ALTER COLUMN invoice_id DROP NOT NULL;
+ALTER TABLE payments
+    ADD COLUMN IF NOT EXISTS client_id UUID REFERENCES clients(id),
+    ADD COLUMN IF NOT EXISTS currency VARCHAR(3),
+    ADD COLUMN IF NOT EXISTS refund_of UUID REFERENCES payments(id),
+    ADD COLUMN IF NOT EXISTS created_by UUID REFERENCES users(id);
+UPDATE payments p SET client_id = i.client_id, currency = i.currency
+    FROM invoices i WHERE i.id = p.invoice_id AND p.client_id IS NULL;
+ALTER TABLE payments ALTER COLUMN client_id SET NOT NULL;
+ALTER TABLE payments ALTER COLUMN currency SET NOT NULL;
+ALTER TABLE payments DROP CONSTRAINT IF EXISTS payments_amount_cents_check;
+ALTER TABLE payments DROP CONSTRAINT IF EXISTS chk_payments_refund;
+ALTER TABLE payments ADD CONSTRAINT chk_payments_refund
+    CHECK ((refund_of IS NULL AND amount_cents > 0) OR (refund_of IS NOT NULL AND amount_cents < 0));
+
+CREATE INDEX IF NOT EXISTS idx_payments_client ON payments (tenant_id, client_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_payments_refund_of ON payments (refund_of) WHERE refund_of IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS credit_notes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    client_id UUID NOT NULL REFERENCES clients(id),
+    -- The invoice credited, if any; the credit is applied to it first.
+    invoice_id UUID REFERENCES invoices(id),
+    credit_note_number VARCHAR(50) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    reason TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'issued' CHECK (status IN ('issued', 'void')),
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    voided_at TIMESTAMPTZ,
+    UNIQUE (tenant_id, credit_note_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_notes_client ON credit_notes (tenant_id, client_id);
+CREATE INDEX IF NOT EXISTS idx_credit_notes_invoice ON credit_notes (invoice_id) WHERE invoice_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS payment_allocations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    invoice_id UUID NOT NULL REFERENCES invoices(id),
+    payment_id UUID REFERENCES payments(id),
+    credit_note_id UUID REFERENCES credit_notes(id),
+    amount_cents BIGINT NOT NULL CHECK (amount_cents <> 0),
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls(payment_id, credit_note_id) = 1)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_invoice ON payment_allocations (invoice_id);
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_payment ON payment_allocations (payment_id) WHERE payment_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_payment_allocations_credit_note ON payment_allocations (credit_note_id) WHERE credit_note_id IS NOT NULL;
+
+-- Existing payments were each for one invoice.
+INSERT INTO payment_allocations (tenant_id, invoice_id, payment_id, amount_cents, created_at)
+SELECT p.tenant_id, p.invoice_id, p.id, p.amount_cents, p.created_at FROM payments p
+WHERE p.refund_of IS NULL AND p.invoice_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM payment_allocations a WHERE a.payment_id = p.id);
+
+-- Refunds, and lost disputes, were only recorded against the payment.
+UPDATE payments SET refunded_cents = amount_cents WHERE dispute_status = 'lost';
+WITH refunds AS (
+    INSERT INTO payments (tenant_id, client_id, currency, amount_cents, method, status, refund_of, notes, created_at)
+    SELECT p.tenant_id, p.client_id, p.currency, -p.refunded_cents, p.method, 'completed', p.id,
+           CASE WHEN p.dispute_status = 'lost' THEN 'Dispute lost' ELSE 'Refund' END, p.created_at
+    FROM payments p
+    WHERE p.refund_of IS NULL AND p.refunded_cents > 0
+      AND NOT EXISTS (SELECT 1 FROM payments r WHERE r.refund_of = p.id)
+    RETURNING tenant_id, refund_of, amount_cents, created_at
+)
+INSERT INTO payment_allocations (tenant_id, invoice_id, payment_id, amount_cents, created_at)
+SELECT r.tenant_id, p.invoice_id, p.id, r.amount_cents, r.created_at
+FROM refunds r JOIN payments p ON p.id = r.refund_of
+WHERE p.invoice_id IS NOT NULL;
+
+ALTER TABLE credit_notes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE credit_notes FORCE ROW LEVEL SECURITY;
+ALTER TABLE payment_allocations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE payment_allocations FORCE ROW LEVEL SECURITY;
+DO $$ BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'credit_notes' AND policyname = 'credit_notes_tenant_isolation') THEN
+        CREATE POLICY credit_notes_tenant_isolation ON credit_notes FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_policies WHERE tablename = 'payment_allocations' AND policyname = 'payment_allocations_tenant_isolation') THEN
+        CREATE POLICY payment_allocations_tenant_isolation ON payment_allocations FOR ALL
+            USING (tenant_id = current_setting('app.current_tenant', true)::UUID);
+    END IF;
+END $$;
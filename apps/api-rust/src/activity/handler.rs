@@ -1,14 +1,11 @@
-use axum::{
-    extract::{Query, State},
-    Json,
-};
+use axum::{extract::Query, Json};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct ActivityItem {
@@ -43,7 +40,7 @@ pub struct ListActivityParams {
 }
 
 pub async fn list_activity(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Query(params): Query<ListActivityParams>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -63,7 +60,7 @@ pub async fn list_activity(
     .bind(offset)
     .bind(&params.action_type)
     .bind(params.actor_id)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -76,7 +73,7 @@ pub async fn list_activity(
     .bind(claims.tid)
     .bind(&params.action_type)
     .bind(params.actor_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -91,14 +88,14 @@ pub async fn list_activity(
 }
 
 pub async fn get_activity_stats(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
 ) -> AppResult<Json<ActivityStats>> {
     let today: (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM activity_log WHERE tenant_id = $1 AND created_at >= CURRENT_DATE",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -106,7 +103,7 @@ pub async fn get_activity_stats(
         "SELECT COUNT(*) FROM activity_log WHERE tenant_id = $1 AND created_at >= CURRENT_DATE - INTERVAL '7 days'",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -1 +1,12 @@
 pub mod handler;
+
+use axum::{routing::get, Router};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/activity", get(handler::list_activity))
+        .route("/activity/stats", get(handler::get_activity_stats))
+}
@@ -0,0 +1,222 @@
+//! Job boards: a job's applications grouped by pipeline stage, and bulk
+//! moves between columns.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+
+use super::handler::{broadcast_moved, move_application};
+use crate::applications::model::*;
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult, FieldError};
+use crate::middleware::tenant::TenantTx;
+use crate::pipeline_stages::pipeline::{self, EXIT_STAGES};
+use crate::AppState;
+
+/// Applications one bulk move may cover.
+pub const MAX_BULK_MOVE: usize = 200;
+
+const DEFAULT_PER_STAGE: i64 = 50;
+const MAX_PER_STAGE: i64 = 200;
+
+pub async fn get_board(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(job_id): Path<Uuid>,
+    Query(params): Query<BoardQuery>,
+) -> AppResult<Json<Board>> {
+    let pipeline = pipeline::for_job(&mut tx, claims.tid, job_id).await?;
+    let per_stage = params
+        .per_stage
+        .unwrap_or(DEFAULT_PER_STAGE)
+        .clamp(1, MAX_PER_STAGE);
+    let include_closed = params.include_closed.unwrap_or(false);
+
+    let job_title: String =
+        sqlx::query_scalar("SELECT title FROM job_posts WHERE id = $1 AND tenant_id = $2")
+            .bind(job_id)
+            .bind(claims.tid)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let counts: HashMap<String, i64> = sqlx::query_as(
+        "SELECT stage, COUNT(*) FROM applications \
+         WHERE job_id = $1 AND tenant_id = $2 GROUP BY stage",
+    )
+    .bind(job_id)
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect();
+
+    let avg_hours: HashMap<String, f64> = sqlx::query_as(
+        "SELECT e.from_stage, AVG(e.duration_hours)::float8 \
+         FROM application_stage_events e \
+         JOIN applications a ON a.id = e.application_id \
+         WHERE a.job_id = $1 AND e.tenant_id = $2 \
+         AND e.from_stage IS NOT NULL AND e.duration_hours IS NOT NULL \
+         GROUP BY e.from_stage",
+    )
+    .bind(job_id)
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect();
+
+    // Longest-waiting cards first in each column.
+    let cards: Vec<BoardCard> = sqlx::query_as(
+        "SELECT id, candidate_id, stage, status, source, match_score, candidate_name, \
+         candidate_headline, location_city, location_state, availability_status, \
+         entered_stage_at, hours_in_stage, applied_at \
+         FROM ( \
+             SELECT a.id, a.candidate_id, a.stage, a.status, a.source, \
+             a.match_score::float8 AS match_score, \
+             COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), 'Candidate') \
+                 AS candidate_name, \
+             cp.headline AS candidate_headline, cp.location_city, cp.location_state, \
+             cp.availability_status, \
+             COALESCE(entered.at, a.created_at) AS entered_stage_at, \
+             (EXTRACT(EPOCH FROM (NOW() - COALESCE(entered.at, a.created_at))) / 3600)::int \
+                 AS hours_in_stage, \
+             a.created_at AS applied_at, \
+             ROW_NUMBER() OVER ( \
+                 PARTITION BY a.stage ORDER BY COALESCE(entered.at, a.created_at), a.id \
+             ) AS position \
+             FROM applications a \
+             LEFT JOIN candidate_profiles cp ON cp.id = a.candidate_id \
+             LEFT JOIN users u ON u.id = cp.user_id \
+             LEFT JOIN LATERAL ( \
+                 SELECT MAX(e.created_at) AS at FROM application_stage_events e \
+                 WHERE e.application_id = a.id AND e.to_stage = a.stage \
+             ) entered ON true \
+             WHERE a.job_id = $1 AND a.tenant_id = $2 \
+             AND ($4 OR a.stage NOT IN ('rejected', 'withdrawn')) \
+         ) c \
+         WHERE position <= $3 \
+         ORDER BY position",
+    )
+    .bind(job_id)
+    .bind(claims.tid)
+    .bind(per_stage)
+    .bind(include_closed)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut by_stage: HashMap<String, Vec<BoardCard>> = HashMap::new();
+    for card in cards {
+        by_stage.entry(card.stage.clone()).or_default().push(card);
+    }
+
+    let mut column = |key: &str, name: &str, terminal: bool, in_pipeline: bool| BoardColumn {
+        key: key.to_string(),
+        name: name.to_string(),
+        terminal,
+        in_pipeline,
+        count: counts.get(key).copied().unwrap_or(0),
+        avg_hours_in_stage: avg_hours.get(key).copied(),
+        applications: by_stage.remove(key).unwrap_or_default(),
+    };
+
+    let mut columns: Vec<BoardColumn> = pipeline
+        .stages
+        .iter()
+        .map(|s| column(&s.key, &s.name, s.terminal, true))
+        .collect();
+    for stage in STAGES {
+        let stray = pipeline.stage(stage).is_none() && !EXIT_STAGES.contains(&stage);
+        if stray && counts.contains_key(stage) {
+            columns.push(column(stage, &title_case(stage), false, false));
+        }
+    }
+    columns.push(column("rejected", "Rejected", true, false));
+    columns.push(column("withdrawn", "Withdrawn", true, false));
+
+    Ok(Json(Board {
+        job_id,
+        job_title,
+        template_id: pipeline.template_id,
+        total: counts.values().sum(),
+        columns,
+    }))
+}
+
+/// Move many applications to one stage, all or nothing. Each move is checked
+/// against its job's pipeline; if any is refused, none are made and every
+/// refusal is reported against its position in `application_ids`.
+pub async fn bulk_move(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<BulkMoveRequest>,
+) -> AppResult<Json<BulkMoveResult>> {
+    if payload.application_ids.is_empty() {
+        return Err(AppError::Validation(
+            "application_ids must not be empty".to_string(),
+        ));
+    }
+    if payload.application_ids.len() > MAX_BULK_MOVE {
+        return Err(AppError::Validation(format!(
+            "At most {} applications can be moved at once",
+            MAX_BULK_MOVE
+        )));
+    }
+
+    let mut errors = Vec::new();
+    let mut moved = Vec::with_capacity(payload.application_ids.len());
+    for (i, &application_id) in payload.application_ids.iter().enumerate() {
+        let path = format!("application_ids[{}]", i);
+        if payload.application_ids[..i].contains(&application_id) {
+            errors.push(FieldError::new(path, "listed more than once"));
+            continue;
+        }
+        match move_application(
+            &mut tx,
+            claims.tid,
+            claims.sub,
+            application_id,
+            &payload.to_stage,
+            payload.notes.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => moved.push(result),
+            Err(AppError::Validation(message) | AppError::NotFound(message)) => {
+                errors.push(FieldError::new(path, message))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+
+    let mut applications = Vec::with_capacity(moved.len());
+    for (application, from_stage) in moved {
+        broadcast_moved(&state, &claims, &application, from_stage);
+        applications.push(application);
+    }
+
+    Ok(Json(BulkMoveResult {
+        moved: applications.len(),
+        applications,
+    }))
+}
+
+fn title_case(key: &str) -> String {
+    key.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
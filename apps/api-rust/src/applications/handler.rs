@@ -3,23 +3,31 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use sqlx::PgConnection;
 use uuid::Uuid;
 
 use crate::applications::model::*;
 use crate::auth::jwt::Claims;
+use crate::automations::events::{self, DomainEvent};
 use crate::error::{AppError, AppResult};
+use crate::matching;
+use crate::middleware::tenant::TenantTx;
+use crate::pipeline_stages::pipeline;
+use crate::ws::WsEventPayload;
 use crate::AppState;
 
 const APPLICATION_COLUMNS: &str = "a.id, a.tenant_id, a.job_id, a.candidate_id, a.stage, \
-    a.status, a.source, a.referrer_id, a.cover_letter, a.resume_document_id, a.match_score, \
+    a.status, a.source, a.referrer_id, a.cover_letter, a.resume_document_id, \
+    a.match_score::float8 AS match_score, \
     a.match_reasons, a.decision_notes, a.rejected_reason, a.offer_amount_cents, \
-    a.offer_equity_pct, a.offer_extended_at, a.offer_accepted_at, a.offer_declined_at, \
-    a.hired_at, a.created_at, a.updated_at";
+    a.offer_equity_pct::float8 AS offer_equity_pct, a.offer_extended_at, a.offer_accepted_at, \
+    a.offer_declined_at, a.hired_at, a.created_at, a.updated_at";
 
 const APPLICATION_WITH_DETAILS_COLUMNS: &str = "a.id, a.tenant_id, a.job_id, a.candidate_id, \
     a.stage, a.status, a.source, a.referrer_id, a.cover_letter, a.resume_document_id, \
-    a.match_score, a.match_reasons, a.decision_notes, a.rejected_reason, a.offer_amount_cents, \
-    a.offer_equity_pct, a.offer_extended_at, a.offer_accepted_at, a.offer_declined_at, \
+    a.match_score::float8 AS match_score, a.match_reasons, a.decision_notes, a.rejected_reason, \
+    a.offer_amount_cents, a.offer_equity_pct::float8 AS offer_equity_pct, a.offer_extended_at, \
+    a.offer_accepted_at, a.offer_declined_at, \
     a.hired_at, a.created_at, a.updated_at, \
     j.title AS job_title, \
     cp.headline AS candidate_headline, \
@@ -30,7 +38,7 @@ const JOIN_CLAUSE: &str = "FROM applications a \
     LEFT JOIN candidate_profiles cp ON cp.id = a.candidate_id";
 
 pub async fn list_applications(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListApplicationsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -81,7 +89,7 @@ pub async fn list_applications(
         count_query = count_query.bind(status);
     }
 
-    let (total,) = count_query.fetch_one(&state.db).await?;
+    let (total,) = count_query.fetch_one(&mut *tx).await?;
 
     // Data query
     let data_sql = format!(
@@ -111,7 +119,7 @@ pub async fn list_applications(
     let applications = data_query
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -128,7 +136,7 @@ pub async fn list_applications(
 }
 
 pub async fn get_application(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
 ) -> AppResult<Json<ApplicationWithDetails>> {
@@ -140,7 +148,7 @@ pub async fn get_application(
     let application: ApplicationWithDetails = sqlx::query_as(&sql)
         .bind(application_id)
         .bind(claims.tid)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
@@ -148,41 +156,53 @@ pub async fn get_application(
 }
 
 pub async fn create_application(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateApplicationRequest>,
 ) -> AppResult<(StatusCode, Json<Application>)> {
     let id = Uuid::new_v4();
+    let pipeline = pipeline::for_job(&mut tx, claims.tid, payload.job_id).await?;
+    let stage = pipeline.initial_stage();
+    let matched =
+        matching::score_pair(&mut tx, claims.tid, payload.job_id, payload.candidate_id).await?;
 
     // Insert the application
     let application: Application = sqlx::query_as(
         "INSERT INTO applications (id, tenant_id, job_id, candidate_id, stage, status, source, \
-         cover_letter, resume_document_id, match_reasons) \
-         VALUES ($1, $2, $3, $4, 'applied', 'active', $5, $6, $7, '{}') \
+         cover_letter, resume_document_id, match_score, match_reasons) \
+         VALUES ($1, $2, $3, $4, $5, 'active', $6, $7, $8, $9::numeric, $10) \
          RETURNING id, tenant_id, job_id, candidate_id, stage, status, source, referrer_id, \
-         cover_letter, resume_document_id, match_score, match_reasons, decision_notes, \
-         rejected_reason, offer_amount_cents, offer_equity_pct, offer_extended_at, \
+         cover_letter, resume_document_id, match_score::float8 AS match_score, match_reasons, \
+         decision_notes, rejected_reason, offer_amount_cents, offer_equity_pct, offer_extended_at, \
          offer_accepted_at, offer_declined_at, hired_at, created_at, updated_at",
     )
     .bind(id)
     .bind(claims.tid)
     .bind(payload.job_id)
     .bind(payload.candidate_id)
+    .bind(stage)
     .bind(payload.source.as_deref())
     .bind(payload.cover_letter.as_deref())
     .bind(payload.resume_document_id)
-    .fetch_one(&state.db)
+    .bind(matched.as_ref().map(|m| m.score))
+    .bind(
+        matched
+            .map(|m| serde_json::to_value(m.reasons).expect("reasons serialize"))
+            .unwrap_or_else(|| serde_json::json!([])),
+    )
+    .fetch_one(&mut *tx)
     .await?;
 
     // Insert initial stage event
     sqlx::query(
         "INSERT INTO application_stage_events (tenant_id, application_id, to_stage, changed_by) \
-         VALUES ($1, $2, 'applied', $3)",
+         VALUES ($1, $2, $3, $4)",
     )
     .bind(claims.tid)
     .bind(id)
+    .bind(stage)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Increment application_count on the job post
@@ -192,7 +212,7 @@ pub async fn create_application(
     )
     .bind(payload.job_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(application)))
@@ -200,22 +220,219 @@ pub async fn create_application(
 
 pub async fn advance_stage(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
     Json(payload): Json<AdvanceStageRequest>,
 ) -> AppResult<Json<Application>> {
+    let (updated, from_stage) = move_application(
+        &mut tx,
+        claims.tid,
+        claims.sub,
+        application_id,
+        &payload.to_stage,
+        payload.notes.as_deref(),
+    )
+    .await?;
+    broadcast_moved(&state, &claims, &updated, from_stage);
+
+    Ok(Json(updated))
+}
+
+/// Move an application as `actor_id`: change its stage, set its status when
+/// it leaves the pipeline, and queue the stage-changed automation event.
+pub async fn move_application(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    actor_id: Uuid,
+    application_id: Uuid,
+    to_stage: &str,
+    notes: Option<&str>,
+) -> AppResult<(Application, String)> {
+    let (mut updated, from_stage) =
+        change_stage(conn, tenant_id, application_id, to_stage, actor_id, notes).await?;
+
+    if to_stage == "rejected" || to_stage == "withdrawn" {
+        updated = sqlx::query_as(&format!(
+            "UPDATE applications SET status = stage, \
+                 rejected_reason = CASE WHEN stage = 'rejected' THEN $3 ELSE rejected_reason END, \
+                 decision_notes = CASE WHEN stage = 'rejected' THEN $3 ELSE decision_notes END, \
+                 updated_at = NOW() \
+                 WHERE id = $1 AND tenant_id = $2 \
+                 RETURNING {}",
+            APPLICATION_COLUMNS.replace("a.", "")
+        ))
+        .bind(application_id)
+        .bind(tenant_id)
+        .bind(notes)
+        .fetch_one(&mut *conn)
+        .await?;
+    }
+
+    events::emit(
+        &mut *conn,
+        tenant_id,
+        actor_id,
+        DomainEvent::ApplicationStageChanged {
+            application_id,
+            job_id: updated.job_id,
+            candidate_id: updated.candidate_id,
+            from_stage: from_stage.clone(),
+            to_stage: updated.stage.clone(),
+        },
+    )
+    .await?;
+
+    Ok((updated, from_stage))
+}
+
+/// Tell the tenant's open boards that a card moved.
+/// Mark an application hired on an accepted offer. The acceptance is the
+/// hiring decision, so the move to `hired` skips the pipeline's transition
+/// rules; applications that already left the pipeline cannot be hired.
+pub async fn hire_application(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    actor_id: Uuid,
+    application_id: Uuid,
+    offer_amount_cents: Option<i64>,
+    offer_equity_pct: Option<f64>,
+) -> AppResult<(Application, String)> {
+    let current: Application = sqlx::query_as(&format!(
+        "SELECT {} FROM applications a WHERE a.id = $1 AND a.tenant_id = $2 FOR UPDATE",
+        APPLICATION_COLUMNS
+    ))
+    .bind(application_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
+
+    if current.status == "rejected" || current.status == "withdrawn" {
+        return Err(AppError::Conflict(format!(
+            "Application is {} and cannot be hired",
+            current.status
+        )));
+    }
+
+    let from_stage = if current.stage == "hired" {
+        current.stage.clone()
+    } else {
+        record_stage_change(
+            conn,
+            tenant_id,
+            current,
+            "hired",
+            actor_id,
+            Some("Offer accepted"),
+        )
+        .await?
+        .1
+    };
+
+    let updated: Application = sqlx::query_as(&format!(
+        "UPDATE applications SET status = 'hired', \
+             offer_amount_cents = COALESCE($3, offer_amount_cents), \
+             offer_equity_pct = COALESCE($4::numeric, offer_equity_pct), \
+             offer_accepted_at = NOW(), hired_at = COALESCE(hired_at, NOW()), \
+             updated_at = NOW() \
+             WHERE id = $1 AND tenant_id = $2 \
+             RETURNING {}",
+        APPLICATION_COLUMNS.replace("a.", "")
+    ))
+    .bind(application_id)
+    .bind(tenant_id)
+    .bind(offer_amount_cents)
+    .bind(offer_equity_pct)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if from_stage != "hired" {
+        events::emit(
+            &mut *conn,
+            tenant_id,
+            actor_id,
+            DomainEvent::ApplicationStageChanged {
+                application_id,
+                job_id: updated.job_id,
+                candidate_id: updated.candidate_id,
+                from_stage: from_stage.clone(),
+                to_stage: updated.stage.clone(),
+            },
+        )
+        .await?;
+    }
+
+    Ok((updated, from_stage))
+}
+
+pub(crate) fn broadcast_moved(
+    state: &AppState,
+    claims: &Claims,
+    application: &Application,
+    from_stage: String,
+) {
+    state.ws_broadcast.send_to_tenant(
+        claims.tid,
+        WsEventPayload::ApplicationMoved {
+            id: application.id,
+            job_id: application.job_id,
+            candidate_id: application.candidate_id,
+            from_stage,
+            to_stage: application.stage.clone(),
+            status: application.status.clone(),
+            moved_by: claims.sub,
+        },
+    );
+}
+
+/// Move an application to `to_stage`, if its job's pipeline allows it, and
+/// record the stage event. Returns the updated application and the stage it
+/// left.
+pub async fn change_stage(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    application_id: Uuid,
+    to_stage: &str,
+    changed_by: Uuid,
+    notes: Option<&str>,
+) -> AppResult<(Application, String)> {
     // Get current application
     let current: Application = sqlx::query_as(&format!(
-        "SELECT {} FROM applications a WHERE a.id = $1 AND a.tenant_id = $2",
+        "SELECT {} FROM applications a WHERE a.id = $1 AND a.tenant_id = $2 FOR UPDATE",
         APPLICATION_COLUMNS
     ))
     .bind(application_id)
-    .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
     .await?
     .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
-    let from_stage = current.stage.clone();
+    pipeline::check_transition(
+        conn,
+        tenant_id,
+        application_id,
+        current.job_id,
+        &current.stage,
+        to_stage,
+    )
+    .await?;
+
+    record_stage_change(conn, tenant_id, current, to_stage, changed_by, notes).await
+}
+
+/// Move a locked application to `to_stage` without consulting the pipeline
+/// and record the stage event.
+async fn record_stage_change(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    current: Application,
+    to_stage: &str,
+    changed_by: Uuid,
+    notes: Option<&str>,
+) -> AppResult<(Application, String)> {
+    let application_id = current.id;
+    let from_stage = current.stage;
 
     // Calculate duration_hours from previous stage event
     let duration_hours: Option<i32> = sqlx::query_as::<_, (Option<i32>,)>(
@@ -225,8 +442,8 @@ pub async fn advance_stage(
          ORDER BY created_at DESC LIMIT 1",
     )
     .bind(application_id)
-    .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
     .await?
     .and_then(|row| row.0);
 
@@ -238,9 +455,9 @@ pub async fn advance_stage(
         APPLICATION_COLUMNS.replace("a.", "")
     ))
     .bind(application_id)
-    .bind(claims.tid)
-    .bind(&payload.to_stage)
-    .fetch_one(&state.db)
+    .bind(tenant_id)
+    .bind(to_stage)
+    .fetch_one(&mut *conn)
     .await?;
 
     // Insert stage event
@@ -249,21 +466,21 @@ pub async fn advance_stage(
          (tenant_id, application_id, from_stage, to_stage, changed_by, notes, duration_hours) \
          VALUES ($1, $2, $3, $4, $5, $6, $7)",
     )
-    .bind(claims.tid)
+    .bind(tenant_id)
     .bind(application_id)
     .bind(&from_stage)
-    .bind(&payload.to_stage)
-    .bind(claims.sub)
-    .bind(payload.notes.as_deref())
+    .bind(to_stage)
+    .bind(changed_by)
+    .bind(notes)
     .bind(duration_hours)
-    .execute(&state.db)
+    .execute(&mut *conn)
     .await?;
 
-    Ok(Json(updated))
+    Ok((updated, from_stage))
 }
 
 pub async fn get_stage_history(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
 ) -> AppResult<Json<Vec<ApplicationStageEvent>>> {
@@ -276,7 +493,7 @@ pub async fn get_stage_history(
     )
     .bind(application_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(events))
@@ -284,96 +501,41 @@ pub async fn get_stage_history(
 
 pub async fn reject_application(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
     Json(payload): Json<AdvanceStageRequest>,
 ) -> AppResult<Json<Application>> {
-    // Get current application
-    let current: Application = sqlx::query_as(&format!(
-        "SELECT {} FROM applications a WHERE a.id = $1 AND a.tenant_id = $2",
-        APPLICATION_COLUMNS
-    ))
-    .bind(application_id)
-    .bind(claims.tid)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
-
-    let from_stage = current.stage.clone();
-
-    // Calculate duration_hours from previous stage event
-    let duration_hours: Option<i32> = sqlx::query_as::<_, (Option<i32>,)>(
-        "SELECT EXTRACT(EPOCH FROM (NOW() - created_at))::int / 3600 \
-         FROM application_stage_events \
-         WHERE application_id = $1 AND tenant_id = $2 \
-         ORDER BY created_at DESC LIMIT 1",
-    )
-    .bind(application_id)
-    .bind(claims.tid)
-    .fetch_optional(&state.db)
-    .await?
-    .and_then(|row| row.0);
-
-    // Update the application
-    let updated: Application = sqlx::query_as(&format!(
-        "UPDATE applications SET stage = 'rejected', status = 'rejected', \
-             rejected_reason = $3, decision_notes = $4, updated_at = NOW() \
-             WHERE id = $1 AND tenant_id = $2 \
-             RETURNING {}",
-        APPLICATION_COLUMNS.replace("a.", "")
-    ))
-    .bind(application_id)
-    .bind(claims.tid)
-    .bind(payload.notes.as_deref())
-    .bind(payload.notes.as_deref())
-    .fetch_one(&state.db)
-    .await?;
-
-    // Insert stage event
-    sqlx::query(
-        "INSERT INTO application_stage_events \
-         (tenant_id, application_id, from_stage, to_stage, changed_by, notes, duration_hours) \
-         VALUES ($1, $2, $3, 'rejected', $4, $5, $6)",
+    let (updated, from_stage) = move_application(
+        &mut tx,
+        claims.tid,
+        claims.sub,
+        application_id,
+        "rejected",
+        payload.notes.as_deref(),
     )
-    .bind(claims.tid)
-    .bind(application_id)
-    .bind(&from_stage)
-    .bind(claims.sub)
-    .bind(payload.notes.as_deref())
-    .bind(duration_hours)
-    .execute(&state.db)
     .await?;
+    broadcast_moved(&state, &claims, &updated, from_stage);
 
     Ok(Json(updated))
 }
 
 pub async fn withdraw_application(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
 ) -> AppResult<Json<Application>> {
-    // Verify application exists
-    let (existing,): (i64,) =
-        sqlx::query_as("SELECT COUNT(*) FROM applications WHERE id = $1 AND tenant_id = $2")
-            .bind(application_id)
-            .bind(claims.tid)
-            .fetch_one(&state.db)
-            .await?;
-
-    if existing == 0 {
-        return Err(AppError::NotFound("Application not found".to_string()));
-    }
-
-    let updated: Application = sqlx::query_as(&format!(
-        "UPDATE applications SET stage = 'withdrawn', status = 'withdrawn', updated_at = NOW() \
-             WHERE id = $1 AND tenant_id = $2 \
-             RETURNING {}",
-        APPLICATION_COLUMNS.replace("a.", "")
-    ))
-    .bind(application_id)
-    .bind(claims.tid)
-    .fetch_one(&state.db)
+    let (updated, from_stage) = move_application(
+        &mut tx,
+        claims.tid,
+        claims.sub,
+        application_id,
+        "withdrawn",
+        None,
+    )
     .await?;
+    broadcast_moved(&state, &claims, &updated, from_stage);
 
     Ok(Json(updated))
 }
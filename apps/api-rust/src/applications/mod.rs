@@ -1,2 +1,38 @@
+pub mod board;
 pub mod handler;
 pub mod model;
+
+use axum::{
+    middleware::from_fn,
+    routing::{get, post},
+    Router,
+};
+
+use crate::middleware::idempotency::idempotency_check;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/applications", get(handler::list_applications))
+        .route(
+            "/applications",
+            post(handler::create_application).layer(from_fn(idempotency_check)),
+        )
+        .route("/applications/bulk-move", post(board::bulk_move))
+        .route("/applications/{id}", get(handler::get_application))
+        .route("/applications/{id}/stage", post(handler::advance_stage))
+        .route(
+            "/applications/{id}/history",
+            get(handler::get_stage_history),
+        )
+        .route(
+            "/applications/{id}/reject",
+            post(handler::reject_application),
+        )
+        .route(
+            "/applications/{id}/withdraw",
+            post(handler::withdraw_application),
+        )
+        .route("/jobs/{id}/board", get(board::get_board))
+}
@@ -3,6 +3,19 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+/// Values allowed in `applications.stage`.
+pub const STAGES: [&str; 9] = [
+    "applied",
+    "screening",
+    "phone_screen",
+    "technical",
+    "onsite",
+    "offer",
+    "hired",
+    "rejected",
+    "withdrawn",
+];
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Application {
     pub id: Uuid,
@@ -95,3 +108,67 @@ pub struct ListApplicationsQuery {
     pub stage: Option<String>,
     pub status: Option<String>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct BoardQuery {
+    /// Cards returned per column; counts always cover every application.
+    pub per_stage: Option<i64>,
+    /// Include rejected and withdrawn cards, not just their counts.
+    pub include_closed: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Board {
+    pub job_id: Uuid,
+    pub job_title: String,
+    pub template_id: Option<Uuid>,
+    pub total: i64,
+    pub columns: Vec<BoardColumn>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BoardColumn {
+    pub key: String,
+    pub name: String,
+    pub terminal: bool,
+    /// False for stages applications are left in after the job's pipeline
+    /// changed, and for `rejected` and `withdrawn`.
+    pub in_pipeline: bool,
+    pub count: i64,
+    /// Mean time applications spent in this stage before moving on.
+    pub avg_hours_in_stage: Option<f64>,
+    pub applications: Vec<BoardCard>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BoardCard {
+    pub id: Uuid,
+    pub candidate_id: Uuid,
+    #[serde(skip)]
+    pub stage: String,
+    pub status: String,
+    pub source: Option<String>,
+    pub match_score: Option<f64>,
+    pub candidate_name: String,
+    pub candidate_headline: Option<String>,
+    pub location_city: Option<String>,
+    pub location_state: Option<String>,
+    pub availability_status: Option<String>,
+    pub entered_stage_at: DateTime<Utc>,
+    pub hours_in_stage: i32,
+    pub applied_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkMoveRequest {
+    pub application_ids: Vec<Uuid>,
+    pub to_stage: String,
+    /// Stage event notes; for rejections, also the rejection reason.
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkMoveResult {
+    pub moved: usize,
+    pub applications: Vec<Application>,
+}
@@ -0,0 +1,296 @@
+//! Approval chains: ordered steps, each naming who may approve it and how
+//! many approvals it needs.
+//!
+//! A step's approvers are the users it lists plus, when it names a role,
+//! every staff member at or above that role. A step is complete once its
+//! quorum is met: a number of distinct approvers, or `"all"` of the listed
+//! users. Any rejection rejects the whole request.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::FieldError;
+use crate::middleware::auth::role_to_level;
+
+/// Roles a step may require, lowest first.
+pub const STAFF_ROLES: [&str; 5] = [
+    "staff_accountant",
+    "senior_accountant",
+    "manager",
+    "admin",
+    "partner",
+];
+
+const MAX_STEPS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub enum Quorum {
+    /// This many distinct approvers.
+    Count(u32),
+    /// Every user listed on the step.
+    All,
+}
+
+impl Default for Quorum {
+    fn default() -> Self {
+        Quorum::Count(1)
+    }
+}
+
+impl TryFrom<Value> for Quorum {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) if s == "all" => Ok(Quorum::All),
+            Value::Number(n) => match n.as_u64() {
+                Some(n @ 1..=100) => Ok(Quorum::Count(n as u32)),
+                _ => Err("must be between 1 and 100".to_string()),
+            },
+            _ => Err("must be a number of approvals or \"all\"".to_string()),
+        }
+    }
+}
+
+impl From<Quorum> for Value {
+    fn from(quorum: Quorum) -> Self {
+        match quorum {
+            Quorum::Count(n) => Value::from(n),
+            Quorum::All => Value::from("all"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub name: String,
+    /// Minimum role of staff who may approve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Users who may approve regardless of role.
+    #[serde(default)]
+    pub user_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub quorum: Quorum,
+}
+
+impl Step {
+    /// The chain used for requests with no workflow: one manager approval.
+    pub fn default_chain() -> Vec<Step> {
+        vec![Step {
+            name: "Approval".to_string(),
+            role: Some("manager".to_string()),
+            user_ids: Vec::new(),
+            quorum: Quorum::Count(1),
+        }]
+    }
+
+    /// Whether a user with `role` may decide this step.
+    pub fn can_decide(&self, user_id: Uuid, role: &str) -> bool {
+        self.user_ids.contains(&user_id)
+            || self.role.as_deref().is_some_and(|min| {
+                STAFF_ROLES.contains(&role) && role_to_level(role) >= role_to_level(min)
+            })
+    }
+
+    /// Approvals this step needs.
+    pub fn required(&self) -> usize {
+        match self.quorum {
+            Quorum::Count(n) => n as usize,
+            Quorum::All => self.user_ids.len(),
+        }
+    }
+
+    /// Whether the distinct `approvers` so far complete this step.
+    pub fn is_satisfied(&self, approvers: &[Uuid]) -> bool {
+        match self.quorum {
+            Quorum::Count(n) => approvers.len() >= n as usize,
+            Quorum::All => self.user_ids.iter().all(|id| approvers.contains(id)),
+        }
+    }
+}
+
+/// Validate a workflow's `steps`, reporting problems under `path`.
+pub fn parse_steps(value: &Value, path: &str) -> Result<Vec<Step>, Vec<FieldError>> {
+    let Value::Array(items) = value else {
+        return Err(vec![FieldError::new(path, "must be an array of steps")]);
+    };
+    if items.is_empty() || items.len() > MAX_STEPS {
+        return Err(vec![FieldError::new(
+            path,
+            format!("must have between 1 and {} steps", MAX_STEPS),
+        )]);
+    }
+
+    let mut errors = Vec::new();
+    let mut steps = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        let Value::Object(map) = item else {
+            errors.push(FieldError::new(path, "must be an object"));
+            continue;
+        };
+        let before = errors.len();
+        for key in map.keys() {
+            if !["name", "role", "user_ids", "quorum"].contains(&key.as_str()) {
+                errors.push(FieldError::new(
+                    format!("{}.{}", path, key),
+                    "unknown field",
+                ));
+            }
+        }
+
+        match map.get("name") {
+            Some(Value::String(name)) if !name.trim().is_empty() => {}
+            _ => errors.push(FieldError::new(
+                format!("{}.name", path),
+                "must be a non-empty string",
+            )),
+        }
+        let role = match map.get("role") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(role)) if STAFF_ROLES.contains(&role.as_str()) => Some(role),
+            Some(_) => {
+                errors.push(FieldError::new(
+                    format!("{}.role", path),
+                    format!("must be one of: {}", STAFF_ROLES.join(", ")),
+                ));
+                None
+            }
+        };
+        let users = match map.get("user_ids") {
+            None | Some(Value::Null) => 0,
+            Some(Value::Array(ids))
+                if ids
+                    .iter()
+                    .all(|id| id.as_str().is_some_and(|s| Uuid::parse_str(s).is_ok())) =>
+            {
+                ids.len()
+            }
+            Some(_) => {
+                errors.push(FieldError::new(
+                    format!("{}.user_ids", path),
+                    "must be an array of user ids",
+                ));
+                0
+            }
+        };
+        if role.is_none() && users == 0 && errors.len() == before {
+            errors.push(FieldError::new(
+                path.clone(),
+                "needs a role or at least one user",
+            ));
+        }
+        match map.get("quorum").cloned().map(Quorum::try_from) {
+            None => {}
+            Some(Err(message)) => errors.push(FieldError::new(format!("{}.quorum", path), message)),
+            Some(Ok(Quorum::All)) if users == 0 => errors.push(FieldError::new(
+                format!("{}.quorum", path),
+                "\"all\" needs user_ids",
+            )),
+            Some(Ok(Quorum::Count(n))) if role.is_none() && n as usize > users => {
+                errors.push(FieldError::new(
+                    format!("{}.quorum", path),
+                    format!(
+                        "needs {} approvals but only {} user(s) are listed",
+                        n, users
+                    ),
+                ))
+            }
+            Some(Ok(_)) => {}
+        }
+
+        if errors.len() == before {
+            match serde_json::from_value(item.clone()) {
+                Ok(step) => steps.push(step),
+                Err(e) => errors.push(FieldError::new(path, e.to_string())),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(steps)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parses_steps_and_reports_problems_by_path() {
+        let user = Uuid::new_v4();
+        let steps = parse_steps(
+            &json!([
+                {"name": "Hiring manager", "role": "manager"},
+                {"name": "Finance", "user_ids": [user], "quorum": "all"},
+            ]),
+            "steps",
+        )
+        .unwrap();
+        assert_eq!(steps[0].quorum, Quorum::Count(1));
+        assert_eq!(steps[1].required(), 1);
+
+        let errors = parse_steps(
+            &json!([
+                {"name": "", "role": "client"},
+                {"name": "Anyone"},
+                {"name": "Pair", "user_ids": [user], "quorum": 2},
+                {"name": "All", "role": "admin", "quorum": "all", "extra": true},
+            ]),
+            "steps",
+        )
+        .unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "steps[0].name",
+                "steps[0].role",
+                "steps[1]",
+                "steps[2].quorum",
+                "steps[3].extra",
+                "steps[3].quorum",
+            ]
+        );
+        assert!(parse_steps(&json!([]), "steps").is_err());
+    }
+
+    #[test]
+    fn test_approvers_by_role_or_listing() {
+        let listed = Uuid::new_v4();
+        let step = Step {
+            name: "Finance".to_string(),
+            role: Some("admin".to_string()),
+            user_ids: vec![listed],
+            quorum: Quorum::Count(2),
+        };
+        assert!(step.can_decide(listed, "staff_accountant"));
+        assert!(step.can_decide(Uuid::new_v4(), "partner"));
+        assert!(!step.can_decide(Uuid::new_v4(), "manager"));
+        assert!(!step.can_decide(Uuid::new_v4(), "client"));
+    }
+
+    #[test]
+    fn test_quorum_counts_distinct_approvers_or_every_listed_user() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut step = Step {
+            name: "Pair".to_string(),
+            role: None,
+            user_ids: vec![a, b],
+            quorum: Quorum::Count(2),
+        };
+        assert!(!step.is_satisfied(&[a]));
+        assert!(step.is_satisfied(&[a, b]));
+
+        step.quorum = Quorum::All;
+        step.role = Some("manager".to_string());
+        assert!(!step.is_satisfied(&[a, Uuid::new_v4()]));
+        assert!(step.is_satisfied(&[b, a]));
+    }
+}
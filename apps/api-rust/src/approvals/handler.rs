@@ -1,139 +1,408 @@
-use crate::AppState;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
-use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use super::chain::{self, Step};
+use super::model::*;
+use super::{entity_for, notify_approvers, steps_of};
 use crate::auth::Claims;
-use crate::errors::AppError;
-
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
-pub struct ApprovalRequest {
-    pub id: String,
-    pub request_type: String,
-    pub title: String,
-    pub description: Option<String>,
-    pub requester_id: String,
-    pub current_step: i32,
-    pub status: String,
-    pub metadata: serde_json::Value,
-    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ListParams {
-    pub status: Option<String>,
-    pub request_type: Option<String>,
-}
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::notifications::notify::{notify, NewNotification};
+use crate::AppState;
 
-#[derive(Debug, Deserialize)]
-pub struct CreateApprovalRequest {
-    pub request_type: String,
-    pub title: String,
-    pub description: Option<String>,
-    pub metadata: Option<serde_json::Value>,
-    pub workflow_id: Option<String>,
-}
+pub(crate) const REQUEST_COLUMNS: &str =
+    "id, tenant_id, workflow_id, request_type, entity_type, entity_id, \
+    title, description, requester_id, COALESCE(current_step, 0) AS current_step, \
+    COALESCE(status, 'pending') AS status, steps, COALESCE(metadata, '{}') AS metadata, \
+    created_at, resolved_at";
 
-#[derive(Debug, Deserialize)]
-pub struct DecisionBody {
-    pub decision: String, // approved, rejected
-    pub comment: Option<String>,
-}
+const WORKFLOW_COLUMNS: &str =
+    "id, tenant_id, name, workflow_type, steps, COALESCE(is_active, true) AS is_active, created_at";
 
 pub async fn list_requests(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Query(params): Query<ListParams>,
-) -> Result<Json<Vec<ApprovalRequest>>, AppError> {
-    let mut query = String::from(
-        "SELECT id::text, request_type, title, description, requester_id::text,
-                current_step, status, metadata, created_at
-         FROM approval_requests WHERE tenant_id = $1",
-    );
-
-    if let Some(ref status) = params.status {
-        query.push_str(&format!(" AND status = '{}'", status));
-    }
-    if let Some(ref rt) = params.request_type {
-        query.push_str(&format!(" AND request_type = '{}'", rt));
-    }
-    query.push_str(" ORDER BY created_at DESC LIMIT 50");
-
-    let requests = sqlx::query_as::<_, ApprovalRequest>(&query)
-        .bind(claims.tid)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
+) -> AppResult<Json<Vec<ApprovalRequest>>> {
+    let requests: Vec<ApprovalRequest> = sqlx::query_as(&format!(
+        "SELECT {} FROM approval_requests \
+         WHERE tenant_id = $1 \
+         AND ($2::text IS NULL OR status = $2) \
+         AND ($3::text IS NULL OR request_type = $3) \
+         ORDER BY created_at DESC LIMIT 50",
+        REQUEST_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(&params.status)
+    .bind(&params.request_type)
+    .fetch_all(&mut *tx)
+    .await?;
 
     Ok(Json(requests))
 }
 
+pub async fn get_request(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApprovalRequestDetail>> {
+    let request: ApprovalRequest = sqlx::query_as(&format!(
+        "SELECT {} FROM approval_requests WHERE id = $1 AND tenant_id = $2",
+        REQUEST_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Approval request not found".to_string()))?;
+
+    let decisions: Vec<ApprovalDecision> = sqlx::query_as(
+        "SELECT d.id, d.approver_id, \
+         COALESCE(TRIM(concat_ws(' ', u.first_name, u.last_name)), '') AS approver_name, \
+         d.step_number, d.decision, d.comment, d.decided_at \
+         FROM approval_decisions d LEFT JOIN users u ON u.id = d.approver_id \
+         WHERE d.request_id = $1 ORDER BY d.decided_at, d.id",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(ApprovalRequestDetail { request, decisions }))
+}
+
+/// Raise a request. It follows the given workflow, else the tenant's active
+/// workflow for its type, else a single manager approval.
 pub async fn create_request(
     claims: Claims,
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreateApprovalRequest>,
-) -> Result<(StatusCode, Json<ApprovalRequest>), AppError> {
-    let request = sqlx::query_as::<_, ApprovalRequest>(
-        "INSERT INTO approval_requests (tenant_id, request_type, title, description, requester_id, metadata, workflow_id)
-         VALUES ($1, $2, $3, $4, $5, $6, $7::uuid)
-         RETURNING id::text, request_type, title, description, requester_id::text,
-                   current_step, status, metadata, created_at"
+) -> AppResult<(StatusCode, Json<ApprovalRequest>)> {
+    if body.title.trim().is_empty() {
+        return Err(AppError::Validation("title is required".to_string()));
+    }
+
+    let entity_type = match entity_for(&body.request_type) {
+        Some((entity_type, table)) => {
+            let entity_id = body.entity_id.ok_or_else(|| {
+                AppError::Validation(format!(
+                    "entity_id is required for {} approvals",
+                    body.request_type
+                ))
+            })?;
+            let exists: bool = sqlx::query_scalar(&format!(
+                "SELECT EXISTS (SELECT 1 FROM {} WHERE id = $1 AND tenant_id = $2)",
+                table
+            ))
+            .bind(entity_id)
+            .bind(claims.tid)
+            .fetch_one(&mut *tx)
+            .await?;
+            if !exists {
+                return Err(AppError::NotFound(format!("{} not found", entity_type)));
+            }
+            let pending: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM approval_requests \
+                 WHERE tenant_id = $1 AND entity_type = $2 AND entity_id = $3 \
+                 AND status = 'pending')",
+            )
+            .bind(claims.tid)
+            .bind(entity_type)
+            .bind(entity_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if pending {
+                return Err(AppError::Conflict(format!(
+                    "This {} already has a pending approval request",
+                    entity_type
+                )));
+            }
+            Some(entity_type)
+        }
+        None => None,
+    };
+
+    let workflow: Option<(Uuid, String, serde_json::Value)> = sqlx::query_as(
+        "SELECT id, workflow_type, steps FROM approval_workflows \
+         WHERE tenant_id = $1 AND COALESCE(is_active, true) \
+         AND (CASE WHEN $2::uuid IS NULL THEN workflow_type = $3 ELSE id = $2 END) \
+         ORDER BY created_at DESC LIMIT 1",
     )
     .bind(claims.tid)
+    .bind(body.workflow_id)
+    .bind(&body.request_type)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (workflow_id, steps) = match workflow {
+        Some((id, workflow_type, steps)) => {
+            if workflow_type != body.request_type {
+                return Err(AppError::Validation(format!(
+                    "Workflow is for {} approvals, not {}",
+                    workflow_type, body.request_type
+                )));
+            }
+            let steps = chain::parse_steps(&steps, "steps").map_err(|_| {
+                AppError::Validation("Workflow steps are invalid; update the workflow".to_string())
+            })?;
+            (Some(id), steps)
+        }
+        None if body.workflow_id.is_some() => {
+            return Err(AppError::NotFound("Workflow not found".to_string()))
+        }
+        None => (None, Step::default_chain()),
+    };
+
+    let request: ApprovalRequest = sqlx::query_as(&format!(
+        "INSERT INTO approval_requests \
+         (tenant_id, request_type, title, description, requester_id, metadata, workflow_id, \
+          entity_type, entity_id, steps) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) \
+         RETURNING {}",
+        REQUEST_COLUMNS
+    ))
+    .bind(claims.tid)
     .bind(&body.request_type)
     .bind(&body.title)
     .bind(&body.description)
     .bind(claims.sub)
     .bind(body.metadata.unwrap_or(serde_json::json!({})))
-    .bind(&body.workflow_id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(AppError::Database)?;
+    .bind(workflow_id)
+    .bind(entity_type)
+    .bind(entity_type.and(body.entity_id))
+    .bind(serde_json::to_value(&steps).expect("steps serialize"))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    notify_approvers(
+        &mut tx,
+        &state.ws_broadcast,
+        &request,
+        "Approval requested",
+        &[],
+    )
+    .await?;
 
     Ok((StatusCode::CREATED, Json(request)))
 }
 
+/// Record a decision on the request's current step. A rejection rejects the
+/// request; an approval that completes the step's quorum moves the request
+/// to the next step, or approves it after the last.
 pub async fn decide_request(
     claims: Claims,
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
     Json(body): Json<DecisionBody>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    // Record the decision
-    sqlx::query(
-        "INSERT INTO approval_decisions (request_id, approver_id, step_number, decision, comment)
-         SELECT $1::uuid, $2::uuid, current_step, $3, $4
-         FROM approval_requests WHERE id = $1::uuid AND tenant_id = $5",
+) -> AppResult<Json<ApprovalRequest>> {
+    let request: ApprovalRequest = sqlx::query_as(&format!(
+        "SELECT {} FROM approval_requests WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        REQUEST_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Approval request not found".to_string()))?;
+
+    if request.status != "pending" {
+        return Err(AppError::Conflict(format!(
+            "Approval request is already {}",
+            request.status
+        )));
+    }
+    if request.requester_id == claims.sub {
+        return Err(AppError::Forbidden(
+            "You cannot decide your own approval request".to_string(),
+        ));
+    }
+    let steps = steps_of(&request);
+    let step_number = request.current_step;
+    let step = steps
+        .get(step_number as usize)
+        .ok_or_else(|| AppError::Internal(format!("Approval step {} is missing", step_number)))?;
+    if !step.can_decide(claims.sub, &claims.role) {
+        return Err(AppError::Forbidden(format!(
+            "You are not an approver for step '{}'",
+            step.name
+        )));
+    }
+
+    let recorded = sqlx::query(
+        "INSERT INTO approval_decisions (request_id, approver_id, step_number, decision, comment) \
+         VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT (request_id, step_number, approver_id) DO NOTHING",
     )
-    .bind(&id)
+    .bind(id)
     .bind(claims.sub)
-    .bind(&body.decision)
+    .bind(step_number)
+    .bind(body.decision.as_str())
     .bind(&body.comment)
-    .bind(claims.tid)
-    .execute(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Update the request status
-    let new_status = if body.decision == "rejected" {
-        "rejected"
-    } else {
-        "approved"
-    };
+    .execute(&mut *tx)
+    .await?;
+    if recorded.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "You have already decided this step".to_string(),
+        ));
+    }
 
-    sqlx::query(
-        "UPDATE approval_requests SET status = $1, updated_at = now()
-         WHERE id = $2::uuid AND tenant_id = $3",
+    let approvers: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT approver_id FROM approval_decisions \
+         WHERE request_id = $1 AND step_number = $2 AND decision = 'approved'",
     )
-    .bind(new_status)
-    .bind(&id)
+    .bind(id)
+    .bind(step_number)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let (status, next_step) = match body.decision {
+        Decision::Rejected => ("rejected", step_number),
+        Decision::Approved if !step.is_satisfied(&approvers) => ("pending", step_number),
+        Decision::Approved if (step_number as usize) + 1 < steps.len() => {
+            ("pending", step_number + 1)
+        }
+        Decision::Approved => ("approved", step_number),
+    };
+
+    let request: ApprovalRequest = sqlx::query_as(&format!(
+        "UPDATE approval_requests SET status = $3, current_step = $4, \
+         step_started_at = CASE WHEN current_step <> $4 THEN NOW() ELSE step_started_at END, \
+         last_reminded_at = CASE WHEN current_step <> $4 THEN NULL ELSE last_reminded_at END, \
+         resolved_at = CASE WHEN $3 = 'pending' THEN NULL ELSE NOW() END, \
+         updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        REQUEST_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(status)
+    .bind(next_step)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if status != "pending" {
+        notify(
+            &mut *tx,
+            &state.ws_broadcast,
+            NewNotification {
+                tenant_id: claims.tid,
+                user_id: request.requester_id,
+                kind: "approval_decided",
+                title: format!("Approval {}: {}", status, request.title),
+                body: body.comment.clone(),
+                resource_type: Some("approval_request"),
+                resource_id: Some(request.id),
+            },
+        )
+        .await?;
+    } else if next_step != step_number {
+        notify_approvers(
+            &mut tx,
+            &state.ws_broadcast,
+            &request,
+            "Approval requested",
+            &[],
+        )
+        .await?;
+    }
+
+    Ok(Json(request))
+}
+
+pub async fn list_workflows(
+    claims: Claims,
+    mut tx: TenantTx,
+) -> AppResult<Json<Vec<ApprovalWorkflow>>> {
+    let workflows: Vec<ApprovalWorkflow> = sqlx::query_as(&format!(
+        "SELECT {} FROM approval_workflows WHERE tenant_id = $1 \
+         ORDER BY workflow_type, created_at DESC",
+        WORKFLOW_COLUMNS
+    ))
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(workflows))
+}
+
+pub async fn create_workflow(
+    claims: Claims,
+    mut tx: TenantTx,
+    Json(body): Json<CreateWorkflowRequest>,
+) -> AppResult<(StatusCode, Json<ApprovalWorkflow>)> {
+    require_role(&claims, "admin")?;
+    if body.name.trim().is_empty() {
+        return Err(AppError::Validation("name is required".to_string()));
+    }
+    if !WORKFLOW_TYPES.contains(&body.workflow_type.as_str()) {
+        return Err(AppError::Validation(format!(
+            "workflow_type must be one of: {}",
+            WORKFLOW_TYPES.join(", ")
+        )));
+    }
+    let steps = chain::parse_steps(&body.steps, "steps").map_err(AppError::InvalidFields)?;
+
+    let workflow: ApprovalWorkflow = sqlx::query_as(&format!(
+        "INSERT INTO approval_workflows (tenant_id, name, workflow_type, steps, is_active) \
+         VALUES ($1, $2, $3, $4, $5) \
+         RETURNING {}",
+        WORKFLOW_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(body.name.trim())
+    .bind(&body.workflow_type)
+    .bind(serde_json::to_value(&steps).expect("steps serialize"))
+    .bind(body.is_active.unwrap_or(true))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(workflow)))
+}
+
+/// Update a workflow. Requests already raised keep the steps they were
+/// raised with.
+pub async fn update_workflow(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateWorkflowRequest>,
+) -> AppResult<Json<ApprovalWorkflow>> {
+    require_role(&claims, "admin")?;
+    if body.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::Validation("name must not be empty".to_string()));
+    }
+    let steps = body
+        .steps
+        .as_ref()
+        .map(|steps| chain::parse_steps(steps, "steps"))
+        .transpose()
+        .map_err(AppError::InvalidFields)?
+        .map(|steps| serde_json::to_value(&steps).expect("steps serialize"));
+
+    let workflow: ApprovalWorkflow = sqlx::query_as(&format!(
+        "UPDATE approval_workflows SET \
+         name = COALESCE($3, name), \
+         steps = COALESCE($4, steps), \
+         is_active = COALESCE($5, is_active) \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        WORKFLOW_COLUMNS
+    ))
+    .bind(id)
     .bind(claims.tid)
-    .execute(&state.db)
-    .await
-    .map_err(AppError::Database)?;
+    .bind(body.name.as_deref().map(str::trim))
+    .bind(steps)
+    .bind(body.is_active)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Workflow not found".to_string()))?;
 
-    Ok(Json(serde_json::json!({ "status": new_status })))
+    Ok(Json(workflow))
 }
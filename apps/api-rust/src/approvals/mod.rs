@@ -1 +1,173 @@
+//! Approval requests and the multi-step workflows they follow.
+//!
+//! A request copies its workflow's [`chain`] when raised and advances one
+//! step at a time as each step reaches its quorum. Requests for an offer or
+//! a job posting gate [`require_approved`]: the offer cannot be sent, or the
+//! job published, until its latest request is approved.
+
+pub mod chain;
 pub mod handler;
+pub mod model;
+pub mod reminders;
+
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::notifications::notify::{notify, NewNotification};
+use crate::ws::WsBroadcast;
+use crate::AppState;
+use chain::Step;
+use model::ApprovalRequest;
+
+/// Request type for offers; the entity is the offer.
+pub const OFFER: &str = "offer";
+/// Request type for job requisitions; the entity is the job post.
+pub const JOB_POSTING: &str = "job_posting";
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/approvals", get(handler::list_requests))
+        .route("/approvals", post(handler::create_request))
+        .route("/approvals/{id}", get(handler::get_request))
+        .route("/approvals/{id}/decide", post(handler::decide_request))
+        .route("/approval-workflows", get(handler::list_workflows))
+        .route("/approval-workflows", post(handler::create_workflow))
+        .route("/approval-workflows/{id}", put(handler::update_workflow))
+}
+
+/// The entity type a request type is linked to, and the table it lives in.
+pub fn entity_for(request_type: &str) -> Option<(&'static str, &'static str)> {
+    match request_type {
+        OFFER => Some(("offer", "offers")),
+        JOB_POSTING => Some(("job", "job_posts")),
+        _ => None,
+    }
+}
+
+/// Refuse to proceed with `entity_id` until its approval is complete. An
+/// approval is needed when the tenant has an active workflow for
+/// `request_type` or a request has been raised for the entity; the latest
+/// request decides.
+pub async fn require_approved(
+    db: impl sqlx::PgExecutor<'_>,
+    tenant_id: Uuid,
+    request_type: &str,
+    entity_id: Uuid,
+) -> AppResult<()> {
+    let (status, step, steps, has_workflow): (Option<String>, Option<i32>, Option<i32>, bool) =
+        sqlx::query_as(
+            "SELECT r.status, r.current_step, jsonb_array_length(r.steps), \
+             EXISTS (SELECT 1 FROM approval_workflows w \
+                     WHERE w.tenant_id = $1 AND w.workflow_type = $2 AND w.is_active) \
+             FROM (SELECT 1) AS one \
+             LEFT JOIN LATERAL ( \
+                 SELECT status, current_step, steps FROM approval_requests \
+                 WHERE tenant_id = $1 AND request_type = $2 AND entity_id = $3 \
+                 AND status <> 'cancelled' \
+                 ORDER BY created_at DESC LIMIT 1 \
+             ) r ON true",
+        )
+        .bind(tenant_id)
+        .bind(request_type)
+        .bind(entity_id)
+        .fetch_one(db)
+        .await?;
+
+    match status.as_deref() {
+        Some("approved") => Ok(()),
+        Some("pending") => Err(AppError::Conflict(format!(
+            "Awaiting approval (step {} of {})",
+            step.unwrap_or(0) + 1,
+            steps.unwrap_or(0).max(1)
+        ))),
+        Some(status) => Err(AppError::Conflict(format!(
+            "Approval was {}; raise a new approval request",
+            status
+        ))),
+        None if has_workflow => Err(AppError::Conflict(
+            "Approval is required; raise an approval request first".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The request's steps; requests raised without a workflow, or before
+/// steps were recorded, need one manager approval.
+pub fn steps_of(request: &ApprovalRequest) -> Vec<Step> {
+    serde_json::from_value::<Vec<Step>>(request.steps.clone())
+        .ok()
+        .filter(|steps| !steps.is_empty())
+        .unwrap_or_else(Step::default_chain)
+}
+
+/// Active staff who may decide `step`, other than the requester.
+pub async fn approvers(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    step: &Step,
+    requester_id: Uuid,
+) -> AppResult<Vec<Uuid>> {
+    let users: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, role FROM users \
+         WHERE tenant_id = $1 AND status = 'active' AND id <> $2 \
+         AND (id = ANY($3) OR role <> 'client')",
+    )
+    .bind(tenant_id)
+    .bind(requester_id)
+    .bind(&step.user_ids)
+    .fetch_all(conn)
+    .await?;
+    Ok(users
+        .into_iter()
+        .filter(|(id, role)| step.can_decide(*id, role))
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// Tell the current step's approvers, except any in `skip`, the request is
+/// waiting for them. Returns how many were notified.
+pub async fn notify_approvers(
+    conn: &mut PgConnection,
+    ws: &WsBroadcast,
+    request: &ApprovalRequest,
+    title: &str,
+    skip: &[Uuid],
+) -> AppResult<usize> {
+    let steps = steps_of(request);
+    let Some(step) = steps.get(request.current_step as usize) else {
+        return Ok(0);
+    };
+    let mut notified = 0;
+    for user_id in approvers(conn, request.tenant_id, step, request.requester_id).await? {
+        if skip.contains(&user_id) {
+            continue;
+        }
+        notify(
+            &mut *conn,
+            ws,
+            NewNotification {
+                tenant_id: request.tenant_id,
+                user_id,
+                kind: "approval_requested",
+                title: format!("{}: {}", title, request.title),
+                body: Some(format!(
+                    "Step {} of {}: {}",
+                    request.current_step + 1,
+                    steps.len(),
+                    step.name
+                )),
+                resource_type: Some("approval_request"),
+                resource_id: Some(request.id),
+            },
+        )
+        .await?;
+        notified += 1;
+    }
+    Ok(notified)
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Kinds of approval a workflow can govern.
+pub const WORKFLOW_TYPES: [&str; 4] = ["job_posting", "offer", "budget", "new_position"];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApprovalWorkflow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub workflow_type: String,
+    /// [`super::chain::Step`]s, in order.
+    pub steps: serde_json::Value,
+    pub is_active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkflowRequest {
+    pub name: String,
+    pub workflow_type: String,
+    pub steps: serde_json::Value,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWorkflowRequest {
+    pub name: Option<String>,
+    pub steps: Option<serde_json::Value>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub workflow_id: Option<Uuid>,
+    pub request_type: String,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub title: String,
+    pub description: Option<String>,
+    pub requester_id: Uuid,
+    /// Index into `steps` of the step awaiting decisions.
+    pub current_step: i32,
+    pub status: String,
+    /// The workflow's steps as they were when the request was raised.
+    pub steps: serde_json::Value,
+    pub metadata: serde_json::Value,
+    pub created_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApprovalDecision {
+    pub id: Uuid,
+    pub approver_id: Uuid,
+    pub approver_name: String,
+    pub step_number: i32,
+    pub decision: String,
+    pub comment: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApprovalRequestDetail {
+    #[serde(flatten)]
+    pub request: ApprovalRequest,
+    pub decisions: Vec<ApprovalDecision>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    pub status: Option<String>,
+    pub request_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApprovalRequest {
+    pub request_type: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    /// Defaults to the tenant's active workflow for `request_type`.
+    pub workflow_id: Option<Uuid>,
+    /// The offer or job the request is for; required for those types.
+    pub entity_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approved,
+    Rejected,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Approved => "approved",
+            Decision::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecisionBody {
+    pub decision: Decision,
+    #[serde(alias = "comments")]
+    pub comment: Option<String>,
+}
@@ -0,0 +1,62 @@
+//! Reminders for approval steps left waiting.
+
+use uuid::Uuid;
+
+use super::handler::REQUEST_COLUMNS;
+use super::model::ApprovalRequest;
+use super::notify_approvers;
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "approvals.remind";
+
+/// How long a step waits, and then how long between reminders.
+const REMIND_AFTER_HOURS: i32 = 24;
+
+/// Remind the current step's approvers who have not yet decided on pending
+/// requests that have waited [`REMIND_AFTER_HOURS`] since the step started
+/// or since the last reminder.
+pub async fn remind_approvers(state: AppState, _job: Job) -> anyhow::Result<()> {
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+
+        let due: Vec<ApprovalRequest> = sqlx::query_as(&format!(
+            "SELECT {} FROM approval_requests \
+             WHERE tenant_id = $1 AND status = 'pending' \
+             AND COALESCE(last_reminded_at, step_started_at, created_at) \
+                 < NOW() - make_interval(hours => $2) \
+             FOR UPDATE SKIP LOCKED",
+            REQUEST_COLUMNS
+        ))
+        .bind(tenant_id)
+        .bind(REMIND_AFTER_HOURS)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for request in due {
+            let decided: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT approver_id FROM approval_decisions \
+                 WHERE request_id = $1 AND step_number = $2",
+            )
+            .bind(request.id)
+            .bind(request.current_step)
+            .fetch_all(&mut *tx)
+            .await?;
+            notify_approvers(
+                &mut tx,
+                &state.ws_broadcast,
+                &request,
+                "Approval still waiting",
+                &decided,
+            )
+            .await?;
+            sqlx::query("UPDATE approval_requests SET last_reminded_at = NOW() WHERE id = $1")
+                .bind(request.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
@@ -1,13 +1,9 @@
-use crate::AppState;
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Path, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 
 use crate::auth::Claims;
 use crate::errors::AppError;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Assessment {
@@ -53,7 +49,7 @@ pub struct CreateAssessment {
 
 pub async fn list_assessments(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<Assessment>>, AppError> {
     let assessments = sqlx::query_as::<_, Assessment>(
         "SELECT id::text, title, description, category, difficulty, duration_minutes,
@@ -62,7 +58,7 @@ pub async fn list_assessments(
          FROM assessments WHERE tenant_id = $1 ORDER BY created_at DESC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -71,7 +67,7 @@ pub async fn list_assessments(
 
 pub async fn create_assessment(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreateAssessment>,
 ) -> Result<(StatusCode, Json<Assessment>), AppError> {
     let assessment = sqlx::query_as::<_, Assessment>(
@@ -89,7 +85,7 @@ pub async fn create_assessment(
     .bind(body.duration_minutes.unwrap_or(30))
     .bind(&body.questions)
     .bind(body.passing_score.unwrap_or(70))
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -98,7 +94,7 @@ pub async fn create_assessment(
 
 pub async fn list_submissions(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Path(assessment_id): Path<String>,
 ) -> Result<Json<Vec<AssessmentSubmission>>, AppError> {
     let subs = sqlx::query_as::<_, AssessmentSubmission>(
@@ -112,7 +108,7 @@ pub async fn list_submissions(
     )
     .bind(&assessment_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
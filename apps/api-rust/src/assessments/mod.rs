@@ -1 +1,19 @@
 pub mod handler;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/assessments", get(handler::list_assessments))
+        .route("/assessments", post(handler::create_assessment))
+        .route(
+            "/assessments/{id}/submissions",
+            get(handler::list_submissions),
+        )
+}
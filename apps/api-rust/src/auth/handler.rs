@@ -11,7 +11,9 @@ use validator::Validate;
 
 use crate::auth::{jwt, password};
 use crate::error::{AppError, AppResult};
+use crate::mailer::{messages, outbox};
 use crate::middleware::security::{self, SecurityEventType};
+use crate::middleware::tenant::TenantTx;
 use crate::AppState;
 
 #[derive(Debug, Deserialize, Validate)]
@@ -595,7 +597,7 @@ pub async fn refresh_token(
 }
 
 pub async fn get_me(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<jwt::Claims>,
 ) -> AppResult<Json<UserResponse>> {
     let user: UserRow = sqlx::query_as(
@@ -604,7 +606,7 @@ pub async fn get_me(
     )
     .bind(claims.sub)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
@@ -627,7 +629,7 @@ pub struct ChangePasswordRequest {
 }
 
 pub async fn change_password(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<jwt::Claims>,
     Json(payload): Json<ChangePasswordRequest>,
 ) -> AppResult<StatusCode> {
@@ -640,7 +642,7 @@ pub async fn change_password(
         sqlx::query_as("SELECT password_hash FROM users WHERE id = $1 AND tenant_id = $2")
             .bind(claims.sub)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     // Verify current password
@@ -665,7 +667,7 @@ pub async fn change_password(
     .bind(&new_hash)
     .bind(claims.sub)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::OK)
@@ -673,6 +675,7 @@ pub async fn change_password(
 
 pub async fn logout(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<jwt::Claims>,
 ) -> AppResult<StatusCode> {
     security::log_security_event(
@@ -708,7 +711,7 @@ pub async fn logout(
         "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
     )
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::NO_CONTENT)
@@ -845,7 +848,7 @@ pub async fn forgot_password(
     .fetch_optional(&state.db)
     .await?;
 
-    if let Some((user_id, tenant_id, _email)) = user {
+    if let Some((user_id, tenant_id, email)) = user {
         let reset_token = Uuid::new_v4().to_string();
         let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
 
@@ -860,12 +863,17 @@ pub async fn forgot_password(
         .execute(&state.db)
         .await?;
 
-        // In production: send email via Resend with reset link containing token
-        tracing::info!(
-            "Password reset requested for user {}, token: {}",
-            user_id,
-            reset_token
-        );
+        outbox::enqueue(
+            &state.db,
+            tenant_id,
+            &messages::password_reset(&email, &state.config.app_base_url, &reset_token),
+            outbox::EnqueueOptions {
+                category: Some("password_reset"),
+                ..Default::default()
+            },
+        )
+        .await?;
+        tracing::info!("Password reset requested for user {}", user_id);
     }
 
     Ok(StatusCode::OK)
@@ -909,28 +917,35 @@ pub async fn reset_password(
 
 pub async fn request_email_verification(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<jwt::Claims>,
 ) -> AppResult<StatusCode> {
     let verification_token = Uuid::new_v4().to_string();
     let expires_at = chrono::Utc::now() + chrono::Duration::hours(24);
 
-    sqlx::query(
+    let email: String = sqlx::query_scalar(
         "UPDATE users SET email_verification_token = $3, email_verification_expires = $4, updated_at = NOW() \
-         WHERE id = $1 AND tenant_id = $2"
+         WHERE id = $1 AND tenant_id = $2 RETURNING email"
     )
     .bind(claims.sub)
     .bind(claims.tid)
     .bind(&verification_token)
     .bind(expires_at)
-    .execute(&state.db)
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-    // In production: send verification email
-    tracing::info!(
-        "Email verification requested for user {}, token: {}",
-        claims.sub,
-        verification_token
-    );
+    outbox::enqueue(
+        &mut *tx,
+        claims.tid,
+        &messages::email_verification(&email, &state.config.app_base_url, &verification_token),
+        outbox::EnqueueOptions {
+            category: Some("email_verification"),
+            created_by: Some(claims.sub),
+            ..Default::default()
+        },
+    )
+    .await?;
 
     Ok(StatusCode::OK)
 }
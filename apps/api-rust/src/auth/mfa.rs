@@ -1,14 +1,10 @@
-use axum::{
-    extract::{Extension, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Extension, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use totp_rs::{Algorithm, Secret, TOTP};
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize)]
 pub struct MfaSetupResponse {
@@ -42,14 +38,14 @@ fn build_totp(secret_bytes: Vec<u8>, account: &str) -> Result<TOTP, AppError> {
 }
 
 pub async fn setup_mfa(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<MfaSetupResponse>> {
     let (mfa_enabled,): (bool,) =
         sqlx::query_as("SELECT mfa_enabled FROM users WHERE id = $1 AND tenant_id = $2")
             .bind(claims.sub)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     if mfa_enabled {
@@ -60,7 +56,7 @@ pub async fn setup_mfa(
         sqlx::query_as("SELECT email FROM users WHERE id = $1 AND tenant_id = $2")
             .bind(claims.sub)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     let secret = Secret::generate_secret();
@@ -79,7 +75,7 @@ pub async fn setup_mfa(
 }
 
 pub async fn enable_mfa(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<MfaEnableRequest>,
 ) -> AppResult<StatusCode> {
@@ -104,14 +100,14 @@ pub async fn enable_mfa(
     .bind(claims.sub)
     .bind(claims.tid)
     .bind(secret.as_slice())
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::OK)
 }
 
 pub async fn verify_mfa(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<MfaVerifyRequest>,
 ) -> AppResult<StatusCode> {
@@ -120,7 +116,7 @@ pub async fn verify_mfa(
     )
     .bind(claims.sub)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("MFA not enabled".to_string()))?;
 
@@ -140,7 +136,7 @@ pub async fn verify_mfa(
 }
 
 pub async fn disable_mfa(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<MfaVerifyRequest>,
 ) -> AppResult<StatusCode> {
@@ -149,7 +145,7 @@ pub async fn disable_mfa(
     )
     .bind(claims.sub)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("MFA not enabled".to_string()))?;
 
@@ -173,7 +169,7 @@ pub async fn disable_mfa(
     )
     .bind(claims.sub)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::OK)
@@ -4,3 +4,45 @@ pub mod mfa;
 pub mod password;
 
 pub use jwt::Claims;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Public routes (registration, login and account recovery), nested under `/api/v1`.
+pub fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(handler::health))
+        .route("/auth/register", post(handler::register))
+        .route(
+            "/auth/register-candidate",
+            post(handler::register_candidate),
+        )
+        .route("/auth/login", post(handler::login))
+        .route("/auth/refresh", post(handler::refresh_token))
+        .route("/auth/mfa/verify-login", post(handler::verify_mfa_login))
+        .route("/auth/forgot-password", post(handler::forgot_password))
+        .route("/auth/reset-password", post(handler::reset_password))
+        .route("/auth/verify-email", post(handler::verify_email))
+        .route("/auth/accept-invite", post(handler::accept_invite))
+}
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/me", get(handler::get_me))
+        .route("/auth/logout", post(handler::logout))
+        .route("/auth/change-password", post(handler::change_password))
+        .route(
+            "/auth/verify-email/request",
+            post(handler::request_email_verification),
+        )
+        // MFA
+        .route("/auth/mfa/setup", post(mfa::setup_mfa))
+        .route("/auth/mfa/enable", post(mfa::enable_mfa))
+        .route("/auth/mfa/verify", post(mfa::verify_mfa))
+        .route("/auth/mfa/disable", post(mfa::disable_mfa))
+}
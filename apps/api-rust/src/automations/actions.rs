@@ -0,0 +1,417 @@
+//! What a rule does once its conditions match.
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::events::{self, Envelope};
+use super::handler::AutomationRule;
+use crate::applications::handler::change_stage;
+use crate::mailer::{outbox, template, OutgoingEmail};
+use crate::notifications::notify::{notify, NewNotification};
+use crate::AppState;
+
+/// One entry of a rule's `actions` array. Text fields may use `{{field}}`
+/// placeholders filled from the event payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// Move the event's application to another stage.
+    MoveStage {
+        to_stage: String,
+    },
+    CreateTask {
+        title: String,
+        description: Option<String>,
+        assigned_to: Option<Uuid>,
+        priority: Option<String>,
+        due_in_days: Option<i64>,
+    },
+    /// Send an email template to `to`: `candidate`, `client` (the primary
+    /// contact) or an email address. Defaults to the candidate.
+    SendEmailTemplate {
+        template_id: Uuid,
+        to: Option<String>,
+    },
+    /// Notify a user, by default the rule's creator.
+    NotifyUser {
+        user_id: Option<Uuid>,
+        title: String,
+        body: Option<String>,
+    },
+    AddToTalentPool {
+        pool_id: Uuid,
+    },
+}
+
+impl Action {
+    pub const TYPES: [&'static str; 5] = [
+        "move_stage",
+        "create_task",
+        "send_email_template",
+        "notify_user",
+        "add_to_talent_pool",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MoveStage { .. } => "move_stage",
+            Self::CreateTask { .. } => "create_task",
+            Self::SendEmailTemplate { .. } => "send_email_template",
+            Self::NotifyUser { .. } => "notify_user",
+            Self::AddToTalentPool { .. } => "add_to_talent_pool",
+        }
+    }
+}
+
+/// The rule being run and the event that triggered it.
+pub struct ActionContext<'a> {
+    pub state: &'a AppState,
+    pub tenant_id: Uuid,
+    pub rule: &'a AutomationRule,
+    pub envelope: &'a Envelope,
+    pub vars: Map<String, Value>,
+}
+
+impl ActionContext<'_> {
+    fn render(&self, text: &str) -> String {
+        template::render(text, &self.vars).text
+    }
+}
+
+/// Perform one action, returning a summary for `automation_log`.
+pub async fn execute(
+    conn: &mut PgConnection,
+    ctx: &ActionContext<'_>,
+    action: &Action,
+) -> anyhow::Result<Value> {
+    let event = &ctx.envelope.event;
+    match action {
+        Action::MoveStage { to_stage } => {
+            let application_id = event
+                .application_id()
+                .ok_or_else(|| anyhow!("{} has no application", event.event_type()))?;
+            let notes = format!("Automation: {}", ctx.rule.name);
+            let (updated, from_stage) = change_stage(
+                conn,
+                ctx.tenant_id,
+                application_id,
+                to_stage,
+                ctx.rule.created_by,
+                Some(&notes),
+            )
+            .await?;
+
+            if from_stage != updated.stage {
+                let mut rule_chain = ctx.envelope.rule_chain.clone();
+                rule_chain.push(ctx.rule.id);
+                events::emit_envelope(
+                    &mut *conn,
+                    ctx.tenant_id,
+                    &Envelope {
+                        event: events::DomainEvent::ApplicationStageChanged {
+                            application_id,
+                            job_id: updated.job_id,
+                            candidate_id: updated.candidate_id,
+                            from_stage: from_stage.clone(),
+                            to_stage: updated.stage.clone(),
+                        },
+                        actor_id: Some(ctx.rule.created_by),
+                        rule_chain,
+                    },
+                )
+                .await?;
+            }
+            Ok(json!({
+                "type": action.name(),
+                "application_id": application_id,
+                "from_stage": from_stage,
+                "to_stage": updated.stage,
+            }))
+        }
+
+        Action::CreateTask {
+            title,
+            description,
+            assigned_to,
+            priority,
+            due_in_days,
+        } => {
+            let due_date = due_in_days
+                .map(|days| (chrono::Utc::now() + chrono::Duration::days(days)).date_naive());
+            let task_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO tasks (tenant_id, client_id, title, description, priority, assigned_to, created_by, due_date) \
+                 VALUES ($1, $2, $3, $4, COALESCE($5, 'medium'), $6, $7, $8) RETURNING id",
+            )
+            .bind(ctx.tenant_id)
+            .bind(event.client_id())
+            .bind(ctx.render(title))
+            .bind(description.as_deref().map(|d| ctx.render(d)))
+            .bind(priority.as_deref())
+            .bind(assigned_to)
+            .bind(ctx.rule.created_by)
+            .bind(due_date)
+            .fetch_one(&mut *conn)
+            .await?;
+            Ok(json!({ "type": action.name(), "task_id": task_id }))
+        }
+
+        Action::SendEmailTemplate { template_id, to } => {
+            let (subject, body, category): (String, String, String) = sqlx::query_as(
+                "SELECT subject, body, category FROM email_templates \
+                 WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+            )
+            .bind(template_id)
+            .bind(ctx.tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| anyhow!("email template {} not found", template_id))?;
+
+            let vars = template::build_context(
+                &mut *conn,
+                ctx.tenant_id,
+                &ctx.state.config.app_base_url,
+                template::ContextRefs {
+                    candidate_id: event.candidate_id(),
+                    job_id: event.job_id(),
+                    invoice_id: event.invoice_id(),
+                },
+                &Value::Object(ctx.vars.clone()),
+            )
+            .await?;
+
+            let recipient = recipient(conn, ctx, to.as_deref(), &vars)
+                .await?
+                .filter(|address| address.contains('@'))
+                .with_context(|| format!("no email address for recipient {:?}", to))?;
+
+            let outbox_id = outbox::enqueue(
+                &mut *conn,
+                ctx.tenant_id,
+                &OutgoingEmail {
+                    to: recipient.clone(),
+                    subject: template::render(&subject, &vars).text,
+                    text_body: template::render(&body, &vars).text,
+                    html_body: None,
+                },
+                outbox::EnqueueOptions {
+                    category: Some(&category),
+                    template_id: Some(*template_id),
+                    created_by: Some(ctx.rule.created_by),
+                },
+            )
+            .await?;
+            Ok(json!({ "type": action.name(), "to": recipient, "outbox_id": outbox_id }))
+        }
+
+        Action::NotifyUser {
+            user_id,
+            title,
+            body,
+        } => {
+            let user_id = user_id.unwrap_or(ctx.rule.created_by);
+            let (resource_type, resource_id) = event.resource();
+            let notification_id = notify(
+                &mut *conn,
+                &ctx.state.ws_broadcast,
+                NewNotification {
+                    tenant_id: ctx.tenant_id,
+                    user_id,
+                    kind: "automation",
+                    title: ctx.render(title),
+                    body: body.as_deref().map(|b| ctx.render(b)),
+                    resource_type: Some(resource_type),
+                    resource_id: Some(resource_id),
+                },
+            )
+            .await?;
+            Ok(json!({
+                "type": action.name(),
+                "user_id": user_id,
+                "notification_id": notification_id,
+            }))
+        }
+
+        Action::AddToTalentPool { pool_id } => {
+            let candidate_id = event
+                .candidate_id()
+                .ok_or_else(|| anyhow!("{} has no candidate", event.event_type()))?;
+            let pool_exists: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM talent_pools WHERE id = $1 AND tenant_id = $2)",
+            )
+            .bind(pool_id)
+            .bind(ctx.tenant_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if !pool_exists {
+                return Err(anyhow!("talent pool {} not found", pool_id));
+            }
+
+            let member_id: Option<Uuid> = sqlx::query_scalar(
+                "INSERT INTO talent_pool_members (pool_id, candidate_name, candidate_email, source, metadata) \
+                 SELECT $1, COALESCE(NULLIF(TRIM(CONCAT(u.first_name, ' ', u.last_name)), ''), 'Unknown'), \
+                        u.email, 'automation', jsonb_build_object('candidate_id', cp.id) \
+                 FROM candidate_profiles cp LEFT JOIN users u ON u.id = cp.user_id \
+                 WHERE cp.id = $2 AND cp.tenant_id = $3 \
+                   AND NOT EXISTS (SELECT 1 FROM talent_pool_members m \
+                                   WHERE m.pool_id = $1 AND m.metadata->>'candidate_id' = cp.id::text) \
+                 RETURNING id",
+            )
+            .bind(pool_id)
+            .bind(candidate_id)
+            .bind(ctx.tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+            Ok(json!({
+                "type": action.name(),
+                "pool_id": pool_id,
+                "member_id": member_id,
+                "already_member": member_id.is_none(),
+            }))
+        }
+    }
+}
+
+/// Describe what [`execute`] would do for an action, reading but never
+/// writing. Used by rule dry runs.
+pub async fn preview(
+    conn: &mut PgConnection,
+    ctx: &ActionContext<'_>,
+    action: &Action,
+) -> anyhow::Result<Value> {
+    let event = &ctx.envelope.event;
+    match action {
+        Action::MoveStage { to_stage } => {
+            let application_id = event
+                .application_id()
+                .ok_or_else(|| anyhow!("{} has no application", event.event_type()))?;
+            let from_stage: String = sqlx::query_scalar(
+                "SELECT stage FROM applications WHERE id = $1 AND tenant_id = $2",
+            )
+            .bind(application_id)
+            .bind(ctx.tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| anyhow!("application {} not found", application_id))?;
+            Ok(json!({
+                "type": action.name(),
+                "application_id": application_id,
+                "from_stage": from_stage,
+                "to_stage": to_stage,
+            }))
+        }
+
+        Action::CreateTask {
+            title,
+            description,
+            assigned_to,
+            priority,
+            due_in_days,
+        } => Ok(json!({
+            "type": action.name(),
+            "title": ctx.render(title),
+            "description": description.as_deref().map(|d| ctx.render(d)),
+            "assigned_to": assigned_to,
+            "priority": priority.as_deref().unwrap_or("medium"),
+            "due_date": due_in_days
+                .map(|days| (chrono::Utc::now() + chrono::Duration::days(days)).date_naive()),
+        })),
+
+        Action::SendEmailTemplate { template_id, to } => {
+            let (subject, body): (String, String) = sqlx::query_as(
+                "SELECT subject, body FROM email_templates \
+                 WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+            )
+            .bind(template_id)
+            .bind(ctx.tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| anyhow!("email template {} not found", template_id))?;
+            let vars = template::build_context(
+                &mut *conn,
+                ctx.tenant_id,
+                &ctx.state.config.app_base_url,
+                template::ContextRefs {
+                    candidate_id: event.candidate_id(),
+                    job_id: event.job_id(),
+                    invoice_id: event.invoice_id(),
+                },
+                &Value::Object(ctx.vars.clone()),
+            )
+            .await?;
+            let recipient = recipient(conn, ctx, to.as_deref(), &vars).await?;
+            Ok(json!({
+                "type": action.name(),
+                "to": recipient,
+                "subject": template::render(&subject, &vars).text,
+                "body": template::render(&body, &vars).text,
+            }))
+        }
+
+        Action::NotifyUser {
+            user_id,
+            title,
+            body,
+        } => Ok(json!({
+            "type": action.name(),
+            "user_id": user_id.unwrap_or(ctx.rule.created_by),
+            "title": ctx.render(title),
+            "body": body.as_deref().map(|b| ctx.render(b)),
+        })),
+
+        Action::AddToTalentPool { pool_id } => {
+            let candidate_id = event
+                .candidate_id()
+                .ok_or_else(|| anyhow!("{} has no candidate", event.event_type()))?;
+            let already_member: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM talent_pool_members m \
+                 JOIN talent_pools p ON p.id = m.pool_id \
+                 WHERE m.pool_id = $1 AND p.tenant_id = $2 AND m.metadata->>'candidate_id' = $3)",
+            )
+            .bind(pool_id)
+            .bind(ctx.tenant_id)
+            .bind(candidate_id.to_string())
+            .fetch_one(&mut *conn)
+            .await?;
+            Ok(json!({
+                "type": action.name(),
+                "pool_id": pool_id,
+                "candidate_id": candidate_id,
+                "already_member": already_member,
+            }))
+        }
+    }
+}
+
+/// Email address for a `send_email_template` recipient: `candidate` (the
+/// default), `client` or a literal address.
+async fn recipient(
+    conn: &mut PgConnection,
+    ctx: &ActionContext<'_>,
+    to: Option<&str>,
+    vars: &Map<String, Value>,
+) -> anyhow::Result<Option<String>> {
+    Ok(match to.unwrap_or("candidate") {
+        "candidate" => vars
+            .get("candidate_email")
+            .and_then(Value::as_str)
+            .map(String::from),
+        "client" => match ctx.envelope.event.client_id() {
+            Some(client_id) => {
+                sqlx::query_scalar(
+                    "SELECT email FROM client_contacts \
+                     WHERE client_id = $1 AND tenant_id = $2 AND email IS NOT NULL \
+                     ORDER BY is_primary DESC, created_at LIMIT 1",
+                )
+                .bind(client_id)
+                .bind(ctx.tenant_id)
+                .fetch_optional(conn)
+                .await?
+            }
+            None => None,
+        },
+        address => Some(address.to_string()),
+    })
+}
@@ -0,0 +1,245 @@
+//! Rule conditions, evaluated against an event's payload.
+//!
+//! A rule's `conditions` is either empty (always matches), a comparison
+//! `{"field": "to_stage", "op": "eq", "value": "offer"}`, or a group
+//! `{"all": [...]}` / `{"any": [...]}` / `{"not": {...}}` of conditions.
+//! Fields are dotted paths into the payload.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Eq,
+    Neq,
+    In,
+    NotIn,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// Substring (case-insensitive) of a string, or element of an array.
+    Contains,
+    /// The field is present and not null; `"value": false` inverts it.
+    Exists,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 10] = [
+        Self::Eq,
+        Self::Neq,
+        Self::In,
+        Self::NotIn,
+        Self::Gt,
+        Self::Gte,
+        Self::Lt,
+        Self::Lte,
+        Self::Contains,
+        Self::Exists,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "eq",
+            Self::Neq => "neq",
+            Self::In => "in",
+            Self::NotIn => "not_in",
+            Self::Gt => "gt",
+            Self::Gte => "gte",
+            Self::Lt => "lt",
+            Self::Lte => "lte",
+            Self::Contains => "contains",
+            Self::Exists => "exists",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Condition {
+    All {
+        all: Vec<Condition>,
+    },
+    Any {
+        any: Vec<Condition>,
+    },
+    Not {
+        not: Box<Condition>,
+    },
+    Compare {
+        field: String,
+        op: Operator,
+        #[serde(default)]
+        value: Value,
+    },
+}
+
+impl Condition {
+    /// Parse a rule's stored conditions. `None` means the rule has none.
+    pub fn parse(value: &Value) -> Result<Option<Condition>, String> {
+        match value {
+            Value::Null => Ok(None),
+            Value::Object(map) if map.is_empty() => Ok(None),
+            _ => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|_| format!("unrecognised condition: {}", value)),
+        }
+    }
+
+    pub fn matches(&self, payload: &Value) -> bool {
+        match self {
+            Self::All { all } => all.iter().all(|c| c.matches(payload)),
+            Self::Any { any } => any.iter().any(|c| c.matches(payload)),
+            Self::Not { not } => !not.matches(payload),
+            Self::Compare { field, op, value } => compare(lookup(payload, field), *op, value),
+        }
+    }
+}
+
+fn lookup<'a>(payload: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(payload, |v, key| match v {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+        .filter(|v| !v.is_null())
+}
+
+fn compare(actual: Option<&Value>, op: Operator, expected: &Value) -> bool {
+    match op {
+        Operator::Exists => actual.is_some() == expected.as_bool().unwrap_or(true),
+        Operator::Eq => actual.is_some_and(|a| equal(a, expected)),
+        Operator::Neq => !actual.is_some_and(|a| equal(a, expected)),
+        Operator::In => actual.is_some_and(|a| one_of(a, expected)),
+        Operator::NotIn => !actual.is_some_and(|a| one_of(a, expected)),
+        Operator::Gt => order(actual, expected).is_some_and(|o| o.is_gt()),
+        Operator::Gte => order(actual, expected).is_some_and(|o| o.is_ge()),
+        Operator::Lt => order(actual, expected).is_some_and(|o| o.is_lt()),
+        Operator::Lte => order(actual, expected).is_some_and(|o| o.is_le()),
+        Operator::Contains => match (actual, expected) {
+            (Some(Value::String(a)), Value::String(e)) => {
+                a.to_lowercase().contains(&e.to_lowercase())
+            }
+            (Some(Value::Array(items)), e) => items.iter().any(|item| equal(item, e)),
+            _ => false,
+        },
+    }
+}
+
+/// JSON equality, treating `5` and `5.0` as the same number.
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn one_of(a: &Value, expected: &Value) -> bool {
+    expected
+        .as_array()
+        .is_some_and(|options| options.iter().any(|o| equal(a, o)))
+}
+
+/// Numbers compare numerically and strings (e.g. ISO dates) lexically;
+/// anything else is unordered.
+fn order(actual: Option<&Value>, expected: &Value) -> Option<std::cmp::Ordering> {
+    match (actual?, expected) {
+        (Value::String(a), Value::String(e)) => Some(a.as_str().cmp(e.as_str())),
+        (a, e) => a.as_f64()?.partial_cmp(&e.as_f64()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(condition: Value, payload: Value) -> bool {
+        Condition::parse(&condition)
+            .unwrap()
+            .map_or(true, |c| c.matches(&payload))
+    }
+
+    #[test]
+    fn test_empty_conditions_always_match() {
+        assert!(check(json!({}), json!({"to_stage": "offer"})));
+        assert!(check(Value::Null, json!({})));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let event = json!({
+            "to_stage": "offer",
+            "amount_cents": 150000,
+            "candidate": {"skills": ["Rust", "SQL"], "city": "Austin"},
+        });
+        assert!(check(
+            json!({"field": "to_stage", "op": "eq", "value": "offer"}),
+            event.clone()
+        ));
+        assert!(!check(
+            json!({"field": "to_stage", "op": "neq", "value": "offer"}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "to_stage", "op": "in", "value": ["offer", "hired"]}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "amount_cents", "op": "gte", "value": 150000.0}),
+            event.clone()
+        ));
+        assert!(!check(
+            json!({"field": "amount_cents", "op": "lt", "value": 1000}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "candidate.skills", "op": "contains", "value": "Rust"}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "candidate.city", "op": "contains", "value": "aus"}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "candidate.phone", "op": "exists", "value": false}),
+            event.clone()
+        ));
+        // A missing field is never equal, and so always "not equal".
+        assert!(!check(
+            json!({"field": "from_stage", "op": "eq", "value": "onsite"}),
+            event.clone()
+        ));
+        assert!(check(
+            json!({"field": "from_stage", "op": "neq", "value": "onsite"}),
+            event
+        ));
+    }
+
+    #[test]
+    fn test_groups() {
+        let event = json!({"to_stage": "rejected", "from_stage": "onsite"});
+        let late_rejection = json!({"all": [
+            {"field": "to_stage", "op": "eq", "value": "rejected"},
+            {"any": [
+                {"field": "from_stage", "op": "eq", "value": "onsite"},
+                {"field": "from_stage", "op": "eq", "value": "offer"},
+            ]},
+        ]});
+        assert!(check(late_rejection.clone(), event.clone()));
+        assert!(!check(json!({"not": late_rejection}), event));
+    }
+
+    #[test]
+    fn test_unrecognised_conditions_are_errors() {
+        assert!(Condition::parse(&json!({"field": "x", "op": "matches"})).is_err());
+        assert!(Condition::parse(&json!([1, 2])).is_err());
+    }
+}
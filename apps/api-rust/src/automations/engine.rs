@@ -0,0 +1,171 @@
+//! Running a tenant's automation rules for a domain event.
+//!
+//! Each matching rule's actions run in their own savepoint, so a failing rule
+//! is rolled back and logged without affecting the others. Events raised by
+//! a rule's actions carry the chain of rules behind them: a rule never runs
+//! twice in one chain, and chains stop at [`MAX_CHAIN_DEPTH`].
+
+use anyhow::Context;
+use serde_json::{json, Map, Value};
+use sqlx::{Acquire, PgConnection};
+use uuid::Uuid;
+
+use super::actions::{self, ActionContext};
+use super::events::Envelope;
+use super::handler::AutomationRule;
+use super::schema::{self, RuleDefinition};
+use crate::error::FieldError;
+use crate::scheduler::{tenant_tx, Job};
+use crate::AppState;
+
+/// Rules deep an event chain may go before further rules are skipped.
+pub const MAX_CHAIN_DEPTH: usize = 3;
+
+/// Outcome of one rule for one event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleOutcome {
+    /// Conditions did not match; nothing is logged.
+    NotMatched,
+    Skipped(String),
+    Succeeded(Vec<Value>),
+    Failed(String),
+}
+
+/// Job handler for [`super::events::JOB_TYPE`].
+pub async fn run(state: AppState, job: Job) -> anyhow::Result<()> {
+    let tenant_id = job
+        .tenant_id
+        .context("automation event queued without a tenant")?;
+    let envelope: Envelope =
+        serde_json::from_value(job.payload).context("invalid automation event")?;
+
+    let mut tx = tenant_tx(&state.db, tenant_id).await?;
+    let rules: Vec<AutomationRule> = sqlx::query_as(
+        "SELECT * FROM automation_rules \
+         WHERE tenant_id = $1 AND trigger_event = $2 AND is_active = true \
+         ORDER BY created_at, id",
+    )
+    .bind(tenant_id)
+    .bind(envelope.event.event_type())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for rule in &rules {
+        let outcome = run_rule(&state, &mut tx, tenant_id, &envelope, rule).await?;
+        record(&mut tx, tenant_id, &envelope, rule, &outcome).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Evaluate one rule against an event and, if it matches, run its actions.
+pub async fn run_rule(
+    state: &AppState,
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    envelope: &Envelope,
+    rule: &AutomationRule,
+) -> anyhow::Result<RuleOutcome> {
+    let definition = match schema::validate(&rule.trigger_event, &rule.conditions, &rule.actions) {
+        Ok(definition) => definition,
+        Err(errors) => return Ok(RuleOutcome::Failed(invalid_rule_message(&errors))),
+    };
+    let payload = envelope.event.payload();
+    if !matches(&definition, &payload) {
+        return Ok(RuleOutcome::NotMatched);
+    }
+
+    if envelope.rule_chain.contains(&rule.id) {
+        return Ok(RuleOutcome::Skipped(
+            "Loop detected: rule already ran earlier in this chain".to_string(),
+        ));
+    }
+    if envelope.rule_chain.len() >= MAX_CHAIN_DEPTH {
+        return Ok(RuleOutcome::Skipped(format!(
+            "Chain depth limit of {} reached",
+            MAX_CHAIN_DEPTH
+        )));
+    }
+
+    let ctx = ActionContext {
+        state,
+        tenant_id,
+        rule,
+        envelope,
+        vars: payload,
+    };
+
+    let mut savepoint = conn.begin().await?;
+    let mut taken = Vec::with_capacity(definition.actions.len());
+    for (index, action) in definition.actions.iter().enumerate() {
+        match actions::execute(&mut savepoint, &ctx, action).await {
+            Ok(summary) => taken.push(summary),
+            Err(e) => {
+                savepoint.rollback().await?;
+                return Ok(RuleOutcome::Failed(format!(
+                    "Action {} ({}) failed: {:#}",
+                    index,
+                    action.name(),
+                    e
+                )));
+            }
+        }
+    }
+    savepoint.commit().await?;
+    Ok(RuleOutcome::Succeeded(taken))
+}
+
+/// Whether an event payload satisfies the rule's conditions.
+pub fn matches(definition: &RuleDefinition, payload: &Map<String, Value>) -> bool {
+    definition
+        .conditions
+        .as_ref()
+        .map_or(true, |c| c.matches(&Value::Object(payload.clone())))
+}
+
+pub fn invalid_rule_message(errors: &[FieldError]) -> String {
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    format!("Invalid rule: {}", errors.join("; "))
+}
+
+/// Write the `automation_log` row for an outcome and count the execution.
+async fn record(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    envelope: &Envelope,
+    rule: &AutomationRule,
+    outcome: &RuleOutcome,
+) -> anyhow::Result<()> {
+    let (status, actions_taken, error) = match outcome {
+        RuleOutcome::NotMatched => return Ok(()),
+        RuleOutcome::Skipped(reason) => ("skipped", json!([]), Some(reason.as_str())),
+        RuleOutcome::Succeeded(taken) => ("success", json!(taken), None),
+        RuleOutcome::Failed(error) => ("failed", json!([]), Some(error.as_str())),
+    };
+
+    sqlx::query(
+        "INSERT INTO automation_log (tenant_id, rule_id, trigger_data, actions_taken, status, error_message) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(tenant_id)
+    .bind(rule.id)
+    .bind(serde_json::to_value(envelope)?)
+    .bind(actions_taken)
+    .bind(status)
+    .bind(error)
+    .execute(&mut *conn)
+    .await?;
+
+    if status != "skipped" {
+        sqlx::query(
+            "UPDATE automation_rules SET execution_count = COALESCE(execution_count, 0) + 1, \
+             last_executed_at = NOW() WHERE id = $1 AND tenant_id = $2",
+        )
+        .bind(rule.id)
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,310 @@
+//! Domain events that automation rules trigger on.
+//!
+//! Handlers [`emit`] an event once their change is made; it is queued as an
+//! `automations.run` job so rules run after the request, in the background.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::scheduler::{self, EnqueueOptions};
+
+pub const JOB_TYPE: &str = "automations.run";
+
+/// Something that happened in a tenant. The tag is the `trigger_event` a
+/// rule matches on; the fields are what its conditions can test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum DomainEvent {
+    #[serde(rename = "application.stage_changed")]
+    ApplicationStageChanged {
+        application_id: Uuid,
+        job_id: Uuid,
+        candidate_id: Uuid,
+        from_stage: String,
+        to_stage: String,
+    },
+    #[serde(rename = "offer.accepted")]
+    OfferAccepted {
+        offer_id: Uuid,
+        application_id: Uuid,
+        job_id: Uuid,
+        candidate_id: Uuid,
+        title: String,
+        base_salary_cents: Option<i64>,
+    },
+    #[serde(rename = "invoice.payment_recorded")]
+    InvoicePaymentRecorded {
+        invoice_id: Uuid,
+        client_id: Uuid,
+        payment_id: Uuid,
+        amount_cents: i64,
+        method: String,
+        invoice_status: String,
+    },
+    #[serde(rename = "candidate.created")]
+    CandidateCreated {
+        candidate_id: Uuid,
+        headline: Option<String>,
+        location_city: Option<String>,
+        location_state: Option<String>,
+        availability_status: Option<String>,
+    },
+}
+
+/// Event types a rule can trigger on, stored as `automation_rules.trigger_event`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    #[serde(rename = "application.stage_changed")]
+    ApplicationStageChanged,
+    #[serde(rename = "offer.accepted")]
+    OfferAccepted,
+    #[serde(rename = "invoice.payment_recorded")]
+    InvoicePaymentRecorded,
+    #[serde(rename = "candidate.created")]
+    CandidateCreated,
+}
+
+impl Trigger {
+    pub const ALL: [Trigger; 4] = [
+        Self::ApplicationStageChanged,
+        Self::OfferAccepted,
+        Self::InvoicePaymentRecorded,
+        Self::CandidateCreated,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ApplicationStageChanged => "application.stage_changed",
+            Self::OfferAccepted => "offer.accepted",
+            Self::InvoicePaymentRecorded => "invoice.payment_recorded",
+            Self::CandidateCreated => "candidate.created",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+
+    /// Payload fields of the event, for conditions and `{{placeholders}}`.
+    pub fn fields(self) -> &'static [&'static str] {
+        match self {
+            Self::ApplicationStageChanged => &[
+                "application_id",
+                "job_id",
+                "candidate_id",
+                "from_stage",
+                "to_stage",
+            ],
+            Self::OfferAccepted => &[
+                "offer_id",
+                "application_id",
+                "job_id",
+                "candidate_id",
+                "title",
+                "base_salary_cents",
+            ],
+            Self::InvoicePaymentRecorded => &[
+                "invoice_id",
+                "client_id",
+                "payment_id",
+                "amount_cents",
+                "method",
+                "invoice_status",
+            ],
+            Self::CandidateCreated => &[
+                "candidate_id",
+                "headline",
+                "location_city",
+                "location_state",
+                "availability_status",
+            ],
+        }
+    }
+
+    pub fn has_field(self, field: &str) -> bool {
+        self.fields().contains(&field)
+    }
+}
+
+impl DomainEvent {
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Self::ApplicationStageChanged { .. } => Trigger::ApplicationStageChanged,
+            Self::OfferAccepted { .. } => Trigger::OfferAccepted,
+            Self::InvoicePaymentRecorded { .. } => Trigger::InvoicePaymentRecorded,
+            Self::CandidateCreated { .. } => Trigger::CandidateCreated,
+        }
+    }
+
+    pub fn event_type(&self) -> &'static str {
+        self.trigger().as_str()
+    }
+
+    /// The event as the object conditions are evaluated against.
+    pub fn payload(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    pub fn application_id(&self) -> Option<Uuid> {
+        match self {
+            Self::ApplicationStageChanged { application_id, .. }
+            | Self::OfferAccepted { application_id, .. } => Some(*application_id),
+            _ => None,
+        }
+    }
+
+    pub fn candidate_id(&self) -> Option<Uuid> {
+        match self {
+            Self::ApplicationStageChanged { candidate_id, .. }
+            | Self::OfferAccepted { candidate_id, .. }
+            | Self::CandidateCreated { candidate_id, .. } => Some(*candidate_id),
+            Self::InvoicePaymentRecorded { .. } => None,
+        }
+    }
+
+    pub fn job_id(&self) -> Option<Uuid> {
+        match self {
+            Self::ApplicationStageChanged { job_id, .. } | Self::OfferAccepted { job_id, .. } => {
+                Some(*job_id)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn invoice_id(&self) -> Option<Uuid> {
+        match self {
+            Self::InvoicePaymentRecorded { invoice_id, .. } => Some(*invoice_id),
+            _ => None,
+        }
+    }
+
+    pub fn client_id(&self) -> Option<Uuid> {
+        match self {
+            Self::InvoicePaymentRecorded { client_id, .. } => Some(*client_id),
+            _ => None,
+        }
+    }
+
+    /// The record the event is about, as `(resource_type, id)`.
+    pub fn resource(&self) -> (&'static str, Uuid) {
+        match self {
+            Self::ApplicationStageChanged { application_id, .. } => {
+                ("application", *application_id)
+            }
+            Self::OfferAccepted { offer_id, .. } => ("offer", *offer_id),
+            Self::InvoicePaymentRecorded { invoice_id, .. } => ("invoice", *invoice_id),
+            Self::CandidateCreated { candidate_id, .. } => ("candidate", *candidate_id),
+        }
+    }
+}
+
+/// An event together with the automation rules that led to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub event: DomainEvent,
+    /// The user whose action raised the event; for events raised by a rule,
+    /// the rule's creator.
+    pub actor_id: Option<Uuid>,
+    /// Rules whose actions caused this event, outermost first. Empty for
+    /// events raised by users.
+    #[serde(default)]
+    pub rule_chain: Vec<Uuid>,
+}
+
+/// Queue a user-raised event for the tenant's automation rules.
+pub async fn emit(
+    db: impl sqlx::PgExecutor<'_>,
+    tenant_id: Uuid,
+    actor_id: Uuid,
+    event: DomainEvent,
+) -> Result<(), sqlx::Error> {
+    emit_envelope(
+        db,
+        tenant_id,
+        &Envelope {
+            event,
+            actor_id: Some(actor_id),
+            rule_chain: Vec::new(),
+        },
+    )
+    .await
+}
+
+pub(crate) async fn emit_envelope(
+    db: impl sqlx::PgExecutor<'_>,
+    tenant_id: Uuid,
+    envelope: &Envelope,
+) -> Result<(), sqlx::Error> {
+    let payload = serde_json::to_value(envelope).expect("envelope serializes");
+    scheduler::enqueue(
+        db,
+        JOB_TYPE,
+        payload,
+        EnqueueOptions {
+            tenant_id: Some(tenant_id),
+            max_attempts: Some(3),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_fields_match_event_payloads() {
+        let id = Uuid::nil();
+        let events = [
+            DomainEvent::ApplicationStageChanged {
+                application_id: id,
+                job_id: id,
+                candidate_id: id,
+                from_stage: "applied".into(),
+                to_stage: "screening".into(),
+            },
+            DomainEvent::OfferAccepted {
+                offer_id: id,
+                application_id: id,
+                job_id: id,
+                candidate_id: id,
+                title: "Engineer".into(),
+                base_salary_cents: None,
+            },
+            DomainEvent::InvoicePaymentRecorded {
+                invoice_id: id,
+                client_id: id,
+                payment_id: id,
+                amount_cents: 100,
+                method: "check".into(),
+                invoice_status: "paid".into(),
+            },
+            DomainEvent::CandidateCreated {
+                candidate_id: id,
+                headline: None,
+                location_city: None,
+                location_state: None,
+                availability_status: None,
+            },
+        ];
+        for event in events {
+            let payload = event.payload();
+            assert_eq!(payload["event"], event.event_type());
+            let mut keys: Vec<&str> = payload
+                .keys()
+                .map(String::as_str)
+                .filter(|k| *k != "event")
+                .collect();
+            let mut fields = event.trigger().fields().to_vec();
+            keys.sort();
+            fields.sort();
+            assert_eq!(keys, fields, "{}", event.event_type());
+        }
+    }
+}
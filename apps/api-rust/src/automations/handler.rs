@@ -4,11 +4,16 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, PgConnection};
 use uuid::Uuid;
 
+use super::actions::{self, ActionContext};
+use super::engine;
+use super::events::{DomainEvent, Envelope};
+use super::schema;
 use crate::auth::jwt::Claims;
-use crate::error::{AppError, AppResult};
+use crate::error::{AppError, AppResult, FieldError};
+use crate::middleware::tenant::TenantTx;
 use crate::AppState;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -56,6 +61,26 @@ pub struct UpdateRulePayload {
     pub is_active: Option<bool>,
 }
 
+/// Event to dry-run a rule against. With neither field set, the tenant's
+/// most recent event of the rule's trigger is used.
+#[derive(Debug, Default, Deserialize)]
+pub struct TestRulePayload {
+    /// Sample event fields; `event` defaults to the rule's trigger.
+    pub event: Option<serde_json::Value>,
+    /// Replay the event recorded in this execution log entry.
+    pub log_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestRuleResult {
+    /// Where the event came from: `sample`, `log` or `recent`.
+    pub source: &'static str,
+    pub event: serde_json::Value,
+    pub matched: bool,
+    /// What each action would do, or why it would fail.
+    pub actions: Vec<serde_json::Value>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LogParams {
     pub page: Option<i64>,
@@ -63,15 +88,12 @@ pub struct LogParams {
     pub rule_id: Option<Uuid>,
 }
 
-pub async fn list_rules(
-    State(state): State<AppState>,
-    claims: Claims,
-) -> AppResult<Json<Vec<AutomationRule>>> {
+pub async fn list_rules(mut tx: TenantTx, claims: Claims) -> AppResult<Json<Vec<AutomationRule>>> {
     let rules = sqlx::query_as::<_, AutomationRule>(
         "SELECT * FROM automation_rules WHERE tenant_id = $1 ORDER BY is_active DESC, name ASC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -79,10 +101,14 @@ pub async fn list_rules(
 }
 
 pub async fn create_rule(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<CreateRulePayload>,
 ) -> AppResult<(StatusCode, Json<AutomationRule>)> {
+    let conditions = payload.conditions.unwrap_or(serde_json::json!({}));
+    schema::validate(&payload.trigger_event, &conditions, &payload.actions)
+        .map_err(AppError::InvalidFields)?;
+
     let rule = sqlx::query_as::<_, AutomationRule>(
         r#"INSERT INTO automation_rules (tenant_id, name, trigger_event, conditions, actions, created_by)
            VALUES ($1, $2, $3, $4, $5, $6)
@@ -91,10 +117,10 @@ pub async fn create_rule(
     .bind(claims.tid)
     .bind(&payload.name)
     .bind(&payload.trigger_event)
-    .bind(payload.conditions.unwrap_or(serde_json::json!({})))
+    .bind(&conditions)
     .bind(&payload.actions)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -102,11 +128,25 @@ pub async fn create_rule(
 }
 
 pub async fn update_rule(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateRulePayload>,
 ) -> AppResult<Json<AutomationRule>> {
+    if payload.trigger_event.is_some() || payload.conditions.is_some() || payload.actions.is_some()
+    {
+        let existing = fetch_rule(&mut tx, claims.tid, id).await?;
+        schema::validate(
+            payload
+                .trigger_event
+                .as_deref()
+                .unwrap_or(&existing.trigger_event),
+            payload.conditions.as_ref().unwrap_or(&existing.conditions),
+            payload.actions.as_ref().unwrap_or(&existing.actions),
+        )
+        .map_err(AppError::InvalidFields)?;
+    }
+
     let rule = sqlx::query_as::<_, AutomationRule>(
         r#"UPDATE automation_rules SET
             name = COALESCE($3, name),
@@ -125,7 +165,7 @@ pub async fn update_rule(
     .bind(payload.conditions)
     .bind(payload.actions)
     .bind(payload.is_active)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -133,15 +173,143 @@ pub async fn update_rule(
     Ok(Json(rule))
 }
 
-pub async fn delete_rule(
+async fn fetch_rule(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> AppResult<AutomationRule> {
+    sqlx::query_as::<_, AutomationRule>(
+        "SELECT * FROM automation_rules WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound("Resource not found".into()))
+}
+
+/// Evaluate a rule against a sample or past event and report the actions it
+/// would take, without taking them.
+pub async fn test_rule(
     State(state): State<AppState>,
     claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<TestRulePayload>,
+) -> AppResult<Json<TestRuleResult>> {
+    let rule = fetch_rule(&mut tx, claims.tid, id).await?;
+    let definition = schema::validate(&rule.trigger_event, &rule.conditions, &rule.actions)
+        .map_err(AppError::InvalidFields)?;
+    let trigger = definition.trigger.as_str();
+
+    let (source, event) = match (payload.event, payload.log_id) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::Validation(
+                "Provide either event or log_id, not both".into(),
+            ))
+        }
+        (Some(mut sample), None) => {
+            if let serde_json::Value::Object(fields) = &mut sample {
+                fields
+                    .entry("event")
+                    .or_insert_with(|| trigger.to_string().into());
+            }
+            let event: DomainEvent = serde_json::from_value(sample).map_err(|e| {
+                AppError::InvalidFields(vec![FieldError::new("event", e.to_string())])
+            })?;
+            ("sample", event)
+        }
+        (None, Some(log_id)) => {
+            let trigger_data: Option<serde_json::Value> = sqlx::query_scalar(
+                "SELECT trigger_data FROM automation_log WHERE id = $1 AND tenant_id = $2",
+            )
+            .bind(log_id)
+            .bind(claims.tid)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound("Log entry not found".into()))?;
+            let envelope: Envelope = trigger_data
+                .and_then(|data| serde_json::from_value(data).ok())
+                .ok_or_else(|| AppError::Validation("Log entry has no replayable event".into()))?;
+            ("log", envelope.event)
+        }
+        (None, None) => {
+            let recent: serde_json::Value = sqlx::query_scalar(
+                "SELECT payload FROM background_jobs \
+                 WHERE tenant_id = $1 AND job_type = $2 AND payload->'event'->>'event' = $3 \
+                 ORDER BY created_at DESC LIMIT 1",
+            )
+            .bind(claims.tid)
+            .bind(super::events::JOB_TYPE)
+            .bind(trigger)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "No {} events recorded yet; pass a sample event",
+                    trigger
+                ))
+            })?;
+            let envelope: Envelope = serde_json::from_value(recent)
+                .map_err(|e| AppError::Internal(format!("Unreadable queued event: {}", e)))?;
+            ("recent", envelope.event)
+        }
+    };
+
+    if event.trigger() != definition.trigger {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "event.event",
+            format!("rule triggers on {}, not {}", trigger, event.event_type()),
+        )]));
+    }
+
+    let vars = event.payload();
+    let matched = engine::matches(&definition, &vars);
+    let envelope = Envelope {
+        event,
+        actor_id: Some(claims.sub),
+        rule_chain: Vec::new(),
+    };
+
+    let mut previews = Vec::new();
+    if matched {
+        let ctx = ActionContext {
+            state: &state,
+            tenant_id: claims.tid,
+            rule: &rule,
+            envelope: &envelope,
+            vars: vars.clone(),
+        };
+        for action in &definition.actions {
+            let preview = match actions::preview(&mut tx, &ctx, action).await {
+                Ok(preview) => preview,
+                Err(e) => serde_json::json!({
+                    "type": action.name(),
+                    "error": format!("{:#}", e),
+                }),
+            };
+            previews.push(preview);
+        }
+    }
+
+    Ok(Json(TestRuleResult {
+        source,
+        event: serde_json::Value::Object(vars),
+        matched,
+        actions: previews,
+    }))
+}
+
+pub async fn delete_rule(
+    mut tx: TenantTx,
+    claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     sqlx::query("DELETE FROM automation_rules WHERE id = $1 AND tenant_id = $2")
         .bind(id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::Database)?;
 
@@ -149,7 +317,7 @@ pub async fn delete_rule(
 }
 
 pub async fn toggle_rule(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<AutomationRule>> {
@@ -160,7 +328,7 @@ pub async fn toggle_rule(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -169,7 +337,7 @@ pub async fn toggle_rule(
 }
 
 pub async fn list_execution_log(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Query(params): Query<LogParams>,
 ) -> AppResult<Json<Vec<AutomationLogEntry>>> {
@@ -187,7 +355,7 @@ pub async fn list_execution_log(
     .bind(per_page)
     .bind(offset)
     .bind(params.rule_id)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -1 +1,25 @@
+pub mod actions;
+pub mod conditions;
+pub mod engine;
+pub mod events;
 pub mod handler;
+pub mod schema;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/automations", get(handler::list_rules))
+        .route("/automations", post(handler::create_rule))
+        .route("/automations/{id}", put(handler::update_rule))
+        .route("/automations/{id}", delete(handler::delete_rule))
+        .route("/automations/{id}/toggle", post(handler::toggle_rule))
+        .route("/automations/{id}/test", post(handler::test_rule))
+        .route("/automations/log", get(handler::list_execution_log))
+}
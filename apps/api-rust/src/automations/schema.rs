@@ -0,0 +1,480 @@
+//! Validation of rule definitions against the supported triggers, condition
+//! operators and actions. Every problem is reported with the path of the
+//! offending field, e.g. `conditions.all[0].op` or `actions[1].to_stage`.
+
+use serde_json::{Map, Value};
+
+use super::actions::Action;
+use super::conditions::{Condition, Operator};
+use super::events::Trigger;
+use crate::applications::model::STAGES;
+use crate::error::FieldError;
+
+const PRIORITIES: [&str; 4] = ["low", "medium", "high", "urgent"];
+
+/// A rule definition that has passed validation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleDefinition {
+    pub trigger: Trigger,
+    /// `None` when the rule has no conditions and always runs.
+    pub conditions: Option<Condition>,
+    pub actions: Vec<Action>,
+}
+
+/// Check a rule's trigger, conditions and actions, collecting every error.
+pub fn validate(
+    trigger_event: &str,
+    conditions: &Value,
+    actions: &Value,
+) -> Result<RuleDefinition, Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    let Some(trigger) = Trigger::parse(trigger_event) else {
+        errors.push(FieldError::new(
+            "trigger_event",
+            format!(
+                "unknown trigger '{}'; expected one of: {}",
+                trigger_event,
+                Trigger::ALL.map(Trigger::as_str).join(", ")
+            ),
+        ));
+        return Err(errors);
+    };
+
+    let conditions = match conditions {
+        Value::Null => None,
+        Value::Object(map) if map.is_empty() => None,
+        value => {
+            check_condition(trigger, value, "conditions", &mut errors);
+            serde_json::from_value(value.clone()).ok()
+        }
+    };
+
+    let mut parsed = Vec::new();
+    match actions {
+        Value::Array(items) if items.is_empty() => {
+            errors.push(FieldError::new(
+                "actions",
+                "at least one action is required",
+            ));
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let path = format!("actions[{}]", i);
+                let before = errors.len();
+                check_action(trigger, item, &path, &mut errors);
+                if errors.len() == before {
+                    match serde_json::from_value::<Action>(item.clone()) {
+                        Ok(action) => parsed.push(action),
+                        Err(e) => errors.push(FieldError::new(path, e.to_string())),
+                    }
+                }
+            }
+        }
+        _ => errors.push(FieldError::new("actions", "must be an array")),
+    }
+
+    if errors.is_empty() {
+        Ok(RuleDefinition {
+            trigger,
+            conditions,
+            actions: parsed,
+        })
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_condition(trigger: Trigger, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let Value::Object(map) = value else {
+        errors.push(FieldError::new(path, "must be an object"));
+        return;
+    };
+
+    for group in ["all", "any"] {
+        if let Some(children) = map.get(group) {
+            only_key(map, group, path, errors);
+            let path = format!("{}.{}", path, group);
+            match children {
+                Value::Array(items) if !items.is_empty() => {
+                    for (i, child) in items.iter().enumerate() {
+                        check_condition(trigger, child, &format!("{}[{}]", path, i), errors);
+                    }
+                }
+                _ => errors.push(FieldError::new(path, "must be a non-empty array")),
+            }
+            return;
+        }
+    }
+    if let Some(child) = map.get("not") {
+        only_key(map, "not", path, errors);
+        check_condition(trigger, child, &format!("{}.not", path), errors);
+        return;
+    }
+
+    for key in map.keys() {
+        if !["field", "op", "value"].contains(&key.as_str()) {
+            errors.push(FieldError::new(
+                format!("{}.{}", path, key),
+                "unknown key; a condition has field, op and value, or one of all, any, not",
+            ));
+        }
+    }
+
+    match map.get("field") {
+        Some(Value::String(field)) => {
+            let root = field.split('.').next().unwrap_or_default();
+            if !trigger.has_field(root) {
+                errors.push(FieldError::new(
+                    format!("{}.field", path),
+                    format!(
+                        "'{}' is not a field of {} events; expected one of: {}",
+                        field,
+                        trigger.as_str(),
+                        trigger.fields().join(", ")
+                    ),
+                ));
+            }
+        }
+        Some(_) => errors.push(FieldError::new(
+            format!("{}.field", path),
+            "must be a string",
+        )),
+        None => errors.push(FieldError::new(format!("{}.field", path), "is required")),
+    }
+
+    let op = match map.get("op") {
+        Some(Value::String(op)) => match Operator::parse(op) {
+            Some(op) => Some(op),
+            None => {
+                errors.push(FieldError::new(
+                    format!("{}.op", path),
+                    format!(
+                        "unknown operator '{}'; expected one of: {}",
+                        op,
+                        Operator::ALL.map(Operator::as_str).join(", ")
+                    ),
+                ));
+                None
+            }
+        },
+        Some(_) => {
+            errors.push(FieldError::new(format!("{}.op", path), "must be a string"));
+            None
+        }
+        None => {
+            errors.push(FieldError::new(format!("{}.op", path), "is required"));
+            None
+        }
+    };
+
+    if let Some(op) = op {
+        let value = map.get("value").unwrap_or(&Value::Null);
+        let expected = match op {
+            Operator::Eq | Operator::Neq => (value.is_null()).then_some("a value"),
+            Operator::In | Operator::NotIn => (!value.is_array()).then_some("an array"),
+            Operator::Gt | Operator::Gte | Operator::Lt | Operator::Lte => {
+                (!value.is_number() && !value.is_string()).then_some("a number or string")
+            }
+            Operator::Contains => {
+                (!value.is_number() && !value.is_string()).then_some("a number or string")
+            }
+            Operator::Exists => (!value.is_null() && !value.is_boolean()).then_some("a boolean"),
+        };
+        if let Some(expected) = expected {
+            errors.push(FieldError::new(
+                format!("{}.value", path),
+                format!("'{}' needs {}", op.as_str(), expected),
+            ));
+        }
+    }
+}
+
+fn only_key(map: &Map<String, Value>, key: &str, path: &str, errors: &mut Vec<FieldError>) {
+    for other in map.keys().filter(|k| *k != key) {
+        errors.push(FieldError::new(
+            format!("{}.{}", path, other),
+            format!("not allowed alongside '{}'", key),
+        ));
+    }
+}
+
+/// How an action field's value is checked.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// Text that may contain `{{placeholders}}` for event fields.
+    Text,
+    Uuid,
+    Days,
+    Stage,
+    Priority,
+    Recipient,
+}
+
+fn action_fields(action_type: &str) -> &'static [(&'static str, Kind, bool)] {
+    match action_type {
+        "move_stage" => &[("to_stage", Kind::Stage, true)],
+        "create_task" => &[
+            ("title", Kind::Text, true),
+            ("description", Kind::Text, false),
+            ("assigned_to", Kind::Uuid, false),
+            ("priority", Kind::Priority, false),
+            ("due_in_days", Kind::Days, false),
+        ],
+        "send_email_template" => &[
+            ("template_id", Kind::Uuid, true),
+            ("to", Kind::Recipient, false),
+        ],
+        "notify_user" => &[
+            ("user_id", Kind::Uuid, false),
+            ("title", Kind::Text, true),
+            ("body", Kind::Text, false),
+        ],
+        "add_to_talent_pool" => &[("pool_id", Kind::Uuid, true)],
+        _ => &[],
+    }
+}
+
+fn check_action(trigger: Trigger, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let Value::Object(map) = value else {
+        errors.push(FieldError::new(path, "must be an object"));
+        return;
+    };
+    let action_type = match map.get("type") {
+        Some(Value::String(t)) if Action::TYPES.contains(&t.as_str()) => t.as_str(),
+        Some(Value::String(t)) => {
+            errors.push(FieldError::new(
+                format!("{}.type", path),
+                format!(
+                    "unknown action '{}'; expected one of: {}",
+                    t,
+                    Action::TYPES.join(", ")
+                ),
+            ));
+            return;
+        }
+        _ => {
+            errors.push(FieldError::new(format!("{}.type", path), "is required"));
+            return;
+        }
+    };
+
+    // Actions that act on a record the trigger's events must carry.
+    let needs = match action_type {
+        "move_stage" => Some("application_id"),
+        "add_to_talent_pool" => Some("candidate_id"),
+        _ => None,
+    };
+    if let Some(field) = needs.filter(|f| !trigger.has_field(f)) {
+        errors.push(FieldError::new(
+            format!("{}.type", path),
+            format!(
+                "{} events have no {}, so '{}' cannot be used",
+                trigger.as_str(),
+                field,
+                action_type
+            ),
+        ));
+    }
+
+    let fields = action_fields(action_type);
+    for key in map.keys().filter(|k| *k != "type") {
+        if !fields.iter().any(|(name, ..)| name == key) {
+            errors.push(FieldError::new(
+                format!("{}.{}", path, key),
+                format!("unknown field for '{}'", action_type),
+            ));
+        }
+    }
+
+    for (name, kind, required) in fields {
+        let path = format!("{}.{}", path, name);
+        let value = match map.get(*name) {
+            None | Some(Value::Null) => {
+                if *required {
+                    errors.push(FieldError::new(path, "is required"));
+                }
+                continue;
+            }
+            Some(value) => value,
+        };
+        if let Err(message) = check_value(trigger, *kind, value) {
+            errors.push(FieldError::new(path, message));
+        }
+    }
+
+    // Emails go to the candidate unless `to` says otherwise.
+    if action_type == "send_email_template"
+        && map.get("to").map_or(true, Value::is_null)
+        && !trigger.has_field("candidate_id")
+    {
+        errors.push(FieldError::new(
+            format!("{}.to", path),
+            format!(
+                "is required: {} events have no candidate to email",
+                trigger.as_str()
+            ),
+        ));
+    }
+}
+
+fn check_value(trigger: Trigger, kind: Kind, value: &Value) -> Result<(), String> {
+    let text = value.as_str();
+    match kind {
+        Kind::Uuid => match text.map(uuid::Uuid::parse_str) {
+            Some(Ok(_)) => Ok(()),
+            _ => Err("must be a UUID".to_string()),
+        },
+        Kind::Days => match value.as_i64() {
+            Some(days) if (0..=3650).contains(&days) => Ok(()),
+            _ => Err("must be a whole number of days between 0 and 3650".to_string()),
+        },
+        Kind::Stage => one_of(text, &STAGES, "stage"),
+        Kind::Priority => one_of(text, &PRIORITIES, "priority"),
+        Kind::Recipient => match text {
+            Some("candidate") if !trigger.has_field("candidate_id") => Err(format!(
+                "{} events have no candidate to email",
+                trigger.as_str()
+            )),
+            Some("client") if !trigger.has_field("client_id") => Err(format!(
+                "{} events have no client to email",
+                trigger.as_str()
+            )),
+            Some("candidate" | "client") => Ok(()),
+            Some(address) if address.contains('@') => Ok(()),
+            _ => Err("must be 'candidate', 'client' or an email address".to_string()),
+        },
+        Kind::Text => {
+            let Some(text) = text.filter(|t| !t.trim().is_empty()) else {
+                return Err("must be a non-empty string".to_string());
+            };
+            let unknown: Vec<&str> = placeholders(text)
+                .filter(|name| !trigger.has_field(name))
+                .collect();
+            if unknown.is_empty() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "unknown placeholder {}; {} events have: {}",
+                    unknown
+                        .iter()
+                        .map(|n| format!("{{{{{}}}}}", n))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    trigger.as_str(),
+                    trigger.fields().join(", ")
+                ))
+            }
+        }
+    }
+}
+
+fn one_of(value: Option<&str>, allowed: &[&str], what: &str) -> Result<(), String> {
+    match value {
+        Some(v) if allowed.contains(&v) => Ok(()),
+        Some(v) => Err(format!(
+            "unknown {} '{}'; expected one of: {}",
+            what,
+            v,
+            allowed.join(", ")
+        )),
+        None => Err("must be a string".to_string()),
+    }
+}
+
+/// Names of the `{{ name }}` placeholders in `text`.
+fn placeholders(text: &str) -> impl Iterator<Item = &str> {
+    text.split("{{")
+        .skip(1)
+        .filter_map(|part| part.split_once("}}").map(|(name, _)| name.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(result: Result<RuleDefinition, Vec<FieldError>>) -> Vec<String> {
+        result.unwrap_err().into_iter().map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn test_valid_rule_parses_into_typed_definition() {
+        let rule = validate(
+            "application.stage_changed",
+            &json!({"all": [{"field": "to_stage", "op": "in", "value": ["offer", "hired"]}]}),
+            &json!([
+                {"type": "move_stage", "to_stage": "hired"},
+                {"type": "create_task", "title": "Onboard ({{to_stage}})", "due_in_days": 3},
+            ]),
+        )
+        .unwrap();
+        assert_eq!(rule.trigger, Trigger::ApplicationStageChanged);
+        assert!(rule.conditions.is_some());
+        assert_eq!(
+            rule.actions[0],
+            Action::MoveStage {
+                to_stage: "hired".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_trigger() {
+        assert_eq!(
+            paths(validate("application.moved", &json!({}), &json!([]))),
+            ["trigger_event"]
+        );
+    }
+
+    #[test]
+    fn test_condition_errors_have_paths() {
+        let errors = paths(validate(
+            "application.stage_changed",
+            &json!({"all": [
+                {"field": "to_stage", "op": "equals", "value": "offer"},
+                {"any": [{"field": "stage", "op": "eq", "value": "x"}]},
+                {"field": "from_stage", "op": "in", "value": "onsite"},
+            ]}),
+            &json!([{"type": "notify_user", "title": "Hi"}]),
+        ));
+        assert_eq!(
+            errors,
+            [
+                "conditions.all[0].op",
+                "conditions.all[1].any[0].field",
+                "conditions.all[2].value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_action_errors_have_paths() {
+        let errors = validate(
+            "invoice.payment_recorded",
+            &json!({}),
+            &json!([
+                {"type": "send_emial", "template_id": "x"},
+                {"type": "move_stage", "to_stage": "intervew"},
+                {"type": "create_task", "title": "Thank {{client_name}}", "priority": "asap"},
+                {"type": "send_email_template", "template_id": uuid::Uuid::nil(), "cc": "a@b.c"},
+            ]),
+        )
+        .unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "actions[0].type",
+                "actions[1].type",
+                "actions[1].to_stage",
+                "actions[2].title",
+                "actions[2].priority",
+                "actions[3].cc",
+                "actions[3].to",
+            ]
+        );
+        assert!(errors[0].message.contains("send_email_template"));
+        assert!(errors[3].message.contains("{{client_name}}"));
+    }
+}
@@ -6,13 +6,26 @@ use axum::{
 use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
+use crate::automations::events::{self, DomainEvent};
 #[allow(unused_imports)]
 use crate::candidates::model::*;
 use crate::error::{AppError, AppResult};
+use crate::extraction::{self, ExtractionJob, Target};
+use crate::matching;
+use crate::middleware::tenant::TenantTx;
+use crate::subscriptions::entitlements::{self, Meter};
 use crate::AppState;
 
+/// `reputation_score` is NUMERIC, which decodes as `f64` once cast.
+const CANDIDATE_COLUMNS: &str = "id, tenant_id, user_id, headline, summary, location_city, \
+    location_state, location_country, remote_preference, availability_status, \
+    desired_salary_min_cents, desired_salary_max_cents, desired_currency, visa_status, \
+    work_authorization, linkedin_url, portfolio_url, github_url, profile_completeness_pct, \
+    is_anonymous, reputation_score::float8 AS reputation_score, response_rate_pct, \
+    interview_attendance_rate_pct, metadata, created_at, updated_at";
+
 pub async fn list_candidates(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListCandidatesQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -28,8 +41,8 @@ pub async fn list_candidates(
     let location_country = params.location_country.as_deref().unwrap_or("");
     let skill = params.skill.as_deref().unwrap_or("");
 
-    let candidates: Vec<CandidateProfile> = sqlx::query_as(
-        "SELECT cp.* FROM candidate_profiles cp \
+    let candidates: Vec<CandidateProfile> = sqlx::query_as(&format!(
+        "SELECT {} FROM candidate_profiles cp \
          WHERE cp.tenant_id = $1 \
          AND ($2 = '' OR LOWER(cp.headline) LIKE $3 OR LOWER(cp.summary) LIKE $3) \
          AND ($4 = '' OR cp.availability_status = $4) \
@@ -42,7 +55,8 @@ pub async fn list_candidates(
          )) \
          ORDER BY cp.created_at DESC \
          LIMIT $8 OFFSET $9",
-    )
+        CANDIDATE_COLUMNS
+    ))
     .bind(claims.tid)
     .bind(search)
     .bind(&search_pattern)
@@ -52,7 +66,7 @@ pub async fn list_candidates(
     .bind(skill)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -75,7 +89,7 @@ pub async fn list_candidates(
     .bind(remote_preference)
     .bind(location_country)
     .bind(skill)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -90,35 +104,40 @@ pub async fn list_candidates(
 }
 
 pub async fn get_candidate(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<CandidateProfile>> {
-    let candidate: CandidateProfile =
-        sqlx::query_as("SELECT * FROM candidate_profiles WHERE id = $1 AND tenant_id = $2")
-            .bind(id)
-            .bind(claims.tid)
-            .fetch_optional(&state.db)
-            .await?
-            .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
+    let candidate: CandidateProfile = sqlx::query_as(&format!(
+        "SELECT {} FROM candidate_profiles WHERE id = $1 AND tenant_id = $2",
+        CANDIDATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
 
     Ok(Json(candidate))
 }
 
 pub async fn create_candidate(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateCandidateRequest>,
 ) -> AppResult<(StatusCode, Json<CandidateProfile>)> {
-    let candidate: CandidateProfile = sqlx::query_as(
+    entitlements::consume(&mut tx, claims.tid, Meter::CandidateContacts, 1).await?;
+
+    let candidate: CandidateProfile = sqlx::query_as(&format!(
         "INSERT INTO candidate_profiles \
          (tenant_id, user_id, headline, summary, location_city, location_state, \
           location_country, remote_preference, availability_status, \
           desired_salary_min_cents, desired_salary_max_cents, \
           visa_status, work_authorization, linkedin_url, portfolio_url, github_url) \
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) \
-         RETURNING *",
-    )
+         RETURNING {}",
+        CANDIDATE_COLUMNS
+    ))
     .bind(claims.tid)
     .bind(claims.sub)
     .bind(&payload.headline)
@@ -135,19 +154,33 @@ pub async fn create_candidate(
     .bind(&payload.linkedin_url)
     .bind(&payload.portfolio_url)
     .bind(&payload.github_url)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    events::emit(
+        &mut *tx,
+        claims.tid,
+        claims.sub,
+        DomainEvent::CandidateCreated {
+            candidate_id: candidate.id,
+            headline: candidate.headline.clone(),
+            location_city: candidate.location_city.clone(),
+            location_state: candidate.location_state.clone(),
+            availability_status: candidate.availability_status.clone(),
+        },
+    )
     .await?;
 
     Ok((StatusCode::CREATED, Json(candidate)))
 }
 
 pub async fn update_candidate(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateCandidateRequest>,
 ) -> AppResult<Json<CandidateProfile>> {
-    let candidate: CandidateProfile = sqlx::query_as(
+    let candidate: CandidateProfile = sqlx::query_as(&format!(
         "UPDATE candidate_profiles SET \
          headline = COALESCE($3, headline), \
          summary = COALESCE($4, summary), \
@@ -166,8 +199,9 @@ pub async fn update_candidate(
          github_url = COALESCE($17, github_url), \
          updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 \
-         RETURNING *",
-    )
+         RETURNING {}",
+        CANDIDATE_COLUMNS
+    ))
     .bind(id)
     .bind(claims.tid)
     .bind(&payload.headline)
@@ -185,15 +219,17 @@ pub async fn update_candidate(
     .bind(&payload.linkedin_url)
     .bind(&payload.portfolio_url)
     .bind(&payload.github_url)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
 
+    matching::rescore_candidate(&mut tx, claims.tid, candidate.id).await?;
+
     Ok(Json(candidate))
 }
 
 pub async fn list_candidate_skills(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
 ) -> AppResult<Json<Vec<CandidateSkill>>> {
@@ -204,14 +240,14 @@ pub async fn list_candidate_skills(
     )
     .bind(candidate_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(skills))
 }
 
 pub async fn add_candidate_skill(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
     Json(payload): Json<AddSkillRequest>,
@@ -221,7 +257,7 @@ pub async fn add_candidate_skill(
         sqlx::query_as("SELECT id FROM candidate_profiles WHERE id = $1 AND tenant_id = $2")
             .bind(candidate_id)
             .bind(claims.tid)
-            .fetch_optional(&state.db)
+            .fetch_optional(&mut *tx)
             .await?
             .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
 
@@ -239,14 +275,16 @@ pub async fn add_candidate_skill(
     .bind(&payload.proficiency_level)
     .bind(payload.years_experience)
     .bind(&payload.evidence_url)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
+    matching::rescore_candidate(&mut tx, claims.tid, candidate_id).await?;
+
     Ok((StatusCode::CREATED, Json(skill)))
 }
 
 pub async fn delete_candidate_skill(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path((candidate_id, skill_id)): Path<(Uuid, Uuid)>,
 ) -> AppResult<StatusCode> {
@@ -257,54 +295,110 @@ pub async fn delete_candidate_skill(
     .bind(skill_id)
     .bind(candidate_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Skill not found".to_string()));
     }
+    matching::rescore_candidate(&mut tx, claims.tid, candidate_id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn upload_candidate_document(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
     Json(payload): Json<UploadDocumentRequest>,
-) -> AppResult<(StatusCode, Json<CandidateDocument>)> {
+) -> AppResult<(StatusCode, Json<CandidateDocumentUpload>)> {
     // Verify the candidate exists and belongs to this tenant
     let _: (Uuid,) =
         sqlx::query_as("SELECT id FROM candidate_profiles WHERE id = $1 AND tenant_id = $2")
             .bind(candidate_id)
             .bind(claims.tid)
-            .fetch_optional(&state.db)
+            .fetch_optional(&mut *tx)
             .await?
             .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
 
     let is_primary = payload.is_primary.unwrap_or(false);
+    let id = Uuid::new_v4();
+    let s3_key = format!(
+        "tenants/{}/candidates/{}/documents/{}/{}",
+        claims.tid, candidate_id, id, &payload.filename
+    );
 
     let document: CandidateDocument = sqlx::query_as(
         "INSERT INTO candidate_documents \
-         (tenant_id, candidate_id, document_type, filename, mime_type, size_bytes, is_primary) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7) \
+         (id, tenant_id, candidate_id, document_type, filename, mime_type, size_bytes, \
+          s3_key, is_primary) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
          RETURNING *",
     )
+    .bind(id)
     .bind(claims.tid)
     .bind(candidate_id)
     .bind(&payload.document_type)
     .bind(&payload.filename)
     .bind(&payload.mime_type)
     .bind(payload.size_bytes)
+    .bind(&s3_key)
     .bind(is_primary)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok((StatusCode::CREATED, Json(document)))
+    let upload_url =
+        crate::storage::presigned_put_url(&state.config, &s3_key, &payload.mime_type).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CandidateDocumentUpload {
+            document,
+            upload_url,
+        }),
+    ))
+}
+
+/// Mark a document's upload as finished and queue it for extraction. Its
+/// `parsed_data` is `{"status": "pending"}` until the job has run.
+pub async fn complete_candidate_document(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path((candidate_id, document_id)): Path<(Uuid, Uuid)>,
+    payload: Option<Json<CompleteUploadRequest>>,
+) -> AppResult<(StatusCode, Json<CandidateDocument>)> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let document: CandidateDocument = sqlx::query_as(
+        "UPDATE candidate_documents \
+         SET parsed_data = '{\"status\": \"pending\"}', updated_at = NOW() \
+         WHERE id = $1 AND candidate_id = $2 AND tenant_id = $3 AND s3_key IS NOT NULL \
+         RETURNING *",
+    )
+    .bind(document_id)
+    .bind(candidate_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Document not found".to_string()))?;
+
+    extraction::enqueue(
+        &mut *tx,
+        claims.tid,
+        &ExtractionJob {
+            target: Target::CandidateDocument,
+            id: document.id,
+            requested_by: claims.sub,
+            upsert_skills: payload.upsert_skills.unwrap_or(false),
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(document)))
 }
 
 pub async fn list_candidate_documents(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
 ) -> AppResult<Json<Vec<CandidateDocument>>> {
@@ -315,7 +409,7 @@ pub async fn list_candidate_documents(
     )
     .bind(candidate_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(documents))
@@ -324,7 +418,7 @@ pub async fn list_candidate_documents(
 // ── Candidate Notes ──────────────────────────────────────────────────────
 
 pub async fn list_candidate_notes(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
 ) -> AppResult<Json<Vec<CandidateNote>>> {
@@ -335,14 +429,14 @@ pub async fn list_candidate_notes(
     )
     .bind(candidate_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(notes))
 }
 
 pub async fn create_candidate_note(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(candidate_id): Path<Uuid>,
     Json(payload): Json<CreateNoteRequest>,
@@ -369,14 +463,14 @@ pub async fn create_candidate_note(
     .bind(&payload.content)
     .bind(is_private)
     .bind(note_type)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(note)))
 }
 
 pub async fn update_candidate_note(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path((candidate_id, note_id)): Path<(Uuid, Uuid)>,
     Json(payload): Json<UpdateNoteRequest>,
@@ -396,7 +490,7 @@ pub async fn update_candidate_note(
     .bind(&payload.content)
     .bind(payload.is_private)
     .bind(&payload.note_type)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| crate::error::AppError::NotFound("Note not found".to_string()))?;
 
@@ -404,7 +498,7 @@ pub async fn update_candidate_note(
 }
 
 pub async fn delete_candidate_note(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path((candidate_id, note_id)): Path<(Uuid, Uuid)>,
 ) -> AppResult<StatusCode> {
@@ -415,7 +509,7 @@ pub async fn delete_candidate_note(
     .bind(note_id)
     .bind(candidate_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -430,7 +524,7 @@ pub async fn delete_candidate_note(
 // ── Candidate Favorites ──────────────────────────────────────────────────
 
 pub async fn list_favorites(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListFavoritesQuery>,
 ) -> AppResult<Json<Vec<CandidateFavorite>>> {
@@ -445,14 +539,14 @@ pub async fn list_favorites(
     .bind(claims.tid)
     .bind(claims.sub)
     .bind(&job_id_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(favorites))
 }
 
 pub async fn add_favorite(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateFavoriteRequest>,
 ) -> AppResult<(StatusCode, Json<CandidateFavorite>)> {
@@ -472,14 +566,14 @@ pub async fn add_favorite(
     .bind(payload.job_id)
     .bind(&tags)
     .bind(&payload.notes)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(favorite)))
 }
 
 pub async fn remove_favorite(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -490,7 +584,7 @@ pub async fn remove_favorite(
     .bind(id)
     .bind(claims.tid)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -1,2 +1,60 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/candidates", get(handler::list_candidates))
+        .route("/candidates", post(handler::create_candidate))
+        .route("/candidates/{id}", get(handler::get_candidate))
+        .route("/candidates/{id}", patch(handler::update_candidate))
+        .route(
+            "/candidates/{id}/skills",
+            get(handler::list_candidate_skills),
+        )
+        .route(
+            "/candidates/{id}/skills",
+            post(handler::add_candidate_skill),
+        )
+        .route(
+            "/candidates/{id}/skills/{skill_id}",
+            delete(handler::delete_candidate_skill),
+        )
+        .route(
+            "/candidates/{id}/documents",
+            get(handler::list_candidate_documents),
+        )
+        .route(
+            "/candidates/{id}/documents",
+            post(handler::upload_candidate_document),
+        )
+        .route(
+            "/candidates/{id}/documents/{document_id}/complete",
+            post(handler::complete_candidate_document),
+        )
+        // Candidate Notes
+        .route("/candidates/{id}/notes", get(handler::list_candidate_notes))
+        .route(
+            "/candidates/{id}/notes",
+            post(handler::create_candidate_note),
+        )
+        .route(
+            "/candidates/{id}/notes/{note_id}",
+            put(handler::update_candidate_note),
+        )
+        .route(
+            "/candidates/{id}/notes/{note_id}",
+            delete(handler::delete_candidate_note),
+        )
+        // Candidate Favorites
+        .route("/favorites", get(handler::list_favorites))
+        .route("/favorites", post(handler::add_favorite))
+        .route("/favorites/{id}", delete(handler::remove_favorite))
+}
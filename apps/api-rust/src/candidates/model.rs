@@ -25,7 +25,7 @@ pub struct CandidateProfile {
     pub github_url: Option<String>,
     pub profile_completeness_pct: i16,
     pub is_anonymous: bool,
-    pub reputation_score: i16,
+    pub reputation_score: f64,
     pub response_rate_pct: i16,
     pub interview_attendance_rate_pct: i16,
     pub metadata: serde_json::Value,
@@ -119,6 +119,20 @@ pub struct UploadDocumentRequest {
     pub is_primary: Option<bool>,
 }
 
+/// A new candidate document and where to PUT its file.
+#[derive(Debug, Serialize)]
+pub struct CandidateDocumentUpload {
+    #[serde(flatten)]
+    pub document: CandidateDocument,
+    pub upload_url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CompleteUploadRequest {
+    /// Add skills found in the document to the candidate's profile.
+    pub upsert_skills: Option<bool>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListCandidatesQuery {
     pub page: Option<i64>,
@@ -1,11 +1,11 @@
-use axum::{extract::State, Json};
+use axum::Json;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct CareerPage {
@@ -50,10 +50,7 @@ pub struct UpdateCareerPagePayload {
     pub custom_css: Option<String>,
 }
 
-pub async fn get_career_page(
-    State(state): State<AppState>,
-    claims: Claims,
-) -> AppResult<Json<CareerPage>> {
+pub async fn get_career_page(mut tx: TenantTx, claims: Claims) -> AppResult<Json<CareerPage>> {
     // Upsert: create if not exists
     let page = sqlx::query_as::<_, CareerPage>(
         r#"INSERT INTO career_pages (tenant_id)
@@ -62,7 +59,7 @@ pub async fn get_career_page(
            RETURNING *"#,
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -70,7 +67,7 @@ pub async fn get_career_page(
 }
 
 pub async fn update_career_page(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<UpdateCareerPagePayload>,
 ) -> AppResult<Json<CareerPage>> {
@@ -111,24 +108,21 @@ pub async fn update_career_page(
     .bind(payload.meta_title)
     .bind(payload.meta_description)
     .bind(payload.custom_css)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
     Ok(Json(page))
 }
 
-pub async fn publish_career_page(
-    State(state): State<AppState>,
-    claims: Claims,
-) -> AppResult<Json<CareerPage>> {
+pub async fn publish_career_page(mut tx: TenantTx, claims: Claims) -> AppResult<Json<CareerPage>> {
     let page = sqlx::query_as::<_, CareerPage>(
         r#"UPDATE career_pages SET is_published = NOT is_published, updated_at = NOW()
            WHERE tenant_id = $1
            RETURNING *"#,
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
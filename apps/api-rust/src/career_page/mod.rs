@@ -1 +1,16 @@
 pub mod handler;
+
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/career-page", get(handler::get_career_page))
+        .route("/career-page", put(handler::update_career_page))
+        .route("/career-page/publish", post(handler::publish_career_page))
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -9,10 +9,10 @@ use validator::Validate;
 use crate::auth::jwt::Claims;
 use crate::clients::model::*;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 pub async fn list_clients(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListClientsQuery>,
 ) -> AppResult<Json<PaginatedResponse<Client>>> {
@@ -46,7 +46,7 @@ pub async fn list_clients(
         .bind(claims.tid)
         .bind(status_filter)
         .bind(pattern)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -54,7 +54,7 @@ pub async fn list_clients(
         )
         .bind(claims.tid)
         .bind(status_filter)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     };
 
@@ -67,7 +67,7 @@ pub async fn list_clients(
         .bind(pattern)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -77,7 +77,7 @@ pub async fn list_clients(
         .bind(status_filter)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -95,7 +95,7 @@ pub async fn list_clients(
 }
 
 pub async fn get_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
 ) -> AppResult<Json<Client>> {
@@ -104,7 +104,7 @@ pub async fn get_client(
     )
     .bind(client_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Client not found".to_string()))?;
 
@@ -112,7 +112,7 @@ pub async fn get_client(
 }
 
 pub async fn create_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateClientRequest>,
 ) -> AppResult<(StatusCode, Json<Client>)> {
@@ -134,7 +134,7 @@ pub async fn create_client(
     .bind(&payload.business_type)
     .bind(&fiscal_year_end)
     .bind(payload.assigned_cpa_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Insert contacts if provided
@@ -151,7 +151,7 @@ pub async fn create_client(
             .bind(contact.email.as_deref())
             .bind(contact.phone.as_deref())
             .bind(is_primary)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
@@ -160,7 +160,7 @@ pub async fn create_client(
 }
 
 pub async fn update_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Json(payload): Json<UpdateClientRequest>,
@@ -175,7 +175,7 @@ pub async fn update_client(
     )
     .bind(client_id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     if existing == 0 {
@@ -192,7 +192,7 @@ pub async fn update_client(
     .bind(payload.fiscal_year_end.as_deref())
     .bind(payload.status.as_deref())
     .bind(payload.assigned_cpa_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(client))
@@ -201,7 +201,7 @@ pub async fn update_client(
 // ── Client Sub-Resource Endpoints ──────────────────────────────────────
 
 pub async fn list_client_documents(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<SubResourceQuery>,
@@ -219,7 +219,7 @@ pub async fn list_client_documents(
     .bind(client_id)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, filename, category, status, version, created_at)| {
@@ -232,7 +232,7 @@ pub async fn list_client_documents(
     )
     .bind(claims.tid)
     .bind(client_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(
@@ -241,7 +241,7 @@ pub async fn list_client_documents(
 }
 
 pub async fn list_client_time_entries(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<SubResourceQuery>,
@@ -259,7 +259,7 @@ pub async fn list_client_time_entries(
     .bind(client_id)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, desc, stype, dur, rate, billable, date)| {
@@ -271,7 +271,7 @@ pub async fn list_client_time_entries(
         sqlx::query_as("SELECT COUNT(*) FROM time_entries WHERE tenant_id = $1 AND client_id = $2")
             .bind(claims.tid)
             .bind(client_id)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     Ok(Json(
@@ -280,7 +280,7 @@ pub async fn list_client_time_entries(
 }
 
 pub async fn list_client_invoices(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<SubResourceQuery>,
@@ -298,7 +298,7 @@ pub async fn list_client_invoices(
     .bind(client_id)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, num, status, total, due, created)| {
@@ -310,7 +310,7 @@ pub async fn list_client_invoices(
         sqlx::query_as("SELECT COUNT(*) FROM invoices WHERE tenant_id = $1 AND client_id = $2")
             .bind(claims.tid)
             .bind(client_id)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     Ok(Json(
@@ -319,7 +319,7 @@ pub async fn list_client_invoices(
 }
 
 pub async fn list_client_messages(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<SubResourceQuery>,
@@ -337,7 +337,7 @@ pub async fn list_client_messages(
     .bind(client_id)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, sender, content, internal, read, created)| {
@@ -349,7 +349,7 @@ pub async fn list_client_messages(
         sqlx::query_as("SELECT COUNT(*) FROM messages WHERE tenant_id = $1 AND client_id = $2")
             .bind(claims.tid)
             .bind(client_id)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     Ok(Json(
@@ -358,7 +358,7 @@ pub async fn list_client_messages(
 }
 
 pub async fn list_client_deadlines(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -369,7 +369,7 @@ pub async fn list_client_deadlines(
     )
     .bind(claims.tid)
     .bind(client_id)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, ftype, desc, due, status, completed)| {
@@ -381,7 +381,7 @@ pub async fn list_client_deadlines(
 }
 
 pub async fn get_client_timeline(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<SubResourceQuery>,
@@ -398,7 +398,7 @@ pub async fn get_client_timeline(
     .bind(client_id)
     .bind(client_id.to_string())
     .bind(limit)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?
     .into_iter()
     .map(|(id, user_id, action, rtype, rid, details, created)| {
@@ -410,7 +410,7 @@ pub async fn get_client_timeline(
 }
 
 pub async fn delete_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -419,7 +419,7 @@ pub async fn delete_client(
     )
     .bind(client_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -432,7 +432,7 @@ pub async fn delete_client(
 // ── Bulk Operations ──────────────────────────────────────────────────
 
 pub async fn bulk_delete_clients(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -452,7 +452,7 @@ pub async fn bulk_delete_clients(
     )
     .bind(claims.tid)
     .bind(&payload.ids)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -462,7 +462,7 @@ pub async fn bulk_delete_clients(
 }
 
 pub async fn bulk_archive_clients(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -482,7 +482,7 @@ pub async fn bulk_archive_clients(
     )
     .bind(claims.tid)
     .bind(&payload.ids)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -494,7 +494,7 @@ pub async fn bulk_archive_clients(
 // ── Soft Delete: Restore & Trash ─────────────────────────────────────
 
 pub async fn restore_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
 ) -> AppResult<Json<Client>> {
@@ -505,7 +505,7 @@ pub async fn restore_client(
     )
     .bind(client_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Client not found in trash".to_string()))?;
 
@@ -513,7 +513,7 @@ pub async fn restore_client(
 }
 
 pub async fn list_clients_trash(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListClientsQuery>,
 ) -> AppResult<Json<PaginatedResponse<Client>>> {
@@ -525,7 +525,7 @@ pub async fn list_clients_trash(
         "SELECT COUNT(*) FROM clients WHERE tenant_id = $1 AND deleted_at IS NOT NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let clients: Vec<Client> = sqlx::query_as(
@@ -536,7 +536,7 @@ pub async fn list_clients_trash(
     .bind(claims.tid)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -1,2 +1,38 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/clients", get(handler::list_clients))
+        .route("/clients", post(handler::create_client))
+        .route("/clients/{id}", get(handler::get_client))
+        .route("/clients/{id}", put(handler::update_client))
+        .route("/clients/{id}", delete(handler::delete_client))
+        .route(
+            "/clients/{id}/documents",
+            get(handler::list_client_documents),
+        )
+        .route(
+            "/clients/{id}/time-entries",
+            get(handler::list_client_time_entries),
+        )
+        .route("/clients/{id}/invoices", get(handler::list_client_invoices))
+        .route("/clients/{id}/messages", get(handler::list_client_messages))
+        .route(
+            "/clients/{id}/deadlines",
+            get(handler::list_client_deadlines),
+        )
+        .route("/clients/{id}/timeline", get(handler::get_client_timeline))
+        .route("/clients/bulk-delete", post(handler::bulk_delete_clients))
+        .route("/clients/bulk-archive", post(handler::bulk_archive_clients))
+        .route("/clients/trash", get(handler::list_clients_trash))
+        .route("/clients/{id}/restore", post(handler::restore_client))
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -10,10 +10,10 @@ use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::compliance::model::*;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 pub async fn list_deadlines(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListDeadlinesQuery>,
 ) -> AppResult<Json<PaginatedResponse<ComplianceDeadline>>> {
@@ -32,12 +32,12 @@ pub async fn list_deadlines(
         )
         .bind(claims.tid)
         .bind(pattern)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as("SELECT COUNT(*) FROM compliance_deadlines WHERE tenant_id = $1")
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
     };
 
@@ -49,7 +49,7 @@ pub async fn list_deadlines(
         .bind(pattern)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -58,7 +58,7 @@ pub async fn list_deadlines(
         .bind(claims.tid)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -76,7 +76,7 @@ pub async fn list_deadlines(
 }
 
 pub async fn create_deadline(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateDeadlineRequest>,
 ) -> AppResult<(StatusCode, Json<ComplianceDeadline>)> {
@@ -94,14 +94,14 @@ pub async fn create_deadline(
     .bind(payload.due_date)
     .bind(payload.assigned_to)
     .bind(payload.notes.as_deref())
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(deadline)))
 }
 
 pub async fn update_deadline(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(deadline_id): Path<Uuid>,
     Json(payload): Json<UpdateDeadlineRequest>,
@@ -111,7 +111,7 @@ pub async fn update_deadline(
     )
     .bind(deadline_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Deadline not found".to_string()))?;
 
@@ -145,21 +145,21 @@ pub async fn update_deadline(
     .bind(notes)
     .bind(completed_at)
     .bind(extension_filed_at)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(updated))
 }
 
 pub async fn delete_deadline(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(deadline_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     let result = sqlx::query("DELETE FROM compliance_deadlines WHERE id = $1 AND tenant_id = $2")
         .bind(deadline_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -170,7 +170,7 @@ pub async fn delete_deadline(
 }
 
 pub async fn list_consent_records(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let rows = sqlx::query_as::<
@@ -187,7 +187,7 @@ pub async fn list_consent_records(
          FROM consent_records WHERE tenant_id = $1 ORDER BY created_at DESC LIMIT 100",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await;
 
     match rows {
@@ -208,7 +208,7 @@ pub async fn list_consent_records(
 }
 
 pub async fn list_deletion_requests(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let rows = sqlx::query_as::<
@@ -224,7 +224,7 @@ pub async fn list_deletion_requests(
          FROM deletion_requests WHERE tenant_id = $1 ORDER BY created_at DESC LIMIT 100",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await;
 
     match rows {
@@ -244,7 +244,7 @@ pub async fn list_deletion_requests(
 }
 
 pub async fn create_deletion_request(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(body): Json<serde_json::Value>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -262,7 +262,7 @@ pub async fn create_deletion_request(
     .bind(email)
     .bind(reason)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await;
 
     match row {
@@ -275,7 +275,7 @@ pub async fn create_deletion_request(
 }
 
 pub async fn list_retention_policies(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let rows = sqlx::query_as::<
@@ -292,7 +292,7 @@ pub async fn list_retention_policies(
          FROM retention_policies WHERE tenant_id = $1 ORDER BY data_type",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await;
 
     match rows {
@@ -1,2 +1,36 @@
 pub mod handler;
 pub mod model;
+pub mod reminders;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/compliance-deadlines", get(handler::list_deadlines))
+        .route("/compliance-deadlines", post(handler::create_deadline))
+        .route("/compliance-deadlines/{id}", put(handler::update_deadline))
+        .route(
+            "/compliance-deadlines/{id}",
+            delete(handler::delete_deadline),
+        )
+        // Compliance / GDPR
+        .route("/compliance/consent", get(handler::list_consent_records))
+        .route(
+            "/compliance/deletion-requests",
+            get(handler::list_deletion_requests),
+        )
+        .route(
+            "/compliance/deletion-requests",
+            post(handler::create_deletion_request),
+        )
+        .route(
+            "/compliance/retention-policies",
+            get(handler::list_retention_policies),
+        )
+}
@@ -0,0 +1,160 @@
+//! 30/14/7/1-day reminders for upcoming compliance deadlines.
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::notifications::notify::{notify, NewNotification};
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "compliance.deadline_reminders";
+
+/// Reminder windows in days, tightest first.
+const WINDOWS: [i64; 4] = [1, 7, 14, 30];
+
+#[derive(Debug, sqlx::FromRow)]
+struct UpcomingDeadline {
+    id: Uuid,
+    filing_type: String,
+    due_date: NaiveDate,
+    assigned_to: Option<Uuid>,
+    client_name: String,
+    reminder_sent_30d: bool,
+    reminder_sent_14d: bool,
+    reminder_sent_7d: bool,
+    reminder_sent_1d: bool,
+}
+
+impl UpcomingDeadline {
+    fn sent(&self, window: i64) -> bool {
+        match window {
+            1 => self.reminder_sent_1d,
+            7 => self.reminder_sent_7d,
+            14 => self.reminder_sent_14d,
+            _ => self.reminder_sent_30d,
+        }
+    }
+}
+
+/// The tightest reminder window a deadline `days_left` away falls into.
+pub fn reminder_window(days_left: i64) -> Option<i64> {
+    if days_left < 0 {
+        return None;
+    }
+    WINDOWS.into_iter().find(|w| days_left <= *w)
+}
+
+/// Notify assignees (or the firm's managers when unassigned) about open
+/// deadlines entering a reminder window. A deadline first seen inside a tight
+/// window gets a single reminder, and the wider windows are marked as sent.
+pub async fn send_deadline_reminders(state: AppState, _job: Job) -> anyhow::Result<()> {
+    let today = chrono::Utc::now().date_naive();
+
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+
+        let deadlines: Vec<UpcomingDeadline> = sqlx::query_as(
+            "SELECT d.id, d.filing_type, COALESCE(d.extended_due_date, d.due_date) AS due_date, \
+                    d.assigned_to, c.name AS client_name, \
+                    d.reminder_sent_30d, d.reminder_sent_14d, d.reminder_sent_7d, d.reminder_sent_1d \
+             FROM compliance_deadlines d JOIN clients c ON c.id = d.client_id \
+             WHERE d.tenant_id = $1 AND d.status <> 'completed' AND d.completed_at IS NULL \
+               AND COALESCE(d.extended_due_date, d.due_date) BETWEEN $2 AND $2 + 30 \
+               AND NOT d.reminder_sent_1d",
+        )
+        .bind(tenant_id)
+        .bind(today)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for deadline in &deadlines {
+            let days_left = (deadline.due_date - today).num_days();
+            let Some(window) = reminder_window(days_left) else {
+                continue;
+            };
+            if deadline.sent(window) {
+                continue;
+            }
+
+            let recipients: Vec<Uuid> = match deadline.assigned_to {
+                Some(user_id) => vec![user_id],
+                None => {
+                    sqlx::query_scalar(
+                        "SELECT id FROM users WHERE tenant_id = $1 AND status = 'active' \
+                         AND role IN ('manager', 'admin', 'partner')",
+                    )
+                    .bind(tenant_id)
+                    .fetch_all(&mut *tx)
+                    .await?
+                }
+            };
+
+            let title = match days_left {
+                0 => format!(
+                    "{} for {} is due today",
+                    deadline.filing_type, deadline.client_name
+                ),
+                1 => format!(
+                    "{} for {} is due tomorrow",
+                    deadline.filing_type, deadline.client_name
+                ),
+                n => format!(
+                    "{} for {} is due in {} days",
+                    deadline.filing_type, deadline.client_name, n
+                ),
+            };
+            let body = format!("Due date: {}", deadline.due_date.format("%B %d, %Y"));
+
+            for user_id in recipients {
+                notify(
+                    &mut *tx,
+                    &state.ws_broadcast,
+                    NewNotification {
+                        tenant_id,
+                        user_id,
+                        kind: "deadline_reminder",
+                        title: title.clone(),
+                        body: Some(body.clone()),
+                        resource_type: Some("compliance_deadline"),
+                        resource_id: Some(deadline.id),
+                    },
+                )
+                .await?;
+            }
+
+            sqlx::query(
+                "UPDATE compliance_deadlines SET \
+                 reminder_sent_30d = TRUE, \
+                 reminder_sent_14d = reminder_sent_14d OR $3 <= 14, \
+                 reminder_sent_7d = reminder_sent_7d OR $3 <= 7, \
+                 reminder_sent_1d = reminder_sent_1d OR $3 <= 1, \
+                 updated_at = NOW() \
+                 WHERE id = $1 AND tenant_id = $2",
+            )
+            .bind(deadline.id)
+            .bind(tenant_id)
+            .bind(window)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reminder_window_picks_tightest() {
+        assert_eq!(reminder_window(0), Some(1));
+        assert_eq!(reminder_window(1), Some(1));
+        assert_eq!(reminder_window(5), Some(7));
+        assert_eq!(reminder_window(14), Some(14));
+        assert_eq!(reminder_window(30), Some(30));
+        assert_eq!(reminder_window(31), None);
+        assert_eq!(reminder_window(-1), None);
+    }
+}
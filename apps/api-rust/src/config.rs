@@ -21,10 +21,36 @@ pub struct Config {
     pub typesense_url: Option<String>,
     #[serde(default)]
     pub typesense_api_key: Option<String>,
+    /// `typesense`, `postgres` or `memory`; defaults to Typesense when
+    /// `typesense_url` is set and Postgres otherwise.
+    #[serde(default)]
+    pub search_backend: Option<String>,
     #[serde(default)]
     pub stripe_secret_key: Option<String>,
     #[serde(default)]
     pub stripe_webhook_secret: Option<String>,
+    #[serde(default = "default_app_base_url")]
+    pub app_base_url: String,
+    /// Public address of this API, for links that bypass the web app such
+    /// as calendar feeds.
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    #[serde(default = "default_mail_transport")]
+    pub mail_transport: String,
+    #[serde(default = "default_mail_from")]
+    pub mail_from: String,
+    #[serde(default = "default_mail_file_dir")]
+    pub mail_file_dir: String,
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+    #[serde(default = "default_smtp_security")]
+    pub smtp_security: String,
 }
 
 fn default_host() -> String {
@@ -51,6 +77,34 @@ fn default_s3_region() -> String {
     "us-east-1".to_string()
 }
 
+fn default_app_base_url() -> String {
+    "http://localhost:3000".to_string()
+}
+
+fn default_api_base_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_mail_transport() -> String {
+    "smtp".to_string()
+}
+
+fn default_mail_from() -> String {
+    "no-reply@localhost".to_string()
+}
+
+fn default_mail_file_dir() -> String {
+    "./tmp/mail".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_security() -> String {
+    "starttls".to_string()
+}
+
 impl Config {
     pub fn from_env() -> Result<Self, envy::Error> {
         envy::from_env::<Config>()
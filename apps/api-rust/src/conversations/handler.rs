@@ -8,11 +8,12 @@ use uuid::Uuid;
 use crate::auth::jwt::Claims;
 use crate::conversations::model::*;
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::ws::WsEventPayload;
 use crate::AppState;
 
 pub async fn list_conversations(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListConversationsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -34,7 +35,7 @@ pub async fn list_conversations(
     .bind(claims.sub)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -44,7 +45,7 @@ pub async fn list_conversations(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -59,7 +60,7 @@ pub async fn list_conversations(
 }
 
 pub async fn create_conversation(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateConversationRequest>,
 ) -> AppResult<(StatusCode, Json<Conversation>)> {
@@ -73,7 +74,7 @@ pub async fn create_conversation(
     .bind(&payload.type_)
     .bind(payload.title.as_deref())
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Insert creator as 'owner'
@@ -84,7 +85,7 @@ pub async fn create_conversation(
     .bind(claims.tid)
     .bind(conversation.id)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Insert other participants as 'member'
@@ -97,7 +98,7 @@ pub async fn create_conversation(
             .bind(claims.tid)
             .bind(conversation.id)
             .bind(user_id)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
@@ -106,7 +107,7 @@ pub async fn create_conversation(
 }
 
 pub async fn get_conversation(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -118,7 +119,7 @@ pub async fn get_conversation(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Conversation not found".to_string()))?;
 
@@ -130,7 +131,7 @@ pub async fn get_conversation(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -140,7 +141,7 @@ pub async fn get_conversation(
 }
 
 pub async fn list_conversation_messages(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Query(params): Query<ListMessagesQuery>,
@@ -168,7 +169,7 @@ pub async fn list_conversation_messages(
     .bind(offset)
     .bind(search)
     .bind(&search_pattern)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -180,7 +181,7 @@ pub async fn list_conversation_messages(
     .bind(id)
     .bind(search)
     .bind(&search_pattern)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -196,6 +197,7 @@ pub async fn list_conversation_messages(
 
 pub async fn send_message(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<SendMessageRequest>,
@@ -214,7 +216,7 @@ pub async fn send_message(
     .bind(&payload.content)
     .bind(message_type)
     .bind(payload.parent_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Update conversation last_message_at and last_message_preview
@@ -231,7 +233,7 @@ pub async fn send_message(
     .bind(&preview)
     .bind(id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Look up sender name for broadcast
@@ -239,7 +241,7 @@ pub async fn send_message(
         "SELECT COALESCE(first_name || ' ' || last_name, 'Unknown') FROM users WHERE id = $1",
     )
     .bind(claims.sub)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .unwrap_or_else(|| "Unknown".to_string());
 
@@ -258,7 +260,7 @@ pub async fn send_message(
 }
 
 pub async fn mark_conversation_read(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -269,7 +271,7 @@ pub async fn mark_conversation_read(
     .bind(claims.sub)
     .bind(id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::NO_CONTENT)
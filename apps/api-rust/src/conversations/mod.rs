@@ -1,2 +1,26 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/conversations", get(handler::list_conversations))
+        .route("/conversations", post(handler::create_conversation))
+        .route("/conversations/{id}", get(handler::get_conversation))
+        .route(
+            "/conversations/{id}/messages",
+            get(handler::list_conversation_messages),
+        )
+        .route("/conversations/{id}/messages", post(handler::send_message))
+        .route(
+            "/conversations/{id}/read",
+            post(handler::mark_conversation_read),
+        )
+}
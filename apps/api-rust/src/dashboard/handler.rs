@@ -1,12 +1,9 @@
-use axum::{
-    extract::{Extension, State},
-    Json,
-};
+use axum::{extract::Extension, Json};
 use serde::Serialize;
 
 use crate::auth::jwt::Claims;
 use crate::error::AppResult;
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize)]
 pub struct DashboardStats {
@@ -19,49 +16,49 @@ pub struct DashboardStats {
 }
 
 pub async fn get_dashboard_stats(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<DashboardStats>> {
     let (active_clients,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM clients WHERE tenant_id = $1 AND status = 'active' AND deleted_at IS NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (total_documents,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM documents WHERE tenant_id = $1 AND deleted_at IS NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (hours_this_week_minutes,): (i64,) = sqlx::query_as(
         "SELECT COALESCE(SUM(duration_minutes), 0)::BIGINT FROM time_entries WHERE tenant_id = $1 AND date >= date_trunc('week', CURRENT_DATE)",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (outstanding_invoices,): (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM invoices WHERE tenant_id = $1 AND status IN ('sent', 'viewed', 'overdue')",
+        "SELECT COUNT(*) FROM invoices WHERE tenant_id = $1 AND status IN ('sent', 'viewed', 'partial', 'overdue')",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (outstanding_amount_cents,): (i64,) = sqlx::query_as(
-        "SELECT COALESCE(SUM(total_cents - amount_paid_cents), 0)::BIGINT FROM invoices WHERE tenant_id = $1 AND status IN ('sent', 'viewed', 'overdue')",
+        "SELECT COALESCE(SUM(total_cents - amount_paid_cents), 0)::BIGINT FROM invoices WHERE tenant_id = $1 AND status IN ('sent', 'viewed', 'partial', 'overdue')",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (revenue_mtd_cents,): (i64,) = sqlx::query_as(
-        "SELECT COALESCE(SUM(amount_paid_cents), 0)::BIGINT FROM invoices WHERE tenant_id = $1 AND status = 'paid' AND paid_date >= date_trunc('month', CURRENT_DATE)",
+        "SELECT COALESCE(SUM(amount_paid_cents - credited_cents), 0)::BIGINT FROM invoices WHERE tenant_id = $1 AND status = 'paid' AND paid_date >= date_trunc('month', CURRENT_DATE)",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(DashboardStats {
@@ -107,7 +104,7 @@ pub struct StageCount {
 }
 
 pub async fn get_hiring_stats(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<HiringStats>> {
     // Total open jobs
@@ -115,14 +112,14 @@ pub async fn get_hiring_stats(
         "SELECT COUNT(*) FROM job_posts WHERE tenant_id = $1 AND status IN ('open', 'draft')",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Total applications
     let (total_applications,): (i64,) =
         sqlx::query_as("SELECT COUNT(*) FROM applications WHERE tenant_id = $1")
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     // Applications this week
@@ -131,7 +128,7 @@ pub async fn get_hiring_stats(
          AND created_at >= date_trunc('week', CURRENT_DATE)",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Average time to hire (days from application created_at to hired_at)
@@ -140,7 +137,7 @@ pub async fn get_hiring_stats(
          FROM applications WHERE tenant_id = $1 AND hired_at IS NOT NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Pipeline by stage (conversion rates)
@@ -159,7 +156,7 @@ pub async fn get_hiring_stats(
            ELSE 8 END",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     // Build conversion rates
@@ -184,7 +181,7 @@ pub async fn get_hiring_stats(
          GROUP BY source ORDER BY count DESC LIMIT 10",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     // Pipeline by stage (all statuses)
@@ -194,7 +191,7 @@ pub async fn get_hiring_stats(
          GROUP BY stage ORDER BY count DESC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(HiringStats {
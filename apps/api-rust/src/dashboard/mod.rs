@@ -1 +1,12 @@
 pub mod handler;
+
+use axum::{routing::get, Router};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/dashboard/stats", get(handler::get_dashboard_stats))
+        .route("/dashboard/hiring-stats", get(handler::get_hiring_stats))
+}
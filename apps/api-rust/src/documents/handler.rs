@@ -10,10 +10,12 @@ use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::documents::model::*;
 use crate::error::{AppError, AppResult};
+use crate::extraction::{self, ExtractionJob, Target};
+use crate::middleware::tenant::TenantTx;
 use crate::AppState;
 
 pub async fn list_documents(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListDocumentsQuery>,
 ) -> AppResult<Json<PaginatedResponse<Document>>> {
@@ -45,12 +47,12 @@ pub async fn list_documents(
         )
         .bind(claims.tid)
         .bind(pattern)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as("SELECT COUNT(*) FROM documents WHERE tenant_id = $1 AND deleted_at IS NULL")
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
     };
 
@@ -62,7 +64,7 @@ pub async fn list_documents(
         .bind(pattern)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -71,7 +73,7 @@ pub async fn list_documents(
         .bind(claims.tid)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -89,7 +91,7 @@ pub async fn list_documents(
 }
 
 pub async fn get_document(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(doc_id): Path<Uuid>,
 ) -> AppResult<Json<Document>> {
@@ -98,7 +100,7 @@ pub async fn get_document(
     )
     .bind(doc_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Document not found".to_string()))?;
 
@@ -107,6 +109,7 @@ pub async fn get_document(
 
 pub async fn create_document(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateDocumentRequest>,
 ) -> AppResult<(StatusCode, Json<UploadResponse>)> {
@@ -164,39 +167,11 @@ pub async fn create_document(
     .bind(&s3_key)
     .bind(payload.category.as_deref())
     .bind(payload.tax_year)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
-    // Generate presigned upload URL via AWS SDK
-    let s3_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-        .endpoint_url(&state.config.s3_endpoint)
-        .region(aws_config::Region::new(state.config.s3_region.clone()))
-        .load()
-        .await;
-    let s3_client = aws_sdk_s3::Client::new(&s3_config);
-
-    let upload_url = match s3_client
-        .put_object()
-        .bucket(&state.config.s3_bucket)
-        .key(&s3_key)
-        .content_type(&payload.mime_type)
-        .presigned(
-            aws_sdk_s3::presigning::PresigningConfig::expires_in(std::time::Duration::from_secs(
-                3600,
-            ))
-            .expect("valid presigning config"),
-        )
-        .await
-    {
-        Ok(presigned) => presigned.uri().to_string(),
-        Err(_) => {
-            // Fallback for local dev if presigning fails
-            format!(
-                "{}/{}/{}",
-                state.config.s3_endpoint, state.config.s3_bucket, &s3_key
-            )
-        }
-    };
+    let upload_url =
+        crate::storage::presigned_put_url(&state.config, &s3_key, &payload.mime_type).await;
 
     Ok((
         StatusCode::CREATED,
@@ -208,7 +183,7 @@ pub async fn create_document(
 }
 
 pub async fn update_document(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(doc_id): Path<Uuid>,
     Json(payload): Json<UpdateDocumentRequest>,
@@ -229,7 +204,7 @@ pub async fn update_document(
     .bind(payload.category.as_deref())
     .bind(payload.tax_year)
     .bind(payload.verification_status.as_deref())
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Document not found".to_string()))?;
 
@@ -237,7 +212,7 @@ pub async fn update_document(
 }
 
 pub async fn delete_document(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(doc_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -246,7 +221,7 @@ pub async fn delete_document(
     )
     .bind(doc_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -259,7 +234,7 @@ pub async fn delete_document(
 // ── Bulk Operations ──────────────────────────────────────────────────
 
 pub async fn bulk_delete_documents(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkDocumentIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -280,7 +255,7 @@ pub async fn bulk_delete_documents(
     )
     .bind(claims.tid)
     .bind(&payload.ids)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -292,7 +267,7 @@ pub async fn bulk_delete_documents(
 // ── Soft Delete: Restore & Trash ─────────────────────────────────────
 
 pub async fn restore_document(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(doc_id): Path<Uuid>,
 ) -> AppResult<Json<Document>> {
@@ -305,15 +280,51 @@ pub async fn restore_document(
     )
     .bind(doc_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Document not found in trash".to_string()))?;
 
     Ok(Json(doc))
 }
 
+/// Mark a document's upload as finished and queue it for extraction into
+/// `ai_extracted_data`.
+pub async fn complete_document(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(doc_id): Path<Uuid>,
+) -> AppResult<(StatusCode, Json<Document>)> {
+    let doc: Document = sqlx::query_as(
+        "UPDATE documents SET ai_extracted_data = '{\"status\": \"pending\"}', \
+         ai_confidence = NULL, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL \
+         RETURNING id, tenant_id, client_id, uploaded_by, filename, mime_type, size_bytes, s3_key, \
+         category, ai_confidence::FLOAT8 as ai_confidence, ai_extracted_data, verification_status, \
+         tax_year, version, created_at, updated_at",
+    )
+    .bind(doc_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Document not found".to_string()))?;
+
+    extraction::enqueue(
+        &mut *tx,
+        claims.tid,
+        &ExtractionJob {
+            target: Target::Document,
+            id: doc.id,
+            requested_by: claims.sub,
+            upsert_skills: false,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(doc)))
+}
+
 pub async fn list_documents_trash(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListDocumentsQuery>,
 ) -> AppResult<Json<PaginatedResponse<Document>>> {
@@ -325,7 +336,7 @@ pub async fn list_documents_trash(
         "SELECT COUNT(*) FROM documents WHERE tenant_id = $1 AND deleted_at IS NOT NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let documents: Vec<Document> = sqlx::query_as(
@@ -338,7 +349,7 @@ pub async fn list_documents_trash(
     .bind(claims.tid)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -1,2 +1,26 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, patch, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/documents", get(handler::list_documents))
+        .route("/documents", post(handler::create_document))
+        .route("/documents/{id}", get(handler::get_document))
+        .route("/documents/{id}", patch(handler::update_document))
+        .route("/documents/{id}", delete(handler::delete_document))
+        .route(
+            "/documents/bulk-delete",
+            post(handler::bulk_delete_documents),
+        )
+        .route("/documents/trash", get(handler::list_documents_trash))
+        .route("/documents/{id}/restore", post(handler::restore_document))
+        .route("/documents/{id}/complete", post(handler::complete_document))
+}
@@ -9,6 +9,8 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::mailer::{outbox, template, OutgoingEmail};
+use crate::middleware::tenant::TenantTx;
 use crate::AppState;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -56,11 +58,15 @@ pub struct UpdateTemplatePayload {
 #[derive(Debug, Deserialize)]
 pub struct SendTemplatePayload {
     pub recipient_email: String,
+    #[serde(default)]
     pub variables: serde_json::Value,
+    pub candidate_id: Option<Uuid>,
+    pub job_id: Option<Uuid>,
+    pub invoice_id: Option<Uuid>,
 }
 
 pub async fn list_templates(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Query(params): Query<ListParams>,
 ) -> AppResult<Json<Vec<EmailTemplate>>> {
@@ -74,7 +80,7 @@ pub async fn list_templates(
     .bind(claims.tid)
     .bind(params.category)
     .bind(params.is_active)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -82,7 +88,7 @@ pub async fn list_templates(
 }
 
 pub async fn create_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<CreateTemplatePayload>,
 ) -> AppResult<(StatusCode, Json<EmailTemplate>)> {
@@ -98,7 +104,7 @@ pub async fn create_template(
     .bind(&payload.body)
     .bind(payload.variables.as_deref().unwrap_or(&[]))
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -106,7 +112,7 @@ pub async fn create_template(
 }
 
 pub async fn get_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<EmailTemplate>> {
@@ -115,7 +121,7 @@ pub async fn get_template(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -124,7 +130,7 @@ pub async fn get_template(
 }
 
 pub async fn update_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateTemplatePayload>,
@@ -149,7 +155,7 @@ pub async fn update_template(
     .bind(payload.body)
     .bind(payload.variables)
     .bind(payload.is_active)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -158,7 +164,7 @@ pub async fn update_template(
 }
 
 pub async fn delete_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -167,7 +173,7 @@ pub async fn delete_template(
     )
     .bind(id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -176,21 +182,80 @@ pub async fn delete_template(
 
 pub async fn send_template(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
-    Json(_payload): Json<SendTemplatePayload>,
+    Json(payload): Json<SendTemplatePayload>,
 ) -> AppResult<Json<serde_json::Value>> {
+    if !payload.recipient_email.contains('@') {
+        return Err(AppError::Validation(
+            "recipient_email must be a valid email address".into(),
+        ));
+    }
+
+    let template = sqlx::query_as::<_, EmailTemplate>(
+        "SELECT * FROM email_templates WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or(AppError::NotFound("Resource not found".into()))?;
+
+    let vars = template::build_context(
+        &mut tx,
+        claims.tid,
+        &state.config.app_base_url,
+        template::ContextRefs {
+            candidate_id: payload.candidate_id,
+            job_id: payload.job_id,
+            invoice_id: payload.invoice_id,
+        },
+        &payload.variables,
+    )
+    .await?;
+
+    let subject = template::render(&template.subject, &vars);
+    let body = template::render(&template.body, &vars);
+    let mut missing = subject.missing;
+    for name in body.missing {
+        if !missing.contains(&name) {
+            missing.push(name);
+        }
+    }
+
+    let outbox_id = outbox::enqueue(
+        &mut *tx,
+        claims.tid,
+        &OutgoingEmail {
+            to: payload.recipient_email.trim().to_string(),
+            subject: subject.text,
+            text_body: body.text,
+            html_body: None,
+        },
+        outbox::EnqueueOptions {
+            category: Some(&template.category),
+            template_id: Some(template.id),
+            created_by: Some(claims.sub),
+        },
+    )
+    .await?;
+
     // Increment usage count
     sqlx::query(
         "UPDATE email_templates SET usage_count = usage_count + 1 WHERE id = $1 AND tenant_id = $2",
     )
     .bind(id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
-    Ok(Json(
-        serde_json::json!({ "status": "queued", "message": "Email queued for delivery" }),
-    ))
+    Ok(Json(serde_json::json!({
+        "status": "queued",
+        "message": "Email queued for delivery",
+        "outbox_id": outbox_id,
+        "missing_variables": missing,
+    })))
 }
@@ -1 +1,19 @@
 pub mod handler;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/email-templates", get(handler::list_templates))
+        .route("/email-templates", post(handler::create_template))
+        .route("/email-templates/{id}", get(handler::get_template))
+        .route("/email-templates/{id}", put(handler::update_template))
+        .route("/email-templates/{id}", delete(handler::delete_template))
+        .route("/email-templates/{id}/send", post(handler::send_template))
+}
@@ -3,14 +3,42 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use serde::Serialize;
 use serde_json::json;
 
+/// A problem with one field of a request body, located by its path
+/// (e.g. `actions[1].to_stage`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub path: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[allow(dead_code)]
 pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// Field-level validation failures, returned together.
+    #[error("Validation error: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidFields(Vec<FieldError>),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
@@ -26,6 +54,22 @@ pub enum AppError {
     #[error("Rate limited")]
     RateLimited,
 
+    /// A metered plan limit would be exceeded.
+    #[error("Plan limit reached for {meter}")]
+    PlanLimit {
+        meter: String,
+        limit: i64,
+        current: i64,
+    },
+
+    /// The tenant's plan does not include the feature.
+    #[error("Feature not in plan: {0}")]
+    FeatureNotEntitled(String),
+
+    /// The tenant's subscription is cancelled or suspended.
+    #[error("Subscription inactive: {0}")]
+    SubscriptionInactive(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
@@ -38,8 +82,21 @@ pub enum AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let mut details = None;
         let (status, code, message) = match &self {
             AppError::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg.clone()),
+            AppError::InvalidFields(errors) => {
+                details = Some(json!({ "errors": errors }));
+                (
+                    StatusCode::BAD_REQUEST,
+                    "VALIDATION_ERROR",
+                    errors
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                )
+            }
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "NOT_FOUND", msg.clone()),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg.clone()),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg.clone()),
@@ -49,6 +106,34 @@ impl IntoResponse for AppError {
                 "RATE_LIMITED",
                 "Too many requests".to_string(),
             ),
+            AppError::PlanLimit {
+                meter,
+                limit,
+                current,
+            } => {
+                details = Some(json!({ "meter": meter, "limit": limit, "current": current }));
+                (
+                    StatusCode::PAYMENT_REQUIRED,
+                    "PLAN_LIMIT_REACHED",
+                    format!("Your plan allows {} {}", limit, meter.replace('_', " ")),
+                )
+            }
+            AppError::FeatureNotEntitled(feature) => {
+                details = Some(json!({ "feature": feature }));
+                (
+                    StatusCode::FORBIDDEN,
+                    "FEATURE_NOT_ENTITLED",
+                    "Your plan does not include this feature".to_string(),
+                )
+            }
+            AppError::SubscriptionInactive(status) => {
+                details = Some(json!({ "subscription_status": status }));
+                (
+                    StatusCode::PAYMENT_REQUIRED,
+                    "SUBSCRIPTION_INACTIVE",
+                    "Your subscription is not active".to_string(),
+                )
+            }
             AppError::Internal(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (
@@ -76,13 +161,16 @@ impl IntoResponse for AppError {
         };
 
         let request_id = uuid::Uuid::new_v4().to_string();
-        let body = json!({
+        let mut body = json!({
             "error": {
                 "code": code,
                 "message": message,
                 "request_id": request_id,
             }
         });
+        if let Some(details) = details {
+            body["error"]["details"] = details;
+        }
 
         (status, Json(body)).into_response()
     }
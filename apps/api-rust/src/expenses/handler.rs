@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -10,10 +10,10 @@ use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
 use crate::expenses::model::*;
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 pub async fn list_expenses(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListExpensesQuery>,
 ) -> AppResult<Json<PaginatedResponse<Expense>>> {
@@ -55,7 +55,7 @@ pub async fn list_expenses(
     }
 
     let count_sql = format!("SELECT COUNT(*) FROM expenses {}", where_clause);
-    let list_sql = format!("SELECT id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, created_at, updated_at FROM expenses {} ORDER BY {} {} LIMIT ${} OFFSET ${}", where_clause, sort_col, sort_dir, param_idx, param_idx + 1);
+    let list_sql = format!("SELECT id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, invoice_id, created_at, updated_at FROM expenses {} ORDER BY {} {} LIMIT ${} OFFSET ${}", where_clause, sort_col, sort_dir, param_idx, param_idx + 1);
 
     let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql).bind(claims.tid);
     let mut list_query = sqlx::query_as::<_, Expense>(&list_sql).bind(claims.tid);
@@ -69,12 +69,12 @@ pub async fn list_expenses(
         list_query = list_query.bind(status.clone());
     }
 
-    let (total,): (i64,) = count_query.fetch_one(&state.db).await?;
+    let (total,): (i64,) = count_query.fetch_one(&mut *tx).await?;
 
     let expenses: Vec<Expense> = list_query
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -91,7 +91,7 @@ pub async fn list_expenses(
 }
 
 pub async fn create_expense(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateExpenseRequest>,
 ) -> AppResult<(StatusCode, Json<Expense>)> {
@@ -102,7 +102,7 @@ pub async fn create_expense(
     let is_reimbursable = payload.is_reimbursable.unwrap_or(false);
 
     let expense: Expense = sqlx::query_as(
-        "INSERT INTO expenses (tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, created_at, updated_at",
+        "INSERT INTO expenses (tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, invoice_id, created_at, updated_at",
     )
     .bind(claims.tid)
     .bind(payload.client_id)
@@ -113,23 +113,23 @@ pub async fn create_expense(
     .bind(payload.date)
     .bind(payload.receipt_document_id)
     .bind(is_reimbursable)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(expense)))
 }
 
 pub async fn get_expense(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(expense_id): Path<Uuid>,
 ) -> AppResult<Json<Expense>> {
     let expense: Expense = sqlx::query_as(
-        "SELECT id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, created_at, updated_at FROM expenses WHERE id = $1 AND tenant_id = $2",
+        "SELECT id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, invoice_id, created_at, updated_at FROM expenses WHERE id = $1 AND tenant_id = $2",
     )
     .bind(expense_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Expense not found".to_string()))?;
 
@@ -137,7 +137,7 @@ pub async fn get_expense(
 }
 
 pub async fn update_expense(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(expense_id): Path<Uuid>,
     Json(payload): Json<UpdateExpenseRequest>,
@@ -152,7 +152,7 @@ pub async fn update_expense(
          status = COALESCE($8, status), \
          updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 \
-         RETURNING id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, created_at, updated_at",
+         RETURNING id, tenant_id, client_id, user_id, category, description, amount_cents, date, receipt_document_id, is_reimbursable, status, invoice_id, created_at, updated_at",
     )
     .bind(expense_id)
     .bind(claims.tid)
@@ -162,7 +162,7 @@ pub async fn update_expense(
     .bind(payload.date)
     .bind(payload.is_reimbursable)
     .bind(payload.status.as_deref())
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Expense not found".to_string()))?;
 
@@ -170,14 +170,14 @@ pub async fn update_expense(
 }
 
 pub async fn delete_expense(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(expense_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     let result = sqlx::query("DELETE FROM expenses WHERE id = $1 AND tenant_id = $2")
         .bind(expense_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -1,2 +1,19 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/expenses", get(handler::list_expenses))
+        .route("/expenses", post(handler::create_expense))
+        .route("/expenses/{id}", get(handler::get_expense))
+        .route("/expenses/{id}", delete(handler::delete_expense))
+        .route("/expenses/{id}", put(handler::update_expense))
+}
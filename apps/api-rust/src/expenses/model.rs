@@ -16,6 +16,8 @@ pub struct Expense {
     pub receipt_document_id: Option<Uuid>,
     pub is_reimbursable: bool,
     pub status: String,
+    /// The invoice this expense was billed on, if any.
+    pub invoice_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -0,0 +1,58 @@
+//! Structured data extracted from uploaded documents.
+//!
+//! Once a client finishes uploading a file, the upload is marked complete and
+//! a `documents.extract` job is queued. The [`worker`] downloads the file,
+//! pulls its [`text`] out locally, parses it as a [`resume`] and stores the
+//! result on the row: `candidate_documents.parsed_data`, or
+//! `documents.ai_extracted_data` and `ai_confidence`.
+
+pub mod resume;
+pub mod text;
+pub mod worker;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::scheduler::{self, EnqueueOptions};
+
+pub const JOB_TYPE: &str = "documents.extract";
+
+/// Which table the document lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    CandidateDocument,
+    Document,
+}
+
+/// Payload of a `documents.extract` job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractionJob {
+    pub target: Target,
+    pub id: Uuid,
+    /// Notified over the WebSocket when processing finishes.
+    pub requested_by: Uuid,
+    /// Add skills found in a candidate's document to their profile.
+    #[serde(default)]
+    pub upsert_skills: bool,
+}
+
+/// Queue extraction of an uploaded document.
+pub async fn enqueue(
+    db: impl sqlx::PgExecutor<'_>,
+    tenant_id: Uuid,
+    job: &ExtractionJob,
+) -> Result<(), sqlx::Error> {
+    scheduler::enqueue(
+        db,
+        JOB_TYPE,
+        serde_json::to_value(job).expect("extraction job serializes"),
+        EnqueueOptions {
+            tenant_id: Some(tenant_id),
+            max_attempts: Some(5),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,695 @@
+//! Rule-based resume parsing.
+//!
+//! Resumes are split into sections by their headings ("Experience",
+//! "Education", ...). Contact details come from patterns anywhere in the
+//! text; work history from lines carrying a date range; education from
+//! degree and institution keywords; skills from the skills section plus a
+//! list of well-known skills found anywhere.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParsedResume {
+    pub contact: Contact,
+    pub summary: Option<String>,
+    pub work_history: Vec<WorkEntry>,
+    pub education: Vec<EducationEntry>,
+    pub skills: Vec<String>,
+    /// Share of the usual resume fields that were found, from 0 to 1.
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub location: Option<String>,
+    pub linkedin: Option<String>,
+    pub github: Option<String>,
+    pub website: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorkEntry {
+    pub title: Option<String>,
+    pub company: Option<String>,
+    /// `YYYY-MM` or `YYYY`.
+    pub start: Option<String>,
+    /// `None` for current roles.
+    pub end: Option<String>,
+    pub current: bool,
+    pub description: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EducationEntry {
+    pub institution: Option<String>,
+    pub degree: Option<String>,
+    pub field: Option<String>,
+    pub graduation_year: Option<i32>,
+}
+
+/// Skills recognised anywhere in a resume, with their `candidate_skills`
+/// category.
+const KNOWN_SKILLS: &[(&str, &str)] = &[
+    ("Rust", "technical"),
+    ("Python", "technical"),
+    ("Java", "technical"),
+    ("JavaScript", "technical"),
+    ("TypeScript", "technical"),
+    ("Go", "technical"),
+    ("C++", "technical"),
+    ("C#", "technical"),
+    ("Ruby", "technical"),
+    ("SQL", "technical"),
+    ("PostgreSQL", "technical"),
+    ("MySQL", "technical"),
+    ("React", "technical"),
+    ("Node.js", "technical"),
+    ("AWS", "technical"),
+    ("Azure", "technical"),
+    ("GCP", "technical"),
+    ("Docker", "technical"),
+    ("Kubernetes", "technical"),
+    ("Terraform", "technical"),
+    ("Git", "tool"),
+    ("Excel", "tool"),
+    ("QuickBooks", "tool"),
+    ("Xero", "tool"),
+    ("NetSuite", "tool"),
+    ("SAP", "tool"),
+    ("Tableau", "tool"),
+    ("Power BI", "tool"),
+    ("Salesforce", "tool"),
+    ("GAAP", "domain"),
+    ("IFRS", "domain"),
+    ("Audit", "domain"),
+    ("Tax Preparation", "domain"),
+    ("Financial Reporting", "domain"),
+    ("Bookkeeping", "domain"),
+    ("Payroll", "domain"),
+    ("Accounts Payable", "domain"),
+    ("Accounts Receivable", "domain"),
+    ("Reconciliation", "domain"),
+    ("Forecasting", "domain"),
+    ("SOX", "domain"),
+    ("Leadership", "soft"),
+    ("Communication", "soft"),
+    ("Project Management", "soft"),
+    ("Spanish", "language"),
+    ("French", "language"),
+    ("German", "language"),
+    ("Mandarin", "language"),
+];
+
+/// Category of a skill named in [`KNOWN_SKILLS`].
+pub fn skill_category(skill: &str) -> Option<&'static str> {
+    KNOWN_SKILLS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(skill))
+        .map(|(_, category)| *category)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Header,
+    Summary,
+    Experience,
+    Education,
+    Skills,
+    Other,
+}
+
+struct Patterns {
+    email: Regex,
+    phone: Regex,
+    linkedin: Regex,
+    github: Regex,
+    url: Regex,
+    location: Regex,
+    date_range: Regex,
+    year: Regex,
+    degree_long: Regex,
+    degree_short: Regex,
+    field: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let month = r"(?:jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)";
+        let date = format!(r"(?:{}\.?\s+\d{{4}}|\d{{1,2}}/\d{{4}}|(?:19|20)\d{{2}})", month);
+        Patterns {
+            email: Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap(),
+            phone: Regex::new(
+                r"(?:\+\d{1,3}[\s.-]?)?\(?\d{3}\)?[\s.-]?\d{3}[\s.-]?\d{4}\b",
+            )
+            .unwrap(),
+            linkedin: Regex::new(r"(?i)(?:https?://)?(?:www\.)?linkedin\.com/in/[A-Za-z0-9_-]+")
+                .unwrap(),
+            github: Regex::new(r"(?i)(?:https?://)?(?:www\.)?github\.com/[A-Za-z0-9_-]+").unwrap(),
+            url: Regex::new(r"(?i)https?://[^\s|,]+").unwrap(),
+            location: Regex::new(r"^([A-Z][A-Za-z.' -]+),\s*([A-Z]{2})(?:\s+\d{5})?$").unwrap(),
+            date_range: Regex::new(&format!(
+                r"(?i)({date})\s*(?:-|–|—|to|until)\s*({date}|present|current|now|today)\b",
+                date = date
+            ))
+            .unwrap(),
+            year: Regex::new(r"\b(?:19|20)\d{2}\b").unwrap(),
+            degree_long: Regex::new(&format!(
+                r"(?i)\b(?:doctor(?:ate)? {subject}|(?:master|bachelor|associate)(?:'s)?(?: {subject})?|high school diploma)",
+                subject = r"of [a-z]+(?: (?:administration|arts|science|studies|technology|engineering))?"
+            ))
+            .unwrap(),
+            degree_short: Regex::new(
+                r"\b(?:Ph\.?\s?D|MBA|M\.B\.A|B\.S|B\.A|M\.S|M\.A|BSc|MSc|BS|BA|MS|MA|CPA)\b\.?",
+            )
+            .unwrap(),
+            field: Regex::new(r"^\s*(?:in|of)\s+([A-Za-z&' ]+)").unwrap(),
+        }
+    })
+}
+
+/// Parse a resume's text.
+pub fn parse(text: &str) -> ParsedResume {
+    let sections = split_sections(text);
+    let lines_of = |wanted: Section| -> Vec<&str> {
+        sections
+            .iter()
+            .filter(|(section, _)| *section == wanted)
+            .flat_map(|(_, lines)| lines.iter().copied())
+            .collect()
+    };
+
+    let header = lines_of(Section::Header);
+    let mut resume = ParsedResume {
+        contact: contact(text, &header),
+        summary: Some(lines_of(Section::Summary).join(" ")).filter(|s| !s.is_empty()),
+        work_history: work_history(&lines_of(Section::Experience)),
+        education: education(&lines_of(Section::Education)),
+        skills: skills(text, &lines_of(Section::Skills)),
+        confidence: 0.0,
+    };
+    resume.confidence = confidence(&resume);
+    resume
+}
+
+/// Lines grouped by the section heading above them. Lines before the first
+/// heading form the header.
+fn split_sections(text: &str) -> Vec<(Section, Vec<&str>)> {
+    let mut sections = vec![(Section::Header, Vec::new())];
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let current = sections.last_mut().unwrap();
+        match heading(line) {
+            // "Languages: Python, SQL" under Skills is a list, not a heading.
+            Some((Section::Other, Some(_))) if current.0 == Section::Skills => current.1.push(line),
+            Some((section, inline)) => {
+                sections.push((section, inline.into_iter().collect()));
+            }
+            None => current.1.push(line),
+        }
+    }
+    sections
+}
+
+/// The section a heading line starts, and any content after a colon, as in
+/// "Skills: Rust, SQL".
+fn heading(line: &str) -> Option<(Section, Option<&str>)> {
+    let (title, inline) = match line.split_once(':') {
+        Some((title, rest)) => (title, Some(rest.trim()).filter(|r| !r.is_empty())),
+        None => (line, None),
+    };
+    if title.len() > 40 {
+        return None;
+    }
+    let key: String = title
+        .to_lowercase()
+        .replace('&', " and ")
+        .chars()
+        .filter(|c| c.is_alphabetic() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let section = match key.as_str() {
+        "summary"
+        | "profile"
+        | "professional summary"
+        | "objective"
+        | "about"
+        | "about me"
+        | "career objective"
+        | "professional profile" => Section::Summary,
+        "experience"
+        | "work experience"
+        | "professional experience"
+        | "employment"
+        | "employment history"
+        | "work history"
+        | "career history"
+        | "relevant experience" => Section::Experience,
+        "education"
+        | "academic background"
+        | "education and training"
+        | "qualifications"
+        | "education and certifications" => Section::Education,
+        "skills" | "technical skills" | "core competencies" | "competencies" | "technologies"
+        | "key skills" | "skills and tools" | "tools" | "areas of expertise" => Section::Skills,
+        "certifications"
+        | "projects"
+        | "awards"
+        | "publications"
+        | "languages"
+        | "interests"
+        | "references"
+        | "volunteer"
+        | "volunteer experience"
+        | "activities"
+        | "licenses"
+        | "licenses and certifications" => Section::Other,
+        _ => return None,
+    };
+    Some((section, inline))
+}
+
+fn contact(text: &str, header: &[&str]) -> Contact {
+    let p = patterns();
+    let header_text = header.join("\n");
+    let found = |re: &Regex, haystack: &str| re.find(haystack).map(|m| m.as_str().to_string());
+
+    let segments: Vec<&str> = header
+        .iter()
+        .flat_map(|line| line.split(['|', '•', '·']))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    Contact {
+        name: segments
+            .iter()
+            .take(3)
+            .find(|s| is_name(s))
+            .map(|s| s.to_string()),
+        email: found(&p.email, text),
+        phone: found(&p.phone, &header_text).or_else(|| found(&p.phone, text)),
+        location: segments
+            .iter()
+            .find(|s| p.location.is_match(s))
+            .map(|s| s.to_string()),
+        linkedin: found(&p.linkedin, text),
+        github: found(&p.github, text),
+        website: p
+            .url
+            .find_iter(&header_text)
+            .map(|m| m.as_str())
+            .find(|url| !url.contains("linkedin.com") && !url.contains("github.com"))
+            .map(str::to_string),
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    (2..=4).contains(&words.len())
+        && words.iter().all(|w| {
+            w.chars().next().is_some_and(char::is_uppercase)
+                && w.chars()
+                    .all(|c| c.is_alphabetic() || matches!(c, '.' | '\'' | '-'))
+        })
+        && heading(s).is_none()
+}
+
+fn work_history(lines: &[&str]) -> Vec<WorkEntry> {
+    let p = patterns();
+    let dated: Vec<usize> = (0..lines.len())
+        .filter(|&i| p.date_range.is_match(lines[i]))
+        .collect();
+
+    // Where each entry's heading lines start: the date line itself, or the
+    // line above it when the role is on a line of its own.
+    let mut starts = Vec::with_capacity(dated.len());
+    let mut entries = Vec::with_capacity(dated.len());
+    for (n, &i) in dated.iter().enumerate() {
+        let line = lines[i];
+        let range = p.date_range.captures(line).unwrap();
+        let rest = clean(&format!(
+            "{} {}",
+            &line[..range.get(0).unwrap().start()],
+            &line[range.get(0).unwrap().end()..]
+        ));
+
+        let floor = if n == 0 { 0 } else { dated[n - 1] + 1 };
+        let above = (i > floor)
+            .then(|| lines[i - 1])
+            .filter(|l| !is_bullet(l) && !l.ends_with('.'));
+        let (title, company, start) = match (rest.is_empty(), above) {
+            (true, Some(above)) => {
+                let (title, company) = split_role(above);
+                (title, company, i - 1)
+            }
+            (false, Some(above)) if !has_separator(&rest) => {
+                (Some(clean(above)), Some(rest), i - 1)
+            }
+            _ => {
+                let (title, company) = split_role(&rest);
+                (title, company, i)
+            }
+        };
+        starts.push(start);
+
+        let end = range.get(2).unwrap().as_str();
+        let current = ["present", "current", "now", "today"].contains(&end.to_lowercase().as_str());
+        entries.push(WorkEntry {
+            title,
+            company,
+            start: normalize_date(range.get(1).unwrap().as_str()),
+            end: if current { None } else { normalize_date(end) },
+            current,
+            description: Vec::new(),
+        });
+    }
+
+    for (n, entry) in entries.iter_mut().enumerate() {
+        let until = starts.get(n + 1).copied().unwrap_or(lines.len());
+        entry.description = lines[dated[n] + 1..until.max(dated[n] + 1)]
+            .iter()
+            .map(|l| strip_bullet(l).to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+    }
+    entries
+}
+
+const ROLE_SEPARATORS: [&str; 7] = [" at ", " @ ", " | ", " — ", " – ", " - ", ", "];
+
+fn has_separator(s: &str) -> bool {
+    ROLE_SEPARATORS.iter().any(|sep| s.contains(sep))
+}
+
+/// "Senior Accountant at Acme" → title and company.
+fn split_role(s: &str) -> (Option<String>, Option<String>) {
+    let s = clean(s);
+    for sep in ROLE_SEPARATORS {
+        if let Some((title, company)) = s.split_once(sep) {
+            let (title, company) = (clean(title), clean(company));
+            return (
+                Some(title).filter(|t| !t.is_empty()),
+                Some(company).filter(|c| !c.is_empty()),
+            );
+        }
+    }
+    (Some(s).filter(|s| !s.is_empty()), None)
+}
+
+fn education(lines: &[&str]) -> Vec<EducationEntry> {
+    let p = patterns();
+    let mut entries: Vec<EducationEntry> = Vec::new();
+    for line in lines {
+        let institution = line
+            .split([',', '|', '–', '—'])
+            .map(str::trim)
+            .find(|part| is_institution(part))
+            .map(clean);
+        let degree = p.degree_long.find(line).or_else(|| {
+            p.degree_short
+                .find_iter(line)
+                // "Boston, MA" is a place, not a degree.
+                .find(|m| !line[..m.start()].ends_with(", "))
+        });
+        let field = degree.and_then(|d| {
+            p.field
+                .captures(&line[d.end()..])
+                .map(|c| clean(c.get(1).unwrap().as_str()))
+        });
+        let year = p
+            .year
+            .find_iter(line)
+            .last()
+            .and_then(|m| m.as_str().parse().ok());
+        if institution.is_none() && degree.is_none() && year.is_none() {
+            continue;
+        }
+
+        let starts_new = entries.last().map_or(true, |e| {
+            (institution.is_some() && e.institution.is_some())
+                || (degree.is_some() && e.degree.is_some())
+        });
+        if starts_new {
+            entries.push(EducationEntry::default());
+        }
+        let entry = entries.last_mut().unwrap();
+        entry.institution = entry.institution.take().or(institution);
+        entry.degree = entry
+            .degree
+            .take()
+            .or(degree.map(|d| d.as_str().trim_end_matches('.').to_string()));
+        entry.field = entry.field.take().or(field).filter(|f| !f.is_empty());
+        entry.graduation_year = year.or(entry.graduation_year);
+    }
+    entries
+}
+
+fn is_institution(s: &str) -> bool {
+    let lower = s.to_lowercase();
+    [
+        "university",
+        "college",
+        "institute",
+        "school",
+        "academy",
+        "polytechnic",
+    ]
+    .iter()
+    .any(|k| lower.contains(k))
+        && !lower.contains("high school diploma")
+}
+
+fn skills(text: &str, section: &[&str]) -> Vec<String> {
+    let mut skills: Vec<String> = Vec::new();
+    let mut add = |skill: &str| {
+        if !skills.iter().any(|s| s.eq_ignore_ascii_case(skill)) {
+            skills.push(skill.to_string());
+        }
+    };
+
+    for line in section {
+        let line = strip_bullet(line);
+        // "Languages: Rust, Go" lists skills under a label.
+        let line = match line.split_once(':') {
+            Some((label, rest)) if label.split_whitespace().count() <= 3 => rest,
+            _ => line,
+        };
+        for item in line.split([',', ';', '•', '|', '·', '/']) {
+            let item = clean(item.trim_end_matches('.'));
+            let words = item.split_whitespace().count();
+            if (1..=40).contains(&item.len())
+                && (1..=4).contains(&words)
+                && item.chars().any(char::is_alphabetic)
+            {
+                add(&item);
+            }
+        }
+    }
+
+    let lower = text.to_lowercase();
+    for (skill, _) in KNOWN_SKILLS {
+        if contains_term(&lower, &skill.to_lowercase()) {
+            add(skill);
+        }
+    }
+    skills
+}
+
+/// Whether `term` occurs in `haystack` as a whole word.
+fn contains_term(haystack: &str, term: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    haystack.match_indices(term).any(|(i, _)| {
+        !is_word(haystack[..i].chars().next_back())
+            && !is_word(haystack[i + term.len()..].chars().next())
+    })
+}
+
+fn confidence(resume: &ParsedResume) -> f64 {
+    let c = &resume.contact;
+    let found = [
+        (c.name.is_some(), 0.15),
+        (c.email.is_some(), 0.2),
+        (c.phone.is_some(), 0.1),
+        (!resume.work_history.is_empty(), 0.25),
+        (!resume.education.is_empty(), 0.15),
+        (!resume.skills.is_empty(), 0.15),
+    ];
+    let score: f64 = found.iter().filter(|(hit, _)| *hit).map(|(_, w)| w).sum();
+    (score * 100.0).round() / 100.0
+}
+
+/// `Jan 2020` → `2020-01`, `3/2019` → `2019-03`, `2018` → `2018`.
+fn normalize_date(date: &str) -> Option<String> {
+    let date = date.trim().trim_end_matches('.');
+    if let Some((month, year)) = date.split_once('/') {
+        let month: u32 = month.parse().ok().filter(|m| (1..=12).contains(m))?;
+        return Some(format!("{}-{:02}", year, month));
+    }
+    match date.split_whitespace().collect::<Vec<_>>()[..] {
+        [year] => Some(year.to_string()),
+        [month, year] => {
+            let month = month.trim_end_matches('.').to_lowercase();
+            let index = [
+                "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+            ]
+            .iter()
+            .position(|m| month.starts_with(m))?;
+            Some(format!("{}-{:02}", year, index + 1))
+        }
+        _ => None,
+    }
+}
+
+fn is_bullet(line: &str) -> bool {
+    line.starts_with(['•', '-', '*', '–', '·', '▪', '◦'])
+}
+
+fn strip_bullet(line: &str) -> &str {
+    line.trim_start_matches(['•', '-', '*', '–', '·', '▪', '◦'])
+        .trim()
+}
+
+/// Trim whitespace and dangling separators.
+fn clean(s: &str) -> String {
+    s.trim_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '|' | ',' | '-' | '–' | '—' | '(' | ')')
+    })
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESUME: &str = "\
+Ada Lovelace
+Austin, TX | ada@example.com | (512) 555-0100
+linkedin.com/in/ada-lovelace | https://ada.dev
+
+Professional Summary
+CPA with eight years in audit and tax.
+
+Experience
+Senior Accountant — Acme Corp
+Jan 2020 – Present
+• Led the SOX audit for 12 clients
+• Built month-end close in NetSuite
+Staff Accountant, Baker & Co   06/2016 - Dec 2019
+- Prepared 300+ individual tax returns
+
+Education
+University of Texas at Austin
+Bachelor of Business Administration in Accounting, 2016
+
+Skills
+Languages: Python, SQL
+Excel; QuickBooks • Financial Reporting
+";
+
+    #[test]
+    fn test_contact_details_come_from_the_header() {
+        let contact = parse(RESUME).contact;
+        assert_eq!(contact.name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(contact.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(contact.phone.as_deref(), Some("(512) 555-0100"));
+        assert_eq!(contact.location.as_deref(), Some("Austin, TX"));
+        assert_eq!(
+            contact.linkedin.as_deref(),
+            Some("linkedin.com/in/ada-lovelace")
+        );
+        assert_eq!(contact.website.as_deref(), Some("https://ada.dev"));
+    }
+
+    #[test]
+    fn test_work_history_entries_are_split_on_date_ranges() {
+        let resume = parse(RESUME);
+        assert_eq!(
+            resume.summary.as_deref(),
+            Some("CPA with eight years in audit and tax.")
+        );
+        assert_eq!(
+            resume.work_history,
+            [
+                WorkEntry {
+                    title: Some("Senior Accountant".into()),
+                    company: Some("Acme Corp".into()),
+                    start: Some("2020-01".into()),
+                    end: None,
+                    current: true,
+                    description: vec![
+                        "Led the SOX audit for 12 clients".into(),
+                        "Built month-end close in NetSuite".into(),
+                    ],
+                },
+                WorkEntry {
+                    title: Some("Staff Accountant".into()),
+                    company: Some("Baker & Co".into()),
+                    start: Some("2016-06".into()),
+                    end: Some("2019-12".into()),
+                    current: false,
+                    description: vec!["Prepared 300+ individual tax returns".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_education_and_skills() {
+        let resume = parse(RESUME);
+        assert_eq!(
+            resume.education,
+            [EducationEntry {
+                institution: Some("University of Texas at Austin".into()),
+                degree: Some("Bachelor of Business Administration".into()),
+                field: Some("Accounting".into()),
+                graduation_year: Some(2016),
+            }]
+        );
+        assert_eq!(
+            resume.skills,
+            [
+                "Python",
+                "SQL",
+                "Excel",
+                "QuickBooks",
+                "Financial Reporting",
+                "NetSuite",
+                "Audit",
+                "SOX"
+            ]
+        );
+        assert_eq!(skill_category("quickbooks"), Some("tool"));
+        assert_eq!(resume.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_role_on_its_own_line_above_the_company() {
+        let resume = parse(
+            "Experience\nTax Manager\nGrant LLP | 2015 to 2019\nEducation\nBoston College, Boston, MA\nMBA, 2014",
+        );
+        let role = &resume.work_history[0];
+        assert_eq!(role.title.as_deref(), Some("Tax Manager"));
+        assert_eq!(role.company.as_deref(), Some("Grant LLP"));
+        assert_eq!(
+            (role.start.as_deref(), role.end.as_deref()),
+            (Some("2015"), Some("2019"))
+        );
+        assert_eq!(resume.education.len(), 1);
+        assert_eq!(resume.education[0].degree.as_deref(), Some("MBA"));
+        assert_eq!(
+            resume.education[0].institution.as_deref(),
+            Some("Boston College")
+        );
+        assert_eq!(resume.confidence, 0.4);
+    }
+}
@@ -0,0 +1,239 @@
+//! Plain text from uploaded files, extracted locally.
+
+use std::io::{Cursor, Read};
+
+/// File formats text can be extracted from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Pdf,
+    Docx,
+    PlainText,
+}
+
+impl Format {
+    /// Work out the format from the file's leading bytes, falling back to
+    /// its MIME type and extension.
+    pub fn detect(bytes: &[u8], mime_type: &str, filename: &str) -> Option<Self> {
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        if bytes.starts_with(b"%PDF") {
+            return Some(Self::Pdf);
+        }
+        if bytes.starts_with(b"PK\x03\x04") {
+            let docx = mime_type
+                == "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+                || extension == "docx";
+            return docx.then_some(Self::Docx);
+        }
+        if mime_type.starts_with("text/") || ["txt", "text", "md"].contains(&extension.as_str()) {
+            return Some(Self::PlainText);
+        }
+        None
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ExtractError {
+    #[error("Unsupported file type '{0}'; PDF, DOCX and plain text can be parsed")]
+    Unsupported(String),
+    #[error("Could not read {format}: {reason}")]
+    Corrupt {
+        format: &'static str,
+        reason: String,
+    },
+    #[error("No text found; the file may be a scanned image")]
+    Empty,
+}
+
+/// Extract the text of a file. PDF extraction is CPU-bound and can panic on
+/// malformed input, so callers should run this on a blocking thread.
+pub fn extract(bytes: &[u8], mime_type: &str, filename: &str) -> Result<String, ExtractError> {
+    let format = Format::detect(bytes, mime_type, filename)
+        .ok_or_else(|| ExtractError::Unsupported(mime_type.to_string()))?;
+    let text = match format {
+        Format::Pdf => {
+            pdf_extract::extract_text_from_mem(bytes).map_err(|e| ExtractError::Corrupt {
+                format: "PDF",
+                reason: e.to_string(),
+            })?
+        }
+        Format::Docx => docx_text(bytes)?,
+        Format::PlainText => String::from_utf8_lossy(bytes)
+            .trim_start_matches('\u{feff}')
+            .to_string(),
+    };
+
+    let text = normalize(&text);
+    if text.is_empty() {
+        return Err(ExtractError::Empty);
+    }
+    Ok(text)
+}
+
+/// Paragraph text of a DOCX file's main document part.
+fn docx_text(bytes: &[u8]) -> Result<String, ExtractError> {
+    let corrupt = |reason: String| ExtractError::Corrupt {
+        format: "DOCX",
+        reason,
+    };
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| corrupt(e.to_string()))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| corrupt(e.to_string()))?
+        .read_to_string(&mut xml)
+        .map_err(|e| corrupt(e.to_string()))?;
+    Ok(wordml_text(&xml))
+}
+
+/// Text of WordprocessingML: paragraphs and breaks become newlines, tabs
+/// stay tabs, and every other tag is dropped.
+fn wordml_text(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len() / 4);
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&decode_entities(&rest[..start]));
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "w:p" if tag.starts_with('/') || tag.ends_with('/') => out.push('\n'),
+            "w:br" | "w:cr" => out.push('\n'),
+            // Tab stops in paragraph properties also use `w:tab`.
+            "w:tab" if !tag.contains("w:pos") => out.push('\t'),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find(';').filter(|&end| end <= 10) else {
+            out.push('&');
+            rest = &after[1..];
+            continue;
+        };
+        let entity = &after[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &after[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Trim lines, collapse runs of spaces and of blank lines.
+fn normalize(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line
+            .replace('\u{a0}', " ")
+            .split(' ')
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let line = line.trim();
+        if line.is_empty() && lines.last().map_or(true, |l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn docx(body: &str) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(
+            "word/document.xml",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        write!(
+            zip,
+            r#"<?xml version="1.0"?><w:document><w:body>{}</w:body></w:document>"#,
+            body
+        )
+        .unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_docx_paragraphs_become_lines() {
+        let bytes = docx(
+            "<w:p><w:r><w:t>Ada  Lovelace</w:t></w:r></w:p>\
+             <w:p><w:r><w:t>R&amp;D</w:t><w:tab/><w:t xml:space=\"preserve\">Engineer</w:t></w:r></w:p>\
+             <w:p/><w:p/><w:p><w:r><w:t>Caf&#233;</w:t></w:r></w:p>",
+        );
+        let text = extract(&bytes, "application/octet-stream", "cv.docx").unwrap();
+        assert_eq!(text, "Ada Lovelace\nR&D\tEngineer\n\nCafé");
+    }
+
+    #[test]
+    fn test_formats_are_detected_from_content_first() {
+        assert_eq!(
+            Format::detect(b"%PDF-1.7", "text/plain", "cv.txt"),
+            Some(Format::Pdf)
+        );
+        assert_eq!(
+            Format::detect(b"PK\x03\x04", "application/zip", "cv.zip"),
+            None
+        );
+        assert_eq!(
+            Format::detect(b"Ada", "application/octet-stream", "cv.TXT"),
+            Some(Format::PlainText)
+        );
+        assert_eq!(
+            extract(b"\xd0\xcf\x11\xe0", "application/msword", "cv.doc"),
+            Err(ExtractError::Unsupported("application/msword".to_string()))
+        );
+        assert_eq!(
+            extract(b" \n\n ", "text/plain", "cv.txt"),
+            Err(ExtractError::Empty)
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! The `documents.extract` job.
+
+use anyhow::Context;
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{resume, text, ExtractionJob, Target};
+use crate::matching;
+use crate::scheduler::{tenant_tx, Job};
+use crate::ws::WsEventPayload;
+use crate::AppState;
+
+/// Skills added to a candidate's profile from one document.
+pub const MAX_SKILLS_ADDED: usize = 50;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Source {
+    candidate_id: Option<Uuid>,
+    filename: String,
+    mime_type: String,
+    s3_key: Option<String>,
+    category: Option<String>,
+}
+
+/// Download an uploaded document and extract it. Storage failures are
+/// returned so the job is retried; a file that cannot be parsed is recorded
+/// as failed on the row instead.
+pub async fn run(state: AppState, job: Job) -> anyhow::Result<()> {
+    let tenant_id = job
+        .tenant_id
+        .context("extraction job queued without a tenant")?;
+    let request: ExtractionJob =
+        serde_json::from_value(job.payload).context("invalid extraction job")?;
+
+    let mut tx = tenant_tx(&state.db, tenant_id).await?;
+    let source = load(&mut tx, tenant_id, &request).await?;
+    tx.commit().await?;
+    let Some(s3_key) = source.and_then(|s| s.s3_key) else {
+        tracing::warn!(id = %request.id, "Document to extract no longer exists");
+        return Ok(());
+    };
+
+    let bytes = crate::storage::get_object(&state.config, &s3_key)
+        .await
+        .map_err(anyhow::Error::msg)?;
+    process(&state, tenant_id, &request, &bytes).await?;
+    Ok(())
+}
+
+/// Extract a document's contents and store the result on its row, returning
+/// what was stored. Notifies the requester once done, whether or not the
+/// file could be parsed.
+pub async fn process(
+    state: &AppState,
+    tenant_id: Uuid,
+    request: &ExtractionJob,
+    bytes: &[u8],
+) -> anyhow::Result<Value> {
+    let mut tx = tenant_tx(&state.db, tenant_id).await?;
+    let source = load(&mut tx, tenant_id, request)
+        .await?
+        .context("document to extract not found")?;
+
+    let parsed = parse(bytes.to_vec(), &source.mime_type, &source.filename).await;
+    let (mut data, confidence) = match &parsed {
+        Ok((parsed, text_length)) => {
+            let mut data = serde_json::to_value(parsed)?;
+            data["status"] = json!("parsed");
+            data["parsed_at"] = json!(Utc::now());
+            data["text_length"] = json!(text_length);
+            (data, Some(parsed.confidence))
+        }
+        Err(error) => (
+            json!({"status": "failed", "parsed_at": Utc::now(), "error": error}),
+            None,
+        ),
+    };
+
+    match request.target {
+        Target::CandidateDocument => {
+            if let (Ok((parsed, _)), Some(candidate_id), true) =
+                (&parsed, source.candidate_id, request.upsert_skills)
+            {
+                let added = add_skills(&mut tx, tenant_id, candidate_id, &parsed.skills).await?;
+                if !added.is_empty() {
+                    matching::rescore_candidate(&mut tx, tenant_id, candidate_id).await?;
+                }
+                data["skills_added"] = json!(added);
+            }
+            sqlx::query(
+                "UPDATE candidate_documents SET parsed_data = $1, updated_at = NOW() \
+                 WHERE id = $2 AND tenant_id = $3",
+            )
+            .bind(&data)
+            .bind(request.id)
+            .bind(tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Target::Document => {
+            sqlx::query(
+                "UPDATE documents SET ai_extracted_data = $1, ai_confidence = $2, \
+                 updated_at = NOW() WHERE id = $3 AND tenant_id = $4",
+            )
+            .bind(&data)
+            .bind(confidence)
+            .bind(request.id)
+            .bind(tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+
+    state.ws_broadcast.send_to_user(
+        tenant_id,
+        request.requested_by,
+        WsEventPayload::DocumentProcessed {
+            id: request.id,
+            filename: source.filename,
+            category: source.category,
+            confidence,
+        },
+    );
+    Ok(data)
+}
+
+async fn load(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    request: &ExtractionJob,
+) -> Result<Option<Source>, sqlx::Error> {
+    let sql = match request.target {
+        Target::CandidateDocument => {
+            "SELECT candidate_id, filename, mime_type, s3_key, document_type AS category \
+             FROM candidate_documents WHERE id = $1 AND tenant_id = $2"
+        }
+        Target::Document => {
+            "SELECT NULL::uuid AS candidate_id, filename, mime_type, s3_key, category \
+             FROM documents WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL"
+        }
+    };
+    sqlx::query_as(sql)
+        .bind(request.id)
+        .bind(tenant_id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// Text extraction is CPU-bound and PDF parsing can panic on malformed
+/// files, so it runs on a blocking thread.
+async fn parse(
+    bytes: Vec<u8>,
+    mime_type: &str,
+    filename: &str,
+) -> Result<(resume::ParsedResume, usize), String> {
+    let (mime_type, filename) = (mime_type.to_string(), filename.to_string());
+    tokio::task::spawn_blocking(move || {
+        let text = text::extract(&bytes, &mime_type, &filename).map_err(|e| e.to_string())?;
+        Ok((resume::parse(&text), text.chars().count()))
+    })
+    .await
+    .unwrap_or_else(|_| Err("The file could not be parsed".to_string()))
+}
+
+/// Add skills the candidate does not already list, matching names without
+/// regard to case. Returns the names added.
+async fn add_skills(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    candidate_id: Uuid,
+    skills: &[String],
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut added = Vec::new();
+    for skill in skills.iter().take(MAX_SKILLS_ADDED) {
+        let inserted = sqlx::query(
+            "INSERT INTO candidate_skills (tenant_id, candidate_id, skill_name, category) \
+             SELECT $1, $2, $3, $4 \
+             WHERE NOT EXISTS ( \
+                 SELECT 1 FROM candidate_skills \
+                 WHERE tenant_id = $1 AND candidate_id = $2 AND LOWER(skill_name) = LOWER($3) \
+             )",
+        )
+        .bind(tenant_id)
+        .bind(candidate_id)
+        .bind(skill)
+        .bind(resume::skill_category(skill))
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            added.push(skill.clone());
+        }
+    }
+    Ok(added)
+}
@@ -1,15 +1,12 @@
-use axum::{
-    extract::{Extension, State},
-    Json,
-};
+use axum::{extract::Extension, Json};
 
 use crate::auth::jwt::Claims;
 use crate::error::AppResult;
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 /// GET /flags - Get all feature flags for the current tenant
 pub async fn get_flags(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     // Query entitlements as feature flags
@@ -20,7 +17,7 @@ pub async fn get_flags(
          ORDER BY feature_key ASC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let flags_map: serde_json::Map<String, serde_json::Value> = flags
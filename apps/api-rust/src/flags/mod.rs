@@ -1 +1,10 @@
 pub mod handler;
+
+use axum::{routing::get, Router};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/flags", get(handler::get_flags))
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Path},
     http::StatusCode,
     Json,
 };
@@ -8,7 +8,7 @@ use uuid::Uuid;
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
 use crate::interviews::model::*;
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 fn generate_room_code() -> String {
     use rand::Rng;
@@ -24,7 +24,7 @@ fn generate_room_code() -> String {
 
 /// POST /interviews/rooms
 pub async fn create_room(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateInterviewRoomRequest>,
 ) -> AppResult<(StatusCode, Json<InterviewRoom>)> {
@@ -47,7 +47,7 @@ pub async fn create_room(
     .bind(max_participants)
     .bind(recording_enabled)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(room)))
@@ -55,7 +55,7 @@ pub async fn create_room(
 
 /// POST /interviews/rooms/:id/token
 pub async fn issue_token(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(room_id): Path<Uuid>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -67,7 +67,7 @@ pub async fn issue_token(
     )
     .bind(room_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Interview room not found".to_string()))?;
 
@@ -86,7 +86,7 @@ pub async fn issue_token(
     .bind(claims.sub)
     .bind(&token_hash)
     .bind(expires_at)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((
@@ -101,7 +101,7 @@ pub async fn issue_token(
 
 /// POST /interviews/sessions/:id/events
 pub async fn record_session_event(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(session_id): Path<Uuid>,
     Json(payload): Json<SessionEventRequest>,
@@ -111,7 +111,7 @@ pub async fn record_session_event(
         sqlx::query_as("SELECT COUNT(*) FROM video_sessions WHERE id = $1 AND tenant_id = $2")
             .bind(session_id)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     if exists == 0 {
@@ -131,7 +131,7 @@ pub async fn record_session_event(
     .bind(claims.sub)
     .bind(&payload.event_type)
     .bind(&event_payload)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(event)))
@@ -139,7 +139,7 @@ pub async fn record_session_event(
 
 /// POST /interviews/sessions/:id/feedback
 pub async fn submit_feedback(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(session_id): Path<Uuid>,
     Json(payload): Json<SubmitFeedbackRequest>,
@@ -149,7 +149,7 @@ pub async fn submit_feedback(
         sqlx::query_as("SELECT COUNT(*) FROM video_sessions WHERE id = $1 AND tenant_id = $2")
             .bind(session_id)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     if exists == 0 {
@@ -175,7 +175,7 @@ pub async fn submit_feedback(
     .bind(session_id)
     .bind(claims.sub)
     .bind(&feedback_data)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((
@@ -1,2 +1,21 @@
 pub mod handler;
 pub mod model;
+
+use axum::{routing::post, Router};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/interviews/rooms", post(handler::create_room))
+        .route("/interviews/rooms/{id}/token", post(handler::issue_token))
+        .route(
+            "/interviews/sessions/{id}/events",
+            post(handler::record_session_event),
+        )
+        .route(
+            "/interviews/sessions/{id}/feedback",
+            post(handler::submit_feedback),
+        )
+}
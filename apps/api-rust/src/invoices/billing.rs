@@ -0,0 +1,536 @@
+//! Billing a client for unbilled work.
+//!
+//! Billable time entries and reimbursable expenses that are not yet on an
+//! invoice are gathered for a date range and turned into line items on a
+//! new draft invoice. Billed rows are stamped with the invoice's id, which
+//! keeps them off later invoices; voiding or deleting the invoice clears
+//! the stamp so the work can be billed again.
+//!
+//! Time is billed in hours at the entry's hourly `rate_cents`. Line totals
+//! are computed from the exact minutes, so an hour-and-twenty-minute entry
+//! at $150/h bills $200.00 even though its quantity shows as 1.33. Rates
+//! and expenses are recorded in the firm's base currency, so the invoice
+//! is issued in it too.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Extension, Query},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::handler::next_invoice_number;
+use super::model::Invoice;
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
+use crate::taxes;
+
+/// How unbilled work is collapsed into line items: one line per service
+/// type (and expense category), or one line per time entry and expense.
+pub const GROUPINGS: [&str; 2] = ["service_type", "entry"];
+/// Service type of lines billing expenses, so tax rates can exempt them.
+pub const EXPENSE_SERVICE_TYPE: &str = "expense";
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnbilledTime {
+    pub id: Uuid,
+    pub service_type: String,
+    pub description: String,
+    pub date: NaiveDate,
+    pub duration_minutes: i32,
+    pub rate_cents: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnbilledExpense {
+    pub id: Uuid,
+    pub category: String,
+    pub description: Option<String>,
+    pub date: NaiveDate,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BillingLine {
+    pub description: String,
+    pub quantity: f64,
+    pub unit_price_cents: i64,
+    pub total_cents: i64,
+    pub time_entry_id: Option<Uuid>,
+    pub expense_id: Option<Uuid>,
+    pub service_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnbilledQuery {
+    pub client_id: Uuid,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    /// One of [`GROUPINGS`]; defaults to `service_type`.
+    pub group_by: Option<String>,
+    /// Include reimbursable expenses; defaults to true.
+    pub include_expenses: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillClientRequest {
+    pub client_id: Uuid,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    pub group_by: Option<String>,
+    pub include_expenses: Option<bool>,
+    pub due_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnbilledSummary {
+    pub lines: Vec<BillingLine>,
+    pub subtotal_cents: i64,
+    pub time_entries: usize,
+    pub expenses: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BilledInvoice {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub time_entries_billed: usize,
+    pub expenses_billed: usize,
+}
+
+/// Bill for `minutes` at an hourly rate, rounding half a cent up.
+pub fn time_total_cents(minutes: i64, rate_cents: i64) -> i64 {
+    (minutes * rate_cents + 30).div_euclid(60)
+}
+
+/// `minutes` as hours, to the two decimal places a line item stores.
+fn hours(minutes: i64) -> f64 {
+    (minutes as f64 / 60.0 * 100.0).round() / 100.0
+}
+
+/// `tax_preparation` -> `Tax preparation`.
+fn label(key: &str) -> String {
+    let words = key.trim().replace(['_', '-'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "General".to_string(),
+    }
+}
+
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// Line items billing `time` and `expenses`, in the order they will appear
+/// on the invoice: time first, then expenses.
+pub fn build_lines(
+    time: &[UnbilledTime],
+    expenses: &[UnbilledExpense],
+    group_by: &str,
+) -> Vec<BillingLine> {
+    let mut lines = Vec::new();
+    if group_by == "entry" {
+        for entry in time {
+            let minutes = entry.duration_minutes as i64;
+            let what = if entry.description.trim().is_empty() {
+                label(&entry.service_type)
+            } else {
+                entry.description.trim().to_string()
+            };
+            lines.push(BillingLine {
+                description: format!("{} - {}", entry.date.format("%b %d, %Y"), what),
+                quantity: hours(minutes),
+                unit_price_cents: entry.rate_cents,
+                total_cents: time_total_cents(minutes, entry.rate_cents),
+                time_entry_id: Some(entry.id),
+                expense_id: None,
+                service_type: entry.service_type.clone(),
+            });
+        }
+        for expense in expenses {
+            let description = match expense.description.as_deref().map(str::trim) {
+                Some(d) if !d.is_empty() => format!("{}: {}", label(&expense.category), d),
+                _ => label(&expense.category),
+            };
+            lines.push(BillingLine {
+                description: format!("{} - {}", expense.date.format("%b %d, %Y"), description),
+                quantity: 1.0,
+                unit_price_cents: expense.amount_cents,
+                total_cents: expense.amount_cents,
+                time_entry_id: None,
+                expense_id: Some(expense.id),
+                service_type: EXPENSE_SERVICE_TYPE.to_string(),
+            });
+        }
+        return lines;
+    }
+
+    // Entries at different rates cannot share a line's unit price.
+    let mut by_service: BTreeMap<(&str, i64), (i64, usize)> = BTreeMap::new();
+    for entry in time {
+        let group = by_service
+            .entry((entry.service_type.as_str(), entry.rate_cents))
+            .or_default();
+        group.0 += entry.duration_minutes as i64;
+        group.1 += 1;
+    }
+    for ((service_type, rate_cents), (minutes, n)) in by_service {
+        lines.push(BillingLine {
+            description: format!(
+                "{} ({})",
+                label(service_type),
+                count(n, "time entry", "time entries")
+            ),
+            quantity: hours(minutes),
+            unit_price_cents: rate_cents,
+            total_cents: time_total_cents(minutes, rate_cents),
+            time_entry_id: None,
+            expense_id: None,
+            service_type: service_type.to_string(),
+        });
+    }
+
+    let mut by_category: BTreeMap<&str, (i64, usize)> = BTreeMap::new();
+    for expense in expenses {
+        let group = by_category.entry(expense.category.as_str()).or_default();
+        group.0 += expense.amount_cents;
+        group.1 += 1;
+    }
+    for (category, (amount_cents, n)) in by_category {
+        lines.push(BillingLine {
+            description: format!(
+                "Expenses: {} ({})",
+                label(category),
+                count(n, "expense", "expenses")
+            ),
+            quantity: 1.0,
+            unit_price_cents: amount_cents,
+            total_cents: amount_cents,
+            time_entry_id: None,
+            expense_id: None,
+            service_type: EXPENSE_SERVICE_TYPE.to_string(),
+        });
+    }
+    lines
+}
+
+fn check_range(
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+    group_by: Option<&str>,
+) -> AppResult<&'static str> {
+    if let (Some(from), Some(to)) = (date_from, date_to) {
+        if from > to {
+            return Err(AppError::Validation(
+                "date_from must be on or before date_to".to_string(),
+            ));
+        }
+    }
+    let group_by = group_by.unwrap_or(GROUPINGS[0]);
+    GROUPINGS
+        .iter()
+        .find(|g| **g == group_by)
+        .copied()
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "Invalid group_by '{}'. Must be one of: {}",
+                group_by,
+                GROUPINGS.join(", ")
+            ))
+        })
+}
+
+/// The client's unbilled billable time and, if asked for, reimbursable
+/// expenses dated within the range. With `lock`, the rows stay locked until
+/// the caller's transaction ends so concurrent billing cannot take them twice.
+async fn load_unbilled(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    client_id: Uuid,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+    include_expenses: bool,
+    lock: bool,
+) -> AppResult<(Vec<UnbilledTime>, Vec<UnbilledExpense>)> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM clients WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL)",
+    )
+    .bind(client_id)
+    .bind(tenant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !exists {
+        return Err(AppError::NotFound("Client not found".to_string()));
+    }
+
+    let lock_clause = if lock { " FOR UPDATE" } else { "" };
+    let time: Vec<UnbilledTime> = sqlx::query_as(&format!(
+        "SELECT id, service_type, description, date, duration_minutes, rate_cents FROM time_entries \
+         WHERE tenant_id = $1 AND client_id = $2 AND is_billable AND NOT is_running \
+         AND invoice_id IS NULL \
+         AND ($3::date IS NULL OR date >= $3) AND ($4::date IS NULL OR date <= $4) \
+         ORDER BY date, created_at{}",
+        lock_clause
+    ))
+    .bind(tenant_id)
+    .bind(client_id)
+    .bind(date_from)
+    .bind(date_to)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let expenses: Vec<UnbilledExpense> = if include_expenses {
+        sqlx::query_as(&format!(
+            "SELECT id, category, description, date, amount_cents FROM expenses \
+             WHERE tenant_id = $1 AND client_id = $2 AND is_reimbursable \
+             AND invoice_id IS NULL AND status <> 'rejected' \
+             AND ($3::date IS NULL OR date >= $3) AND ($4::date IS NULL OR date <= $4) \
+             ORDER BY date, created_at{}",
+            lock_clause
+        ))
+        .bind(tenant_id)
+        .bind(client_id)
+        .bind(date_from)
+        .bind(date_to)
+        .fetch_all(&mut *conn)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok((time, expenses))
+}
+
+/// Preview the lines `bill_client` would produce, without billing anything.
+pub async fn list_unbilled(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<UnbilledQuery>,
+) -> AppResult<Json<UnbilledSummary>> {
+    let group_by = check_range(params.date_from, params.date_to, params.group_by.as_deref())?;
+    let (time, expenses) = load_unbilled(
+        &mut tx,
+        claims.tid,
+        params.client_id,
+        params.date_from,
+        params.date_to,
+        params.include_expenses.unwrap_or(true),
+        false,
+    )
+    .await?;
+
+    let lines = build_lines(&time, &expenses, group_by);
+    Ok(Json(UnbilledSummary {
+        subtotal_cents: lines.iter().map(|l| l.total_cents).sum(),
+        lines,
+        time_entries: time.len(),
+        expenses: expenses.len(),
+    }))
+}
+
+/// Create a draft invoice for a client's unbilled time and expenses and
+/// mark them as billed on it.
+pub async fn bill_client(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<BillClientRequest>,
+) -> AppResult<(StatusCode, Json<BilledInvoice>)> {
+    let group_by = check_range(
+        payload.date_from,
+        payload.date_to,
+        payload.group_by.as_deref(),
+    )?;
+
+    let (time, expenses) = load_unbilled(
+        &mut tx,
+        claims.tid,
+        payload.client_id,
+        payload.date_from,
+        payload.date_to,
+        payload.include_expenses.unwrap_or(true),
+        true,
+    )
+    .await?;
+
+    let lines = build_lines(&time, &expenses, group_by);
+    if lines.is_empty() {
+        return Err(AppError::Validation(
+            "No unbilled time or expenses for this client in the selected range".to_string(),
+        ));
+    }
+
+    let id = Uuid::new_v4();
+    let invoice_number = next_invoice_number(&mut tx, claims.tid).await?;
+    let subtotal_cents: i64 = lines.iter().map(|l| l.total_cents).sum();
+
+    let mut invoice: Invoice = sqlx::query_as(
+        "INSERT INTO invoices (id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, due_date, notes, created_by, currency) VALUES ($1, $2, $3, $4, 'draft', $5, 0, $5, $6, $7, $8, (SELECT base_currency FROM tenants WHERE id = $2)) RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .bind(payload.client_id)
+    .bind(&invoice_number)
+    .bind(subtotal_cents)
+    .bind(payload.due_date)
+    .bind(payload.notes.as_deref())
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (i, line) in lines.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO invoice_line_items (tenant_id, invoice_id, description, quantity, unit_price_cents, total_cents, time_entry_id, expense_id, service_type, sort_order) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(claims.tid)
+        .bind(id)
+        .bind(&line.description)
+        .bind(line.quantity)
+        .bind(line.unit_price_cents)
+        .bind(line.total_cents)
+        .bind(line.time_entry_id)
+        .bind(line.expense_id)
+        .bind(&line.service_type)
+        .bind(i as i32)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let time_ids: Vec<Uuid> = time.iter().map(|t| t.id).collect();
+    sqlx::query(
+        "UPDATE time_entries SET invoice_id = $1, updated_at = NOW() WHERE tenant_id = $2 AND id = ANY($3)",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .bind(&time_ids)
+    .execute(&mut *tx)
+    .await?;
+    let expense_ids: Vec<Uuid> = expenses.iter().map(|e| e.id).collect();
+    sqlx::query(
+        "UPDATE expenses SET invoice_id = $1, updated_at = NOW() WHERE tenant_id = $2 AND id = ANY($3)",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .bind(&expense_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    invoice.tax_cents = taxes::engine::apply_to_invoice(&mut tx, claims.tid, id).await?;
+    invoice.total_cents = invoice.subtotal_cents + invoice.tax_cents;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BilledInvoice {
+            invoice,
+            time_entries_billed: time_ids.len(),
+            expenses_billed: expense_ids.len(),
+        }),
+    ))
+}
+
+/// Return the time entries and expenses billed on an invoice to the
+/// unbilled pool.
+pub async fn release(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE time_entries SET invoice_id = NULL, updated_at = NOW() WHERE invoice_id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE expenses SET invoice_id = NULL, updated_at = NOW() WHERE invoice_id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(service_type: &str, minutes: i32, rate_cents: i64) -> UnbilledTime {
+        UnbilledTime {
+            id: Uuid::new_v4(),
+            service_type: service_type.to_string(),
+            description: String::new(),
+            date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            duration_minutes: minutes,
+            rate_cents,
+        }
+    }
+
+    fn expense(category: &str, amount_cents: i64) -> UnbilledExpense {
+        UnbilledExpense {
+            id: Uuid::new_v4(),
+            category: category.to_string(),
+            description: Some("Filing fee".to_string()),
+            date: NaiveDate::from_ymd_opt(2026, 3, 3).unwrap(),
+            amount_cents,
+        }
+    }
+
+    #[test]
+    fn test_groups_by_service_type_and_rate() {
+        let time = vec![
+            time("tax_preparation", 80, 15000),
+            time("tax_preparation", 40, 15000),
+            time("tax_preparation", 20, 20000),
+            time("bookkeeping", 7, 9000),
+        ];
+        let expenses = vec![expense("travel", 4200), expense("travel", 800)];
+        let lines = build_lines(&time, &expenses, "service_type");
+
+        let summary: Vec<(&str, f64, i64, i64)> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l.description.as_str(),
+                    l.quantity,
+                    l.unit_price_cents,
+                    l.total_cents,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                // 7 minutes at $90/h is $10.50.
+                ("Bookkeeping (1 time entry)", 0.12, 9000, 1050),
+                ("Tax preparation (2 time entries)", 2.0, 15000, 30000),
+                ("Tax preparation (1 time entry)", 0.33, 20000, 6667),
+                ("Expenses: Travel (2 expenses)", 1.0, 5000, 5000),
+            ]
+        );
+        assert!(lines
+            .iter()
+            .all(|l| l.time_entry_id.is_none() && l.expense_id.is_none()));
+    }
+
+    #[test]
+    fn test_one_line_per_entry_links_its_source() {
+        let time = vec![time("audit", 90, 10000)];
+        let expenses = vec![expense("filing", 2500)];
+        let lines = build_lines(&time, &expenses, "entry");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].description, "Mar 02, 2026 - Audit");
+        assert_eq!(lines[0].total_cents, 15000);
+        assert_eq!(lines[0].time_entry_id, Some(time[0].id));
+        assert_eq!(lines[1].description, "Mar 03, 2026 - Filing: Filing fee");
+        assert_eq!(lines[1].expense_id, Some(expenses[0].id));
+    }
+}
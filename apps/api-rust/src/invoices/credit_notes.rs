@@ -0,0 +1,366 @@
+//! Credit notes and applying client credit.
+//!
+//! A credit note gives a client credit, usually against an invoice it
+//! corrects; that invoice takes the credit first and the rest stays with
+//! the client. Unapplied credit, from credit notes or overpayments, can be
+//! applied to any open invoice of the client in the same currency. Voiding
+//! a credit note takes back whatever it paid.
+
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::invoices::model::Invoice;
+use crate::invoices::status::InvoiceStatus;
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::money::Currency;
+use crate::payments::ledger::{self, Source};
+
+const CREDIT_NOTE_COLUMNS: &str = "n.id, n.tenant_id, n.client_id, n.invoice_id, \
+    n.credit_note_number, n.currency, n.amount_cents, \
+    CASE WHEN n.status = 'issued' THEN n.amount_cents - COALESCE((SELECT SUM(a.amount_cents) \
+    FROM payment_allocations a WHERE a.credit_note_id = n.id), 0) ELSE 0 END::BIGINT AS unallocated_cents, \
+    n.reason, n.status, n.created_by, n.created_at, n.voided_at";
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CreditNote {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub client_id: Uuid,
+    pub invoice_id: Option<Uuid>,
+    pub credit_note_number: String,
+    pub currency: String,
+    pub amount_cents: i64,
+    /// Credit not yet applied to an invoice.
+    pub unallocated_cents: i64,
+    pub reason: Option<String>,
+    /// `issued` or `void`.
+    pub status: String,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub voided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCreditNoteRequest {
+    /// Required unless `invoice_id` is given.
+    pub client_id: Option<Uuid>,
+    /// The invoice credited; it takes the credit first.
+    pub invoice_id: Option<Uuid>,
+    pub amount_cents: i64,
+    /// ISO 4217 code; defaults to the invoice's currency, else the firm's
+    /// base currency.
+    pub currency: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListCreditNotesQuery {
+    pub client_id: Option<Uuid>,
+    pub invoice_id: Option<Uuid>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyCreditRequest {
+    /// Defaults to as much as covers the balance.
+    pub amount_cents: Option<i64>,
+}
+
+/// Next sequential `CN-NNNNN` number for a tenant, under a per-tenant
+/// advisory lock held until the caller's transaction ends.
+async fn next_credit_note_number(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+) -> Result<String, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('credit_note_number:' || $1::text))")
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let next_num: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(CAST(SUBSTRING(credit_note_number FROM 4) AS BIGINT)), 0) + 1 \
+         FROM credit_notes WHERE tenant_id = $1 AND credit_note_number LIKE 'CN-%'",
+    )
+    .bind(tenant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(format!("CN-{:05}", next_num))
+}
+
+async fn load_credit_note(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> AppResult<CreditNote> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM credit_notes n WHERE n.id = $1 AND n.tenant_id = $2",
+        CREDIT_NOTE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Credit note not found".to_string()))
+}
+
+pub async fn list_credit_notes(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<ListCreditNotesQuery>,
+) -> AppResult<Json<Vec<CreditNote>>> {
+    let notes: Vec<CreditNote> = sqlx::query_as(&format!(
+        "SELECT {} FROM credit_notes n WHERE n.tenant_id = $1 \
+         AND ($2::UUID IS NULL OR n.client_id = $2) \
+         AND ($3::UUID IS NULL OR n.invoice_id = $3) \
+         AND ($4::TEXT IS NULL OR n.status = $4) \
+         ORDER BY n.created_at DESC",
+        CREDIT_NOTE_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(params.client_id)
+    .bind(params.invoice_id)
+    .bind(params.status.as_deref())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(notes))
+}
+
+pub async fn get_credit_note(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<CreditNote>> {
+    Ok(Json(load_credit_note(&mut tx, claims.tid, id).await?))
+}
+
+pub async fn create_credit_note(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateCreditNoteRequest>,
+) -> AppResult<(StatusCode, Json<CreditNote>)> {
+    require_role(&claims, "manager")?;
+    if payload.amount_cents <= 0 {
+        return Err(AppError::Validation(
+            "amount_cents must be positive".to_string(),
+        ));
+    }
+    let currency = payload
+        .currency
+        .as_deref()
+        .map(Currency::parse)
+        .transpose()?;
+
+    let (client_id, currency) = match payload.invoice_id {
+        Some(invoice_id) => {
+            let (client_id, invoice_currency, status): (Uuid, String, String) = sqlx::query_as(
+                "SELECT client_id, currency, status FROM invoices \
+                 WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL",
+            )
+            .bind(invoice_id)
+            .bind(claims.tid)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+            if status == InvoiceStatus::Draft.as_str() {
+                return Err(AppError::Validation(
+                    "Draft invoices are edited, not credited".to_string(),
+                ));
+            }
+            if payload.client_id.is_some_and(|c| c != client_id) {
+                return Err(AppError::Validation(
+                    "The invoice belongs to another client".to_string(),
+                ));
+            }
+            if currency.is_some_and(|c| c.code != invoice_currency) {
+                return Err(AppError::Validation(format!(
+                    "The invoice is in {}",
+                    invoice_currency
+                )));
+            }
+            (client_id, invoice_currency)
+        }
+        None => {
+            let client_id = payload.client_id.ok_or_else(|| {
+                AppError::Validation("client_id or invoice_id is required".to_string())
+            })?;
+            let base: String = sqlx::query_scalar(
+                "SELECT t.base_currency FROM clients c JOIN tenants t ON t.id = c.tenant_id \
+                 WHERE c.id = $1 AND c.tenant_id = $2 AND c.deleted_at IS NULL",
+            )
+            .bind(client_id)
+            .bind(claims.tid)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Client not found".to_string()))?;
+            (
+                client_id,
+                currency.map(|c| c.code.to_string()).unwrap_or(base),
+            )
+        }
+    };
+
+    let number = next_credit_note_number(&mut tx, claims.tid).await?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO credit_notes (tenant_id, client_id, invoice_id, credit_note_number, currency, amount_cents, reason, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+    )
+    .bind(claims.tid)
+    .bind(client_id)
+    .bind(payload.invoice_id)
+    .bind(&number)
+    .bind(&currency)
+    .bind(payload.amount_cents)
+    .bind(
+        payload
+            .reason
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty()),
+    )
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(invoice_id) = payload.invoice_id {
+        let (status, due): (String, i64) = sqlx::query_as(
+            "SELECT status, total_cents - amount_paid_cents FROM invoices WHERE id = $1",
+        )
+        .bind(invoice_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let amount = payload.amount_cents.min(due);
+        if InvoiceStatus::parse(&status).is_some_and(InvoiceStatus::is_open) && amount > 0 {
+            ledger::allocate(
+                &mut tx,
+                claims.tid,
+                Source::CreditNote(id),
+                invoice_id,
+                amount,
+                Some(claims.sub),
+            )
+            .await?;
+        }
+    }
+
+    let note = load_credit_note(&mut tx, claims.tid, id).await?;
+
+    Ok((StatusCode::CREATED, Json(note)))
+}
+
+/// Void a credit note, taking back whatever it paid.
+pub async fn void_credit_note(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<CreditNote>> {
+    require_role(&claims, "manager")?;
+
+    let status: String = sqlx::query_scalar(
+        "SELECT status FROM credit_notes WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Credit note not found".to_string()))?;
+    if status == "void" {
+        return Err(AppError::Conflict(
+            "Credit note is already void".to_string(),
+        ));
+    }
+
+    ledger::reverse_source(
+        &mut tx,
+        claims.tid,
+        Source::CreditNote(id),
+        Some(claims.sub),
+    )
+    .await?;
+    sqlx::query("UPDATE credit_notes SET status = 'void', voided_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let note = load_credit_note(&mut tx, claims.tid, id).await?;
+
+    Ok(Json(note))
+}
+
+/// Pay an invoice from the client's unapplied credit in its currency:
+/// credit notes first, then overpayments, oldest first.
+pub async fn apply_credit(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(invoice_id): Path<Uuid>,
+    Json(payload): Json<ApplyCreditRequest>,
+) -> AppResult<Json<Invoice>> {
+    let (client_id, currency, status, due): (Uuid, String, String, i64) = sqlx::query_as(
+        "SELECT client_id, currency, status, total_cents - amount_paid_cents FROM invoices \
+         WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    if !InvoiceStatus::parse(&status).is_some_and(InvoiceStatus::is_open) {
+        return Err(AppError::Validation(format!(
+            "Invoice is {} and cannot take payments",
+            status
+        )));
+    }
+    let wanted = payload.amount_cents.unwrap_or(due);
+    if wanted <= 0 || wanted > due {
+        return Err(AppError::Validation(format!(
+            "amount_cents must be between 1 and {}",
+            due
+        )));
+    }
+
+    let credit = ledger::available_credit(&mut tx, claims.tid, client_id, &currency).await?;
+    if credit.iter().map(|(_, left)| left).sum::<i64>() < wanted {
+        return Err(AppError::Validation(
+            "The client does not have enough credit".to_string(),
+        ));
+    }
+    let mut left = wanted;
+    for (source, available) in credit {
+        if left == 0 {
+            break;
+        }
+        let amount = available.min(left);
+        ledger::allocate(
+            &mut tx,
+            claims.tid,
+            source,
+            invoice_id,
+            amount,
+            Some(claims.sub),
+        )
+        .await?;
+        left -= amount;
+    }
+
+    let invoice: Invoice = sqlx::query_as(
+        "SELECT id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, \
+         amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, \
+         stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at \
+         FROM invoices WHERE id = $1",
+    )
+    .bind(invoice_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok(Json(invoice))
+}
@@ -0,0 +1,573 @@
+//! Overdue invoices and dunning.
+//!
+//! An hourly pass moves open invoices (`sent`, `viewed` and `partial`)
+//! whose due date has passed to `overdue`, then applies the tenant's
+//! dunning steps to overdue invoices with a balance. A step fires once per
+//! invoice, when the invoice is at least its `days_past_due` late: it can
+//! email the client a reminder and add a late fee line. When several steps
+//! come due together (the steps were configured after the invoice went
+//! overdue, or the job was down) only the latest is applied and the others
+//! are recorded as skipped, so a client never gets a burst of reminders or
+//! stacked fees.
+//!
+//! Late fees are a flat amount in the firm's base currency, converted into
+//! the invoice's currency, plus a share of the balance due. The fee line
+//! has service type [`LATE_FEE_SERVICE_TYPE`], so tax rates can exempt it.
+
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::mailer::{messages, outbox};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::money::{self, rates::Converter, Currency};
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::taxes;
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "invoices.dunning";
+
+pub const LATE_FEE_SERVICE_TYPE: &str = "late_fee";
+
+const STEP_COLUMNS: &str = "id, tenant_id, days_past_due, send_reminder, late_fee_cents, \
+    late_fee_ppm, late_fee_ppm / 10000.0::float8 AS late_fee_percent, message, is_active, \
+    created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DunningStep {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub days_past_due: i32,
+    pub send_reminder: bool,
+    /// Flat fee in minor units of the firm's base currency.
+    pub late_fee_cents: i64,
+    /// Parts per million of the balance due: 1.5% is 15000.
+    pub late_fee_ppm: i32,
+    pub late_fee_percent: f64,
+    /// Added to the reminder email.
+    pub message: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDunningStepRequest {
+    pub days_past_due: i32,
+    pub send_reminder: Option<bool>,
+    pub late_fee_cents: Option<i64>,
+    /// Up to four decimal places, e.g. 1.5.
+    pub late_fee_percent: Option<f64>,
+    pub message: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDunningStepRequest {
+    pub days_past_due: Option<i32>,
+    pub send_reminder: Option<bool>,
+    pub late_fee_cents: Option<i64>,
+    pub late_fee_percent: Option<f64>,
+    pub message: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// A step's effect on one invoice.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DunningEvent {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub dunning_step_id: Option<Uuid>,
+    pub days_past_due: i32,
+    /// Came due together with a later step and was not applied.
+    pub skipped: bool,
+    pub late_fee_cents: i64,
+    pub email_outbox_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct OverdueInvoice {
+    client_id: Uuid,
+    invoice_number: String,
+    currency: String,
+    due_date: NaiveDate,
+    total_cents: i64,
+    amount_paid_cents: i64,
+}
+
+/// The fee a step charges on `balance_cents`: `flat_cents` plus `ppm`
+/// parts per million of the balance, rounded half up.
+pub fn late_fee(balance_cents: i64, flat_cents: i64, ppm: i32) -> i64 {
+    let share = (balance_cents.max(0) as i128 * ppm as i128 + 500_000) / 1_000_000;
+    flat_cents + share as i64
+}
+
+/// Move sent, viewed and partly paid invoices due before `today` to
+/// `overdue`. Returns how many moved.
+pub async fn mark_overdue(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    today: NaiveDate,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE invoices SET status = 'overdue', updated_at = NOW() \
+         WHERE tenant_id = $1 AND status IN ('sent', 'viewed', 'partial') AND due_date < $2 \
+         AND deleted_at IS NULL",
+    )
+    .bind(tenant_id)
+    .bind(today)
+    .execute(&mut *conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Mark past-due invoices overdue and apply due dunning steps, across all
+/// tenants. A failing invoice is logged and skipped so it cannot block the
+/// others; the job then fails so the runner retries it.
+pub async fn run_dunning(state: AppState, _job: Job) -> anyhow::Result<()> {
+    let today = Utc::now().date_naive();
+    let mut failures = 0;
+
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+        let marked = mark_overdue(&mut tx, tenant_id, today).await?;
+        if marked > 0 {
+            tracing::info!(tenant_id = %tenant_id, "Marked {} invoice(s) overdue", marked);
+        }
+        let due: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT i.id FROM invoices i \
+             WHERE i.tenant_id = $1 AND i.status = 'overdue' AND i.deleted_at IS NULL \
+             AND i.total_cents > i.amount_paid_cents \
+             AND EXISTS (SELECT 1 FROM dunning_steps s \
+                 WHERE s.tenant_id = i.tenant_id AND s.is_active \
+                 AND s.days_past_due <= $2 - i.due_date \
+                 AND NOT EXISTS (SELECT 1 FROM invoice_dunning_events e \
+                     WHERE e.invoice_id = i.id AND e.dunning_step_id = s.id)) \
+             ORDER BY i.due_date",
+        )
+        .bind(tenant_id)
+        .bind(today)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        for invoice_id in due {
+            if let Err(e) = dun_invoice(&state.db, tenant_id, invoice_id, today).await {
+                failures += 1;
+                tracing::error!(invoice_id = %invoice_id, "Failed to apply dunning step: {:#}", e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} overdue invoice(s) failed", failures);
+    }
+    Ok(())
+}
+
+/// Apply the latest due, not yet applied dunning step to one overdue
+/// invoice. Returns the step applied, if any.
+pub async fn dun_invoice(
+    db: &sqlx::PgPool,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+    today: NaiveDate,
+) -> anyhow::Result<Option<Uuid>> {
+    let mut tx = tenant_tx(db, tenant_id).await?;
+
+    let invoice: Option<OverdueInvoice> = sqlx::query_as(
+        "SELECT client_id, invoice_number, currency, due_date, total_cents, amount_paid_cents \
+         FROM invoices WHERE id = $1 AND tenant_id = $2 AND status = 'overdue' \
+         AND deleted_at IS NULL AND total_cents > amount_paid_cents AND due_date IS NOT NULL \
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    // Paid meanwhile, or being dunned by another instance.
+    let Some(invoice) = invoice else {
+        return Ok(None);
+    };
+    let days_past_due = (today - invoice.due_date).num_days();
+
+    let mut steps: Vec<DunningStep> = sqlx::query_as(&format!(
+        "SELECT {} FROM dunning_steps s WHERE tenant_id = $1 AND is_active AND days_past_due <= $2 \
+         AND NOT EXISTS (SELECT 1 FROM invoice_dunning_events e \
+             WHERE e.invoice_id = $3 AND e.dunning_step_id = s.id) \
+         ORDER BY days_past_due",
+        STEP_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(days_past_due as i32)
+    .bind(invoice_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let Some(step) = steps.pop() else {
+        return Ok(None);
+    };
+
+    for skipped in &steps {
+        sqlx::query(
+            "INSERT INTO invoice_dunning_events (tenant_id, invoice_id, dunning_step_id, days_past_due, skipped) \
+             VALUES ($1, $2, $3, $4, TRUE)",
+        )
+        .bind(tenant_id)
+        .bind(invoice_id)
+        .bind(skipped.id)
+        .bind(days_past_due as i32)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let (firm_name, base, locale): (String, String, String) =
+        sqlx::query_as("SELECT name, base_currency, locale FROM tenants WHERE id = $1")
+            .bind(tenant_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let mut flat_cents = step.late_fee_cents;
+    if flat_cents > 0 {
+        let base = Currency::parse(&base)?;
+        let mut converter = Converter::load(&mut tx, tenant_id, base, today).await?;
+        flat_cents = converter.convert_from_base(flat_cents, &invoice.currency, today)?;
+    }
+    let balance = invoice.total_cents - invoice.amount_paid_cents;
+    let fee_cents = late_fee(balance, flat_cents, step.late_fee_ppm);
+
+    let mut balance_due = balance;
+    if fee_cents > 0 {
+        sqlx::query(
+            "INSERT INTO invoice_line_items (tenant_id, invoice_id, description, quantity, unit_price_cents, total_cents, service_type, sort_order) \
+             VALUES ($1, $2, $3, 1, $4, $4, $5, \
+                 (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM invoice_line_items WHERE invoice_id = $2))",
+        )
+        .bind(tenant_id)
+        .bind(invoice_id)
+        .bind(format!("Late fee ({} days past due)", days_past_due))
+        .bind(fee_cents)
+        .bind(LATE_FEE_SERVICE_TYPE)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE invoices SET subtotal_cents = subtotal_cents + $3, updated_at = NOW() \
+             WHERE id = $1 AND tenant_id = $2",
+        )
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .bind(fee_cents)
+        .execute(&mut *tx)
+        .await?;
+        taxes::engine::apply_to_invoice(&mut tx, tenant_id, invoice_id).await?;
+        balance_due = sqlx::query_scalar(
+            "SELECT total_cents - amount_paid_cents FROM invoices WHERE id = $1 AND tenant_id = $2",
+        )
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    }
+
+    let mut email_outbox_id = None;
+    if step.send_reminder {
+        let email: Option<String> =
+            sqlx::query_scalar("SELECT email FROM clients WHERE id = $1 AND tenant_id = $2")
+                .bind(invoice.client_id)
+                .bind(tenant_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .flatten();
+        match email.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+            Some(email) => {
+                let fee =
+                    (fee_cents > 0).then(|| money::format(fee_cents, &invoice.currency, &locale));
+                let message = messages::invoice_overdue(
+                    email,
+                    &firm_name,
+                    &invoice.invoice_number,
+                    &money::format(balance_due, &invoice.currency, &locale),
+                    days_past_due,
+                    fee.as_deref(),
+                    step.message.as_deref(),
+                );
+                email_outbox_id = Some(
+                    outbox::enqueue(
+                        &mut *tx,
+                        tenant_id,
+                        &message,
+                        outbox::EnqueueOptions {
+                            category: Some("invoice"),
+                            ..Default::default()
+                        },
+                    )
+                    .await?,
+                );
+            }
+            None => tracing::warn!(
+                "Overdue reminder for invoice {} not sent: client has no email address",
+                invoice_id
+            ),
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO invoice_dunning_events (tenant_id, invoice_id, dunning_step_id, days_past_due, late_fee_cents, email_outbox_id) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(tenant_id)
+    .bind(invoice_id)
+    .bind(step.id)
+    .bind(days_past_due as i32)
+    .bind(fee_cents)
+    .bind(email_outbox_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    tracing::info!(
+        invoice_id = %invoice_id,
+        step_id = %step.id,
+        late_fee_cents = fee_cents,
+        "Applied dunning step to invoice {}",
+        invoice.invoice_number
+    );
+    Ok(Some(step.id))
+}
+
+/// `percent` as parts per million, refusing more precision than a
+/// `late_fee_ppm` can hold.
+fn fee_ppm(percent: f64) -> AppResult<i32> {
+    if !percent.is_finite() || !(0.0..=100.0).contains(&percent) {
+        return Err(AppError::Validation(
+            "late_fee_percent must be between 0 and 100".to_string(),
+        ));
+    }
+    let ppm = (percent * 10_000.0).round();
+    if (ppm - percent * 10_000.0).abs() > 1e-6 {
+        return Err(AppError::Validation(
+            "late_fee_percent allows at most four decimal places".to_string(),
+        ));
+    }
+    Ok(ppm as i32)
+}
+
+fn check_step(days_past_due: i32, late_fee_cents: i64) -> AppResult<()> {
+    if days_past_due < 1 {
+        return Err(AppError::Validation(
+            "days_past_due must be at least 1".to_string(),
+        ));
+    }
+    if late_fee_cents < 0 {
+        return Err(AppError::Validation(
+            "late_fee_cents cannot be negative".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Blank messages are stored as NULL.
+fn clean_message(message: &str) -> Option<String> {
+    Some(message.trim().to_string()).filter(|m| !m.is_empty())
+}
+
+fn step_conflict(days_past_due: i32) -> AppError {
+    AppError::Conflict(format!(
+        "A dunning step at {} days past due already exists",
+        days_past_due
+    ))
+}
+
+pub async fn list_dunning_steps(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+) -> AppResult<Json<Vec<DunningStep>>> {
+    let steps: Vec<DunningStep> = sqlx::query_as(&format!(
+        "SELECT {} FROM dunning_steps WHERE tenant_id = $1 ORDER BY days_past_due",
+        STEP_COLUMNS
+    ))
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(steps))
+}
+
+pub async fn create_dunning_step(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateDunningStepRequest>,
+) -> AppResult<(StatusCode, Json<DunningStep>)> {
+    require_role(&claims, "admin")?;
+    let late_fee_cents = payload.late_fee_cents.unwrap_or(0);
+    check_step(payload.days_past_due, late_fee_cents)?;
+    let late_fee_ppm = fee_ppm(payload.late_fee_percent.unwrap_or(0.0))?;
+
+    let step: DunningStep = sqlx::query_as(&format!(
+        "WITH s AS ( \
+             INSERT INTO dunning_steps (tenant_id, days_past_due, send_reminder, late_fee_cents, late_fee_ppm, message, is_active) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (tenant_id, days_past_due) DO NOTHING RETURNING * \
+         ) SELECT {} FROM s",
+        STEP_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(payload.days_past_due)
+    .bind(payload.send_reminder.unwrap_or(true))
+    .bind(late_fee_cents)
+    .bind(late_fee_ppm)
+    .bind(payload.message.as_deref().and_then(clean_message))
+    .bind(payload.is_active.unwrap_or(true))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| step_conflict(payload.days_past_due))?;
+
+    Ok((StatusCode::CREATED, Json(step)))
+}
+
+pub async fn update_dunning_step(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateDunningStepRequest>,
+) -> AppResult<Json<DunningStep>> {
+    require_role(&claims, "admin")?;
+    let current: DunningStep = sqlx::query_as(&format!(
+        "SELECT {} FROM dunning_steps WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        STEP_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Dunning step not found".to_string()))?;
+
+    let days_past_due = payload.days_past_due.unwrap_or(current.days_past_due);
+    let late_fee_cents = payload.late_fee_cents.unwrap_or(current.late_fee_cents);
+    check_step(days_past_due, late_fee_cents)?;
+    let late_fee_ppm = match payload.late_fee_percent {
+        Some(percent) => fee_ppm(percent)?,
+        None => current.late_fee_ppm,
+    };
+    let message = match payload.message.as_deref() {
+        Some(message) => clean_message(message),
+        None => current.message,
+    };
+
+    let taken: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM dunning_steps WHERE tenant_id = $1 AND days_past_due = $2 AND id <> $3)",
+    )
+    .bind(claims.tid)
+    .bind(days_past_due)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if taken {
+        return Err(step_conflict(days_past_due));
+    }
+
+    let step: DunningStep = sqlx::query_as(&format!(
+        "WITH s AS ( \
+             UPDATE dunning_steps SET days_past_due = $3, send_reminder = $4, late_fee_cents = $5, \
+                 late_fee_ppm = $6, message = $7, is_active = $8, updated_at = NOW() \
+             WHERE id = $1 AND tenant_id = $2 RETURNING * \
+         ) SELECT {} FROM s",
+        STEP_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(days_past_due)
+    .bind(payload.send_reminder.unwrap_or(current.send_reminder))
+    .bind(late_fee_cents)
+    .bind(late_fee_ppm)
+    .bind(message)
+    .bind(payload.is_active.unwrap_or(current.is_active))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok(Json(step))
+}
+
+/// Delete a step. Invoices it already reached keep their fees, and their
+/// events lose the link to the step.
+pub async fn delete_dunning_step(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_role(&claims, "admin")?;
+    let result = sqlx::query("DELETE FROM dunning_steps WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(claims.tid)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Dunning step not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The dunning steps that have reached an invoice, oldest first.
+pub async fn list_invoice_dunning(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(invoice_id): Path<Uuid>,
+) -> AppResult<Json<Vec<DunningEvent>>> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM invoices WHERE id = $1 AND tenant_id = $2)",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists {
+        return Err(AppError::NotFound("Invoice not found".to_string()));
+    }
+
+    let events: Vec<DunningEvent> = sqlx::query_as(
+        "SELECT id, invoice_id, dunning_step_id, days_past_due, skipped, late_fee_cents, email_outbox_id, created_at \
+         FROM invoice_dunning_events WHERE invoice_id = $1 AND tenant_id = $2 \
+         ORDER BY created_at, days_past_due",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_late_fee_adds_flat_and_share_of_balance() {
+        assert_eq!(late_fee(100_000, 0, 0), 0);
+        assert_eq!(late_fee(100_000, 2_500, 0), 2_500);
+        // 1.5% of $1,000.00
+        assert_eq!(late_fee(100_000, 0, 15_000), 1_500);
+        // 1.5% of $0.33 is 0.495 cents and of $0.34 is 0.51 cents.
+        assert_eq!(late_fee(33, 0, 15_000), 0);
+        assert_eq!(late_fee(34, 2_500, 15_000), 2_501);
+        assert_eq!(late_fee(-500, 100, 15_000), 100);
+    }
+
+    #[test]
+    fn test_fee_ppm_precision() {
+        assert_eq!(fee_ppm(1.5).unwrap(), 15_000);
+        assert_eq!(fee_ppm(0.0125).unwrap(), 125);
+        assert!(fee_ppm(0.00001).is_err());
+        assert!(fee_ppm(101.0).is_err());
+        assert!(fee_ppm(f64::NAN).is_err());
+    }
+}
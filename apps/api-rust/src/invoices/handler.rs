@@ -8,13 +8,22 @@ use uuid::Uuid;
 use validator::Validate;
 
 use crate::auth::jwt::Claims;
+use crate::automations::events::{self, DomainEvent};
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
 use crate::invoices::model::*;
+use crate::invoices::status::InvoiceStatus;
+use crate::invoices::{billing, pdf, recurring};
+use crate::mailer::{messages, outbox};
+use crate::middleware::tenant::TenantTx;
+use crate::money::{self, Currency};
+use crate::payments::ledger;
+use crate::storage;
+use crate::taxes::{self, engine::TaxableLine, model::InvoiceTax};
 use crate::AppState;
 
 pub async fn list_invoices(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListInvoicesQuery>,
 ) -> AppResult<Json<PaginatedResponse<Invoice>>> {
@@ -87,7 +96,7 @@ pub async fn list_invoices(
     if let Some(client_id) = params.client_id {
         count_q = count_q.bind(client_id);
     }
-    let (total,) = count_q.fetch_one(&state.db).await?;
+    let (total,) = count_q.fetch_one(&mut *tx).await?;
 
     // Build data query
     let mut data_q = sqlx::query_as::<_, Invoice>(&data_sql).bind(claims.tid);
@@ -103,7 +112,7 @@ pub async fn list_invoices(
     let invoices = data_q
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -120,7 +129,7 @@ pub async fn list_invoices(
 }
 
 pub async fn get_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
 ) -> AppResult<Json<Invoice>> {
@@ -129,15 +138,84 @@ pub async fn get_invoice(
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
 
     Ok(Json(invoice))
 }
 
+/// Next sequential `INV-NNNNN` number for a tenant. Takes a per-tenant
+/// advisory lock held until the caller's transaction ends, so concurrent
+/// issuers never read the same MAX.
+pub async fn next_invoice_number(
+    conn: &mut sqlx::PgConnection,
+    tenant_id: Uuid,
+) -> Result<String, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('invoice_number:' || $1::text))")
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let (next_num,): (i64,) = sqlx::query_as(
+        "SELECT COALESCE(MAX(CAST(SUBSTRING(invoice_number FROM 5) AS BIGINT)), 0) + 1 \
+         FROM invoices WHERE tenant_id = $1 AND invoice_number LIKE 'INV-%'",
+    )
+    .bind(tenant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(format!("INV-{:05}", next_num))
+}
+
+/// Replace the invoice's line items with `items`, linking any time entries
+/// they bill. Returns the new subtotal.
+async fn insert_line_items(
+    conn: &mut sqlx::PgConnection,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+    items: &[CreateLineItemRequest],
+) -> AppResult<i64> {
+    sqlx::query("DELETE FROM invoice_line_items WHERE invoice_id = $1 AND tenant_id = $2")
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut subtotal_cents = 0;
+    for (i, li) in items.iter().enumerate() {
+        let line_total = line_total_cents(li.quantity, li.unit_price_cents);
+        subtotal_cents += line_total;
+        sqlx::query(
+            "INSERT INTO invoice_line_items (tenant_id, invoice_id, description, quantity, unit_price_cents, total_cents, time_entry_id, service_type, sort_order) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(tenant_id)
+        .bind(invoice_id)
+        .bind(&li.description)
+        .bind(li.quantity)
+        .bind(li.unit_price_cents)
+        .bind(line_total)
+        .bind(li.time_entry_id)
+        .bind(li.service_type.as_deref())
+        .bind(i as i32)
+        .execute(&mut *conn)
+        .await?;
+
+        // Mark time entry as invoiced if linked
+        if let Some(te_id) = li.time_entry_id {
+            sqlx::query("UPDATE time_entries SET invoice_id = $1 WHERE id = $2 AND tenant_id = $3")
+                .bind(invoice_id)
+                .bind(te_id)
+                .bind(tenant_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    Ok(subtotal_cents)
+}
+
 pub async fn create_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateInvoiceRequest>,
 ) -> AppResult<(StatusCode, Json<Invoice>)> {
@@ -150,187 +228,259 @@ pub async fn create_invoice(
             "At least one line item is required".to_string(),
         ));
     }
+    let currency = payload
+        .currency
+        .as_deref()
+        .map(Currency::parse)
+        .transpose()?;
 
     let id = Uuid::new_v4();
 
-    // Generate invoice number using MAX to avoid race conditions
-    let (next_num,): (i64,) = sqlx::query_as(
-        "SELECT COALESCE(MAX(CAST(SUBSTRING(invoice_number FROM 5) AS BIGINT)), 0) + 1 \
-         FROM invoices WHERE tenant_id = $1 AND invoice_number LIKE 'INV-%'",
+    let invoice_number = next_invoice_number(&mut tx, claims.tid).await?;
+
+    // Totals are filled in once the line items exist.
+    let mut invoice: Invoice = sqlx::query_as(
+        "INSERT INTO invoices (id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, due_date, notes, created_by, currency) VALUES ($1, $2, $3, $4, 'draft', 0, 0, 0, $5, $6, $7, COALESCE($8, (SELECT base_currency FROM tenants WHERE id = $2))) RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at",
     )
+    .bind(id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .bind(payload.client_id)
+    .bind(&invoice_number)
+    .bind(payload.due_date)
+    .bind(payload.notes.as_deref())
+    .bind(claims.sub)
+    .bind(currency.map(|c| c.code))
+    .fetch_one(&mut *tx)
     .await?;
 
-    let invoice_number = format!("INV-{:05}", next_num);
+    invoice.subtotal_cents =
+        insert_line_items(&mut tx, claims.tid, id, &payload.line_items).await?;
+    sqlx::query(
+        "UPDATE invoices SET subtotal_cents = $3, total_cents = $3 WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .bind(invoice.subtotal_cents)
+    .execute(&mut *tx)
+    .await?;
+    invoice.tax_cents = taxes::engine::apply_to_invoice(&mut tx, claims.tid, id).await?;
+    invoice.total_cents = invoice.subtotal_cents + invoice.tax_cents;
 
-    // Calculate totals
-    let subtotal_cents: i64 = payload
-        .line_items
-        .iter()
-        .map(|li| (li.quantity * li.unit_price_cents as f64) as i64)
-        .sum();
-    let tax_cents: i64 = 0; // Tax calculation deferred to tax engine
-    let total_cents = subtotal_cents + tax_cents;
+    Ok((StatusCode::CREATED, Json(invoice)))
+}
 
-    let invoice: Invoice = sqlx::query_as(
-        "INSERT INTO invoices (id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, due_date, notes, created_by) VALUES ($1, $2, $3, $4, 'draft', $5, $6, $7, $8, $9, $10) RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at",
+/// Edit a draft invoice and recompute its tax with the current rates.
+pub async fn update_invoice(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(invoice_id): Path<Uuid>,
+    Json(payload): Json<UpdateInvoiceRequest>,
+) -> AppResult<Json<Invoice>> {
+    payload
+        .validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    if payload.line_items.as_ref().is_some_and(|l| l.is_empty()) {
+        return Err(AppError::Validation(
+            "At least one line item is required".to_string(),
+        ));
+    }
+
+    let draft: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM invoices WHERE id = $1 AND tenant_id = $2 AND status = 'draft' AND deleted_at IS NULL FOR UPDATE",
     )
-    .bind(id)
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if draft.is_none() {
+        return Err(AppError::NotFound(
+            "Invoice not found or not in draft status".to_string(),
+        ));
+    }
+
+    let subtotal_cents = match &payload.line_items {
+        Some(items) => Some(insert_line_items(&mut tx, claims.tid, invoice_id, items).await?),
+        None => None,
+    };
+    sqlx::query(
+        "UPDATE invoices SET subtotal_cents = COALESCE($3, subtotal_cents), due_date = COALESCE($4, due_date), notes = COALESCE($5, notes), updated_at = NOW() WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
     .bind(claims.tid)
-    .bind(payload.client_id)
-    .bind(&invoice_number)
     .bind(subtotal_cents)
-    .bind(tax_cents)
-    .bind(total_cents)
     .bind(payload.due_date)
     .bind(payload.notes.as_deref())
-    .bind(claims.sub)
-    .fetch_one(&state.db)
+    .execute(&mut *tx)
     .await?;
+    taxes::engine::apply_to_invoice(&mut tx, claims.tid, invoice_id).await?;
 
-    // Insert line items
-    for (i, li) in payload.line_items.iter().enumerate() {
-        let line_total = (li.quantity * li.unit_price_cents as f64) as i64;
-        sqlx::query(
-            "INSERT INTO invoice_line_items (tenant_id, invoice_id, description, quantity, unit_price_cents, total_cents, time_entry_id, sort_order) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
-        )
-        .bind(claims.tid)
-        .bind(id)
-        .bind(&li.description)
-        .bind(li.quantity)
-        .bind(li.unit_price_cents)
-        .bind(line_total)
-        .bind(li.time_entry_id)
-        .bind(i as i32)
-        .execute(&state.db)
-        .await?;
+    let invoice: Invoice = sqlx::query_as(
+        "SELECT id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at FROM invoices WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_one(&mut *tx)
+    .await?;
 
-        // Mark time entry as invoiced if linked
-        if let Some(te_id) = li.time_entry_id {
-            sqlx::query("UPDATE time_entries SET invoice_id = $1 WHERE id = $2 AND tenant_id = $3")
-                .bind(id)
-                .bind(te_id)
-                .bind(claims.tid)
-                .execute(&state.db)
-                .await?;
-        }
+    Ok(Json(invoice))
+}
+
+/// The invoice's tax, rate by rate.
+pub async fn get_invoice_taxes(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(invoice_id): Path<Uuid>,
+) -> AppResult<Json<Vec<InvoiceTax>>> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM invoices WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL)",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists {
+        return Err(AppError::NotFound("Invoice not found".to_string()));
     }
 
-    Ok((StatusCode::CREATED, Json(invoice)))
+    let taxes: Vec<InvoiceTax> = sqlx::query_as(
+        "SELECT tax_rate_id, name, jurisdiction, rate_ppm, is_compound, taxable_cents, tax_cents \
+         FROM invoice_taxes WHERE invoice_id = $1 AND tenant_id = $2 ORDER BY sort_order",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(taxes))
 }
 
+/// Move an invoice along its lifecycle by hand (see [`InvoiceStatus`]).
+/// Voiding it takes back any payments and credit applied, leaving them as
+/// the client's credit.
 pub async fn update_invoice_status(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
     Json(payload): Json<UpdateInvoiceStatusRequest>,
 ) -> AppResult<Json<Invoice>> {
-    // Fetch current status
     let (current_status,): (String,) =
-        sqlx::query_as("SELECT status FROM invoices WHERE id = $1 AND tenant_id = $2")
+        sqlx::query_as("SELECT status FROM invoices WHERE id = $1 AND tenant_id = $2 FOR UPDATE")
             .bind(invoice_id)
             .bind(claims.tid)
-            .fetch_optional(&state.db)
+            .fetch_optional(&mut *tx)
             .await?
             .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    let current = InvoiceStatus::parse(&current_status).ok_or_else(|| {
+        AppError::Internal(format!("Unknown invoice status '{}'", current_status))
+    })?;
+
+    let target = InvoiceStatus::parse(&payload.status)
+        .filter(|target| current.can_transition_to(*target))
+        .ok_or_else(|| {
+            let allowed = current.manual_transitions();
+            AppError::Validation(format!(
+                "Cannot transition invoice from '{}' to '{}'. Allowed transitions: {}",
+                current,
+                payload.status,
+                if allowed.is_empty() {
+                    "none".to_string()
+                } else {
+                    allowed
+                        .iter()
+                        .map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            ))
+        })?;
 
-    // Validate state transitions
-    let valid_transitions: &[(&str, &[&str])] = &[
-        ("draft", &["sent", "cancelled"]),
-        ("sent", &["viewed", "paid", "overdue", "cancelled"]),
-        ("viewed", &["paid", "overdue", "cancelled"]),
-        ("overdue", &["paid", "cancelled", "sent"]),
-        ("cancelled", &["draft"]),
-        ("void", &[]),
-    ];
-
-    let allowed = valid_transitions
-        .iter()
-        .find(|(from, _)| *from == current_status.as_str())
-        .map(|(_, to)| to.contains(&payload.status.as_str()))
-        .unwrap_or(false);
-
-    if !allowed {
-        return Err(AppError::Validation(format!(
-            "Cannot transition invoice from '{}' to '{}'. Allowed transitions: {}",
-            current_status,
-            payload.status,
-            valid_transitions
-                .iter()
-                .find(|(from, _)| *from == current_status.as_str())
-                .map(|(_, to)| to.join(", "))
-                .unwrap_or_else(|| "none".to_string())
-        )));
+    // A void invoice bills nothing: its payments go back to credit and its
+    // work can go on another invoice.
+    if target == InvoiceStatus::Void {
+        ledger::reverse_allocations(&mut tx, claims.tid, invoice_id, Some(claims.sub)).await?;
+        billing::release(&mut tx, claims.tid, invoice_id).await?;
     }
 
-    let paid_date = if payload.status == "paid" {
-        Some(chrono::Utc::now().date_naive())
-    } else {
-        None
-    };
-
-    let issued_date = if payload.status == "sent" {
-        Some(chrono::Utc::now().date_naive())
-    } else {
-        None
-    };
-
     let invoice: Invoice = sqlx::query_as(
-        "UPDATE invoices SET status = $3, paid_date = COALESCE($4, paid_date), issued_date = COALESCE($5, issued_date), updated_at = NOW() WHERE id = $1 AND tenant_id = $2 RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at",
+        "UPDATE invoices SET status = $3, \
+         issued_date = CASE WHEN $3 = 'sent' THEN CURRENT_DATE ELSE issued_date END, \
+         viewed_at = CASE WHEN $3 = 'viewed' THEN COALESCE(viewed_at, NOW()) ELSE viewed_at END, \
+         updated_at = NOW() WHERE id = $1 AND tenant_id = $2 \
+         RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, \
+         amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, \
+         stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at",
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .bind(&payload.status)
-    .bind(paid_date)
-    .bind(issued_date)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    .bind(target.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
 
     Ok(Json(invoice))
 }
 
 pub async fn delete_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
+    let status: String = sqlx::query_scalar(
+        "SELECT status FROM invoices WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+    )
+    .bind(invoice_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    // Issued invoices are part of the ledger and numbering sequence.
+    if status != InvoiceStatus::Draft.as_str() {
+        return Err(AppError::Conflict(format!(
+            "Cannot delete a '{}' invoice; void it instead",
+            status
+        )));
+    }
+
     // Delete line items first
     sqlx::query("DELETE FROM invoice_line_items WHERE invoice_id = $1 AND tenant_id = $2")
         .bind(invoice_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
-    // Unlink time entries
-    sqlx::query(
-        "UPDATE time_entries SET invoice_id = NULL WHERE invoice_id = $1 AND tenant_id = $2",
-    )
-    .bind(invoice_id)
-    .bind(claims.tid)
-    .execute(&state.db)
-    .await?;
+    // Unlink time entries and expenses
+    billing::release(&mut tx, claims.tid, invoice_id).await?;
 
-    let result = sqlx::query("DELETE FROM invoices WHERE id = $1 AND tenant_id = $2")
+    sqlx::query("DELETE FROM invoices WHERE id = $1 AND tenant_id = $2")
         .bind(invoice_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Invoice not found".to_string()));
-    }
-
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn send_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
 ) -> AppResult<Json<Invoice>> {
-    let invoice: Invoice = sqlx::query_as(
+    let invoice = send_draft(&mut tx, &claims, invoice_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound("Invoice not found or not in draft status".to_string())
+        })?;
+
+    Ok(Json(invoice))
+}
+
+/// Mark a draft invoice sent and queue the email to the client. Returns
+/// `None` when the invoice does not exist or is not a draft.
+async fn send_draft(
+    conn: &mut sqlx::PgConnection,
+    claims: &Claims,
+    invoice_id: Uuid,
+) -> AppResult<Option<Invoice>> {
+    let invoice: Option<Invoice> = sqlx::query_as(
         "UPDATE invoices SET status = 'sent', sent_at = NOW(), issued_date = COALESCE(issued_date, CURRENT_DATE), updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 AND status = 'draft' \
          RETURNING id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, \
@@ -339,74 +489,130 @@ pub async fn send_invoice(
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Invoice not found or not in draft status".to_string()))?;
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(invoice) = invoice else {
+        return Ok(None);
+    };
+
+    let recipient: Option<(Option<String>, String, String)> = sqlx::query_as(
+        "SELECT c.email, t.name, t.locale FROM clients c JOIN tenants t ON t.id = c.tenant_id \
+         WHERE c.id = $1 AND c.tenant_id = $2",
+    )
+    .bind(invoice.client_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *conn)
+    .await?;
 
-    // In production: send email via Resend here
-    // For now, just update status and return
+    match recipient {
+        Some((Some(email), firm_name, locale)) if !email.trim().is_empty() => {
+            let due = invoice.due_date.map(|d| d.format("%B %d, %Y").to_string());
+            outbox::enqueue(
+                &mut *conn,
+                claims.tid,
+                &messages::invoice_sent(
+                    email.trim(),
+                    &firm_name,
+                    &invoice.invoice_number,
+                    &money::format(
+                        invoice.total_cents - invoice.amount_paid_cents,
+                        &invoice.currency,
+                        &locale,
+                    ),
+                    due.as_deref(),
+                ),
+                outbox::EnqueueOptions {
+                    category: Some("invoice"),
+                    created_by: Some(claims.sub),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+        _ => tracing::warn!(
+            "Invoice {} sent without email: client has no email address",
+            invoice.id
+        ),
+    }
 
-    Ok(Json(invoice))
+    Ok(Some(invoice))
 }
 
+/// Record a payment against an invoice. Anything over the balance stays
+/// with the client as credit for later invoices.
 pub async fn record_payment(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
     Json(payload): Json<RecordPaymentRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    // Verify invoice exists and is payable
+    if payload.amount_cents <= 0 {
+        return Err(AppError::Validation(
+            "amount_cents must be positive".to_string(),
+        ));
+    }
+    ledger::validate_method(&payload.method)?;
+
     let invoice: Invoice = sqlx::query_as(
         "SELECT id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, \
          amount_paid_cents, currency, due_date, issued_date, paid_date, notes, stripe_payment_intent_id, \
          stripe_invoice_id, pdf_s3_key, created_by, created_at, updated_at \
-         FROM invoices WHERE id = $1 AND tenant_id = $2 AND status IN ('sent', 'viewed', 'overdue')"
+         FROM invoices WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL \
+         AND status IN ('sent', 'viewed', 'partial', 'overdue') FOR UPDATE",
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Invoice not found or not payable".to_string()))?;
 
-    // Record payment in payments table
-    let payment_id = Uuid::new_v4();
-    sqlx::query(
-        "INSERT INTO payments (id, tenant_id, invoice_id, amount_cents, method, stripe_payment_id, notes) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    let payment_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO payments (tenant_id, invoice_id, client_id, currency, amount_cents, method, stripe_payment_id, notes, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
     )
-    .bind(payment_id)
     .bind(claims.tid)
     .bind(invoice_id)
+    .bind(invoice.client_id)
+    .bind(&invoice.currency)
     .bind(payload.amount_cents)
     .bind(&payload.method)
     .bind(payload.stripe_payment_id.as_deref())
     .bind(payload.notes.as_deref())
-    .execute(&state.db)
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
     .await?;
 
-    // Update invoice amount_paid and status
-    let new_paid = invoice.amount_paid_cents + payload.amount_cents;
-    let new_status = if new_paid >= invoice.total_cents {
-        "paid"
-    } else {
-        &invoice.status
-    };
-    let paid_date = if new_paid >= invoice.total_cents {
-        Some(chrono::Utc::now().date_naive())
+    let applied = payload
+        .amount_cents
+        .min(invoice.total_cents - invoice.amount_paid_cents);
+    let new_status = if applied > 0 {
+        ledger::allocate(
+            &mut tx,
+            claims.tid,
+            ledger::Source::Payment(payment_id),
+            invoice_id,
+            applied,
+            Some(claims.sub),
+        )
+        .await?
+        .status
     } else {
-        invoice.paid_date
+        InvoiceStatus::parse(&invoice.status).unwrap_or(InvoiceStatus::Sent)
     };
 
-    sqlx::query(
-        "UPDATE invoices SET amount_paid_cents = $3, status = $4, paid_date = $5, updated_at = NOW() \
-         WHERE id = $1 AND tenant_id = $2"
+    events::emit(
+        &mut *tx,
+        claims.tid,
+        claims.sub,
+        DomainEvent::InvoicePaymentRecorded {
+            invoice_id,
+            client_id: invoice.client_id,
+            payment_id,
+            amount_cents: payload.amount_cents,
+            method: payload.method.clone(),
+            invoice_status: new_status.to_string(),
+        },
     )
-    .bind(invoice_id)
-    .bind(claims.tid)
-    .bind(new_paid)
-    .bind(new_status)
-    .bind(paid_date)
-    .execute(&state.db)
     .await?;
 
     Ok((
@@ -416,8 +622,9 @@ pub async fn record_payment(
             "invoice_id": invoice_id,
             "amount_cents": payload.amount_cents,
             "method": payload.method,
-            "new_total_paid_cents": new_paid,
-            "invoice_status": new_status,
+            "new_total_paid_cents": invoice.amount_paid_cents + applied,
+            "credit_cents": payload.amount_cents - applied,
+            "invoice_status": new_status.as_str(),
         })),
     ))
 }
@@ -425,7 +632,7 @@ pub async fn record_payment(
 // ── Bulk Operations ──────────────────────────────────────────────────
 
 pub async fn bulk_send_invoices(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkInvoiceIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -440,23 +647,21 @@ pub async fn bulk_send_invoices(
         ));
     }
 
-    let result = sqlx::query(
-        "UPDATE invoices SET status = 'sent', sent_at = NOW(), issued_date = COALESCE(issued_date, CURRENT_DATE), updated_at = NOW() \
-         WHERE tenant_id = $1 AND status = 'draft' AND id = ANY($2)",
-    )
-    .bind(claims.tid)
-    .bind(&payload.ids)
-    .execute(&state.db)
-    .await?;
+    let mut sent = 0;
+    for &invoice_id in &payload.ids {
+        if send_draft(&mut tx, &claims, invoice_id).await?.is_some() {
+            sent += 1;
+        }
+    }
 
     Ok(Json(serde_json::json!({
-        "sent": result.rows_affected(),
+        "sent": sent,
         "requested": payload.ids.len(),
     })))
 }
 
 pub async fn bulk_delete_invoices(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkInvoiceIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -478,7 +683,7 @@ pub async fn bulk_delete_invoices(
     )
     .bind(claims.tid)
     .bind(&payload.ids)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -490,7 +695,7 @@ pub async fn bulk_delete_invoices(
 // ── Soft Delete: Restore & Trash ─────────────────────────────────────
 
 pub async fn restore_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
 ) -> AppResult<Json<Invoice>> {
@@ -503,7 +708,7 @@ pub async fn restore_invoice(
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Invoice not found in trash".to_string()))?;
 
@@ -511,7 +716,7 @@ pub async fn restore_invoice(
 }
 
 pub async fn list_invoices_trash(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListInvoicesQuery>,
 ) -> AppResult<Json<PaginatedResponse<Invoice>>> {
@@ -523,7 +728,7 @@ pub async fn list_invoices_trash(
         "SELECT COUNT(*) FROM invoices WHERE tenant_id = $1 AND deleted_at IS NOT NULL",
     )
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let invoices: Vec<Invoice> = sqlx::query_as(
@@ -536,7 +741,7 @@ pub async fn list_invoices_trash(
     .bind(claims.tid)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -554,8 +759,10 @@ pub async fn list_invoices_trash(
 
 // ── Recurring Invoices ───────────────────────────────────────────────
 
+const RECURRING_COLS: &str = "id, tenant_id, client_id, schedule, anchor_date, next_issue_date, payment_terms_days, notes, line_items, subtotal_cents, tax_cents, total_cents, currency, is_active, last_issued_at, invoices_generated, created_by, created_at, updated_at";
+
 pub async fn create_recurring_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateRecurringInvoiceRequest>,
 ) -> AppResult<(StatusCode, Json<RecurringInvoice>)> {
@@ -563,12 +770,11 @@ pub async fn create_recurring_invoice(
         .validate()
         .map_err(|e| AppError::Validation(e.to_string()))?;
 
-    let valid_schedules = ["weekly", "monthly", "quarterly", "annually"];
-    if !valid_schedules.contains(&payload.schedule.as_str()) {
+    if !recurring::SCHEDULES.contains(&payload.schedule.as_str()) {
         return Err(AppError::Validation(format!(
             "Invalid schedule '{}'. Must be one of: {}",
             payload.schedule,
-            valid_schedules.join(", ")
+            recurring::SCHEDULES.join(", ")
         )));
     }
 
@@ -577,43 +783,66 @@ pub async fn create_recurring_invoice(
             "At least one line item is required".to_string(),
         ));
     }
+    let currency = payload
+        .currency
+        .as_deref()
+        .map(Currency::parse)
+        .transpose()?;
 
-    let subtotal_cents: i64 = payload
+    let amounts: Vec<i64> = payload
         .line_items
         .iter()
-        .map(|li| (li.quantity * li.unit_price_cents as f64) as i64)
-        .sum();
-    let tax_cents: i64 = 0;
+        .map(|li| line_total_cents(li.quantity, li.unit_price_cents))
+        .collect();
+    let subtotal_cents: i64 = amounts.iter().sum();
+    // An estimate at today's rates; each issued invoice is taxed when issued.
+    let lines: Vec<TaxableLine> = payload
+        .line_items
+        .iter()
+        .zip(&amounts)
+        .map(|(li, amount_cents)| TaxableLine {
+            amount_cents: *amount_cents,
+            service_type: li.service_type.as_deref(),
+        })
+        .collect();
+    let tax_cents = taxes::engine::estimate(&mut tx, claims.tid, payload.client_id, &lines).await?;
     let total_cents = subtotal_cents + tax_cents;
 
     let line_items_json = serde_json::to_value(&payload.line_items)
         .map_err(|e| AppError::Internal(format!("Failed to serialize line items: {}", e)))?;
 
+    let first_issue_date = payload
+        .next_issue_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
     let id = Uuid::new_v4();
-    let recurring: RecurringInvoice = sqlx::query_as(
-        "INSERT INTO recurring_invoices (id, tenant_id, client_id, schedule, next_issue_date, notes, line_items, subtotal_cents, tax_cents, total_cents, created_by) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) \
-         RETURNING id, tenant_id, client_id, schedule, next_issue_date, notes, line_items, subtotal_cents, tax_cents, total_cents, currency, is_active, last_issued_at, invoices_generated, created_by, created_at, updated_at",
-    )
+    let recurring: RecurringInvoice = sqlx::query_as(&format!(
+        "INSERT INTO recurring_invoices (id, tenant_id, client_id, schedule, anchor_date, next_issue_date, payment_terms_days, notes, line_items, subtotal_cents, tax_cents, total_cents, created_by, currency) \
+         VALUES ($1, $2, $3, $4, $5, $5, $6, $7, $8, $9, $10, $11, $12, COALESCE($13, (SELECT base_currency FROM tenants WHERE id = $2))) \
+         RETURNING {}",
+        RECURRING_COLS
+    ))
     .bind(id)
     .bind(claims.tid)
     .bind(payload.client_id)
     .bind(&payload.schedule)
-    .bind(payload.next_issue_date)
+    .bind(first_issue_date)
+    .bind(payload.payment_terms_days.unwrap_or(30))
     .bind(payload.notes.as_deref())
     .bind(&line_items_json)
     .bind(subtotal_cents)
     .bind(tax_cents)
     .bind(total_cents)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .bind(currency.map(|c| c.code))
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(recurring)))
 }
 
 pub async fn list_recurring_invoices(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListRecurringInvoicesQuery>,
 ) -> AppResult<Json<PaginatedResponse<RecurringInvoice>>> {
@@ -621,22 +850,31 @@ pub async fn list_recurring_invoices(
     let per_page = params.per_page.unwrap_or(25).clamp(1, 100);
     let offset = (page - 1) * per_page;
 
-    let (total,): (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM recurring_invoices WHERE tenant_id = $1 AND deleted_at IS NULL",
-    )
+    let filter = "tenant_id = $1 AND deleted_at IS NULL \
+                  AND ($2::uuid IS NULL OR client_id = $2) \
+                  AND ($3::boolean IS NULL OR is_active = $3)";
+
+    let (total,): (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM recurring_invoices WHERE {}",
+        filter
+    ))
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .bind(params.client_id)
+    .bind(params.is_active)
+    .fetch_one(&mut *tx)
     .await?;
 
-    let recurring: Vec<RecurringInvoice> = sqlx::query_as(
-        "SELECT id, tenant_id, client_id, schedule, next_issue_date, notes, line_items, subtotal_cents, tax_cents, total_cents, currency, is_active, last_issued_at, invoices_generated, created_by, created_at, updated_at \
-         FROM recurring_invoices WHERE tenant_id = $1 AND deleted_at IS NULL \
-         ORDER BY next_issue_date ASC LIMIT $2 OFFSET $3",
-    )
+    let recurring: Vec<RecurringInvoice> = sqlx::query_as(&format!(
+        "SELECT {} FROM recurring_invoices WHERE {} \
+         ORDER BY next_issue_date ASC LIMIT $4 OFFSET $5",
+        RECURRING_COLS, filter
+    ))
     .bind(claims.tid)
+    .bind(params.client_id)
+    .bind(params.is_active)
     .bind(per_page)
     .bind(offset)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -653,7 +891,7 @@ pub async fn list_recurring_invoices(
 }
 
 pub async fn delete_recurring_invoice(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(recurring_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -663,7 +901,7 @@ pub async fn delete_recurring_invoice(
     )
     .bind(recurring_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -675,6 +913,71 @@ pub async fn delete_recurring_invoice(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Stop issuing invoices from a template until it is resumed.
+pub async fn pause_recurring_invoice(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(recurring_id): Path<Uuid>,
+) -> AppResult<Json<RecurringInvoice>> {
+    let recurring: RecurringInvoice = sqlx::query_as(&format!(
+        "UPDATE recurring_invoices SET is_active = FALSE, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL \
+         RETURNING {}",
+        RECURRING_COLS
+    ))
+    .bind(recurring_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Recurring invoice not found".to_string()))?;
+
+    Ok(Json(recurring))
+}
+
+/// Resume a paused template. Periods that fell due while it was paused are
+/// skipped: the next issue date moves to the first scheduled date from today.
+pub async fn resume_recurring_invoice(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(recurring_id): Path<Uuid>,
+) -> AppResult<Json<RecurringInvoice>> {
+    let existing: RecurringInvoice = sqlx::query_as(&format!(
+        "SELECT {} FROM recurring_invoices WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL",
+        RECURRING_COLS
+    ))
+    .bind(recurring_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Recurring invoice not found".to_string()))?;
+
+    if existing.is_active {
+        return Ok(Json(existing));
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let next_issue_date = if existing.next_issue_date < today {
+        recurring::first_on_or_after(existing.anchor_date, &existing.schedule, today)
+            .ok_or_else(|| AppError::Internal("Invalid recurring schedule".to_string()))?
+    } else {
+        existing.next_issue_date
+    };
+
+    let recurring: RecurringInvoice = sqlx::query_as(&format!(
+        "UPDATE recurring_invoices SET is_active = TRUE, next_issue_date = $3, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL \
+         RETURNING {}",
+        RECURRING_COLS
+    ))
+    .bind(recurring_id)
+    .bind(claims.tid)
+    .bind(next_issue_date)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok(Json(recurring))
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct InvoiceLineItem {
     description: String,
@@ -683,10 +986,26 @@ struct InvoiceLineItem {
     total_cents: i64,
 }
 
-/// Generate an HTML invoice and return it as a downloadable HTML file.
-/// In production, pipe this through a headless browser or wkhtmltopdf for real PDF.
+#[derive(Debug, sqlx::FromRow)]
+struct PdfClientRow {
+    name: String,
+    email: Option<String>,
+    phone: Option<String>,
+    address: Option<serde_json::Value>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PdfFirmRow {
+    name: String,
+    settings: serde_json::Value,
+    brand_color: Option<String>,
+    locale: String,
+}
+
+/// Render the invoice as a PDF, store it at `pdf_s3_key` and return it for download.
 pub async fn generate_invoice_pdf(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(invoice_id): Path<Uuid>,
 ) -> Result<Response, AppError> {
@@ -695,157 +1014,142 @@ pub async fn generate_invoice_pdf(
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
 
     let line_items: Vec<InvoiceLineItem> = sqlx::query_as(
-        "SELECT description, quantity, unit_price_cents, total_cents FROM invoice_line_items WHERE invoice_id = $1 AND tenant_id = $2 ORDER BY sort_order",
+        "SELECT description, quantity::FLOAT8 AS quantity, unit_price_cents, total_cents FROM invoice_line_items WHERE invoice_id = $1 AND tenant_id = $2 ORDER BY sort_order",
     )
     .bind(invoice_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
-    // Get client name
-    let client_name: String =
-        sqlx::query_scalar("SELECT name FROM clients WHERE id = $1 AND tenant_id = $2")
-            .bind(invoice.client_id)
-            .bind(claims.tid)
-            .fetch_optional(&state.db)
-            .await?
-            .unwrap_or_else(|| "Unknown Client".to_string());
+    let client: Option<PdfClientRow> = sqlx::query_as(
+        "SELECT name, email, phone, address FROM clients WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice.client_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    // Get firm name
-    let firm_name: String = sqlx::query_scalar("SELECT name FROM tenants WHERE id = $1")
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .unwrap_or_else(|| "CPA Firm".to_string());
+    // Firm branding: tenant settings take precedence over the career page color
+    let firm: Option<PdfFirmRow> = sqlx::query_as(
+        "SELECT t.name, t.settings, COALESCE(t.settings->>'brand_color', cp.primary_color) AS brand_color, t.locale \
+         FROM tenants t LEFT JOIN career_pages cp ON cp.tenant_id = t.id WHERE t.id = $1",
+    )
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    let fmt_cents = |c: i64| -> String { format!("${:.2}", c as f64 / 100.0) };
+    let (firm_name, firm_details, brand_color, locale) = match firm {
+        Some(f) => {
+            let details = ["address", "phone", "email", "website"]
+                .iter()
+                .filter_map(|k| f.settings.get(*k).and_then(|v| v.as_str()))
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| v.to_string())
+                .collect();
+            (f.name, details, f.brand_color, f.locale)
+        }
+        None => (
+            "CPA Firm".to_string(),
+            Vec::new(),
+            None,
+            "en-US".to_string(),
+        ),
+    };
 
-    let html_escape = |s: &str| -> String {
-        s.replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;")
-            .replace('"', "&quot;")
-            .replace('\'', "&#x27;")
+    let (client_name, client_details) = match client {
+        Some(c) => {
+            let mut details: Vec<String> = Vec::new();
+            if let Some(serde_json::Value::Object(addr)) = &c.address {
+                let street = ["line1", "street", "line2"]
+                    .iter()
+                    .filter_map(|k| addr.get(*k).and_then(|v| v.as_str()))
+                    .filter(|v| !v.trim().is_empty())
+                    .map(|v| v.to_string());
+                details.extend(street);
+                let locality: Vec<&str> = ["city", "state", "postal_code", "zip"]
+                    .iter()
+                    .filter_map(|k| addr.get(*k).and_then(|v| v.as_str()))
+                    .filter(|v| !v.trim().is_empty())
+                    .collect();
+                if !locality.is_empty() {
+                    details.push(locality.join(" "));
+                }
+            }
+            details.extend(c.email);
+            details.extend(c.phone);
+            (c.name, details)
+        }
+        None => ("Unknown Client".to_string(), Vec::new()),
     };
 
-    let due_date_str = invoice
-        .due_date
-        .map(|d| d.format("%B %d, %Y").to_string())
-        .unwrap_or_else(|| "N/A".to_string());
-
-    let issued_date_str = invoice
-        .issued_date
-        .map(|d| d.format("%B %d, %Y").to_string())
-        .unwrap_or_else(|| invoice.created_at.format("%B %d, %Y").to_string());
-
-    let mut rows_html = String::new();
-    for li in &line_items {
-        rows_html.push_str(&format!(
-            "<tr><td style='padding:8px;border-bottom:1px solid #eee'>{}</td>\
-             <td style='padding:8px;border-bottom:1px solid #eee;text-align:right'>{:.2}</td>\
-             <td style='padding:8px;border-bottom:1px solid #eee;text-align:right'>{}</td>\
-             <td style='padding:8px;border-bottom:1px solid #eee;text-align:right'>{}</td></tr>",
-            html_escape(&li.description),
-            li.quantity,
-            fmt_cents(li.unit_price_cents),
-            fmt_cents(li.total_cents),
-        ));
-    }
+    let data = pdf::InvoicePdfData {
+        firm_name,
+        firm_details,
+        brand_color: brand_color.unwrap_or_else(|| pdf::DEFAULT_BRAND_COLOR.to_string()),
+        client_name,
+        client_details,
+        invoice_number: invoice.invoice_number.clone(),
+        status: invoice.status.to_uppercase(),
+        issued_date: invoice
+            .issued_date
+            .map(|d| d.format("%B %d, %Y").to_string())
+            .unwrap_or_else(|| invoice.created_at.format("%B %d, %Y").to_string()),
+        due_date: invoice
+            .due_date
+            .map(|d| d.format("%B %d, %Y").to_string())
+            .unwrap_or_else(|| "N/A".to_string()),
+        line_items: line_items
+            .into_iter()
+            .map(|li| pdf::PdfLineItem {
+                description: li.description,
+                quantity: li.quantity,
+                unit_price_cents: li.unit_price_cents,
+                total_cents: li.total_cents,
+            })
+            .collect(),
+        subtotal_cents: invoice.subtotal_cents,
+        tax_cents: invoice.tax_cents,
+        total_cents: invoice.total_cents,
+        amount_paid_cents: invoice.amount_paid_cents,
+        notes: invoice.notes.clone(),
+        currency: invoice.currency.clone(),
+        locale,
+    };
 
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html><head><meta charset="utf-8"><title>Invoice {inv_num}</title>
-<style>body{{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,sans-serif;margin:40px;color:#333}}
-.header{{display:flex;justify-content:space-between;margin-bottom:40px}}
-.firm-name{{font-size:24px;font-weight:700;color:#1a1a1a}}
-.invoice-title{{font-size:28px;font-weight:700;color:#2563eb;text-align:right}}
-.meta{{display:flex;justify-content:space-between;margin-bottom:30px}}
-.meta-block{{line-height:1.6}}
-.meta-label{{font-size:12px;color:#666;text-transform:uppercase;letter-spacing:0.5px}}
-table{{width:100%;border-collapse:collapse;margin-bottom:30px}}
-th{{text-align:left;padding:10px 8px;border-bottom:2px solid #333;font-size:13px;text-transform:uppercase;letter-spacing:0.5px}}
-.totals{{text-align:right;margin-top:20px}}
-.totals td{{padding:4px 8px}}
-.total-row{{font-size:18px;font-weight:700;color:#2563eb}}
-.status{{display:inline-block;padding:4px 12px;border-radius:12px;font-size:12px;font-weight:600;text-transform:uppercase}}
-.status-paid{{background:#dcfce7;color:#166534}}
-.status-sent{{background:#dbeafe;color:#1e40af}}
-.status-draft{{background:#f3f4f6;color:#374151}}
-.status-overdue{{background:#fee2e2;color:#991b1b}}
-.notes{{margin-top:30px;padding:16px;background:#f9fafb;border-radius:8px;font-size:14px}}
-</style></head><body>
-<div class="header">
-  <div class="firm-name">{firm}</div>
-  <div class="invoice-title">INVOICE</div>
-</div>
-<div class="meta">
-  <div class="meta-block">
-    <div class="meta-label">Bill To</div>
-    <div style="font-size:16px;font-weight:600">{client}</div>
-  </div>
-  <div class="meta-block" style="text-align:right">
-    <div><span class="meta-label">Invoice #:</span> {inv_num}</div>
-    <div><span class="meta-label">Issued:</span> {issued}</div>
-    <div><span class="meta-label">Due:</span> {due}</div>
-    <div><span class="meta-label">Status:</span> <span class="status status-{status_lower}">{status}</span></div>
-  </div>
-</div>
-<table>
-  <thead><tr>
-    <th>Description</th>
-    <th style="text-align:right">Qty</th>
-    <th style="text-align:right">Unit Price</th>
-    <th style="text-align:right">Amount</th>
-  </tr></thead>
-  <tbody>{rows}</tbody>
-</table>
-<table class="totals" style="width:300px;margin-left:auto">
-  <tr><td>Subtotal</td><td>{subtotal}</td></tr>
-  <tr><td>Tax</td><td>{tax}</td></tr>
-  <tr class="total-row"><td style="border-top:2px solid #333;padding-top:8px">Total</td><td style="border-top:2px solid #333;padding-top:8px">{total}</td></tr>
-  <tr><td>Amount Paid</td><td>{paid}</td></tr>
-  <tr style="font-weight:600"><td>Balance Due</td><td>{balance}</td></tr>
-</table>
-{notes_section}
-</body></html>"#,
-        firm = html_escape(&firm_name),
-        client = html_escape(&client_name),
-        inv_num = html_escape(&invoice.invoice_number),
-        issued = issued_date_str,
-        due = due_date_str,
-        status = html_escape(&invoice.status),
-        status_lower = html_escape(&invoice.status.to_lowercase()),
-        rows = rows_html,
-        subtotal = fmt_cents(invoice.subtotal_cents),
-        tax = fmt_cents(invoice.tax_cents),
-        total = fmt_cents(invoice.total_cents),
-        paid = fmt_cents(invoice.amount_paid_cents),
-        balance = fmt_cents(invoice.total_cents - invoice.amount_paid_cents),
-        notes_section = invoice
-            .notes
-            .as_ref()
-            .map(|n| format!(
-                "<div class='notes'><strong>Notes:</strong> {}</div>",
-                html_escape(n)
-            ))
-            .unwrap_or_default(),
+    let bytes = pdf::render_invoice(&data);
+
+    // Persist the rendered file; a storage outage should not block the download
+    let s3_key = format!(
+        "tenants/{}/invoices/{}/{}.pdf",
+        claims.tid, invoice.id, invoice.invoice_number
     );
+    match storage::put_object(&state.config, &s3_key, "application/pdf", bytes.clone()).await {
+        Ok(()) => {
+            sqlx::query("UPDATE invoices SET pdf_s3_key = $3 WHERE id = $1 AND tenant_id = $2")
+                .bind(invoice_id)
+                .bind(claims.tid)
+                .bind(&s3_key)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Err(e) => tracing::warn!("Failed to store invoice PDF: {}", e),
+    }
 
     Ok((
         StatusCode::OK,
         [
-            (axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (axum::http::header::CONTENT_TYPE, "application/pdf"),
             (
                 axum::http::header::CONTENT_DISPOSITION,
-                &format!("inline; filename=\"{}.html\"", invoice.invoice_number),
+                &format!("attachment; filename=\"{}.pdf\"", invoice.invoice_number),
             ),
         ],
-        html,
+        bytes,
     )
         .into_response())
 }
@@ -1,2 +1,95 @@
+pub mod billing;
+pub mod credit_notes;
+pub mod dunning;
 pub mod handler;
 pub mod model;
+pub mod pdf;
+pub mod recurring;
+pub mod status;
+
+use axum::{
+    middleware::from_fn,
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+
+use crate::middleware::idempotency::idempotency_check;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/invoices", get(handler::list_invoices))
+        .route("/invoices", post(handler::create_invoice))
+        .route("/invoices/unbilled", get(billing::list_unbilled))
+        .route("/invoices/bill", post(billing::bill_client))
+        .route("/invoices/dunning-steps", get(dunning::list_dunning_steps))
+        .route(
+            "/invoices/dunning-steps",
+            post(dunning::create_dunning_step),
+        )
+        .route(
+            "/invoices/dunning-steps/{id}",
+            put(dunning::update_dunning_step),
+        )
+        .route(
+            "/invoices/dunning-steps/{id}",
+            delete(dunning::delete_dunning_step),
+        )
+        .route("/invoices/recurring", get(handler::list_recurring_invoices))
+        .route(
+            "/invoices/recurring",
+            post(handler::create_recurring_invoice),
+        )
+        .route(
+            "/invoices/recurring/{id}",
+            delete(handler::delete_recurring_invoice),
+        )
+        .route(
+            "/invoices/recurring/{id}/pause",
+            post(handler::pause_recurring_invoice),
+        )
+        .route(
+            "/invoices/recurring/{id}/resume",
+            post(handler::resume_recurring_invoice),
+        )
+        .route("/invoices/{id}", get(handler::get_invoice))
+        .route("/invoices/{id}", put(handler::update_invoice))
+        .route("/invoices/{id}", delete(handler::delete_invoice))
+        .route("/invoices/{id}/taxes", get(handler::get_invoice_taxes))
+        .route("/invoices/{id}/pdf", get(handler::generate_invoice_pdf))
+        .route("/invoices/{id}/dunning", get(dunning::list_invoice_dunning))
+        .route(
+            "/invoices/{id}/apply-credit",
+            post(credit_notes::apply_credit).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/invoices/{id}/status",
+            patch(handler::update_invoice_status),
+        )
+        .route(
+            "/invoices/{id}/send",
+            post(handler::send_invoice).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/invoices/{id}/payment",
+            post(handler::record_payment).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/invoices/bulk-send",
+            post(handler::bulk_send_invoices).layer(from_fn(idempotency_check)),
+        )
+        .route("/invoices/bulk-delete", post(handler::bulk_delete_invoices))
+        .route("/invoices/trash", get(handler::list_invoices_trash))
+        .route("/invoices/{id}/restore", post(handler::restore_invoice))
+        .route("/credit-notes", get(credit_notes::list_credit_notes))
+        .route(
+            "/credit-notes",
+            post(credit_notes::create_credit_note).layer(from_fn(idempotency_check)),
+        )
+        .route("/credit-notes/{id}", get(credit_notes::get_credit_note))
+        .route(
+            "/credit-notes/{id}/void",
+            post(credit_notes::void_credit_note).layer(from_fn(idempotency_check)),
+        )
+}
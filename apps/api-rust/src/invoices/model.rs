@@ -33,6 +33,9 @@ pub struct CreateInvoiceRequest {
     pub due_date: Option<NaiveDate>,
     pub notes: Option<String>,
     pub line_items: Vec<CreateLineItemRequest>,
+    /// ISO 4217 code; defaults to the firm's base currency. Line item
+    /// prices are in its minor units.
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -42,6 +45,25 @@ pub struct CreateLineItemRequest {
     pub quantity: f64,
     pub unit_price_cents: i64,
     pub time_entry_id: Option<Uuid>,
+    /// Lets tax rates exempt the line by service type.
+    #[validate(length(max = 50))]
+    pub service_type: Option<String>,
+}
+
+/// Total for a line, rounded to the nearest cent. Fractional quantities
+/// (e.g. 1.5 hours) rarely land on a whole cent, and truncating would
+/// under-bill every such line.
+pub fn line_total_cents(quantity: f64, unit_price_cents: i64) -> i64 {
+    (quantity * unit_price_cents as f64).round() as i64
+}
+
+/// Edits to a draft invoice. Line items, when given, replace the existing
+/// ones; tax is recomputed either way.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateInvoiceRequest {
+    pub due_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub line_items: Option<Vec<CreateLineItemRequest>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -84,7 +106,9 @@ pub struct RecurringInvoice {
     pub tenant_id: uuid::Uuid,
     pub client_id: uuid::Uuid,
     pub schedule: String,
+    pub anchor_date: chrono::NaiveDate,
     pub next_issue_date: chrono::NaiveDate,
+    pub payment_terms_days: i32,
     pub notes: Option<String>,
     pub line_items: serde_json::Value,
     pub subtotal_cents: i64,
@@ -103,16 +127,33 @@ pub struct RecurringInvoice {
 pub struct CreateRecurringInvoiceRequest {
     pub client_id: uuid::Uuid,
     pub schedule: String,
-    pub next_issue_date: chrono::NaiveDate,
+    /// First issue date; defaults to today.
+    pub next_issue_date: Option<chrono::NaiveDate>,
+    #[validate(range(min = 0, max = 365))]
+    pub payment_terms_days: Option<i32>,
     pub notes: Option<String>,
     pub line_items: Vec<CreateLineItemRequest>,
+    /// ISO 4217 code; defaults to the firm's base currency.
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct ListRecurringInvoicesQuery {
     pub page: Option<i64>,
     pub per_page: Option<i64>,
     pub client_id: Option<uuid::Uuid>,
     pub is_active: Option<bool>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_total_rounds_to_the_nearest_cent() {
+        assert_eq!(line_total_cents(1.0, 25_000), 25_000);
+        assert_eq!(line_total_cents(1.5, 3_333), 5_000);
+        assert_eq!(line_total_cents(0.1, 3), 0);
+        assert_eq!(line_total_cents(2.0 / 3.0, 10_000), 6_667);
+    }
+}
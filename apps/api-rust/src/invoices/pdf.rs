@@ -0,0 +1,732 @@
+//! Native PDF rendering for invoices and plain-text letters.
+//!
+//! Produces a self-contained PDF 1.4 document using the standard Helvetica
+//! fonts, so no external renderer or font files are required. Long invoices
+//! flow onto additional pages with the line-item table header repeated.
+
+use std::fmt::Write as _;
+
+const PAGE_WIDTH: f32 = 612.0; // US Letter
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 50.0;
+const FOOTER_HEIGHT: f32 = 30.0;
+
+const COL_DESC_X: f32 = MARGIN;
+const COL_DESC_WIDTH: f32 = 270.0;
+const COL_QTY_RIGHT: f32 = 390.0;
+const COL_UNIT_RIGHT: f32 = 476.0;
+const COL_AMOUNT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+const BODY_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 13.0;
+
+/// Default brand color used when the firm has not configured one.
+pub const DEFAULT_BRAND_COLOR: &str = "#2563eb";
+
+/// Everything needed to lay out an invoice, already resolved from the database.
+#[derive(Debug, Clone)]
+pub struct InvoicePdfData {
+    pub firm_name: String,
+    pub firm_details: Vec<String>,
+    pub brand_color: String,
+    pub client_name: String,
+    pub client_details: Vec<String>,
+    pub invoice_number: String,
+    pub status: String,
+    pub issued_date: String,
+    pub due_date: String,
+    pub line_items: Vec<PdfLineItem>,
+    pub subtotal_cents: i64,
+    pub tax_cents: i64,
+    pub total_cents: i64,
+    pub amount_paid_cents: i64,
+    pub notes: Option<String>,
+    /// ISO code of the invoice's currency; amounts are in its minor units.
+    pub currency: String,
+    /// The firm's locale, which decides how amounts are written.
+    pub locale: String,
+}
+
+impl InvoicePdfData {
+    fn money(&self, minor: i64) -> String {
+        crate::money::format(minor, &self.currency, &self.locale)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfLineItem {
+    pub description: String,
+    pub quantity: f64,
+    pub unit_price_cents: i64,
+    pub total_cents: i64,
+}
+
+/// Render an invoice to PDF bytes.
+pub fn render_invoice(data: &InvoicePdfData) -> Vec<u8> {
+    let brand = parse_hex_color(&data.brand_color)
+        .or_else(|| parse_hex_color(DEFAULT_BRAND_COLOR))
+        .unwrap_or((0.0, 0.0, 0.0));
+
+    let mut doc = Document::new();
+    let mut y = draw_header(&mut doc, data, brand);
+
+    // Line items table
+    y = draw_table_header(&mut doc, y);
+    for li in &data.line_items {
+        let lines = wrap_text(&li.description, Font::Regular, BODY_SIZE, COL_DESC_WIDTH);
+        let row_height = lines.len() as f32 * LINE_HEIGHT + 8.0;
+        if y - row_height < MARGIN + FOOTER_HEIGHT {
+            doc.new_page();
+            y = draw_table_header(&mut doc, PAGE_HEIGHT - MARGIN);
+        }
+
+        let baseline = y - LINE_HEIGHT + 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            doc.text(
+                COL_DESC_X,
+                baseline - i as f32 * LINE_HEIGHT,
+                Font::Regular,
+                BODY_SIZE,
+                line,
+            );
+        }
+        doc.text_right(
+            COL_QTY_RIGHT,
+            baseline,
+            Font::Regular,
+            BODY_SIZE,
+            &format_quantity(li.quantity),
+        );
+        doc.text_right(
+            COL_UNIT_RIGHT,
+            baseline,
+            Font::Regular,
+            BODY_SIZE,
+            &data.money(li.unit_price_cents),
+        );
+        doc.text_right(
+            COL_AMOUNT_RIGHT,
+            baseline,
+            Font::Regular,
+            BODY_SIZE,
+            &data.money(li.total_cents),
+        );
+
+        y -= row_height;
+        doc.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 0.5, (0.88, 0.88, 0.88));
+    }
+
+    // Totals block — kept together on one page
+    let totals: [(&str, i64, bool); 5] = [
+        ("Subtotal", data.subtotal_cents, false),
+        ("Tax", data.tax_cents, false),
+        ("Total", data.total_cents, true),
+        ("Amount Paid", data.amount_paid_cents, false),
+        (
+            "Balance Due",
+            data.total_cents - data.amount_paid_cents,
+            true,
+        ),
+    ];
+    let totals_height = totals.len() as f32 * 18.0 + 16.0;
+    if y - totals_height < MARGIN + FOOTER_HEIGHT {
+        doc.new_page();
+        y = PAGE_HEIGHT - MARGIN;
+    }
+    y -= 16.0;
+    let label_x = COL_UNIT_RIGHT - 80.0;
+    for (label, cents, emphasize) in totals {
+        let (font, size) = if emphasize {
+            (Font::Bold, 12.0)
+        } else {
+            (Font::Regular, BODY_SIZE)
+        };
+        if label == "Total" {
+            doc.line(
+                label_x,
+                y + 2.0,
+                COL_AMOUNT_RIGHT,
+                y + 2.0,
+                1.0,
+                (0.2, 0.2, 0.2),
+            );
+            y -= 4.0;
+        }
+        y -= 14.0;
+        let color = if label == "Total" {
+            brand
+        } else {
+            (0.2, 0.2, 0.2)
+        };
+        doc.colored_text(label_x, y, font, size, label, color);
+        doc.colored_text_right(COL_AMOUNT_RIGHT, y, font, size, &data.money(cents), color);
+    }
+
+    // Notes
+    if let Some(notes) = data.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        let width = PAGE_WIDTH - 2.0 * MARGIN;
+        let lines: Vec<String> = notes
+            .lines()
+            .flat_map(|l| wrap_text(l, Font::Regular, BODY_SIZE, width))
+            .collect();
+        y -= 28.0;
+        if y - LINE_HEIGHT * 2.0 < MARGIN + FOOTER_HEIGHT {
+            doc.new_page();
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        doc.text(MARGIN, y, Font::Bold, BODY_SIZE, "Notes");
+        for line in lines {
+            y -= LINE_HEIGHT;
+            if y < MARGIN + FOOTER_HEIGHT {
+                doc.new_page();
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            doc.text(MARGIN, y, Font::Regular, BODY_SIZE, &line);
+        }
+    }
+
+    // Footer with page numbers, once the page count is known
+    let page_count = doc.pages.len();
+    for i in 0..page_count {
+        let footer = format!(
+            "Invoice {} - Page {} of {}",
+            data.invoice_number,
+            i + 1,
+            page_count
+        );
+        let width = text_width(&footer, Font::Regular, 8.0);
+        doc.pages[i].push_text(
+            (PAGE_WIDTH - width) / 2.0,
+            MARGIN - 20.0,
+            Font::Regular,
+            8.0,
+            &footer,
+            (0.45, 0.45, 0.45),
+        );
+    }
+
+    doc.finish(&format!("Invoice {}", data.invoice_number))
+}
+
+/// Render a letter: a bold title followed by the body, one paragraph per
+/// line, wrapped to the page and continued onto further pages as needed.
+pub fn render_letter(title: &str, body: &str) -> Vec<u8> {
+    let width = PAGE_WIDTH - 2.0 * MARGIN;
+    let mut doc = Document::new();
+    let mut y = PAGE_HEIGHT - MARGIN - 16.0;
+    for line in wrap_text(title, Font::Bold, 16.0, width) {
+        doc.text(MARGIN, y, Font::Bold, 16.0, &line);
+        y -= 20.0;
+    }
+    y -= LINE_HEIGHT;
+
+    for paragraph in body.lines() {
+        if paragraph.trim().is_empty() {
+            y -= LINE_HEIGHT / 2.0;
+            continue;
+        }
+        for line in wrap_text(paragraph, Font::Regular, BODY_SIZE + 1.0, width) {
+            if y < MARGIN {
+                doc.new_page();
+                y = PAGE_HEIGHT - MARGIN - LINE_HEIGHT;
+            }
+            doc.text(MARGIN, y, Font::Regular, BODY_SIZE + 1.0, &line);
+            y -= LINE_HEIGHT + 1.0;
+        }
+    }
+
+    doc.finish(title)
+}
+
+/// Draw the firm branding band, bill-to block and invoice metadata.
+/// Returns the y coordinate where the line-item table should start.
+fn draw_header(doc: &mut Document, data: &InvoicePdfData, brand: (f32, f32, f32)) -> f32 {
+    doc.rect(0.0, PAGE_HEIGHT - 8.0, PAGE_WIDTH, 8.0, brand);
+
+    let mut y = PAGE_HEIGHT - MARGIN - 6.0;
+    doc.colored_text(MARGIN, y, Font::Bold, 20.0, &data.firm_name, brand);
+    doc.colored_text_right(PAGE_WIDTH - MARGIN, y, Font::Bold, 24.0, "INVOICE", brand);
+
+    for line in &data.firm_details {
+        y -= LINE_HEIGHT;
+        doc.colored_text(MARGIN, y, Font::Regular, 9.0, line, (0.4, 0.4, 0.4));
+    }
+
+    y -= 36.0;
+    let top = y;
+
+    // Bill-to block (left)
+    doc.colored_text(MARGIN, y, Font::Bold, 8.0, "BILL TO", (0.4, 0.4, 0.4));
+    y -= 16.0;
+    doc.text(MARGIN, y, Font::Bold, 12.0, &data.client_name);
+    for line in &data.client_details {
+        y -= LINE_HEIGHT;
+        doc.text(MARGIN, y, Font::Regular, BODY_SIZE, line);
+    }
+
+    // Metadata block (right)
+    let mut meta_y = top;
+    let meta = [
+        ("Invoice #", data.invoice_number.as_str()),
+        ("Issued", data.issued_date.as_str()),
+        ("Due", data.due_date.as_str()),
+        ("Status", data.status.as_str()),
+    ];
+    for (label, value) in meta {
+        doc.colored_text(
+            360.0,
+            meta_y,
+            Font::Bold,
+            8.0,
+            &label.to_uppercase(),
+            (0.4, 0.4, 0.4),
+        );
+        doc.text_right(PAGE_WIDTH - MARGIN, meta_y, Font::Regular, BODY_SIZE, value);
+        meta_y -= 16.0;
+    }
+
+    y.min(meta_y) - 24.0
+}
+
+/// Draw the line-item column headings. Returns the y coordinate below them.
+fn draw_table_header(doc: &mut Document, y: f32) -> f32 {
+    let height = 20.0;
+    doc.rect(
+        MARGIN,
+        y - height,
+        PAGE_WIDTH - 2.0 * MARGIN,
+        height,
+        (0.95, 0.96, 0.97),
+    );
+    let baseline = y - 14.0;
+    doc.text(COL_DESC_X + 4.0, baseline, Font::Bold, 9.0, "DESCRIPTION");
+    doc.text_right(COL_QTY_RIGHT, baseline, Font::Bold, 9.0, "QTY");
+    doc.text_right(COL_UNIT_RIGHT, baseline, Font::Bold, 9.0, "UNIT PRICE");
+    doc.text_right(COL_AMOUNT_RIGHT - 4.0, baseline, Font::Bold, 9.0, "AMOUNT");
+    y - height - 4.0
+}
+
+/// Whole quantities without decimals, fractional ones to two places.
+fn format_quantity(quantity: f64) -> String {
+    if quantity.fract() == 0.0 {
+        format!("{}", quantity as i64)
+    } else {
+        format!("{:.2}", quantity)
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Option<(f32, f32, f32)> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((
+        channel(0)? as f32 / 255.0,
+        channel(2)? as f32 / 255.0,
+        channel(4)? as f32 / 255.0,
+    ))
+}
+
+// ── Fonts ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+/// Glyph widths (1/1000 em) for printable ASCII 32..=126, from the Adobe
+/// Helvetica and Helvetica-Bold AFM metrics.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+fn char_width(ch: char, font: Font) -> u16 {
+    let table = match font {
+        Font::Regular => &HELVETICA_WIDTHS,
+        Font::Bold => &HELVETICA_BOLD_WIDTHS,
+    };
+    match ch as u32 {
+        c @ 32..=126 => table[(c - 32) as usize],
+        0xa0 => 278, // no-break space, as used in amounts
+        _ => 556,
+    }
+}
+
+fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    text.chars()
+        .map(|c| char_width(c, font) as f32)
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+/// Greedy word wrap; words longer than the line are hard-broken.
+fn wrap_text(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if text_width(&candidate, font, size) <= max_width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for ch in word.chars() {
+            current.push(ch);
+            if text_width(&current, font, size) > max_width {
+                current.pop();
+                lines.push(std::mem::take(&mut current));
+                current.push(ch);
+            }
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Encode text as a PDF literal string in WinAnsiEncoding. Characters
+/// outside Latin-1, other than the euro sign, are replaced with '?'.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len() + 2);
+    out.push(b'(');
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(ch as u8);
+            }
+            '\n' | '\r' | '\t' => out.push(b' '),
+            c if (c as u32) < 32 => {}
+            c if (c as u32) < 127 || (160..=255).contains(&(c as u32)) => out.push(c as u8),
+            '€' => out.push(0x80),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+// ── Low-level document writer ────────────────────────────────────────
+
+struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    fn push_text(
+        &mut self,
+        x: f32,
+        y: f32,
+        font: Font,
+        size: f32,
+        text: &str,
+        color: (f32, f32, f32),
+    ) {
+        let mut op = String::new();
+        let _ = write!(
+            op,
+            "BT {:.3} {:.3} {:.3} rg /{} {:.1} Tf {:.2} {:.2} Td ",
+            color.0,
+            color.1,
+            color.2,
+            font.resource(),
+            size,
+            x,
+            y
+        );
+        self.content.extend_from_slice(op.as_bytes());
+        self.content.extend_from_slice(&pdf_string(text));
+        self.content.extend_from_slice(b" Tj ET\n");
+    }
+}
+
+struct Document {
+    pages: Vec<Page>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Self {
+            pages: vec![Page {
+                content: Vec::new(),
+            }],
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Page {
+            content: Vec::new(),
+        });
+    }
+
+    fn current(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("document always has a page")
+    }
+
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        self.colored_text(x, y, font, size, text, (0.1, 0.1, 0.1));
+    }
+
+    fn colored_text(
+        &mut self,
+        x: f32,
+        y: f32,
+        font: Font,
+        size: f32,
+        text: &str,
+        color: (f32, f32, f32),
+    ) {
+        self.current().push_text(x, y, font, size, text, color);
+    }
+
+    fn text_right(&mut self, right: f32, y: f32, font: Font, size: f32, text: &str) {
+        self.colored_text_right(right, y, font, size, text, (0.1, 0.1, 0.1));
+    }
+
+    fn colored_text_right(
+        &mut self,
+        right: f32,
+        y: f32,
+        font: Font,
+        size: f32,
+        text: &str,
+        color: (f32, f32, f32),
+    ) {
+        let x = right - text_width(text, font, size);
+        self.colored_text(x, y, font, size, text, color);
+    }
+
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: (f32, f32, f32)) {
+        let op = format!(
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f\n",
+            color.0, color.1, color.2, x, y, w, h
+        );
+        self.current().content.extend_from_slice(op.as_bytes());
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, color: (f32, f32, f32)) {
+        let op = format!(
+            "{:.3} {:.3} {:.3} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S\n",
+            color.0, color.1, color.2, width, x1, y1, x2, y2
+        );
+        self.current().content.extend_from_slice(op.as_bytes());
+    }
+
+    /// Serialize all pages into a complete PDF file with a valid xref table.
+    fn finish(self, title: &str) -> Vec<u8> {
+        // Object layout: 1 catalog, 2 pages, 3-4 fonts, 5 info, then
+        // (page, content) pairs starting at 6.
+        let page_count = self.pages.len();
+        let mut objects: Vec<Vec<u8>> = Vec::with_capacity(5 + page_count * 2);
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+
+        let kids: Vec<String> = (0..page_count)
+            .map(|i| format!("{} 0 R", 6 + i * 2))
+            .collect();
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                page_count
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+        let mut info = b"<< /Producer (cpa-backend) /Title ".to_vec();
+        info.extend_from_slice(&pdf_string(title));
+        info.extend_from_slice(b" >>");
+        objects.push(info);
+
+        for (i, page) in self.pages.into_iter().enumerate() {
+            let content_id = 7 + i * 2;
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH, PAGE_HEIGHT, content_id
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            stream.extend_from_slice(&page.content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        );
+        out.extend_from_slice(xref.as_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(items: usize) -> InvoicePdfData {
+        InvoicePdfData {
+            firm_name: "Acme CPA".to_string(),
+            firm_details: vec!["1 Main St".to_string()],
+            brand_color: "#123456".to_string(),
+            client_name: "Client (LLC)".to_string(),
+            client_details: vec!["billing@example.com".to_string()],
+            invoice_number: "INV-0001".to_string(),
+            status: "sent".to_string(),
+            issued_date: "January 01, 2026".to_string(),
+            due_date: "January 31, 2026".to_string(),
+            line_items: (0..items)
+                .map(|i| PdfLineItem {
+                    description: format!("Consulting services, phase {}", i),
+                    quantity: 1.5,
+                    unit_price_cents: 10_000,
+                    total_cents: 15_000,
+                })
+                .collect(),
+            subtotal_cents: 15_000 * items as i64,
+            tax_cents: 0,
+            total_cents: 15_000 * items as i64,
+            amount_paid_cents: 0,
+            notes: Some("Thank you for your business".to_string()),
+            currency: "USD".to_string(),
+            locale: "en-US".to_string(),
+        }
+    }
+
+    fn page_count(pdf: &[u8]) -> usize {
+        let text = String::from_utf8_lossy(pdf);
+        text.matches("/Type /Page ").count()
+    }
+
+    #[test]
+    fn test_render_produces_valid_pdf_structure() {
+        let pdf = render_invoice(&sample(3));
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(Client \\(LLC\\))"));
+        assert!(text.contains("($450.00)"));
+
+        // startxref must point at the xref table
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let offset: usize = text[start..].lines().next().unwrap().parse().unwrap();
+        assert_eq!(&pdf[offset..offset + 4], b"xref");
+        assert_eq!(page_count(&pdf), 1);
+    }
+
+    #[test]
+    fn test_long_invoice_breaks_across_pages() {
+        let pdf = render_invoice(&sample(120));
+        let pages = page_count(&pdf);
+        assert!(pages > 1);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(&format!("(Invoice INV-0001 - Page {} of {})", pages, pages)));
+        assert_eq!(text.matches("(DESCRIPTION)").count(), pages);
+    }
+
+    #[test]
+    fn test_letter_wraps_onto_pages() {
+        let body = "Dear Ada,\n\nWe are pleased to offer you the role.\n".repeat(40);
+        let pdf = render_letter("Offer of Employment", &body);
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert!(page_count(&pdf) > 1);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(Offer of Employment)"));
+        assert!(text.contains("(We are pleased to offer you the role.)"));
+    }
+
+    #[test]
+    fn test_amounts_use_invoice_currency_and_locale() {
+        let mut data = sample(1);
+        data.currency = "EUR".to_string();
+        data.locale = "de-DE".to_string();
+        let pdf = render_invoice(&data);
+        // 150,00 € with a no-break space, in WinAnsiEncoding.
+        assert!(pdf.windows(10).any(|w| w == b"(150,00\xa0\x80)"));
+
+        data.currency = "JPY".to_string();
+        data.locale = "ja-JP".to_string();
+        let text = String::from_utf8_lossy(&render_invoice(&data)).into_owned();
+        assert!(!text.contains("$"));
+        assert!(text.contains("15,000)"));
+    }
+
+    #[test]
+    fn test_wrap_text_respects_width() {
+        let lines = wrap_text(
+            "a fairly long description that must wrap onto several lines of output",
+            Font::Regular,
+            BODY_SIZE,
+            100.0,
+        );
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|l| text_width(l, Font::Regular, BODY_SIZE) <= 100.0));
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#ffffff"), Some((1.0, 1.0, 1.0)));
+        assert_eq!(parse_hex_color("000000"), Some((0.0, 0.0, 0.0)));
+        assert_eq!(parse_hex_color("#fff"), None);
+    }
+}
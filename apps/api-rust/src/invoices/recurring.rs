@@ -0,0 +1,291 @@
+//! Issuance of invoices from `recurring_invoices` templates.
+//!
+//! Issue dates are always derived from the template's `anchor_date`, so a
+//! schedule anchored on the 31st issues on the last day of short months and
+//! returns to the 31st afterwards. Each period produces at most one invoice
+//! (enforced by `idx_invoices_recurring_period`), which makes catching up
+//! after downtime and re-running a failed job safe.
+
+use chrono::{Days, Months, NaiveDate};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::handler::next_invoice_number;
+use super::model::line_total_cents;
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::taxes;
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "invoices.issue_recurring";
+
+/// Periods issued per template in one run; anything older is picked up by
+/// the next run rather than holding the template lock indefinitely.
+const MAX_CATCH_UP_PERIODS: usize = 24;
+
+pub const SCHEDULES: [&str; 4] = ["weekly", "monthly", "quarterly", "annually"];
+
+#[derive(Debug, sqlx::FromRow)]
+struct DueTemplate {
+    id: Uuid,
+    client_id: Uuid,
+    schedule: String,
+    anchor_date: NaiveDate,
+    next_issue_date: NaiveDate,
+    payment_terms_days: i32,
+    notes: Option<String>,
+    line_items: serde_json::Value,
+    currency: String,
+    created_by: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct TemplateLineItem {
+    description: String,
+    quantity: f64,
+    unit_price_cents: i64,
+    service_type: Option<String>,
+}
+
+/// The `n`th issue date of a schedule starting at `anchor` (n = 0 is the anchor).
+pub fn period_date(anchor: NaiveDate, schedule: &str, n: u32) -> Option<NaiveDate> {
+    match schedule {
+        "weekly" => anchor.checked_add_days(Days::new(7 * n as u64)),
+        "monthly" => anchor.checked_add_months(Months::new(n)),
+        "quarterly" => anchor.checked_add_months(Months::new(3 * n)),
+        "annually" => anchor.checked_add_months(Months::new(12 * n)),
+        _ => None,
+    }
+}
+
+/// The first issue date of the schedule that is on or after `date`.
+pub fn first_on_or_after(anchor: NaiveDate, schedule: &str, date: NaiveDate) -> Option<NaiveDate> {
+    if date <= anchor {
+        return Some(anchor);
+    }
+    // Start from an estimate just below the answer and walk forward.
+    let days = (date - anchor).num_days() as u32;
+    let mut n = match schedule {
+        "weekly" => days / 7,
+        "monthly" => days / 31,
+        "quarterly" => days / 92,
+        "annually" => days / 366,
+        _ => return None,
+    };
+    loop {
+        let candidate = period_date(anchor, schedule, n)?;
+        if candidate >= date {
+            return Some(candidate);
+        }
+        n += 1;
+    }
+}
+
+/// The issue date following `date` on the schedule.
+pub fn next_after(anchor: NaiveDate, schedule: &str, date: NaiveDate) -> Option<NaiveDate> {
+    first_on_or_after(anchor, schedule, date.succ_opt()?)
+}
+
+/// Issue every period that has come due for each active template, across
+/// all tenants. A failing template is logged and skipped so it cannot block
+/// the others; the job then fails so the runner retries it.
+pub async fn issue_due(state: AppState, _job: Job) -> anyhow::Result<()> {
+    let today = chrono::Utc::now().date_naive();
+    let mut failures = 0;
+
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+        let due: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM recurring_invoices \
+             WHERE tenant_id = $1 AND is_active = TRUE AND deleted_at IS NULL AND next_issue_date <= $2 \
+             ORDER BY next_issue_date",
+        )
+        .bind(tenant_id)
+        .bind(today)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        for recurring_id in due {
+            if let Err(e) = issue_template(&state.db, tenant_id, recurring_id, today).await {
+                failures += 1;
+                tracing::error!(
+                    recurring_id = %recurring_id,
+                    "Failed to issue recurring invoice: {:#}",
+                    e
+                );
+            }
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} recurring invoice template(s) failed", failures);
+    }
+    Ok(())
+}
+
+/// Issue all due periods of one template in a single transaction. Returns the
+/// number of invoices created.
+pub async fn issue_template(
+    db: &sqlx::PgPool,
+    tenant_id: Uuid,
+    recurring_id: Uuid,
+    today: NaiveDate,
+) -> anyhow::Result<usize> {
+    let mut tx = tenant_tx(db, tenant_id).await?;
+
+    let template: Option<DueTemplate> = sqlx::query_as(
+        "SELECT id, client_id, schedule, anchor_date, next_issue_date, payment_terms_days, notes, \
+                line_items, currency, created_by \
+         FROM recurring_invoices \
+         WHERE id = $1 AND tenant_id = $2 AND is_active = TRUE AND deleted_at IS NULL AND next_issue_date <= $3 \
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(recurring_id)
+    .bind(tenant_id)
+    .bind(today)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    // Already issued, paused, or being issued by another instance.
+    let Some(template) = template else {
+        return Ok(0);
+    };
+
+    let items: Vec<TemplateLineItem> = serde_json::from_value(template.line_items.clone())?;
+    let subtotal_cents: i64 = items
+        .iter()
+        .map(|li| line_total_cents(li.quantity, li.unit_price_cents))
+        .sum();
+
+    let mut issue_date = template.next_issue_date;
+    let mut issued = 0;
+    let mut periods = 0;
+    while issue_date <= today && periods < MAX_CATCH_UP_PERIODS {
+        let invoice_id = Uuid::new_v4();
+        let invoice_number = next_invoice_number(&mut tx, tenant_id).await?;
+        let due_date = issue_date.checked_add_days(Days::new(template.payment_terms_days as u64));
+
+        let inserted: Option<Uuid> = sqlx::query_scalar(
+            "INSERT INTO invoices (id, tenant_id, client_id, invoice_number, status, subtotal_cents, tax_cents, total_cents, currency, due_date, issued_date, notes, recurring_invoice_id, created_by) \
+             VALUES ($1, $2, $3, $4, 'draft', $5, $6, $7, $8, $9, $10, $11, $12, $13) \
+             ON CONFLICT (recurring_invoice_id, issued_date) WHERE recurring_invoice_id IS NOT NULL DO NOTHING \
+             RETURNING id",
+        )
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .bind(template.client_id)
+        .bind(&invoice_number)
+        .bind(subtotal_cents)
+        .bind(0i64)
+        .bind(subtotal_cents)
+        .bind(&template.currency)
+        .bind(due_date)
+        .bind(issue_date)
+        .bind(template.notes.as_deref())
+        .bind(template.id)
+        .bind(template.created_by)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if inserted.is_some() {
+            for (i, li) in items.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO invoice_line_items (tenant_id, invoice_id, description, quantity, unit_price_cents, total_cents, service_type, sort_order) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                )
+                .bind(tenant_id)
+                .bind(invoice_id)
+                .bind(&li.description)
+                .bind(li.quantity)
+                .bind(li.unit_price_cents)
+                .bind(line_total_cents(li.quantity, li.unit_price_cents))
+                .bind(li.service_type.as_deref())
+                .bind(i as i32)
+                .execute(&mut *tx)
+                .await?;
+            }
+            // Taxed at the rates in force when issued, not when templated.
+            taxes::engine::apply_to_invoice(&mut tx, tenant_id, invoice_id).await?;
+            issued += 1;
+            tracing::info!(
+                recurring_id = %template.id,
+                invoice_id = %invoice_id,
+                period = %issue_date,
+                "Issued recurring invoice {}",
+                invoice_number
+            );
+        }
+
+        periods += 1;
+        issue_date = next_after(template.anchor_date, &template.schedule, issue_date)
+            .ok_or_else(|| anyhow::anyhow!("Invalid schedule '{}'", template.schedule))?;
+    }
+
+    sqlx::query(
+        "UPDATE recurring_invoices SET next_issue_date = $3, \
+         last_issued_at = CASE WHEN $4 > 0 THEN NOW() ELSE last_issued_at END, \
+         invoices_generated = invoices_generated + $4, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(template.id)
+    .bind(tenant_id)
+    .bind(issue_date)
+    .bind(issued as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(issued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn test_period_date_by_schedule() {
+        assert_eq!(period_date(d(2026, 1, 1), "weekly", 1), Some(d(2026, 1, 8)));
+        assert_eq!(
+            period_date(d(2026, 11, 15), "quarterly", 1),
+            Some(d(2027, 2, 15))
+        );
+        assert_eq!(
+            period_date(d(2024, 2, 29), "annually", 1),
+            Some(d(2025, 2, 28))
+        );
+        assert_eq!(period_date(d(2026, 1, 1), "daily", 1), None);
+    }
+
+    #[test]
+    fn test_month_end_anchor_does_not_drift() {
+        let anchor = d(2026, 1, 31);
+        let feb = next_after(anchor, "monthly", anchor).unwrap();
+        assert_eq!(feb, d(2026, 2, 28));
+        assert_eq!(next_after(anchor, "monthly", feb), Some(d(2026, 3, 31)));
+    }
+
+    #[test]
+    fn test_first_on_or_after() {
+        let anchor = d(2026, 1, 5);
+        assert_eq!(
+            first_on_or_after(anchor, "weekly", d(2025, 12, 1)),
+            Some(anchor)
+        );
+        assert_eq!(
+            first_on_or_after(anchor, "weekly", d(2026, 1, 12)),
+            Some(d(2026, 1, 12))
+        );
+        assert_eq!(
+            first_on_or_after(anchor, "monthly", d(2026, 7, 6)),
+            Some(d(2026, 8, 5))
+        );
+        assert_eq!(
+            first_on_or_after(anchor, "annually", d(2030, 1, 4)),
+            Some(d(2030, 1, 5))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! The invoice lifecycle.
+//!
+//! A draft is sent to the client, who may view it. From then on its status
+//! follows the money: `partial` while part of it is paid, `paid` once it is
+//! covered, and `overdue` while a balance is left after the due date.
+//! Those statuses are derived by [`InvoiceStatus::settled`] whenever
+//! payments or credit are applied or taken back, so by hand an invoice can
+//! only be sent, marked viewed or voided. `void` is final.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    Draft,
+    Sent,
+    Viewed,
+    Partial,
+    Overdue,
+    Paid,
+    Void,
+}
+
+impl InvoiceStatus {
+    pub const ALL: [InvoiceStatus; 7] = [
+        InvoiceStatus::Draft,
+        InvoiceStatus::Sent,
+        InvoiceStatus::Viewed,
+        InvoiceStatus::Partial,
+        InvoiceStatus::Overdue,
+        InvoiceStatus::Paid,
+        InvoiceStatus::Void,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::Viewed => "viewed",
+            InvoiceStatus::Partial => "partial",
+            InvoiceStatus::Overdue => "overdue",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Void => "void",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<InvoiceStatus> {
+        Self::ALL.into_iter().find(|s| s.as_str() == status)
+    }
+
+    /// Sent and not yet settled or voided: payments and credit apply.
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            InvoiceStatus::Sent
+                | InvoiceStatus::Viewed
+                | InvoiceStatus::Partial
+                | InvoiceStatus::Overdue
+        )
+    }
+
+    /// The statuses a user may move an invoice to from this one.
+    pub fn manual_transitions(self) -> &'static [InvoiceStatus] {
+        match self {
+            InvoiceStatus::Draft => &[InvoiceStatus::Sent, InvoiceStatus::Void],
+            InvoiceStatus::Sent => &[InvoiceStatus::Viewed, InvoiceStatus::Void],
+            InvoiceStatus::Viewed
+            | InvoiceStatus::Partial
+            | InvoiceStatus::Overdue
+            | InvoiceStatus::Paid => &[InvoiceStatus::Void],
+            InvoiceStatus::Void => &[],
+        }
+    }
+
+    pub fn can_transition_to(self, to: InvoiceStatus) -> bool {
+        self.manual_transitions().contains(&to)
+    }
+
+    /// The status of an open or paid invoice once `paid_cents` of its
+    /// `total_cents` are covered; a zero-total invoice is paid as soon as it
+    /// is open. Drafts and void invoices keep theirs.
+    pub fn settled(self, total_cents: i64, paid_cents: i64, past_due: bool, viewed: bool) -> Self {
+        if !self.is_open() && self != InvoiceStatus::Paid {
+            return self;
+        }
+        if paid_cents >= total_cents {
+            InvoiceStatus::Paid
+        } else if past_due {
+            InvoiceStatus::Overdue
+        } else if paid_cents > 0 {
+            InvoiceStatus::Partial
+        } else if viewed {
+            InvoiceStatus::Viewed
+        } else {
+            InvoiceStatus::Sent
+        }
+    }
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvoiceStatus::*;
+    use super::*;
+
+    #[test]
+    fn test_manual_transitions() {
+        assert!(Draft.can_transition_to(Sent));
+        assert!(Sent.can_transition_to(Viewed));
+        assert!(Paid.can_transition_to(Void));
+        // Payments decide these.
+        assert!(!Sent.can_transition_to(Paid));
+        assert!(!Viewed.can_transition_to(Partial));
+        assert!(!Sent.can_transition_to(Overdue));
+        assert!(!Void.can_transition_to(Draft));
+        for status in InvoiceStatus::ALL {
+            assert_eq!(InvoiceStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(InvoiceStatus::parse("cancelled"), None);
+    }
+
+    #[test]
+    fn test_settled_follows_the_balance() {
+        assert_eq!(Sent.settled(10_000, 4_000, false, false), Partial);
+        assert_eq!(Partial.settled(10_000, 10_000, false, false), Paid);
+        assert_eq!(Overdue.settled(10_000, 12_000, true, false), Paid);
+        assert_eq!(Overdue.settled(10_000, 4_000, true, false), Overdue);
+        // Refunded in full, back to where it was before any payment.
+        assert_eq!(Paid.settled(10_000, 0, false, true), Viewed);
+        assert_eq!(Paid.settled(10_000, 0, false, false), Sent);
+        assert_eq!(Paid.settled(10_000, 0, true, false), Overdue);
+        assert_eq!(Draft.settled(10_000, 10_000, false, false), Draft);
+        assert_eq!(Void.settled(10_000, 0, false, false), Void);
+    }
+
+    #[test]
+    fn test_settled_zero_total_is_paid() {
+        assert_eq!(Sent.settled(0, 0, false, false), Paid);
+        assert_eq!(Overdue.settled(0, 0, true, false), Paid);
+        assert_eq!(Draft.settled(0, 0, false, false), Draft);
+    }
+}
@@ -6,13 +6,17 @@ use axum::{
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::approvals;
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
 use crate::jobs::model::*;
+use crate::matching;
+use crate::middleware::tenant::TenantTx;
+use crate::subscriptions::entitlements::{self, Meter};
 use crate::AppState;
 
 pub async fn list_jobs(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListJobsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -87,7 +91,7 @@ pub async fn list_jobs(
         count_query = count_query.bind(pattern);
     }
 
-    let (total,) = count_query.fetch_one(&state.db).await?;
+    let (total,) = count_query.fetch_one(&mut *tx).await?;
 
     // Data query
     let data_sql = format!(
@@ -125,7 +129,7 @@ pub async fn list_jobs(
     let jobs = data_query
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -142,7 +146,7 @@ pub async fn list_jobs(
 }
 
 pub async fn get_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(job_id): Path<Uuid>,
 ) -> AppResult<Json<JobPost>> {
@@ -157,7 +161,7 @@ pub async fn get_job(
     )
     .bind(job_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
 
@@ -165,7 +169,7 @@ pub async fn get_job(
 }
 
 pub async fn create_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateJobRequest>,
 ) -> AppResult<(StatusCode, Json<JobPost>)> {
@@ -173,6 +177,8 @@ pub async fn create_job(
         .validate()
         .map_err(|e| AppError::Validation(e.to_string()))?;
 
+    entitlements::consume(&mut tx, claims.tid, Meter::JobPosts, 1).await?;
+
     let id = Uuid::new_v4();
     let work_mode = payload.work_mode.as_deref().unwrap_or("onsite");
     let employment_type = payload.employment_type.as_deref().unwrap_or("full_time");
@@ -181,15 +187,28 @@ pub async fn create_job(
     let is_urgent = payload.is_urgent.unwrap_or(false);
     let skills_required = payload.skills_required.as_deref().unwrap_or(&[]);
     let skills_preferred = payload.skills_preferred.as_deref().unwrap_or(&[]);
+    let pipeline_template_id: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM pipeline_templates \
+         WHERE tenant_id = $1 AND (id = $2 OR ($2 IS NULL AND is_default))",
+    )
+    .bind(claims.tid)
+    .bind(payload.pipeline_template_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if payload.pipeline_template_id.is_some() && pipeline_template_id.is_none() {
+        return Err(AppError::Validation(
+            "Pipeline template not found".to_string(),
+        ));
+    }
 
     let job: JobPost = sqlx::query_as(
         "INSERT INTO job_posts (id, tenant_id, title, department, description, requirements, \
          responsibilities, benefits, location_city, location_state, location_country, work_mode, \
          employment_type, seniority_level, salary_min_cents, salary_max_cents, equity_offered, \
          status, visibility, hiring_manager_id, recruiter_id, max_applications, is_urgent, \
-         skills_required, skills_preferred, metadata) \
+         skills_required, skills_preferred, metadata, pipeline_template_id) \
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, \
-         'draft', $18, $19, $20, $21, $22, $23, $24, '{}') \
+         'draft', $18, $19, $20, $21, $22, $23, $24, '{}', $25) \
          RETURNING id, tenant_id, organization_id, company_id, title, department, description, \
          requirements, responsibilities, benefits, location_city, location_state, location_country, \
          work_mode, employment_type, seniority_level, salary_min_cents, salary_max_cents, \
@@ -221,14 +240,15 @@ pub async fn create_job(
     .bind(is_urgent)
     .bind(skills_required)
     .bind(skills_preferred)
-    .fetch_one(&state.db)
+    .bind(pipeline_template_id)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(job)))
 }
 
 pub async fn update_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(job_id): Path<Uuid>,
     Json(payload): Json<UpdateJobRequest>,
@@ -238,7 +258,7 @@ pub async fn update_job(
         sqlx::query_as("SELECT COUNT(*) FROM job_posts WHERE id = $1 AND tenant_id = $2")
             .bind(job_id)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     if existing == 0 {
@@ -302,36 +322,39 @@ pub async fn update_job(
     .bind(payload.is_urgent)
     .bind(payload.skills_required.as_deref())
     .bind(payload.skills_preferred.as_deref())
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
+    matching::rescore_job(&mut tx, claims.tid, job.id).await?;
+
     Ok(Json(job))
 }
 
 pub async fn delete_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(job_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     let result = sqlx::query("DELETE FROM job_posts WHERE id = $1 AND tenant_id = $2")
         .bind(job_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Job not found".to_string()));
     }
+    entitlements::release(&mut tx, claims.tid, Meter::JobPosts, 1).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn publish_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(job_id): Path<Uuid>,
 ) -> AppResult<Json<JobPost>> {
-    // Verify job exists and is in draft status
+    // Lock the job so concurrent publishes see each other's status change.
     let existing: JobPost = sqlx::query_as(
         "SELECT id, tenant_id, organization_id, company_id, title, department, description, \
          requirements, responsibilities, benefits, location_city, location_state, location_country, \
@@ -339,11 +362,11 @@ pub async fn publish_job(
          salary_currency, equity_offered, status, visibility, posted_at, closes_at, filled_at, \
          hiring_manager_id, recruiter_id, max_applications, application_count, is_urgent, \
          skills_required, skills_preferred, metadata, created_at, updated_at \
-         FROM job_posts WHERE id = $1 AND tenant_id = $2",
+         FROM job_posts WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
     )
     .bind(job_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
 
@@ -353,10 +376,11 @@ pub async fn publish_job(
             existing.status
         )));
     }
+    approvals::require_approved(&mut *tx, claims.tid, approvals::JOB_POSTING, job_id).await?;
 
     let job: JobPost = sqlx::query_as(
         "UPDATE job_posts SET status = 'open', posted_at = NOW(), updated_at = NOW() \
-         WHERE id = $1 AND tenant_id = $2 \
+         WHERE id = $1 AND tenant_id = $2 AND status = 'draft' \
          RETURNING id, tenant_id, organization_id, company_id, title, department, description, \
          requirements, responsibilities, benefits, location_city, location_state, location_country, \
          work_mode, employment_type, seniority_level, salary_min_cents, salary_max_cents, \
@@ -366,8 +390,9 @@ pub async fn publish_job(
     )
     .bind(job_id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Job is no longer a draft".to_string()))?;
 
     Ok(Json(job))
 }
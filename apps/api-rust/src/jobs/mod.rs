@@ -1,2 +1,27 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, patch, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Public job listings, nested under `/api/v1`.
+pub fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route("/public/jobs", get(handler::list_public_jobs))
+        .route("/public/jobs/{id}", get(handler::get_public_job))
+}
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/jobs", get(handler::list_jobs))
+        .route("/jobs", post(handler::create_job))
+        .route("/jobs/{id}", get(handler::get_job))
+        .route("/jobs/{id}", patch(handler::update_job))
+        .route("/jobs/{id}", delete(handler::delete_job))
+        .route("/jobs/{id}/publish", post(handler::publish_job))
+}
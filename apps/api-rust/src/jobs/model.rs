@@ -68,6 +68,8 @@ pub struct CreateJobRequest {
     pub is_urgent: Option<bool>,
     pub skills_required: Option<Vec<String>>,
     pub skills_preferred: Option<Vec<String>>,
+    /// Hiring pipeline for the job; defaults to the tenant's default template.
+    pub pipeline_template_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
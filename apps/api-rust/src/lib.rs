@@ -0,0 +1,217 @@
+#![allow(dead_code, clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod applications;
+pub mod auth;
+pub mod candidates;
+pub mod clients;
+pub mod compliance;
+pub mod config;
+pub mod conversations;
+pub mod dashboard;
+pub mod documents;
+pub mod error;
+pub mod errors {
+    pub use crate::error::*;
+}
+pub mod expenses;
+pub mod extraction;
+pub mod flags;
+pub mod interviews;
+pub mod invoices;
+pub mod jobs;
+pub mod mailer;
+pub mod matching;
+pub mod meetings;
+pub mod messages;
+pub mod middleware;
+pub mod money;
+pub mod notifications;
+pub mod offers;
+pub mod payments;
+pub mod reports;
+pub mod scheduler;
+pub mod scorecards;
+pub mod settings;
+pub mod shortcuts;
+pub mod storage;
+pub mod subscriptions;
+pub mod tasks;
+pub mod taxes;
+pub mod time_entries;
+pub mod video_rooms;
+pub mod workflows;
+pub mod ws;
+
+// Advanced hiring modules
+pub mod activity;
+pub mod automations;
+pub mod career_page;
+pub mod email_templates;
+pub mod question_bank;
+pub mod saved_jobs;
+pub mod search;
+
+// Next-level hiring modules
+pub mod approvals;
+pub mod assessments;
+pub mod onboarding;
+pub mod pipeline_stages;
+pub mod referrals;
+pub mod talent_pools;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware as axum_mw,
+    middleware::Next,
+    response::Response,
+    Router,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::trace::TraceLayer;
+
+use config::Config;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub db: sqlx::PgPool,
+    pub config: Config,
+    pub ws_broadcast: ws::WsBroadcast,
+    pub rate_limiter: middleware::rate_limit::RateLimiter,
+    pub redis: Option<std::sync::Arc<fred::clients::RedisClient>>,
+    pub mailer: mailer::Mailer,
+    pub search: search::SearchEngine,
+}
+
+async fn security_headers(req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert(
+        "x-content-type-options",
+        HeaderValue::from_static("nosniff"),
+    );
+    headers.insert("x-frame-options", HeaderValue::from_static("DENY"));
+    headers.insert(
+        "x-xss-protection",
+        HeaderValue::from_static("1; mode=block"),
+    );
+    headers.insert(
+        "referrer-policy",
+        HeaderValue::from_static("strict-origin-when-cross-origin"),
+    );
+    headers.insert(
+        "permissions-policy",
+        HeaderValue::from_static("camera=(), microphone=(), geolocation=()"),
+    );
+    response
+}
+
+/// Build the full application router: public and authenticated API routes
+/// under `/api/v1`, plus the global middleware stack.
+pub fn router(state: AppState) -> Router {
+    // Authenticated routes (require JWT)
+    let protected_routes = Router::new()
+        .merge(dashboard::routes())
+        .merge(search::routes())
+        .merge(clients::routes())
+        .merge(time_entries::routes())
+        .merge(documents::routes())
+        .merge(invoices::routes())
+        .merge(taxes::routes())
+        .merge(money::routes())
+        .merge(workflows::routes())
+        .merge(tasks::routes())
+        .merge(compliance::routes())
+        .merge(expenses::routes())
+        .merge(notifications::routes())
+        .merge(candidates::routes())
+        .merge(jobs::routes())
+        .merge(matching::routes())
+        .merge(applications::routes())
+        .merge(scorecards::routes())
+        .merge(offers::routes())
+        .merge(subscriptions::routes())
+        .merge(conversations::routes())
+        .merge(meetings::routes())
+        .merge(video_rooms::routes())
+        .merge(interviews::routes())
+        .merge(shortcuts::routes())
+        .merge(flags::routes())
+        .merge(messages::routes())
+        .merge(settings::routes())
+        .merge(reports::routes())
+        .merge(payments::routes())
+        .merge(saved_jobs::routes())
+        .merge(email_templates::routes())
+        .merge(career_page::routes())
+        .merge(question_bank::routes())
+        .merge(automations::routes())
+        .merge(activity::routes())
+        .merge(pipeline_stages::routes())
+        .merge(approvals::routes())
+        .merge(referrals::routes())
+        .merge(talent_pools::routes())
+        .merge(assessments::routes())
+        .merge(onboarding::routes())
+        .merge(middleware::audit_handler::routes())
+        .merge(auth::routes())
+        // Audit log middleware (runs after auth, before handlers)
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::audit::audit_log,
+        ))
+        // Auth middleware
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::require_auth,
+        ));
+
+    // Public routes (no JWT)
+    let public_routes = Router::new()
+        .merge(auth::public_routes())
+        .merge(jobs::public_routes())
+        .merge(payments::public_routes())
+        .merge(meetings::public_routes())
+        .merge(ws::routes());
+
+    // Build CORS layer
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::exact(
+            state
+                .config
+                .cors_origin
+                .parse()
+                .expect("Invalid CORS origin"),
+        ))
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::PATCH,
+            Method::OPTIONS,
+        ])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            HeaderName::from_static("x-csrf-token"),
+            HeaderName::from_static("idempotency-key"),
+        ])
+        .allow_credentials(true);
+
+    Router::new()
+        .nest("/api/v1", public_routes.merge(protected_routes))
+        // Layers
+        .layer(axum_mw::from_fn(middleware::csrf::csrf_protection))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit::rate_limit,
+        ))
+        .layer(axum_mw::from_fn(security_headers))
+        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024)) // 10MB max body
+        .layer(TraceLayer::new_for_http())
+        .layer(cors)
+        .with_state(state)
+}
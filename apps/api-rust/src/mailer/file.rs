@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use super::{MailError, OutgoingEmail};
+
+/// Writes each message as an `.eml` file so local development can inspect
+/// exactly what would have been sent.
+#[derive(Clone)]
+pub struct FileMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(dir: &str, from: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            from: from.to_string(),
+        }
+    }
+
+    pub async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        if !email.to.contains('@') {
+            return Err(MailError::Address(email.to.clone()));
+        }
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| MailError::Transport(format!("Failed to create mail dir: {}", e)))?;
+
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            uuid::Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, self.format(email))
+            .await
+            .map_err(|e| MailError::Transport(format!("Failed to write {:?}: {}", path, e)))
+    }
+
+    fn format(&self, email: &OutgoingEmail) -> String {
+        let mut out = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            self.from,
+            email.to,
+            email.subject,
+            chrono::Utc::now().to_rfc2822(),
+            email.text_body
+        );
+        if let Some(html) = &email.html_body {
+            out.push_str("\r\n--- text/html ---\r\n");
+            out.push_str(html);
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_eml() {
+        let dir = std::env::temp_dir().join(format!("mail-test-{}", uuid::Uuid::new_v4()));
+        let mailer = FileMailer::new(dir.to_str().unwrap(), "firm@example.com");
+        mailer
+            .send(&OutgoingEmail {
+                to: "jane@example.com".to_string(),
+                subject: "Hello".to_string(),
+                text_body: "Body text".to_string(),
+                html_body: None,
+            })
+            .await
+            .unwrap();
+
+        let mut entries = std::fs::read_dir(&dir).unwrap();
+        let path = entries.next().unwrap().unwrap().path();
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert!(contents.contains("To: jane@example.com"));
+        assert!(contents.contains("Subject: Hello"));
+        assert!(contents.ends_with("Body text\r\n"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
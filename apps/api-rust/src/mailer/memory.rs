@@ -0,0 +1,28 @@
+use std::sync::{Arc, Mutex};
+
+use super::{MailError, OutgoingEmail};
+
+/// Collects messages in memory. Used by tests to assert on delivered mail.
+#[derive(Clone, Default)]
+pub struct MemoryMailer {
+    sent: Arc<Mutex<Vec<OutgoingEmail>>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        if !email.to.contains('@') {
+            return Err(MailError::Address(email.to.clone()));
+        }
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+
+    /// Snapshot of everything sent so far.
+    pub fn sent(&self) -> Vec<OutgoingEmail> {
+        self.sent.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,136 @@
+//! Built-in transactional messages (account and billing emails that are not
+//! driven by tenant-editable `email_templates`).
+
+use super::OutgoingEmail;
+
+pub fn password_reset(to: &str, base_url: &str, token: &str) -> OutgoingEmail {
+    let link = format!(
+        "{}/reset-password?token={}",
+        base_url.trim_end_matches('/'),
+        token
+    );
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: "Reset your password".to_string(),
+        text_body: format!(
+            "We received a request to reset your password.\n\n\
+             Reset it here (the link expires in 1 hour):\n{}\n\n\
+             If you did not request this, you can ignore this email.",
+            link
+        ),
+        html_body: None,
+    }
+}
+
+pub fn email_verification(to: &str, base_url: &str, token: &str) -> OutgoingEmail {
+    let link = format!(
+        "{}/verify-email?token={}",
+        base_url.trim_end_matches('/'),
+        token
+    );
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: "Verify your email address".to_string(),
+        text_body: format!(
+            "Please confirm your email address by opening the link below \
+             (valid for 24 hours):\n{}",
+            link
+        ),
+        html_body: None,
+    }
+}
+
+pub fn user_invite(
+    to: &str,
+    base_url: &str,
+    token: &str,
+    firm_name: &str,
+    first_name: &str,
+) -> OutgoingEmail {
+    let link = format!(
+        "{}/accept-invite?token={}",
+        base_url.trim_end_matches('/'),
+        token
+    );
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: format!("You've been invited to join {}", firm_name),
+        text_body: format!(
+            "Hi {},\n\n{} has invited you to their workspace.\n\n\
+             Accept the invitation and set your password here:\n{}",
+            first_name, firm_name, link
+        ),
+        html_body: None,
+    }
+}
+
+pub fn invoice_sent(
+    to: &str,
+    firm_name: &str,
+    invoice_number: &str,
+    amount: &str,
+    due_date: Option<&str>,
+) -> OutgoingEmail {
+    let due = due_date
+        .map(|d| format!(" and is due on {}", d))
+        .unwrap_or_default();
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: format!("Invoice {} from {}", invoice_number, firm_name),
+        text_body: format!(
+            "Hello,\n\n{} has sent you invoice {} for {}{}.\n\n\
+             You can view and pay it from your client portal.\n\nThank you for your business.",
+            firm_name, invoice_number, amount, due
+        ),
+        html_body: None,
+    }
+}
+
+/// A dunning reminder for an invoice `days_past_due` days late, mentioning
+/// the late fee just added to it, if any.
+pub fn invoice_overdue(
+    to: &str,
+    firm_name: &str,
+    invoice_number: &str,
+    balance: &str,
+    days_past_due: i64,
+    late_fee: Option<&str>,
+    message: Option<&str>,
+) -> OutgoingEmail {
+    let fee = late_fee
+        .map(|f| format!(" This includes a late fee of {}.", f))
+        .unwrap_or_default();
+    let message = message
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(|m| format!("\n\n{}", m))
+        .unwrap_or_default();
+    let days = if days_past_due == 1 { "day" } else { "days" };
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: format!(
+            "Reminder: invoice {} from {} is overdue",
+            invoice_number, firm_name
+        ),
+        text_body: format!(
+            "Hello,\n\nInvoice {} from {} is {} {} past due, with {} outstanding.{}{}\n\n\
+             You can view and pay it from your client portal. If you have already paid, \
+             please disregard this reminder.",
+            invoice_number, firm_name, days_past_due, days, balance, fee, message
+        ),
+        html_body: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_reset_link() {
+        let email = password_reset("a@b.com", "https://app.example.com/", "tok");
+        assert!(email
+            .text_body
+            .contains("https://app.example.com/reset-password?token=tok"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Outbound email delivery.
+//!
+//! Handlers never talk to a transport directly: they render content and call
+//! [`outbox::enqueue`], and the dispatcher started from `main` delivers queued
+//! messages through the configured [`Mailer`].
+
+pub mod file;
+pub mod memory;
+pub mod messages;
+pub mod outbox;
+pub mod smtp;
+pub mod template;
+
+use crate::config::Config;
+
+/// A fully rendered message ready for delivery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("Invalid address: {0}")]
+    Address(String),
+    #[error("Transport error: {0}")]
+    Transport(String),
+}
+
+/// The configured delivery transport. `file` and `memory` exist for local
+/// development and tests; production uses `smtp`.
+#[derive(Clone)]
+pub enum Mailer {
+    Smtp(smtp::SmtpMailer),
+    File(file::FileMailer),
+    Memory(memory::MemoryMailer),
+}
+
+impl Mailer {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        match config.mail_transport.as_str() {
+            "smtp" => Ok(Mailer::Smtp(smtp::SmtpMailer::from_config(config)?)),
+            "file" => Ok(Mailer::File(file::FileMailer::new(
+                &config.mail_file_dir,
+                &config.mail_from,
+            ))),
+            "memory" => Ok(Mailer::Memory(memory::MemoryMailer::new())),
+            other => anyhow::bail!(
+                "Unknown MAIL_TRANSPORT '{}'. Must be one of: smtp, file, memory",
+                other
+            ),
+        }
+    }
+
+    pub async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        match self {
+            Mailer::Smtp(m) => m.send(email).await,
+            Mailer::File(m) => m.send(email).await,
+            Mailer::Memory(m) => m.send(email),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use super::{Mailer, OutgoingEmail};
+use crate::error::AppResult;
+
+/// Messages claimed per dispatcher pass.
+const BATCH_SIZE: i64 = 25;
+/// Rows stuck in `sending` longer than this (e.g. after a crash) are retried,
+/// or failed once they have used their last attempt.
+const STALE_LOCK_MINUTES: i32 = 10;
+
+/// Optional metadata recorded alongside a queued message.
+#[derive(Debug, Default, Clone)]
+pub struct EnqueueOptions<'a> {
+    pub category: Option<&'a str>,
+    pub template_id: Option<Uuid>,
+    pub created_by: Option<Uuid>,
+}
+
+/// Persist a message to the outbox. Delivery happens asynchronously.
+pub async fn enqueue(
+    db: impl sqlx::PgExecutor<'_>,
+    tenant_id: Uuid,
+    email: &OutgoingEmail,
+    opts: EnqueueOptions<'_>,
+) -> AppResult<Uuid> {
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO email_outbox (tenant_id, template_id, category, to_email, subject, body_text, body_html, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(opts.template_id)
+    .bind(opts.category.unwrap_or("custom"))
+    .bind(&email.to)
+    .bind(&email.subject)
+    .bind(&email.text_body)
+    .bind(email.html_body.as_deref())
+    .bind(opts.created_by)
+    .fetch_one(db)
+    .await?;
+
+    Ok(id)
+}
+
+/// Delay before the next attempt after `attempts` failures: 1, 2, 4, 8... minutes, capped at 6 hours.
+pub fn retry_backoff(attempts: i32) -> chrono::Duration {
+    let exp = attempts.clamp(1, 10) - 1;
+    chrono::Duration::minutes((1i64 << exp).min(360))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedMessage {
+    id: Uuid,
+    to_email: String,
+    subject: String,
+    body_text: String,
+    body_html: Option<String>,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+/// Deliver one batch of due messages. Returns the number processed.
+pub async fn dispatch_due(db: &sqlx::PgPool, mailer: &Mailer) -> Result<usize, sqlx::Error> {
+    // Claiming counts as an attempt, so a message whose send keeps crashing
+    // the dispatcher stops being reclaimed once it has used them all.
+    sqlx::query(
+        "UPDATE email_outbox SET status = 'failed', locked_at = NULL, last_error = 'lock expired', updated_at = NOW() \
+         WHERE status = 'sending' AND attempts >= max_attempts \
+         AND locked_at < NOW() - make_interval(mins => $1)",
+    )
+    .bind(STALE_LOCK_MINUTES)
+    .execute(db)
+    .await?;
+
+    let claimed: Vec<ClaimedMessage> = sqlx::query_as(
+        "UPDATE email_outbox SET status = 'sending', attempts = attempts + 1, locked_at = NOW(), updated_at = NOW() \
+         WHERE id IN ( \
+             SELECT id FROM email_outbox \
+             WHERE (status = 'pending' AND next_attempt_at <= NOW()) \
+                OR (status = 'sending' AND attempts < max_attempts \
+                    AND locked_at < NOW() - make_interval(mins => $2)) \
+             ORDER BY next_attempt_at \
+             LIMIT $1 \
+             FOR UPDATE SKIP LOCKED \
+         ) \
+         RETURNING id, to_email, subject, body_text, body_html, attempts, max_attempts",
+    )
+    .bind(BATCH_SIZE)
+    .bind(STALE_LOCK_MINUTES)
+    .fetch_all(db)
+    .await?;
+
+    for msg in &claimed {
+        let email = OutgoingEmail {
+            to: msg.to_email.clone(),
+            subject: msg.subject.clone(),
+            text_body: msg.body_text.clone(),
+            html_body: msg.body_html.clone(),
+        };
+
+        match mailer.send(&email).await {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE email_outbox SET status = 'sent', sent_at = NOW(), locked_at = NULL, last_error = NULL, updated_at = NOW() \
+                     WHERE id = $1",
+                )
+                .bind(msg.id)
+                .execute(db)
+                .await?;
+            }
+            Err(e) => {
+                let exhausted =
+                    msg.attempts >= msg.max_attempts || matches!(e, super::MailError::Address(_));
+                tracing::warn!(
+                    outbox_id = %msg.id,
+                    attempts = msg.attempts,
+                    "Email delivery failed{}: {}",
+                    if exhausted { " permanently" } else { "" },
+                    e
+                );
+                sqlx::query(
+                    "UPDATE email_outbox SET status = $2, last_error = $3, locked_at = NULL, \
+                     next_attempt_at = NOW() + $4, updated_at = NOW() WHERE id = $1",
+                )
+                .bind(msg.id)
+                .bind(if exhausted { "failed" } else { "pending" })
+                .bind(e.to_string())
+                .bind(retry_backoff(msg.attempts))
+                .execute(db)
+                .await?;
+            }
+        }
+    }
+
+    Ok(claimed.len())
+}
+
+/// Spawn the background dispatcher that drains the outbox.
+pub fn spawn_dispatcher(db: sqlx::PgPool, mailer: Mailer) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            // Keep draining while full batches come back
+            loop {
+                match dispatch_due(&db, &mailer).await {
+                    Ok(n) if n as i64 == BATCH_SIZE => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::error!("Email outbox dispatch failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff_grows_and_caps() {
+        assert_eq!(retry_backoff(1), chrono::Duration::minutes(1));
+        assert_eq!(retry_backoff(2), chrono::Duration::minutes(2));
+        assert_eq!(retry_backoff(4), chrono::Duration::minutes(8));
+        assert_eq!(retry_backoff(50), chrono::Duration::minutes(360));
+    }
+}
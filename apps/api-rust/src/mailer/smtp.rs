@@ -0,0 +1,74 @@
+use lettre::message::{header::ContentType, Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{MailError, OutgoingEmail};
+use crate::config::Config;
+
+/// SMTP delivery via a pooled async connection.
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("SMTP_HOST is required when MAIL_TRANSPORT=smtp"))?;
+
+        let mut builder = match config.smtp_security.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            other => anyhow::bail!(
+                "Unknown SMTP_SECURITY '{}'. Must be one of: tls, starttls, none",
+                other
+            ),
+        }
+        .port(config.smtp_port);
+
+        if let (Some(user), Some(pass)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config
+                .mail_from
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid MAIL_FROM: {}", e))?,
+        })
+    }
+
+    pub async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        let to: Mailbox = email
+            .to
+            .parse()
+            .map_err(|e| MailError::Address(format!("{}: {}", email.to, e)))?;
+
+        let builder = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject);
+
+        let message = match &email.html_body {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(
+                email.text_body.clone(),
+                html.clone(),
+            )),
+            None => builder
+                .header(ContentType::TEXT_PLAIN)
+                .body(email.text_body.clone()),
+        }
+        .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| MailError::Transport(e.to_string()))
+    }
+}
@@ -0,0 +1,218 @@
+//! `{{variable}}` rendering for `email_templates` and the context loaders
+//! that resolve candidate, job and invoice variables from the database.
+
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+
+/// Result of rendering a template string.
+#[derive(Debug, PartialEq)]
+pub struct Rendered {
+    pub text: String,
+    /// Variables referenced by the template that had no value in the context.
+    pub missing: Vec<String>,
+}
+
+/// Replace every `{{ name }}` placeholder with its value from `vars`.
+/// Unknown variables render as empty strings and are reported in `missing`;
+/// an unterminated `{{` is emitted verbatim.
+pub fn render(template: &str, vars: &Map<String, Value>) -> Rendered {
+    let mut text = String::with_capacity(template.len());
+    let mut missing = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            text.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let name = after[..end].trim();
+        match vars.get(name) {
+            Some(Value::String(s)) => text.push_str(s),
+            Some(Value::Null) | None => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+            Some(other) => text.push_str(&other.to_string()),
+        }
+        rest = &after[end + 2..];
+    }
+    text.push_str(rest);
+
+    Rendered { text, missing }
+}
+
+/// Optional entities whose fields should be exposed as template variables.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ContextRefs {
+    pub candidate_id: Option<Uuid>,
+    pub job_id: Option<Uuid>,
+    pub invoice_id: Option<Uuid>,
+}
+
+/// Build the variable map for a tenant, merging entity context with any
+/// caller-supplied overrides (overrides win).
+pub async fn build_context(
+    conn: &mut sqlx::PgConnection,
+    tenant_id: Uuid,
+    app_base_url: &str,
+    refs: ContextRefs,
+    overrides: &Value,
+) -> AppResult<Map<String, Value>> {
+    let mut vars = Map::new();
+
+    let tenant: Option<(String, String)> =
+        sqlx::query_as("SELECT name, locale FROM tenants WHERE id = $1")
+            .bind(tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let (company_name, locale) = match tenant {
+        Some((name, locale)) => (Some(name), locale),
+        None => (None, "en-US".to_string()),
+    };
+    insert(&mut vars, "company_name", company_name);
+    insert(&mut vars, "portal_link", Some(app_base_url.to_string()));
+
+    if let Some(candidate_id) = refs.candidate_id {
+        let row: Option<(
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        )> = sqlx::query_as(
+            "SELECT u.first_name, u.last_name, u.email, cp.headline \
+                 FROM candidate_profiles cp LEFT JOIN users u ON u.id = cp.user_id \
+                 WHERE cp.id = $1 AND cp.tenant_id = $2",
+        )
+        .bind(candidate_id)
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some((first, last, email, headline)) = row {
+            let full = [first.as_deref(), last.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            insert(
+                &mut vars,
+                "candidate_name",
+                Some(full).filter(|s| !s.is_empty()),
+            );
+            insert(&mut vars, "candidate_first_name", first);
+            insert(&mut vars, "candidate_last_name", last);
+            insert(&mut vars, "candidate_email", email);
+            insert(&mut vars, "candidate_headline", headline);
+        }
+    }
+
+    if let Some(job_id) = refs.job_id {
+        let row: Option<(
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            String,
+        )> = sqlx::query_as(
+            "SELECT title, department, location_city, location_state, work_mode \
+                 FROM job_posts WHERE id = $1 AND tenant_id = $2",
+        )
+        .bind(job_id)
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some((title, department, city, region, work_mode)) = row {
+            let location = [city.as_deref(), region.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", ");
+            insert(&mut vars, "job_title", Some(title));
+            insert(&mut vars, "job_department", department);
+            insert(
+                &mut vars,
+                "job_location",
+                Some(location).filter(|s| !s.is_empty()),
+            );
+            insert(&mut vars, "job_work_mode", Some(work_mode));
+        }
+    }
+
+    if let Some(invoice_id) = refs.invoice_id {
+        let row: Option<(String, i64, i64, Option<chrono::NaiveDate>, String, String)> = sqlx::query_as(
+            "SELECT i.invoice_number, i.total_cents, i.amount_paid_cents, i.due_date, c.name, i.currency \
+             FROM invoices i JOIN clients c ON c.id = i.client_id \
+             WHERE i.id = $1 AND i.tenant_id = $2",
+        )
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some((number, total, paid, due, client_name, currency)) = row {
+            let fmt = |minor: i64| crate::money::format(minor, &currency, &locale);
+            insert(&mut vars, "invoice_number", Some(number));
+            insert(&mut vars, "invoice_total", Some(fmt(total)));
+            insert(&mut vars, "invoice_balance", Some(fmt(total - paid)));
+            insert(
+                &mut vars,
+                "invoice_due_date",
+                due.map(|d| d.format("%B %d, %Y").to_string()),
+            );
+            insert(&mut vars, "client_name", Some(client_name));
+        }
+    }
+
+    if let Value::Object(extra) = overrides {
+        for (k, v) in extra {
+            vars.insert(k.clone(), v.clone());
+        }
+    }
+
+    Ok(vars)
+}
+
+fn insert(vars: &mut Map<String, Value>, key: &str, value: Option<String>) {
+    if let Some(v) = value {
+        vars.insert(key.to_string(), Value::String(v));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(v: Value) -> Map<String, Value> {
+        v.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_render_substitutes_variables() {
+        let r = render(
+            "Hi {{candidate_name}}, re: {{ job_title }}",
+            &vars(json!({"candidate_name": "Jane", "job_title": "Engineer"})),
+        );
+        assert_eq!(r.text, "Hi Jane, re: Engineer");
+        assert!(r.missing.is_empty());
+    }
+
+    #[test]
+    fn test_render_reports_missing_once() {
+        let r = render("{{a}} {{b}} {{a}}", &vars(json!({"b": 3})));
+        assert_eq!(r.text, " 3 ");
+        assert_eq!(r.missing, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn test_render_leaves_unterminated_placeholder() {
+        let r = render("Total: {{amount", &Map::new());
+        assert_eq!(r.text, "Total: {{amount");
+        assert!(r.missing.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+use uuid::Uuid;
+
+use super::model::*;
+use super::score;
+use super::{candidate_facts, job_facts};
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
+
+/// Candidates or jobs scored per request. The most likely matches are read
+/// first: candidates listing one of the job's skills, then the most recently
+/// updated; jobs most recently posted.
+const MAX_SCANNED: i64 = 1000;
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+pub async fn recommended_candidates(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(job_id): Path<Uuid>,
+    Query(params): Query<RecommendationQuery>,
+) -> AppResult<Json<Vec<RecommendedCandidate>>> {
+    let job = job_facts(&mut tx, claims.tid, &[job_id])
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
+    let wanted: Vec<String> = job
+        .skills_required
+        .iter()
+        .chain(&job.skills_preferred)
+        .map(|s| s.to_lowercase())
+        .collect();
+
+    let mut candidates: Vec<RecommendedCandidate> = sqlx::query_as(
+        "SELECT cp.id AS candidate_id, \
+         COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), 'Candidate') \
+             AS candidate_name, \
+         cp.headline, cp.location_city, cp.location_state, cp.availability_status, \
+         EXISTS ( \
+             SELECT 1 FROM applications a WHERE a.candidate_id = cp.id AND a.job_id = $2 \
+         ) AS applied \
+         FROM candidate_profiles cp \
+         LEFT JOIN users u ON u.id = cp.user_id \
+         WHERE cp.tenant_id = $1 \
+         AND cp.availability_status IS DISTINCT FROM 'unavailable' \
+         AND ($4 OR NOT EXISTS ( \
+             SELECT 1 FROM applications a WHERE a.candidate_id = cp.id AND a.job_id = $2 \
+         )) \
+         ORDER BY EXISTS ( \
+             SELECT 1 FROM candidate_skills s \
+             WHERE s.candidate_id = cp.id AND LOWER(s.skill_name) = ANY($3) \
+         ) DESC, cp.updated_at DESC, cp.id \
+         LIMIT $5",
+    )
+    .bind(claims.tid)
+    .bind(job_id)
+    .bind(&wanted)
+    .bind(params.include_applied.unwrap_or(false))
+    .bind(MAX_SCANNED)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let ids: Vec<Uuid> = candidates.iter().map(|c| c.candidate_id).collect();
+    let facts: HashMap<Uuid, _> = candidate_facts(&mut tx, claims.tid, &ids)
+        .await?
+        .into_iter()
+        .map(|facts| (facts.id, facts))
+        .collect();
+    for candidate in &mut candidates {
+        if let Some(facts) = facts.get(&candidate.candidate_id) {
+            let matched = score::score(facts, &job);
+            candidate.match_score = matched.score;
+            candidate.match_reasons = matched.reasons;
+        }
+    }
+
+    Ok(Json(rank(candidates, &params, |c| c.match_score)))
+}
+
+pub async fn recommended_jobs(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(candidate_id): Path<Uuid>,
+    Query(params): Query<RecommendationQuery>,
+) -> AppResult<Json<Vec<RecommendedJob>>> {
+    let candidate = candidate_facts(&mut tx, claims.tid, &[candidate_id])
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound("Candidate not found".to_string()))?;
+
+    let mut jobs: Vec<RecommendedJob> = sqlx::query_as(
+        "SELECT j.id AS job_id, j.title, j.department, j.location_city, j.location_state, \
+         j.work_mode, j.employment_type, \
+         EXISTS ( \
+             SELECT 1 FROM applications a WHERE a.job_id = j.id AND a.candidate_id = $2 \
+         ) AS applied \
+         FROM job_posts j \
+         WHERE j.tenant_id = $1 AND j.status = 'open' \
+         AND ($3 OR NOT EXISTS ( \
+             SELECT 1 FROM applications a WHERE a.job_id = j.id AND a.candidate_id = $2 \
+         )) \
+         ORDER BY j.posted_at DESC NULLS LAST, j.created_at DESC, j.id \
+         LIMIT $4",
+    )
+    .bind(claims.tid)
+    .bind(candidate_id)
+    .bind(params.include_applied.unwrap_or(false))
+    .bind(MAX_SCANNED)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let ids: Vec<Uuid> = jobs.iter().map(|j| j.job_id).collect();
+    let facts: HashMap<Uuid, _> = job_facts(&mut tx, claims.tid, &ids)
+        .await?
+        .into_iter()
+        .map(|facts| (facts.id, facts))
+        .collect();
+    for job in &mut jobs {
+        if let Some(facts) = facts.get(&job.job_id) {
+            let matched = score::score(&candidate, facts);
+            job.match_score = matched.score;
+            job.match_reasons = matched.reasons;
+        }
+    }
+
+    Ok(Json(rank(jobs, &params, |j| j.match_score)))
+}
+
+/// Best matches first, keeping scan order between equal scores.
+fn rank<T>(mut items: Vec<T>, params: &RecommendationQuery, score: impl Fn(&T) -> f64) -> Vec<T> {
+    let min_score = params.min_score.unwrap_or(0.0);
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    items.retain(|item| score(item) >= min_score);
+    items.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    items.truncate(limit);
+    items
+}
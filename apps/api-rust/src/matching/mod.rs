@@ -0,0 +1,183 @@
+//! Candidate–job matching.
+//!
+//! [`score`] rates a candidate against a job from their skills, work mode,
+//! location and salary. Applications are scored when created and rescored
+//! whenever the candidate's profile or skills or the job change; open
+//! applications only, so closed ones keep the score they were decided on.
+
+pub mod handler;
+pub mod model;
+pub mod score;
+
+use std::collections::HashMap;
+
+use axum::{routing::get, Router};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::AppState;
+use score::{CandidateFacts, JobFacts, Match};
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/jobs/{id}/recommended-candidates",
+            get(handler::recommended_candidates),
+        )
+        .route(
+            "/candidates/{id}/recommended-jobs",
+            get(handler::recommended_jobs),
+        )
+}
+
+const CANDIDATE_FACT_COLUMNS: &str = "id, location_city, location_state, location_country, \
+    remote_preference, desired_salary_min_cents, desired_salary_max_cents, desired_currency";
+
+const JOB_FACT_COLUMNS: &str = "id, COALESCE(skills_required, '{}') AS skills_required, \
+    COALESCE(skills_preferred, '{}') AS skills_preferred, work_mode, location_city, \
+    location_state, location_country, salary_min_cents, salary_max_cents, salary_currency";
+
+/// Matching facts for the given candidates, with their skills.
+pub async fn candidate_facts(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    candidate_ids: &[Uuid],
+) -> Result<Vec<CandidateFacts>, sqlx::Error> {
+    let mut candidates: Vec<CandidateFacts> = sqlx::query_as(&format!(
+        "SELECT {} FROM candidate_profiles WHERE id = ANY($1) AND tenant_id = $2",
+        CANDIDATE_FACT_COLUMNS
+    ))
+    .bind(candidate_ids)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let skills: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT candidate_id, skill_name FROM candidate_skills \
+         WHERE candidate_id = ANY($1) AND tenant_id = $2 ORDER BY skill_name",
+    )
+    .bind(candidate_ids)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut by_candidate: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (candidate_id, skill) in skills {
+        by_candidate.entry(candidate_id).or_default().push(skill);
+    }
+    for candidate in &mut candidates {
+        candidate.skills = by_candidate.remove(&candidate.id).unwrap_or_default();
+    }
+    Ok(candidates)
+}
+
+/// Matching facts for the given jobs.
+pub async fn job_facts(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    job_ids: &[Uuid],
+) -> Result<Vec<JobFacts>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM job_posts WHERE id = ANY($1) AND tenant_id = $2",
+        JOB_FACT_COLUMNS
+    ))
+    .bind(job_ids)
+    .bind(tenant_id)
+    .fetch_all(conn)
+    .await
+}
+
+/// Score a candidate against a job; `None` if either does not exist.
+pub async fn score_pair(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    job_id: Uuid,
+    candidate_id: Uuid,
+) -> Result<Option<Match>, sqlx::Error> {
+    let candidate = candidate_facts(conn, tenant_id, &[candidate_id]).await?;
+    let job = job_facts(conn, tenant_id, &[job_id]).await?;
+    Ok(match (candidate.first(), job.first()) {
+        (Some(candidate), Some(job)) => Some(score::score(candidate, job)),
+        _ => None,
+    })
+}
+
+/// Rescore a candidate's open applications. Returns how many were updated.
+pub async fn rescore_candidate(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    candidate_id: Uuid,
+) -> Result<usize, sqlx::Error> {
+    rescore(conn, tenant_id, "candidate_id", candidate_id).await
+}
+
+/// Rescore a job's open applications. Returns how many were updated.
+pub async fn rescore_job(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    job_id: Uuid,
+) -> Result<usize, sqlx::Error> {
+    rescore(conn, tenant_id, "job_id", job_id).await
+}
+
+async fn rescore(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    column: &str,
+    id: Uuid,
+) -> Result<usize, sqlx::Error> {
+    let applications: Vec<(Uuid, Uuid, Uuid)> = sqlx::query_as(&format!(
+        "SELECT id, job_id, candidate_id FROM applications \
+         WHERE {} = $1 AND tenant_id = $2 AND status IN ('active', 'on_hold')",
+        column
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    if applications.is_empty() {
+        return Ok(0);
+    }
+
+    let job_ids: Vec<Uuid> = applications.iter().map(|(_, job_id, _)| *job_id).collect();
+    let candidate_ids: Vec<Uuid> = applications.iter().map(|(_, _, c)| *c).collect();
+    let jobs: HashMap<Uuid, JobFacts> = job_facts(conn, tenant_id, &job_ids)
+        .await?
+        .into_iter()
+        .map(|job| (job.id, job))
+        .collect();
+    let candidates: HashMap<Uuid, CandidateFacts> =
+        candidate_facts(conn, tenant_id, &candidate_ids)
+            .await?
+            .into_iter()
+            .map(|candidate| (candidate.id, candidate))
+            .collect();
+
+    let mut ids = Vec::with_capacity(applications.len());
+    let mut scores = Vec::with_capacity(applications.len());
+    let mut reasons = Vec::with_capacity(applications.len());
+    for (application_id, job_id, candidate_id) in applications {
+        let (Some(job), Some(candidate)) = (jobs.get(&job_id), candidates.get(&candidate_id))
+        else {
+            continue;
+        };
+        let matched = score::score(candidate, job);
+        ids.push(application_id);
+        scores.push(matched.score);
+        reasons.push(serde_json::to_value(&matched.reasons).expect("reasons serialize"));
+    }
+
+    sqlx::query(
+        "UPDATE applications a SET match_score = s.score::numeric, match_reasons = s.reasons, \
+         updated_at = NOW() \
+         FROM UNNEST($1::uuid[], $2::float8[], $3::jsonb[]) AS s(id, score, reasons) \
+         WHERE a.id = s.id AND a.tenant_id = $4",
+    )
+    .bind(&ids)
+    .bind(&scores)
+    .bind(&reasons)
+    .bind(tenant_id)
+    .execute(conn)
+    .await?;
+    Ok(ids.len())
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::score::Reason;
+
+#[derive(Debug, Deserialize)]
+pub struct RecommendationQuery {
+    pub limit: Option<i64>,
+    pub min_score: Option<f64>,
+    /// Include candidates who already applied, or jobs already applied to.
+    pub include_applied: Option<bool>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RecommendedCandidate {
+    pub candidate_id: Uuid,
+    pub candidate_name: String,
+    pub headline: Option<String>,
+    pub location_city: Option<String>,
+    pub location_state: Option<String>,
+    pub availability_status: Option<String>,
+    pub applied: bool,
+    #[sqlx(skip)]
+    pub match_score: f64,
+    #[sqlx(skip)]
+    pub match_reasons: Vec<Reason>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RecommendedJob {
+    pub job_id: Uuid,
+    pub title: String,
+    pub department: Option<String>,
+    pub location_city: Option<String>,
+    pub location_state: Option<String>,
+    pub work_mode: Option<String>,
+    pub employment_type: Option<String>,
+    pub applied: bool,
+    #[sqlx(skip)]
+    pub match_score: f64,
+    #[sqlx(skip)]
+    pub match_reasons: Vec<Reason>,
+}
@@ -0,0 +1,371 @@
+//! Scoring a candidate against a job.
+//!
+//! A score is the weighted average of the factors that apply to the job, from
+//! 0 to 100. Each factor scores 0 to 1 and is recorded as a [`Reason`] with a
+//! human-readable explanation, so the same inputs always give the same score
+//! and the score can be explained line by line.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What matching needs to know about a candidate.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct CandidateFacts {
+    pub id: Uuid,
+    #[sqlx(skip)]
+    pub skills: Vec<String>,
+    pub location_city: Option<String>,
+    pub location_state: Option<String>,
+    pub location_country: Option<String>,
+    pub remote_preference: Option<String>,
+    pub desired_salary_min_cents: Option<i64>,
+    pub desired_salary_max_cents: Option<i64>,
+    pub desired_currency: Option<String>,
+}
+
+/// What matching needs to know about a job.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct JobFacts {
+    pub id: Uuid,
+    pub skills_required: Vec<String>,
+    pub skills_preferred: Vec<String>,
+    pub work_mode: Option<String>,
+    pub location_city: Option<String>,
+    pub location_state: Option<String>,
+    pub location_country: Option<String>,
+    pub salary_min_cents: Option<i64>,
+    pub salary_max_cents: Option<i64>,
+    pub salary_currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Factor {
+    RequiredSkills,
+    PreferredSkills,
+    WorkMode,
+    Location,
+    Salary,
+}
+
+impl Factor {
+    /// Share of the score, out of 100, when every factor applies.
+    pub fn weight(self) -> f64 {
+        match self {
+            Self::RequiredSkills => 40.0,
+            Self::PreferredSkills => 15.0,
+            Self::WorkMode => 15.0,
+            Self::Location => 15.0,
+            Self::Salary => 15.0,
+        }
+    }
+}
+
+/// How one factor contributed, stored in `applications.match_reasons`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reason {
+    pub factor: Factor,
+    pub weight: f64,
+    /// From 0 to 1.
+    pub score: f64,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// From 0 to 100, to two decimal places.
+    pub score: f64,
+    pub reasons: Vec<Reason>,
+}
+
+/// Score a candidate against a job. Factors the job says nothing about (no
+/// skills listed, no salary band, no location for an on-site role) are left
+/// out rather than counted as a match or a miss.
+pub fn score(candidate: &CandidateFacts, job: &JobFacts) -> Match {
+    let reasons: Vec<Reason> = [
+        skills(
+            Factor::RequiredSkills,
+            "required",
+            &candidate.skills,
+            &job.skills_required,
+        ),
+        skills(
+            Factor::PreferredSkills,
+            "preferred",
+            &candidate.skills,
+            &job.skills_preferred,
+        ),
+        work_mode(candidate, job),
+        location(candidate, job),
+        salary(candidate, job),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let total: f64 = reasons.iter().map(|r| r.weight).sum();
+    let earned: f64 = reasons.iter().map(|r| r.weight * r.score).sum();
+    let score = if total > 0.0 {
+        (earned / total * 10_000.0).round() / 100.0
+    } else {
+        0.0
+    };
+    Match { score, reasons }
+}
+
+fn reason(factor: Factor, score: f64, detail: String) -> Reason {
+    Reason {
+        factor,
+        weight: factor.weight(),
+        score: (score * 10_000.0).round() / 10_000.0,
+        detail,
+        matched: Vec::new(),
+        missing: Vec::new(),
+    }
+}
+
+/// Skill names compare without case, punctuation or extra spaces, so
+/// "Node.js" matches "nodejs".
+pub fn normalize_skill(skill: &str) -> String {
+    skill
+        .to_lowercase()
+        .replace(['.', '-', '_'], "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn skills(factor: Factor, kind: &str, have: &[String], wanted: &[String]) -> Option<Reason> {
+    let wanted: Vec<&String> = wanted.iter().filter(|s| !s.trim().is_empty()).collect();
+    if wanted.is_empty() {
+        return None;
+    }
+    let have: Vec<String> = have.iter().map(|s| normalize_skill(s)).collect();
+    let (matched, missing): (Vec<&String>, Vec<&String>) = wanted
+        .iter()
+        .partition(|skill| have.contains(&normalize_skill(skill)));
+
+    let mut reason = reason(
+        factor,
+        matched.len() as f64 / wanted.len() as f64,
+        format!("Has {} of {} {} skills", matched.len(), wanted.len(), kind),
+    );
+    reason.matched = matched.into_iter().cloned().collect();
+    reason.missing = missing.into_iter().cloned().collect();
+    Some(reason)
+}
+
+fn work_mode(candidate: &CandidateFacts, job: &JobFacts) -> Option<Reason> {
+    let mode = job.work_mode.as_deref()?;
+    let preference = candidate.remote_preference.as_deref().unwrap_or("flexible");
+    let score = match (mode, preference) {
+        (_, "flexible") => 1.0,
+        (mode, preference) if mode == preference => 1.0,
+        ("remote", "hybrid") => 0.75,
+        ("remote", _) => 0.5,
+        ("hybrid", "onsite") => 0.75,
+        ("hybrid", _) => 0.5,
+        ("onsite", "hybrid") => 0.5,
+        _ => 0.0,
+    };
+    Some(reason(
+        Factor::WorkMode,
+        score,
+        format!("Job is {}; candidate prefers {}", mode, preference),
+    ))
+}
+
+fn location(candidate: &CandidateFacts, job: &JobFacts) -> Option<Reason> {
+    if job.work_mode.as_deref() == Some("remote") {
+        return None;
+    }
+    let same = |a: &Option<String>, b: &Option<String>| match (a, b) {
+        (Some(a), Some(b)) => a.trim().eq_ignore_ascii_case(b.trim()),
+        _ => false,
+    };
+    let job_place = [&job.location_city, &job.location_state]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if job_place.is_empty() {
+        return None;
+    }
+
+    let (score, detail) = if candidate.location_city.is_none() && candidate.location_state.is_none()
+    {
+        (
+            0.5,
+            format!("Candidate location unknown; job is in {}", job_place),
+        )
+    } else if same(&candidate.location_state, &job.location_state)
+        && (job.location_city.is_none() || same(&candidate.location_city, &job.location_city))
+    {
+        (1.0, format!("Located in {}", job_place))
+    } else if same(&candidate.location_state, &job.location_state) {
+        (0.6, format!("Same state as {}", job_place))
+    } else if same(&candidate.location_country, &job.location_country) {
+        (0.3, format!("Same country, outside {}", job_place))
+    } else {
+        (0.0, format!("Not located near {}", job_place))
+    };
+    Some(reason(Factor::Location, score, detail))
+}
+
+fn salary(candidate: &CandidateFacts, job: &JobFacts) -> Option<Reason> {
+    let ceiling = job.salary_max_cents.or(job.salary_min_cents)?;
+    let Some(floor) = candidate
+        .desired_salary_min_cents
+        .or(candidate.desired_salary_max_cents)
+    else {
+        return Some(reason(
+            Factor::Salary,
+            0.5,
+            "Candidate salary expectations unknown".to_string(),
+        ));
+    };
+    if let (Some(wanted), Some(offered)) = (&candidate.desired_currency, &job.salary_currency) {
+        if !wanted.eq_ignore_ascii_case(offered) {
+            return Some(reason(
+                Factor::Salary,
+                0.5,
+                format!("Candidate wants {}, job pays in {}", wanted, offered),
+            ));
+        }
+    }
+
+    if floor <= ceiling {
+        return Some(reason(
+            Factor::Salary,
+            1.0,
+            format!(
+                "Wants {}, within the job's {}",
+                dollars(floor),
+                band(job.salary_min_cents, job.salary_max_cents)
+            ),
+        ));
+    }
+    // Expectations 25% or more above the top of the band score nothing.
+    let over = (floor - ceiling) as f64 / ceiling.max(1) as f64;
+    Some(reason(
+        Factor::Salary,
+        (1.0 - over * 4.0).max(0.0),
+        format!(
+            "Wants {}, {:.0}% above the job's {}",
+            dollars(floor),
+            over * 100.0,
+            band(job.salary_min_cents, job.salary_max_cents)
+        ),
+    ))
+}
+
+fn dollars(cents: i64) -> String {
+    let whole = (cents / 100).to_string();
+    let mut out = String::with_capacity(whole.len() + whole.len() / 3 + 1);
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    format!("${}", out)
+}
+
+fn band(min: Option<i64>, max: Option<i64>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("{} – {} band", dollars(min), dollars(max)),
+        (None, Some(max)) => format!("maximum of {}", dollars(max)),
+        (Some(min), None) => format!("{} salary", dollars(min)),
+        (None, None) => "salary".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> JobFacts {
+        JobFacts {
+            skills_required: vec!["Rust".into(), "PostgreSQL".into(), "AWS".into()],
+            skills_preferred: vec!["Node.js".into()],
+            work_mode: Some("hybrid".into()),
+            location_city: Some("Austin".into()),
+            location_state: Some("TX".into()),
+            location_country: Some("US".into()),
+            salary_min_cents: Some(12_000_000),
+            salary_max_cents: Some(15_000_000),
+            salary_currency: Some("USD".into()),
+            ..Default::default()
+        }
+    }
+
+    fn candidate() -> CandidateFacts {
+        CandidateFacts {
+            skills: vec!["rust".into(), "PostgreSQL".into(), "nodejs".into()],
+            location_city: Some("austin".into()),
+            location_state: Some("TX".into()),
+            location_country: Some("US".into()),
+            remote_preference: Some("flexible".into()),
+            desired_salary_min_cents: Some(14_000_000),
+            desired_currency: Some("USD".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_score_is_weighted_average_of_factors() {
+        let result = score(&candidate(), &job());
+        // Required skills 2/3 of 40, everything else in full: 86.67 of 100.
+        assert_eq!(result.score, 86.67);
+        let required = &result.reasons[0];
+        assert_eq!(required.factor, Factor::RequiredSkills);
+        assert_eq!(required.detail, "Has 2 of 3 required skills");
+        assert_eq!(required.matched, ["Rust", "PostgreSQL"]);
+        assert_eq!(required.missing, ["AWS"]);
+        assert_eq!(result.reasons[1].matched, ["Node.js"]);
+        assert_eq!(
+            result.reasons[4].detail,
+            "Wants $140,000, within the job's $120,000 – $150,000 band"
+        );
+    }
+
+    #[test]
+    fn test_factors_the_job_does_not_specify_are_left_out() {
+        let job = JobFacts {
+            work_mode: Some("remote".into()),
+            ..Default::default()
+        };
+        let candidate = CandidateFacts {
+            remote_preference: Some("onsite".into()),
+            ..Default::default()
+        };
+        let result = score(&candidate, &job);
+        assert_eq!(result.reasons.len(), 1);
+        assert_eq!(result.reasons[0].factor, Factor::WorkMode);
+        assert_eq!(result.score, 50.0);
+    }
+
+    #[test]
+    fn test_salary_expectations_above_the_band_lose_points() {
+        let mut candidate = candidate();
+        candidate.desired_salary_min_cents = Some(16_500_000);
+        let reason = score(&candidate, &job()).reasons.pop().unwrap();
+        assert_eq!(reason.factor, Factor::Salary);
+        assert_eq!(reason.score, 0.6);
+        assert_eq!(
+            reason.detail,
+            "Wants $165,000, 10% above the job's $120,000 – $150,000 band"
+        );
+
+        candidate.location_state = Some("CA".into());
+        candidate.remote_preference = Some("remote".into());
+        let result = score(&candidate, &job());
+        assert_eq!(result.reasons[2].score, 0.5);
+        assert_eq!(result.reasons[3].score, 0.3);
+    }
+}
@@ -0,0 +1,388 @@
+//! Meetings as iCalendar: per-meeting invites, subscription feeds, and
+//! attendee replies flowing back.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use rand::Rng;
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::handler::MEETING_COLUMNS;
+use super::ics::{self, Attendee, Event, Method};
+use super::model::MeetingRequest;
+use super::scheduling::hash_token;
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::scheduler::tenant_tx;
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct ParticipantRow {
+    meeting_id: Uuid,
+    user_id: Uuid,
+    email: String,
+    name: String,
+    role: String,
+    response_status: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarFeed {
+    pub url: String,
+    /// The same feed as a `webcal://` link calendar apps subscribe to.
+    pub webcal_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplyResult {
+    pub meeting_id: Uuid,
+    pub updated: Vec<ReplyUpdate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplyUpdate {
+    pub user_id: Uuid,
+    pub email: String,
+    pub response_status: &'static str,
+}
+
+/// Host used in event UIDs, so they are unique beyond this deployment.
+fn uid_host(state: &AppState) -> &str {
+    let base = state.config.app_base_url.as_str();
+    let host = base.split_once("://").map_or(base, |(_, rest)| rest);
+    host.split(['/', ':']).next().unwrap_or("localhost")
+}
+
+fn uid(meeting_id: Uuid, host: &str) -> String {
+    format!("{}@{}", meeting_id, host)
+}
+
+/// The event's SEQUENCE: one per reschedule, and one more once cancelled.
+fn sequence(meeting: &MeetingRequest, reschedules: i64) -> i64 {
+    reschedules + i64::from(matches!(meeting.status.as_str(), "cancelled" | "denied"))
+}
+
+async fn reschedule_counts(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    meeting_ids: &[Uuid],
+) -> AppResult<Vec<(Uuid, i64)>> {
+    Ok(sqlx::query_as(
+        "SELECT meeting_id, COUNT(*) FROM meeting_reschedule_events \
+         WHERE tenant_id = $1 AND meeting_id = ANY($2) GROUP BY meeting_id",
+    )
+    .bind(tenant_id)
+    .bind(meeting_ids)
+    .fetch_all(conn)
+    .await?)
+}
+
+async fn participants(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    meeting_ids: &[Uuid],
+) -> AppResult<Vec<ParticipantRow>> {
+    Ok(sqlx::query_as(
+        "SELECT mp.meeting_id, mp.user_id, u.email, \
+         CONCAT_WS(' ', u.first_name, u.last_name) AS name, \
+         COALESCE(mp.role, 'attendee') AS role, \
+         COALESCE(mp.response_status, 'pending') AS response_status \
+         FROM meeting_participants mp JOIN users u ON u.id = mp.user_id \
+         WHERE mp.tenant_id = $1 AND mp.meeting_id = ANY($2) \
+         ORDER BY mp.created_at",
+    )
+    .bind(tenant_id)
+    .bind(meeting_ids)
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Build events for `meetings`; meetings without a confirmed time are left
+/// out.
+async fn events(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    host: &str,
+    meetings: &[MeetingRequest],
+) -> AppResult<Vec<Event>> {
+    let ids: Vec<Uuid> = meetings.iter().map(|m| m.id).collect();
+    let counts = reschedule_counts(conn, tenant_id, &ids).await?;
+    let people = participants(conn, tenant_id, &ids).await?;
+    let now = Utc::now();
+
+    Ok(meetings
+        .iter()
+        .filter_map(|meeting| {
+            let start = meeting.accepted_time?;
+            let reschedules = counts
+                .iter()
+                .find(|(id, _)| *id == meeting.id)
+                .map_or(0, |(_, n)| *n);
+            let attendee = |p: &ParticipantRow| Attendee {
+                email: p.email.clone(),
+                name: Some(p.name.clone()),
+                partstat: ics::partstat(&p.response_status),
+            };
+            let on_meeting = people.iter().filter(|p| p.meeting_id == meeting.id);
+            let organizer = on_meeting
+                .clone()
+                .find(|p| p.user_id == meeting.requested_by || p.role == "organizer")
+                .map(attendee);
+            let attendees = on_meeting
+                .filter(|p| Some(&p.email) != organizer.as_ref().map(|o| &o.email))
+                .map(attendee)
+                .collect();
+            Some(Event {
+                uid: uid(meeting.id, host),
+                sequence: sequence(meeting, reschedules),
+                stamp: now,
+                start,
+                end: start + Duration::minutes(meeting.duration_minutes.max(1) as i64),
+                summary: meeting.title.clone(),
+                description: meeting.description.clone(),
+                location: meeting
+                    .location
+                    .clone()
+                    .or_else(|| meeting.meeting_url.clone()),
+                url: meeting.meeting_url.clone(),
+                status: match meeting.status.as_str() {
+                    "accepted" | "completed" => "CONFIRMED",
+                    "cancelled" | "denied" => "CANCELLED",
+                    _ => "TENTATIVE",
+                },
+                categories: Some(if meeting.application_id.is_some() {
+                    "INTERVIEW"
+                } else {
+                    "MEETING"
+                }),
+                organizer,
+                attendees,
+            })
+        })
+        .collect())
+}
+
+fn calendar_response(body: String, filename: Option<&str>) -> Response {
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        body,
+    )
+        .into_response();
+    if let Some(filename) = filename {
+        if let Ok(value) = format!("attachment; filename=\"{}\"", filename).parse() {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_DISPOSITION, value);
+        }
+    }
+    response
+}
+
+/// GET /meetings/{id}/ics — the meeting as an invite, or as a cancellation
+/// once cancelled or denied.
+pub async fn meeting_ics(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Response> {
+    let meeting: MeetingRequest = sqlx::query_as(&format!(
+        "SELECT {} FROM meeting_requests WHERE id = $1 AND tenant_id = $2",
+        MEETING_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
+
+    let method = match meeting.status.as_str() {
+        "cancelled" | "denied" => Method::Cancel,
+        _ => Method::Request,
+    };
+    let events = events(&mut tx, claims.tid, uid_host(&state), &[meeting]).await?;
+    if events.is_empty() {
+        return Err(AppError::Conflict(
+            "Meeting has no confirmed time yet".to_string(),
+        ));
+    }
+
+    Ok(calendar_response(
+        ics::calendar(method, None, &events),
+        Some("meeting.ics"),
+    ))
+}
+
+/// POST /meetings/calendar-feed — a new subscribe URL for the caller's
+/// meetings. Any earlier URL stops working.
+pub async fn create_calendar_feed(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+) -> AppResult<(StatusCode, Json<CalendarFeed>)> {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    let token = hex::encode(bytes);
+
+    sqlx::query(
+        "INSERT INTO calendar_feed_tokens (tenant_id, user_id, token_hash) \
+         VALUES ($1, $2, $3) \
+         ON CONFLICT (user_id) DO UPDATE \
+         SET token_hash = EXCLUDED.token_hash, last_used_at = NULL, created_at = NOW()",
+    )
+    .bind(claims.tid)
+    .bind(claims.sub)
+    .bind(hash_token(&token))
+    .execute(&mut *tx)
+    .await?;
+
+    let url = format!(
+        "{}/api/v1/public/calendar/{}.ics",
+        state.config.api_base_url.trim_end_matches('/'),
+        token
+    );
+    let webcal_url = match url.split_once("://") {
+        Some((_, rest)) => format!("webcal://{}", rest),
+        None => url.clone(),
+    };
+    Ok((StatusCode::CREATED, Json(CalendarFeed { url, webcal_url })))
+}
+
+/// DELETE /meetings/calendar-feed — stop serving the caller's feed.
+pub async fn delete_calendar_feed(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+) -> AppResult<StatusCode> {
+    sqlx::query("DELETE FROM calendar_feed_tokens WHERE tenant_id = $1 AND user_id = $2")
+        .bind(claims.tid)
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /public/calendar/{token} — the feed owner's upcoming accepted
+/// meetings and interviews.
+pub async fn calendar_feed(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Response> {
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+    let (tenant_id, user_id): (Uuid, Uuid) =
+        sqlx::query_as("SELECT tenant_id, user_id FROM calendar_feed_tokens WHERE token_hash = $1")
+            .bind(hash_token(token))
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Calendar feed not found".to_string()))?;
+
+    let mut tx = tenant_tx(&state.db, tenant_id).await?;
+    let meetings: Vec<MeetingRequest> = sqlx::query_as(&format!(
+        "SELECT {} FROM meeting_requests \
+         WHERE tenant_id = $1 AND status = 'accepted' AND accepted_time IS NOT NULL \
+         AND accepted_time + make_interval(mins => COALESCE(duration_minutes, 60)) > NOW() \
+         AND id IN (SELECT meeting_id FROM meeting_participants \
+                    WHERE user_id = $2 AND response_status <> 'declined') \
+         ORDER BY accepted_time",
+        MEETING_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let events = events(&mut tx, tenant_id, uid_host(&state), &meetings).await?;
+    sqlx::query("UPDATE calendar_feed_tokens SET last_used_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(calendar_response(
+        ics::calendar(Method::Publish, Some("Meetings"), &events),
+        None,
+    ))
+}
+
+/// POST /meetings/ics-reply — apply an attendee's iCalendar `REPLY`, as
+/// received by email, to their participant response. Admin only; meant
+/// for the inbound mail integration.
+pub async fn ingest_reply(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    body: String,
+) -> AppResult<Json<ReplyResult>> {
+    require_role(&claims, "admin")?;
+    let reply = ics::parse_reply(&body).map_err(AppError::Validation)?;
+    let meeting_id = reply
+        .uid
+        .split('@')
+        .next()
+        .and_then(|id| id.parse::<Uuid>().ok())
+        .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
+
+    let meeting: MeetingRequest = sqlx::query_as(&format!(
+        "SELECT {} FROM meeting_requests WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        MEETING_COLUMNS
+    ))
+    .bind(meeting_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
+
+    let reschedules = reschedule_counts(&mut tx, claims.tid, &[meeting.id])
+        .await?
+        .first()
+        .map_or(0, |(_, n)| *n);
+    if reply.sequence < sequence(&meeting, reschedules) {
+        return Err(AppError::Conflict(
+            "Reply is for an earlier version of the meeting".to_string(),
+        ));
+    }
+
+    let mut updated = Vec::new();
+    for (email, partstat) in &reply.attendees {
+        let Some(status) = ics::response_status(partstat) else {
+            continue;
+        };
+        let user_id: Option<Uuid> = sqlx::query_scalar(
+            "UPDATE meeting_participants mp \
+             SET response_status = $4, responded_at = NOW() \
+             FROM users u \
+             WHERE u.id = mp.user_id AND LOWER(u.email) = $3 \
+             AND mp.meeting_id = $1 AND mp.tenant_id = $2 \
+             RETURNING mp.user_id",
+        )
+        .bind(meeting.id)
+        .bind(claims.tid)
+        .bind(email)
+        .bind(status)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(user_id) = user_id {
+            updated.push(ReplyUpdate {
+                user_id,
+                email: email.clone(),
+                response_status: status,
+            });
+        }
+    }
+    if updated.is_empty() {
+        return Err(AppError::Validation(
+            "The reply names no participant of this meeting".to_string(),
+        ));
+    }
+
+    Ok(Json(ReplyResult {
+        meeting_id: meeting.id,
+        updated,
+    }))
+}
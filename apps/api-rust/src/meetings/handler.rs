@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -8,10 +8,15 @@ use uuid::Uuid;
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
 use crate::meetings::model::*;
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
+
+pub(crate) const MEETING_COLUMNS: &str = "id, tenant_id, title, description, requested_by, \
+    meeting_type, duration_minutes, location, meeting_url, status, proposed_times, \
+    accepted_time, accepted_timezone, application_id, conversation_id, cancellation_reason, \
+    created_at, updated_at";
 
 pub async fn create_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateMeetingRequest>,
 ) -> AppResult<(StatusCode, Json<MeetingRequest>)> {
@@ -39,7 +44,7 @@ pub async fn create_meeting(
     .bind(payload.location.as_deref())
     .bind(&proposed_times_json)
     .bind(payload.application_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Insert organizer participant
@@ -50,7 +55,7 @@ pub async fn create_meeting(
     .bind(claims.tid)
     .bind(meeting.id)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Insert attendee participants
@@ -63,7 +68,7 @@ pub async fn create_meeting(
             .bind(claims.tid)
             .bind(meeting.id)
             .bind(user_id)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
@@ -72,7 +77,7 @@ pub async fn create_meeting(
 }
 
 pub async fn list_meetings(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListMeetingsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -97,7 +102,7 @@ pub async fn list_meetings(
     .bind(per_page)
     .bind(offset)
     .bind(status_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -109,7 +114,7 @@ pub async fn list_meetings(
     .bind(claims.tid)
     .bind(claims.sub)
     .bind(status_filter)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -124,7 +129,7 @@ pub async fn list_meetings(
 }
 
 pub async fn get_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -138,7 +143,7 @@ pub async fn get_meeting(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
 
@@ -150,7 +155,7 @@ pub async fn get_meeting(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -160,7 +165,7 @@ pub async fn get_meeting(
 }
 
 pub async fn accept_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<AcceptMeetingRequest>,
@@ -182,7 +187,7 @@ pub async fn accept_meeting(
     .bind(claims.tid)
     .bind(accepted_time)
     .bind(&payload.timezone)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
 
@@ -194,14 +199,14 @@ pub async fn accept_meeting(
     .bind(id)
     .bind(claims.sub)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(meeting))
 }
 
 pub async fn deny_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<DenyMeetingRequest>,
@@ -218,7 +223,7 @@ pub async fn deny_meeting(
     .bind(id)
     .bind(claims.tid)
     .bind(payload.reason.as_deref())
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
 
@@ -230,14 +235,14 @@ pub async fn deny_meeting(
     .bind(id)
     .bind(claims.sub)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(meeting))
 }
 
 pub async fn reschedule_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<RescheduleMeetingRequest>,
@@ -245,17 +250,19 @@ pub async fn reschedule_meeting(
     let new_proposed_times =
         serde_json::to_value(&payload.proposed_times).unwrap_or_else(|_| serde_json::json!([]));
 
-    // Insert reschedule event
+    // Insert reschedule event; each one bumps the meeting's iCalendar SEQUENCE
     sqlx::query(
-        "INSERT INTO meeting_reschedule_events (tenant_id, meeting_id, rescheduled_by, reason, new_proposed_times) \
-         VALUES ($1, $2, $3, $4, $5)",
+        "INSERT INTO meeting_reschedule_events \
+         (tenant_id, meeting_id, requested_by, reason, proposed_times, original_time) \
+         SELECT $1, $2, $3, $4, $5, accepted_time FROM meeting_requests \
+         WHERE id = $2 AND tenant_id = $1",
     )
     .bind(claims.tid)
     .bind(id)
     .bind(claims.sub)
     .bind(payload.reason.as_deref())
     .bind(&new_proposed_times)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Update meeting status and proposed times
@@ -271,7 +278,7 @@ pub async fn reschedule_meeting(
     .bind(id)
     .bind(claims.tid)
     .bind(&new_proposed_times)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
 
@@ -279,7 +286,7 @@ pub async fn reschedule_meeting(
 }
 
 pub async fn cancel_meeting(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<MeetingRequest>> {
@@ -294,7 +301,7 @@ pub async fn cancel_meeting(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))?;
 
@@ -0,0 +1,370 @@
+//! iCalendar (RFC 5545) invites and feeds, and parsing attendee replies.
+
+use chrono::{DateTime, Utc};
+
+const PRODID: &str = "-//CPA Platform//Meetings//EN";
+/// Longest line, in octets, before it is folded.
+const FOLD_AT: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// An invite, or an update to one.
+    Request,
+    Cancel,
+    /// A read-only calendar such as a subscription feed.
+    Publish,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Request => "REQUEST",
+            Method::Cancel => "CANCEL",
+            Method::Publish => "PUBLISH",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Attendee {
+    pub email: String,
+    pub name: Option<String>,
+    pub partstat: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub uid: String,
+    /// Bumped each time the meeting is rescheduled or cancelled.
+    pub sequence: i64,
+    pub stamp: DateTime<Utc>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub url: Option<String>,
+    /// `CONFIRMED`, `TENTATIVE` or `CANCELLED`.
+    pub status: &'static str,
+    pub categories: Option<&'static str>,
+    pub organizer: Option<Attendee>,
+    pub attendees: Vec<Attendee>,
+}
+
+/// The participation status for a `meeting_participants.response_status`.
+pub fn partstat(response_status: &str) -> &'static str {
+    match response_status {
+        "accepted" => "ACCEPTED",
+        "declined" => "DECLINED",
+        "tentative" => "TENTATIVE",
+        _ => "NEEDS-ACTION",
+    }
+}
+
+/// The `meeting_participants.response_status` for a participation status.
+pub fn response_status(partstat: &str) -> Option<&'static str> {
+    match partstat.to_ascii_uppercase().as_str() {
+        "ACCEPTED" => Some("accepted"),
+        "DECLINED" => Some("declined"),
+        "TENTATIVE" => Some("tentative"),
+        "NEEDS-ACTION" => Some("pending"),
+        _ => None,
+    }
+}
+
+fn format_time(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A quoted parameter value; DQUOTE cannot be escaped, so it is dropped.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace(['"', '\r', '\n'], ""))
+}
+
+/// Append `line` folded to [`FOLD_AT`] octets, without splitting a
+/// character, and terminated by CRLF.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > FOLD_AT {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn address(property: &str, person: &Attendee, extra: &str) -> String {
+    let mut line = property.to_string();
+    if let Some(name) = person.name.as_deref().filter(|n| !n.is_empty()) {
+        line.push_str(";CN=");
+        line.push_str(&quote(name));
+    }
+    line.push_str(extra);
+    line.push_str(":mailto:");
+    line.push_str(&person.email);
+    line
+}
+
+/// A VCALENDAR holding `events`. `name` labels subscription feeds.
+pub fn calendar(method: Method, name: Option<&str>, events: &[Event]) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{}", PRODID));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, &format!("METHOD:{}", method.as_str()));
+    if let Some(name) = name {
+        push_line(&mut out, &format!("X-WR-CALNAME:{}", escape(name)));
+    }
+    for event in events {
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}", event.uid));
+        push_line(&mut out, &format!("SEQUENCE:{}", event.sequence));
+        push_line(&mut out, &format!("DTSTAMP:{}", format_time(event.stamp)));
+        push_line(&mut out, &format!("DTSTART:{}", format_time(event.start)));
+        push_line(&mut out, &format!("DTEND:{}", format_time(event.end)));
+        push_line(&mut out, &format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(description) = &event.description {
+            push_line(&mut out, &format!("DESCRIPTION:{}", escape(description)));
+        }
+        if let Some(location) = &event.location {
+            push_line(&mut out, &format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(url) = &event.url {
+            push_line(&mut out, &format!("URL:{}", url));
+        }
+        push_line(&mut out, &format!("STATUS:{}", event.status));
+        if let Some(categories) = event.categories {
+            push_line(&mut out, &format!("CATEGORIES:{}", categories));
+        }
+        if let Some(organizer) = &event.organizer {
+            push_line(&mut out, &address("ORGANIZER", organizer, ""));
+        }
+        for attendee in &event.attendees {
+            let rsvp = if method == Method::Request {
+                ";RSVP=TRUE"
+            } else {
+                ""
+            };
+            let extra = format!(
+                ";ROLE=REQ-PARTICIPANT;PARTSTAT={}{}",
+                attendee.partstat, rsvp
+            );
+            push_line(&mut out, &address("ATTENDEE", attendee, &extra));
+        }
+        push_line(&mut out, "END:VEVENT");
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// An attendee's answer to an invite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub uid: String,
+    pub sequence: i64,
+    /// (lower-cased email, PARTSTAT) for each attendee in the reply.
+    pub attendees: Vec<(String, String)>,
+}
+
+/// Split `s` on `sep`, ignoring separators inside double quotes.
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == sep && !quoted {
+            parts.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// One content line: name, parameters and value.
+fn parse_line(line: &str) -> Option<(String, Vec<(String, String)>, String)> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = split_unquoted(head, ';').into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| {
+            (
+                k.trim().to_ascii_uppercase(),
+                v.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some((name, params, value.to_string()))
+}
+
+/// Parse an iTIP `REPLY` for the first event it contains.
+pub fn parse_reply(input: &str) -> Result<Reply, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in input.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+
+    let (mut method, mut in_event, mut done) = (None, false, false);
+    let (mut uid, mut sequence, mut attendees) = (None, 0, Vec::new());
+    for line in &lines {
+        let Some((name, params, value)) = parse_line(line) else {
+            continue;
+        };
+        match name.as_str() {
+            "METHOD" => method = Some(value.trim().to_ascii_uppercase()),
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") && !done => in_event = true,
+            "END" if value.eq_ignore_ascii_case("VEVENT") && in_event => {
+                in_event = false;
+                done = true;
+            }
+            "UID" if in_event => uid = Some(value.trim().to_string()),
+            "SEQUENCE" if in_event => {
+                sequence = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| "SEQUENCE is not a number".to_string())?
+            }
+            "ATTENDEE" if in_event => {
+                let email = value.trim();
+                let email = if email.len() > 7 && email[..7].eq_ignore_ascii_case("mailto:") {
+                    &email[7..]
+                } else {
+                    email
+                };
+                let partstat = params
+                    .iter()
+                    .find(|(k, _)| k == "PARTSTAT")
+                    .map_or("NEEDS-ACTION".to_string(), |(_, v)| v.to_ascii_uppercase());
+                attendees.push((email.to_ascii_lowercase(), partstat));
+            }
+            _ => {}
+        }
+    }
+
+    if method.as_deref() != Some("REPLY") {
+        return Err("METHOD must be REPLY".to_string());
+    }
+    let uid = uid.ok_or("the event has no UID")?;
+    if attendees.is_empty() {
+        return Err("the reply has no ATTENDEE".to_string());
+    }
+    Ok(Reply {
+        uid,
+        sequence,
+        attendees,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event() -> Event {
+        let start = Utc.with_ymd_and_hms(2030, 3, 11, 16, 0, 0).unwrap();
+        Event {
+            uid: "abc@example.com".to_string(),
+            sequence: 2,
+            stamp: start,
+            start,
+            end: start + chrono::Duration::hours(1),
+            summary: "Interview: Ada, Senior Accountant".to_string(),
+            description: Some("Bring a laptop; we'll\nsupply coffee".to_string()),
+            location: None,
+            url: None,
+            status: "CONFIRMED",
+            categories: Some("INTERVIEW"),
+            organizer: Some(Attendee {
+                email: "grace@example.com".to_string(),
+                name: Some("Grace \"G\" Test".to_string()),
+                partstat: "ACCEPTED",
+            }),
+            attendees: vec![Attendee {
+                email: "a-very-long-address-for-folding-purposes@interviewers.example.com"
+                    .to_string(),
+                name: Some("Nora Test".to_string()),
+                partstat: "NEEDS-ACTION",
+            }],
+        }
+    }
+
+    #[test]
+    fn test_writes_escaped_folded_invites() {
+        let ics = calendar(Method::Request, None, &[event()]);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains("METHOD:REQUEST\r\n"));
+        assert!(ics.contains("SEQUENCE:2\r\n"));
+        assert!(ics.contains("DTSTART:20300311T160000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Interview: Ada\\, Senior Accountant\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bring a laptop\\; we'll\\nsupply coffee\r\n"));
+        assert!(ics.contains("ORGANIZER;CN=\"Grace G Test\":mailto:grace@example.com\r\n"));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "{:?}", line);
+        }
+        // The folded attendee reads back as one line.
+        assert!(ics
+            .replace("\r\n ", "")
+            .contains("PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:a-very-long-address"));
+
+        let cancel = calendar(Method::Cancel, None, &[event()]);
+        assert!(cancel.contains("METHOD:CANCEL\r\n"));
+        assert!(!cancel.contains("RSVP"));
+    }
+
+    #[test]
+    fn test_parses_replies() {
+        let reply = "BEGIN:VCALENDAR\r\nMETHOD:REPLY\r\nBEGIN:VEVENT\r\n\
+             UID:abc@example.com\r\nSEQUENCE:2\r\n\
+             ATTENDEE;CN=\"Test, Nora\";PARTSTAT=declined:MAILTO:No\r\n ra@Example.com\r\n\
+             END:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(
+            parse_reply(reply),
+            Ok(Reply {
+                uid: "abc@example.com".to_string(),
+                sequence: 2,
+                attendees: vec![("nora@example.com".to_string(), "DECLINED".to_string())],
+            })
+        );
+        assert_eq!(response_status("DECLINED"), Some("declined"));
+
+        let invite = calendar(Method::Request, None, &[event()]);
+        assert_eq!(
+            parse_reply(&invite),
+            Err("METHOD must be REPLY".to_string())
+        );
+    }
+}
@@ -1,2 +1,57 @@
+pub mod calendar;
 pub mod handler;
+pub mod ics;
 pub mod model;
+pub mod scheduling;
+pub mod slots;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Public routes (no auth), nested under `/api/v1`.
+pub fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/public/scheduling/{token}",
+            get(scheduling::get_scheduling_page),
+        )
+        .route("/public/scheduling/{token}", post(scheduling::book_slot))
+        .route("/public/calendar/{token}", get(calendar::calendar_feed))
+}
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/meetings/request", post(handler::create_meeting))
+        .route("/meetings", get(handler::list_meetings))
+        .route("/meetings/{id}", get(handler::get_meeting))
+        .route("/meetings/{id}/accept", post(handler::accept_meeting))
+        .route("/meetings/{id}/deny", post(handler::deny_meeting))
+        .route(
+            "/meetings/{id}/reschedule",
+            post(handler::reschedule_meeting),
+        )
+        .route("/meetings/{id}/cancel", post(handler::cancel_meeting))
+        .route("/meetings/availability", get(scheduling::get_availability))
+        .route("/meetings/availability", put(scheduling::set_availability))
+        .route("/meetings/slots", post(scheduling::find_slots))
+        .route("/meetings/{id}/slots", get(scheduling::meeting_slots))
+        .route(
+            "/meetings/{id}/scheduling-link",
+            post(scheduling::create_scheduling_link),
+        )
+        .route("/meetings/{id}/ics", get(calendar::meeting_ics))
+        .route(
+            "/meetings/calendar-feed",
+            post(calendar::create_calendar_feed),
+        )
+        .route(
+            "/meetings/calendar-feed",
+            delete(calendar::delete_calendar_feed),
+        )
+        .route("/meetings/ics-reply", post(calendar::ingest_reply))
+}
@@ -71,3 +71,94 @@ pub struct ListMeetingsQuery {
     pub per_page: Option<i64>,
     pub status: Option<String>,
 }
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AvailabilityBlock {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub day_of_week: Option<i16>,
+    pub start_time: chrono::NaiveTime,
+    pub end_time: chrono::NaiveTime,
+    pub timezone: Option<String>,
+    pub is_recurring: Option<bool>,
+    pub specific_date: Option<chrono::NaiveDate>,
+    pub is_blocked: Option<bool>,
+}
+
+/// One window of a user's availability. Recurring windows repeat on
+/// `day_of_week` (0 = Sunday); a `specific_date` applies once. Blocked
+/// windows are taken out of the user's availability.
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityBlockInput {
+    pub day_of_week: Option<i16>,
+    pub start_time: chrono::NaiveTime,
+    pub end_time: chrono::NaiveTime,
+    pub timezone: String,
+    pub specific_date: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    pub is_blocked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetAvailabilityRequest {
+    pub blocks: Vec<AvailabilityBlockInput>,
+}
+
+/// The range to search for slots; defaults to the next two weeks.
+#[derive(Debug, Default, Deserialize)]
+pub struct SlotWindowQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub step_minutes: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FindSlotsRequest {
+    pub participant_user_ids: Vec<Uuid>,
+    pub duration_minutes: Option<i32>,
+    #[serde(flatten)]
+    pub window: SlotWindowQuery,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSchedulingLinkRequest {
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulingLink {
+    pub token: String,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What a candidate sees when opening a scheduling link.
+#[derive(Debug, Serialize)]
+pub struct PublicSchedulingPage {
+    pub title: String,
+    pub description: Option<String>,
+    pub meeting_type: String,
+    pub duration_minutes: i32,
+    pub location: Option<String>,
+    pub organizer_name: String,
+    pub expires_at: DateTime<Utc>,
+    pub slots: Vec<super::slots::Slot>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BookSlotRequest {
+    pub starts_at: DateTime<Utc>,
+    pub timezone: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicBooking {
+    pub title: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub timezone: String,
+    pub meeting_type: String,
+    pub location: Option<String>,
+    pub meeting_url: Option<String>,
+}
@@ -0,0 +1,503 @@
+//! Availability, slot search and candidate self-scheduling links.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::handler::MEETING_COLUMNS;
+use super::model::*;
+use super::slots::{self, Interval, Slot};
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult, FieldError};
+use crate::middleware::tenant::TenantTx;
+use crate::notifications::notify::{notify, NewNotification};
+use crate::scheduler::tenant_tx;
+use crate::AppState;
+
+const AVAILABILITY_COLUMNS: &str = "id, user_id, day_of_week, start_time, end_time, timezone, \
+    is_recurring, specific_date, is_blocked";
+
+const LINK_EXPIRY_DAYS: i64 = 7;
+/// Longest range a single slot search may cover.
+const MAX_WINDOW_DAYS: i64 = 62;
+const MAX_LIMIT: usize = 100;
+
+/// Resolve a search range: from now (or later) for two weeks by default.
+fn window(q: &SlotWindowQuery) -> AppResult<(DateTime<Utc>, DateTime<Utc>, Duration, usize)> {
+    let now = Utc::now();
+    let from = q.from.map_or(now, |from| from.max(now));
+    let to =
+        q.to.unwrap_or(from + Duration::days(slots::DEFAULT_HORIZON_DAYS));
+    let step = q.step_minutes.unwrap_or(slots::DEFAULT_STEP_MINUTES);
+    let limit = q.limit.unwrap_or(slots::DEFAULT_LIMIT);
+
+    let mut errors = Vec::new();
+    if to <= from {
+        errors.push(FieldError::new(
+            "to",
+            "must be in the future and after from",
+        ));
+    } else if to - from > Duration::days(MAX_WINDOW_DAYS) {
+        errors.push(FieldError::new(
+            "to",
+            format!("range cannot exceed {} days", MAX_WINDOW_DAYS),
+        ));
+    }
+    if !(5..=240).contains(&step) {
+        errors.push(FieldError::new("step_minutes", "must be between 5 and 240"));
+    }
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        errors.push(FieldError::new(
+            "limit",
+            format!("must be between 1 and {}", MAX_LIMIT),
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+    Ok((from, to, Duration::minutes(step), limit))
+}
+
+fn validate_duration(minutes: i32) -> AppResult<Duration> {
+    if !(5..=480).contains(&minutes) {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "duration_minutes",
+            "must be between 5 and 480",
+        )]));
+    }
+    Ok(Duration::minutes(minutes as i64))
+}
+
+async fn is_timezone(conn: &mut PgConnection, name: &str) -> AppResult<bool> {
+    Ok(
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(name)
+            .fetch_one(conn)
+            .await?,
+    )
+}
+
+async fn load_meeting(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> AppResult<MeetingRequest> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM meeting_requests WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        MEETING_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Meeting not found".to_string()))
+}
+
+/// Participants who have not declined.
+async fn participant_ids(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    meeting_id: Uuid,
+) -> AppResult<Vec<Uuid>> {
+    Ok(sqlx::query_scalar(
+        "SELECT user_id FROM meeting_participants \
+         WHERE tenant_id = $1 AND meeting_id = $2 AND response_status <> 'declined' \
+         ORDER BY user_id",
+    )
+    .bind(tenant_id)
+    .bind(meeting_id)
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Ranked slots for everyone on `meeting`.
+async fn slots_for_meeting(
+    conn: &mut PgConnection,
+    meeting: &MeetingRequest,
+    q: &SlotWindowQuery,
+) -> AppResult<Vec<Slot>> {
+    let (from, to, step, limit) = window(q)?;
+    let duration = Duration::minutes(meeting.duration_minutes.max(5) as i64);
+    let users = participant_ids(conn, meeting.tenant_id, meeting.id).await?;
+    let free = slots::common_free_time(conn, meeting.tenant_id, &users, from, to, Some(meeting.id))
+        .await?;
+    Ok(slots::rank(&free, duration, step, from, to, limit))
+}
+
+/// GET /meetings/availability — the caller's availability blocks.
+pub async fn get_availability(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+) -> AppResult<Json<Vec<AvailabilityBlock>>> {
+    let blocks: Vec<AvailabilityBlock> = sqlx::query_as(&format!(
+        "SELECT {} FROM meeting_availability_blocks \
+         WHERE tenant_id = $1 AND user_id = $2 \
+         ORDER BY specific_date NULLS FIRST, day_of_week, start_time",
+        AVAILABILITY_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(claims.sub)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(blocks))
+}
+
+/// PUT /meetings/availability — replace the caller's availability blocks.
+pub async fn set_availability(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<SetAvailabilityRequest>,
+) -> AppResult<Json<Vec<AvailabilityBlock>>> {
+    let mut errors = Vec::new();
+    for (i, block) in payload.blocks.iter().enumerate() {
+        let path = |field: &str| format!("blocks[{}].{}", i, field);
+        match (block.day_of_week, block.specific_date) {
+            (None, None) => errors.push(FieldError::new(
+                path("day_of_week"),
+                "day_of_week or specific_date is required",
+            )),
+            (Some(day), _) if !(0..=6).contains(&day) => errors.push(FieldError::new(
+                path("day_of_week"),
+                "must be between 0 (Sunday) and 6 (Saturday)",
+            )),
+            _ => {}
+        }
+        if block.start_time == block.end_time {
+            errors.push(FieldError::new(
+                path("end_time"),
+                "must differ from start_time",
+            ));
+        }
+        if !is_timezone(&mut tx, &block.timezone).await? {
+            errors.push(FieldError::new(path("timezone"), "is not a known timezone"));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+
+    sqlx::query("DELETE FROM meeting_availability_blocks WHERE tenant_id = $1 AND user_id = $2")
+        .bind(claims.tid)
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+
+    let mut blocks = Vec::with_capacity(payload.blocks.len());
+    for block in &payload.blocks {
+        let row: AvailabilityBlock = sqlx::query_as(&format!(
+            "INSERT INTO meeting_availability_blocks \
+             (tenant_id, user_id, day_of_week, start_time, end_time, timezone, \
+              is_recurring, specific_date, is_blocked) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             RETURNING {}",
+            AVAILABILITY_COLUMNS
+        ))
+        .bind(claims.tid)
+        .bind(claims.sub)
+        .bind(block.day_of_week.filter(|_| block.specific_date.is_none()))
+        .bind(block.start_time)
+        .bind(block.end_time)
+        .bind(&block.timezone)
+        .bind(block.specific_date.is_none())
+        .bind(block.specific_date)
+        .bind(block.is_blocked)
+        .fetch_one(&mut *tx)
+        .await?;
+        blocks.push(row);
+    }
+
+    Ok(Json(blocks))
+}
+
+/// POST /meetings/slots — times when every listed user is free.
+pub async fn find_slots(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<FindSlotsRequest>,
+) -> AppResult<Json<Vec<Slot>>> {
+    let mut users = payload.participant_user_ids.clone();
+    users.sort();
+    users.dedup();
+    if users.is_empty() {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "participant_user_ids",
+            "at least one participant is required",
+        )]));
+    }
+    let duration = validate_duration(payload.duration_minutes.unwrap_or(60))?;
+    let (from, to, step, limit) = window(&payload.window)?;
+
+    let known: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE tenant_id = $1 AND id = ANY($2)")
+            .bind(claims.tid)
+            .bind(&users)
+            .fetch_one(&mut *tx)
+            .await?;
+    if known != users.len() as i64 {
+        return Err(AppError::NotFound("Participant not found".to_string()));
+    }
+
+    let free = slots::common_free_time(&mut tx, claims.tid, &users, from, to, None).await?;
+    Ok(Json(slots::rank(&free, duration, step, from, to, limit)))
+}
+
+/// GET /meetings/{id}/slots — times when the meeting's participants are free.
+pub async fn meeting_slots(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<SlotWindowQuery>,
+) -> AppResult<Json<Vec<Slot>>> {
+    let meeting = load_meeting(&mut tx, claims.tid, id).await?;
+    Ok(Json(slots_for_meeting(&mut tx, &meeting, &params).await?))
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// POST /meetings/{id}/scheduling-link — a link the candidate can use to
+/// pick a time themselves.
+pub async fn create_scheduling_link(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<CreateSchedulingLinkRequest>>,
+) -> AppResult<(StatusCode, Json<SchedulingLink>)> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let days = payload.expires_in_days.unwrap_or(LINK_EXPIRY_DAYS);
+    if !(1..=60).contains(&days) {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "expires_in_days",
+            "must be between 1 and 60",
+        )]));
+    }
+
+    let meeting = load_meeting(&mut tx, claims.tid, id).await?;
+    if meeting.status != "pending" && meeting.status != "rescheduled" {
+        return Err(AppError::Conflict(format!(
+            "Cannot schedule a meeting in '{}' status",
+            meeting.status
+        )));
+    }
+
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    let token = hex::encode(bytes);
+    let expires_at: DateTime<Utc> = sqlx::query_scalar(
+        "INSERT INTO meeting_scheduling_links \
+         (tenant_id, meeting_id, token_hash, created_by, expires_at) \
+         VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5)) \
+         RETURNING expires_at",
+    )
+    .bind(claims.tid)
+    .bind(id)
+    .bind(hash_token(&token))
+    .bind(claims.sub)
+    .bind(days as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let url = format!(
+        "{}/schedule/{}",
+        state.config.app_base_url.trim_end_matches('/'),
+        token
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(SchedulingLink {
+            token,
+            url,
+            expires_at,
+        }),
+    ))
+}
+
+#[derive(sqlx::FromRow)]
+struct LinkRow {
+    id: Uuid,
+    tenant_id: Uuid,
+    meeting_id: Uuid,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+}
+
+/// Find an unexpired link by its token.
+async fn find_link(db: impl sqlx::PgExecutor<'_>, token: &str) -> AppResult<LinkRow> {
+    let link: LinkRow = sqlx::query_as(
+        "SELECT id, tenant_id, meeting_id, expires_at, used_at \
+         FROM meeting_scheduling_links WHERE token_hash = $1 FOR UPDATE",
+    )
+    .bind(hash_token(token))
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Scheduling link not found".to_string()))?;
+
+    if link.used_at.is_some() {
+        return Err(AppError::Conflict(
+            "This meeting has already been scheduled".to_string(),
+        ));
+    }
+    if link.expires_at <= Utc::now() {
+        return Err(AppError::NotFound(
+            "Scheduling link has expired".to_string(),
+        ));
+    }
+    Ok(link)
+}
+
+fn ensure_open(meeting: &MeetingRequest) -> AppResult<()> {
+    if meeting.status != "pending" && meeting.status != "rescheduled" {
+        return Err(AppError::Conflict(
+            "This meeting is no longer open for scheduling".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// GET /public/scheduling/{token} — the meeting and its open slots.
+pub async fn get_scheduling_page(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Json<PublicSchedulingPage>> {
+    let link = find_link(&state.db, &token).await?;
+    let mut tx = tenant_tx(&state.db, link.tenant_id).await?;
+    let meeting = load_meeting(&mut tx, link.tenant_id, link.meeting_id).await?;
+    ensure_open(&meeting)?;
+
+    let slots = slots_for_meeting(&mut tx, &meeting, &SlotWindowQuery::default()).await?;
+    let organizer_name: String =
+        sqlx::query_scalar("SELECT CONCAT_WS(' ', first_name, last_name) FROM users WHERE id = $1")
+            .bind(meeting.requested_by)
+            .fetch_one(&mut *tx)
+            .await?;
+    tx.commit().await?;
+
+    Ok(Json(PublicSchedulingPage {
+        title: meeting.title,
+        description: meeting.description,
+        meeting_type: meeting.meeting_type,
+        duration_minutes: meeting.duration_minutes,
+        location: meeting.location,
+        organizer_name,
+        expires_at: link.expires_at,
+        slots,
+    }))
+}
+
+/// POST /public/scheduling/{token} — book a slot; the meeting is accepted
+/// for everyone on it.
+pub async fn book_slot(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(payload): Json<BookSlotRequest>,
+) -> AppResult<Json<PublicBooking>> {
+    let tenant_id = find_link(&state.db, &token).await?.tenant_id;
+    let mut tx = tenant_tx(&state.db, tenant_id).await?;
+    let link = find_link(&mut *tx, &token).await?;
+    let meeting = load_meeting(&mut tx, tenant_id, link.meeting_id).await?;
+    ensure_open(&meeting)?;
+
+    if !is_timezone(&mut tx, &payload.timezone).await? {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "timezone",
+            "is not a known timezone",
+        )]));
+    }
+    let starts_at = payload.starts_at;
+    let ends_at = starts_at + Duration::minutes(meeting.duration_minutes.max(5) as i64);
+    if starts_at <= Utc::now() {
+        return Err(AppError::InvalidFields(vec![FieldError::new(
+            "starts_at",
+            "must be in the future",
+        )]));
+    }
+
+    // Serialize bookings that share a participant so two links cannot
+    // claim the same free time.
+    let users = participant_ids(&mut tx, tenant_id, meeting.id).await?;
+    sqlx::query("SELECT id FROM users WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+        .bind(&users)
+        .execute(&mut *tx)
+        .await?;
+    let free = slots::common_free_time(
+        &mut tx,
+        tenant_id,
+        &users,
+        starts_at,
+        ends_at,
+        Some(meeting.id),
+    )
+    .await?;
+    if !slots::fits(&free, Interval::new(starts_at, ends_at)) {
+        return Err(AppError::Conflict(
+            "That time is no longer available".to_string(),
+        ));
+    }
+
+    let meeting: MeetingRequest = sqlx::query_as(&format!(
+        "UPDATE meeting_requests \
+         SET status = 'accepted', accepted_time = $3, accepted_timezone = $4, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        MEETING_COLUMNS
+    ))
+    .bind(meeting.id)
+    .bind(tenant_id)
+    .bind(starts_at)
+    .bind(&payload.timezone)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE meeting_participants SET response_status = 'accepted', responded_at = NOW() \
+         WHERE meeting_id = $1 AND tenant_id = $2 AND response_status <> 'declined'",
+    )
+    .bind(meeting.id)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE meeting_scheduling_links SET used_at = NOW() WHERE id = $1")
+        .bind(link.id)
+        .execute(&mut *tx)
+        .await?;
+
+    notify(
+        &mut *tx,
+        &state.ws_broadcast,
+        NewNotification {
+            tenant_id,
+            user_id: meeting.requested_by,
+            kind: "meeting_scheduled",
+            title: format!("Meeting scheduled: {}", meeting.title),
+            body: Some(format!(
+                "Booked for {} ({})",
+                starts_at.format("%Y-%m-%d %H:%M UTC"),
+                payload.timezone
+            )),
+            resource_type: Some("meeting"),
+            resource_id: Some(meeting.id),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(PublicBooking {
+        title: meeting.title,
+        starts_at,
+        ends_at,
+        timezone: payload.timezone,
+        meeting_type: meeting.meeting_type,
+        location: meeting.location,
+        meeting_url: meeting.meeting_url,
+    }))
+}
@@ -0,0 +1,296 @@
+//! Finding times when every participant is free.
+//!
+//! Each participant's availability blocks are expanded into concrete
+//! windows by Postgres, which resolves the block's local times in its IANA
+//! timezone so DST changes move the window rather than the wall-clock
+//! hours. Blocked windows and accepted meetings are then taken out, the
+//! participants' free time intersected, and candidate slots ranked.
+//!
+//! A participant with no availability blocks is treated as free whenever
+//! they have no meeting.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+
+/// How far ahead to look when no range is given.
+pub const DEFAULT_HORIZON_DAYS: i64 = 14;
+/// Slots start on multiples of this many minutes past the hour.
+pub const DEFAULT_STEP_MINUTES: i64 = 30;
+pub const DEFAULT_LIMIT: usize = 20;
+/// Room on either side of a slot beyond which more earns no extra score.
+const BUFFER_CAP_MINUTES: i64 = 60;
+/// Upper bound on slots considered before ranking.
+const MAX_CANDIDATES: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Interval {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Interval { start, end }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Slot {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Higher is better: sooner, and with more room around it.
+    pub score: f64,
+}
+
+/// Sort `intervals` and merge any that overlap or touch.
+pub fn normalize(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.retain(|i| i.start < i.end);
+    intervals.sort_by_key(|i| i.start);
+    let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// Times covered by both `a` and `b`; both must be normalized.
+pub fn intersect(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        let start = a[i].start.max(b[j].start);
+        let end = a[i].end.min(b[j].end);
+        if start < end {
+            out.push(Interval::new(start, end));
+        }
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+/// Times in `a` not covered by `b`; both must be normalized.
+pub fn subtract(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
+    let mut out = Vec::new();
+    for interval in a {
+        let mut start = interval.start;
+        for cut in b {
+            if cut.end <= start || cut.start >= interval.end {
+                continue;
+            }
+            if cut.start > start {
+                out.push(Interval::new(start, cut.start));
+            }
+            start = start.max(cut.end);
+        }
+        if start < interval.end {
+            out.push(Interval::new(start, interval.end));
+        }
+    }
+    out
+}
+
+/// Whether `slot` lies wholly inside one of the normalized `free` intervals.
+pub fn fits(free: &[Interval], slot: Interval) -> bool {
+    free.iter()
+        .any(|f| f.start <= slot.start && slot.end <= f.end)
+}
+
+/// The first multiple of `step` at or after `t`.
+fn ceil_to_step(t: DateTime<Utc>, step: Duration) -> DateTime<Utc> {
+    let step = step.num_seconds().max(60);
+    let secs = t.timestamp();
+    let rounded = (secs + step - 1).div_euclid(step) * step;
+    DateTime::from_timestamp(rounded, 0).unwrap_or(t)
+}
+
+/// Slots of `duration` inside the normalized `free` intervals, starting on
+/// `step` boundaries no earlier than `from`, best first.
+///
+/// A slot scores up to 0.6 for being early in the search range and up to
+/// 0.4 for the room left free on both sides of it, so back-to-back slots
+/// wedged between other commitments rank below ones with breathing space.
+pub fn rank(
+    free: &[Interval],
+    duration: Duration,
+    step: Duration,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: usize,
+) -> Vec<Slot> {
+    let horizon = (to - from).num_minutes().max(1) as f64;
+    let cap = Duration::minutes(BUFFER_CAP_MINUTES);
+    let mut slots = Vec::new();
+    'outer: for interval in free {
+        let mut start = ceil_to_step(interval.start.max(from), step);
+        while start + duration <= interval.end {
+            let end = start + duration;
+            let room = (start - interval.start).min(interval.end - end).min(cap);
+            let soon = 1.0 - (start - from).num_minutes() as f64 / horizon;
+            let buffer = room.num_minutes() as f64 / BUFFER_CAP_MINUTES as f64;
+            let score = 0.6 * soon.clamp(0.0, 1.0) + 0.4 * buffer;
+            slots.push(Slot {
+                starts_at: start,
+                ends_at: end,
+                score: (score * 1000.0).round() / 1000.0,
+            });
+            if slots.len() >= MAX_CANDIDATES {
+                break 'outer;
+            }
+            start += step;
+        }
+    }
+    slots.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.starts_at.cmp(&b.starts_at))
+    });
+    slots.truncate(limit);
+    slots
+}
+
+/// Free time shared by all of `user_ids` between `from` and `to`. Meetings
+/// other than `exclude_meeting` count as busy.
+pub async fn common_free_time(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    user_ids: &[Uuid],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    exclude_meeting: Option<Uuid>,
+) -> AppResult<Vec<Interval>> {
+    let windows: Vec<(Uuid, bool, DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT b.user_id, COALESCE(b.is_blocked, false), \
+         (d + b.start_time) AT TIME ZONE tz, \
+         (d + b.end_time + CASE WHEN b.end_time <= b.start_time \
+             THEN INTERVAL '1 day' ELSE INTERVAL '0' END) AT TIME ZONE tz \
+         FROM meeting_availability_blocks b \
+         CROSS JOIN LATERAL (SELECT COALESCE(b.timezone, 'UTC') AS tz) z \
+         CROSS JOIN LATERAL generate_series( \
+             (($3::timestamptz AT TIME ZONE tz)::date - 1)::timestamp, \
+             ($4::timestamptz AT TIME ZONE tz)::date::timestamp, \
+             INTERVAL '1 day') AS d \
+         WHERE b.tenant_id = $1 AND b.user_id = ANY($2) \
+         AND CASE WHEN b.specific_date IS NOT NULL THEN d::date = b.specific_date \
+                  ELSE EXTRACT(DOW FROM d) = b.day_of_week END",
+    )
+    .bind(tenant_id)
+    .bind(user_ids)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let busy: Vec<(DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT mr.accepted_time, \
+         mr.accepted_time + make_interval(mins => COALESCE(mr.duration_minutes, 60)) \
+         FROM meeting_requests mr \
+         JOIN meeting_participants mp ON mp.meeting_id = mr.id \
+         WHERE mr.tenant_id = $1 AND mp.user_id = ANY($2) \
+         AND mr.status = 'accepted' AND mr.accepted_time IS NOT NULL \
+         AND mp.response_status <> 'declined' \
+         AND mr.id IS DISTINCT FROM $5 \
+         AND mr.accepted_time < $4 \
+         AND mr.accepted_time + make_interval(mins => COALESCE(mr.duration_minutes, 60)) > $3",
+    )
+    .bind(tenant_id)
+    .bind(user_ids)
+    .bind(from)
+    .bind(to)
+    .bind(exclude_meeting)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut free = vec![Interval::new(from, to)];
+    for user_id in user_ids {
+        let (blocked, open): (Vec<_>, Vec<_>) = windows
+            .iter()
+            .filter(|(id, ..)| id == user_id)
+            .partition(|(_, is_blocked, ..)| *is_blocked);
+        let collect = |rows: Vec<&(Uuid, bool, DateTime<Utc>, DateTime<Utc>)>| {
+            normalize(rows.iter().map(|r| Interval::new(r.2, r.3)).collect())
+        };
+        if !open.is_empty() {
+            free = intersect(&free, &collect(open));
+        }
+        free = subtract(&free, &collect(blocked));
+    }
+    let busy = normalize(busy.into_iter().map(|(s, e)| Interval::new(s, e)).collect());
+    Ok(subtract(&free, &busy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, h, m, 0).unwrap()
+    }
+
+    fn iv(a: (u32, u32), b: (u32, u32)) -> Interval {
+        Interval::new(at(a.0, a.1), at(b.0, b.1))
+    }
+
+    #[test]
+    fn test_normalizes_intersects_and_subtracts() {
+        let merged = normalize(vec![
+            iv((11, 0), (12, 0)),
+            iv((9, 0), (10, 0)),
+            iv((10, 0), (10, 30)),
+        ]);
+        assert_eq!(merged, vec![iv((9, 0), (10, 30)), iv((11, 0), (12, 0))]);
+
+        let other = vec![iv((10, 0), (11, 30))];
+        assert_eq!(
+            intersect(&merged, &other),
+            vec![iv((10, 0), (10, 30)), iv((11, 0), (11, 30))]
+        );
+        assert_eq!(
+            subtract(&merged, &other),
+            vec![iv((9, 0), (10, 0)), iv((11, 30), (12, 0))]
+        );
+        assert!(fits(&merged, iv((9, 15), (10, 15))));
+        assert!(!fits(&merged, iv((10, 0), (11, 0))));
+    }
+
+    #[test]
+    fn test_ranks_sooner_slots_with_room_first() {
+        let free = vec![iv((9, 0), (10, 0)), iv((13, 0), (17, 0))];
+        let slots = rank(
+            &free,
+            Duration::minutes(60),
+            Duration::minutes(30),
+            at(8, 10),
+            at(18, 0),
+            50,
+        );
+        // Two slots fit in the afternoon for every half hour up to 16:00,
+        // one in the morning.
+        assert_eq!(slots.len(), 8);
+        assert!(slots.iter().all(|s| s.starts_at.timestamp() % 1800 == 0));
+        // 14:00 has an hour either side; 09:00 is sooner but wedged in.
+        assert_eq!(slots[0].starts_at, at(14, 0));
+        assert!(slots.iter().any(|s| s.starts_at == at(9, 0)));
+
+        let limited = rank(
+            &free,
+            Duration::minutes(60),
+            Duration::minutes(30),
+            at(8, 10),
+            at(18, 0),
+            3,
+        );
+        assert_eq!(limited, slots[..3].to_vec());
+    }
+}
@@ -8,11 +8,12 @@ use uuid::Uuid;
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
 use crate::messages::model::*;
+use crate::middleware::tenant::TenantTx;
 use crate::ws::WsEventPayload;
 use crate::AppState;
 
 pub async fn list_messages_for_client(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
     Query(params): Query<ListMessagesQuery>,
@@ -39,7 +40,7 @@ pub async fn list_messages_for_client(
     .bind(offset)
     .bind(search)
     .bind(&search_pattern)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -50,7 +51,7 @@ pub async fn list_messages_for_client(
     .bind(client_id)
     .bind(search)
     .bind(&search_pattern)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -66,6 +67,7 @@ pub async fn list_messages_for_client(
 
 pub async fn create_message(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateMessageRequest>,
 ) -> AppResult<(StatusCode, Json<MessageWithSender>)> {
@@ -82,7 +84,7 @@ pub async fn create_message(
     .bind(payload.parent_id)
     .bind(&payload.content)
     .bind(is_internal)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Insert attachments if provided
@@ -94,7 +96,7 @@ pub async fn create_message(
             .bind(claims.tid)
             .bind(message.id)
             .bind(doc_id)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
@@ -104,7 +106,7 @@ pub async fn create_message(
         "SELECT COALESCE(first_name || ' ' || last_name, 'Unknown') FROM users WHERE id = $1",
     )
     .bind(claims.sub)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .unwrap_or_else(|| "Unknown".to_string());
 
@@ -144,6 +146,7 @@ pub async fn create_message(
 
 pub async fn mark_message_read(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(message_id): Path<Uuid>,
 ) -> AppResult<Json<Message>> {
@@ -154,7 +157,7 @@ pub async fn mark_message_read(
     )
     .bind(message_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
 
@@ -172,7 +175,7 @@ pub async fn mark_message_read(
 }
 
 pub async fn mark_conversation_read(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(client_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -183,14 +186,14 @@ pub async fn mark_conversation_read(
     .bind(claims.tid)
     .bind(client_id)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn get_unread_counts(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let counts: Vec<(Uuid, i64)> = sqlx::query_as(
@@ -201,7 +204,7 @@ pub async fn get_unread_counts(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let map: std::collections::HashMap<String, i64> = counts
@@ -213,7 +216,7 @@ pub async fn get_unread_counts(
 }
 
 pub async fn delete_message(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(message_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -222,7 +225,7 @@ pub async fn delete_message(
             .bind(message_id)
             .bind(claims.tid)
             .bind(claims.sub)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
 
     if result.rows_affected() == 0 {
@@ -1,2 +1,26 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/messages", post(handler::create_message))
+        .route("/messages/unread-counts", get(handler::get_unread_counts))
+        .route(
+            "/messages/client/{client_id}",
+            get(handler::list_messages_for_client),
+        )
+        .route(
+            "/messages/client/{client_id}/read-all",
+            put(handler::mark_conversation_read),
+        )
+        .route("/messages/{id}/read", put(handler::mark_message_read))
+        .route("/messages/{id}", delete(handler::delete_message))
+}
@@ -6,6 +6,7 @@ use axum::{
 use serde_json::json;
 
 use crate::auth::jwt::Claims;
+use crate::scheduler::tenant_tx;
 use crate::AppState;
 
 /// Audit log middleware that records mutating API calls (POST, PUT, PATCH, DELETE).
@@ -53,6 +54,13 @@ pub async fn audit_log(State(state): State<AppState>, req: Request, next: Next)
             let ua = user_agent.clone();
 
             tokio::spawn(async move {
+                let mut tx = match tenant_tx(&db, tenant_id).await {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        tracing::warn!("Failed to write audit log: {}", e);
+                        return;
+                    }
+                };
                 let details = json!({
                     "status": status,
                     "method": method.as_str(),
@@ -70,8 +78,12 @@ pub async fn audit_log(State(state): State<AppState>, req: Request, next: Next)
                 .bind(&details)
                 .bind(ip.as_deref())
                 .bind(ua.as_deref())
-                .execute(&db)
+                .execute(&mut *tx)
                 .await;
+                let result = match result {
+                    Ok(_) => tx.commit().await,
+                    Err(e) => Err(e),
+                };
 
                 if let Err(e) = result {
                     tracing::warn!("Failed to write audit log: {}", e);
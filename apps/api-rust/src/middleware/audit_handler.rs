@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Extension, Query, State},
-    Json,
+    extract::{Extension, Query},
+    routing::get,
+    Json, Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -9,6 +10,7 @@ use uuid::Uuid;
 use crate::auth::jwt::Claims;
 use crate::error::AppResult;
 use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
 use crate::AppState;
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
@@ -35,7 +37,7 @@ pub struct AuditLogQuery {
 }
 
 pub async fn list_audit_logs(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<AuditLogQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -83,7 +85,7 @@ pub async fn list_audit_logs(
     if let Some(ref action) = params.action {
         count_q = count_q.bind(format!("%{}%", action));
     }
-    let (total,) = count_q.fetch_one(&state.db).await?;
+    let (total,) = count_q.fetch_one(&mut *tx).await?;
 
     // Build data query
     let mut data_q = sqlx::query_as::<_, AuditLogEntry>(&query_sql).bind(claims.tid);
@@ -99,7 +101,7 @@ pub async fn list_audit_logs(
     let logs = data_q
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     Ok(Json(serde_json::json!({
@@ -112,3 +114,8 @@ pub async fn list_audit_logs(
         }
     })))
 }
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/audit-logs", get(list_audit_logs))
+}
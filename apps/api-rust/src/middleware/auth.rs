@@ -6,9 +6,11 @@ use axum::{
 
 use crate::auth::jwt::{validate_token, Claims};
 use crate::error::AppError;
+use crate::middleware::tenant::TxSlot;
 use crate::AppState;
 
-/// Extract JWT claims from the Authorization header and inject into request extensions.
+/// Extract JWT claims from the Authorization header and inject into request extensions,
+/// along with the request's tenant transaction slot.
 pub async fn require_auth(
     State(state): State<AppState>,
     mut req: Request,
@@ -23,17 +25,20 @@ pub async fn require_auth(
 
     let token_data = validate_token(token, &state.config.jwt_secret)?;
 
-    // Set tenant context for RLS — parameterized to prevent SQL injection
-    sqlx::query("SELECT set_config('app.current_tenant', $1, true)")
-        .bind(token_data.claims.tid.to_string())
-        .execute(&state.db)
-        .await
-        .map_err(|e| AppError::Internal(format!("Failed to set tenant context: {}", e)))?;
+    // Handlers that take a `TenantTx` share one transaction with the tenant
+    // context set; it is committed or rolled back once the response is known.
+    let slot = TxSlot::new(state.db.clone(), token_data.claims.tid);
+    req.extensions_mut().insert(slot.clone());
 
     // Inject claims into request extensions
     req.extensions_mut().insert(token_data.claims);
 
-    Ok(next.run(req).await)
+    let response = next.run(req).await;
+    slot.finish(response.status())
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to complete transaction: {}", e)))?;
+
+    Ok(response)
 }
 
 /// Require a minimum role level.
@@ -48,7 +53,7 @@ pub fn require_role(claims: &Claims, min_role: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-fn role_to_level(role: &str) -> u8 {
+pub(crate) fn role_to_level(role: &str) -> u8 {
     match role {
         "client" => 0,
         "staff_accountant" => 1,
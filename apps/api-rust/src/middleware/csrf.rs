@@ -22,9 +22,15 @@ fn generate_csrf_token() -> String {
 /// Check if the request path should skip CSRF validation.
 /// - Webhook endpoints (verified by their own signature)
 /// - Paths that use Bearer token auth (API routes with Authorization header)
+/// - Requests without cookies (no ambient credentials to forge, e.g. the
+///   token refresh call and server-to-server clients)
 fn should_skip_csrf(req: &Request) -> bool {
     let path = req.uri().path();
 
+    if !req.headers().contains_key(header::COOKIE) {
+        return true;
+    }
+
     // Skip CSRF for webhook endpoints (they use signature-based verification)
     if path.contains("/webhooks/") {
         return true;
@@ -94,9 +100,10 @@ pub async fn csrf_protection(req: Request, next: Next) -> Result<Response, AppEr
         let mut response = next.run(req).await;
 
         if existing_token.is_none() {
+            // Readable by JS (no HttpOnly) so the client can echo it in X-CSRF-Token
             let token = generate_csrf_token();
             let cookie_value = format!(
-                "{}={}; Path=/; HttpOnly=false; SameSite=Strict; Secure",
+                "{}={}; Path=/; SameSite=Strict; Secure",
                 CSRF_COOKIE_NAME, token
             );
             if let Ok(header_value) = cookie_value.parse() {
@@ -150,6 +157,26 @@ mod tests {
         assert!(constant_time_eq(b"", b""));
     }
 
+    fn request(headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/auth/login");
+        for (k, v) in headers {
+            builder = builder.header(*k, *v);
+        }
+        builder.body(axum::body::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_should_skip_csrf() {
+        assert!(should_skip_csrf(&request(&[])));
+        assert!(should_skip_csrf(&request(&[
+            ("cookie", "csrf_token=abc"),
+            ("authorization", "Bearer x")
+        ])));
+        assert!(!should_skip_csrf(&request(&[("cookie", "csrf_token=abc")])));
+    }
+
     #[test]
     fn test_generate_csrf_token() {
         let token1 = generate_csrf_token();
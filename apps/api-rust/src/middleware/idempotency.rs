@@ -1,92 +1,351 @@
+//! `Idempotency-Key` support for authenticated, mutating routes.
+//!
+//! The first request with a key claims it, runs, and has its full response
+//! (status, selected headers and body) stored. Retries with the same key and
+//! the same request replay that response without running the handler again.
+//! Keys are scoped per tenant and user; reusing one for a different method,
+//! path or body is rejected. A duplicate that arrives while the original is
+//! still running waits for it, then gets a 409 if it has not finished.
+//!
+//! Applied per route with `from_fn(idempotency_check)`, inside `require_auth`.
+
+use std::time::{Duration, Instant};
+
 use axum::{
+    body::{to_bytes, Body},
     extract::Request,
-    http::{HeaderMap, Method, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::AppState;
+use crate::auth::jwt::Claims;
+use crate::error::AppError;
+use crate::middleware::tenant::TxSlot;
 
 const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
 
-pub async fn idempotency_check(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    headers: HeaderMap,
-    request: Request,
-    next: Next,
-) -> Response {
-    // Only apply to mutating methods
-    let method = request.method().clone();
-    if !matches!(method, Method::POST | Method::PUT | Method::PATCH) {
+/// In-flight keys locked longer than this are assumed abandoned and reclaimed.
+const STALE_LOCK_SECS: f64 = 300.0;
+/// How long a duplicate waits for the original request before giving up.
+const WAIT_FOR_ORIGINAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Response headers stored and replayed alongside the body.
+const REPLAYED_HEADERS: [HeaderName; 3] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_DISPOSITION,
+    header::LOCATION,
+];
+
+#[derive(sqlx::FromRow)]
+struct StoredKey {
+    request_hash: String,
+    response_status: Option<i32>,
+    response_headers: Option<serde_json::Value>,
+    response_body: Option<Vec<u8>>,
+}
+
+struct Scope<'a> {
+    db: &'a PgPool,
+    tenant_id: Uuid,
+    user_id: Uuid,
+    key: &'a str,
+}
+
+pub async fn idempotency_check(request: Request, next: Next) -> Response {
+    if !matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH
+    ) {
         return next.run(request).await;
     }
 
-    // Check for idempotency key header
-    let key = match headers
+    let key = match request
+        .headers()
         .get(IDEMPOTENCY_HEADER)
         .and_then(|v| v.to_str().ok())
     {
-        Some(k) => k.to_string(),
+        Some(k) => k.trim().to_string(),
         None => return next.run(request).await, // No key, proceed normally
     };
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return AppError::Validation(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_KEY_LEN
+        ))
+        .into_response();
+    }
 
-    let fingerprint = format!("{}:{}", method.as_str(), request.uri().path());
+    let (claims, slot) = match (
+        request.extensions().get::<Claims>().cloned(),
+        request.extensions().get::<TxSlot>().cloned(),
+    ) {
+        (Some(claims), Some(slot)) => (claims, slot),
+        _ => return next.run(request).await,
+    };
 
-    // Check if key already exists
-    let existing: Option<(String, Option<i32>, Option<String>)> = sqlx::query_as(
-        "SELECT fingerprint, response_status, response_body \
-         FROM idempotency_keys \
-         WHERE key = $1 AND expires_at > NOW()",
-    )
-    .bind(&key)
-    .fetch_optional(&state.db)
-    .await
-    .unwrap_or(None);
-
-    if let Some((stored_fingerprint, status, body)) = existing {
-        // Key exists - check fingerprint match
-        if stored_fingerprint != fingerprint {
-            return (
-                StatusCode::CONFLICT,
-                "Idempotency key already used with a different request",
-            )
-                .into_response();
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return AppError::Validation("Request body too large".to_string()).into_response()
         }
+    };
+    let request_hash = request_hash(&parts.method, &parts.uri.to_string(), &body);
+    let method = parts.method.to_string();
+    let path = parts.uri.path().to_string();
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let scope = Scope {
+        db: slot.db(),
+        tenant_id: claims.tid,
+        user_id: claims.sub,
+        key: &key,
+    };
 
-        // Return cached response if available
-        if let (Some(status_code), Some(response_body)) = (status, body) {
-            let status = StatusCode::from_u16(status_code as u16).unwrap_or(StatusCode::OK);
-            return (status, response_body).into_response();
+    let deadline = Instant::now() + WAIT_FOR_ORIGINAL;
+    loop {
+        let claimed = match claim(&scope, &method, &path, &request_hash).await {
+            Ok(claimed) => claimed,
+            Err(e) => return AppError::Database(e).into_response(),
+        };
+        if let Some(id) = claimed {
+            return run_and_store(&scope, id, &slot, request, next).await;
         }
 
-        // Key exists but no response yet (concurrent request) - proceed
-        return next.run(request).await;
+        let stored = match fetch(&scope).await {
+            Ok(stored) => stored,
+            Err(e) => return AppError::Database(e).into_response(),
+        };
+        match stored {
+            // The original failed and released the key; try to claim it again.
+            None => continue,
+            Some(stored) if stored.request_hash != request_hash => {
+                return AppError::Conflict(
+                    "Idempotency key already used with a different request".to_string(),
+                )
+                .into_response();
+            }
+            Some(StoredKey {
+                response_status: Some(status),
+                response_headers,
+                response_body,
+                ..
+            }) => return replay(status, response_headers, response_body),
+            Some(_) if Instant::now() >= deadline => {
+                return AppError::Conflict(
+                    "A request with this idempotency key is still in progress".to_string(),
+                )
+                .into_response();
+            }
+            Some(_) => tokio::time::sleep(POLL_INTERVAL).await,
+        }
     }
+}
 
-    // Insert new idempotency key
-    let _ = sqlx::query(
-        "INSERT INTO idempotency_keys (key, fingerprint, expires_at) \
-         VALUES ($1, $2, NOW() + INTERVAL '24 hours') \
-         ON CONFLICT (key) DO NOTHING",
+/// Claim the key for this request. Expired keys and keys abandoned mid-flight
+/// are taken over; otherwise returns `None` when another request holds it.
+async fn claim(
+    scope: &Scope<'_>,
+    method: &str,
+    path: &str,
+    request_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO idempotency_keys (tenant_id, user_id, key, request_method, request_path, request_hash) \
+         VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (tenant_id, user_id, key) DO UPDATE SET \
+             request_method = EXCLUDED.request_method, request_path = EXCLUDED.request_path, \
+             request_hash = EXCLUDED.request_hash, response_status = NULL, response_headers = NULL, \
+             response_body = NULL, completed_at = NULL, locked_at = NOW(), created_at = NOW(), \
+             expires_at = NOW() + INTERVAL '24 hours' \
+         WHERE idempotency_keys.expires_at <= NOW() \
+            OR (idempotency_keys.response_status IS NULL \
+                AND idempotency_keys.locked_at < NOW() - make_interval(secs => $7)) \
+         RETURNING id",
     )
-    .bind(&key)
-    .bind(&fingerprint)
-    .execute(&state.db)
-    .await;
+    .bind(scope.tenant_id)
+    .bind(scope.user_id)
+    .bind(scope.key)
+    .bind(method)
+    .bind(path)
+    .bind(request_hash)
+    .bind(STALE_LOCK_SECS)
+    .fetch_optional(scope.db)
+    .await
+}
+
+async fn fetch(scope: &Scope<'_>) -> Result<Option<StoredKey>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT request_hash, response_status, response_headers, response_body \
+         FROM idempotency_keys \
+         WHERE tenant_id = $1 AND user_id = $2 AND key = $3 AND expires_at > NOW()",
+    )
+    .bind(scope.tenant_id)
+    .bind(scope.user_id)
+    .bind(scope.key)
+    .fetch_optional(scope.db)
+    .await
+}
 
-    // Proceed with request
+async fn release(scope: &Scope<'_>, id: Uuid) {
+    if let Err(e) = sqlx::query("DELETE FROM idempotency_keys WHERE id = $1")
+        .bind(id)
+        .execute(scope.db)
+        .await
+    {
+        tracing::warn!(key = %scope.key, "Failed to release idempotency key: {}", e);
+    }
+}
+
+async fn run_and_store(
+    scope: &Scope<'_>,
+    id: Uuid,
+    slot: &TxSlot,
+    request: Request,
+    next: Next,
+) -> Response {
     let response = next.run(request).await;
+    let status = response.status();
+
+    // Server errors are not cached so the client can retry them.
+    if status.is_server_error() {
+        release(scope, id).await;
+        return response;
+    }
+
+    // Settle the handler's transaction before recording the outcome, so a
+    // replay never reports work that was rolled back.
+    if let Err(e) = slot.finish(status).await {
+        release(scope, id).await;
+        return AppError::Internal(format!("Failed to complete transaction: {}", e))
+            .into_response();
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            release(scope, id).await;
+            return AppError::Internal(format!("Failed to read response body: {}", e))
+                .into_response();
+        }
+    };
 
-    // Cache response status (we can't easily cache the body without consuming it)
-    let status_code = response.status().as_u16() as i32;
-    let _ = sqlx::query(
-        "UPDATE idempotency_keys SET response_status = $2, locked_at = NOW() \
-         WHERE key = $1",
+    let stored = sqlx::query(
+        "UPDATE idempotency_keys SET response_status = $2, response_headers = $3, \
+         response_body = $4, completed_at = NOW() \
+         WHERE id = $1",
     )
-    .bind(&key)
-    .bind(status_code)
-    .execute(&state.db)
+    .bind(id)
+    .bind(status.as_u16() as i32)
+    .bind(stored_headers(&parts.headers))
+    .bind(body.as_ref())
+    .execute(scope.db)
     .await;
+    if let Err(e) = stored {
+        tracing::error!(key = %scope.key, "Failed to store idempotent response: {}", e);
+        release(scope, id).await;
+    }
 
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(status: i32, headers: Option<serde_json::Value>, body: Option<Vec<u8>>) -> Response {
+    let status = StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+    let mut response = Response::new(Body::from(body.unwrap_or_default()));
+    *response.status_mut() = status;
+    if let Some(serde_json::Value::Object(map)) = headers {
+        for (name, value) in map {
+            if let (Ok(name), Some(Ok(value))) = (
+                HeaderName::try_from(name),
+                value.as_str().map(HeaderValue::from_str),
+            ) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+    }
     response
+        .headers_mut()
+        .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn stored_headers(headers: &HeaderMap) -> serde_json::Value {
+    REPLAYED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            Some((name.to_string(), serde_json::Value::from(value)))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Fingerprint of what the key was first used for: method, URI and body.
+fn request_hash(method: &Method, uri: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b"\n");
+    hasher.update(uri);
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_hash_covers_method_uri_and_body() {
+        let base = request_hash(&Method::POST, "/api/v1/invoices/1/payment", b"{\"a\":1}");
+        assert_eq!(
+            base,
+            request_hash(&Method::POST, "/api/v1/invoices/1/payment", b"{\"a\":1}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::PUT, "/api/v1/invoices/1/payment", b"{\"a\":1}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/api/v1/invoices/2/payment", b"{\"a\":1}")
+        );
+        assert_ne!(
+            base,
+            request_hash(&Method::POST, "/api/v1/invoices/1/payment", b"{\"a\":2}")
+        );
+    }
+
+    #[test]
+    fn test_replay_restores_stored_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(
+            header::SET_COOKIE,
+            HeaderValue::from_static("session=secret"),
+        );
+        let stored = stored_headers(&headers);
+        assert_eq!(
+            stored,
+            serde_json::json!({"content-type": "application/json"})
+        );
+
+        let response = replay(201, Some(stored), Some(b"{}".to_vec()));
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[REPLAYED_HEADER], "true");
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+    }
 }
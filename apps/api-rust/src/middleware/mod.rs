@@ -5,3 +5,4 @@ pub mod csrf;
 pub mod idempotency;
 pub mod rate_limit;
 pub mod security;
+pub mod tenant;
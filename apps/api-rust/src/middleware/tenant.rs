@@ -0,0 +1,100 @@
+//! Request-scoped database transaction carrying the caller's tenant.
+//!
+//! `require_auth` attaches a [`TxSlot`] to every authenticated request. The
+//! [`TenantTx`] extractor lazily begins a transaction on one pooled
+//! connection with `app.current_tenant` set, so tenant RLS policies apply to
+//! every statement a handler runs through it. When the handler returns, the
+//! transaction is committed for successful responses and rolled back for
+//! 4xx/5xx responses.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use axum::extract::FromRequestParts;
+use axum::http::{request::Parts, StatusCode};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::scheduler::tenant_tx;
+
+type SharedTx = Arc<Mutex<Option<Transaction<'static, Postgres>>>>;
+
+/// Per-request holder for the tenant transaction, stored in request extensions.
+#[derive(Clone)]
+pub struct TxSlot {
+    db: PgPool,
+    tenant_id: Uuid,
+    tx: SharedTx,
+}
+
+impl TxSlot {
+    pub fn new(db: PgPool, tenant_id: Uuid) -> Self {
+        Self {
+            db,
+            tenant_id,
+            tx: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The pool the transaction is drawn from.
+    pub fn db(&self) -> &PgPool {
+        &self.db
+    }
+
+    /// Commit or roll back the transaction, if the handler opened one. Later
+    /// calls are no-ops, so middleware may settle it before `require_auth`.
+    pub async fn finish(&self, status: StatusCode) -> Result<(), sqlx::Error> {
+        let Some(tx) = self.tx.lock().await.take() else {
+            return Ok(());
+        };
+        if status.is_client_error() || status.is_server_error() {
+            tx.rollback().await
+        } else {
+            tx.commit().await
+        }
+    }
+}
+
+/// Extractor for the request's tenant-scoped transaction.
+///
+/// Derefs to `PgConnection`, so queries run with `.execute(&mut *tx)`.
+/// Handlers must not commit it themselves; the outcome follows the response
+/// status.
+pub struct TenantTx(OwnedMutexGuard<Option<Transaction<'static, Postgres>>>);
+
+impl<S: Send + Sync> FromRequestParts<S> for TenantTx {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let slot = parts
+            .extensions
+            .get::<TxSlot>()
+            .cloned()
+            .ok_or_else(|| AppError::Unauthorized("Missing authentication".to_string()))?;
+
+        let mut guard = slot
+            .tx
+            .try_lock_owned()
+            .map_err(|_| AppError::Internal("Tenant transaction already in use".to_string()))?;
+        if guard.is_none() {
+            *guard = Some(tenant_tx(&slot.db, slot.tenant_id).await?);
+        }
+        Ok(TenantTx(guard))
+    }
+}
+
+impl Deref for TenantTx {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        self.0.as_ref().expect("tenant transaction is open")
+    }
+}
+
+impl DerefMut for TenantTx {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        self.0.as_mut().expect("tenant transaction is open")
+    }
+}
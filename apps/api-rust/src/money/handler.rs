@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use super::model::*;
+use super::rates::{self, Rate};
+use super::{Currency, CURRENCIES};
+use crate::auth::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+
+fn clean_source(source: Option<&str>, default: &str) -> AppResult<String> {
+    let source = source.map(str::trim).filter(|s| !s.is_empty());
+    match source {
+        Some(s) if s.len() > 50 => Err(AppError::Validation(
+            "source must be at most 50 characters".to_string(),
+        )),
+        Some(s) => Ok(s.to_string()),
+        None => Ok(default.to_string()),
+    }
+}
+
+pub async fn list_currencies() -> Json<&'static [Currency]> {
+    Json(CURRENCIES)
+}
+
+pub async fn list_exchange_rates(
+    claims: Claims,
+    mut tx: TenantTx,
+    Query(params): Query<ListExchangeRatesQuery>,
+) -> AppResult<Json<Vec<ExchangeRate>>> {
+    let rates: Vec<ExchangeRate> = sqlx::query_as(&format!(
+        "SELECT {} FROM exchange_rates WHERE tenant_id = $1 \
+         AND ($2::text IS NULL OR from_currency = UPPER($2)) \
+         AND ($3::text IS NULL OR to_currency = UPPER($3)) \
+         AND ($4::date IS NULL OR rate_date >= $4) \
+         AND ($5::date IS NULL OR rate_date <= $5) \
+         ORDER BY rate_date DESC, from_currency, to_currency LIMIT 1000",
+        EXCHANGE_RATE_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(params.from_currency.as_deref().map(str::trim))
+    .bind(params.to_currency.as_deref().map(str::trim))
+    .bind(params.date_from)
+    .bind(params.date_to)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(rates))
+}
+
+pub async fn upsert_exchange_rate(
+    claims: Claims,
+    mut tx: TenantTx,
+    Json(body): Json<UpsertExchangeRateRequest>,
+) -> AppResult<(StatusCode, Json<ExchangeRate>)> {
+    require_role(&claims, "admin")?;
+    let from = Currency::parse(&body.from_currency)?;
+    let to = Currency::parse(&body.to_currency)?;
+    if from == to {
+        return Err(AppError::Validation(
+            "from_currency and to_currency must differ".to_string(),
+        ));
+    }
+    let rate = body.rate.to_text();
+    if Rate::parse(&rate).is_none() {
+        return Err(AppError::Validation(format!(
+            "rate must be a positive decimal with at most {} decimal places",
+            rates::RATE_SCALE
+        )));
+    }
+    let source = clean_source(body.source.as_deref(), "manual")?;
+
+    let rate: ExchangeRate = sqlx::query_as(&format!(
+        "WITH r AS ( \
+             INSERT INTO exchange_rates (tenant_id, rate_date, from_currency, to_currency, rate, source, created_by) \
+             VALUES ($1, $2, $3, $4, $5::numeric, $6, $7) \
+             ON CONFLICT (tenant_id, from_currency, to_currency, rate_date) DO UPDATE SET \
+             rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = NOW() \
+             RETURNING * \
+         ) SELECT {} FROM r",
+        EXCHANGE_RATE_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(body.rate_date)
+    .bind(from.code)
+    .bind(to.code)
+    .bind(&rate)
+    .bind(&source)
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(rate)))
+}
+
+/// POST /exchange-rates/import — load a rate file sent as the request
+/// body, one `date,from,to,rate` line per rate. Rates already stored for
+/// the same pair and date are replaced. Nothing is stored if any line is
+/// invalid.
+pub async fn import_exchange_rates(
+    claims: Claims,
+    mut tx: TenantTx,
+    Query(params): Query<ImportExchangeRatesQuery>,
+    body: String,
+) -> AppResult<Json<ImportResult>> {
+    require_role(&claims, "admin")?;
+    let parsed = rates::parse_csv(&body).map_err(AppError::Validation)?;
+    if parsed.is_empty() {
+        return Err(AppError::Validation(
+            "The file contains no exchange rates".to_string(),
+        ));
+    }
+    let source = clean_source(params.source.as_deref(), "import")?;
+
+    let dates: Vec<chrono::NaiveDate> = parsed.iter().map(|r| r.rate_date).collect();
+    let froms: Vec<&str> = parsed.iter().map(|r| r.from_currency).collect();
+    let tos: Vec<&str> = parsed.iter().map(|r| r.to_currency).collect();
+    let values: Vec<&str> = parsed.iter().map(|r| r.rate.as_str()).collect();
+    // A pair and date repeated in the file keeps its last line.
+    sqlx::query(
+        "INSERT INTO exchange_rates (tenant_id, rate_date, from_currency, to_currency, rate, source, created_by) \
+         SELECT DISTINCT ON (f, t, d) $1, d, f, t, r::numeric, $6, $7 \
+         FROM UNNEST($2::date[], $3::text[], $4::text[], $5::text[]) WITH ORDINALITY AS u(d, f, t, r, n) \
+         ORDER BY f, t, d, n DESC \
+         ON CONFLICT (tenant_id, from_currency, to_currency, rate_date) DO UPDATE SET \
+         rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = NOW()",
+    )
+    .bind(claims.tid)
+    .bind(&dates)
+    .bind(&froms)
+    .bind(&tos)
+    .bind(&values)
+    .bind(&source)
+    .bind(claims.sub)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(Json(ImportResult {
+        imported: parsed.len(),
+    }))
+}
+
+pub async fn delete_exchange_rate(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_role(&claims, "admin")?;
+    let result = sqlx::query("DELETE FROM exchange_rates WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(claims.tid)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Exchange rate not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,298 @@
+//! Currency-aware amounts.
+//!
+//! Amounts are stored as integer minor units of their own currency: cents
+//! for USD, yen for JPY (which has no minor unit), fils for KWD (which has
+//! three). The `*_cents` columns keep their names but hold minor units of
+//! the row's currency. [`Currency`] knows the ISO 4217 exponent, [`Locale`]
+//! how a tenant writes numbers, and [`rates`] converts between currencies
+//! with the tenant's stored exchange rates.
+
+pub mod handler;
+pub mod model;
+pub mod rates;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::error::{AppError, AppResult};
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/currencies", get(handler::list_currencies))
+        .route("/exchange-rates", get(handler::list_exchange_rates))
+        .route("/exchange-rates", post(handler::upsert_exchange_rate))
+        .route(
+            "/exchange-rates/import",
+            post(handler::import_exchange_rates),
+        )
+        .route(
+            "/exchange-rates/{id}",
+            delete(handler::delete_exchange_rate),
+        )
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Currency {
+    pub code: &'static str,
+    /// ISO 4217 exponent: digits after the decimal point.
+    pub minor_units: u8,
+    /// Written before or after the amount as the locale prefers; `None`
+    /// writes the code instead. Only symbols the invoice PDF's fonts can
+    /// draw are listed.
+    pub symbol: Option<&'static str>,
+    pub name: &'static str,
+}
+
+macro_rules! currencies {
+    ($(($code:literal, $units:literal, $symbol:expr, $name:literal)),* $(,)?) => {
+        pub const CURRENCIES: &[Currency] = &[
+            $(Currency { code: $code, minor_units: $units, symbol: $symbol, name: $name }),*
+        ];
+    };
+}
+
+currencies![
+    ("AED", 2, None, "UAE Dirham"),
+    ("ARS", 2, None, "Argentine Peso"),
+    ("AUD", 2, Some("A$"), "Australian Dollar"),
+    ("BHD", 3, None, "Bahraini Dinar"),
+    ("BRL", 2, Some("R$"), "Brazilian Real"),
+    ("CAD", 2, Some("CA$"), "Canadian Dollar"),
+    ("CHF", 2, None, "Swiss Franc"),
+    ("CLP", 0, None, "Chilean Peso"),
+    ("CNY", 2, Some("CN¥"), "Chinese Yuan"),
+    ("COP", 2, None, "Colombian Peso"),
+    ("CZK", 2, None, "Czech Koruna"),
+    ("DKK", 2, None, "Danish Krone"),
+    ("EUR", 2, Some("€"), "Euro"),
+    ("GBP", 2, Some("£"), "British Pound"),
+    ("HKD", 2, Some("HK$"), "Hong Kong Dollar"),
+    ("HUF", 2, None, "Hungarian Forint"),
+    ("IDR", 2, None, "Indonesian Rupiah"),
+    ("ILS", 2, None, "Israeli New Shekel"),
+    ("INR", 2, None, "Indian Rupee"),
+    ("ISK", 0, None, "Icelandic Krona"),
+    ("JOD", 3, None, "Jordanian Dinar"),
+    ("JPY", 0, Some("¥"), "Japanese Yen"),
+    ("KRW", 0, None, "South Korean Won"),
+    ("KWD", 3, None, "Kuwaiti Dinar"),
+    ("MXN", 2, Some("MX$"), "Mexican Peso"),
+    ("MYR", 2, None, "Malaysian Ringgit"),
+    ("NOK", 2, None, "Norwegian Krone"),
+    ("NZD", 2, Some("NZ$"), "New Zealand Dollar"),
+    ("OMR", 3, None, "Omani Rial"),
+    ("PHP", 2, None, "Philippine Peso"),
+    ("PLN", 2, None, "Polish Zloty"),
+    ("SAR", 2, None, "Saudi Riyal"),
+    ("SEK", 2, None, "Swedish Krona"),
+    ("SGD", 2, Some("S$"), "Singapore Dollar"),
+    ("THB", 2, None, "Thai Baht"),
+    ("TND", 3, None, "Tunisian Dinar"),
+    ("TRY", 2, None, "Turkish Lira"),
+    ("TWD", 2, Some("NT$"), "New Taiwan Dollar"),
+    ("UGX", 0, None, "Ugandan Shilling"),
+    ("USD", 2, Some("$"), "US Dollar"),
+    ("VND", 0, None, "Vietnamese Dong"),
+    ("ZAR", 2, None, "South African Rand"),
+];
+
+impl Currency {
+    /// Look up a supported currency by ISO code, ignoring case.
+    pub fn find(code: &str) -> Option<&'static Currency> {
+        let code = code.trim();
+        CURRENCIES
+            .iter()
+            .find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Like [`find`](Self::find), as a validation error for request input.
+    pub fn parse(code: &str) -> AppResult<&'static Currency> {
+        Self::find(code)
+            .ok_or_else(|| AppError::Validation(format!("Unsupported currency: {}", code.trim())))
+    }
+
+    /// `10^minor_units`: minor units in one major unit.
+    pub fn scale(&self) -> i64 {
+        10_i64.pow(self.minor_units as u32)
+    }
+}
+
+/// How a locale writes amounts of money.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Locale {
+    pub tag: &'static str,
+    pub group_separator: &'static str,
+    pub decimal_separator: &'static str,
+    pub symbol_first: bool,
+    /// Separate the symbol (or code) from the digits with a space.
+    pub symbol_spaced: bool,
+}
+
+const NBSP: &str = "\u{a0}";
+
+macro_rules! locales {
+    ($(($tag:literal, $group:expr, $decimal:literal, $first:literal, $spaced:literal)),* $(,)?) => {
+        pub const LOCALES: &[Locale] = &[
+            $(Locale {
+                tag: $tag,
+                group_separator: $group,
+                decimal_separator: $decimal,
+                symbol_first: $first,
+                symbol_spaced: $spaced,
+            }),*
+        ];
+    };
+}
+
+locales![
+    ("en-US", ",", ".", true, false),
+    ("en-GB", ",", ".", true, false),
+    ("en-AU", ",", ".", true, false),
+    ("en-CA", ",", ".", true, false),
+    ("fr-CA", NBSP, ",", false, true),
+    ("de-DE", ".", ",", false, true),
+    ("de-CH", "'", ".", true, true),
+    ("es-ES", ".", ",", false, true),
+    ("es-MX", ",", ".", true, false),
+    ("fr-FR", NBSP, ",", false, true),
+    ("it-IT", ".", ",", false, true),
+    ("ja-JP", ",", ".", true, false),
+    ("nl-NL", ".", ",", true, true),
+    ("pt-BR", ".", ",", true, true),
+    ("sv-SE", NBSP, ",", false, true),
+];
+
+impl Locale {
+    /// Look up a supported locale by BCP 47 tag, ignoring case.
+    pub fn find(tag: &str) -> Option<&'static Locale> {
+        let tag = tag.trim();
+        LOCALES.iter().find(|l| l.tag.eq_ignore_ascii_case(tag))
+    }
+
+    /// Like [`find`](Self::find), falling back to `en-US`.
+    pub fn or_default(tag: &str) -> &'static Locale {
+        Self::find(tag).unwrap_or(&LOCALES[0])
+    }
+}
+
+/// An amount in minor units of a currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+    pub minor: i64,
+    pub currency: &'static Currency,
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: &'static Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    /// Write the amount the way `locale` does, e.g. `$1,234.50` in en-US
+    /// and `1.234,50 €` in de-DE.
+    pub fn format(&self, locale: &Locale) -> String {
+        let (label, is_code) = match self.currency.symbol {
+            Some(symbol) => (symbol, false),
+            None => (self.currency.code, true),
+        };
+        render(
+            self.minor,
+            self.currency.minor_units,
+            label,
+            is_code,
+            locale,
+        )
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format(Locale::or_default("en-US")))
+    }
+}
+
+/// Format `minor` units of the currency named `code` for the locale tagged
+/// `locale`. Codes outside the supported set are written as two-decimal
+/// amounts labelled with the code, so stored data always renders.
+pub fn format(minor: i64, code: &str, locale: &str) -> String {
+    let locale = Locale::or_default(locale);
+    match Currency::find(code) {
+        Some(currency) => Money::new(minor, currency).format(locale),
+        None => render(minor, 2, &code.trim().to_uppercase(), true, locale),
+    }
+}
+
+fn render(minor: i64, minor_units: u8, label: &str, is_code: bool, locale: &Locale) -> String {
+    let sign = if minor < 0 { "-" } else { "" };
+    let abs = minor.unsigned_abs();
+    let scale = 10_u64.pow(minor_units as u32);
+    let whole = (abs / scale).to_string();
+
+    let mut digits = String::with_capacity(whole.len() * 2);
+    for (i, ch) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            digits.push_str(locale.group_separator);
+        }
+        digits.push(ch);
+    }
+    if minor_units > 0 {
+        digits.push_str(locale.decimal_separator);
+        digits.push_str(&format!(
+            "{:0width$}",
+            abs % scale,
+            width = minor_units as usize
+        ));
+    }
+
+    // Codes are always spaced from the digits; symbols only where the
+    // locale does so.
+    let space = if locale.symbol_spaced || is_code {
+        NBSP
+    } else {
+        ""
+    };
+    if locale.symbol_first {
+        format!("{}{}{}{}", sign, label, space, digits)
+    } else {
+        format!("{}{}{}{}", sign, digits, space, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(minor: i64, code: &str, locale: &str) -> String {
+        format(minor, code, locale).replace(NBSP, " ")
+    }
+
+    #[test]
+    fn test_formats_minor_units_per_currency_and_locale() {
+        assert_eq!(fmt(0, "USD", "en-US"), "$0.00");
+        assert_eq!(fmt(123_456_789, "usd", "en-US"), "$1,234,567.89");
+        assert_eq!(fmt(-100_000, "USD", "en-US"), "-$1,000.00");
+        assert_eq!(fmt(123_456, "EUR", "de-DE"), "1.234,56 €");
+        assert_eq!(fmt(123_456, "EUR", "fr-FR"), "1 234,56 €");
+        assert_eq!(fmt(123_456, "CHF", "de-CH"), "CHF 1'234.56");
+        assert_eq!(fmt(123_456, "CHF", "en-US"), "CHF 1,234.56");
+        // No minor unit: the stored amount is whole yen.
+        assert_eq!(fmt(1_234_567, "JPY", "ja-JP"), "¥1,234,567");
+        // Three minor units.
+        assert_eq!(fmt(1_234_567, "KWD", "en-GB"), "KWD 1,234.567");
+        assert_eq!(fmt(5, "KWD", "en-GB"), "KWD 0.005");
+        // Unknown codes and locales still render.
+        assert_eq!(fmt(150, "xyz", "tlh"), "XYZ 1.50");
+    }
+
+    #[test]
+    fn test_looks_up_codes_and_tags_case_insensitively() {
+        assert_eq!(Currency::find(" jpy ").unwrap().minor_units, 0);
+        assert_eq!(Currency::find("BHD").unwrap().scale(), 1000);
+        assert!(Currency::parse("DOGE").is_err());
+        assert_eq!(Locale::find("DE-de").unwrap().tag, "de-DE");
+        assert_eq!(Locale::or_default("xx").tag, "en-US");
+    }
+}
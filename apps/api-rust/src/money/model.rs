@@ -0,0 +1,71 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The rate is read as text, without trailing zeros, so it is never
+/// rounded through a float.
+pub const EXCHANGE_RATE_COLUMNS: &str = "id, tenant_id, rate_date, from_currency, to_currency, \
+    RTRIM(RTRIM(rate::text, '0'), '.') AS rate, source, created_by, created_at, updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rate_date: NaiveDate,
+    pub from_currency: String,
+    pub to_currency: String,
+    /// Units of `to_currency` one unit of `from_currency` buys, as a
+    /// decimal string.
+    pub rate: String,
+    pub source: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListExchangeRatesQuery {
+    pub from_currency: Option<String>,
+    pub to_currency: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+}
+
+/// A rate may be sent as a JSON number or, to avoid float rounding on the
+/// client, as a decimal string.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RateInput {
+    Text(String),
+    Number(f64),
+}
+
+impl RateInput {
+    pub fn to_text(&self) -> String {
+        match self {
+            RateInput::Text(text) => text.trim().to_string(),
+            RateInput::Number(n) => n.to_string(),
+        }
+    }
+}
+
+/// Adds a rate, or replaces the one stored for the same pair and date.
+#[derive(Debug, Deserialize)]
+pub struct UpsertExchangeRateRequest {
+    pub rate_date: NaiveDate,
+    pub from_currency: String,
+    pub to_currency: String,
+    pub rate: RateInput,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportExchangeRatesQuery {
+    /// Recorded on each imported rate; defaults to `import`.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub imported: usize,
+}
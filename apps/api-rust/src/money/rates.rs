@@ -0,0 +1,411 @@
+//! Exchange rates and conversion into a tenant's base currency.
+//!
+//! Rates are kept exactly: the stored `NUMERIC` is read as text and parsed
+//! into a fraction, and conversions are done in integer minor units and
+//! rounded once, half away from zero. A rate is in effect from its date
+//! until a later one for the same pair; a rate stored the other way round
+//! (base to foreign) is used inverted when it is the more recent.
+
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::model::{ExchangeRate, EXCHANGE_RATE_COLUMNS};
+use super::Currency;
+use crate::error::{AppError, AppResult};
+
+/// Digits kept after the decimal point, as in the `exchange_rates.rate`
+/// column.
+pub const RATE_SCALE: u32 = 12;
+
+/// A positive exchange rate as an exact fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    num: i128,
+    den: i128,
+}
+
+impl Rate {
+    /// Parse a positive decimal such as `1.0825`, with at most
+    /// [`RATE_SCALE`] decimal places and twelve integer digits.
+    pub fn parse(text: &str) -> Option<Rate> {
+        let text = text.trim();
+        let (whole, frac) = text.split_once('.').unwrap_or((text, ""));
+        if whole.is_empty() && frac.is_empty()
+            || whole.len() > 12
+            || frac.len() > RATE_SCALE as usize
+            || !whole
+                .bytes()
+                .chain(frac.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let num: i128 = format!("{}{}", whole, frac).parse().ok()?;
+        if num == 0 {
+            return None;
+        }
+        Some(Rate {
+            num,
+            den: 10_i128.pow(frac.len() as u32),
+        })
+    }
+
+    pub fn inverse(self) -> Rate {
+        Rate {
+            num: self.den,
+            den: self.num,
+        }
+    }
+
+    /// `minor` units of `from` in minor units of `to`, or `None` if the
+    /// arithmetic would overflow.
+    pub fn convert(self, minor: i64, from: &Currency, to: &Currency) -> Option<i64> {
+        let mut num = (minor as i128).checked_mul(self.num)?;
+        let mut den = self.den;
+        if to.minor_units >= from.minor_units {
+            num = num.checked_mul(10_i128.pow((to.minor_units - from.minor_units) as u32))?;
+        } else {
+            den = den.checked_mul(10_i128.pow((from.minor_units - to.minor_units) as u32))?;
+        }
+        let half = den / 2;
+        let rounded = if num >= 0 {
+            (num + half) / den
+        } else {
+            (num - half) / den
+        };
+        i64::try_from(rounded).ok()
+    }
+}
+
+/// Trim a `NUMERIC` rendered as text to its significant digits.
+pub fn normalize(text: &str) -> String {
+    match text.split_once('.') {
+        Some((whole, frac)) => {
+            let frac = frac.trim_end_matches('0');
+            if frac.is_empty() {
+                whole.to_string()
+            } else {
+                format!("{}.{}", whole, frac)
+            }
+        }
+        None => text.to_string(),
+    }
+}
+
+/// A rate parsed from an imported file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedRate {
+    pub rate_date: NaiveDate,
+    pub from_currency: &'static str,
+    pub to_currency: &'static str,
+    pub rate: String,
+}
+
+/// Parse `date,from,to,rate` lines, e.g. `2026-03-02,EUR,USD,1.0825`. A
+/// header line, blank lines and lines starting with `#` are skipped. The
+/// first bad line is reported by number.
+pub fn parse_csv(text: &str) -> Result<Vec<ParsedRate>, String> {
+    let mut rates = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let err = |msg: String| format!("Line {}: {}", i + 1, msg);
+        if fields.len() != 4 {
+            return Err(err(format!("expected 4 fields, found {}", fields.len())));
+        }
+        let Ok(rate_date) = NaiveDate::parse_from_str(fields[0], "%Y-%m-%d") else {
+            if rates.is_empty()
+                && fields[0]
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || c == '_')
+            {
+                continue; // header
+            }
+            return Err(err(format!("invalid date '{}'", fields[0])));
+        };
+        let from = Currency::find(fields[1])
+            .ok_or_else(|| err(format!("unsupported currency '{}'", fields[1])))?;
+        let to = Currency::find(fields[2])
+            .ok_or_else(|| err(format!("unsupported currency '{}'", fields[2])))?;
+        if from == to {
+            return Err(err("currencies must differ".to_string()));
+        }
+        if Rate::parse(fields[3]).is_none() {
+            return Err(err(format!("invalid rate '{}'", fields[3])));
+        }
+        rates.push(ParsedRate {
+            rate_date,
+            from_currency: from.code,
+            to_currency: to.code,
+            rate: normalize(fields[3]),
+        });
+    }
+    Ok(rates)
+}
+
+/// Converts amounts into a base currency with a tenant's stored rates,
+/// remembering each rate it used.
+pub struct Converter {
+    base: &'static Currency,
+    rates: Vec<(ExchangeRate, Rate)>,
+    used: Vec<ExchangeRate>,
+}
+
+impl Converter {
+    /// Load the tenant's rates to and from `base` dated on or before
+    /// `until`.
+    pub async fn load(
+        conn: &mut PgConnection,
+        tenant_id: Uuid,
+        base: &'static Currency,
+        until: NaiveDate,
+    ) -> AppResult<Self> {
+        let rows: Vec<ExchangeRate> = sqlx::query_as(&format!(
+            "SELECT {} FROM exchange_rates \
+             WHERE tenant_id = $1 AND (from_currency = $2 OR to_currency = $2) AND rate_date <= $3 \
+             ORDER BY rate_date DESC",
+            EXCHANGE_RATE_COLUMNS
+        ))
+        .bind(tenant_id)
+        .bind(base.code)
+        .bind(until)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(Self::new(base, rows))
+    }
+
+    pub fn new(base: &'static Currency, rows: Vec<ExchangeRate>) -> Self {
+        let rates = rows
+            .into_iter()
+            .filter_map(|row| Rate::parse(&row.rate).map(|rate| (row, rate)))
+            .collect();
+        Self {
+            base,
+            rates,
+            used: Vec::new(),
+        }
+    }
+
+    pub fn base(&self) -> &'static Currency {
+        self.base
+    }
+
+    /// `minor` units of `code` in the base currency, at the rate in effect
+    /// on `date`. Fails when no rate is on file for that day.
+    pub fn convert(&mut self, minor: i64, code: &str, date: NaiveDate) -> AppResult<i64> {
+        let from = Currency::parse(code)?;
+        if from == self.base {
+            return Ok(minor);
+        }
+        let rate = self.rate_to_base(from, date)?;
+        rate.convert(minor, from, self.base)
+            .ok_or_else(|| AppError::Validation("Amount too large to convert".to_string()))
+    }
+
+    /// `minor` units of the base currency in `code`: the reverse of
+    /// [`Converter::convert`].
+    pub fn convert_from_base(&mut self, minor: i64, code: &str, date: NaiveDate) -> AppResult<i64> {
+        let to = Currency::parse(code)?;
+        if to == self.base {
+            return Ok(minor);
+        }
+        let rate = self.rate_to_base(to, date)?;
+        rate.inverse()
+            .convert(minor, self.base, to)
+            .ok_or_else(|| AppError::Validation("Amount too large to convert".to_string()))
+    }
+
+    /// The rate from `from` into the base currency in effect on `date`,
+    /// noting the row it came from as used.
+    fn rate_to_base(&mut self, from: &'static Currency, date: NaiveDate) -> AppResult<Rate> {
+        // The newest rate on or before the date is in effect; a direct rate
+        // wins a tie with an inverse one of the same date.
+        let found = self
+            .rates
+            .iter()
+            .filter(|(row, _)| row.rate_date <= date)
+            .filter_map(|(row, rate)| {
+                if row.from_currency == from.code && row.to_currency == self.base.code {
+                    Some((row, *rate, 0))
+                } else if row.from_currency == self.base.code && row.to_currency == from.code {
+                    Some((row, rate.inverse(), 1))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|(row, _, inverse)| (std::cmp::Reverse(row.rate_date), *inverse));
+        let Some((row, rate, _)) = found else {
+            return Err(AppError::Validation(format!(
+                "No exchange rate from {} to {} on or before {}",
+                from.code, self.base.code, date
+            )));
+        };
+        if !self.used.iter().any(|u| u.id == row.id) {
+            self.used.push(row.clone());
+        }
+        Ok(rate)
+    }
+
+    /// The rates used so far, oldest first.
+    pub fn used(&self) -> Vec<ExchangeRate> {
+        let mut used = self.used.clone();
+        used.sort_by(|a, b| {
+            (a.rate_date, &a.from_currency, &a.to_currency).cmp(&(
+                b.rate_date,
+                &b.from_currency,
+                &b.to_currency,
+            ))
+        });
+        used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency(code: &str) -> &'static Currency {
+        Currency::find(code).unwrap()
+    }
+
+    fn row(date: &str, from: &str, to: &str, rate: &str) -> ExchangeRate {
+        ExchangeRate {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::nil(),
+            rate_date: date.parse().unwrap(),
+            from_currency: from.to_string(),
+            to_currency: to.to_string(),
+            rate: rate.to_string(),
+            source: "manual".to_string(),
+            created_by: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_converts_exactly_across_minor_units() {
+        let usd = currency("USD");
+        let rate = Rate::parse("1.0825").unwrap();
+        // EUR 1,000.00 -> USD 1,082.50
+        assert_eq!(rate.convert(100_000, currency("EUR"), usd), Some(108_250));
+        // EUR 0.01 -> 1.0825 cents, rounded to 1.
+        assert_eq!(rate.convert(1, currency("EUR"), usd), Some(1));
+        assert_eq!(rate.convert(-2, currency("EUR"), usd), Some(-2));
+        // JPY has no minor unit: 15,000 yen at 0.0067 is $100.50.
+        let yen = Rate::parse("0.0067").unwrap();
+        assert_eq!(yen.convert(15_000, currency("JPY"), usd), Some(10_050));
+        // And back, inverted: $100.50 / 0.0067 = 15,000 yen.
+        assert_eq!(
+            yen.inverse().convert(10_050, usd, currency("JPY")),
+            Some(15_000)
+        );
+        // KWD has three: 1.234 KWD at 3.25 is $4.0105, rounded to $4.01.
+        let kwd = Rate::parse("3.25").unwrap();
+        assert_eq!(kwd.convert(1_234, currency("KWD"), usd), Some(401));
+        assert_eq!(rate.convert(i64::MAX, currency("EUR"), usd), None);
+
+        for bad in [
+            "",
+            ".",
+            "0",
+            "0.000",
+            "-1.2",
+            "1e3",
+            "1.0000000000001",
+            "abc",
+        ] {
+            assert_eq!(Rate::parse(bad), None, "{}", bad);
+        }
+        assert_eq!(normalize("1.082500000000"), "1.0825");
+        assert_eq!(normalize("2.000000000000"), "2");
+    }
+
+    #[test]
+    fn test_uses_the_latest_rate_on_or_before_the_date() {
+        let eur_usd_march = row("2026-03-01", "EUR", "USD", "1.08");
+        let usd_eur_april = row("2026-04-01", "USD", "EUR", "0.8");
+        let mut converter = Converter::new(
+            currency("USD"),
+            vec![
+                row("2026-02-01", "EUR", "USD", "1.05"),
+                eur_usd_march.clone(),
+                usd_eur_april.clone(),
+                row("2026-03-15", "GBP", "EUR", "1.2"),
+            ],
+        );
+        let march: NaiveDate = "2026-03-20".parse().unwrap();
+        assert_eq!(converter.convert(10_000, "EUR", march).unwrap(), 10_800);
+        assert_eq!(converter.convert(10_000, "usd", march).unwrap(), 10_000);
+        assert_eq!(
+            converter.convert_from_base(10_800, "EUR", march).unwrap(),
+            10_000
+        );
+        // The inverse rate is newer in April: 100 / 0.8.
+        let april: NaiveDate = "2026-04-02".parse().unwrap();
+        assert_eq!(converter.convert(10_000, "EUR", april).unwrap(), 12_500);
+        assert_eq!(converter.convert(10_000, "EUR", march).unwrap(), 10_800);
+
+        let err = converter.convert(100, "GBP", april).unwrap_err();
+        assert!(err.to_string().contains("No exchange rate from GBP to USD"));
+        let err = converter
+            .convert(100, "EUR", "2026-01-31".parse().unwrap())
+            .unwrap_err();
+        assert!(err.to_string().contains("on or before 2026-01-31"));
+
+        let used: Vec<Uuid> = converter.used().iter().map(|r| r.id).collect();
+        assert_eq!(used, vec![eur_usd_march.id, usd_eur_april.id]);
+    }
+
+    #[test]
+    fn test_parses_rate_files() {
+        let text = "date,from,to,rate\n\
+                    # ECB reference rates\n\
+                    2026-03-02, eur ,USD,1.082500\n\
+                    \n\
+                    2026-03-02,USD,JPY,149.5\n";
+        let rates = parse_csv(text).unwrap();
+        assert_eq!(
+            rates,
+            vec![
+                ParsedRate {
+                    rate_date: "2026-03-02".parse().unwrap(),
+                    from_currency: "EUR",
+                    to_currency: "USD",
+                    rate: "1.0825".to_string(),
+                },
+                ParsedRate {
+                    rate_date: "2026-03-02".parse().unwrap(),
+                    from_currency: "USD",
+                    to_currency: "JPY",
+                    rate: "149.5".to_string(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse_csv("2026-03-02,EUR,USD,1.08\n2026-03-03,EUR,XXX,1.08").unwrap_err(),
+            "Line 2: unsupported currency 'XXX'"
+        );
+        assert_eq!(
+            parse_csv("2026-03-02,EUR,EUR,1").unwrap_err(),
+            "Line 1: currencies must differ"
+        );
+        assert_eq!(
+            parse_csv("2026-03-02,EUR,USD").unwrap_err(),
+            "Line 1: expected 4 fields, found 3"
+        );
+        assert_eq!(
+            parse_csv("2026-03-02,EUR,USD,0").unwrap_err(),
+            "Line 1: invalid rate '0'"
+        );
+        assert_eq!(
+            parse_csv("2026-03-02,EUR,USD,1\nmarch,EUR,USD,1").unwrap_err(),
+            "Line 2: invalid date 'march'"
+        );
+    }
+}
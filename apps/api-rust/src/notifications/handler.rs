@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -7,11 +7,11 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::notifications::model::*;
-use crate::AppState;
 
 pub async fn list_notifications(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListNotificationsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -30,7 +30,7 @@ pub async fn list_notifications(
         .bind(claims.sub)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -42,7 +42,7 @@ pub async fn list_notifications(
         .bind(claims.sub)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -52,13 +52,13 @@ pub async fn list_notifications(
         )
         .bind(claims.tid)
         .bind(claims.sub)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as("SELECT COUNT(*) FROM notifications WHERE tenant_id = $1 AND user_id = $2")
             .bind(claims.tid)
             .bind(claims.sub)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
     };
 
@@ -74,7 +74,7 @@ pub async fn list_notifications(
 }
 
 pub async fn get_unread_count(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let (count,): (i64,) = sqlx::query_as(
@@ -82,14 +82,14 @@ pub async fn get_unread_count(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({ "unread_count": count })))
 }
 
 pub async fn mark_read(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(notification_id): Path<Uuid>,
 ) -> AppResult<Json<Notification>> {
@@ -101,7 +101,7 @@ pub async fn mark_read(
     .bind(notification_id)
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))?;
 
@@ -109,7 +109,7 @@ pub async fn mark_read(
 }
 
 pub async fn mark_all_read(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     let result = sqlx::query(
@@ -118,7 +118,7 @@ pub async fn mark_all_read(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(
@@ -127,7 +127,7 @@ pub async fn mark_all_read(
 }
 
 pub async fn create_notification(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateNotificationRequest>,
 ) -> AppResult<(StatusCode, Json<Notification>)> {
@@ -143,14 +143,14 @@ pub async fn create_notification(
     .bind(&payload.body)
     .bind(&payload.resource_type)
     .bind(payload.resource_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(notification)))
 }
 
 pub async fn delete_notification(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(notification_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -159,7 +159,7 @@ pub async fn delete_notification(
             .bind(notification_id)
             .bind(claims.tid)
             .bind(claims.sub)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
 
     if result.rows_affected() == 0 {
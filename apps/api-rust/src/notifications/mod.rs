@@ -1,2 +1,25 @@
 pub mod handler;
 pub mod model;
+pub mod notify;
+pub mod reminders;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/notifications", get(handler::list_notifications))
+        .route("/notifications", post(handler::create_notification))
+        .route(
+            "/notifications/unread-count",
+            get(handler::get_unread_count),
+        )
+        .route("/notifications/read-all", put(handler::mark_all_read))
+        .route("/notifications/{id}/read", put(handler::mark_read))
+        .route("/notifications/{id}", delete(handler::delete_notification))
+}
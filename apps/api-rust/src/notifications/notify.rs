@@ -0,0 +1,48 @@
+use uuid::Uuid;
+
+use crate::ws::{WsBroadcast, WsEventPayload};
+
+/// A system-generated in-app notification.
+#[derive(Debug, Clone)]
+pub struct NewNotification<'a> {
+    pub tenant_id: Uuid,
+    pub user_id: Uuid,
+    pub kind: &'a str,
+    pub title: String,
+    pub body: Option<String>,
+    pub resource_type: Option<&'a str>,
+    pub resource_id: Option<Uuid>,
+}
+
+/// Persist a notification and push it to the user's open WebSocket sessions.
+pub async fn notify(
+    db: impl sqlx::PgExecutor<'_>,
+    ws: &WsBroadcast,
+    n: NewNotification<'_>,
+) -> Result<Uuid, sqlx::Error> {
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO notifications (tenant_id, user_id, type, title, body, resource_type, resource_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(n.tenant_id)
+    .bind(n.user_id)
+    .bind(n.kind)
+    .bind(&n.title)
+    .bind(n.body.as_deref())
+    .bind(n.resource_type)
+    .bind(n.resource_id)
+    .fetch_one(db)
+    .await?;
+
+    ws.send_to_user(
+        n.tenant_id,
+        n.user_id,
+        WsEventPayload::Notification {
+            id,
+            title: n.title,
+            body: n.body,
+        },
+    );
+
+    Ok(id)
+}
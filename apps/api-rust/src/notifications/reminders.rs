@@ -0,0 +1,107 @@
+//! Delivery of due `reminder_jobs` rows.
+
+use uuid::Uuid;
+
+use super::notify::{notify, NewNotification};
+use crate::mailer::{outbox, OutgoingEmail};
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "reminders.fire_due";
+
+#[derive(Debug, sqlx::FromRow)]
+struct DueReminder {
+    id: Uuid,
+    user_id: Uuid,
+    reminder_type: String,
+    reference_type: Option<String>,
+    reference_id: Option<Uuid>,
+    channel: Option<String>,
+    title: Option<String>,
+    body: Option<String>,
+}
+
+/// Fire every reminder whose `fire_at` has passed. Each reminder produces an
+/// in-app notification; `email` reminders are also queued to the outbox.
+pub async fn fire_due(state: AppState, _job: Job) -> anyhow::Result<()> {
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+
+        let due: Vec<DueReminder> = sqlx::query_as(
+            "UPDATE reminder_jobs SET fired = TRUE, fired_at = NOW() \
+             WHERE tenant_id = $1 AND fired = FALSE AND fire_at <= NOW() \
+             RETURNING id, user_id, reminder_type, reference_type, reference_id, channel, title, body",
+        )
+        .bind(tenant_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for reminder in &due {
+            let title = reminder
+                .title
+                .clone()
+                .unwrap_or_else(|| default_title(&reminder.reminder_type).to_string());
+
+            notify(
+                &mut *tx,
+                &state.ws_broadcast,
+                NewNotification {
+                    tenant_id,
+                    user_id: reminder.user_id,
+                    kind: "reminder",
+                    title: title.clone(),
+                    body: reminder.body.clone(),
+                    resource_type: reminder.reference_type.as_deref(),
+                    resource_id: reminder.reference_id,
+                },
+            )
+            .await?;
+
+            if reminder.channel.as_deref() == Some("email") {
+                let to: Option<String> = sqlx::query_scalar(
+                    "SELECT email FROM users WHERE id = $1 AND tenant_id = $2 AND status = 'active'",
+                )
+                .bind(reminder.user_id)
+                .bind(tenant_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                if let Some(to) = to {
+                    let email = OutgoingEmail {
+                        to,
+                        subject: title,
+                        text_body: reminder.body.clone().unwrap_or_default(),
+                        html_body: None,
+                    };
+                    outbox::enqueue(
+                        &mut *tx,
+                        tenant_id,
+                        &email,
+                        outbox::EnqueueOptions {
+                            category: Some("reminder"),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                }
+            }
+
+            tracing::debug!(reminder_id = %reminder.id, "Fired reminder");
+        }
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+fn default_title(reminder_type: &str) -> &'static str {
+    match reminder_type {
+        "meeting_24h" => "Meeting tomorrow",
+        "meeting_1h" => "Meeting in 1 hour",
+        "meeting_10m" => "Meeting in 10 minutes",
+        "application_follow_up" => "Follow up on application",
+        "interview_feedback" => "Interview feedback due",
+        "offer_deadline" => "Offer deadline approaching",
+        _ => "Reminder",
+    }
+}
@@ -0,0 +1,58 @@
+//! Expiry of sent offers whose `expiry_date` has passed.
+
+use uuid::Uuid;
+
+use crate::notifications::notify::{notify, NewNotification};
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "offers.expire";
+
+/// Move sent offers past their expiry date to `expired`, close their
+/// negotiations and notify the offer's creator. Offers awaiting an answer to
+/// a counter-offer are left for the recruiter, who must set a new expiry
+/// date when responding.
+pub async fn expire_offers(state: AppState, _job: Job) -> anyhow::Result<()> {
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+
+        let expired: Vec<(Uuid, String, Uuid)> = sqlx::query_as(
+            "UPDATE offers SET status = 'expired', updated_at = NOW() \
+             WHERE tenant_id = $1 AND status = 'sent' AND expiry_date < CURRENT_DATE \
+             RETURNING id, title, created_by",
+        )
+        .bind(tenant_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let ids: Vec<Uuid> = expired.iter().map(|(id, _, _)| *id).collect();
+        sqlx::query(
+            "UPDATE offer_negotiations SET status = 'expired', updated_at = NOW() \
+             WHERE tenant_id = $1 AND offer_id = ANY($2)",
+        )
+        .bind(tenant_id)
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+
+        for (id, title, created_by) in expired {
+            notify(
+                &mut *tx,
+                &state.ws_broadcast,
+                NewNotification {
+                    tenant_id,
+                    user_id: created_by,
+                    kind: "offer_expired",
+                    title: format!("Offer expired: {}", title),
+                    body: Some("The candidate did not respond before the expiry date.".to_string()),
+                    resource_type: Some("offer"),
+                    resource_id: Some(id),
+                },
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
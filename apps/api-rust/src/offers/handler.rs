@@ -3,39 +3,70 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use sqlx::PgConnection;
 use uuid::Uuid;
 
+use crate::applications::handler::{broadcast_moved, hire_application};
+use crate::approvals;
 use crate::auth::jwt::Claims;
+use crate::automations::events::{self, DomainEvent};
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::offers::model::*;
+use crate::offers::negotiation;
 use crate::AppState;
 
+/// `equity_pct` is NUMERIC; read it as a float.
+pub(crate) const OFFER_COLUMNS: &str = "id, tenant_id, application_id, job_id, candidate_id, \
+    status, title, base_salary_cents, salary_currency, equity_pct::float8 AS equity_pct, \
+    signing_bonus_cents, start_date, expiry_date, benefits_summary, custom_terms, sent_at, \
+    viewed_at, responded_at, accepted_at, declined_at, decline_reason, letter_document_id, \
+    created_by, created_at, updated_at";
+
+/// Load an offer, locking it for the rest of the transaction.
+pub(crate) async fn lock_offer(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> AppResult<Offer> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM offers WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        OFFER_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))
+}
+
 pub async fn list_offers(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListOffersQuery>,
 ) -> AppResult<Json<Vec<Offer>>> {
     let job_id_filter = params.job_id.map(|id| id.to_string()).unwrap_or_default();
     let status_filter = params.status.as_deref().unwrap_or("");
 
-    let offers: Vec<Offer> = sqlx::query_as(
-        "SELECT * FROM offers \
+    let offers: Vec<Offer> = sqlx::query_as(&format!(
+        "SELECT {} FROM offers \
          WHERE tenant_id = $1 \
          AND ($2 = '' OR job_id::text = $2) \
          AND ($3 = '' OR status = $3) \
          ORDER BY created_at DESC",
-    )
+        OFFER_COLUMNS
+    ))
     .bind(claims.tid)
     .bind(&job_id_filter)
     .bind(status_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(offers))
 }
 
 pub async fn create_offer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateOfferRequest>,
 ) -> AppResult<(StatusCode, Json<Offer>)> {
@@ -47,20 +78,21 @@ pub async fn create_offer(
     let _: (Uuid,) = sqlx::query_as("SELECT id FROM applications WHERE id = $1 AND tenant_id = $2")
         .bind(payload.application_id)
         .bind(claims.tid)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
     let salary_currency = payload.salary_currency.as_deref().unwrap_or("USD");
 
-    let offer: Offer = sqlx::query_as(
+    let offer: Offer = sqlx::query_as(&format!(
         "INSERT INTO offers \
          (tenant_id, application_id, job_id, candidate_id, status, title, \
           base_salary_cents, salary_currency, equity_pct, signing_bonus_cents, \
           start_date, expiry_date, benefits_summary, custom_terms, created_by) \
          VALUES ($1, $2, $3, $4, 'draft', $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) \
-         RETURNING *",
-    )
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
     .bind(claims.tid)
     .bind(payload.application_id)
     .bind(payload.job_id)
@@ -75,40 +107,46 @@ pub async fn create_offer(
     .bind(&payload.benefits_summary)
     .bind(&payload.custom_terms)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(offer)))
 }
 
 pub async fn get_offer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<Offer>> {
-    let offer: Offer = sqlx::query_as("SELECT * FROM offers WHERE id = $1 AND tenant_id = $2")
-        .bind(id)
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
+    let offer: Offer = sqlx::query_as(&format!(
+        "SELECT {} FROM offers WHERE id = $1 AND tenant_id = $2",
+        OFFER_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
 
     Ok(Json(offer))
 }
 
 pub async fn update_offer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateOfferRequest>,
 ) -> AppResult<Json<Offer>> {
     // Only allow updates to draft offers
-    let existing: Offer = sqlx::query_as("SELECT * FROM offers WHERE id = $1 AND tenant_id = $2")
-        .bind(id)
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
+    let existing: Offer = sqlx::query_as(&format!(
+        "SELECT {} FROM offers WHERE id = $1 AND tenant_id = $2",
+        OFFER_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
 
     if existing.status != "draft" {
         return Err(AppError::Validation(format!(
@@ -117,7 +155,7 @@ pub async fn update_offer(
         )));
     }
 
-    let offer: Offer = sqlx::query_as(
+    let offer: Offer = sqlx::query_as(&format!(
         "UPDATE offers SET \
          title = COALESCE($3, title), \
          base_salary_cents = COALESCE($4, base_salary_cents), \
@@ -130,8 +168,9 @@ pub async fn update_offer(
          custom_terms = COALESCE($11, custom_terms), \
          updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 \
-         RETURNING *",
-    )
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
     .bind(id)
     .bind(claims.tid)
     .bind(&payload.title)
@@ -143,23 +182,18 @@ pub async fn update_offer(
     .bind(payload.expiry_date)
     .bind(&payload.benefits_summary)
     .bind(&payload.custom_terms)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(offer))
 }
 
 pub async fn send_offer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<Offer>> {
-    let existing: Offer = sqlx::query_as("SELECT * FROM offers WHERE id = $1 AND tenant_id = $2")
-        .bind(id)
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
+    let existing = lock_offer(&mut tx, claims.tid, id).await?;
 
     if existing.status != "draft" {
         return Err(AppError::Validation(format!(
@@ -167,31 +201,31 @@ pub async fn send_offer(
             existing.status
         )));
     }
+    approvals::require_approved(&mut *tx, claims.tid, approvals::OFFER, id).await?;
 
-    let offer: Offer = sqlx::query_as(
+    let offer: Offer = sqlx::query_as(&format!(
         "UPDATE offers SET status = 'sent', sent_at = NOW(), updated_at = NOW() \
-         WHERE id = $1 AND tenant_id = $2 \
-         RETURNING *",
-    )
+         WHERE id = $1 AND tenant_id = $2 AND status = 'draft' \
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
     .bind(id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Offer is no longer a draft".to_string()))?;
 
     Ok(Json(offer))
 }
 
+/// Accept a sent offer and hire the candidate on its application.
 pub async fn accept_offer(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<Offer>> {
-    let existing: Offer = sqlx::query_as("SELECT * FROM offers WHERE id = $1 AND tenant_id = $2")
-        .bind(id)
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
+    let existing = lock_offer(&mut tx, claims.tid, id).await?;
 
     if existing.status != "sent" {
         return Err(AppError::Validation(format!(
@@ -200,44 +234,87 @@ pub async fn accept_offer(
         )));
     }
 
-    let offer: Offer = sqlx::query_as(
+    if existing
+        .expiry_date
+        .is_some_and(|d| d < chrono::Utc::now().date_naive())
+    {
+        return Err(AppError::Validation("Offer has expired".to_string()));
+    }
+
+    let offer: Offer = sqlx::query_as(&format!(
         "UPDATE offers SET \
          status = 'accepted', \
          responded_at = NOW(), \
          accepted_at = NOW(), \
          updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 \
-         RETURNING *",
-    )
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
     .bind(id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
+    negotiation::close(
+        &mut tx,
+        &offer,
+        "accepted",
+        "accept",
+        None,
+        Some(claims.sub),
+    )
+    .await?;
+
+    let (application, from_stage) = hire_application(
+        &mut tx,
+        claims.tid,
+        claims.sub,
+        offer.application_id,
+        offer.base_salary_cents,
+        offer.equity_pct,
+    )
+    .await?;
+
+    events::emit(
+        &mut *tx,
+        claims.tid,
+        claims.sub,
+        DomainEvent::OfferAccepted {
+            offer_id: offer.id,
+            application_id: offer.application_id,
+            job_id: offer.job_id,
+            candidate_id: offer.candidate_id,
+            title: offer.title.clone(),
+            base_salary_cents: offer.base_salary_cents,
+        },
+    )
+    .await?;
+
+    if from_stage != application.stage {
+        broadcast_moved(&state, &claims, &application, from_stage);
+    }
+
     Ok(Json(offer))
 }
 
+/// Decline a sent offer, or one still being negotiated.
 pub async fn decline_offer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<DeclineOfferRequest>,
 ) -> AppResult<Json<Offer>> {
-    let existing: Offer = sqlx::query_as("SELECT * FROM offers WHERE id = $1 AND tenant_id = $2")
-        .bind(id)
-        .bind(claims.tid)
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Offer not found".to_string()))?;
+    let existing = lock_offer(&mut tx, claims.tid, id).await?;
 
-    if existing.status != "sent" {
+    if existing.status != "sent" && existing.status != "negotiating" {
         return Err(AppError::Validation(format!(
             "Cannot decline offer in '{}' status. Only sent offers can be declined.",
             existing.status
         )));
     }
 
-    let offer: Offer = sqlx::query_as(
+    let offer: Offer = sqlx::query_as(&format!(
         "UPDATE offers SET \
          status = 'declined', \
          responded_at = NOW(), \
@@ -245,12 +322,32 @@ pub async fn decline_offer(
          decline_reason = $3, \
          updated_at = NOW() \
          WHERE id = $1 AND tenant_id = $2 \
-         RETURNING *",
-    )
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
     .bind(id)
     .bind(claims.tid)
     .bind(&payload.reason)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    negotiation::close(
+        &mut tx,
+        &offer,
+        "declined",
+        "decline",
+        payload.reason.clone(),
+        Some(claims.sub),
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE applications SET offer_declined_at = NOW(), updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(offer.application_id)
+    .bind(claims.tid)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(offer))
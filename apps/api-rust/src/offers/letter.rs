@@ -0,0 +1,279 @@
+//! Offer letters, rendered from the tenant's `offer_letter` email template
+//! (or a built-in one) to PDF and filed with the candidate's documents.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use super::handler::lock_offer;
+use super::model::{GenerateLetterRequest, Offer};
+use crate::auth::jwt::Claims;
+use crate::candidates::model::CandidateDocument;
+use crate::error::{AppError, AppResult};
+use crate::invoices::pdf::render_letter;
+use crate::mailer::template;
+use crate::middleware::tenant::TenantTx;
+use crate::money;
+use crate::storage;
+use crate::AppState;
+
+/// `email_templates.category` of templates usable as offer letters.
+pub const TEMPLATE_CATEGORY: &str = "offer_letter";
+
+const DEFAULT_SUBJECT: &str = "Offer of Employment: {{offer_title}}";
+
+const DEFAULT_BODY: &str = "{{offer_date}}
+
+Dear {{candidate_name}},
+
+On behalf of {{company_name}}, we are pleased to offer you the position of {{offer_title}}.
+
+{{offer_compensation}}
+
+{{offer_benefits}}
+
+{{offer_terms}}
+
+Please confirm your acceptance by {{offer_expiry_date}}.
+
+Sincerely,
+{{company_name}}";
+
+/// Render the offer's letter, store the PDF and link it to the offer,
+/// replacing any earlier letter.
+pub async fn generate_letter(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<GenerateLetterRequest>>,
+) -> AppResult<(StatusCode, Json<CandidateDocument>)> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let offer = lock_offer(&mut tx, claims.tid, id).await?;
+    if matches!(offer.status.as_str(), "declined" | "expired") {
+        return Err(AppError::Validation(format!(
+            "Cannot write a letter for an offer in '{}' status",
+            offer.status
+        )));
+    }
+
+    let (subject, body): (String, String) = match sqlx::query_as(
+        "SELECT subject, body FROM email_templates \
+         WHERE tenant_id = $1 AND category = $2 AND is_active = true \
+         AND ($3::uuid IS NULL OR id = $3) \
+         ORDER BY is_default DESC, updated_at DESC LIMIT 1",
+    )
+    .bind(claims.tid)
+    .bind(TEMPLATE_CATEGORY)
+    .bind(payload.template_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    {
+        Some(template) => template,
+        None if payload.template_id.is_some() => {
+            return Err(AppError::NotFound(
+                "Offer letter template not found".to_string(),
+            ))
+        }
+        None => (DEFAULT_SUBJECT.to_string(), DEFAULT_BODY.to_string()),
+    };
+
+    let locale: String = sqlx::query_scalar("SELECT locale FROM tenants WHERE id = $1")
+        .bind(claims.tid)
+        .fetch_one(&mut *tx)
+        .await?;
+    let vars = template::build_context(
+        &mut tx,
+        claims.tid,
+        &state.config.app_base_url,
+        template::ContextRefs {
+            candidate_id: Some(offer.candidate_id),
+            job_id: Some(offer.job_id),
+            invoice_id: None,
+        },
+        &Value::Object(offer_variables(
+            &offer,
+            chrono::Utc::now().date_naive(),
+            &locale,
+        )),
+    )
+    .await?;
+    let title = template::render(&subject, &vars).text;
+    let text = template::render(&body, &vars).text;
+    let pdf = tokio::task::spawn_blocking(move || render_letter(&title, &text))
+        .await
+        .map_err(|e| AppError::Internal(format!("Offer letter rendering failed: {}", e)))?;
+
+    let document_id = Uuid::new_v4();
+    let filename = "offer-letter.pdf";
+    let s3_key = format!(
+        "tenants/{}/candidates/{}/documents/{}/{}",
+        claims.tid, offer.candidate_id, document_id, filename
+    );
+    let size_bytes = pdf.len() as i64;
+    storage::put_object(&state.config, &s3_key, "application/pdf", pdf)
+        .await
+        .map_err(AppError::Internal)?;
+
+    let document: CandidateDocument = sqlx::query_as(
+        "INSERT INTO candidate_documents \
+         (id, tenant_id, candidate_id, document_type, filename, mime_type, size_bytes, s3_key) \
+         VALUES ($1, $2, $3, 'offer_letter', $4, 'application/pdf', $5, $6) \
+         RETURNING *",
+    )
+    .bind(document_id)
+    .bind(claims.tid)
+    .bind(offer.candidate_id)
+    .bind(filename)
+    .bind(size_bytes)
+    .bind(&s3_key)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE offers SET letter_document_id = $3, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .bind(document_id)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+/// Template variables describing the offer, as of `today`, with amounts
+/// written for `locale`.
+pub fn offer_variables(
+    offer: &Offer,
+    today: chrono::NaiveDate,
+    locale: &str,
+) -> Map<String, Value> {
+    let currency = offer.salary_currency.as_deref().unwrap_or("USD");
+    let money = |cents: i64| money::format(cents, currency, locale);
+    let date = |d: chrono::NaiveDate| d.format("%B %d, %Y").to_string();
+
+    let mut compensation = Vec::new();
+    if let Some(cents) = offer.base_salary_cents {
+        compensation.push(format!("Base salary: {} per year", money(cents)));
+    }
+    if let Some(pct) = offer.equity_pct {
+        compensation.push(format!("Equity: {:.2}%", pct));
+    }
+    if let Some(cents) = offer.signing_bonus_cents {
+        compensation.push(format!("Signing bonus: {}", money(cents)));
+    }
+    if let Some(start) = offer.start_date {
+        compensation.push(format!("Start date: {}", date(start)));
+    }
+
+    let mut vars = Map::new();
+    let mut insert = |key: &str, value: Option<String>| {
+        if let Some(v) = value {
+            vars.insert(key.to_string(), Value::String(v));
+        }
+    };
+    insert("offer_title", Some(offer.title.clone()));
+    insert("offer_date", Some(date(today)));
+    insert("offer_salary", offer.base_salary_cents.map(money));
+    insert(
+        "offer_equity",
+        offer.equity_pct.map(|p| format!("{:.2}%", p)),
+    );
+    insert("offer_signing_bonus", offer.signing_bonus_cents.map(money));
+    insert("offer_start_date", offer.start_date.map(date));
+    insert(
+        "offer_expiry_date",
+        Some(
+            offer
+                .expiry_date
+                .map_or_else(|| "your earliest convenience".to_string(), date),
+        ),
+    );
+    insert("offer_compensation", Some(compensation.join("\n")));
+    insert(
+        "offer_benefits",
+        Some(offer.benefits_summary.clone().unwrap_or_default()),
+    );
+    insert(
+        "offer_terms",
+        Some(offer.custom_terms.clone().unwrap_or_default()),
+    );
+    vars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+
+    fn offer() -> Offer {
+        Offer {
+            id: Uuid::nil(),
+            tenant_id: Uuid::nil(),
+            application_id: Uuid::nil(),
+            job_id: Uuid::nil(),
+            candidate_id: Uuid::nil(),
+            status: "sent".to_string(),
+            title: "Senior Accountant".to_string(),
+            base_salary_cents: Some(9_500_000),
+            salary_currency: Some("USD".to_string()),
+            equity_pct: None,
+            signing_bonus_cents: Some(500_000),
+            start_date: NaiveDate::from_ymd_opt(2026, 3, 2),
+            expiry_date: None,
+            benefits_summary: None,
+            custom_terms: None,
+            sent_at: None,
+            viewed_at: None,
+            responded_at: None,
+            accepted_at: None,
+            declined_at: None,
+            decline_reason: None,
+            letter_document_id: None,
+            created_by: Uuid::nil(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_default_letter_lists_only_offered_terms() {
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let mut vars = offer_variables(&offer(), today, "en-US");
+        vars.insert("candidate_name".into(), "Ada Lovelace".into());
+        vars.insert("company_name".into(), "Acme CPA".into());
+
+        let body = template::render(DEFAULT_BODY, &vars);
+        assert!(body.missing.is_empty(), "{:?}", body.missing);
+        assert!(body
+            .text
+            .starts_with("February 01, 2026\n\nDear Ada Lovelace,"));
+        assert!(body.text.contains(
+            "Base salary: $95,000.00 per year\nSigning bonus: $5,000.00\nStart date: March 02, 2026"
+        ));
+        assert!(!body.text.contains("Equity"));
+        assert!(body.text.contains("by your earliest convenience."));
+        assert_eq!(
+            template::render(DEFAULT_SUBJECT, &vars).text,
+            "Offer of Employment: Senior Accountant"
+        );
+    }
+
+    #[test]
+    fn test_salary_is_written_in_its_currency_for_the_locale() {
+        let offer = Offer {
+            salary_currency: Some("EUR".to_string()),
+            ..offer()
+        };
+        let vars = offer_variables(&offer, Utc::now().date_naive(), "de-DE");
+        assert_eq!(vars["offer_salary"], "95.000,00\u{a0}€");
+        let vars = offer_variables(&offer, Utc::now().date_naive(), "en-US");
+        assert_eq!(vars["offer_salary"], "€95,000.00");
+    }
+}
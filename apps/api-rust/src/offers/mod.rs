@@ -1,2 +1,52 @@
+pub mod expiry;
 pub mod handler;
+pub mod letter;
 pub mod model;
+pub mod negotiation;
+
+use axum::{
+    middleware::from_fn,
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::middleware::idempotency::idempotency_check;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/offers", get(handler::list_offers))
+        .route(
+            "/offers",
+            post(handler::create_offer).layer(from_fn(idempotency_check)),
+        )
+        .route("/offers/{id}", get(handler::get_offer))
+        .route("/offers/{id}", put(handler::update_offer))
+        .route(
+            "/offers/{id}/send",
+            post(handler::send_offer).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/offers/{id}/accept",
+            post(handler::accept_offer).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/offers/{id}/decline",
+            post(handler::decline_offer).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/offers/{id}/counter",
+            post(negotiation::counter_offer).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/offers/{id}/negotiation",
+            get(negotiation::get_negotiation),
+        )
+        .route(
+            "/offers/{id}/negotiation/respond",
+            post(negotiation::respond_to_counter).layer(from_fn(idempotency_check)),
+        )
+        .route("/offers/{id}/letter", post(letter::generate_letter))
+        .route("/offer-negotiations", get(negotiation::list_negotiations))
+}
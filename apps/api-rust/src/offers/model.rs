@@ -2,6 +2,8 @@ use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::error::FieldError;
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Offer {
     pub id: Uuid,
@@ -25,6 +27,7 @@ pub struct Offer {
     pub accepted_at: Option<DateTime<Utc>>,
     pub declined_at: Option<DateTime<Utc>>,
     pub decline_reason: Option<String>,
+    pub letter_document_id: Option<Uuid>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -69,3 +72,131 @@ pub struct ListOffersQuery {
 pub struct DeclineOfferRequest {
     pub reason: Option<String>,
 }
+
+/// The negotiable terms of an offer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OfferTerms {
+    pub base_salary_cents: Option<i64>,
+    pub equity_pct: Option<f64>,
+    pub signing_bonus_cents: Option<i64>,
+    pub start_date: Option<NaiveDate>,
+}
+
+impl OfferTerms {
+    pub fn of(offer: &Offer) -> Self {
+        Self {
+            base_salary_cents: offer.base_salary_cents,
+            equity_pct: offer.equity_pct,
+            signing_bonus_cents: offer.signing_bonus_cents,
+            start_date: offer.start_date,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the proposed amounts, by field.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for (field, cents) in [
+            ("base_salary_cents", self.base_salary_cents),
+            ("signing_bonus_cents", self.signing_bonus_cents),
+        ] {
+            if cents.is_some_and(|c| c < 0) {
+                errors.push(FieldError::new(field, "must not be negative"));
+            }
+        }
+        if self
+            .equity_pct
+            .is_some_and(|pct| !(0.0..=100.0).contains(&pct))
+        {
+            errors.push(FieldError::new("equity_pct", "must be between 0 and 100"));
+        }
+        errors
+    }
+
+    /// These terms, falling back to `base` for any left unset.
+    pub fn or(&self, base: &OfferTerms) -> Self {
+        Self {
+            base_salary_cents: self.base_salary_cents.or(base.base_salary_cents),
+            equity_pct: self.equity_pct.or(base.equity_pct),
+            signing_bonus_cents: self.signing_bonus_cents.or(base.signing_bonus_cents),
+            start_date: self.start_date.or(base.start_date),
+        }
+    }
+}
+
+/// One entry in a negotiation's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NegotiationRound {
+    pub round: i32,
+    /// `company` or `candidate`.
+    pub proposed_by: String,
+    /// `offer`, `counter`, `accept`, `reject`, `revise` or `decline`.
+    pub action: String,
+    #[serde(flatten)]
+    pub terms: OfferTerms,
+    pub notes: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OfferNegotiation {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub offer_id: Option<Uuid>,
+    pub application_id: Uuid,
+    pub candidate_name: Option<String>,
+    pub job_title: Option<String>,
+    pub status: String,
+    /// [`NegotiationRound`]s, oldest first.
+    pub rounds: serde_json::Value,
+    /// The terms most recently proposed, by either side.
+    pub current_offer: serde_json::Value,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListNegotiationsQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CounterOfferRequest {
+    #[serde(flatten)]
+    pub terms: OfferTerms,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NegotiationResponse {
+    /// Take the candidate's counter as the new terms.
+    Accept,
+    /// Keep the current terms.
+    Reject,
+    /// Propose new terms.
+    Revise,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RespondNegotiationRequest {
+    pub action: NegotiationResponse,
+    /// New terms for `revise`; unset terms are kept.
+    #[serde(flatten)]
+    pub terms: OfferTerms,
+    pub notes: Option<String>,
+    /// Close the negotiation to further counters.
+    #[serde(default)]
+    pub final_offer: bool,
+    pub expiry_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GenerateLetterRequest {
+    /// An `offer_letter` email template; defaults to the tenant's default one.
+    pub template_id: Option<Uuid>,
+}
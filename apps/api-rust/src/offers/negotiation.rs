@@ -0,0 +1,375 @@
+//! Offer negotiation.
+//!
+//! The candidate counters a sent offer, which puts it in `negotiating` until
+//! a recruiter accepts the counter, rejects it or revises the terms; the
+//! offer is then sent again on the resulting terms. Every step, including the
+//! original offer and the final accept or decline, is appended to the
+//! negotiation's rounds.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use chrono::Utc;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::handler::{lock_offer, OFFER_COLUMNS};
+use super::model::*;
+use crate::auth::jwt::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
+use crate::notifications::notify::{notify, NewNotification};
+use crate::AppState;
+
+const NEGOTIATION_COLUMNS: &str = "id, tenant_id, offer_id, application_id, candidate_name, \
+    job_title, COALESCE(status, 'initial_offer') AS status, \
+    COALESCE(rounds, '[]'::jsonb) AS rounds, \
+    COALESCE(current_offer, '{}'::jsonb) AS current_offer, created_at, updated_at";
+
+pub async fn list_negotiations(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<ListNegotiationsQuery>,
+) -> AppResult<Json<Vec<OfferNegotiation>>> {
+    let negotiations: Vec<OfferNegotiation> = sqlx::query_as(&format!(
+        "SELECT {} FROM offer_negotiations \
+         WHERE tenant_id = $1 AND ($2::text IS NULL OR status = $2) \
+         ORDER BY updated_at DESC NULLS LAST, id",
+        NEGOTIATION_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(&params.status)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(negotiations))
+}
+
+pub async fn get_negotiation(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(offer_id): Path<Uuid>,
+) -> AppResult<Json<OfferNegotiation>> {
+    let negotiation: OfferNegotiation = sqlx::query_as(&format!(
+        "SELECT {} FROM offer_negotiations WHERE offer_id = $1 AND tenant_id = $2",
+        NEGOTIATION_COLUMNS
+    ))
+    .bind(offer_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Offer has not been negotiated".to_string()))?;
+
+    Ok(Json(negotiation))
+}
+
+/// Record the candidate's counter-proposal to a sent offer. Terms left out
+/// of the counter stay as offered.
+pub async fn counter_offer(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CounterOfferRequest>,
+) -> AppResult<Json<OfferNegotiation>> {
+    if payload.terms.is_empty() {
+        return Err(AppError::Validation(
+            "A counter-offer must propose at least one term".to_string(),
+        ));
+    }
+    let errors = payload.terms.validate();
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+
+    let offer = lock_offer(&mut tx, claims.tid, id).await?;
+    match offer.status.as_str() {
+        "sent" => {}
+        "negotiating" => {
+            return Err(AppError::Conflict(
+                "The previous counter-offer has not been answered yet".to_string(),
+            ))
+        }
+        status => {
+            return Err(AppError::Validation(format!(
+                "Cannot counter an offer in '{}' status",
+                status
+            )))
+        }
+    }
+    if offer
+        .expiry_date
+        .is_some_and(|d| d < Utc::now().date_naive())
+    {
+        return Err(AppError::Validation("Offer has expired".to_string()));
+    }
+
+    let negotiation = match lock(&mut tx, claims.tid, id).await? {
+        Some(negotiation) => negotiation,
+        None => open(&mut tx, &offer).await?,
+    };
+    if negotiation.status == "final_offer" {
+        return Err(AppError::Validation(
+            "This is a final offer; it can only be accepted or declined".to_string(),
+        ));
+    }
+
+    let round = NegotiationRound {
+        round: next_round(&negotiation),
+        proposed_by: "candidate".to_string(),
+        action: "counter".to_string(),
+        terms: payload.terms.or(&OfferTerms::of(&offer)),
+        notes: payload.notes,
+        user_id: Some(claims.sub),
+        created_at: Utc::now(),
+    };
+    let negotiation = append(&mut tx, &negotiation, "counter_offer", round).await?;
+
+    sqlx::query(
+        "UPDATE offers SET status = 'negotiating', responded_at = NOW(), updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .execute(&mut *tx)
+    .await?;
+
+    notify(
+        &mut *tx,
+        &state.ws_broadcast,
+        NewNotification {
+            tenant_id: claims.tid,
+            user_id: offer.created_by,
+            kind: "offer_countered",
+            title: format!("Counter-offer received: {}", offer.title),
+            body: negotiation.candidate_name.clone(),
+            resource_type: Some("offer"),
+            resource_id: Some(offer.id),
+        },
+    )
+    .await?;
+
+    Ok(Json(negotiation))
+}
+
+/// Answer the candidate's counter-offer and send the offer again on the
+/// resulting terms.
+pub async fn respond_to_counter(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RespondNegotiationRequest>,
+) -> AppResult<Json<OfferNegotiation>> {
+    let errors = payload.terms.validate();
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+
+    let offer = lock_offer(&mut tx, claims.tid, id).await?;
+    if offer.status != "negotiating" {
+        return Err(AppError::Conflict(
+            "The offer has no counter-offer awaiting a response".to_string(),
+        ));
+    }
+    let negotiation = lock(&mut tx, claims.tid, id)
+        .await?
+        .ok_or_else(|| AppError::Conflict("The offer has not been negotiated".to_string()))?;
+
+    let offered = OfferTerms::of(&offer);
+    let (action, terms) = match payload.action {
+        NegotiationResponse::Accept => (
+            "accept",
+            serde_json::from_value::<OfferTerms>(negotiation.current_offer.clone())
+                .map_err(|e| AppError::Internal(format!("Invalid counter-offer terms: {}", e)))?
+                .or(&offered),
+        ),
+        NegotiationResponse::Reject => ("reject", offered),
+        NegotiationResponse::Revise => {
+            if payload.terms.is_empty() {
+                return Err(AppError::Validation(
+                    "A revised offer must change at least one term".to_string(),
+                ));
+            }
+            ("revise", payload.terms.or(&offered))
+        }
+    };
+
+    let expiry_date = payload.expiry_date.or(offer.expiry_date);
+    if expiry_date.is_some_and(|d| d < Utc::now().date_naive()) {
+        return Err(AppError::Validation(
+            "The offer's expiry date has passed; set a new expiry_date".to_string(),
+        ));
+    }
+
+    let round = NegotiationRound {
+        round: next_round(&negotiation),
+        proposed_by: "company".to_string(),
+        action: action.to_string(),
+        terms: terms.clone(),
+        notes: payload.notes,
+        user_id: Some(claims.sub),
+        created_at: Utc::now(),
+    };
+    let status = if payload.final_offer {
+        "final_offer"
+    } else {
+        "revised_offer"
+    };
+    let negotiation = append(&mut tx, &negotiation, status, round).await?;
+
+    let offer: Offer = sqlx::query_as(&format!(
+        "UPDATE offers SET status = 'sent', sent_at = NOW(), \
+         base_salary_cents = $3, equity_pct = $4::numeric, signing_bonus_cents = $5, \
+         start_date = $6, expiry_date = $7, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        OFFER_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(terms.base_salary_cents)
+    .bind(terms.equity_pct)
+    .bind(terms.signing_bonus_cents)
+    .bind(terms.start_date)
+    .bind(expiry_date)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let candidate_user: Option<Uuid> =
+        sqlx::query_scalar("SELECT user_id FROM candidate_profiles WHERE id = $1")
+            .bind(offer.candidate_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten();
+    if let Some(user_id) = candidate_user {
+        notify(
+            &mut *tx,
+            &state.ws_broadcast,
+            NewNotification {
+                tenant_id: claims.tid,
+                user_id,
+                kind: "offer_updated",
+                title: format!("Your offer has been updated: {}", offer.title),
+                body: None,
+                resource_type: Some("offer"),
+                resource_id: Some(offer.id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(Json(negotiation))
+}
+
+/// Record the candidate's final answer on the offer's negotiation, if it
+/// was negotiated.
+pub(crate) async fn close(
+    conn: &mut PgConnection,
+    offer: &Offer,
+    status: &str,
+    action: &str,
+    notes: Option<String>,
+    user_id: Option<Uuid>,
+) -> AppResult<()> {
+    let Some(negotiation) = lock(conn, offer.tenant_id, offer.id).await? else {
+        return Ok(());
+    };
+    let round = NegotiationRound {
+        round: next_round(&negotiation),
+        proposed_by: "candidate".to_string(),
+        action: action.to_string(),
+        terms: OfferTerms::of(offer),
+        notes,
+        user_id,
+        created_at: Utc::now(),
+    };
+    append(conn, &negotiation, status, round).await?;
+    Ok(())
+}
+
+async fn lock(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    offer_id: Uuid,
+) -> AppResult<Option<OfferNegotiation>> {
+    Ok(sqlx::query_as(&format!(
+        "SELECT {} FROM offer_negotiations WHERE offer_id = $1 AND tenant_id = $2 FOR UPDATE",
+        NEGOTIATION_COLUMNS
+    ))
+    .bind(offer_id)
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await?)
+}
+
+/// Start a negotiation with the offer as sent as its first round.
+async fn open(conn: &mut PgConnection, offer: &Offer) -> AppResult<OfferNegotiation> {
+    let first = NegotiationRound {
+        round: 1,
+        proposed_by: "company".to_string(),
+        action: "offer".to_string(),
+        terms: OfferTerms::of(offer),
+        notes: None,
+        user_id: Some(offer.created_by),
+        created_at: offer.sent_at.unwrap_or(offer.created_at),
+    };
+    let terms = serde_json::to_value(&first.terms).expect("terms serialize");
+    let rounds = serde_json::to_value([&first]).expect("rounds serialize");
+
+    Ok(sqlx::query_as(&format!(
+        "INSERT INTO offer_negotiations \
+         (tenant_id, offer_id, application_id, candidate_name, job_title, status, rounds, \
+          current_offer) \
+         SELECT $1, $2, $3, \
+         (SELECT COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), \
+                 'Candidate') \
+          FROM candidate_profiles cp LEFT JOIN users u ON u.id = cp.user_id \
+          WHERE cp.id = $4), \
+         $5, 'initial_offer', $6, $7 \
+         RETURNING {}",
+        NEGOTIATION_COLUMNS
+    ))
+    .bind(offer.tenant_id)
+    .bind(offer.id)
+    .bind(offer.application_id)
+    .bind(offer.candidate_id)
+    .bind(&offer.title)
+    .bind(rounds)
+    .bind(terms)
+    .fetch_one(conn)
+    .await?)
+}
+
+/// Append a round, make its terms the current proposal and move the
+/// negotiation to `status`.
+async fn append(
+    conn: &mut PgConnection,
+    negotiation: &OfferNegotiation,
+    status: &str,
+    round: NegotiationRound,
+) -> AppResult<OfferNegotiation> {
+    let terms = serde_json::to_value(&round.terms).expect("terms serialize");
+    let round = serde_json::to_value(&round).expect("round serializes");
+
+    Ok(sqlx::query_as(&format!(
+        "UPDATE offer_negotiations SET status = $3, \
+         rounds = COALESCE(rounds, '[]'::jsonb) || jsonb_build_array($4::jsonb), \
+         current_offer = $5, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        NEGOTIATION_COLUMNS
+    ))
+    .bind(negotiation.id)
+    .bind(negotiation.tenant_id)
+    .bind(status)
+    .bind(round)
+    .bind(terms)
+    .fetch_one(conn)
+    .await?)
+}
+
+fn next_round(negotiation: &OfferNegotiation) -> i32 {
+    negotiation.rounds.as_array().map_or(0, Vec::len) as i32 + 1
+}
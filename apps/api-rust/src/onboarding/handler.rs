@@ -1,13 +1,9 @@
-use crate::AppState;
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Path, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 
 use crate::auth::Claims;
 use crate::errors::AppError;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct OnboardingTemplate {
@@ -48,14 +44,14 @@ pub struct CreateInstance {
 
 pub async fn list_templates(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<OnboardingTemplate>>, AppError> {
     let templates = sqlx::query_as::<_, OnboardingTemplate>(
         "SELECT id::text, name, role_type, phases, is_default, created_at
          FROM onboarding_templates WHERE tenant_id = $1 ORDER BY name",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -64,7 +60,7 @@ pub async fn list_templates(
 
 pub async fn list_instances(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<OnboardingInstance>>, AppError> {
     let instances = sqlx::query_as::<_, OnboardingInstance>(
         "SELECT id::text, new_hire_name, new_hire_email, job_title, department,
@@ -72,7 +68,7 @@ pub async fn list_instances(
          FROM onboarding_instances WHERE tenant_id = $1 ORDER BY start_date DESC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -81,7 +77,7 @@ pub async fn list_instances(
 
 pub async fn create_instance(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreateInstance>,
 ) -> Result<(StatusCode, Json<OnboardingInstance>), AppError> {
     let start_date = chrono::NaiveDate::parse_from_str(&body.start_date, "%Y-%m-%d")
@@ -103,7 +99,7 @@ pub async fn create_instance(
     .bind(start_date)
     .bind(&body.manager_id)
     .bind(&body.buddy_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -112,7 +108,7 @@ pub async fn create_instance(
 
 pub async fn update_progress(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Path(id): Path<String>,
     Json(body): Json<serde_json::Value>,
 ) -> Result<Json<OnboardingInstance>, AppError> {
@@ -134,7 +130,7 @@ pub async fn update_progress(
     .bind(tasks)
     .bind(&id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -1 +1,17 @@
 pub mod handler;
+
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/onboarding/templates", get(handler::list_templates))
+        .route("/onboarding", get(handler::list_instances))
+        .route("/onboarding", post(handler::create_instance))
+        .route("/onboarding/{id}/progress", put(handler::update_progress))
+}
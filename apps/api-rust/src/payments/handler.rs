@@ -2,33 +2,44 @@ use stripe_rust as stripe;
 
 use axum::{
     body::Bytes,
-    extract::{Extension, State},
+    extract::{Extension, Path, Query, State},
     http::{HeaderMap, StatusCode},
     Json,
 };
 
+use sqlx::PgConnection;
+use uuid::Uuid;
+
 use crate::auth::jwt::Claims;
+use crate::automations::events::{self, DomainEvent};
+use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::money::Currency;
+use crate::payments::ledger::{self, Source};
 use crate::payments::model::*;
+use crate::payments::webhook;
 use crate::AppState;
 
 pub async fn create_payment_intent(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreatePaymentIntentRequest>,
 ) -> AppResult<Json<PaymentIntentResponse>> {
-    // Look up the invoice
+    // Look up the invoice; the client pays what is left of it
     let invoice: (i64, String, Option<String>) = sqlx::query_as(
-        "SELECT total_cents, currency, stripe_payment_intent_id \
-         FROM invoices WHERE id = $1 AND tenant_id = $2 AND status IN ('sent', 'viewed', 'overdue')"
+        "SELECT total_cents - amount_paid_cents, currency, stripe_payment_intent_id \
+         FROM invoices WHERE id = $1 AND tenant_id = $2 AND status IN ('sent', 'viewed', 'partial', 'overdue')"
     )
     .bind(payload.invoice_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Invoice not found or not payable".to_string()))?;
 
-    let (total_cents, currency, existing_pi) = invoice;
+    let (amount_cents, currency, existing_pi) = invoice;
 
     // If there's already a payment intent, return it
     if let Some(pi_id) = existing_pi {
@@ -36,7 +47,7 @@ pub async fn create_payment_intent(
         return Ok(Json(PaymentIntentResponse {
             client_secret: format!("{}_secret_existing", pi_id),
             payment_intent_id: pi_id,
-            amount_cents: total_cents,
+            amount_cents,
             currency,
         }));
     }
@@ -49,7 +60,11 @@ pub async fn create_payment_intent(
         .unwrap_or("sk_test_placeholder");
     let client = stripe::Client::new(stripe_secret);
 
-    let mut create_params = stripe::CreatePaymentIntent::new(total_cents, stripe::Currency::USD);
+    // Stripe takes amounts in the currency's minor unit, as stored.
+    let stripe_currency: stripe::Currency = currency.to_lowercase().parse().map_err(|_| {
+        AppError::Validation(format!("Card payments are not available in {}", currency))
+    })?;
+    let mut create_params = stripe::CreatePaymentIntent::new(amount_cents, stripe_currency);
     create_params.metadata = Some(std::collections::HashMap::from([
         ("invoice_id".to_string(), payload.invoice_id.to_string()),
         ("tenant_id".to_string(), claims.tid.to_string()),
@@ -67,13 +82,13 @@ pub async fn create_payment_intent(
             .bind(&pi_id)
             .bind(payload.invoice_id)
             .bind(claims.tid)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
 
             Ok(Json(PaymentIntentResponse {
                 client_secret,
                 payment_intent_id: pi_id,
-                amount_cents: total_cents,
+                amount_cents,
                 currency,
             }))
         }
@@ -89,91 +104,379 @@ pub async fn stripe_webhook(
     headers: HeaderMap,
     body: Bytes,
 ) -> AppResult<StatusCode> {
-    let webhook_secret = state.config.stripe_webhook_secret.as_deref().unwrap_or("");
+    let webhook_secret = state
+        .config
+        .stripe_webhook_secret
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| AppError::Internal("Stripe webhook secret not configured".to_string()))?;
     let signature = headers
         .get("stripe-signature")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let payload = std::str::from_utf8(&body)
-        .map_err(|_| AppError::Validation("Invalid webhook payload".to_string()))?;
-
-    // Verify webhook signature
-    let event =
-        stripe::Webhook::construct_event(payload, signature, webhook_secret).map_err(|e| {
-            tracing::warn!("Stripe webhook signature verification failed: {}", e);
-            AppError::Unauthorized("Invalid webhook signature".to_string())
-        })?;
-
-    match event.type_ {
-        stripe::EventType::PaymentIntentSucceeded => {
-            if let stripe::EventObject::PaymentIntent(pi) = event.data.object {
-                let pi_id = pi.id.to_string();
-                tracing::info!("Payment succeeded for PaymentIntent: {}", pi_id);
-
-                // Update invoice status to paid
-                let result = sqlx::query(
-                    "UPDATE invoices SET status = 'paid', paid_date = CURRENT_DATE, \
-                     amount_paid_cents = total_cents, updated_at = NOW() \
-                     WHERE stripe_payment_intent_id = $1",
+    webhook::verify_signature(
+        &body,
+        signature,
+        webhook_secret,
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| {
+        tracing::warn!("Stripe webhook signature verification failed: {}", e);
+        AppError::Unauthorized("Invalid webhook signature".to_string())
+    })?;
+
+    // Stored before processing; a processing failure is answered with a 500
+    // so Stripe redelivers, and the retry job picks it up in the meantime.
+    webhook::ingest(&state, &body).await.map_err(|e| {
+        if e.is::<serde_json::Error>() {
+            AppError::Validation("Invalid webhook payload".to_string())
+        } else {
+            tracing::error!("Stripe webhook processing failed: {:#}", e);
+            AppError::Internal("Webhook processing failed".to_string())
+        }
+    })?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Webhook events attributed to the caller's tenant, newest first.
+pub async fn list_stripe_events(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<StripeEventQuery>,
+) -> AppResult<Json<Vec<StripeEventSummary>>> {
+    require_role(&claims, "admin")?;
+
+    let events = sqlx::query_as::<_, StripeEventSummary>(
+        "SELECT id, event_type, object_id, status, attempts, last_error, stripe_created_at, \
+         received_at, processed_at \
+         FROM stripe_events \
+         WHERE tenant_id = $1 AND ($2::TEXT IS NULL OR status = $2) \
+         ORDER BY stripe_created_at DESC \
+         LIMIT $3",
+    )
+    .bind(claims.tid)
+    .bind(params.status.as_deref())
+    .bind(params.limit.unwrap_or(50).clamp(1, 200))
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(events))
+}
+
+/// Re-apply a stored webhook event from its payload.
+pub async fn replay_stripe_event(
+    State(state): State<AppState>,
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> AppResult<Json<ReplayResponse>> {
+    require_role(&claims, "admin")?;
+
+    let owned: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM stripe_events WHERE id = $1 AND tenant_id = $2)",
+    )
+    .bind(&id)
+    .bind(claims.tid)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !owned {
+        return Err(AppError::NotFound("Stripe event not found".to_string()));
+    }
+
+    let outcome = webhook::replay(&state, &id).await.map_err(|e| {
+        tracing::error!(event_id = %id, "Stripe event replay failed: {:#}", e);
+        AppError::Internal("Replay failed".to_string())
+    })?;
+
+    Ok(Json(ReplayResponse {
+        id,
+        outcome: match outcome {
+            webhook::Outcome::Processed => "processed",
+            webhook::Outcome::Ignored => "ignored",
+            webhook::Outcome::Duplicate => "duplicate",
+        },
+    }))
+}
+
+// ── Payments and allocation ──────────────────────────────────────────
+
+async fn load_payment(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    payment_id: Uuid,
+) -> AppResult<PaymentDetail> {
+    let payment: Payment = sqlx::query_as(&format!(
+        "SELECT {} FROM payments p WHERE p.id = $1 AND p.tenant_id = $2",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payment_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+    let allocations: Vec<Allocation> = sqlx::query_as(
+        "SELECT a.id, a.invoice_id, i.invoice_number, a.payment_id, a.credit_note_id, \
+         a.amount_cents, a.created_at \
+         FROM payment_allocations a JOIN invoices i ON i.id = a.invoice_id \
+         WHERE a.payment_id = $1 AND a.tenant_id = $2 ORDER BY a.created_at, a.id",
+    )
+    .bind(payment_id)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let refunds: Vec<Payment> = sqlx::query_as(&format!(
+        "SELECT {} FROM payments p WHERE p.refund_of = $1 AND p.tenant_id = $2 ORDER BY p.created_at",
+        PAYMENT_COLUMNS
+    ))
+    .bind(payment_id)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(PaymentDetail {
+        payment,
+        allocations,
+        refunds,
+    })
+}
+
+/// Allocate a payment as requested, or to the client's oldest open invoices
+/// when `allocations` is omitted, and emit a payment event per invoice paid.
+async fn apply_payment(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    payment_id: Uuid,
+    allocations: Option<&[AllocationRequest]>,
+) -> AppResult<()> {
+    let source = Source::Payment(payment_id);
+    let applied: Vec<(Uuid, i64)> = match allocations {
+        Some(requested) => {
+            for a in requested {
+                ledger::allocate(
+                    conn,
+                    claims.tid,
+                    source,
+                    a.invoice_id,
+                    a.amount_cents,
+                    Some(claims.sub),
                 )
-                .bind(&pi_id)
-                .execute(&state.db)
                 .await?;
-
-                if result.rows_affected() > 0 {
-                    // Get invoice details for notification
-                    let invoice_info: Option<(uuid::Uuid, uuid::Uuid, String, i64)> =
-                        sqlx::query_as(
-                            "SELECT tenant_id, created_by, invoice_number, total_cents \
-                         FROM invoices WHERE stripe_payment_intent_id = $1",
-                        )
-                        .bind(&pi_id)
-                        .fetch_optional(&state.db)
-                        .await?;
-
-                    if let Some((tenant_id, created_by, invoice_number, amount)) = invoice_info {
-                        // Send real-time notification via WebSocket
-                        state.ws_broadcast.send_to_user(
-                            tenant_id,
-                            created_by,
-                            crate::ws::WsEventPayload::InvoicePaid {
-                                id: uuid::Uuid::nil(),
-                                invoice_number: invoice_number.clone(),
-                                amount_cents: amount,
-                            },
-                        );
-
-                        // Create in-app notification
-                        // Set tenant context first for RLS — parameterized
-                        sqlx::query("SELECT set_config('app.current_tenant', $1, true)")
-                            .bind(tenant_id.to_string())
-                            .execute(&state.db)
-                            .await?;
-
-                        sqlx::query(
-                            "INSERT INTO notifications (tenant_id, user_id, type, title, body) \
-                             VALUES ($1, $2, 'invoice_paid', $3, $4)",
-                        )
-                        .bind(tenant_id)
-                        .bind(created_by)
-                        .bind(format!("Invoice {} paid", invoice_number))
-                        .bind(format!("Payment of ${:.2} received", amount as f64 / 100.0))
-                        .execute(&state.db)
-                        .await?;
-                    }
-                }
             }
+            requested
+                .iter()
+                .map(|a| (a.invoice_id, a.amount_cents))
+                .collect()
         }
-        stripe::EventType::PaymentIntentPaymentFailed => {
-            if let stripe::EventObject::PaymentIntent(pi) = event.data.object {
-                tracing::warn!("Payment failed for PaymentIntent: {}", pi.id);
+        None => ledger::auto_allocate(conn, claims.tid, source, Some(claims.sub)).await?,
+    };
+
+    let method: String = sqlx::query_scalar("SELECT method FROM payments WHERE id = $1")
+        .bind(payment_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    for (invoice_id, amount_cents) in applied {
+        let (client_id, status): (Uuid, String) =
+            sqlx::query_as("SELECT client_id, status FROM invoices WHERE id = $1")
+                .bind(invoice_id)
+                .fetch_one(&mut *conn)
+                .await?;
+        events::emit(
+            &mut *conn,
+            claims.tid,
+            claims.sub,
+            DomainEvent::InvoicePaymentRecorded {
+                invoice_id,
+                client_id,
+                payment_id,
+                amount_cents,
+                method: method.clone(),
+                invoice_status: status,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn list_payments(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<ListPaymentsQuery>,
+) -> AppResult<Json<PaginatedResponse<Payment>>> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(25).clamp(1, 100);
+
+    let filter = "p.tenant_id = $1 AND ($2::UUID IS NULL OR p.client_id = $2) \
+         AND ($3::UUID IS NULL OR EXISTS (SELECT 1 FROM payment_allocations a \
+             WHERE a.invoice_id = $3 AND a.payment_id IN (p.id, p.refund_of)))";
+    let total: i64 =
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM payments p WHERE {}", filter))
+            .bind(claims.tid)
+            .bind(params.client_id)
+            .bind(params.invoice_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let payments: Vec<Payment> = sqlx::query_as(&format!(
+        "SELECT {} FROM payments p WHERE {} ORDER BY p.created_at DESC, p.id LIMIT $4 OFFSET $5",
+        PAYMENT_COLUMNS, filter
+    ))
+    .bind(claims.tid)
+    .bind(params.client_id)
+    .bind(params.invoice_id)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(PaginatedResponse {
+        data: payments,
+        meta: PaginationMeta {
+            page,
+            per_page,
+            total,
+            total_pages: (total as f64 / per_page as f64).ceil() as i64,
+        },
+    }))
+}
+
+pub async fn get_payment(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(payment_id): Path<Uuid>,
+) -> AppResult<Json<PaymentDetail>> {
+    Ok(Json(load_payment(&mut tx, claims.tid, payment_id).await?))
+}
+
+/// Record a payment from a client, allocated to their invoices; whatever is
+/// left over is credit.
+pub async fn create_payment(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreatePaymentRequest>,
+) -> AppResult<(StatusCode, Json<PaymentDetail>)> {
+    if payload.amount_cents <= 0 {
+        return Err(AppError::Validation(
+            "amount_cents must be positive".to_string(),
+        ));
+    }
+    ledger::validate_method(&payload.method)?;
+    let currency = payload
+        .currency
+        .as_deref()
+        .map(Currency::parse)
+        .transpose()?;
+
+    let payment_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO payments (tenant_id, client_id, currency, amount_cents, method, notes, created_by) \
+         SELECT $1, c.id, COALESCE($3, t.base_currency), $4, $5, $6, $7 \
+         FROM clients c JOIN tenants t ON t.id = c.tenant_id \
+         WHERE c.id = $2 AND c.tenant_id = $1 AND c.deleted_at IS NULL \
+         RETURNING id",
+    )
+    .bind(claims.tid)
+    .bind(payload.client_id)
+    .bind(currency.map(|c| c.code))
+    .bind(payload.amount_cents)
+    .bind(&payload.method)
+    .bind(payload.notes.as_deref())
+    .bind(claims.sub)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Client not found".to_string()))?;
+
+    apply_payment(&mut tx, &claims, payment_id, payload.allocations.as_deref()).await?;
+    let detail = load_payment(&mut tx, claims.tid, payment_id).await?;
+
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+/// Allocate a payment's remaining credit.
+pub async fn allocate_payment(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(payment_id): Path<Uuid>,
+    Json(payload): Json<AllocatePaymentRequest>,
+) -> AppResult<Json<PaymentDetail>> {
+    apply_payment(&mut tx, &claims, payment_id, payload.allocations.as_deref()).await?;
+    let detail = load_payment(&mut tx, claims.tid, payment_id).await?;
+
+    Ok(Json(detail))
+}
+
+/// Refund part or all of a payment, taking it from the client's credit
+/// first and then off the invoices it paid.
+pub async fn refund_payment(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(payment_id): Path<Uuid>,
+    Json(payload): Json<RefundPaymentRequest>,
+) -> AppResult<(StatusCode, Json<PaymentDetail>)> {
+    require_role(&claims, "manager")?;
+
+    let refundable: i64 = sqlx::query_scalar(
+        "SELECT amount_cents - refunded_cents FROM payments \
+         WHERE id = $1 AND tenant_id = $2 AND refund_of IS NULL",
+    )
+    .bind(payment_id)
+    .bind(claims.tid)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+    ledger::refund(
+        &mut tx,
+        claims.tid,
+        payment_id,
+        payload.amount_cents.unwrap_or(refundable),
+        payload.notes.as_deref().unwrap_or("Refund"),
+        Some(claims.sub),
+    )
+    .await?;
+    let detail = load_payment(&mut tx, claims.tid, payment_id).await?;
+
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+/// Unapplied credit the client holds, per currency.
+pub async fn get_client_credit(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Path(client_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ClientCredit>>> {
+    let currencies: Vec<String> = sqlx::query_scalar(
+        "SELECT currency FROM payments WHERE tenant_id = $1 AND client_id = $2 \
+         UNION SELECT currency FROM credit_notes WHERE tenant_id = $1 AND client_id = $2 \
+         ORDER BY 1",
+    )
+    .bind(claims.tid)
+    .bind(client_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut credit = Vec::new();
+    for currency in currencies {
+        let (mut payments_cents, mut credit_notes_cents) = (0, 0);
+        for (source, left) in
+            ledger::available_credit(&mut tx, claims.tid, client_id, &currency).await?
+        {
+            match source {
+                Source::Payment(_) => payments_cents += left,
+                Source::CreditNote(_) => credit_notes_cents += left,
             }
         }
-        _ => {
-            tracing::debug!("Unhandled Stripe event type: {:?}", event.type_);
+        if payments_cents + credit_notes_cents > 0 {
+            credit.push(ClientCredit {
+                currency,
+                payments_cents,
+                credit_notes_cents,
+                total_cents: payments_cents + credit_notes_cents,
+            });
         }
     }
 
-    Ok(StatusCode::OK)
+    Ok(Json(credit))
 }
@@ -0,0 +1,514 @@
+//! Payment allocation.
+//!
+//! A payment belongs to a client and is allocated to one or more of its
+//! invoices; whatever is not allocated is credit the client can apply to
+//! later invoices. Credit notes are credit too, and are allocated the same
+//! way. Allocations are never edited: money taken back off an invoice is a
+//! negative allocation. An invoice's `amount_paid_cents` is the sum of its
+//! allocations, and its status follows from that (see [`settle_invoice`]).
+
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::invoices::status::InvoiceStatus;
+
+/// Payment methods the `payments` table accepts.
+pub const METHODS: &[&str] = &["credit_card", "ach", "check", "cash", "wire", "other"];
+
+pub fn validate_method(method: &str) -> AppResult<()> {
+    if METHODS.contains(&method) {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "method must be one of: {}",
+            METHODS.join(", ")
+        )))
+    }
+}
+
+/// Where allocated money comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Payment(Uuid),
+    CreditNote(Uuid),
+}
+
+impl Source {
+    fn payment_id(self) -> Option<Uuid> {
+        match self {
+            Source::Payment(id) => Some(id),
+            Source::CreditNote(_) => None,
+        }
+    }
+
+    fn credit_note_id(self) -> Option<Uuid> {
+        match self {
+            Source::Payment(_) => None,
+            Source::CreditNote(id) => Some(id),
+        }
+    }
+}
+
+/// A source's owner and what is left of it to allocate.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SourceBalance {
+    pub client_id: Uuid,
+    pub currency: String,
+    pub unallocated_cents: i64,
+}
+
+/// Lock a payment or credit note and return what is left of it. Refunds,
+/// void credit notes and refunded amounts have nothing left.
+pub async fn source_balance(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    source: Source,
+) -> AppResult<SourceBalance> {
+    let (sql, id) = match source {
+        Source::Payment(id) => (
+            "SELECT client_id, currency, CASE WHEN refund_of IS NULL AND status <> 'failed' \
+             THEN amount_cents - refunded_cents - COALESCE((SELECT SUM(a.amount_cents) \
+             FROM payment_allocations a WHERE a.payment_id = p.id), 0) ELSE 0 END::BIGINT AS unallocated_cents \
+             FROM payments p WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+            id,
+        ),
+        Source::CreditNote(id) => (
+            "SELECT client_id, currency, CASE WHEN status = 'issued' \
+             THEN amount_cents - COALESCE((SELECT SUM(a.amount_cents) \
+             FROM payment_allocations a WHERE a.credit_note_id = n.id), 0) ELSE 0 END::BIGINT AS unallocated_cents \
+             FROM credit_notes n WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+            id,
+        ),
+    };
+    sqlx::query_as(sql)
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(match source {
+                Source::Payment(_) => "Payment not found".to_string(),
+                Source::CreditNote(_) => "Credit note not found".to_string(),
+            })
+        })
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LedgerInvoice {
+    client_id: Uuid,
+    invoice_number: String,
+    currency: String,
+    status: String,
+    total_cents: i64,
+    amount_paid_cents: i64,
+}
+
+/// Allocate `amount_cents` of `source` to an open invoice of the same client
+/// and currency, up to the invoice's balance.
+pub async fn allocate(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    source: Source,
+    invoice_id: Uuid,
+    amount_cents: i64,
+    actor: Option<Uuid>,
+) -> AppResult<Settlement> {
+    if amount_cents <= 0 {
+        return Err(AppError::Validation(
+            "Allocation amount must be positive".to_string(),
+        ));
+    }
+    let invoice: LedgerInvoice = sqlx::query_as(
+        "SELECT client_id, invoice_number, currency, status, total_cents, amount_paid_cents \
+         FROM invoices WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    if !InvoiceStatus::parse(&invoice.status).is_some_and(InvoiceStatus::is_open) {
+        return Err(AppError::Validation(format!(
+            "Invoice {} is {} and cannot take payments",
+            invoice.invoice_number, invoice.status
+        )));
+    }
+
+    let balance = source_balance(conn, tenant_id, source).await?;
+    if balance.client_id != invoice.client_id {
+        return Err(AppError::Validation(format!(
+            "Invoice {} belongs to another client",
+            invoice.invoice_number
+        )));
+    }
+    if balance.currency != invoice.currency {
+        return Err(AppError::Validation(format!(
+            "Invoice {} is in {}, not {}",
+            invoice.invoice_number, invoice.currency, balance.currency
+        )));
+    }
+    let due = invoice.total_cents - invoice.amount_paid_cents;
+    if amount_cents > due {
+        return Err(AppError::Validation(format!(
+            "Invoice {} has only {} left to pay",
+            invoice.invoice_number, due
+        )));
+    }
+    if amount_cents > balance.unallocated_cents {
+        return Err(AppError::Validation(format!(
+            "Only {} is left to allocate",
+            balance.unallocated_cents
+        )));
+    }
+
+    insert_allocation(conn, tenant_id, source, invoice_id, amount_cents, actor).await?;
+    settle_invoice(conn, tenant_id, invoice_id).await
+}
+
+async fn insert_allocation(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    source: Source,
+    invoice_id: Uuid,
+    amount_cents: i64,
+    actor: Option<Uuid>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO payment_allocations (tenant_id, invoice_id, payment_id, credit_note_id, amount_cents, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(tenant_id)
+    .bind(invoice_id)
+    .bind(source.payment_id())
+    .bind(source.credit_note_id())
+    .bind(amount_cents)
+    .bind(actor)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct Settlement {
+    pub status: InvoiceStatus,
+    pub amount_paid_cents: i64,
+}
+
+/// Recompute an invoice's paid amount from its allocations and move its
+/// status to match (see [`InvoiceStatus::settled`]).
+pub async fn settle_invoice(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+) -> AppResult<Settlement> {
+    let (status, total_cents, due_date, viewed, paid, credited): (
+        String,
+        i64,
+        Option<NaiveDate>,
+        bool,
+        i64,
+        i64,
+    ) = sqlx::query_as(
+        "SELECT i.status, i.total_cents, i.due_date, i.viewed_at IS NOT NULL, \
+         COALESCE(SUM(a.amount_cents), 0)::BIGINT, \
+         COALESCE(SUM(a.amount_cents) FILTER (WHERE a.credit_note_id IS NOT NULL), 0)::BIGINT \
+         FROM invoices i LEFT JOIN payment_allocations a ON a.invoice_id = i.id \
+         WHERE i.id = $1 AND i.tenant_id = $2 GROUP BY i.id",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+
+    let current = InvoiceStatus::parse(&status)
+        .ok_or_else(|| AppError::Internal(format!("Unknown invoice status '{}'", status)))?;
+    let today = chrono::Utc::now().date_naive();
+    let past_due = due_date.is_some_and(|d| d < today);
+    let status = current.settled(total_cents, paid, past_due, viewed);
+
+    sqlx::query(
+        "UPDATE invoices SET amount_paid_cents = $3, credited_cents = $4, status = $5, \
+         paid_date = CASE WHEN $5 = 'paid' THEN COALESCE(paid_date, CURRENT_DATE) END, \
+         updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .bind(paid)
+    .bind(credited)
+    .bind(status.as_str())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Settlement {
+        status,
+        amount_paid_cents: paid,
+    })
+}
+
+/// Split `amount_cents` across `balances` (invoice, amount due) in order,
+/// paying each in full before the next.
+pub fn plan_allocations(amount_cents: i64, balances: &[(Uuid, i64)]) -> Vec<(Uuid, i64)> {
+    let mut left = amount_cents;
+    let mut plan = Vec::new();
+    for &(invoice_id, due) in balances {
+        if left <= 0 {
+            break;
+        }
+        let amount = due.min(left);
+        if amount > 0 {
+            plan.push((invoice_id, amount));
+            left -= amount;
+        }
+    }
+    plan
+}
+
+/// Allocate what is left of `source` to the client's open invoices in its
+/// currency, oldest due first. Returns what went where.
+pub async fn auto_allocate(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    source: Source,
+    actor: Option<Uuid>,
+) -> AppResult<Vec<(Uuid, i64)>> {
+    let balance = source_balance(conn, tenant_id, source).await?;
+    if balance.unallocated_cents <= 0 {
+        return Ok(Vec::new());
+    }
+    let open: Vec<(Uuid, i64)> = sqlx::query_as(
+        "SELECT id, total_cents - amount_paid_cents FROM invoices \
+         WHERE tenant_id = $1 AND client_id = $2 AND currency = $3 \
+         AND status IN ('sent', 'viewed', 'partial', 'overdue') AND deleted_at IS NULL \
+         AND total_cents > amount_paid_cents \
+         ORDER BY due_date NULLS LAST, created_at",
+    )
+    .bind(tenant_id)
+    .bind(balance.client_id)
+    .bind(&balance.currency)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let plan = plan_allocations(balance.unallocated_cents, &open);
+    for &(invoice_id, amount) in &plan {
+        allocate(conn, tenant_id, source, invoice_id, amount, actor).await?;
+    }
+    Ok(plan)
+}
+
+/// Unapplied credit a client holds in `currency`: issued credit notes
+/// first, then payments, oldest first.
+pub async fn available_credit(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    client_id: Uuid,
+    currency: &str,
+) -> Result<Vec<(Source, i64)>, sqlx::Error> {
+    let rows: Vec<(Uuid, bool, i64, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT id, TRUE, amount_cents - COALESCE((SELECT SUM(a.amount_cents) \
+             FROM payment_allocations a WHERE a.credit_note_id = n.id), 0)::BIGINT, created_at \
+         FROM credit_notes n \
+         WHERE tenant_id = $1 AND client_id = $2 AND currency = $3 AND status = 'issued' \
+         UNION ALL \
+         SELECT id, FALSE, amount_cents - refunded_cents - COALESCE((SELECT SUM(a.amount_cents) \
+             FROM payment_allocations a WHERE a.payment_id = p.id), 0)::BIGINT, created_at \
+         FROM payments p \
+         WHERE tenant_id = $1 AND client_id = $2 AND currency = $3 \
+         AND refund_of IS NULL AND status <> 'failed' \
+         ORDER BY 2 DESC, 4, 1",
+    )
+    .bind(tenant_id)
+    .bind(client_id)
+    .bind(currency)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter(|(_, _, left, _)| *left > 0)
+        .map(|(id, is_note, left, _)| {
+            let source = if is_note {
+                Source::CreditNote(id)
+            } else {
+                Source::Payment(id)
+            };
+            (source, left)
+        })
+        .collect())
+}
+
+/// Take every allocation back off an invoice, returning the money to the
+/// credit it came from.
+pub async fn reverse_allocations(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+    actor: Option<Uuid>,
+) -> AppResult<()> {
+    let net: Vec<(Option<Uuid>, Option<Uuid>, i64)> = sqlx::query_as(
+        "SELECT payment_id, credit_note_id, SUM(amount_cents)::BIGINT FROM payment_allocations \
+         WHERE invoice_id = $1 AND tenant_id = $2 \
+         GROUP BY payment_id, credit_note_id HAVING SUM(amount_cents) > 0",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (payment_id, credit_note_id, amount) in net {
+        let source = match (payment_id, credit_note_id) {
+            (Some(id), _) => Source::Payment(id),
+            (None, Some(id)) => Source::CreditNote(id),
+            (None, None) => continue,
+        };
+        insert_allocation(conn, tenant_id, source, invoice_id, -amount, actor).await?;
+    }
+    settle_invoice(conn, tenant_id, invoice_id).await?;
+    Ok(())
+}
+
+/// Take back everything a payment or credit note has allocated, e.g. when
+/// a credit note is voided.
+pub async fn reverse_source(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    source: Source,
+    actor: Option<Uuid>,
+) -> AppResult<()> {
+    let net: Vec<(Uuid, i64)> = sqlx::query_as(
+        "SELECT invoice_id, SUM(amount_cents)::BIGINT FROM payment_allocations \
+         WHERE tenant_id = $1 AND (payment_id = $2 OR credit_note_id = $3) \
+         GROUP BY invoice_id HAVING SUM(amount_cents) > 0",
+    )
+    .bind(tenant_id)
+    .bind(source.payment_id())
+    .bind(source.credit_note_id())
+    .fetch_all(&mut *conn)
+    .await?;
+    for (invoice_id, amount) in net {
+        insert_allocation(conn, tenant_id, source, invoice_id, -amount, actor).await?;
+        settle_invoice(conn, tenant_id, invoice_id).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RefundablePayment {
+    client_id: Uuid,
+    currency: String,
+    amount_cents: i64,
+    refunded_cents: i64,
+    method: String,
+}
+
+/// Refund `amount_cents` of a payment: from its unallocated credit first,
+/// then off the invoices it paid, latest due first. The refund is recorded
+/// as a payment with a negative amount. Returns the refund's id.
+pub async fn refund(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    payment_id: Uuid,
+    amount_cents: i64,
+    notes: &str,
+    actor: Option<Uuid>,
+) -> AppResult<Uuid> {
+    let balance = source_balance(conn, tenant_id, Source::Payment(payment_id)).await?;
+    let payment: RefundablePayment = sqlx::query_as(
+        "SELECT client_id, currency, amount_cents, refunded_cents, method FROM payments \
+         WHERE id = $1 AND tenant_id = $2 AND refund_of IS NULL",
+    )
+    .bind(payment_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+    let refundable = payment.amount_cents - payment.refunded_cents;
+    if amount_cents <= 0 || amount_cents > refundable {
+        return Err(AppError::Validation(format!(
+            "Refund must be between 1 and {}",
+            refundable
+        )));
+    }
+
+    let mut from_invoices = amount_cents - balance.unallocated_cents.clamp(0, amount_cents);
+    if from_invoices > 0 {
+        let paid: Vec<(Uuid, i64)> = sqlx::query_as(
+            "SELECT a.invoice_id, SUM(a.amount_cents)::BIGINT \
+             FROM payment_allocations a JOIN invoices i ON i.id = a.invoice_id \
+             WHERE a.payment_id = $1 AND a.tenant_id = $2 \
+             GROUP BY a.invoice_id, i.due_date, i.created_at HAVING SUM(a.amount_cents) > 0 \
+             ORDER BY i.due_date DESC NULLS FIRST, i.created_at DESC",
+        )
+        .bind(payment_id)
+        .bind(tenant_id)
+        .fetch_all(&mut *conn)
+        .await?;
+        for (invoice_id, allocated) in paid {
+            if from_invoices == 0 {
+                break;
+            }
+            let amount = allocated.min(from_invoices);
+            insert_allocation(
+                conn,
+                tenant_id,
+                Source::Payment(payment_id),
+                invoice_id,
+                -amount,
+                actor,
+            )
+            .await?;
+            settle_invoice(conn, tenant_id, invoice_id).await?;
+            from_invoices -= amount;
+        }
+    }
+
+    let refund_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO payments (tenant_id, client_id, currency, amount_cents, method, status, refund_of, notes, created_by) \
+         VALUES ($1, $2, $3, $4, $5, 'completed', $6, $7, $8) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(payment.client_id)
+    .bind(&payment.currency)
+    .bind(-amount_cents)
+    .bind(&payment.method)
+    .bind(payment_id)
+    .bind(notes)
+    .bind(actor)
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE payments SET refunded_cents = refunded_cents + $2, \
+         status = CASE WHEN refunded_cents + $2 >= amount_cents THEN 'refunded' ELSE status END \
+         WHERE id = $1",
+    )
+    .bind(payment_id)
+    .bind(amount_cents)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(refund_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_allocations_pays_in_order() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let open = [(a, 3_000), (b, 5_000), (c, 2_000)];
+        assert_eq!(plan_allocations(6_000, &open), vec![(a, 3_000), (b, 3_000)]);
+        assert_eq!(
+            plan_allocations(12_000, &open),
+            vec![(a, 3_000), (b, 5_000), (c, 2_000)]
+        );
+        assert_eq!(plan_allocations(0, &open), vec![]);
+        assert_eq!(
+            plan_allocations(1_000, &[(a, 0), (b, 5_000)]),
+            vec![(b, 1_000)]
+        );
+    }
+}
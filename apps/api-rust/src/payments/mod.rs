@@ -1,2 +1,47 @@
 pub mod handler;
+pub mod ledger;
 pub mod model;
+pub mod webhook;
+
+use axum::{
+    middleware::from_fn,
+    routing::{get, post},
+    Router,
+};
+
+use crate::middleware::idempotency::idempotency_check;
+use crate::AppState;
+
+/// Provider webhooks, nested under `/api/v1` and verified by signature.
+pub fn public_routes() -> Router<AppState> {
+    Router::new().route("/webhooks/stripe", post(handler::stripe_webhook))
+}
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/payments/create-intent",
+            post(handler::create_payment_intent).layer(from_fn(idempotency_check)),
+        )
+        .route("/payments", get(handler::list_payments))
+        .route(
+            "/payments",
+            post(handler::create_payment).layer(from_fn(idempotency_check)),
+        )
+        .route("/payments/{id}", get(handler::get_payment))
+        .route(
+            "/payments/{id}/allocate",
+            post(handler::allocate_payment).layer(from_fn(idempotency_check)),
+        )
+        .route(
+            "/payments/{id}/refund",
+            post(handler::refund_payment).layer(from_fn(idempotency_check)),
+        )
+        .route("/clients/{id}/credit", get(handler::get_client_credit))
+        .route("/payments/stripe-events", get(handler::list_stripe_events))
+        .route(
+            "/payments/stripe-events/{id}/replay",
+            post(handler::replay_stripe_event),
+        )
+}
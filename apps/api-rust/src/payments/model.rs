@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -13,3 +14,129 @@ pub struct PaymentIntentResponse {
     pub amount_cents: i64,
     pub currency: String,
 }
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StripeEventSummary {
+    pub id: String,
+    pub event_type: String,
+    pub object_id: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub stripe_created_at: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StripeEventQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayResponse {
+    pub id: String,
+    pub outcome: &'static str,
+}
+
+// ── Payments and allocation ──────────────────────────────────────────
+
+/// Columns selected into [`Payment`] from `payments p`.
+pub const PAYMENT_COLUMNS: &str = "p.id, p.client_id, p.invoice_id, p.amount_cents, p.currency, \
+     p.method, p.status, p.notes, p.stripe_payment_id, p.refund_of, p.refunded_cents, \
+     CASE WHEN p.refund_of IS NULL AND p.status <> 'failed' \
+     THEN p.amount_cents - p.refunded_cents - COALESCE((SELECT SUM(a.amount_cents) \
+     FROM payment_allocations a WHERE a.payment_id = p.id), 0) ELSE 0 END::BIGINT AS unallocated_cents, \
+     p.created_by, p.created_at";
+
+/// A payment from a client, or a refund of one (negative, with `refund_of`).
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    /// The invoice the payment was taken against, if any; see allocations
+    /// for where the money went.
+    pub invoice_id: Option<Uuid>,
+    pub amount_cents: i64,
+    pub currency: String,
+    pub method: String,
+    pub status: String,
+    pub notes: Option<String>,
+    pub stripe_payment_id: Option<String>,
+    pub refund_of: Option<Uuid>,
+    pub refunded_cents: i64,
+    /// Credit left for other invoices.
+    pub unallocated_cents: i64,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Allocation {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub payment_id: Option<Uuid>,
+    pub credit_note_id: Option<Uuid>,
+    /// Negative when money was taken back off the invoice.
+    pub amount_cents: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentDetail {
+    #[serde(flatten)]
+    pub payment: Payment,
+    pub allocations: Vec<Allocation>,
+    pub refunds: Vec<Payment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllocationRequest {
+    pub invoice_id: Uuid,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePaymentRequest {
+    pub client_id: Uuid,
+    pub amount_cents: i64,
+    /// ISO 4217 code; defaults to the firm's base currency.
+    pub currency: Option<String>,
+    pub method: String,
+    pub notes: Option<String>,
+    /// Where the money goes; when omitted it pays the client's open
+    /// invoices in the currency, oldest due first. Anything left is credit.
+    pub allocations: Option<Vec<AllocationRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllocatePaymentRequest {
+    /// As for [`CreatePaymentRequest::allocations`].
+    pub allocations: Option<Vec<AllocationRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundPaymentRequest {
+    /// Defaults to everything not yet refunded.
+    pub amount_cents: Option<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPaymentsQuery {
+    pub client_id: Option<Uuid>,
+    pub invoice_id: Option<Uuid>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Unapplied credit a client holds in one currency.
+#[derive(Debug, Serialize)]
+pub struct ClientCredit {
+    pub currency: String,
+    pub payments_cents: i64,
+    pub credit_notes_cents: i64,
+    pub total_cents: i64,
+}
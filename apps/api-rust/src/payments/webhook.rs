@@ -0,0 +1,1151 @@
+//! Stripe webhook ingestion.
+//!
+//! Verified deliveries are stored in `stripe_events` under their Stripe event
+//! id and then applied in one transaction with the owning tenant's context
+//! set. An event that was processed (or deliberately ignored) is never applied
+//! again on redelivery. Events that fail stay in the table and are retried by
+//! [`retry_failed`]; [`replay`] re-applies any stored event on demand.
+//!
+//! Only the fields used here are deserialised, so payloads from newer API
+//! versions keep working.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::invoices::status::InvoiceStatus;
+use crate::money;
+use crate::notifications::notify::{notify, NewNotification};
+use crate::payments::ledger::{self, Source};
+use crate::scheduler::{set_tenant, Job};
+use crate::ws::{WsBroadcast, WsEventPayload};
+use crate::AppState;
+
+pub const RETRY_JOB_TYPE: &str = "payments.retry_stripe_events";
+
+/// Deliveries whose signature timestamp is further off than this are rejected.
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+/// Automatic retries per event; after that it waits for a manual replay.
+const MAX_ATTEMPTS: i32 = 8;
+
+// ── Signatures ───────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("malformed stripe-signature header")]
+    Malformed,
+    #[error("signature timestamp outside tolerance")]
+    Expired,
+    #[error("no matching v1 signature")]
+    Mismatch,
+}
+
+fn signer(secret: &str, timestamp: i64, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+/// Check a `stripe-signature` header (`t=<unix>,v1=<hex>[,v1=...]`) against
+/// the endpoint secret.
+pub fn verify_signature(
+    payload: &[u8],
+    header: &str,
+    secret: &str,
+    now: i64,
+) -> Result<(), SignatureError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", sig)) => signatures.extend(hex::decode(sig).ok()),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or(SignatureError::Malformed)?;
+    if signatures.is_empty() {
+        return Err(SignatureError::Malformed);
+    }
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        return Err(SignatureError::Expired);
+    }
+
+    let mac = signer(secret, timestamp, payload);
+    if signatures
+        .iter()
+        .any(|sig| mac.clone().verify_slice(sig).is_ok())
+    {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+/// The `stripe-signature` header Stripe would send for `payload`.
+pub fn sign(payload: &[u8], secret: &str, timestamp: i64) -> String {
+    let sig = signer(secret, timestamp, payload).finalize().into_bytes();
+    format!("t={},v1={}", timestamp, hex::encode(sig))
+}
+
+// ── Events ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct Event {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub created: i64,
+    pub data: EventData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventData {
+    pub object: serde_json::Value,
+}
+
+impl Event {
+    fn object<T: serde::de::DeserializeOwned>(&self) -> anyhow::Result<T> {
+        Ok(T::deserialize(&self.data.object)?)
+    }
+
+    fn object_id(&self) -> Option<&str> {
+        self.data.object.get("id").and_then(|v| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Processed,
+    /// Acknowledged without changes (unhandled type, nothing to match).
+    Ignored,
+    /// Already processed or ignored earlier.
+    Duplicate,
+}
+
+type Metadata = HashMap<String, String>;
+
+#[derive(Debug, Deserialize)]
+struct PaymentIntent {
+    id: String,
+    #[serde(default)]
+    amount: i64,
+    #[serde(default)]
+    amount_received: i64,
+    customer: Option<String>,
+    #[serde(default)]
+    metadata: Metadata,
+    last_payment_error: Option<PaymentError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentError {
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Charge {
+    id: String,
+    payment_intent: Option<String>,
+    #[serde(default)]
+    amount_refunded: i64,
+    customer: Option<String>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dispute {
+    id: String,
+    charge: String,
+    payment_intent: Option<String>,
+    amount: i64,
+    status: String,
+    reason: Option<String>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subscription {
+    id: String,
+    customer: Option<String>,
+    status: String,
+    current_period_start: Option<i64>,
+    current_period_end: Option<i64>,
+    trial_end: Option<i64>,
+    canceled_at: Option<i64>,
+    #[serde(default)]
+    metadata: Metadata,
+    #[serde(default)]
+    items: List<SubscriptionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct List<T> {
+    data: Vec<T>,
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionItem {
+    price: Option<Price>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Price {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeInvoice {
+    id: String,
+    customer: Option<String>,
+    subscription: Option<String>,
+    parent: Option<InvoiceParent>,
+    status: Option<String>,
+    #[serde(default)]
+    amount_due: i64,
+    #[serde(default)]
+    amount_paid: i64,
+    currency: String,
+    due_date: Option<i64>,
+    #[serde(default)]
+    status_transitions: StatusTransitions,
+    invoice_pdf: Option<String>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceParent {
+    subscription_details: Option<SubscriptionDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionDetails {
+    subscription: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StatusTransitions {
+    paid_at: Option<i64>,
+}
+
+impl StripeInvoice {
+    /// Newer API versions moved the subscription under `parent`.
+    fn subscription_id(&self) -> Option<&str> {
+        self.subscription.as_deref().or_else(|| {
+            self.parent
+                .as_ref()?
+                .subscription_details
+                .as_ref()?
+                .subscription
+                .as_deref()
+        })
+    }
+}
+
+fn timestamp(secs: Option<i64>) -> Option<DateTime<Utc>> {
+    secs.and_then(|s| DateTime::from_timestamp(s, 0))
+}
+
+/// Local `subscriptions.status` for a Stripe subscription status.
+fn subscription_status(stripe_status: &str) -> Option<&'static str> {
+    Some(match stripe_status {
+        "trialing" => "trialing",
+        "active" => "active",
+        "past_due" | "unpaid" | "incomplete" => "past_due",
+        "canceled" | "incomplete_expired" => "cancelled",
+        "paused" => "suspended",
+        _ => return None,
+    })
+}
+
+// ── Ingestion ────────────────────────────────────────────────────────
+
+/// Result of applying an event, committed with it.
+struct Applied {
+    tenant_id: Option<Uuid>,
+    /// Set when the event was acknowledged without changing anything.
+    ignored: Option<&'static str>,
+    /// Pushed to WebSocket clients once the transaction has committed.
+    broadcasts: Vec<(Uuid, Uuid, WsEventPayload)>,
+}
+
+impl Applied {
+    fn done(tenant_id: Uuid) -> Self {
+        Self {
+            tenant_id: Some(tenant_id),
+            ignored: None,
+            broadcasts: Vec::new(),
+        }
+    }
+
+    fn ignored(tenant_id: Option<Uuid>, reason: &'static str) -> Self {
+        Self {
+            tenant_id,
+            ignored: Some(reason),
+            broadcasts: Vec::new(),
+        }
+    }
+}
+
+/// Store a verified delivery and apply it.
+pub async fn ingest(state: &AppState, payload: &[u8]) -> anyhow::Result<Outcome> {
+    let event: Event = serde_json::from_slice(payload)?;
+    let raw: serde_json::Value = serde_json::from_slice(payload)?;
+
+    sqlx::query(
+        "INSERT INTO stripe_events (id, event_type, object_id, payload, stripe_created_at) \
+         VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT (id) DO NOTHING",
+    )
+    .bind(&event.id)
+    .bind(&event.event_type)
+    .bind(event.object_id())
+    .bind(&raw)
+    .bind(timestamp(Some(event.created)).unwrap_or_else(Utc::now))
+    .execute(&state.db)
+    .await?;
+
+    process(state, &event.id).await
+}
+
+/// Apply a stored event unless it has already been processed or ignored.
+pub async fn process(state: &AppState, event_id: &str) -> anyhow::Result<Outcome> {
+    let mut tx = state.db.begin().await?;
+
+    // The row lock serialises concurrent deliveries of the same event.
+    let stored: Option<(serde_json::Value, String)> =
+        sqlx::query_as("SELECT payload, status FROM stripe_events WHERE id = $1 FOR UPDATE")
+            .bind(event_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let Some((payload, status)) = stored else {
+        anyhow::bail!("Unknown Stripe event {}", event_id);
+    };
+    if status == "processed" || status == "ignored" {
+        return Ok(Outcome::Duplicate);
+    }
+
+    let event: Event = serde_json::from_value(payload)?;
+    match apply(&mut tx, &state.ws_broadcast, &event).await {
+        Ok(applied) => {
+            sqlx::query(
+                "UPDATE stripe_events SET status = $2, tenant_id = $3, last_error = $4, \
+                 attempts = attempts + 1, processed_at = NOW() \
+                 WHERE id = $1",
+            )
+            .bind(event_id)
+            .bind(if applied.ignored.is_some() {
+                "ignored"
+            } else {
+                "processed"
+            })
+            .bind(applied.tenant_id)
+            .bind(applied.ignored)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            for (tenant_id, user_id, payload) in applied.broadcasts {
+                state.ws_broadcast.send_to_user(tenant_id, user_id, payload);
+            }
+            tracing::info!(
+                event_id,
+                event_type = %event.event_type,
+                "Stripe event {}",
+                applied.ignored.unwrap_or("processed")
+            );
+            Ok(if applied.ignored.is_some() {
+                Outcome::Ignored
+            } else {
+                Outcome::Processed
+            })
+        }
+        Err(e) => {
+            tx.rollback().await?;
+            sqlx::query(
+                "UPDATE stripe_events SET status = 'failed', attempts = attempts + 1, last_error = $2 \
+                 WHERE id = $1",
+            )
+            .bind(event_id)
+            .bind(format!("{:#}", e))
+            .execute(&state.db)
+            .await?;
+            Err(e)
+        }
+    }
+}
+
+/// Re-apply a stored event from its payload, even if it was processed
+/// before. Every handler below is safe to run twice.
+pub async fn replay(state: &AppState, event_id: &str) -> anyhow::Result<Outcome> {
+    sqlx::query("UPDATE stripe_events SET status = 'received' WHERE id = $1")
+        .bind(event_id)
+        .execute(&state.db)
+        .await?;
+    process(state, event_id).await
+}
+
+/// Retry events that failed (or were interrupted) on delivery, oldest first.
+pub async fn retry_failed(state: AppState, _job: Job) -> anyhow::Result<()> {
+    let pending: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM stripe_events \
+         WHERE status IN ('failed', 'received') AND attempts < $1 \
+           AND received_at < NOW() - INTERVAL '1 minute' \
+         ORDER BY stripe_created_at \
+         LIMIT 100",
+    )
+    .bind(MAX_ATTEMPTS)
+    .fetch_all(&state.db)
+    .await?;
+
+    for event_id in pending {
+        if let Err(e) = process(&state, &event_id).await {
+            tracing::warn!(event_id = %event_id, "Stripe event retry failed: {:#}", e);
+        }
+    }
+    Ok(())
+}
+
+async fn apply(
+    conn: &mut PgConnection,
+    ws: &WsBroadcast,
+    event: &Event,
+) -> anyhow::Result<Applied> {
+    match event.event_type.as_str() {
+        "payment_intent.succeeded" => payment_succeeded(conn, ws, event).await,
+        "payment_intent.payment_failed" => payment_failed(conn, event).await,
+        "charge.refunded" => charge_refunded(conn, event).await,
+        t if t.starts_with("charge.dispute.") => dispute_changed(conn, ws, event).await,
+        t if t.starts_with("customer.subscription.") => subscription_changed(conn, event).await,
+        t if t.starts_with("invoice.") => billing_invoice_changed(conn, event).await,
+        _ => Ok(Applied::ignored(None, "unhandled event type")),
+    }
+}
+
+/// The tenant that owns a Stripe object: explicit `tenant_id` metadata, then
+/// the tenant's Stripe customer, then an earlier event about a related object
+/// (e.g. the payment intent behind a refunded charge). Sets the tenant
+/// context on `conn` once found.
+async fn resolve_tenant(
+    conn: &mut PgConnection,
+    metadata: &Metadata,
+    customer: Option<&str>,
+    related: &[Option<&str>],
+) -> anyhow::Result<Uuid> {
+    let mut tenant_id = metadata.get("tenant_id").and_then(|v| v.parse().ok());
+
+    if let (None, Some(customer)) = (tenant_id, customer) {
+        tenant_id = sqlx::query_scalar("SELECT id FROM tenants WHERE stripe_customer_id = $1")
+            .bind(customer)
+            .fetch_optional(&mut *conn)
+            .await?;
+    }
+    for object_id in related.iter().flatten() {
+        if tenant_id.is_some() {
+            break;
+        }
+        tenant_id = sqlx::query_scalar(
+            "SELECT tenant_id FROM stripe_events \
+             WHERE object_id = $1 AND tenant_id IS NOT NULL \
+             ORDER BY stripe_created_at LIMIT 1",
+        )
+        .bind(object_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    }
+
+    // Unresolved events fail so they are retried once related events arrive.
+    let tenant_id = tenant_id.ok_or_else(|| anyhow::anyhow!("No tenant found for event"))?;
+    set_tenant(conn, tenant_id).await?;
+    Ok(tenant_id)
+}
+
+struct PaymentEvent<'a> {
+    kind: &'a str,
+    amount_cents: Option<i64>,
+    subscription_id: Option<Uuid>,
+    billing_invoice_id: Option<Uuid>,
+    metadata: serde_json::Value,
+}
+
+async fn record_payment_event(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    event: &Event,
+    pe: PaymentEvent<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO payment_events (tenant_id, subscription_id, invoice_id, event_type, amount_cents, stripe_event_id, metadata) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) \
+         ON CONFLICT (stripe_event_id) WHERE stripe_event_id IS NOT NULL DO NOTHING",
+    )
+    .bind(tenant_id)
+    .bind(pe.subscription_id)
+    .bind(pe.billing_invoice_id)
+    .bind(pe.kind)
+    .bind(pe.amount_cents)
+    .bind(&event.id)
+    .bind(pe.metadata)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// ── Client invoice payments ──────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct PaidInvoice {
+    id: Uuid,
+    client_id: Uuid,
+    invoice_number: String,
+    status: String,
+    total_cents: i64,
+    amount_paid_cents: i64,
+    created_by: Uuid,
+    currency: String,
+    /// The tenant's, for writing amounts in notifications.
+    locale: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CardPayment {
+    id: Uuid,
+    invoice_id: Option<Uuid>,
+    amount_cents: i64,
+    refunded_cents: i64,
+    dispute_status: Option<String>,
+}
+
+async fn payment_succeeded(
+    conn: &mut PgConnection,
+    ws: &WsBroadcast,
+    event: &Event,
+) -> anyhow::Result<Applied> {
+    let pi: PaymentIntent = event.object()?;
+    let tenant_id = resolve_tenant(conn, &pi.metadata, pi.customer.as_deref(), &[]).await?;
+    let invoice_id: Option<Uuid> = pi.metadata.get("invoice_id").and_then(|v| v.parse().ok());
+
+    let invoice: Option<PaidInvoice> = sqlx::query_as(
+        "SELECT id, client_id, invoice_number, status, total_cents, amount_paid_cents, created_by, currency, \
+         (SELECT locale FROM tenants WHERE id = $1) AS locale FROM invoices \
+         WHERE tenant_id = $1 AND (id = $2 OR stripe_payment_intent_id = $3) AND deleted_at IS NULL \
+         LIMIT 1 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .bind(invoice_id)
+    .bind(&pi.id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(invoice) = invoice else {
+        return Ok(Applied::ignored(Some(tenant_id), "no matching invoice"));
+    };
+
+    let recorded: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM payments WHERE tenant_id = $1 AND stripe_payment_id = $2)",
+    )
+    .bind(tenant_id)
+    .bind(&pi.id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if recorded {
+        return Ok(Applied::ignored(
+            Some(tenant_id),
+            "payment already recorded",
+        ));
+    }
+
+    let amount = pi.amount_received;
+    let payment_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO payments (tenant_id, invoice_id, client_id, currency, amount_cents, method, stripe_payment_id, notes) \
+         VALUES ($1, $2, $3, $4, $5, 'credit_card', $6, 'Stripe') RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(invoice.id)
+    .bind(invoice.client_id)
+    .bind(&invoice.currency)
+    .bind(amount)
+    .bind(&pi.id)
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE invoices SET stripe_payment_intent_id = COALESCE(stripe_payment_intent_id, $2), updated_at = NOW() \
+         WHERE id = $1",
+    )
+    .bind(invoice.id)
+    .bind(&pi.id)
+    .execute(&mut *conn)
+    .await?;
+
+    // The payment covers what is due; anything over it, or a payment on an
+    // invoice no longer open, is left as the client's credit.
+    let due = invoice.total_cents - invoice.amount_paid_cents;
+    let open = InvoiceStatus::parse(&invoice.status).is_some_and(InvoiceStatus::is_open);
+    let (paid, fully_paid) = if open && due > 0 {
+        ledger::allocate(
+            conn,
+            tenant_id,
+            Source::Payment(payment_id),
+            invoice.id,
+            amount.min(due),
+            None,
+        )
+        .await
+        .map(|settled| {
+            (
+                settled.amount_paid_cents,
+                settled.status == InvoiceStatus::Paid,
+            )
+        })?
+    } else {
+        (invoice.amount_paid_cents, false)
+    };
+
+    record_payment_event(
+        conn,
+        tenant_id,
+        event,
+        PaymentEvent {
+            kind: "charge_succeeded",
+            amount_cents: Some(amount),
+            subscription_id: None,
+            billing_invoice_id: None,
+            metadata: serde_json::json!({
+                "payment_intent": pi.id,
+                "client_invoice_id": invoice.id,
+            }),
+        },
+    )
+    .await?;
+
+    let title = if fully_paid {
+        format!("Invoice {} paid", invoice.invoice_number)
+    } else {
+        format!("Partial payment on invoice {}", invoice.invoice_number)
+    };
+    notify(
+        &mut *conn,
+        ws,
+        NewNotification {
+            tenant_id,
+            user_id: invoice.created_by,
+            kind: "invoice_paid",
+            title,
+            body: Some(format!(
+                "Payment of {} received",
+                money::format(amount, &invoice.currency, &invoice.locale)
+            )),
+            resource_type: Some("invoice"),
+            resource_id: Some(invoice.id),
+        },
+    )
+    .await?;
+
+    let mut applied = Applied::done(tenant_id);
+    if fully_paid {
+        applied.broadcasts.push((
+            tenant_id,
+            invoice.created_by,
+            WsEventPayload::InvoicePaid {
+                id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                amount_cents: paid,
+            },
+        ));
+    }
+    Ok(applied)
+}
+
+async fn payment_failed(conn: &mut PgConnection, event: &Event) -> anyhow::Result<Applied> {
+    let pi: PaymentIntent = event.object()?;
+    let tenant_id = resolve_tenant(conn, &pi.metadata, pi.customer.as_deref(), &[]).await?;
+    let error = pi.last_payment_error.and_then(|e| e.message);
+    tracing::warn!(payment_intent = %pi.id, "Stripe payment failed: {:?}", error);
+
+    record_payment_event(
+        conn,
+        tenant_id,
+        event,
+        PaymentEvent {
+            kind: "charge_failed",
+            amount_cents: Some(pi.amount),
+            subscription_id: None,
+            billing_invoice_id: None,
+            metadata: serde_json::json!({
+                "payment_intent": pi.id,
+                "client_invoice_id": pi.metadata.get("invoice_id"),
+                "error": error,
+            }),
+        },
+    )
+    .await?;
+    Ok(Applied::done(tenant_id))
+}
+
+/// The card payment a charge belongs to, locked for update.
+async fn card_payment(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    payment_intent: Option<&str>,
+    charge_id: &str,
+) -> Result<Option<CardPayment>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, invoice_id, amount_cents, refunded_cents, dispute_status FROM payments \
+         WHERE tenant_id = $1 AND stripe_payment_id IN ($2, $3) \
+         LIMIT 1 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .bind(payment_intent.unwrap_or(charge_id))
+    .bind(charge_id)
+    .fetch_optional(conn)
+    .await
+}
+
+async fn charge_refunded(conn: &mut PgConnection, event: &Event) -> anyhow::Result<Applied> {
+    let charge: Charge = event.object()?;
+    let pi = charge.payment_intent.as_deref();
+    let tenant_id = resolve_tenant(
+        conn,
+        &charge.metadata,
+        charge.customer.as_deref(),
+        &[pi, Some(&charge.id)],
+    )
+    .await?;
+
+    let Some(payment) = card_payment(conn, tenant_id, pi, &charge.id).await? else {
+        return Ok(Applied::ignored(Some(tenant_id), "no matching payment"));
+    };
+
+    // amount_refunded is cumulative; only the part not yet recorded applies.
+    let delta = charge.amount_refunded - payment.refunded_cents;
+    if delta <= 0 {
+        return Ok(Applied::ignored(Some(tenant_id), "refund already recorded"));
+    }
+
+    ledger::refund(conn, tenant_id, payment.id, delta, "Refund", None).await?;
+
+    record_payment_event(
+        conn,
+        tenant_id,
+        event,
+        PaymentEvent {
+            kind: "refund",
+            amount_cents: Some(delta),
+            subscription_id: None,
+            billing_invoice_id: None,
+            metadata: serde_json::json!({
+                "charge": charge.id,
+                "payment_intent": pi,
+                "payment_id": payment.id,
+                "client_invoice_id": payment.invoice_id,
+                "total_refunded_cents": charge.amount_refunded,
+            }),
+        },
+    )
+    .await?;
+    Ok(Applied::done(tenant_id))
+}
+
+async fn dispute_changed(
+    conn: &mut PgConnection,
+    ws: &WsBroadcast,
+    event: &Event,
+) -> anyhow::Result<Applied> {
+    let dispute: Dispute = event.object()?;
+    let pi = dispute.payment_intent.as_deref();
+    let tenant_id = resolve_tenant(
+        conn,
+        &dispute.metadata,
+        None,
+        &[pi, Some(&dispute.charge), Some(&dispute.id)],
+    )
+    .await?;
+
+    let Some(payment) = card_payment(conn, tenant_id, pi, &dispute.charge).await? else {
+        return Ok(Applied::ignored(Some(tenant_id), "no matching payment"));
+    };
+
+    sqlx::query("UPDATE payments SET stripe_dispute_id = $2, dispute_status = $3 WHERE id = $1")
+        .bind(payment.id)
+        .bind(&dispute.id)
+        .bind(&dispute.status)
+        .execute(&mut *conn)
+        .await?;
+
+    // A lost dispute takes the funds back for good.
+    let refundable = payment.amount_cents - payment.refunded_cents;
+    if dispute.status == "lost"
+        && payment.dispute_status.as_deref() != Some("lost")
+        && refundable > 0
+    {
+        ledger::refund(
+            conn,
+            tenant_id,
+            payment.id,
+            dispute.amount.min(refundable),
+            "Dispute lost",
+            None,
+        )
+        .await?;
+    }
+
+    record_payment_event(
+        conn,
+        tenant_id,
+        event,
+        PaymentEvent {
+            kind: "dispute",
+            amount_cents: Some(dispute.amount),
+            subscription_id: None,
+            billing_invoice_id: None,
+            metadata: serde_json::json!({
+                "dispute": dispute.id,
+                "stripe_event_type": event.event_type,
+                "status": dispute.status,
+                "reason": dispute.reason,
+                "charge": dispute.charge,
+                "payment_id": payment.id,
+                "client_invoice_id": payment.invoice_id,
+            }),
+        },
+    )
+    .await?;
+
+    if event.event_type == "charge.dispute.created" {
+        let invoice: Option<(String, Uuid, String, String)> = sqlx::query_as(
+            "SELECT i.invoice_number, i.created_by, i.currency, t.locale \
+             FROM invoices i JOIN tenants t ON t.id = i.tenant_id WHERE i.id = $1",
+        )
+        .bind(payment.invoice_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some((invoice_number, created_by, currency, locale)) = invoice {
+            notify(
+                &mut *conn,
+                ws,
+                NewNotification {
+                    tenant_id,
+                    user_id: created_by,
+                    kind: "payment_disputed",
+                    title: format!("Payment on invoice {} disputed", invoice_number),
+                    body: Some(format!(
+                        "{} disputed ({})",
+                        money::format(dispute.amount, &currency, &locale),
+                        dispute.reason.as_deref().unwrap_or("no reason given")
+                    )),
+                    resource_type: Some("invoice"),
+                    resource_id: payment.invoice_id,
+                },
+            )
+            .await?;
+        }
+    }
+    Ok(Applied::done(tenant_id))
+}
+
+// ── Subscription billing ─────────────────────────────────────────────
+
+async fn subscription_changed(conn: &mut PgConnection, event: &Event) -> anyhow::Result<Applied> {
+    let sub: Subscription = event.object()?;
+    let tenant_id = resolve_tenant(
+        conn,
+        &sub.metadata,
+        sub.customer.as_deref(),
+        &[Some(&sub.id)],
+    )
+    .await?;
+
+    let status = subscription_status(&sub.status)
+        .ok_or_else(|| anyhow::anyhow!("Unknown subscription status '{}'", sub.status))?;
+    let cancelled_at =
+        timestamp(sub.canceled_at).or_else(|| (status == "cancelled").then(Utc::now));
+
+    // Plan and billing cycle follow the subscribed price, when we know it.
+    let price_id = sub
+        .items
+        .data
+        .first()
+        .and_then(|item| item.price.as_ref())
+        .map(|p| p.id.as_str());
+    let plan: Option<(Uuid, bool)> = match price_id {
+        Some(price_id) => {
+            sqlx::query_as(
+                "SELECT id, stripe_price_id_annual IS NOT DISTINCT FROM $1 FROM plans \
+                 WHERE stripe_price_id_monthly = $1 OR stripe_price_id_annual = $1 \
+                 LIMIT 1",
+            )
+            .bind(price_id)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        None => None,
+    };
+    let plan_id = plan.map(|(id, _)| id);
+    let billing_cycle = plan.map(|(_, annual)| if annual { "annual" } else { "monthly" });
+
+    // Link to the tenant's local subscription the first time we hear about it.
+    let existing: Option<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM subscriptions \
+         WHERE tenant_id = $1 AND (stripe_subscription_id = $2 OR stripe_subscription_id IS NULL) \
+         ORDER BY stripe_subscription_id IS NULL, created_at DESC \
+         LIMIT 1 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .bind(&sub.id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let subscription_id = match existing {
+        Some(id) => {
+            sqlx::query(
+                "UPDATE subscriptions SET stripe_subscription_id = $3, \
+                 stripe_customer_id = COALESCE($4, stripe_customer_id), status = $5, \
+                 plan_id = COALESCE($6, plan_id), billing_cycle = COALESCE($7, billing_cycle), \
+                 current_period_start = COALESCE($8, current_period_start), \
+                 current_period_end = COALESCE($9, current_period_end), \
+                 trial_ends_at = $10, cancelled_at = $11, updated_at = NOW() \
+                 WHERE id = $1 AND tenant_id = $2",
+            )
+            .bind(id)
+            .bind(tenant_id)
+            .bind(&sub.id)
+            .bind(sub.customer.as_deref())
+            .bind(status)
+            .bind(plan_id)
+            .bind(billing_cycle)
+            .bind(timestamp(sub.current_period_start))
+            .bind(timestamp(sub.current_period_end))
+            .bind(timestamp(sub.trial_end))
+            .bind(cancelled_at)
+            .execute(&mut *conn)
+            .await?;
+            id
+        }
+        None => {
+            let Some(plan_id) = plan_id else {
+                return Ok(Applied::ignored(
+                    Some(tenant_id),
+                    "no local subscription or plan for price",
+                ));
+            };
+            sqlx::query_scalar(
+                "INSERT INTO subscriptions (tenant_id, plan_id, status, billing_cycle, \
+                 current_period_start, current_period_end, trial_ends_at, cancelled_at, \
+                 stripe_subscription_id, stripe_customer_id) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id",
+            )
+            .bind(tenant_id)
+            .bind(plan_id)
+            .bind(status)
+            .bind(billing_cycle.unwrap_or("monthly"))
+            .bind(timestamp(sub.current_period_start))
+            .bind(timestamp(sub.current_period_end))
+            .bind(timestamp(sub.trial_end))
+            .bind(cancelled_at)
+            .bind(&sub.id)
+            .bind(sub.customer.as_deref())
+            .fetch_one(&mut *conn)
+            .await?
+        }
+    };
+
+    let kind = match event.event_type.as_str() {
+        "customer.subscription.created" => "subscription_created",
+        "customer.subscription.deleted" => "subscription_cancelled",
+        _ => "subscription_updated",
+    };
+    record_payment_event(
+        conn,
+        tenant_id,
+        event,
+        PaymentEvent {
+            kind,
+            amount_cents: None,
+            subscription_id: Some(subscription_id),
+            billing_invoice_id: None,
+            metadata: serde_json::json!({
+                "subscription": sub.id,
+                "stripe_event_type": event.event_type,
+                "status": sub.status,
+                "price": price_id,
+            }),
+        },
+    )
+    .await?;
+    Ok(Applied::done(tenant_id))
+}
+
+async fn billing_invoice_changed(
+    conn: &mut PgConnection,
+    event: &Event,
+) -> anyhow::Result<Applied> {
+    let invoice: StripeInvoice = event.object()?;
+    let Some(stripe_subscription) = invoice.subscription_id() else {
+        return Ok(Applied::ignored(None, "not a subscription invoice"));
+    };
+    let tenant_id = resolve_tenant(
+        conn,
+        &invoice.metadata,
+        invoice.customer.as_deref(),
+        &[Some(stripe_subscription)],
+    )
+    .await?;
+
+    // Fails (and is retried) if the subscription event has not arrived yet.
+    let subscription_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM subscriptions WHERE tenant_id = $1 AND stripe_subscription_id = $2",
+    )
+    .bind(tenant_id)
+    .bind(stripe_subscription)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Subscription {} not synced yet", stripe_subscription))?;
+
+    let status = if event.event_type == "invoice.deleted" {
+        "void"
+    } else {
+        match invoice.status.as_deref().unwrap_or("draft") {
+            s @ ("draft" | "open" | "paid" | "void" | "uncollectible") => s,
+            other => anyhow::bail!("Unknown invoice status '{}'", other),
+        }
+    };
+
+    let billing_invoice_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO billing_invoices (tenant_id, subscription_id, amount_cents, currency, status, \
+         stripe_invoice_id, due_date, paid_at, pdf_url) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+         ON CONFLICT (stripe_invoice_id) WHERE stripe_invoice_id IS NOT NULL DO UPDATE SET \
+         amount_cents = EXCLUDED.amount_cents, currency = EXCLUDED.currency, status = EXCLUDED.status, \
+         due_date = EXCLUDED.due_date, paid_at = COALESCE(EXCLUDED.paid_at, billing_invoices.paid_at), \
+         pdf_url = COALESCE(EXCLUDED.pdf_url, billing_invoices.pdf_url), updated_at = NOW() \
+         RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(subscription_id)
+    .bind(invoice.amount_due)
+    .bind(invoice.currency.to_uppercase())
+    .bind(status)
+    .bind(&invoice.id)
+    .bind(timestamp(invoice.due_date).map(|t| t.date_naive()))
+    .bind(timestamp(invoice.status_transitions.paid_at))
+    .bind(invoice.invoice_pdf.as_deref())
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let payment_event = match event.event_type.as_str() {
+        "invoice.paid" => Some(("invoice_paid", invoice.amount_paid)),
+        "invoice.payment_failed" => Some(("invoice_payment_failed", invoice.amount_due)),
+        _ => None,
+    };
+    if let Some((kind, amount)) = payment_event {
+        record_payment_event(
+            conn,
+            tenant_id,
+            event,
+            PaymentEvent {
+                kind,
+                amount_cents: Some(amount),
+                subscription_id: Some(subscription_id),
+                billing_invoice_id: Some(billing_invoice_id),
+                metadata: serde_json::json!({ "invoice": invoice.id }),
+            },
+        )
+        .await?;
+    }
+    Ok(Applied::done(tenant_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "whsec_test";
+    const PAYLOAD: &[u8] = br#"{"id":"evt_1","type":"ping"}"#;
+
+    #[test]
+    fn test_signature_round_trip() {
+        let header = sign(PAYLOAD, SECRET, 1_700_000_000);
+        assert_eq!(
+            verify_signature(PAYLOAD, &header, SECRET, 1_700_000_100),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_signature_rejects_tampering_and_wrong_secret() {
+        let header = sign(PAYLOAD, SECRET, 1_700_000_000);
+        assert_eq!(
+            verify_signature(br#"{"id":"evt_2"}"#, &header, SECRET, 1_700_000_000),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify_signature(PAYLOAD, &header, "whsec_other", 1_700_000_000),
+            Err(SignatureError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn test_signature_checks_timestamp_and_format() {
+        let header = sign(PAYLOAD, SECRET, 1_700_000_000);
+        assert_eq!(
+            verify_signature(PAYLOAD, &header, SECRET, 1_700_000_301),
+            Err(SignatureError::Expired)
+        );
+        assert_eq!(
+            verify_signature(PAYLOAD, "v1=abcd", SECRET, 1_700_000_000),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            verify_signature(PAYLOAD, "t=1700000000", SECRET, 1_700_000_000),
+            Err(SignatureError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_signature_accepts_any_listed_secret_version() {
+        // During secret rotation Stripe signs with both secrets.
+        let good = sign(PAYLOAD, SECRET, 1_700_000_000);
+        let v1 = good.split_once(",v1=").unwrap().1;
+        let header = format!("t=1700000000,v1={},v1={}", "00".repeat(32), v1);
+        assert_eq!(
+            verify_signature(PAYLOAD, &header, SECRET, 1_700_000_000),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_subscription_status_mapping() {
+        assert_eq!(subscription_status("active"), Some("active"));
+        assert_eq!(subscription_status("unpaid"), Some("past_due"));
+        assert_eq!(subscription_status("canceled"), Some("cancelled"));
+        assert_eq!(subscription_status("paused"), Some("suspended"));
+        assert_eq!(subscription_status("something_new"), None);
+    }
+
+    #[test]
+    fn test_invoice_subscription_from_parent() {
+        let invoice: StripeInvoice = serde_json::from_value(serde_json::json!({
+            "id": "in_1",
+            "currency": "usd",
+            "parent": { "subscription_details": { "subscription": "sub_1" } }
+        }))
+        .unwrap();
+        assert_eq!(invoice.subscription_id(), Some("sub_1"));
+    }
+}
@@ -1,13 +1,16 @@
-use crate::AppState;
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{extract::Path, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
 
+use super::pipeline::{self, Pipeline, StageDef};
 use crate::auth::Claims;
-use crate::errors::AppError;
+use crate::errors::{AppError, AppResult, FieldError};
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct PipelineTemplate {
-    pub id: String,
+    pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub is_default: bool,
@@ -23,16 +26,48 @@ pub struct CreatePipelineTemplate {
     pub is_default: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdatePipelineTemplate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub stages: Option<serde_json::Value>,
+    pub is_default: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderStages {
+    /// Every stage key of the template, in the new order.
+    pub order: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateJobPipeline {
+    /// `None` keeps the job's template.
+    pub template_id: Option<Uuid>,
+    /// Replaces the job's overrides; `None` keeps them.
+    pub overrides: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobPipeline {
+    pub job_id: Uuid,
+    pub overrides: serde_json::Value,
+    #[serde(flatten)]
+    pub pipeline: Pipeline,
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, description, is_default, stages, created_at";
+
 pub async fn list_templates(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<PipelineTemplate>>, AppError> {
-    let templates = sqlx::query_as::<_, PipelineTemplate>(
-        "SELECT id::text, name, description, is_default, stages, created_at
-         FROM pipeline_templates WHERE tenant_id = $1 ORDER BY is_default DESC, name",
-    )
+    let templates = sqlx::query_as::<_, PipelineTemplate>(&format!(
+        "SELECT {} FROM pipeline_templates WHERE tenant_id = $1 ORDER BY is_default DESC, name",
+        TEMPLATE_COLUMNS
+    ))
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -41,22 +76,287 @@ pub async fn list_templates(
 
 pub async fn create_template(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreatePipelineTemplate>,
 ) -> Result<(StatusCode, Json<PipelineTemplate>), AppError> {
-    let template = sqlx::query_as::<_, PipelineTemplate>(
-        "INSERT INTO pipeline_templates (tenant_id, name, description, stages, is_default)
-         VALUES ($1, $2, $3, $4, $5)
-         RETURNING id::text, name, description, is_default, stages, created_at",
-    )
+    let stages = validate_template(&body.name, &body.stages)?;
+    let is_default = body.is_default.unwrap_or(false);
+    if is_default {
+        clear_default(&mut tx, claims.tid).await?;
+    }
+
+    let template = sqlx::query_as::<_, PipelineTemplate>(&format!(
+        "INSERT INTO pipeline_templates (tenant_id, name, description, stages, is_default) \
+         VALUES ($1, $2, $3, $4, $5) \
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
     .bind(claims.tid)
-    .bind(&body.name)
+    .bind(body.name.trim())
     .bind(&body.description)
-    .bind(&body.stages)
-    .bind(body.is_default.unwrap_or(false))
-    .fetch_one(&state.db)
+    .bind(stages_json(&stages))
+    .bind(is_default)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
     Ok((StatusCode::CREATED, Json(template)))
 }
+
+pub async fn update_template(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdatePipelineTemplate>,
+) -> AppResult<Json<PipelineTemplate>> {
+    let existing = fetch_template(&mut tx, claims.tid, id).await?;
+    let name = body.name.unwrap_or(existing.name);
+    let stages = validate_template(&name, body.stages.as_ref().unwrap_or(&existing.stages))?;
+    check_jobs_still_valid(&mut tx, claims.tid, id, &stages).await?;
+
+    let is_default = body.is_default.unwrap_or(existing.is_default);
+    if is_default && !existing.is_default {
+        clear_default(&mut tx, claims.tid).await?;
+    }
+
+    let template = sqlx::query_as::<_, PipelineTemplate>(&format!(
+        "UPDATE pipeline_templates SET name = $3, description = $4, stages = $5, \
+         is_default = $6, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(name.trim())
+    .bind(body.description.or(existing.description))
+    .bind(stages_json(&stages))
+    .bind(is_default)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok(Json(template))
+}
+
+/// Delete a template no job uses.
+pub async fn delete_template(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    fetch_template(&mut tx, claims.tid, id).await?;
+
+    let in_use: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM job_posts WHERE tenant_id = $1 AND pipeline_template_id = $2",
+    )
+    .bind(claims.tid)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if in_use > 0 {
+        return Err(AppError::Conflict(format!(
+            "Pipeline template is used by {} job(s); move them to another template first",
+            in_use
+        )));
+    }
+
+    sqlx::query("DELETE FROM pipeline_templates WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(claims.tid)
+        .execute(&mut *tx)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Put a template's stages in a new order. `order` must list every stage once.
+pub async fn reorder_stages(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ReorderStages>,
+) -> AppResult<Json<PipelineTemplate>> {
+    let existing = fetch_template(&mut tx, claims.tid, id).await?;
+    let stages = validate_template(&existing.name, &existing.stages)?;
+
+    let mut keys: Vec<&str> = stages.iter().map(|s| s.key.as_str()).collect();
+    let mut order: Vec<&str> = body.order.iter().map(String::as_str).collect();
+    keys.sort_unstable();
+    order.sort_unstable();
+    if keys != order {
+        return Err(AppError::Validation(format!(
+            "order must list each of the template's stages exactly once: {}",
+            stages
+                .iter()
+                .map(|s| s.key.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let reordered: Vec<StageDef> = body
+        .order
+        .iter()
+        .filter_map(|key| stages.iter().find(|s| &s.key == key).cloned())
+        .collect();
+    let reordered = validate_template(&existing.name, &stages_json(&reordered))?;
+    check_jobs_still_valid(&mut tx, claims.tid, id, &reordered).await?;
+
+    let template = sqlx::query_as::<_, PipelineTemplate>(&format!(
+        "UPDATE pipeline_templates SET stages = $3, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2 \
+         RETURNING {}",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(stages_json(&reordered))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok(Json(template))
+}
+
+/// The pipeline in force for a job, with its overrides applied.
+pub async fn get_job_pipeline(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<JobPipeline>> {
+    Ok(Json(job_pipeline(&mut tx, claims.tid, job_id).await?))
+}
+
+/// Link a job to a template and/or replace its per-stage overrides.
+pub async fn update_job_pipeline(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(job_id): Path<Uuid>,
+    Json(body): Json<UpdateJobPipeline>,
+) -> AppResult<Json<JobPipeline>> {
+    let current = job_pipeline(&mut tx, claims.tid, job_id).await?;
+    let template_id = body.template_id.or(current.pipeline.template_id);
+    let overrides = body.overrides.unwrap_or(current.overrides);
+
+    let stages = match template_id {
+        Some(template_id) => {
+            let template = fetch_template(&mut tx, claims.tid, template_id).await?;
+            validate_template(&template.name, &template.stages)?
+        }
+        None => Pipeline::built_in().stages,
+    };
+    pipeline::apply_overrides(&stages, &overrides).map_err(AppError::InvalidFields)?;
+
+    sqlx::query(
+        "UPDATE job_posts SET pipeline_template_id = $3, pipeline_overrides = $4, \
+         updated_at = NOW() WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(job_id)
+    .bind(claims.tid)
+    .bind(template_id)
+    .bind(&overrides)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(Json(job_pipeline(&mut tx, claims.tid, job_id).await?))
+}
+
+async fn job_pipeline(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    job_id: Uuid,
+) -> AppResult<JobPipeline> {
+    let pipeline = pipeline::for_job(conn, tenant_id, job_id).await?;
+    let overrides: serde_json::Value = sqlx::query_scalar(
+        "SELECT pipeline_overrides FROM job_posts WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(job_id)
+    .bind(tenant_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(JobPipeline {
+        job_id,
+        overrides,
+        pipeline,
+    })
+}
+
+async fn fetch_template(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> AppResult<PipelineTemplate> {
+    sqlx::query_as::<_, PipelineTemplate>(&format!(
+        "SELECT {} FROM pipeline_templates WHERE id = $1 AND tenant_id = $2",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Pipeline template not found".to_string()))
+}
+
+fn validate_template(name: &str, stages: &serde_json::Value) -> AppResult<Vec<StageDef>> {
+    let mut errors = Vec::new();
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    }
+    match pipeline::parse_stages(stages, "stages") {
+        Ok(stages) if errors.is_empty() => Ok(stages),
+        Ok(_) => Err(AppError::InvalidFields(errors)),
+        Err(stage_errors) => {
+            errors.extend(stage_errors);
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+}
+
+fn stages_json(stages: &[StageDef]) -> serde_json::Value {
+    serde_json::to_value(stages).expect("stages serialize")
+}
+
+async fn clear_default(conn: &mut PgConnection, tenant_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        "UPDATE pipeline_templates SET is_default = false, updated_at = NOW() \
+         WHERE tenant_id = $1 AND is_default",
+    )
+    .bind(tenant_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Refuse template changes that would break a job's overrides, e.g. by
+/// removing a stage a job overrides.
+async fn check_jobs_still_valid(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    template_id: Uuid,
+    stages: &[StageDef],
+) -> AppResult<()> {
+    let jobs: Vec<(String, serde_json::Value)> = sqlx::query_as(
+        "SELECT title, pipeline_overrides FROM job_posts \
+         WHERE tenant_id = $1 AND pipeline_template_id = $2 AND pipeline_overrides <> '{}' \
+         ORDER BY title",
+    )
+    .bind(tenant_id)
+    .bind(template_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (title, overrides) in jobs {
+        if let Err(errors) = pipeline::apply_overrides(stages, &overrides) {
+            return Err(AppError::Conflict(format!(
+                "Job '{}' has pipeline overrides this change would break: {}",
+                title,
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            )));
+        }
+    }
+    Ok(())
+}
@@ -1 +1,24 @@
 pub mod handler;
+pub mod pipeline;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/pipeline-templates", get(handler::list_templates))
+        .route("/pipeline-templates", post(handler::create_template))
+        .route("/pipeline-templates/{id}", put(handler::update_template))
+        .route("/pipeline-templates/{id}", delete(handler::delete_template))
+        .route(
+            "/pipeline-templates/{id}/reorder",
+            post(handler::reorder_stages),
+        )
+        .route("/jobs/{id}/pipeline", get(handler::get_job_pipeline))
+        .route("/jobs/{id}/pipeline", put(handler::update_job_pipeline))
+}
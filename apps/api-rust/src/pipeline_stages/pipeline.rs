@@ -0,0 +1,522 @@
+//! Hiring pipelines and the application stage state machine.
+//!
+//! A pipeline is an ordered list of stages drawn from
+//! [`crate::applications::model::STAGES`]. Each stage may restrict which
+//! stages follow it, require submitted scorecards before an application
+//! leaves it, or be terminal. `rejected` and `withdrawn` are not listed in
+//! pipelines: they are always reachable from a non-terminal stage, and are
+//! themselves terminal.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::applications::model::STAGES;
+use crate::error::{AppError, AppResult, FieldError};
+
+/// Stages every pipeline can exit to.
+pub const EXIT_STAGES: [&str; 2] = ["rejected", "withdrawn"];
+
+const MAX_REQUIRED_SCORECARDS: i64 = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageDef {
+    pub key: String,
+    pub name: String,
+    /// Stages an application may move to from this one. `None` allows any
+    /// stage in the pipeline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Vec<String>>,
+    /// Submitted scorecards for this stage needed before leaving it, other
+    /// than to reject or withdraw.
+    #[serde(default)]
+    pub required_scorecards: i64,
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+/// The pipeline in force for a job.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pipeline {
+    /// `None` when the job uses the built-in pipeline.
+    pub template_id: Option<Uuid>,
+    pub stages: Vec<StageDef>,
+}
+
+impl Pipeline {
+    /// Used by jobs with no template when the tenant has no default.
+    pub fn built_in() -> Self {
+        let stage = |key: &str, name: &str, terminal: bool| StageDef {
+            key: key.to_string(),
+            name: name.to_string(),
+            next: None,
+            required_scorecards: 0,
+            terminal,
+        };
+        Self {
+            template_id: None,
+            stages: vec![
+                stage("applied", "Applied", false),
+                stage("screening", "Screening", false),
+                stage("phone_screen", "Phone Screen", false),
+                stage("technical", "Technical", false),
+                stage("onsite", "Onsite", false),
+                stage("offer", "Offer", false),
+                stage("hired", "Hired", true),
+            ],
+        }
+    }
+
+    /// Stage new applications start in.
+    pub fn initial_stage(&self) -> &str {
+        &self.stages[0].key
+    }
+
+    pub fn stage(&self, key: &str) -> Option<&StageDef> {
+        self.stages.iter().find(|s| s.key == key)
+    }
+
+    /// Check a move from `from` to `to`, given how many scorecards have
+    /// been submitted for `from`. Returns why the move is not allowed.
+    pub fn check_transition(&self, from: &str, to: &str, submitted: i64) -> Result<(), String> {
+        if from == to {
+            return Err(format!("Application is already in stage '{}'", to));
+        }
+        if EXIT_STAGES.contains(&from) || self.stage(from).is_some_and(|s| s.terminal) {
+            return Err(format!(
+                "Stage '{}' is terminal; the application cannot move on",
+                from
+            ));
+        }
+        if EXIT_STAGES.contains(&to) {
+            return Ok(());
+        }
+        if self.stage(to).is_none() {
+            return Err(format!(
+                "'{}' is not a stage in this job's pipeline; expected one of: {}",
+                to,
+                self.stage_keys().join(", ")
+            ));
+        }
+
+        // Applications left in a stage the pipeline no longer has may move
+        // to any of its stages.
+        let Some(current) = self.stage(from) else {
+            return Ok(());
+        };
+        if let Some(next) = &current.next {
+            if !next.iter().any(|n| n == to) {
+                let mut allowed: Vec<&str> = next.iter().map(String::as_str).collect();
+                allowed.extend(EXIT_STAGES);
+                return Err(format!(
+                    "Cannot move from '{}' to '{}'; allowed: {}",
+                    from,
+                    to,
+                    allowed.join(", ")
+                ));
+            }
+        }
+        if submitted < current.required_scorecards {
+            return Err(format!(
+                "Stage '{}' needs {} submitted scorecard(s) before moving on; {} submitted",
+                from, current.required_scorecards, submitted
+            ));
+        }
+        Ok(())
+    }
+
+    fn stage_keys(&self) -> Vec<&str> {
+        self.stages.iter().map(|s| s.key.as_str()).collect()
+    }
+}
+
+/// Validate a template's `stages`, reporting problems under `path`.
+pub fn parse_stages(value: &Value, path: &str) -> Result<Vec<StageDef>, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let Value::Array(items) = value else {
+        return Err(vec![FieldError::new(path, "must be an array of stages")]);
+    };
+    if items.is_empty() {
+        return Err(vec![FieldError::new(
+            path,
+            "at least one stage is required",
+        )]);
+    }
+
+    let keys: Vec<Option<&str>> = items
+        .iter()
+        .map(|item| item.get("key").and_then(Value::as_str))
+        .collect();
+
+    for (i, item) in items.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        let Value::Object(map) = item else {
+            errors.push(FieldError::new(path, "must be an object"));
+            continue;
+        };
+        for key in map.keys() {
+            if !["key", "name", "next", "required_scorecards", "terminal"].contains(&key.as_str()) {
+                errors.push(FieldError::new(
+                    format!("{}.{}", path, key),
+                    "unknown field",
+                ));
+            }
+        }
+
+        match keys[i] {
+            Some(key) if EXIT_STAGES.contains(&key) => errors.push(FieldError::new(
+                format!("{}.key", path),
+                format!("'{}' is always available and cannot be listed", key),
+            )),
+            Some(key) if !STAGES.contains(&key) => errors.push(FieldError::new(
+                format!("{}.key", path),
+                format!(
+                    "unknown stage '{}'; expected one of: {}",
+                    key,
+                    STAGES
+                        .iter()
+                        .filter(|s| !EXIT_STAGES.contains(s))
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )),
+            Some(key) if keys[..i].contains(&Some(key)) => errors.push(FieldError::new(
+                format!("{}.key", path),
+                format!("stage '{}' is listed twice", key),
+            )),
+            Some(_) => {}
+            None => errors.push(FieldError::new(format!("{}.key", path), "is required")),
+        }
+
+        match map.get("name") {
+            Some(Value::String(name)) if !name.trim().is_empty() => {}
+            _ => errors.push(FieldError::new(
+                format!("{}.name", path),
+                "must be a non-empty string",
+            )),
+        }
+
+        let terminal = match map.get("terminal") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(terminal)) => *terminal,
+            Some(_) => {
+                errors.push(FieldError::new(
+                    format!("{}.terminal", path),
+                    "must be a boolean",
+                ));
+                false
+            }
+        };
+        if terminal && i == 0 {
+            errors.push(FieldError::new(
+                format!("{}.terminal", path),
+                "the first stage cannot be terminal",
+            ));
+        }
+
+        match map.get("next") {
+            None | Some(Value::Null) => {}
+            Some(_) if terminal => errors.push(FieldError::new(
+                format!("{}.next", path),
+                "terminal stages have no next stages",
+            )),
+            Some(Value::Array(next)) => {
+                for (j, n) in next.iter().enumerate() {
+                    let next_path = format!("{}.next[{}]", path, j);
+                    match n.as_str() {
+                        Some(n) if Some(n) == keys[i] => {
+                            errors.push(FieldError::new(next_path, "a stage cannot lead to itself"))
+                        }
+                        Some(n) if EXIT_STAGES.contains(&n) || keys.contains(&Some(n)) => {}
+                        Some(n) => errors.push(FieldError::new(
+                            next_path,
+                            format!("'{}' is not a stage in this pipeline", n),
+                        )),
+                        None => errors.push(FieldError::new(next_path, "must be a string")),
+                    }
+                }
+            }
+            Some(_) => errors.push(FieldError::new(
+                format!("{}.next", path),
+                "must be an array of stage keys",
+            )),
+        }
+
+        match map.get("required_scorecards") {
+            None | Some(Value::Null) => {}
+            Some(n)
+                if n.as_i64()
+                    .is_some_and(|n| (0..=MAX_REQUIRED_SCORECARDS).contains(&n)) => {}
+            Some(_) => errors.push(FieldError::new(
+                format!("{}.required_scorecards", path),
+                format!(
+                    "must be a whole number from 0 to {}",
+                    MAX_REQUIRED_SCORECARDS
+                ),
+            )),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    serde_json::from_value(value.clone()).map_err(|e| vec![FieldError::new(path, e.to_string())])
+}
+
+/// Apply a job's per-stage overrides to its template's stages. Each entry
+/// may change `name`, `next`, `required_scorecards` or `terminal`, or drop
+/// the stage with `"enabled": false`.
+pub fn apply_overrides(
+    stages: &[StageDef],
+    overrides: &Value,
+) -> Result<Vec<StageDef>, Vec<FieldError>> {
+    let empty = Map::new();
+    let overrides = match overrides {
+        Value::Null => &empty,
+        Value::Object(map) => map,
+        _ => return Err(vec![FieldError::new("overrides", "must be an object")]),
+    };
+
+    let mut errors = Vec::new();
+    let mut merged = Vec::new();
+    for (key, patch) in overrides {
+        if !stages.iter().any(|s| &s.key == key) {
+            errors.push(FieldError::new(
+                format!("overrides.{}", key),
+                "not a stage in the job's pipeline template",
+            ));
+        } else if let Some(map) = patch.as_object() {
+            for field in map.keys() {
+                if !["name", "next", "required_scorecards", "terminal", "enabled"]
+                    .contains(&field.as_str())
+                {
+                    errors.push(FieldError::new(
+                        format!("overrides.{}.{}", key, field),
+                        "unknown field",
+                    ));
+                }
+            }
+            if !map.get("enabled").map_or(true, Value::is_boolean) {
+                errors.push(FieldError::new(
+                    format!("overrides.{}.enabled", key),
+                    "must be a boolean",
+                ));
+            }
+        } else {
+            errors.push(FieldError::new(
+                format!("overrides.{}", key),
+                "must be an object",
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    for stage in stages {
+        let mut value = serde_json::to_value(stage).expect("stage serializes");
+        if let Some(Value::Object(patch)) = overrides.get(&stage.key) {
+            if patch.get("enabled") == Some(&Value::Bool(false)) {
+                continue;
+            }
+            for (field, v) in patch.iter().filter(|(f, _)| *f != "enabled") {
+                value[field] = v.clone();
+            }
+        }
+        merged.push(value);
+    }
+
+    // Re-check the result, e.g. for `next` pointing at a dropped stage.
+    parse_stages(&Value::Array(merged), "stages").map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|e| FieldError::new("overrides", format!("result is invalid at {}", e)))
+            .collect()
+    })
+}
+
+/// The pipeline for a job: its template (or the tenant's default template,
+/// or the built-in pipeline) with the job's overrides applied.
+pub async fn for_job(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    job_id: Uuid,
+) -> AppResult<Pipeline> {
+    let (template_id, overrides): (Option<Uuid>, Value) = sqlx::query_as(
+        "SELECT pipeline_template_id, pipeline_overrides FROM job_posts \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(job_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
+
+    let template: Option<(Uuid, String, Value)> = sqlx::query_as(
+        "SELECT id, name, stages FROM pipeline_templates \
+         WHERE tenant_id = $1 AND (id = $2 OR ($2 IS NULL AND is_default))",
+    )
+    .bind(tenant_id)
+    .bind(template_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let pipeline = match template {
+        Some((id, name, stages)) => Pipeline {
+            template_id: Some(id),
+            stages: parse_stages(&stages, "stages").map_err(|errors| {
+                AppError::Validation(format!(
+                    "Pipeline template '{}' is invalid: {}",
+                    name,
+                    join(&errors)
+                ))
+            })?,
+        },
+        None => Pipeline::built_in(),
+    };
+
+    let stages = apply_overrides(&pipeline.stages, &overrides).map_err(|errors| {
+        AppError::Validation(format!(
+            "This job's pipeline overrides are invalid: {}",
+            join(&errors)
+        ))
+    })?;
+    Ok(Pipeline { stages, ..pipeline })
+}
+
+/// Check that an application may move from `from_stage` to `to_stage` under
+/// its job's pipeline.
+pub async fn check_transition(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    application_id: Uuid,
+    job_id: Uuid,
+    from_stage: &str,
+    to_stage: &str,
+) -> AppResult<()> {
+    let pipeline = for_job(conn, tenant_id, job_id).await?;
+    let submitted: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM scorecards \
+         WHERE application_id = $1 AND tenant_id = $2 AND interview_stage = $3 \
+         AND submitted_at IS NOT NULL",
+    )
+    .bind(application_id)
+    .bind(tenant_id)
+    .bind(from_stage)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    pipeline
+        .check_transition(from_stage, to_stage, submitted)
+        .map_err(AppError::Validation)
+}
+
+fn join(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pipeline() -> Pipeline {
+        Pipeline {
+            template_id: None,
+            stages: parse_stages(
+                &json!([
+                    {"key": "applied", "name": "Applied", "next": ["screening"]},
+                    {"key": "screening", "name": "Screen", "next": ["technical"]},
+                    {"key": "technical", "name": "Tech", "required_scorecards": 2},
+                    {"key": "offer", "name": "Offer"},
+                    {"key": "hired", "name": "Hired", "terminal": true},
+                ]),
+                "stages",
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_transitions_follow_next_stages() {
+        let p = pipeline();
+        assert!(p.check_transition("applied", "screening", 0).is_ok());
+        let err = p.check_transition("applied", "offer", 0).unwrap_err();
+        assert!(
+            err.contains("allowed: screening, rejected, withdrawn"),
+            "{}",
+            err
+        );
+        assert!(p.check_transition("applied", "onsite", 0).is_err());
+        assert!(p.check_transition("applied", "applied", 0).is_err());
+    }
+
+    #[test]
+    fn test_scorecards_gate_leaving_a_stage_but_not_exits() {
+        let p = pipeline();
+        assert!(p.check_transition("technical", "offer", 1).is_err());
+        assert!(p.check_transition("technical", "offer", 2).is_ok());
+        assert!(p.check_transition("technical", "rejected", 0).is_ok());
+    }
+
+    #[test]
+    fn test_terminal_stages_are_final() {
+        let p = pipeline();
+        assert!(p.check_transition("hired", "offer", 0).is_err());
+        assert!(p.check_transition("hired", "withdrawn", 0).is_err());
+        assert!(p.check_transition("rejected", "applied", 0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_stages_report_paths() {
+        let errors = parse_stages(
+            &json!([
+                {"key": "applied", "name": "Applied", "next": ["onsite"], "terminal": true},
+                {"key": "rejected", "name": "No"},
+                {"key": "intervew", "name": ""},
+            ]),
+            "stages",
+        )
+        .unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "stages[0].terminal",
+                "stages[0].next",
+                "stages[1].key",
+                "stages[2].key",
+                "stages[2].name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overrides_patch_and_drop_stages() {
+        let p = pipeline();
+        let stages = apply_overrides(
+            &p.stages,
+            &json!({
+                "technical": {"required_scorecards": 0, "name": "Take-home"},
+                "offer": {"enabled": false},
+            }),
+        )
+        .unwrap();
+        let keys: Vec<&str> = stages.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, ["applied", "screening", "technical", "hired"]);
+        assert_eq!(stages[2].name, "Take-home");
+        assert_eq!(stages[2].required_scorecards, 0);
+
+        // Dropping a stage another stage leads to is rejected.
+        let errors =
+            apply_overrides(&p.stages, &json!({"technical": {"enabled": false}})).unwrap_err();
+        assert_eq!(errors[0].path, "overrides");
+        assert!(errors[0].message.contains("stages[1].next[0]"));
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Path, Query},
     http::StatusCode,
     Json,
 };
@@ -9,7 +9,7 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct InterviewQuestion {
@@ -88,7 +88,7 @@ pub struct UpdateQuestionSetPayload {
 }
 
 pub async fn list_questions(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Query(params): Query<ListQuestionsParams>,
 ) -> AppResult<Json<Vec<InterviewQuestion>>> {
@@ -106,7 +106,7 @@ pub async fn list_questions(
     .bind(params.difficulty)
     .bind(params.is_starred)
     .bind(params.search)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -114,7 +114,7 @@ pub async fn list_questions(
 }
 
 pub async fn create_question(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<CreateQuestionPayload>,
 ) -> AppResult<(StatusCode, Json<InterviewQuestion>)> {
@@ -131,7 +131,7 @@ pub async fn create_question(
     .bind(payload.scoring_rubric)
     .bind(payload.tags.as_deref().unwrap_or(&[]))
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -139,7 +139,7 @@ pub async fn create_question(
 }
 
 pub async fn update_question(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateQuestionPayload>,
@@ -166,7 +166,7 @@ pub async fn update_question(
     .bind(payload.scoring_rubric)
     .bind(payload.is_starred)
     .bind(payload.tags)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -175,14 +175,14 @@ pub async fn update_question(
 }
 
 pub async fn delete_question(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     sqlx::query("DELETE FROM interview_questions WHERE id = $1 AND tenant_id = $2")
         .bind(id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::Database)?;
 
@@ -190,14 +190,14 @@ pub async fn delete_question(
 }
 
 pub async fn list_question_sets(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
 ) -> AppResult<Json<Vec<QuestionSet>>> {
     let sets = sqlx::query_as::<_, QuestionSet>(
         "SELECT * FROM question_sets WHERE tenant_id = $1 ORDER BY name ASC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -205,7 +205,7 @@ pub async fn list_question_sets(
 }
 
 pub async fn create_question_set(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<CreateQuestionSetPayload>,
 ) -> AppResult<(StatusCode, Json<QuestionSet>)> {
@@ -220,7 +220,7 @@ pub async fn create_question_set(
     .bind(payload.interview_type)
     .bind(&payload.question_ids)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -228,7 +228,7 @@ pub async fn create_question_set(
 }
 
 pub async fn update_question_set(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateQuestionSetPayload>,
@@ -249,7 +249,7 @@ pub async fn update_question_set(
     .bind(payload.description)
     .bind(payload.interview_type)
     .bind(payload.question_ids)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(AppError::Database)?
     .ok_or(AppError::NotFound("Resource not found".into()))?;
@@ -258,14 +258,14 @@ pub async fn update_question_set(
 }
 
 pub async fn delete_question_set(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     sqlx::query("DELETE FROM question_sets WHERE id = $1 AND tenant_id = $2")
         .bind(id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::Database)?;
 
@@ -1 +1,21 @@
 pub mod handler;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/questions", get(handler::list_questions))
+        .route("/questions", post(handler::create_question))
+        .route("/questions/{id}", put(handler::update_question))
+        .route("/questions/{id}", delete(handler::delete_question))
+        .route("/question-sets", get(handler::list_question_sets))
+        .route("/question-sets", post(handler::create_question_set))
+        .route("/question-sets/{id}", put(handler::update_question_set))
+        .route("/question-sets/{id}", delete(handler::delete_question_set))
+}
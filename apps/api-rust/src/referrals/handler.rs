@@ -1,9 +1,9 @@
-use crate::AppState;
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 
 use crate::auth::Claims;
 use crate::errors::AppError;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Referral {
@@ -31,7 +31,7 @@ pub struct CreateReferral {
 
 pub async fn list_referrals(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<Referral>>, AppError> {
     let referrals = sqlx::query_as::<_, Referral>(
         "SELECT id::text, referrer_id::text, candidate_name, candidate_email,
@@ -40,7 +40,7 @@ pub async fn list_referrals(
          FROM referrals WHERE tenant_id = $1 ORDER BY created_at DESC LIMIT 100",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -49,7 +49,7 @@ pub async fn list_referrals(
 
 pub async fn create_referral(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreateReferral>,
 ) -> Result<(StatusCode, Json<Referral>), AppError> {
     let referral = sqlx::query_as::<_, Referral>(
@@ -66,7 +66,7 @@ pub async fn create_referral(
     .bind(&body.job_id)
     .bind(&body.relationship)
     .bind(&body.notes)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -1 +1,15 @@
 pub mod handler;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/referrals", get(handler::list_referrals))
+        .route("/referrals", post(handler::create_referral))
+}
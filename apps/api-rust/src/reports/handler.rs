@@ -1,16 +1,64 @@
 use axum::{
-    extract::{Extension, Query, State},
+    extract::{Extension, Query},
     Json,
 };
 
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
 use crate::auth::jwt::Claims;
 use crate::error::AppResult;
 use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::money::{rates::Converter, Currency};
 use crate::reports::model::*;
-use crate::AppState;
 
+/// The tenant's base currency, with a converter into it holding the rates
+/// in effect up to `until`.
+async fn base_converter(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    until: Option<NaiveDate>,
+) -> AppResult<Converter> {
+    let base: String = sqlx::query_scalar("SELECT base_currency FROM tenants WHERE id = $1")
+        .bind(tenant_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let base = Currency::parse(&base)?;
+    match until {
+        Some(until) => Converter::load(conn, tenant_id, base, until).await,
+        None => Ok(Converter::new(base, Vec::new())),
+    }
+}
+
+/// Sum `(key, currency, date, amount)` rows into base-currency amounts
+/// per key, converting each at the rate in effect on its date. Keys keep
+/// the order they first appear in.
+fn convert_grouped(
+    converter: &mut Converter,
+    rows: Vec<(String, String, NaiveDate, i64)>,
+) -> AppResult<Vec<(String, i64)>> {
+    let mut totals: Vec<(String, i64)> = Vec::new();
+    for (key, currency, date, amount) in rows {
+        let converted = converter.convert(amount, &currency, date)?;
+        match totals.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, total)) => *total += converted,
+            None => totals.push((key, converted)),
+        }
+    }
+    Ok(totals)
+}
+
+fn latest_date(rows: &[(String, String, NaiveDate, i64)]) -> Option<NaiveDate> {
+    rows.iter().map(|(_, _, date, _)| *date).max()
+}
+
+/// Profit and loss in the tenant's base currency. Invoices in other
+/// currencies are converted at the rate in effect on their paid date;
+/// expenses are recorded in the base currency.
 pub async fn get_profit_loss(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ReportQuery>,
 ) -> AppResult<Json<ProfitLossReport>> {
@@ -19,34 +67,64 @@ pub async fn get_profit_loss(
     let start = params.start_date.as_deref().unwrap_or("2026-01-01");
     let end = params.end_date.as_deref().unwrap_or("2026-12-31");
 
-    // Revenue: paid invoices in period
-    let (revenue_cents,): (i64,) = sqlx::query_as(
-        "SELECT COALESCE(SUM(amount_paid_cents), 0)::BIGINT FROM invoices \
-         WHERE tenant_id = $1 AND status = 'paid' AND paid_date >= $2::DATE AND paid_date <= $3::DATE"
+    // Revenue: paid invoices in period, less what credit notes settled,
+    // by client business type
+    let revenue_rows: Vec<(String, String, NaiveDate, i64)> = sqlx::query_as(
+        "SELECT COALESCE(c.business_type, 'Other'), i.currency, i.paid_date, \
+         COALESCE(SUM(i.amount_paid_cents - i.credited_cents), 0)::BIGINT \
+         FROM invoices i JOIN clients c ON c.id = i.client_id \
+         WHERE i.tenant_id = $1 AND i.status = 'paid' AND i.paid_date >= $2::DATE AND i.paid_date <= $3::DATE \
+         GROUP BY 1, 2, 3 ORDER BY 3, 1",
     )
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_one(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
-    // Revenue by service type from time entries linked to paid invoices
-    let service_revenue: Vec<(Option<String>, i64)> = sqlx::query_as(
-        "SELECT c.business_type, COALESCE(SUM(i.amount_paid_cents), 0)::BIGINT \
-         FROM invoices i JOIN clients c ON c.id = i.client_id \
+    // Sales tax collected on paid invoices, by rate
+    let tax_rows: Vec<(String, Option<String>, String, NaiveDate, i64)> = sqlx::query_as(
+        "SELECT it.name, it.jurisdiction, i.currency, i.paid_date, COALESCE(SUM(it.tax_cents), 0)::BIGINT \
+         FROM invoice_taxes it JOIN invoices i ON i.id = it.invoice_id \
          WHERE i.tenant_id = $1 AND i.status = 'paid' AND i.paid_date >= $2::DATE AND i.paid_date <= $3::DATE \
-         GROUP BY c.business_type ORDER BY 2 DESC"
+         GROUP BY 1, 2, 3, 4 ORDER BY 4, 1",
     )
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
+    let tax_rows: Vec<(String, String, NaiveDate, i64)> = tax_rows
+        .into_iter()
+        .map(|(name, jurisdiction, currency, date, amount)| {
+            let label = match jurisdiction {
+                Some(j) => format!("{} ({})", name, j),
+                None => name,
+            };
+            (label, currency, date, amount)
+        })
+        .collect();
+
+    let until = latest_date(&revenue_rows).max(latest_date(&tax_rows));
+    let mut converter = base_converter(&mut tx, claims.tid, until).await?;
 
-    let revenue_items: Vec<ReportLineItem> = service_revenue
+    let mut revenue_by_type = convert_grouped(&mut converter, revenue_rows)?;
+    revenue_by_type.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+    let revenue_items: Vec<ReportLineItem> = revenue_by_type
         .into_iter()
         .map(|(label, amount)| ReportLineItem {
-            label: label.unwrap_or_else(|| "Other".to_string()),
+            label,
+            amount_cents: amount,
+        })
+        .collect();
+    let revenue_cents: i64 = revenue_items.iter().map(|i| i.amount_cents).sum();
+
+    let mut tax_by_rate = convert_grouped(&mut converter, tax_rows)?;
+    tax_by_rate.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let tax_items: Vec<ReportLineItem> = tax_by_rate
+        .into_iter()
+        .map(|(label, amount)| ReportLineItem {
+            label,
             amount_cents: amount,
         })
         .collect();
@@ -59,7 +137,7 @@ pub async fn get_profit_loss(
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let expense_by_category: Vec<(String, i64)> = sqlx::query_as(
@@ -70,7 +148,7 @@ pub async fn get_profit_loss(
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let expense_items: Vec<ReportLineItem> = expense_by_category
@@ -84,6 +162,7 @@ pub async fn get_profit_loss(
     Ok(Json(ProfitLossReport {
         period_start: start.to_string(),
         period_end: end.to_string(),
+        base_currency: converter.base().code.to_string(),
         revenue: ProfitLossSection {
             total_cents: revenue_cents,
             items: revenue_items,
@@ -92,12 +171,19 @@ pub async fn get_profit_loss(
             total_cents: expense_cents,
             items: expense_items,
         },
+        sales_tax_collected: ProfitLossSection {
+            total_cents: tax_items.iter().map(|i| i.amount_cents).sum(),
+            items: tax_items,
+        },
         net_income_cents: revenue_cents - expense_cents,
+        exchange_rates: converter.used(),
     }))
 }
 
+/// Monthly cash flow in the tenant's base currency, converted as in
+/// [`get_profit_loss`].
 pub async fn get_cash_flow(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ReportQuery>,
 ) -> AppResult<Json<CashFlowReport>> {
@@ -106,17 +192,17 @@ pub async fn get_cash_flow(
     let start = params.start_date.as_deref().unwrap_or("2026-01-01");
     let end = params.end_date.as_deref().unwrap_or("2026-12-31");
 
-    // Monthly inflows (paid invoices)
-    let inflows: Vec<(String, i64)> = sqlx::query_as(
-        "SELECT TO_CHAR(paid_date, 'YYYY-MM'), COALESCE(SUM(amount_paid_cents), 0)::BIGINT \
+    // Monthly inflows (paid invoices, less what credit notes settled)
+    let inflows: Vec<(String, String, NaiveDate, i64)> = sqlx::query_as(
+        "SELECT TO_CHAR(paid_date, 'YYYY-MM'), currency, paid_date, COALESCE(SUM(amount_paid_cents - credited_cents), 0)::BIGINT \
          FROM invoices WHERE tenant_id = $1 AND status = 'paid' \
          AND paid_date >= $2::DATE AND paid_date <= $3::DATE \
-         GROUP BY TO_CHAR(paid_date, 'YYYY-MM') ORDER BY 1",
+         GROUP BY 1, 2, 3 ORDER BY 3",
     )
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     // Monthly outflows (expenses)
@@ -128,10 +214,11 @@ pub async fn get_cash_flow(
     .bind(claims.tid)
     .bind(start)
     .bind(end)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
-    let inflow_entries: Vec<CashFlowEntry> = inflows
+    let mut converter = base_converter(&mut tx, claims.tid, latest_date(&inflows)).await?;
+    let inflow_entries: Vec<CashFlowEntry> = convert_grouped(&mut converter, inflows)?
         .into_iter()
         .map(|(month, amount)| CashFlowEntry {
             month,
@@ -153,14 +240,78 @@ pub async fn get_cash_flow(
     Ok(Json(CashFlowReport {
         period_start: start.to_string(),
         period_end: end.to_string(),
+        base_currency: converter.base().code.to_string(),
         inflows: inflow_entries,
         outflows: outflow_entries,
         net_cash_flow_cents: total_in - total_out,
+        exchange_rates: converter.used(),
+    }))
+}
+
+/// Accounts receivable aging: each client's unpaid balances on sent
+/// invoices, bucketed by days past due as of `as_of`. Balances in other
+/// currencies are converted at the rate in effect on `as_of`.
+pub async fn get_ar_aging(
+    mut tx: TenantTx,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<ArAgingQuery>,
+) -> AppResult<Json<ArAgingReport>> {
+    require_role(&claims, "manager")?;
+    let as_of = params
+        .as_of
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let rows: Vec<(Uuid, String, String, Option<NaiveDate>, i64)> = sqlx::query_as(
+        "SELECT c.id, c.name, i.currency, i.due_date, i.total_cents - i.amount_paid_cents \
+         FROM invoices i JOIN clients c ON c.id = i.client_id \
+         WHERE i.tenant_id = $1 AND i.status IN ('sent', 'viewed', 'partial', 'overdue') \
+         AND i.deleted_at IS NULL AND i.total_cents > i.amount_paid_cents \
+         AND (i.issued_date IS NULL OR i.issued_date <= $2) \
+         AND ($3::uuid IS NULL OR i.client_id = $3) \
+         ORDER BY c.name, c.id, i.due_date",
+    )
+    .bind(claims.tid)
+    .bind(as_of)
+    .bind(params.client_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut converter = base_converter(&mut tx, claims.tid, Some(as_of)).await?;
+    let mut clients: Vec<ArAgingClient> = Vec::new();
+    let mut totals = AgingBuckets::default();
+    for (client_id, client_name, currency, due_date, balance) in rows {
+        let amount = converter.convert(balance, &currency, as_of)?;
+        let days_past_due = due_date.map(|due| (as_of - due).num_days());
+        totals.add(days_past_due, amount);
+        let client = match clients.iter_mut().find(|c| c.client_id == client_id) {
+            Some(client) => client,
+            None => {
+                clients.push(ArAgingClient {
+                    client_id,
+                    client_name,
+                    invoice_count: 0,
+                    buckets: AgingBuckets::default(),
+                });
+                clients.last_mut().unwrap()
+            }
+        };
+        client.invoice_count += 1;
+        client.buckets.add(days_past_due, amount);
+    }
+    // Stable, so equal balances stay in name order.
+    clients.sort_by_key(|c| std::cmp::Reverse(c.buckets.total_cents));
+
+    Ok(Json(ArAgingReport {
+        as_of,
+        base_currency: converter.base().code.to_string(),
+        clients,
+        totals,
+        exchange_rates: converter.used(),
     }))
 }
 
 pub async fn get_team_utilization(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     require_role(&claims, "manager")?;
@@ -177,11 +328,9 @@ pub async fn get_team_utilization(
          ORDER BY total_minutes DESC"
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
-    use uuid::Uuid;
-
     let data: Vec<serde_json::Value> = utilization
         .into_iter()
         .map(|(id, first, last, total, billable)| {
@@ -1,2 +1,15 @@
 pub mod handler;
 pub mod model;
+
+use axum::{routing::get, Router};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/reports/pl", get(handler::get_profit_loss))
+        .route("/reports/cashflow", get(handler::get_cash_flow))
+        .route("/reports/ar-aging", get(handler::get_ar_aging))
+        .route("/team/utilization", get(handler::get_team_utilization))
+}
@@ -1,4 +1,8 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::money::model::ExchangeRate;
 
 #[derive(Debug, Deserialize)]
 pub struct ReportQuery {
@@ -8,13 +12,20 @@ pub struct ReportQuery {
     pub client_id: Option<String>,
 }
 
+/// Amounts are in minor units of `base_currency`.
 #[derive(Debug, Serialize)]
 pub struct ProfitLossReport {
     pub period_start: String,
     pub period_end: String,
+    pub base_currency: String,
     pub revenue: ProfitLossSection,
     pub expenses: ProfitLossSection,
+    /// Sales tax on the period's paid invoices, by rate. It is part of the
+    /// revenue figure and owed to the taxing authorities.
+    pub sales_tax_collected: ProfitLossSection,
     pub net_income_cents: i64,
+    /// Rates used to convert other currencies into `base_currency`.
+    pub exchange_rates: Vec<ExchangeRate>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,13 +40,17 @@ pub struct ReportLineItem {
     pub amount_cents: i64,
 }
 
+/// Amounts are in minor units of `base_currency`.
 #[derive(Debug, Serialize)]
 pub struct CashFlowReport {
     pub period_start: String,
     pub period_end: String,
+    pub base_currency: String,
     pub inflows: Vec<CashFlowEntry>,
     pub outflows: Vec<CashFlowEntry>,
     pub net_cash_flow_cents: i64,
+    /// Rates used to convert other currencies into `base_currency`.
+    pub exchange_rates: Vec<ExchangeRate>,
 }
 
 #[derive(Debug, Serialize)]
@@ -43,3 +58,94 @@ pub struct CashFlowEntry {
     pub month: String,
     pub amount_cents: i64,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ArAgingQuery {
+    /// Date invoices are aged to; defaults to today.
+    pub as_of: Option<NaiveDate>,
+    pub client_id: Option<Uuid>,
+}
+
+/// Balances due by days past the invoice due date, in minor units of the
+/// report's base currency.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct AgingBuckets {
+    /// Not yet due, or without a due date.
+    pub current_cents: i64,
+    pub days_0_30_cents: i64,
+    pub days_31_60_cents: i64,
+    pub days_61_90_cents: i64,
+    pub days_over_90_cents: i64,
+    pub total_cents: i64,
+}
+
+impl AgingBuckets {
+    /// Add a balance `days_past_due` days late (negative when not yet due).
+    pub fn add(&mut self, days_past_due: Option<i64>, amount_cents: i64) {
+        let bucket = match days_past_due {
+            None => &mut self.current_cents,
+            Some(d) if d < 0 => &mut self.current_cents,
+            Some(0..=30) => &mut self.days_0_30_cents,
+            Some(31..=60) => &mut self.days_31_60_cents,
+            Some(61..=90) => &mut self.days_61_90_cents,
+            Some(_) => &mut self.days_over_90_cents,
+        };
+        *bucket += amount_cents;
+        self.total_cents += amount_cents;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArAgingClient {
+    pub client_id: Uuid,
+    pub client_name: String,
+    pub invoice_count: i64,
+    #[serde(flatten)]
+    pub buckets: AgingBuckets,
+}
+
+/// Unpaid balances of sent invoices as they stand now, aged to `as_of`.
+/// Amounts are in minor units of `base_currency`.
+#[derive(Debug, Serialize)]
+pub struct ArAgingReport {
+    pub as_of: NaiveDate,
+    pub base_currency: String,
+    /// Largest balance first.
+    pub clients: Vec<ArAgingClient>,
+    pub totals: AgingBuckets,
+    /// Rates used, as of `as_of`, to convert other currencies into
+    /// `base_currency`.
+    pub exchange_rates: Vec<ExchangeRate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aging_buckets_by_days_past_due() {
+        let mut buckets = AgingBuckets::default();
+        for (days, amount) in [
+            (None, 1),
+            (Some(-5), 2),
+            (Some(0), 4),
+            (Some(30), 8),
+            (Some(31), 16),
+            (Some(90), 32),
+            (Some(91), 64),
+        ] {
+            buckets.add(days, amount);
+        }
+        assert_eq!(
+            buckets,
+            AgingBuckets {
+                current_cents: 3,
+                days_0_30_cents: 12,
+                days_31_60_cents: 16,
+                days_61_90_cents: 32,
+                days_over_90_cents: 64,
+                total_cents: 127,
+            }
+        );
+    }
+}
@@ -1,15 +1,11 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Path, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct SavedJob {
@@ -41,7 +37,7 @@ pub struct SaveJobPayload {
 }
 
 pub async fn list_saved_jobs(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
 ) -> AppResult<Json<Vec<SavedJobWithDetails>>> {
     let saved = sqlx::query_as::<_, SavedJobWithDetails>(
@@ -56,7 +52,7 @@ pub async fn list_saved_jobs(
            ORDER BY sj.created_at DESC"#,
     )
     .bind(claims.sub)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -64,7 +60,7 @@ pub async fn list_saved_jobs(
 }
 
 pub async fn save_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Json(payload): Json<SaveJobPayload>,
 ) -> AppResult<(StatusCode, Json<SavedJob>)> {
@@ -76,7 +72,7 @@ pub async fn save_job(
     )
     .bind(claims.sub)
     .bind(payload.job_id)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -84,14 +80,14 @@ pub async fn save_job(
 }
 
 pub async fn unsave_job(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     claims: Claims,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     sqlx::query("DELETE FROM saved_jobs WHERE id = $1 AND user_id = $2")
         .bind(id)
         .bind(claims.sub)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::Database)?;
 
@@ -1 +1,16 @@
 pub mod handler;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/saved-jobs", get(handler::list_saved_jobs))
+        .route("/saved-jobs", post(handler::save_job))
+        .route("/saved-jobs/{id}", delete(handler::unsave_job))
+}
@@ -0,0 +1,163 @@
+//! Durable, Postgres-backed background job runner.
+//!
+//! Jobs live in `background_jobs`. Each job type has a registered handler;
+//! the runner claims due jobs with `FOR UPDATE SKIP LOCKED`, executes them
+//! and retries failures with exponential backoff until `max_attempts`.
+//! Periodic work is declared with [`JobRunner::every`] and enqueued once per
+//! time slot, so multiple API instances never double-schedule it.
+
+pub mod runner;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::AppState;
+
+pub use runner::JobRunner;
+
+/// A claimed job as seen by its handler.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub job_type: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+}
+
+pub type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+pub type JobHandler = Arc<dyn Fn(AppState, Job) -> JobFuture + Send + Sync>;
+
+/// Job types and their handlers.
+#[derive(Clone, Default)]
+pub struct Registry {
+    handlers: HashMap<&'static str, JobHandler>,
+}
+
+impl Registry {
+    pub fn register<F, Fut>(&mut self, job_type: &'static str, handler: F)
+    where
+        F: Fn(AppState, Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.handlers.insert(
+            job_type,
+            Arc::new(move |state, job| Box::pin(handler(state, job))),
+        );
+    }
+
+    pub fn get(&self, job_type: &str) -> Option<&JobHandler> {
+        self.handlers.get(job_type)
+    }
+}
+
+/// Options for an ad-hoc job.
+#[derive(Debug, Default, Clone)]
+pub struct EnqueueOptions {
+    pub tenant_id: Option<Uuid>,
+    pub run_at: Option<DateTime<Utc>>,
+    pub max_attempts: Option<i32>,
+    pub priority: Option<i16>,
+    /// Jobs sharing a key are enqueued at most once.
+    pub unique_key: Option<String>,
+}
+
+/// Enqueue a job. Returns `None` if a job with the same `unique_key` exists.
+pub async fn enqueue(
+    db: impl sqlx::PgExecutor<'_>,
+    job_type: &str,
+    payload: serde_json::Value,
+    opts: EnqueueOptions,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO background_jobs (tenant_id, job_type, payload, run_at, max_attempts, priority, unique_key) \
+         VALUES ($1, $2, $3, COALESCE($4, NOW()), COALESCE($5, 5), COALESCE($6, 0), $7) \
+         ON CONFLICT (unique_key) DO NOTHING RETURNING id",
+    )
+    .bind(opts.tenant_id)
+    .bind(job_type)
+    .bind(payload)
+    .bind(opts.run_at)
+    .bind(opts.max_attempts)
+    .bind(opts.priority)
+    .bind(opts.unique_key)
+    .fetch_optional(db)
+    .await
+}
+
+/// Tenants visited by periodic jobs.
+pub async fn active_tenant_ids(db: &sqlx::PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM tenants WHERE status = 'active' ORDER BY id")
+        .fetch_all(db)
+        .await
+}
+
+/// Begin a transaction with `app.current_tenant` set, so tenant RLS policies
+/// apply to everything run through it.
+pub async fn tenant_tx(
+    db: &sqlx::PgPool,
+    tenant_id: Uuid,
+) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    set_tenant(&mut tx, tenant_id).await?;
+    Ok(tx)
+}
+
+/// Set `app.current_tenant` for the rest of the current transaction.
+pub async fn set_tenant(conn: &mut sqlx::PgConnection, tenant_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT set_config('app.current_tenant', $1, true)")
+        .bind(tenant_id.to_string())
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Delay before retrying after `attempts` failures: 30s, 1m, 2m, 4m... capped at 1 hour.
+pub fn retry_backoff(attempts: i32) -> chrono::Duration {
+    let exp = attempts.clamp(1, 12) - 1;
+    chrono::Duration::seconds((30i64 << exp).min(3600))
+}
+
+/// Unique key for the time slot of a periodic job, so each slot is enqueued once.
+pub fn schedule_slot_key(job_type: &str, every: Duration, now: DateTime<Utc>) -> String {
+    let secs = every.as_secs().max(1) as i64;
+    format!("{}@{}", job_type, now.timestamp().div_euclid(secs) * secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(retry_backoff(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_backoff(3), chrono::Duration::seconds(120));
+        assert_eq!(retry_backoff(20), chrono::Duration::seconds(3600));
+    }
+
+    #[test]
+    fn test_schedule_slot_key_is_stable_within_slot() {
+        let every = Duration::from_secs(3600);
+        let a = Utc.with_ymd_and_hms(2026, 1, 1, 10, 5, 0).unwrap();
+        let b = Utc.with_ymd_and_hms(2026, 1, 1, 10, 55, 0).unwrap();
+        let c = Utc.with_ymd_and_hms(2026, 1, 1, 11, 0, 0).unwrap();
+        assert_eq!(
+            schedule_slot_key("x", every, a),
+            schedule_slot_key("x", every, b)
+        );
+        assert_ne!(
+            schedule_slot_key("x", every, b),
+            schedule_slot_key("x", every, c)
+        );
+    }
+}
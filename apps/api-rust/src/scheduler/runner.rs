@@ -0,0 +1,215 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::task::JoinSet;
+
+use super::{enqueue, retry_backoff, schedule_slot_key, EnqueueOptions, Job, Registry};
+use crate::AppState;
+
+/// Running jobs whose lock is older than this are assumed lost and requeued.
+const STALE_LOCK_MINUTES: i32 = 15;
+/// Succeeded jobs are deleted after this many days. Every schedule slot adds
+/// a row, so without this the table only grows.
+const SUCCEEDED_RETENTION_DAYS: i32 = 7;
+
+pub struct JobRunner {
+    registry: Registry,
+    schedules: Vec<(&'static str, Duration)>,
+    concurrency: i64,
+    poll_interval: Duration,
+    worker_id: String,
+}
+
+impl Default for JobRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobRunner {
+    pub fn new() -> Self {
+        Self {
+            registry: Registry::default(),
+            schedules: Vec::new(),
+            concurrency: 4,
+            poll_interval: Duration::from_secs(2),
+            worker_id: format!("api-{}", uuid::Uuid::new_v4()),
+        }
+    }
+
+    pub fn register<F, Fut>(mut self, job_type: &'static str, handler: F) -> Self
+    where
+        F: Fn(AppState, Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.registry.register(job_type, handler);
+        self
+    }
+
+    /// Enqueue `job_type` once per `every` interval.
+    pub fn every(mut self, job_type: &'static str, every: Duration) -> Self {
+        self.schedules.push((job_type, every));
+        self
+    }
+
+    /// Start the scheduler and worker loops on the Tokio runtime.
+    pub fn spawn(self, state: AppState) {
+        let schedules = self.schedules.clone();
+        let db = state.db.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                for (job_type, every) in &schedules {
+                    let key = schedule_slot_key(job_type, *every, chrono::Utc::now());
+                    let opts = EnqueueOptions {
+                        unique_key: Some(key),
+                        ..Default::default()
+                    };
+                    if let Err(e) = enqueue(&db, job_type, serde_json::json!({}), opts).await {
+                        tracing::error!("Failed to schedule job {}: {}", job_type, e);
+                    }
+                }
+                if let Err(e) = purge_succeeded(&db).await {
+                    tracing::error!("Failed to purge succeeded jobs: {}", e);
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.requeue_stale(&state.db).await {
+                    tracing::error!("Failed to requeue stale jobs: {}", e);
+                }
+                // Keep working while full batches come back
+                loop {
+                    match self.run_batch(&state).await {
+                        Ok(n) if n as i64 == self.concurrency => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            tracing::error!("Job runner failed to claim jobs: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Release jobs whose worker died or hung past [`STALE_LOCK_MINUTES`].
+    /// Each claim counted as an attempt, so jobs that used their last one
+    /// are dead rather than retried.
+    pub async fn requeue_stale(&self, db: &sqlx::PgPool) -> Result<(), sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE background_jobs SET \
+             status = CASE WHEN attempts >= max_attempts THEN 'dead' ELSE 'queued' END, \
+             locked_at = NULL, locked_by = NULL, last_error = 'lock expired', updated_at = NOW() \
+             WHERE status = 'running' AND locked_at < NOW() - make_interval(mins => $1)",
+        )
+        .bind(STALE_LOCK_MINUTES)
+        .execute(db)
+        .await?;
+        if result.rows_affected() > 0 {
+            tracing::warn!("Released {} stale background jobs", result.rows_affected());
+        }
+        Ok(())
+    }
+
+    /// Claim and execute up to `concurrency` due jobs. Returns how many ran.
+    async fn run_batch(&self, state: &AppState) -> Result<usize, sqlx::Error> {
+        let jobs: Vec<Job> = sqlx::query_as(
+            "UPDATE background_jobs SET status = 'running', attempts = attempts + 1, \
+             locked_at = NOW(), locked_by = $2, updated_at = NOW() \
+             WHERE id IN ( \
+                 SELECT id FROM background_jobs \
+                 WHERE status = 'queued' AND run_at <= NOW() \
+                 ORDER BY priority DESC, run_at \
+                 LIMIT $1 \
+                 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING id, tenant_id, job_type, payload, attempts, max_attempts, run_at",
+        )
+        .bind(self.concurrency)
+        .bind(&self.worker_id)
+        .fetch_all(&state.db)
+        .await?;
+
+        let count = jobs.len();
+        let mut set = JoinSet::new();
+        for job in jobs {
+            let handler = self.registry.get(&job.job_type).cloned();
+            let state = state.clone();
+            set.spawn(async move {
+                let result = match handler {
+                    Some(h) => h(state.clone(), job.clone()).await,
+                    None => Err(anyhow::anyhow!(
+                        "No handler registered for '{}'",
+                        job.job_type
+                    )),
+                };
+                if let Err(e) = finish(&state.db, &job, result).await {
+                    tracing::error!(job_id = %job.id, "Failed to record job result: {}", e);
+                }
+            });
+        }
+        while set.join_next().await.is_some() {}
+
+        Ok(count)
+    }
+}
+
+/// Delete succeeded jobs older than [`SUCCEEDED_RETENTION_DAYS`]. Failed and
+/// dead jobs are kept for inspection.
+pub async fn purge_succeeded(db: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM background_jobs \
+         WHERE status = 'succeeded' AND completed_at < NOW() - make_interval(days => $1)",
+    )
+    .bind(SUCCEEDED_RETENTION_DAYS)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Record the outcome of a job run: success, retry with backoff, or dead.
+async fn finish(
+    db: &sqlx::PgPool,
+    job: &Job,
+    result: anyhow::Result<()>,
+) -> Result<(), sqlx::Error> {
+    match result {
+        Ok(()) => {
+            sqlx::query(
+                "UPDATE background_jobs SET status = 'succeeded', completed_at = NOW(), \
+                 locked_at = NULL, last_error = NULL, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(job.id)
+            .execute(db)
+            .await?;
+        }
+        Err(e) => {
+            let dead = job.attempts >= job.max_attempts;
+            tracing::warn!(
+                job_id = %job.id,
+                job_type = %job.job_type,
+                attempts = job.attempts,
+                "Background job failed{}: {:#}",
+                if dead { " permanently" } else { "" },
+                e
+            );
+            sqlx::query(
+                "UPDATE background_jobs SET status = $2, last_error = $3, locked_at = NULL, \
+                 run_at = NOW() + $4, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(job.id)
+            .bind(if dead { "dead" } else { "queued" })
+            .bind(format!("{:#}", e))
+            .bind(retry_backoff(job.attempts))
+            .execute(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -7,11 +7,11 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::scorecards::model::*;
-use crate::AppState;
 
 pub async fn list_scorecards(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListScorecardsQuery>,
 ) -> AppResult<Json<Vec<Scorecard>>> {
@@ -32,14 +32,14 @@ pub async fn list_scorecards(
     .bind(claims.tid)
     .bind(&application_id_filter)
     .bind(&job_id_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(scorecards))
 }
 
 pub async fn create_scorecard(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateScorecardRequest>,
 ) -> AppResult<(StatusCode, Json<Scorecard>)> {
@@ -73,7 +73,7 @@ pub async fn create_scorecard(
     let _: (Uuid,) = sqlx::query_as("SELECT id FROM applications WHERE id = $1 AND tenant_id = $2")
         .bind(payload.application_id)
         .bind(claims.tid)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
@@ -96,14 +96,14 @@ pub async fn create_scorecard(
     .bind(&payload.concerns)
     .bind(&payload.notes)
     .bind(&criteria_scores)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(scorecard)))
 }
 
 pub async fn get_scorecard(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<Scorecard>> {
@@ -111,7 +111,7 @@ pub async fn get_scorecard(
         sqlx::query_as("SELECT * FROM scorecards WHERE id = $1 AND tenant_id = $2")
             .bind(id)
             .bind(claims.tid)
-            .fetch_optional(&state.db)
+            .fetch_optional(&mut *tx)
             .await?
             .ok_or_else(|| AppError::NotFound("Scorecard not found".to_string()))?;
 
@@ -119,7 +119,7 @@ pub async fn get_scorecard(
 }
 
 pub async fn update_scorecard(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateScorecardRequest>,
@@ -172,7 +172,7 @@ pub async fn update_scorecard(
     .bind(&payload.concerns)
     .bind(&payload.notes)
     .bind(&payload.criteria_scores)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Scorecard not found".to_string()))?;
 
@@ -180,14 +180,14 @@ pub async fn update_scorecard(
 }
 
 pub async fn delete_scorecard(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
     let result = sqlx::query("DELETE FROM scorecards WHERE id = $1 AND tenant_id = $2")
         .bind(id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -198,7 +198,7 @@ pub async fn delete_scorecard(
 }
 
 pub async fn get_scorecard_summary(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(application_id): Path<Uuid>,
 ) -> AppResult<Json<ScorecardSummary>> {
@@ -206,7 +206,7 @@ pub async fn get_scorecard_summary(
     let _: (Uuid,) = sqlx::query_as("SELECT id FROM applications WHERE id = $1 AND tenant_id = $2")
         .bind(application_id)
         .bind(claims.tid)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
@@ -215,7 +215,7 @@ pub async fn get_scorecard_summary(
     )
     .bind(application_id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     let (average_score,): (Option<f64>,) = sqlx::query_as(
@@ -224,42 +224,42 @@ pub async fn get_scorecard_summary(
     )
     .bind(application_id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Score breakdown
     let (s1,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND overall_score = 1"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (s2,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND overall_score = 2"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (s3,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND overall_score = 3"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (s4,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND overall_score = 4"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (s5,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND overall_score = 5"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
 
     // Recommendation counts
     let (strong_hire,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND recommendation = 'strong_hire'"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (hire,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND recommendation = 'hire'"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (neutral,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND recommendation = 'neutral'"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (no_hire,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND recommendation = 'no_hire'"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
     let (strong_no_hire,): (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM scorecards WHERE application_id = $1 AND tenant_id = $2 AND recommendation = 'strong_no_hire'"
-    ).bind(application_id).bind(claims.tid).fetch_one(&state.db).await?;
+    ).bind(application_id).bind(claims.tid).fetch_one(&mut *tx).await?;
 
     Ok(Json(ScorecardSummary {
         application_id,
@@ -283,7 +283,7 @@ pub async fn get_scorecard_summary(
 }
 
 pub async fn create_decision_record(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateDecisionRecordRequest>,
 ) -> AppResult<(StatusCode, Json<DecisionRecord>)> {
@@ -300,7 +300,7 @@ pub async fn create_decision_record(
     let _: (Uuid,) = sqlx::query_as("SELECT id FROM applications WHERE id = $1 AND tenant_id = $2")
         .bind(payload.application_id)
         .bind(claims.tid)
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Application not found".to_string()))?;
 
@@ -318,14 +318,14 @@ pub async fn create_decision_record(
     .bind(claims.sub)
     .bind(&payload.rationale)
     .bind(&evidence_refs)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(record)))
 }
 
 pub async fn list_decision_records(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListDecisionRecordsQuery>,
 ) -> AppResult<Json<Vec<DecisionRecord>>> {
@@ -342,7 +342,7 @@ pub async fn list_decision_records(
     )
     .bind(claims.tid)
     .bind(&application_id_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(records))
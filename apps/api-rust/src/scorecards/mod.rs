@@ -1,2 +1,28 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::subscriptions::entitlements::require_feature;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/scorecards", get(handler::list_scorecards))
+        .route("/scorecards", post(handler::create_scorecard))
+        .route(
+            "/scorecards/summary/{application_id}",
+            get(handler::get_scorecard_summary),
+        )
+        .route("/scorecards/{id}", get(handler::get_scorecard))
+        .route("/scorecards/{id}", put(handler::update_scorecard))
+        .route("/scorecards/{id}", delete(handler::delete_scorecard))
+        // Decision Records
+        .route("/decision-records", get(handler::list_decision_records))
+        .route("/decision-records", post(handler::create_decision_record))
+        .route_layer(require_feature("scorecards"))
+}
@@ -0,0 +1,148 @@
+//! Building [`SearchDocument`]s from the tables they index.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{Entity, SearchDocument};
+
+#[derive(sqlx::FromRow)]
+struct DocumentRow {
+    entity_id: Uuid,
+    title: String,
+    subtitle: Option<String>,
+    body: String,
+    skills: Vec<String>,
+    location: Option<String>,
+    stage: Option<String>,
+    owner_id: Option<Uuid>,
+    url: String,
+    updated_at: DateTime<Utc>,
+}
+
+/// One query per entity. `$1` is the tenant, `$2` optionally restricts to
+/// a set of ids. Rows that are soft-deleted are left out, so they drop out of
+/// the index.
+fn load_sql(entity: Entity) -> &'static str {
+    match entity {
+        Entity::Client => {
+            "SELECT c.id AS entity_id, c.name::TEXT AS title, c.business_type::TEXT AS subtitle, \
+             concat_ws(' ', c.business_name, c.email, array_to_string(c.tags, ' '), \
+               (SELECT string_agg(concat_ws(' ', cc.first_name, cc.last_name, cc.email), ' ') \
+                FROM client_contacts cc WHERE cc.client_id = c.id)) AS body, \
+             '{}'::TEXT[] AS skills, NULL::TEXT AS location, NULL::TEXT AS stage, \
+             NULL::UUID AS owner_id, '/clients/' || c.id AS url, c.updated_at \
+             FROM clients c \
+             WHERE c.tenant_id = $1 AND c.deleted_at IS NULL \
+               AND ($2::UUID[] IS NULL OR c.id = ANY($2))"
+        }
+        Entity::Document => {
+            "SELECT d.id AS entity_id, d.filename::TEXT AS title, d.category::TEXT AS subtitle, \
+             concat_ws(' ', d.category, c.name) AS body, \
+             '{}'::TEXT[] AS skills, NULL::TEXT AS location, NULL::TEXT AS stage, \
+             NULL::UUID AS owner_id, '/documents/' || d.id AS url, d.updated_at \
+             FROM documents d LEFT JOIN clients c ON c.id = d.client_id \
+             WHERE d.tenant_id = $1 AND d.deleted_at IS NULL \
+               AND ($2::UUID[] IS NULL OR d.id = ANY($2))"
+        }
+        Entity::Invoice => {
+            "SELECT i.id AS entity_id, i.invoice_number::TEXT AS title, \
+             i.status || ' - $' || to_char(i.total_cents / 100.0, 'FM999999990.00') AS subtitle, \
+             concat_ws(' ', i.notes, c.name) AS body, \
+             '{}'::TEXT[] AS skills, NULL::TEXT AS location, NULL::TEXT AS stage, \
+             NULL::UUID AS owner_id, '/invoices/' || i.id AS url, i.updated_at \
+             FROM invoices i LEFT JOIN clients c ON c.id = i.client_id \
+             WHERE i.tenant_id = $1 AND i.deleted_at IS NULL \
+               AND ($2::UUID[] IS NULL OR i.id = ANY($2))"
+        }
+        Entity::Candidate => {
+            "SELECT cp.id AS entity_id, \
+             COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), cp.headline, 'Candidate') AS title, \
+             cp.headline::TEXT AS subtitle, \
+             concat_ws(' ', cp.summary, u.email, array_to_string(sk.skills, ' ')) AS body, \
+             COALESCE(sk.skills, '{}') AS skills, \
+             NULLIF(concat_ws(', ', cp.location_city, cp.location_state), '') AS location, \
+             NULL::TEXT AS stage, NULL::UUID AS owner_id, '/talent/' || cp.id AS url, cp.updated_at \
+             FROM candidate_profiles cp \
+             LEFT JOIN users u ON u.id = cp.user_id \
+             LEFT JOIN LATERAL (SELECT array_agg(s.skill_name::TEXT ORDER BY s.skill_name) AS skills \
+               FROM candidate_skills s WHERE s.candidate_id = cp.id) sk ON true \
+             WHERE cp.tenant_id = $1 AND ($2::UUID[] IS NULL OR cp.id = ANY($2))"
+        }
+        Entity::Job => {
+            "SELECT j.id AS entity_id, j.title::TEXT AS title, j.department::TEXT AS subtitle, \
+             concat_ws(' ', j.description, j.requirements, j.responsibilities) AS body, \
+             (COALESCE(j.skills_required, '{}') || COALESCE(j.skills_preferred, '{}'))::TEXT[] AS skills, \
+             NULLIF(concat_ws(', ', j.location_city, j.location_state), '') AS location, \
+             NULL::TEXT AS stage, NULL::UUID AS owner_id, '/hiring/jobs/' || j.id AS url, j.updated_at \
+             FROM job_posts j \
+             WHERE j.tenant_id = $1 AND ($2::UUID[] IS NULL OR j.id = ANY($2))"
+        }
+        Entity::Application => {
+            "SELECT a.id AS entity_id, \
+             COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), cp.headline, 'Candidate') AS title, \
+             j.title::TEXT AS subtitle, \
+             concat_ws(' ', a.cover_letter, a.source, array_to_string(sk.skills, ' ')) AS body, \
+             COALESCE(sk.skills, '{}') AS skills, \
+             NULLIF(concat_ws(', ', j.location_city, j.location_state), '') AS location, \
+             a.stage::TEXT AS stage, NULL::UUID AS owner_id, '/hiring/evaluate/' || a.id AS url, a.updated_at \
+             FROM applications a \
+             JOIN job_posts j ON j.id = a.job_id \
+             JOIN candidate_profiles cp ON cp.id = a.candidate_id \
+             LEFT JOIN users u ON u.id = cp.user_id \
+             LEFT JOIN LATERAL (SELECT array_agg(s.skill_name::TEXT ORDER BY s.skill_name) AS skills \
+               FROM candidate_skills s WHERE s.candidate_id = cp.id) sk ON true \
+             WHERE a.tenant_id = $1 AND ($2::UUID[] IS NULL OR a.id = ANY($2))"
+        }
+        Entity::Note => {
+            "SELECT n.id AS entity_id, \
+             'Note on ' || COALESCE(NULLIF(TRIM(concat_ws(' ', u.first_name, u.last_name)), ''), cp.headline, 'candidate') AS title, \
+             n.note_type::TEXT AS subtitle, n.content AS body, \
+             '{}'::TEXT[] AS skills, NULL::TEXT AS location, NULL::TEXT AS stage, \
+             CASE WHEN n.is_private THEN n.author_id END AS owner_id, \
+             '/talent/' || n.candidate_id AS url, n.updated_at \
+             FROM candidate_notes n \
+             JOIN candidate_profiles cp ON cp.id = n.candidate_id \
+             LEFT JOIN users u ON u.id = cp.user_id \
+             WHERE n.tenant_id = $1 AND ($2::UUID[] IS NULL OR n.id = ANY($2))"
+        }
+    }
+}
+
+/// Load the documents for `entity` in a tenant, all of them or just `ids`.
+/// Ids without a document were deleted.
+pub async fn load(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    entity: Entity,
+    ids: Option<&[Uuid]>,
+) -> Result<Vec<SearchDocument>, sqlx::Error> {
+    let rows: Vec<DocumentRow> = sqlx::query_as(load_sql(entity))
+        .bind(tenant_id)
+        .bind(ids)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let mut skills = row.skills;
+            skills.sort();
+            skills.dedup();
+            SearchDocument {
+                tenant_id,
+                entity,
+                entity_id: row.entity_id,
+                title: row.title,
+                subtitle: row.subtitle.filter(|s| !s.is_empty()),
+                body: row.body,
+                skills,
+                location: row.location,
+                stage: row.stage,
+                owner_id: row.owner_id,
+                url: row.url,
+                updated_at: row.updated_at,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,106 @@
+//! Keeping the search engine in step with the database.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use uuid::Uuid;
+
+use super::{documents, Entity};
+use crate::scheduler::{active_tenant_ids, set_tenant, tenant_tx, Job};
+use crate::AppState;
+
+pub const SYNC_JOB_TYPE: &str = "search.sync";
+pub const REINDEX_JOB_TYPE: &str = "search.reindex";
+
+/// Outbox rows, and documents per upsert, handled at a time.
+const BATCH_SIZE: usize = 500;
+
+/// Push queued row changes to the search engine until the outbox is empty.
+pub async fn sync(state: AppState, _job: Job) -> anyhow::Result<()> {
+    while sync_batch(&state).await? == BATCH_SIZE {}
+    Ok(())
+}
+
+/// Index one batch of queued changes; returns how many outbox rows it took.
+/// The batch is removed from the outbox only once the engine has it, so a
+/// failure leaves it queued for the next run.
+pub async fn sync_batch(state: &AppState) -> anyhow::Result<usize> {
+    let mut tx = state.db.begin().await?;
+    let queued: Vec<(Uuid, Uuid, String, Uuid)> = sqlx::query_as(
+        "SELECT id, tenant_id, entity, entity_id FROM search_outbox \
+         ORDER BY created_at LIMIT $1 FOR UPDATE SKIP LOCKED",
+    )
+    .bind(BATCH_SIZE as i64)
+    .fetch_all(&mut *tx)
+    .await?;
+    if queued.is_empty() {
+        return Ok(0);
+    }
+
+    let mut changed: BTreeMap<(Uuid, Entity), BTreeSet<Uuid>> = BTreeMap::new();
+    for (_, tenant_id, entity, entity_id) in &queued {
+        match Entity::parse(entity) {
+            Some(entity) => {
+                changed
+                    .entry((*tenant_id, entity))
+                    .or_default()
+                    .insert(*entity_id);
+            }
+            None => tracing::warn!(entity = %entity, "Unknown entity in search outbox"),
+        }
+    }
+
+    for ((tenant_id, entity), ids) in changed {
+        set_tenant(&mut tx, tenant_id).await?;
+        let ids: Vec<Uuid> = ids.into_iter().collect();
+        let docs = documents::load(&mut tx, tenant_id, entity, Some(&ids)).await?;
+        let gone: Vec<Uuid> = ids
+            .into_iter()
+            .filter(|id| !docs.iter().any(|d| d.entity_id == *id))
+            .collect();
+        state.search.upsert(&mut tx, &docs).await?;
+        state
+            .search
+            .delete(&mut tx, tenant_id, entity, &gone)
+            .await?;
+    }
+
+    let ids: Vec<Uuid> = queued.iter().map(|(id, ..)| *id).collect();
+    sqlx::query("DELETE FROM search_outbox WHERE id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(queued.len())
+}
+
+/// Rebuild the index for one tenant, or every active tenant. Returns the
+/// number of documents indexed.
+pub async fn reindex(state: &AppState, tenant_id: Option<Uuid>) -> anyhow::Result<usize> {
+    let tenants = match tenant_id {
+        Some(tenant_id) => vec![tenant_id],
+        None => active_tenant_ids(&state.db).await?,
+    };
+
+    let mut indexed = 0;
+    for tenant_id in tenants {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+        state.search.clear(&mut tx, tenant_id).await?;
+        for entity in Entity::ALL {
+            let docs = documents::load(&mut tx, tenant_id, entity, None).await?;
+            for chunk in docs.chunks(BATCH_SIZE) {
+                state.search.upsert(&mut tx, chunk).await?;
+            }
+            indexed += docs.len();
+        }
+        tx.commit().await?;
+        tracing::info!(%tenant_id, "Search index rebuilt");
+    }
+    Ok(indexed)
+}
+
+/// Job form of [`reindex`], for the job's tenant or all tenants.
+pub async fn run_reindex(state: AppState, job: Job) -> anyhow::Result<()> {
+    let indexed = reindex(&state, job.tenant_id).await?;
+    tracing::info!(indexed, "Search reindex complete");
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use super::{
+    sort_facets, Entity, FacetCount, SearchDocument, SearchHit, SearchPage, SearchRequest,
+    HIGHLIGHT_END, HIGHLIGHT_START,
+};
+
+/// Keeps documents in memory and searches them the way Typesense does: every
+/// query word must match a word of the document as a prefix or within the
+/// typo budget for its length. Used by tests as a stand-in for Typesense.
+#[derive(Clone, Default)]
+pub struct MemoryEngine {
+    docs: Arc<Mutex<HashMap<(Entity, Uuid), SearchDocument>>>,
+}
+
+impl MemoryEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn upsert(&self, docs: &[SearchDocument]) {
+        let mut stored = self.docs.lock().unwrap();
+        for doc in docs {
+            stored.insert((doc.entity, doc.entity_id), doc.clone());
+        }
+    }
+
+    pub fn delete(&self, tenant_id: Uuid, entity: Entity, ids: &[Uuid]) {
+        let mut stored = self.docs.lock().unwrap();
+        for id in ids {
+            if stored
+                .get(&(entity, *id))
+                .is_some_and(|d| d.tenant_id == tenant_id)
+            {
+                stored.remove(&(entity, *id));
+            }
+        }
+    }
+
+    pub fn clear(&self, tenant_id: Uuid) {
+        self.docs
+            .lock()
+            .unwrap()
+            .retain(|_, d| d.tenant_id != tenant_id);
+    }
+
+    /// Snapshot of a tenant's indexed documents.
+    pub fn documents(&self, tenant_id: Uuid) -> Vec<SearchDocument> {
+        self.docs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|d| d.tenant_id == tenant_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn search(&self, request: &SearchRequest) -> SearchPage {
+        let terms = words(&request.q);
+        if terms.is_empty() {
+            return SearchPage::default();
+        }
+
+        let mut matched: Vec<(u32, SearchDocument)> = self
+            .docs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|d| request.filters_match(d))
+            .filter_map(|d| score(d, &terms).map(|s| (s, d.clone())))
+            .collect();
+        matched
+            .sort_by(|(a, da), (b, db)| b.cmp(a).then_with(|| db.updated_at.cmp(&da.updated_at)));
+
+        let mut facets: BTreeMap<String, BTreeMap<String, i64>> = BTreeMap::new();
+        for (_, doc) in &matched {
+            let mut count = |field: &str, value: &str| {
+                *facets
+                    .entry(field.to_string())
+                    .or_default()
+                    .entry(value.to_string())
+                    .or_default() += 1;
+            };
+            count("entity", doc.entity.as_str());
+            for skill in &doc.skills {
+                count("skills", skill);
+            }
+            if let Some(location) = &doc.location {
+                count("location", location);
+            }
+            if let Some(stage) = &doc.stage {
+                count("stage", stage);
+            }
+        }
+        let mut facets = facets
+            .into_iter()
+            .map(|(field, counts)| {
+                let counts = counts
+                    .into_iter()
+                    .map(|(value, count)| FacetCount { value, count })
+                    .collect();
+                (field, counts)
+            })
+            .collect();
+        sort_facets(&mut facets);
+
+        let found = matched.len() as i64;
+        let hits = matched
+            .into_iter()
+            .skip(request.offset().max(0) as usize)
+            .take(request.per_page.max(0) as usize)
+            .map(|(_, document)| {
+                let highlight = [
+                    Some(&document.title),
+                    document.subtitle.as_ref(),
+                    Some(&document.body),
+                ]
+                .into_iter()
+                .flatten()
+                .find_map(|text| highlight(text, &terms));
+                SearchHit {
+                    document,
+                    highlight,
+                }
+            })
+            .collect();
+
+        SearchPage {
+            hits,
+            found,
+            facets,
+        }
+    }
+}
+
+/// Lowercased alphanumeric words of `text`.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Typos allowed for a query word, following Typesense's defaults: none below
+/// four characters, one below seven, two after that.
+fn typo_budget(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+fn word_matches(term: &str, word: &str) -> bool {
+    word.starts_with(term) || edit_distance(term, word) <= typo_budget(term)
+}
+
+/// Relevance of `doc` for `terms`, or `None` unless every term matches. Each
+/// term counts for the most important field it appears in.
+fn score(doc: &SearchDocument, terms: &[String]) -> Option<u32> {
+    let fields: [(u32, Vec<String>); 4] = [
+        (8, words(&doc.title)),
+        (4, words(doc.subtitle.as_deref().unwrap_or_default())),
+        (4, words(&doc.skills.join(" "))),
+        (1, words(&doc.body)),
+    ];
+    terms.iter().try_fold(0, |total, term| {
+        fields
+            .iter()
+            .filter(|(_, words)| words.iter().any(|w| word_matches(term, w)))
+            .map(|(weight, _)| *weight)
+            .max()
+            .map(|weight| total + weight)
+    })
+}
+
+/// `text` with every word matching one of `terms` wrapped in highlight tags,
+/// or `None` if nothing matched.
+fn highlight(text: &str, terms: &[String]) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut marked = false;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| c.is_alphanumeric() != rest.starts_with(char::is_alphanumeric))
+            .unwrap_or(rest.len());
+        let (piece, tail) = rest.split_at(split);
+        let lower = piece.to_lowercase();
+        if piece.starts_with(char::is_alphanumeric) && terms.iter().any(|t| word_matches(t, &lower))
+        {
+            out.push_str(HIGHLIGHT_START);
+            out.push_str(piece);
+            out.push_str(HIGHLIGHT_END);
+            marked = true;
+        } else {
+            out.push_str(piece);
+        }
+        rest = tail;
+    }
+    marked.then_some(out)
+}
+
+/// Edit distance counting a swap of adjacent characters as one edit (optimal
+/// string alignment), as Typesense does.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(tenant_id: Uuid, entity: Entity, title: &str, skills: &[&str]) -> SearchDocument {
+        SearchDocument {
+            tenant_id,
+            entity,
+            entity_id: Uuid::new_v4(),
+            title: title.to_string(),
+            subtitle: None,
+            body: String::new(),
+            skills: skills.iter().map(|s| s.to_string()).collect(),
+            location: Some("Austin, TX".to_string()),
+            stage: None,
+            owner_id: None,
+            url: "/".to_string(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn request(tenant_id: Uuid, q: &str) -> SearchRequest {
+        SearchRequest {
+            tenant_id,
+            user_id: Uuid::new_v4(),
+            q: q.to_string(),
+            entities: vec![],
+            skills: vec![],
+            location: None,
+            stage: None,
+            page: 1,
+            per_page: 20,
+        }
+    }
+
+    #[test]
+    fn test_typos_prefixes_and_highlights() {
+        let tenant = Uuid::new_v4();
+        let engine = MemoryEngine::new();
+        engine.upsert(&[
+            doc(
+                tenant,
+                Entity::Candidate,
+                "Ada Lovelace",
+                &["Python", "Rust"],
+            ),
+            doc(tenant, Entity::Job, "Rust Engineer", &["Rust"]),
+            doc(Uuid::new_v4(), Entity::Job, "Rust Engineer", &["Rust"]),
+        ]);
+
+        let page = engine.search(&request(tenant, "pyhton"));
+        assert_eq!(page.found, 1);
+        assert_eq!(page.hits[0].document.title, "Ada Lovelace");
+
+        let page = engine.search(&request(tenant, "engin"));
+        assert_eq!(page.found, 1);
+        assert_eq!(
+            page.hits[0].highlight.as_deref(),
+            Some("Rust <mark>Engineer</mark>")
+        );
+
+        let page = engine.search(&request(tenant, "rust"));
+        assert_eq!(page.found, 2);
+        // The title match ranks above the skill match.
+        assert_eq!(page.hits[0].document.entity, Entity::Job);
+        assert_eq!(
+            page.facets["skills"],
+            vec![
+                FacetCount {
+                    value: "Rust".to_string(),
+                    count: 2
+                },
+                FacetCount {
+                    value: "Python".to_string(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_short_words_allow_no_typos() {
+        assert!(word_matches("sql", "sqlite"));
+        assert!(!word_matches("sql", "sal"));
+        assert!(word_matches("jonh", "john"));
+        assert!(!word_matches("jonh", "jane"));
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("pyhton", "python"), 1);
+    }
+
+    #[test]
+    fn test_private_documents_are_only_visible_to_their_owner() {
+        let tenant = Uuid::new_v4();
+        let engine = MemoryEngine::new();
+        let mut note = doc(tenant, Entity::Note, "Note on Ada", &[]);
+        let owner = Uuid::new_v4();
+        note.owner_id = Some(owner);
+        engine.upsert(&[note]);
+
+        assert_eq!(engine.search(&request(tenant, "ada")).found, 0);
+        let mut req = request(tenant, "ada");
+        req.user_id = owner;
+        assert_eq!(engine.search(&req).found, 1);
+    }
+}
@@ -1 +1,279 @@
+//! Global search.
+//!
+//! Searchable rows are flattened into [`SearchDocument`]s and kept in a search
+//! engine: Typesense when it is configured, otherwise the `search_documents`
+//! table searched with Postgres full-text search. Row changes are queued by
+//! database triggers in `search_outbox` and pushed to the engine by the
+//! [`indexer`] sync job; [`indexer::reindex`] rebuilds a tenant from scratch.
+
+pub mod documents;
 pub mod handler;
+pub mod indexer;
+pub mod memory;
+pub mod postgres;
+pub mod typesense;
+
+use std::collections::BTreeMap;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::AppState;
+
+/// Fields results can be faceted on.
+pub const FACET_FIELDS: [&str; 4] = ["entity", "skills", "location", "stage"];
+
+/// Highlighted terms are wrapped in these tags by every engine.
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Client,
+    Document,
+    Invoice,
+    Candidate,
+    Job,
+    Application,
+    Note,
+}
+
+impl Entity {
+    pub const ALL: [Entity; 7] = [
+        Entity::Client,
+        Entity::Document,
+        Entity::Invoice,
+        Entity::Candidate,
+        Entity::Job,
+        Entity::Application,
+        Entity::Note,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entity::Client => "client",
+            Entity::Document => "document",
+            Entity::Invoice => "invoice",
+            Entity::Candidate => "candidate",
+            Entity::Job => "job",
+            Entity::Application => "application",
+            Entity::Note => "note",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Entity::ALL.into_iter().find(|e| e.as_str() == s)
+    }
+
+    /// The plural used by the `type` query parameter.
+    pub fn plural(self) -> &'static str {
+        match self {
+            Entity::Client => "clients",
+            Entity::Document => "documents",
+            Entity::Invoice => "invoices",
+            Entity::Candidate => "candidates",
+            Entity::Job => "jobs",
+            Entity::Application => "applications",
+            Entity::Note => "notes",
+        }
+    }
+}
+
+/// One searchable record, as stored in the engine.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchDocument {
+    pub tenant_id: Uuid,
+    pub entity: Entity,
+    pub entity_id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub body: String,
+    pub skills: Vec<String>,
+    pub location: Option<String>,
+    pub stage: Option<String>,
+    /// Restricts the document to a single user (private notes).
+    pub owner_id: Option<Uuid>,
+    pub url: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SearchDocument {
+    fn visible_to(&self, user_id: Uuid) -> bool {
+        self.owner_id.map_or(true, |owner| owner == user_id)
+    }
+}
+
+/// A search within one tenant, as seen by one user.
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub tenant_id: Uuid,
+    pub user_id: Uuid,
+    pub q: String,
+    /// Empty searches every entity.
+    pub entities: Vec<Entity>,
+    /// Documents must carry all of these skills.
+    pub skills: Vec<String>,
+    pub location: Option<String>,
+    pub stage: Option<String>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl SearchRequest {
+    fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+
+    /// Whether `doc` passes every filter (but not necessarily the query).
+    fn filters_match(&self, doc: &SearchDocument) -> bool {
+        doc.tenant_id == self.tenant_id
+            && doc.visible_to(self.user_id)
+            && (self.entities.is_empty() || self.entities.contains(&doc.entity))
+            && self.skills.iter().all(|s| doc.skills.contains(s))
+            && self
+                .location
+                .as_ref()
+                .map_or(true, |l| doc.location.as_deref() == Some(l.as_str()))
+            && self
+                .stage
+                .as_ref()
+                .map_or(true, |s| doc.stage.as_deref() == Some(s.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub document: SearchDocument,
+    /// A snippet with the matched terms wrapped in `<mark>`.
+    pub highlight: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    /// Matches across all pages.
+    pub found: i64,
+    /// Counts per value of each of [`FACET_FIELDS`], over all matches.
+    pub facets: BTreeMap<String, Vec<FacetCount>>,
+}
+
+/// Sort facet counts the way every engine reports them: most frequent first.
+fn sort_facets(facets: &mut BTreeMap<String, Vec<FacetCount>>) {
+    for counts in facets.values_mut() {
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    }
+}
+
+/// The configured search engine. `memory` exists for tests; `postgres` is the
+/// fallback when Typesense is not configured.
+#[derive(Clone)]
+pub enum SearchEngine {
+    Typesense(typesense::TypesenseEngine),
+    Postgres(postgres::PostgresEngine),
+    Memory(memory::MemoryEngine),
+}
+
+impl SearchEngine {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let backend = match config.search_backend.as_deref() {
+            Some(backend) => backend,
+            None if config.typesense_url.is_some() => "typesense",
+            None => "postgres",
+        };
+        match backend {
+            "typesense" => Ok(SearchEngine::Typesense(
+                typesense::TypesenseEngine::from_config(config)?,
+            )),
+            "postgres" => Ok(SearchEngine::Postgres(postgres::PostgresEngine)),
+            "memory" => Ok(SearchEngine::Memory(memory::MemoryEngine::new())),
+            other => anyhow::bail!(
+                "Unknown SEARCH_BACKEND '{}'. Must be one of: typesense, postgres, memory",
+                other
+            ),
+        }
+    }
+
+    /// Insert or replace documents. `conn` must be in the documents' tenant.
+    pub async fn upsert(
+        &self,
+        conn: &mut PgConnection,
+        docs: &[SearchDocument],
+    ) -> anyhow::Result<()> {
+        if docs.is_empty() {
+            return Ok(());
+        }
+        match self {
+            SearchEngine::Typesense(e) => e.upsert(docs).await,
+            SearchEngine::Postgres(e) => e.upsert(conn, docs).await,
+            SearchEngine::Memory(e) => {
+                e.upsert(docs);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn delete(
+        &self,
+        conn: &mut PgConnection,
+        tenant_id: Uuid,
+        entity: Entity,
+        ids: &[Uuid],
+    ) -> anyhow::Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        match self {
+            SearchEngine::Typesense(e) => e.delete(tenant_id, entity, ids).await,
+            SearchEngine::Postgres(e) => e.delete(conn, entity, ids).await,
+            SearchEngine::Memory(e) => {
+                e.delete(tenant_id, entity, ids);
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove every document of a tenant, ahead of a reindex.
+    pub async fn clear(&self, conn: &mut PgConnection, tenant_id: Uuid) -> anyhow::Result<()> {
+        match self {
+            SearchEngine::Typesense(e) => e.clear(tenant_id).await,
+            SearchEngine::Postgres(e) => e.clear(conn, tenant_id).await,
+            SearchEngine::Memory(e) => {
+                e.clear(tenant_id);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn search(
+        &self,
+        conn: &mut PgConnection,
+        request: &SearchRequest,
+    ) -> anyhow::Result<SearchPage> {
+        match self {
+            SearchEngine::Typesense(e) => e.search(request).await,
+            SearchEngine::Postgres(e) => e.search(conn, request).await,
+            SearchEngine::Memory(e) => Ok(e.search(request)),
+        }
+    }
+}
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/search", get(handler::global_search))
+        .route("/search/reindex", post(handler::reindex))
+}
@@ -0,0 +1,240 @@
+//! Postgres full-text search over `search_documents`, used when Typesense is
+//! not configured. Matching uses the stored `search_vector` with prefix terms;
+//! trigram word similarity against titles and skills tolerates typos.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{
+    sort_facets, Entity, FacetCount, SearchDocument, SearchHit, SearchPage, SearchRequest,
+};
+
+/// Minimum `word_similarity` for a fuzzy match on a title or skill.
+const FUZZY_THRESHOLD: f32 = 0.4;
+
+/// Rows matching the request. Parameters: `$1` tenant, `$2` user, `$3`
+/// tsquery, `$4` entities, `$5` required skills, `$6` location, `$7` stage,
+/// `$8` raw query text, `$9` fuzzy threshold.
+const MATCHED: &str = "SELECT d.*, query, \
+     ts_rank(d.search_vector, query) \
+       + word_similarity($8, d.title || ' ' || array_to_string(d.skills, ' ')) AS score \
+     FROM search_documents d, to_tsquery('english', $3) AS query \
+     WHERE d.tenant_id = $1 AND (d.owner_id IS NULL OR d.owner_id = $2) \
+       AND (cardinality($4::TEXT[]) = 0 OR d.entity = ANY($4)) \
+       AND d.skills @> $5::TEXT[] \
+       AND ($6::TEXT IS NULL OR d.location = $6) \
+       AND ($7::TEXT IS NULL OR d.stage = $7) \
+       AND (d.search_vector @@ query \
+         OR word_similarity($8, d.title || ' ' || array_to_string(d.skills, ' ')) >= $9)";
+
+#[derive(sqlx::FromRow)]
+struct HitRow {
+    tenant_id: Uuid,
+    entity: String,
+    entity_id: Uuid,
+    title: String,
+    subtitle: Option<String>,
+    body: String,
+    skills: Vec<String>,
+    location: Option<String>,
+    stage: Option<String>,
+    owner_id: Option<Uuid>,
+    url: String,
+    updated_at: DateTime<Utc>,
+    title_highlight: String,
+    body_highlight: String,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct PostgresEngine;
+
+impl PostgresEngine {
+    pub async fn upsert(
+        &self,
+        conn: &mut PgConnection,
+        docs: &[SearchDocument],
+    ) -> anyhow::Result<()> {
+        for doc in docs {
+            sqlx::query(
+                "INSERT INTO search_documents \
+                 (tenant_id, entity, entity_id, title, subtitle, body, skills, location, stage, \
+                  owner_id, url, updated_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) \
+                 ON CONFLICT (entity, entity_id) DO UPDATE SET \
+                 title = EXCLUDED.title, subtitle = EXCLUDED.subtitle, body = EXCLUDED.body, \
+                 skills = EXCLUDED.skills, location = EXCLUDED.location, stage = EXCLUDED.stage, \
+                 owner_id = EXCLUDED.owner_id, url = EXCLUDED.url, updated_at = EXCLUDED.updated_at",
+            )
+            .bind(doc.tenant_id)
+            .bind(doc.entity.as_str())
+            .bind(doc.entity_id)
+            .bind(&doc.title)
+            .bind(&doc.subtitle)
+            .bind(&doc.body)
+            .bind(&doc.skills)
+            .bind(&doc.location)
+            .bind(&doc.stage)
+            .bind(doc.owner_id)
+            .bind(&doc.url)
+            .bind(doc.updated_at)
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn delete(
+        &self,
+        conn: &mut PgConnection,
+        entity: Entity,
+        ids: &[Uuid],
+    ) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM search_documents WHERE entity = $1 AND entity_id = ANY($2)")
+            .bind(entity.as_str())
+            .bind(ids)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear(&self, conn: &mut PgConnection, tenant_id: Uuid) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM search_documents WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn search(
+        &self,
+        conn: &mut PgConnection,
+        request: &SearchRequest,
+    ) -> anyhow::Result<SearchPage> {
+        let Some(ts_query) = prefix_tsquery(&request.q) else {
+            return Ok(SearchPage::default());
+        };
+        let entities: Vec<&str> = request.entities.iter().map(|e| e.as_str()).collect();
+
+        let rows: Vec<HitRow> = sqlx::query_as(&format!(
+            "WITH matched AS ({MATCHED}) \
+             SELECT tenant_id, entity, entity_id, title, subtitle, body, skills, location, stage, \
+             owner_id, url, updated_at, \
+             ts_headline('english', title, query, 'StartSel=<mark>, StopSel=</mark>, HighlightAll=true') \
+               AS title_highlight, \
+             ts_headline('english', body, query, \
+               'StartSel=<mark>, StopSel=</mark>, MaxFragments=1, MinWords=5, MaxWords=20') \
+               AS body_highlight \
+             FROM matched ORDER BY score DESC, updated_at DESC LIMIT $10 OFFSET $11"
+        ))
+        .bind(request.tenant_id)
+        .bind(request.user_id)
+        .bind(&ts_query)
+        .bind(&entities)
+        .bind(&request.skills)
+        .bind(&request.location)
+        .bind(&request.stage)
+        .bind(&request.q)
+        .bind(FUZZY_THRESHOLD)
+        .bind(request.per_page)
+        .bind(request.offset())
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let counts: Vec<(String, String, i64)> = sqlx::query_as(&format!(
+            "WITH matched AS ({MATCHED}) \
+             SELECT 'entity', entity::TEXT, COUNT(*) FROM matched GROUP BY entity \
+             UNION ALL SELECT 'skills', skill, COUNT(*) FROM matched, unnest(skills) AS skill \
+               GROUP BY skill \
+             UNION ALL SELECT 'location', location, COUNT(*) FROM matched \
+               WHERE location IS NOT NULL GROUP BY location \
+             UNION ALL SELECT 'stage', stage::TEXT, COUNT(*) FROM matched \
+               WHERE stage IS NOT NULL GROUP BY stage"
+        ))
+        .bind(request.tenant_id)
+        .bind(request.user_id)
+        .bind(&ts_query)
+        .bind(&entities)
+        .bind(&request.skills)
+        .bind(&request.location)
+        .bind(&request.stage)
+        .bind(&request.q)
+        .bind(FUZZY_THRESHOLD)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut page = SearchPage::default();
+        for (field, value, count) in counts {
+            if field == "entity" {
+                page.found += count;
+            }
+            page.facets
+                .entry(field)
+                .or_insert_with(Vec::new)
+                .push(FacetCount { value, count });
+        }
+        sort_facets(&mut page.facets);
+
+        page.hits = rows
+            .into_iter()
+            .filter_map(|row| {
+                let entity = Entity::parse(&row.entity)?;
+                let highlight = [row.body_highlight, row.title_highlight]
+                    .into_iter()
+                    .find(|h| h.contains(super::HIGHLIGHT_START));
+                Some(SearchHit {
+                    document: SearchDocument {
+                        tenant_id: row.tenant_id,
+                        entity,
+                        entity_id: row.entity_id,
+                        title: row.title,
+                        subtitle: row.subtitle,
+                        body: row.body,
+                        skills: row.skills,
+                        location: row.location,
+                        stage: row.stage,
+                        owner_id: row.owner_id,
+                        url: row.url,
+                        updated_at: row.updated_at,
+                    },
+                    highlight,
+                })
+            })
+            .collect();
+        Ok(page)
+    }
+}
+
+/// Turn free text into a tsquery matching every word as a prefix, e.g.
+/// `"senior rust"` becomes `senior:* & rust:*`. Punctuation is dropped so user
+/// input can never produce an invalid query. `None` if no words remain.
+fn prefix_tsquery(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("{}:*", w.to_lowercase()))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_tsquery_sanitizes_input() {
+        assert_eq!(
+            prefix_tsquery("Senior  rust"),
+            Some("senior:* & rust:*".to_string())
+        );
+        assert_eq!(
+            prefix_tsquery("c++ & (dev)!"),
+            Some("c:* & dev:*".to_string())
+        );
+        assert_eq!(prefix_tsquery(" !:* "), None);
+    }
+}
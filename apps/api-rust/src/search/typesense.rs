@@ -0,0 +1,433 @@
+//! Typesense client. All tenants share one collection; every query is
+//! filtered on `tenant_id`, and on `visible_to` for per-user documents.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use super::{
+    sort_facets, Entity, FacetCount, SearchDocument, SearchHit, SearchPage, SearchRequest,
+    FACET_FIELDS, HIGHLIGHT_END, HIGHLIGHT_START,
+};
+use crate::config::Config;
+
+const COLLECTION: &str = "search_documents";
+
+/// `visible_to` value of documents every user of the tenant may see.
+const EVERYONE: &str = "*";
+
+/// Searched fields and their relative weights.
+const QUERY_BY: &str = "title,subtitle,skills,body";
+const QUERY_BY_WEIGHTS: &str = "4,2,2,1";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TypesenseDocument {
+    id: String,
+    tenant_id: Uuid,
+    entity: String,
+    entity_id: Uuid,
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subtitle: Option<String>,
+    body: String,
+    skills: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner_id: Option<Uuid>,
+    visible_to: Vec<String>,
+    url: String,
+    updated_at: i64,
+}
+
+impl From<&SearchDocument> for TypesenseDocument {
+    fn from(doc: &SearchDocument) -> Self {
+        TypesenseDocument {
+            id: doc.entity_id.to_string(),
+            tenant_id: doc.tenant_id,
+            entity: doc.entity.as_str().to_string(),
+            entity_id: doc.entity_id,
+            title: doc.title.clone(),
+            subtitle: doc.subtitle.clone(),
+            body: doc.body.clone(),
+            skills: doc.skills.clone(),
+            location: doc.location.clone(),
+            stage: doc.stage.clone(),
+            owner_id: doc.owner_id,
+            visible_to: vec![doc
+                .owner_id
+                .map_or_else(|| EVERYONE.to_string(), |o| o.to_string())],
+            url: doc.url.clone(),
+            updated_at: doc.updated_at.timestamp(),
+        }
+    }
+}
+
+impl TypesenseDocument {
+    fn into_document(self) -> Option<SearchDocument> {
+        Some(SearchDocument {
+            tenant_id: self.tenant_id,
+            entity: Entity::parse(&self.entity)?,
+            entity_id: self.entity_id,
+            title: self.title,
+            subtitle: self.subtitle,
+            body: self.body,
+            skills: self.skills,
+            location: self.location,
+            stage: self.stage,
+            owner_id: self.owner_id,
+            url: self.url,
+            updated_at: Utc.timestamp_opt(self.updated_at, 0).single()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    found: i64,
+    #[serde(default)]
+    hits: Vec<Hit>,
+    #[serde(default)]
+    facet_counts: Vec<FacetResult>,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+    document: TypesenseDocument,
+    #[serde(default)]
+    highlights: Vec<Highlight>,
+}
+
+#[derive(Deserialize)]
+struct Highlight {
+    snippet: Option<String>,
+    #[serde(default)]
+    snippets: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FacetResult {
+    field_name: String,
+    counts: Vec<FacetValue>,
+}
+
+#[derive(Deserialize)]
+struct FacetValue {
+    value: String,
+    count: i64,
+}
+
+#[derive(Clone)]
+pub struct TypesenseEngine {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    collection_ready: Arc<OnceCell<()>>,
+}
+
+impl TypesenseEngine {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let base_url = config
+            .typesense_url
+            .clone()
+            .context("TYPESENSE_URL is required for the typesense search backend")?;
+        let api_key = config
+            .typesense_api_key
+            .clone()
+            .context("TYPESENSE_API_KEY is required for the typesense search backend")?;
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            collection_ready: Arc::new(OnceCell::new()),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, self.url(path))
+            .header("X-TYPESENSE-API-KEY", &self.api_key)
+    }
+
+    /// Create the collection on first use if it does not exist yet.
+    async fn ensure_collection(&self) -> anyhow::Result<()> {
+        self.collection_ready
+            .get_or_try_init(|| async {
+                let existing = self
+                    .request(
+                        reqwest::Method::GET,
+                        &format!("/collections/{}", COLLECTION),
+                    )
+                    .send()
+                    .await?;
+                if existing.status() == reqwest::StatusCode::NOT_FOUND {
+                    self.request(reqwest::Method::POST, "/collections")
+                        .json(&collection_schema())
+                        .send()
+                        .await?
+                        .error_for_status()?;
+                } else {
+                    existing.error_for_status()?;
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn upsert(&self, docs: &[SearchDocument]) -> anyhow::Result<()> {
+        self.ensure_collection().await?;
+        let mut lines = Vec::with_capacity(docs.len());
+        for doc in docs {
+            lines.push(serde_json::to_string(&TypesenseDocument::from(doc))?);
+        }
+
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/collections/{}/documents/import?action=upsert", COLLECTION),
+            )
+            .header("content-type", "text/plain")
+            .body(lines.join("\n"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        // The import answers 200 with one result per line; failures are
+        // reported per document.
+        for line in response.lines() {
+            let result: serde_json::Value = serde_json::from_str(line)?;
+            if result["success"] != serde_json::Value::Bool(true) {
+                anyhow::bail!("Typesense import failed: {}", result["error"]);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete(
+        &self,
+        tenant_id: Uuid,
+        entity: Entity,
+        ids: &[Uuid],
+    ) -> anyhow::Result<()> {
+        let filter = format!(
+            "tenant_id:={} && entity:={} && entity_id:=[{}]",
+            quote(&tenant_id.to_string()),
+            quote(entity.as_str()),
+            ids.iter()
+                .map(|id| quote(&id.to_string()))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        self.delete_by_filter(&filter).await
+    }
+
+    pub async fn clear(&self, tenant_id: Uuid) -> anyhow::Result<()> {
+        self.delete_by_filter(&format!("tenant_id:={}", quote(&tenant_id.to_string())))
+            .await
+    }
+
+    async fn delete_by_filter(&self, filter: &str) -> anyhow::Result<()> {
+        self.ensure_collection().await?;
+        self.request(
+            reqwest::Method::DELETE,
+            &format!("/collections/{}/documents", COLLECTION),
+        )
+        .query(&[("filter_by", filter)])
+        .send()
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn search(&self, request: &SearchRequest) -> anyhow::Result<SearchPage> {
+        self.ensure_collection().await?;
+        let result: SearchResult = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/collections/{}/documents/search", COLLECTION),
+            )
+            .query(&search_params(request))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let mut page = SearchPage {
+            found: result.found,
+            ..SearchPage::default()
+        };
+        for facet in result.facet_counts {
+            page.facets.insert(
+                facet.field_name,
+                facet
+                    .counts
+                    .into_iter()
+                    .map(|c| FacetCount {
+                        value: c.value,
+                        count: c.count,
+                    })
+                    .collect(),
+            );
+        }
+        sort_facets(&mut page.facets);
+
+        page.hits = result
+            .hits
+            .into_iter()
+            .filter_map(|hit| {
+                let highlight = hit
+                    .highlights
+                    .into_iter()
+                    .find_map(|h| h.snippet.or_else(|| h.snippets.into_iter().next()));
+                Some(SearchHit {
+                    document: hit.document.into_document()?,
+                    highlight,
+                })
+            })
+            .collect();
+        Ok(page)
+    }
+}
+
+fn collection_schema() -> serde_json::Value {
+    serde_json::json!({
+        "name": COLLECTION,
+        "fields": [
+            { "name": "tenant_id", "type": "string" },
+            { "name": "entity", "type": "string", "facet": true },
+            { "name": "entity_id", "type": "string" },
+            { "name": "title", "type": "string" },
+            { "name": "subtitle", "type": "string", "optional": true },
+            { "name": "body", "type": "string" },
+            { "name": "skills", "type": "string[]", "facet": true },
+            { "name": "location", "type": "string", "facet": true, "optional": true },
+            { "name": "stage", "type": "string", "facet": true, "optional": true },
+            { "name": "owner_id", "type": "string", "optional": true, "index": false },
+            { "name": "visible_to", "type": "string[]" },
+            { "name": "url", "type": "string", "index": false, "optional": true },
+            { "name": "updated_at", "type": "int64" }
+        ],
+        "default_sorting_field": "updated_at"
+    })
+}
+
+/// Quote a filter value. Backticks delimit values in `filter_by`, so any in
+/// the value itself are dropped.
+fn quote(value: &str) -> String {
+    format!("`{}`", value.replace('`', ""))
+}
+
+fn filter_by(request: &SearchRequest) -> String {
+    let mut clauses = vec![
+        format!("tenant_id:={}", quote(&request.tenant_id.to_string())),
+        format!(
+            "visible_to:=[{},{}]",
+            quote(EVERYONE),
+            quote(&request.user_id.to_string())
+        ),
+    ];
+    if !request.entities.is_empty() {
+        let entities: Vec<String> = request.entities.iter().map(|e| quote(e.as_str())).collect();
+        clauses.push(format!("entity:=[{}]", entities.join(",")));
+    }
+    // One clause per skill: documents must have all of them.
+    for skill in &request.skills {
+        clauses.push(format!("skills:={}", quote(skill)));
+    }
+    if let Some(location) = &request.location {
+        clauses.push(format!("location:={}", quote(location)));
+    }
+    if let Some(stage) = &request.stage {
+        clauses.push(format!("stage:={}", quote(stage)));
+    }
+    clauses.join(" && ")
+}
+
+fn search_params(request: &SearchRequest) -> Vec<(&'static str, String)> {
+    vec![
+        ("q", request.q.clone()),
+        ("query_by", QUERY_BY.to_string()),
+        ("query_by_weights", QUERY_BY_WEIGHTS.to_string()),
+        ("num_typos", "2".to_string()),
+        ("prefix", "true".to_string()),
+        ("filter_by", filter_by(request)),
+        ("facet_by", FACET_FIELDS.join(",")),
+        ("sort_by", "_text_match:desc,updated_at:desc".to_string()),
+        ("highlight_fields", "title,subtitle,body".to_string()),
+        ("highlight_start_tag", HIGHLIGHT_START.to_string()),
+        ("highlight_end_tag", HIGHLIGHT_END.to_string()),
+        ("page", request.page.to_string()),
+        ("per_page", request.per_page.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_scopes_to_tenant_user_and_facets() {
+        let tenant = Uuid::from_u128(1);
+        let user = Uuid::from_u128(2);
+        let request = SearchRequest {
+            tenant_id: tenant,
+            user_id: user,
+            q: "rust".to_string(),
+            entities: vec![Entity::Candidate, Entity::Application],
+            skills: vec!["Rust".to_string(), "Go`lang".to_string()],
+            location: Some("Austin, TX".to_string()),
+            stage: Some("interview".to_string()),
+            page: 1,
+            per_page: 20,
+        };
+        assert_eq!(
+            filter_by(&request),
+            format!(
+                "tenant_id:=`{}` && visible_to:=[`*`,`{}`] && entity:=[`candidate`,`application`] \
+                 && skills:=`Rust` && skills:=`Golang` && location:=`Austin, TX` && stage:=`interview`",
+                tenant, user
+            )
+        );
+    }
+
+    #[test]
+    fn test_documents_round_trip() {
+        let doc = SearchDocument {
+            tenant_id: Uuid::new_v4(),
+            entity: Entity::Note,
+            entity_id: Uuid::new_v4(),
+            title: "Note on Ada".to_string(),
+            subtitle: None,
+            body: "Strong systems background".to_string(),
+            skills: vec![],
+            location: None,
+            stage: None,
+            owner_id: Some(Uuid::new_v4()),
+            url: "/talent/1".to_string(),
+            updated_at: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+        };
+        let stored = TypesenseDocument::from(&doc);
+        assert_eq!(stored.visible_to, vec![doc.owner_id.unwrap().to_string()]);
+        let json = serde_json::to_value(&stored).unwrap();
+        assert!(json.get("subtitle").is_none());
+
+        let back: TypesenseDocument = serde_json::from_value(json).unwrap();
+        assert_eq!(back.into_document(), Some(doc));
+    }
+}
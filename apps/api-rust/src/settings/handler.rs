@@ -7,20 +7,24 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::mailer::{messages, outbox};
 use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+use crate::money::{Currency, Locale};
 use crate::settings::model::*;
+use crate::subscriptions::entitlements::{self, Meter};
 use crate::AppState;
 
 pub async fn get_firm_settings(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<FirmSettings>> {
     let firm: FirmSettings = sqlx::query_as(
-        "SELECT id, name, slug, tier, status, settings, created_at, updated_at \
+        "SELECT id, name, slug, tier, status, settings, base_currency, locale, created_at, updated_at \
          FROM tenants WHERE id = $1",
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Firm not found".to_string()))?;
 
@@ -28,24 +32,40 @@ pub async fn get_firm_settings(
 }
 
 pub async fn update_firm_settings(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<UpdateFirmSettingsRequest>,
 ) -> AppResult<Json<FirmSettings>> {
     require_role(&claims, "admin")?;
+    let base_currency = payload
+        .base_currency
+        .as_deref()
+        .map(Currency::parse)
+        .transpose()?;
+    let locale =
+        match payload.locale.as_deref() {
+            Some(tag) => Some(Locale::find(tag).ok_or_else(|| {
+                AppError::Validation(format!("Unsupported locale: {}", tag.trim()))
+            })?),
+            None => None,
+        };
 
     let firm: FirmSettings = sqlx::query_as(
         "UPDATE tenants SET \
          name = COALESCE($2, name), \
          settings = COALESCE($3, settings), \
+         base_currency = COALESCE($4, base_currency), \
+         locale = COALESCE($5, locale), \
          updated_at = NOW() \
          WHERE id = $1 \
-         RETURNING id, name, slug, tier, status, settings, created_at, updated_at",
+         RETURNING id, name, slug, tier, status, settings, base_currency, locale, created_at, updated_at",
     )
     .bind(claims.tid)
     .bind(&payload.name)
     .bind(&payload.settings)
-    .fetch_optional(&state.db)
+    .bind(base_currency.map(|c| c.code))
+    .bind(locale.map(|l| l.tag))
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Firm not found".to_string()))?;
 
@@ -53,7 +73,7 @@ pub async fn update_firm_settings(
 }
 
 pub async fn get_profile(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<UserProfile>> {
     let user: UserProfile = sqlx::query_as(
@@ -62,7 +82,7 @@ pub async fn get_profile(
     )
     .bind(claims.sub)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
@@ -70,7 +90,7 @@ pub async fn get_profile(
 }
 
 pub async fn update_profile(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<UpdateProfileRequest>,
 ) -> AppResult<Json<UserProfile>> {
@@ -86,7 +106,7 @@ pub async fn update_profile(
     .bind(claims.tid)
     .bind(&payload.first_name)
     .bind(&payload.last_name)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
@@ -94,7 +114,7 @@ pub async fn update_profile(
 }
 
 pub async fn list_users(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     require_role(&claims, "admin")?;
@@ -104,7 +124,7 @@ pub async fn list_users(
          FROM users WHERE tenant_id = $1 AND status != 'deleted' ORDER BY created_at"
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({ "data": users })))
@@ -112,6 +132,7 @@ pub async fn list_users(
 
 pub async fn invite_user(
     State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<InviteUserRequest>,
 ) -> AppResult<(StatusCode, Json<UserProfile>)> {
@@ -140,7 +161,7 @@ pub async fn invite_user(
         sqlx::query_as("SELECT id FROM users WHERE tenant_id = $1 AND LOWER(email) = $2")
             .bind(claims.tid)
             .bind(&normalized_email)
-            .fetch_optional(&state.db)
+            .fetch_optional(&mut *tx)
             .await?;
 
     if existing.is_some() {
@@ -149,6 +170,9 @@ pub async fn invite_user(
         ));
     }
 
+    // Invited users hold a seat from the moment they are invited
+    entitlements::consume(&mut tx, claims.tid, Meter::ActiveUsers, 1).await?;
+
     // Create invited user with real hashed temp password and invite token
     let invite_token = Uuid::new_v4().to_string();
     let temp_password = Uuid::new_v4().to_string();
@@ -167,14 +191,37 @@ pub async fn invite_user(
     .bind(&payload.last_name)
     .bind(&payload.role)
     .bind(&invite_token)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let firm_name: String = sqlx::query_scalar("SELECT name FROM tenants WHERE id = $1")
+        .bind(claims.tid)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    outbox::enqueue(
+        &mut *tx,
+        claims.tid,
+        &messages::user_invite(
+            &user.email,
+            &state.config.app_base_url,
+            &invite_token,
+            &firm_name,
+            &user.first_name,
+        ),
+        outbox::EnqueueOptions {
+            category: Some("user_invite"),
+            created_by: Some(claims.sub),
+            ..Default::default()
+        },
+    )
     .await?;
 
     Ok((StatusCode::CREATED, Json(user)))
 }
 
 pub async fn update_user_role(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(user_id): Path<Uuid>,
     Json(payload): Json<UpdateUserRoleRequest>,
@@ -203,7 +250,7 @@ pub async fn update_user_role(
     .bind(user_id)
     .bind(claims.tid)
     .bind(&payload.role)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
@@ -211,7 +258,7 @@ pub async fn update_user_role(
 }
 
 pub async fn delete_user(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(user_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -226,12 +273,13 @@ pub async fn delete_user(
     )
     .bind(user_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("User not found".to_string()));
     }
+    entitlements::release(&mut tx, claims.tid, Meter::ActiveUsers, 1).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,2 +1,22 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/settings/firm", get(handler::get_firm_settings))
+        .route("/settings/firm", put(handler::update_firm_settings))
+        .route("/settings/profile", get(handler::get_profile))
+        .route("/settings/profile", put(handler::update_profile))
+        .route("/settings/users", get(handler::list_users))
+        .route("/settings/users/invite", post(handler::invite_user))
+        .route("/settings/users/{id}/role", put(handler::update_user_role))
+        .route("/settings/users/{id}", delete(handler::delete_user))
+}
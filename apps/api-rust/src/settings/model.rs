@@ -11,6 +11,10 @@ pub struct FirmSettings {
     pub tier: String,
     pub status: String,
     pub settings: serde_json::Value,
+    /// Currency the firm reports in; other currencies are converted into it.
+    pub base_currency: String,
+    /// BCP 47 tag amounts are formatted for, e.g. `en-US`.
+    pub locale: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -20,6 +24,8 @@ pub struct UpdateFirmSettingsRequest {
     #[validate(length(min = 1, max = 255))]
     pub name: Option<String>,
     pub settings: Option<serde_json::Value>,
+    pub base_currency: Option<String>,
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
@@ -1,17 +1,13 @@
-use axum::{
-    extract::{Extension, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Extension, http::StatusCode, Json};
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::shortcuts::model::*;
-use crate::AppState;
 
 /// GET /shortcuts - Get user's active shortcut profile and bindings
 pub async fn get_shortcuts(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<serde_json::Value>> {
     // Get or create default profile
@@ -23,7 +19,7 @@ pub async fn get_shortcuts(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     {
         Some(p) => p,
@@ -36,7 +32,7 @@ pub async fn get_shortcuts(
             )
             .bind(claims.tid)
             .bind(claims.sub)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
         }
     };
@@ -49,7 +45,7 @@ pub async fn get_shortcuts(
     )
     .bind(claims.tid)
     .bind(profile.id)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -60,7 +56,7 @@ pub async fn get_shortcuts(
 
 /// PATCH /shortcuts - Update shortcut bindings
 pub async fn update_shortcuts(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<UpdateShortcutsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -73,7 +69,7 @@ pub async fn update_shortcuts(
     )
     .bind(claims.tid)
     .bind(claims.sub)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Shortcut profile not found".to_string()))?;
 
@@ -94,7 +90,7 @@ pub async fn update_shortcuts(
         .bind(&binding.keys)
         .bind(scope)
         .bind(is_enabled)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
     }
 
@@ -107,7 +103,7 @@ pub async fn update_shortcuts(
     )
     .bind(claims.tid)
     .bind(profile.id)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -118,7 +114,7 @@ pub async fn update_shortcuts(
 
 /// POST /shortcuts/usage-events - Track shortcut usage
 pub async fn record_usage_event(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<ShortcutUsageEvent>,
 ) -> AppResult<StatusCode> {
@@ -131,7 +127,7 @@ pub async fn record_usage_event(
     .bind(&payload.action)
     .bind(&payload.keys)
     .bind(payload.context.as_deref())
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(StatusCode::CREATED)
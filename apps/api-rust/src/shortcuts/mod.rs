@@ -1,2 +1,17 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{get, patch, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/shortcuts", get(handler::get_shortcuts))
+        .route("/shortcuts", patch(handler::update_shortcuts))
+        .route("/shortcuts/usage-events", post(handler::record_usage_event))
+}
@@ -0,0 +1,71 @@
+use crate::config::Config;
+
+/// Build an S3 client pointed at the configured endpoint and region.
+pub async fn s3_client(config: &Config) -> aws_sdk_s3::Client {
+    let s3_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .endpoint_url(&config.s3_endpoint)
+        .region(aws_config::Region::new(config.s3_region.clone()))
+        .load()
+        .await;
+    aws_sdk_s3::Client::new(&s3_config)
+}
+
+/// Upload a generated object (PDFs, letters, exports) to the document bucket.
+pub async fn put_object(
+    config: &Config,
+    key: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<(), String> {
+    s3_client(config)
+        .await
+        .put_object()
+        .bucket(&config.s3_bucket)
+        .key(key)
+        .content_type(content_type)
+        .body(aws_sdk_s3::primitives::ByteStream::from(body))
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("S3 upload of '{}' failed: {}", key, e))
+}
+
+/// A URL the client can PUT an upload to for the next hour.
+pub async fn presigned_put_url(config: &Config, key: &str, content_type: &str) -> String {
+    let presigned = s3_client(config)
+        .await
+        .put_object()
+        .bucket(&config.s3_bucket)
+        .key(key)
+        .content_type(content_type)
+        .presigned(
+            aws_sdk_s3::presigning::PresigningConfig::expires_in(std::time::Duration::from_secs(
+                3600,
+            ))
+            .expect("valid presigning config"),
+        )
+        .await;
+    match presigned {
+        Ok(presigned) => presigned.uri().to_string(),
+        // Fallback for local dev if presigning fails
+        Err(_) => format!("{}/{}/{}", config.s3_endpoint, config.s3_bucket, key),
+    }
+}
+
+/// Download an object from the document bucket.
+pub async fn get_object(config: &Config, key: &str) -> Result<Vec<u8>, String> {
+    let object = s3_client(config)
+        .await
+        .get_object()
+        .bucket(&config.s3_bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| format!("S3 download of '{}' failed: {}", key, e))?;
+    object
+        .body
+        .collect()
+        .await
+        .map(|data| data.into_bytes().to_vec())
+        .map_err(|e| format!("S3 download of '{}' failed: {}", key, e))
+}
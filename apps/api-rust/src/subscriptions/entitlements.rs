@@ -0,0 +1,356 @@
+//! Server-side enforcement of plan limits and features.
+//!
+//! Metered resources are counted in `usage_meters` on the tenant's current
+//! subscription. Handlers call [`consume`] before the insert, in the same
+//! transaction, and [`release`] alongside the delete, so a rolled-back request
+//! never leaves the count off. Features are checked against `entitlements`, which
+//! [`sync_plan`] fills from the plan's `features` list; routes are gated with
+//! [`require_feature`].
+//!
+//! Tenants without any subscription are not on a hiring plan and are not
+//! metered.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    middleware::{from_fn, FromFnLayer, Next},
+    response::{IntoResponse, Response},
+};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
+use crate::subscriptions::model::Plan;
+
+/// Grants every feature when present on a plan.
+const ALL_FEATURES: &str = "all_features";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Meter {
+    JobPosts,
+    ActiveUsers,
+    CandidateContacts,
+}
+
+impl Meter {
+    pub const ALL: [Meter; 3] = [
+        Meter::JobPosts,
+        Meter::ActiveUsers,
+        Meter::CandidateContacts,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Meter::JobPosts => "job_posts",
+            Meter::ActiveUsers => "active_users",
+            Meter::CandidateContacts => "candidate_contacts",
+        }
+    }
+
+    /// The plan's limit for this meter; `None` is unlimited.
+    fn limit(self, plan: &Plan) -> Option<i64> {
+        let max = match self {
+            Meter::JobPosts => plan.max_jobs,
+            Meter::ActiveUsers => plan.max_users,
+            Meter::CandidateContacts => plan.max_candidates,
+        };
+        // Plans use -1 for "unlimited".
+        max.filter(|v| *v >= 0).map(i64::from)
+    }
+
+    /// Current usage, counted from the resource table.
+    fn count_sql(self) -> &'static str {
+        match self {
+            Meter::JobPosts => "SELECT COUNT(*) FROM job_posts WHERE tenant_id = $1",
+            Meter::ActiveUsers => {
+                "SELECT COUNT(*) FROM users WHERE tenant_id = $1 AND status != 'deleted'"
+            }
+            Meter::CandidateContacts => {
+                "SELECT COUNT(*) FROM candidate_profiles WHERE tenant_id = $1"
+            }
+        }
+    }
+}
+
+/// The tenant's most recent subscription, if it has one.
+async fn current_subscription(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+) -> Result<Option<(Uuid, String)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, status FROM subscriptions WHERE tenant_id = $1 \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Apply the plan to subscriptions that have never had it applied (created
+/// before enforcement, or by a Stripe webhook).
+async fn ensure_synced(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    subscription_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let synced: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM usage_meters WHERE subscription_id = $1)")
+            .bind(subscription_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    if synced {
+        return Ok(());
+    }
+
+    let plan: Plan = sqlx::query_as(
+        "SELECT p.id, p.name, p.slug, p.description, p.price_monthly_cents, p.price_annual_cents, \
+         p.max_jobs, p.max_users, p.max_candidates, p.features, p.is_active, \
+         p.stripe_price_id_monthly, p.stripe_price_id_annual, p.sort_order, \
+         p.created_at, p.updated_at \
+         FROM plans p JOIN subscriptions s ON s.plan_id = p.id \
+         WHERE s.id = $1",
+    )
+    .bind(subscription_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    sync_plan(conn, tenant_id, subscription_id, &plan).await
+}
+
+fn ensure_usable(status: &str) -> AppResult<()> {
+    match status {
+        "cancelled" | "suspended" => Err(AppError::SubscriptionInactive(status.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// Count `amount` new units against the tenant's limit, failing with
+/// `PlanLimit` if that would exceed it. The check and increment are a single
+/// statement, so concurrent requests cannot both take the last unit.
+pub async fn consume(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    meter: Meter,
+    amount: i64,
+) -> AppResult<()> {
+    let Some((subscription_id, status)) = current_subscription(conn, tenant_id).await? else {
+        return Ok(());
+    };
+    ensure_usable(&status)?;
+    ensure_synced(conn, tenant_id, subscription_id).await?;
+
+    let updated: Option<i64> = sqlx::query_scalar(
+        "UPDATE usage_meters SET current_value = COALESCE(current_value, 0) + $3, updated_at = NOW() \
+         WHERE subscription_id = $1 AND meter_type = $2 \
+           AND (limit_value IS NULL OR COALESCE(current_value, 0) + $3 <= limit_value) \
+         RETURNING current_value",
+    )
+    .bind(subscription_id)
+    .bind(meter.as_str())
+    .bind(amount)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if updated.is_some() {
+        return Ok(());
+    }
+
+    let usage: Option<(Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT current_value, limit_value FROM usage_meters \
+         WHERE subscription_id = $1 AND meter_type = $2",
+    )
+    .bind(subscription_id)
+    .bind(meter.as_str())
+    .fetch_optional(&mut *conn)
+    .await?;
+    match usage {
+        None => Ok(()),
+        Some((current, limit)) => Err(AppError::PlanLimit {
+            meter: meter.as_str().to_string(),
+            limit: limit.unwrap_or_default(),
+            current: current.unwrap_or_default(),
+        }),
+    }
+}
+
+/// Return `amount` units after the resources were deleted.
+pub async fn release(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    meter: Meter,
+    amount: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE usage_meters SET current_value = GREATEST(COALESCE(current_value, 0) - $3, 0), \
+         updated_at = NOW() \
+         WHERE meter_type = $2 AND subscription_id = \
+           (SELECT id FROM subscriptions WHERE tenant_id = $1 ORDER BY created_at DESC LIMIT 1)",
+    )
+    .bind(tenant_id)
+    .bind(meter.as_str())
+    .bind(amount)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Fail with `FeatureNotEntitled` unless the tenant's plan (or an add-on or
+/// override) enables `feature`.
+pub async fn check_feature(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    feature: &str,
+) -> AppResult<()> {
+    let Some((subscription_id, status)) = current_subscription(conn, tenant_id).await? else {
+        return Ok(());
+    };
+    ensure_usable(&status)?;
+    ensure_synced(conn, tenant_id, subscription_id).await?;
+
+    let enabled: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM entitlements \
+         WHERE tenant_id = $1 AND feature_key IN ($2, $3) AND is_enabled = true \
+           AND (expires_at IS NULL OR expires_at > NOW()))",
+    )
+    .bind(tenant_id)
+    .bind(feature)
+    .bind(ALL_FEATURES)
+    .fetch_one(conn)
+    .await?;
+    if enabled {
+        Ok(())
+    } else {
+        Err(AppError::FeatureNotEntitled(feature.to_string()))
+    }
+}
+
+type GuardFuture = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+/// Route layer that rejects requests unless the tenant is entitled to
+/// `feature`. Apply inside `require_auth`, e.g.
+/// `.route_layer(require_feature("talent_pools"))`.
+pub fn require_feature(
+    feature: &'static str,
+) -> FromFnLayer<
+    impl Fn(Request, Next) -> GuardFuture + Clone + Send + Sync + 'static,
+    (),
+    (Request,),
+> {
+    from_fn(move |request: Request, next: Next| -> GuardFuture {
+        Box::pin(async move {
+            let claims = request
+                .extensions()
+                .get::<crate::auth::jwt::Claims>()
+                .cloned();
+            let Some(claims) = claims else {
+                return next.run(request).await;
+            };
+
+            // Checked in the request's tenant transaction, released before
+            // the handler takes it.
+            let (mut parts, body) = request.into_parts();
+            let checked = match TenantTx::from_request_parts(&mut parts, &()).await {
+                Ok(mut tx) => check_feature(&mut tx, claims.tid, feature).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = checked {
+                return e.into_response();
+            }
+            next.run(Request::from_parts(parts, body)).await
+        })
+    })
+}
+
+/// Bring a subscription's meters and plan entitlements in line with `plan`:
+/// limits follow the plan, counts are taken from the live tables, and
+/// plan-sourced entitlements are replaced by the plan's features. Add-on and
+/// override entitlements are kept.
+pub async fn sync_plan(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    subscription_id: Uuid,
+    plan: &Plan,
+) -> Result<(), sqlx::Error> {
+    for meter in Meter::ALL {
+        let current: i64 = sqlx::query_scalar(meter.count_sql())
+            .bind(tenant_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        sqlx::query(
+            "INSERT INTO usage_meters \
+             (tenant_id, subscription_id, meter_type, current_value, limit_value, period_start, period_end) \
+             VALUES ($1, $2, $3, $4, $5, NOW(), NOW() + INTERVAL '1 month') \
+             ON CONFLICT (subscription_id, meter_type) DO UPDATE SET \
+             current_value = EXCLUDED.current_value, limit_value = EXCLUDED.limit_value, \
+             updated_at = NOW()",
+        )
+        .bind(tenant_id)
+        .bind(subscription_id)
+        .bind(meter.as_str())
+        .bind(current)
+        .bind(meter.limit(plan))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM entitlements WHERE tenant_id = $1 AND source = 'plan'")
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "INSERT INTO entitlements (tenant_id, feature_key, is_enabled, source) \
+         SELECT $1, feature, true, 'plan' FROM jsonb_array_elements_text($2) AS feature \
+         ON CONFLICT (tenant_id, feature_key) DO NOTHING",
+    )
+    .bind(tenant_id)
+    .bind(&plan.features)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(max_jobs: Option<i32>, max_users: Option<i32>) -> Plan {
+        Plan {
+            id: Uuid::nil(),
+            name: "Test".to_string(),
+            slug: "test".to_string(),
+            description: None,
+            price_monthly_cents: 0,
+            price_annual_cents: None,
+            max_jobs,
+            max_users,
+            max_candidates: Some(100),
+            features: serde_json::json!([]),
+            is_active: true,
+            stripe_price_id_monthly: None,
+            stripe_price_id_annual: None,
+            sort_order: 0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_meter_limits_from_plan() {
+        let p = plan(Some(2), Some(-1));
+        assert_eq!(Meter::JobPosts.limit(&p), Some(2));
+        assert_eq!(Meter::ActiveUsers.limit(&p), None);
+        assert_eq!(Meter::CandidateContacts.limit(&p), Some(100));
+        assert_eq!(Meter::JobPosts.limit(&plan(None, None)), None);
+    }
+
+    #[test]
+    fn test_inactive_subscriptions_are_rejected() {
+        assert!(ensure_usable("trialing").is_ok());
+        assert!(ensure_usable("past_due").is_ok());
+        assert!(matches!(
+            ensure_usable("suspended"),
+            Err(AppError::SubscriptionInactive(_))
+        ));
+    }
+}
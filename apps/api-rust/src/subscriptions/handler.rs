@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Path},
     http::StatusCode,
     Json,
 };
@@ -7,13 +7,14 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
-use crate::AppState;
+use crate::middleware::tenant::TenantTx;
+use crate::subscriptions::entitlements::sync_plan;
 
 use super::model::*;
 
 /// GET /plans — list all active plans ordered by sort_order.
 pub async fn list_plans(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(_claims): Extension<Claims>,
 ) -> AppResult<Json<Vec<Plan>>> {
     let plans: Vec<Plan> = sqlx::query_as(
@@ -23,7 +24,7 @@ pub async fn list_plans(
          created_at, updated_at \
          FROM plans WHERE is_active = true ORDER BY sort_order ASC",
     )
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(plans))
@@ -31,7 +32,7 @@ pub async fn list_plans(
 
 /// GET /subscription — get the current tenant's subscription with plan details.
 pub async fn get_current_subscription(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<SubscriptionWithPlan>> {
     let subscription: SubscriptionWithPlan = sqlx::query_as(
@@ -45,7 +46,7 @@ pub async fn get_current_subscription(
          ORDER BY s.created_at DESC LIMIT 1",
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Subscription not found".to_string()))?;
 
@@ -54,7 +55,7 @@ pub async fn get_current_subscription(
 
 /// POST /subscription — create a new subscription for the current tenant.
 pub async fn create_subscription(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateSubscriptionRequest>,
 ) -> AppResult<(StatusCode, Json<Subscription>)> {
@@ -67,7 +68,7 @@ pub async fn create_subscription(
          FROM plans WHERE id = $1 AND is_active = true",
     )
     .bind(payload.plan_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Plan not found".to_string()))?;
 
@@ -76,7 +77,7 @@ pub async fn create_subscription(
         "SELECT id FROM subscriptions WHERE tenant_id = $1 AND status IN ('active', 'trialing')",
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?;
 
     if existing.is_some() {
@@ -96,7 +97,7 @@ pub async fn create_subscription(
     }
 
     let id = Uuid::new_v4();
-    let seats_limit = plan.max_users.unwrap_or(5);
+    let seats_limit = plan.max_users.filter(|v| *v >= 0).unwrap_or(5);
     let period_interval = if billing_cycle == "annual" {
         "1 year"
     } else {
@@ -122,38 +123,18 @@ pub async fn create_subscription(
     .bind(payload.plan_id)
     .bind(&billing_cycle)
     .bind(seats_limit)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
-    // Insert default usage meters for the plan
-    let meter_types = vec![
-        ("jobs", plan.max_jobs.map(|v| v as i64)),
-        ("users", plan.max_users.map(|v| v as i64)),
-        ("candidates", plan.max_candidates.map(|v| v as i64)),
-    ];
-
-    for (meter_type, limit_value) in meter_types {
-        sqlx::query(
-            "INSERT INTO usage_meters \
-             (id, tenant_id, subscription_id, meter_type, current_value, limit_value, \
-              period_start, period_end) \
-             VALUES ($1, $2, $3, $4, 0, $5, NOW(), NOW() + INTERVAL '1 month')",
-        )
-        .bind(Uuid::new_v4())
-        .bind(claims.tid)
-        .bind(id)
-        .bind(meter_type)
-        .bind(limit_value)
-        .execute(&state.db)
-        .await?;
-    }
+    // Meters start from current usage, so existing records count.
+    sync_plan(&mut tx, claims.tid, id, &plan).await?;
 
     Ok((StatusCode::CREATED, Json(subscription)))
 }
 
 /// PUT /subscription/plan — change the current subscription's plan.
 pub async fn change_plan(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<ChangePlanRequest>,
 ) -> AppResult<Json<Subscription>> {
@@ -166,11 +147,11 @@ pub async fn change_plan(
          FROM plans WHERE id = $1 AND is_active = true",
     )
     .bind(payload.plan_id)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Plan not found".to_string()))?;
 
-    let new_seats_limit = new_plan.max_users.unwrap_or(5);
+    let new_seats_limit = new_plan.max_users.filter(|v| *v >= 0).unwrap_or(5);
 
     let subscription: Subscription = sqlx::query_as(
         "UPDATE subscriptions SET \
@@ -184,35 +165,18 @@ pub async fn change_plan(
     .bind(claims.tid)
     .bind(payload.plan_id)
     .bind(new_seats_limit)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Active subscription not found".to_string()))?;
 
-    // Update usage meter limits to match the new plan
-    let meter_updates = vec![
-        ("jobs", new_plan.max_jobs.map(|v| v as i64)),
-        ("users", new_plan.max_users.map(|v| v as i64)),
-        ("candidates", new_plan.max_candidates.map(|v| v as i64)),
-    ];
-
-    for (meter_type, limit_value) in meter_updates {
-        sqlx::query(
-            "UPDATE usage_meters SET limit_value = $3, updated_at = NOW() \
-             WHERE subscription_id = $1 AND meter_type = $2",
-        )
-        .bind(subscription.id)
-        .bind(meter_type)
-        .bind(limit_value)
-        .execute(&state.db)
-        .await?;
-    }
+    sync_plan(&mut tx, claims.tid, subscription.id, &new_plan).await?;
 
     Ok(Json(subscription))
 }
 
 /// POST /subscription/cancel — cancel the current subscription (effective at period end).
 pub async fn cancel_subscription(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<Subscription>> {
     let subscription: Subscription = sqlx::query_as(
@@ -225,7 +189,7 @@ pub async fn cancel_subscription(
           seats_used, seats_limit, metadata, created_at, updated_at",
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Active subscription not found".to_string()))?;
 
@@ -234,7 +198,7 @@ pub async fn cancel_subscription(
 
 /// GET /subscription/usage — list usage meters for the current subscription.
 pub async fn get_usage(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<Vec<UsageMeter>>> {
     // Find the current active/trialing subscription
@@ -244,7 +208,7 @@ pub async fn get_usage(
          ORDER BY created_at DESC LIMIT 1",
     )
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Active subscription not found".to_string()))?;
 
@@ -257,7 +221,7 @@ pub async fn get_usage(
     )
     .bind(claims.tid)
     .bind(sub.0)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(meters))
@@ -265,7 +229,7 @@ pub async fn get_usage(
 
 /// GET /entitlements — list all entitlements for the current tenant.
 pub async fn list_entitlements(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<Vec<Entitlement>>> {
     let entitlements: Vec<Entitlement> = sqlx::query_as(
@@ -276,7 +240,7 @@ pub async fn list_entitlements(
          ORDER BY feature_key ASC",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(entitlements))
@@ -284,7 +248,7 @@ pub async fn list_entitlements(
 
 /// GET /entitlements/:feature_key — check a specific entitlement.
 pub async fn check_entitlement(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(feature_key): Path<String>,
 ) -> AppResult<Json<Entitlement>> {
@@ -296,7 +260,7 @@ pub async fn check_entitlement(
     )
     .bind(claims.tid)
     .bind(&feature_key)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Entitlement not found".to_string()))?;
 
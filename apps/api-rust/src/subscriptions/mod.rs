@@ -1,2 +1,27 @@
+pub mod entitlements;
 pub mod handler;
 pub mod model;
+pub mod trials;
+
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/plans", get(handler::list_plans))
+        .route("/subscription", get(handler::get_current_subscription))
+        .route("/subscription", post(handler::create_subscription))
+        .route("/subscription/plan", put(handler::change_plan))
+        .route("/subscription/cancel", post(handler::cancel_subscription))
+        .route("/subscription/usage", get(handler::get_usage))
+        .route("/entitlements", get(handler::list_entitlements))
+        .route(
+            "/entitlements/{feature_key}",
+            get(handler::check_entitlement),
+        )
+}
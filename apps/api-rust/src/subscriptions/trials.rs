@@ -0,0 +1,73 @@
+//! Downgrade of trials that ran out without being converted.
+
+use uuid::Uuid;
+
+use crate::scheduler::{active_tenant_ids, tenant_tx, Job};
+use crate::subscriptions::entitlements::sync_plan;
+use crate::subscriptions::model::Plan;
+use crate::AppState;
+
+pub const JOB_TYPE: &str = "subscriptions.expire_trials";
+
+/// Move expired trials onto the free plan, or suspend them when there is
+/// none. Trials billed through Stripe are left to its webhooks.
+pub async fn expire_trials(state: AppState, _job: Job) -> anyhow::Result<()> {
+    let free_plan: Option<Plan> = sqlx::query_as(
+        "SELECT id, name, slug, description, price_monthly_cents, price_annual_cents, \
+         max_jobs, max_users, max_candidates, features, is_active, \
+         stripe_price_id_monthly, stripe_price_id_annual, sort_order, \
+         created_at, updated_at \
+         FROM plans WHERE is_active = true AND price_monthly_cents = 0 \
+         ORDER BY sort_order ASC LIMIT 1",
+    )
+    .fetch_optional(&state.db)
+    .await?;
+
+    for tenant_id in active_tenant_ids(&state.db).await? {
+        let mut tx = tenant_tx(&state.db, tenant_id).await?;
+
+        let expired: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM subscriptions \
+             WHERE tenant_id = $1 AND status = 'trialing' AND trial_ends_at < NOW() \
+               AND stripe_subscription_id IS NULL \
+             FOR UPDATE",
+        )
+        .bind(tenant_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for subscription_id in expired {
+            match &free_plan {
+                Some(plan) => {
+                    sqlx::query(
+                        "UPDATE subscriptions SET status = 'active', plan_id = $3, \
+                         seats_limit = COALESCE($4, seats_limit), updated_at = NOW() \
+                         WHERE id = $1 AND tenant_id = $2",
+                    )
+                    .bind(subscription_id)
+                    .bind(tenant_id)
+                    .bind(plan.id)
+                    .bind(plan.max_users.filter(|v| *v >= 0))
+                    .execute(&mut *tx)
+                    .await?;
+                    sync_plan(&mut tx, tenant_id, subscription_id, plan).await?;
+                    tracing::info!(%tenant_id, plan = %plan.slug, "Trial expired; downgraded");
+                }
+                None => {
+                    sqlx::query(
+                        "UPDATE subscriptions SET status = 'suspended', updated_at = NOW() \
+                         WHERE id = $1 AND tenant_id = $2",
+                    )
+                    .bind(subscription_id)
+                    .bind(tenant_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    tracing::info!(%tenant_id, "Trial expired; no free plan, suspended");
+                }
+            }
+        }
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
@@ -1,13 +1,9 @@
-use crate::AppState;
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::Path, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 
 use crate::auth::Claims;
 use crate::errors::AppError;
+use crate::middleware::tenant::TenantTx;
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct TalentPool {
@@ -48,7 +44,7 @@ pub struct AddMember {
 
 pub async fn list_pools(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
 ) -> Result<Json<Vec<TalentPool>>, AppError> {
     let pools = sqlx::query_as::<_, TalentPool>(
         "SELECT tp.id::text, tp.name, tp.description, tp.pool_type, tp.created_at,
@@ -59,7 +55,7 @@ pub async fn list_pools(
          GROUP BY tp.id ORDER BY tp.name",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -68,7 +64,7 @@ pub async fn list_pools(
 
 pub async fn create_pool(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Json(body): Json<CreatePool>,
 ) -> Result<(StatusCode, Json<TalentPool>), AppError> {
     let tp = sqlx::query_as::<_, TalentPool>(
@@ -81,7 +77,7 @@ pub async fn create_pool(
     .bind(&body.description)
     .bind(body.pool_type.unwrap_or_else(|| "custom".to_string()))
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -90,7 +86,7 @@ pub async fn create_pool(
 
 pub async fn list_members(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Path(pool_id): Path<String>,
 ) -> Result<Json<Vec<PoolMember>>, AppError> {
     let members = sqlx::query_as::<_, PoolMember>(
@@ -104,7 +100,7 @@ pub async fn list_members(
     )
     .bind(&pool_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
@@ -113,7 +109,7 @@ pub async fn list_members(
 
 pub async fn add_member(
     claims: Claims,
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Path(pool_id): Path<String>,
     Json(body): Json<AddMember>,
 ) -> Result<(StatusCode, Json<PoolMember>), AppError> {
@@ -130,7 +126,7 @@ pub async fn add_member(
     .bind(&body.source)
     .bind(&body.notes)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::Database)?;
 
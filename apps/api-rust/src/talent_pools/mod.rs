@@ -1 +1,19 @@
 pub mod handler;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::subscriptions::entitlements::require_feature;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/talent-pools", get(handler::list_pools))
+        .route("/talent-pools", post(handler::create_pool))
+        .route("/talent-pools/{id}/members", get(handler::list_members))
+        .route("/talent-pools/{id}/members", post(handler::add_member))
+        .route_layer(require_feature("talent_pools"))
+}
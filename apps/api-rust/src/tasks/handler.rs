@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -9,11 +9,11 @@ use validator::Validate;
 use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::tasks::model::*;
-use crate::AppState;
 
 pub async fn list_tasks(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListTasksQuery>,
 ) -> AppResult<Json<PaginatedResponse<Task>>> {
@@ -81,7 +81,7 @@ pub async fn list_tasks(
     if let Some(client_id) = params.client_id {
         count_q = count_q.bind(client_id);
     }
-    let (total,) = count_q.fetch_one(&state.db).await?;
+    let (total,) = count_q.fetch_one(&mut *tx).await?;
 
     // Build data query with dynamic binds
     let mut data_q = sqlx::query_as::<_, Task>(&data_sql).bind(claims.tid);
@@ -103,7 +103,7 @@ pub async fn list_tasks(
     let tasks = data_q
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?;
 
     let total_pages = (total as f64 / per_page as f64).ceil() as i64;
@@ -120,7 +120,7 @@ pub async fn list_tasks(
 }
 
 pub async fn get_task(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(task_id): Path<Uuid>,
 ) -> AppResult<Json<Task>> {
@@ -129,7 +129,7 @@ pub async fn get_task(
     )
     .bind(task_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Task not found".to_string()))?;
 
@@ -137,7 +137,7 @@ pub async fn get_task(
 }
 
 pub async fn create_task(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateTaskRequest>,
 ) -> AppResult<(StatusCode, Json<Task>)> {
@@ -169,14 +169,14 @@ pub async fn create_task(
     .bind(payload.workflow_instance_id)
     .bind(payload.workflow_step_index)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(task)))
 }
 
 pub async fn update_task(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(task_id): Path<Uuid>,
     Json(payload): Json<UpdateTaskRequest>,
@@ -187,7 +187,7 @@ pub async fn update_task(
     )
     .bind(task_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Task not found".to_string()))?;
 
@@ -242,14 +242,14 @@ pub async fn update_task(
     .bind(due_date)
     .bind(sort_order)
     .bind(completed_at)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(task))
 }
 
 pub async fn delete_task(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(task_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -258,7 +258,7 @@ pub async fn delete_task(
     )
     .bind(task_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -271,7 +271,7 @@ pub async fn delete_task(
 // ── Bulk Operations ──────────────────────────────────────────────────
 
 pub async fn bulk_update_tasks(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkTaskUpdateRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -327,7 +327,7 @@ pub async fn bulk_update_tasks(
     .bind(payload.status.as_deref())
     .bind(payload.assigned_to)
     .bind(payload.priority.as_deref())
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -337,7 +337,7 @@ pub async fn bulk_update_tasks(
 }
 
 pub async fn bulk_delete_tasks(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<BulkTaskIdsRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -357,7 +357,7 @@ pub async fn bulk_delete_tasks(
     )
     .bind(claims.tid)
     .bind(&payload.ids)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -1,2 +1,21 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/tasks", get(handler::list_tasks))
+        .route("/tasks", post(handler::create_task))
+        .route("/tasks/{id}", get(handler::get_task))
+        .route("/tasks/{id}", put(handler::update_task))
+        .route("/tasks/{id}", delete(handler::delete_task))
+        .route("/tasks/bulk-update", post(handler::bulk_update_tasks))
+        .route("/tasks/bulk-delete", post(handler::bulk_delete_tasks))
+}
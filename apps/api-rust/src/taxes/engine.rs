@@ -0,0 +1,303 @@
+//! Computing the tax an invoice carries.
+//!
+//! Each line is taxed on its own by every applicable rate, in priority
+//! order, and rounded to a whole cent (half away from zero) on the spot.
+//! The invoice's tax is then the plain sum of its lines' tax, and the
+//! per-rate breakdown sums to the same figure, so no cent is ever lost or
+//! invented between the lines, the breakdown and the total.
+//!
+//! A simple rate taxes the line amount. A compound rate taxes the line
+//! amount plus the tax the rates before it have already put on that line.
+
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::model::{TaxRate, TAX_RATE_COLUMNS};
+use crate::error::{AppError, AppResult};
+
+/// `rate_ppm` is in parts per million of the taxable amount.
+pub const PPM: i64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaxableLine<'a> {
+    pub amount_cents: i64,
+    pub service_type: Option<&'a str>,
+}
+
+/// What one rate contributed across all lines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RateTotal {
+    pub tax_rate_id: Uuid,
+    pub name: String,
+    pub jurisdiction: Option<String>,
+    pub rate_ppm: i32,
+    pub is_compound: bool,
+    pub taxable_cents: i64,
+    pub tax_cents: i64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Calculation {
+    /// Tax on each line, in the order the lines were given.
+    pub line_tax_cents: Vec<i64>,
+    /// Rates that taxed at least one line, in the order applied.
+    pub rates: Vec<RateTotal>,
+    pub tax_cents: i64,
+}
+
+/// `amount_cents` at `rate_ppm`, rounded half away from zero.
+pub fn tax_on(amount_cents: i64, rate_ppm: i32) -> i64 {
+    let product = amount_cents as i128 * rate_ppm as i128;
+    let half = (PPM / 2) as i128;
+    let rounded = if product >= 0 {
+        (product + half) / PPM as i128
+    } else {
+        (product - half) / PPM as i128
+    };
+    rounded as i64
+}
+
+/// Tax `lines` with `rates`, which must already be in priority order and
+/// limited to those that apply to the invoice's client.
+pub fn calculate(rates: &[TaxRate], lines: &[TaxableLine]) -> Calculation {
+    let mut totals: Vec<Option<RateTotal>> = vec![None; rates.len()];
+    let mut line_tax_cents = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        let mut line_tax = 0;
+        for (rate, total) in rates.iter().zip(totals.iter_mut()) {
+            let exempt = line.service_type.is_some_and(|s| {
+                rate.exempt_service_types
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(s))
+            });
+            if exempt {
+                continue;
+            }
+            let base = if rate.is_compound {
+                line.amount_cents + line_tax
+            } else {
+                line.amount_cents
+            };
+            let tax = tax_on(base, rate.rate_ppm);
+            line_tax += tax;
+            let total = total.get_or_insert_with(|| RateTotal {
+                tax_rate_id: rate.id,
+                name: rate.name.clone(),
+                jurisdiction: rate.jurisdiction.clone(),
+                rate_ppm: rate.rate_ppm,
+                is_compound: rate.is_compound,
+                taxable_cents: 0,
+                tax_cents: 0,
+            });
+            total.taxable_cents += base;
+            total.tax_cents += tax;
+        }
+        line_tax_cents.push(line_tax);
+    }
+
+    Calculation {
+        tax_cents: line_tax_cents.iter().sum(),
+        line_tax_cents,
+        rates: totals.into_iter().flatten().collect(),
+    }
+}
+
+/// Active rates that apply to `client_id`, in the order they are applied.
+/// A rate with a jurisdiction applies only when it matches the state in the
+/// client's address.
+pub async fn rates_for_client(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    client_id: Uuid,
+) -> AppResult<Vec<TaxRate>> {
+    let rates = sqlx::query_as(&format!(
+        "SELECT {} FROM tax_rates r \
+         WHERE r.tenant_id = $1 AND r.is_active AND NOT ($2 = ANY(r.exempt_client_ids)) \
+         AND (COALESCE(TRIM(r.jurisdiction), '') = '' OR EXISTS ( \
+             SELECT 1 FROM clients c WHERE c.id = $2 AND c.tenant_id = $1 \
+             AND UPPER(TRIM(c.address->>'state')) = UPPER(TRIM(r.jurisdiction)))) \
+         ORDER BY r.priority, r.created_at",
+        TAX_RATE_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(client_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rates)
+}
+
+/// The tax `client_id` would be charged on `lines`, without storing it.
+pub async fn estimate(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    client_id: Uuid,
+    lines: &[TaxableLine<'_>],
+) -> AppResult<i64> {
+    let rates = rates_for_client(conn, tenant_id, client_id).await?;
+    Ok(calculate(&rates, lines).tax_cents)
+}
+
+/// Tax the invoice's current line items: store each line's tax and the
+/// per-rate breakdown, and update the invoice's tax and total. Returns the
+/// invoice's tax.
+pub async fn apply_to_invoice(
+    conn: &mut PgConnection,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+) -> AppResult<i64> {
+    let client_id: Uuid =
+        sqlx::query_scalar("SELECT client_id FROM invoices WHERE id = $1 AND tenant_id = $2")
+            .bind(invoice_id)
+            .bind(tenant_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+
+    let rows: Vec<(Uuid, i64, Option<String>)> = sqlx::query_as(
+        "SELECT id, total_cents, service_type FROM invoice_line_items \
+         WHERE invoice_id = $1 AND tenant_id = $2 ORDER BY sort_order",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let lines: Vec<TaxableLine> = rows
+        .iter()
+        .map(|(_, amount_cents, service_type)| TaxableLine {
+            amount_cents: *amount_cents,
+            service_type: service_type.as_deref(),
+        })
+        .collect();
+
+    let rates = rates_for_client(conn, tenant_id, client_id).await?;
+    let calc = calculate(&rates, &lines);
+
+    let ids: Vec<Uuid> = rows.iter().map(|(id, ..)| *id).collect();
+    sqlx::query(
+        "UPDATE invoice_line_items l SET tax_cents = t.tax_cents \
+         FROM UNNEST($1::uuid[], $2::bigint[]) AS t(id, tax_cents) \
+         WHERE l.id = t.id AND l.tenant_id = $3",
+    )
+    .bind(&ids)
+    .bind(&calc.line_tax_cents)
+    .bind(tenant_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM invoice_taxes WHERE invoice_id = $1 AND tenant_id = $2")
+        .bind(invoice_id)
+        .bind(tenant_id)
+        .execute(&mut *conn)
+        .await?;
+    for (i, rate) in calc.rates.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO invoice_taxes (tenant_id, invoice_id, tax_rate_id, name, jurisdiction, rate_ppm, is_compound, taxable_cents, tax_cents, sort_order) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(tenant_id)
+        .bind(invoice_id)
+        .bind(rate.tax_rate_id)
+        .bind(&rate.name)
+        .bind(rate.jurisdiction.as_deref())
+        .bind(rate.rate_ppm)
+        .bind(rate.is_compound)
+        .bind(rate.taxable_cents)
+        .bind(rate.tax_cents)
+        .bind(i as i32)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        "UPDATE invoices SET tax_cents = $3, total_cents = subtotal_cents + $3, updated_at = NOW() \
+         WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(invoice_id)
+    .bind(tenant_id)
+    .bind(calc.tax_cents)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(calc.tax_cents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(name: &str, rate_ppm: i32, is_compound: bool, exempt: &[&str]) -> TaxRate {
+        TaxRate {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::nil(),
+            name: name.to_string(),
+            jurisdiction: None,
+            rate_ppm,
+            rate_percent: rate_ppm as f64 / 10_000.0,
+            is_compound,
+            priority: 0,
+            exempt_service_types: exempt.iter().map(|s| s.to_string()).collect(),
+            exempt_client_ids: Vec::new(),
+            is_active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn line(amount_cents: i64, service_type: &str) -> TaxableLine<'_> {
+        TaxableLine {
+            amount_cents,
+            service_type: Some(service_type),
+        }
+    }
+
+    #[test]
+    fn test_rounds_half_away_from_zero() {
+        // 8.875% of $1.00 is 8.875 cents.
+        assert_eq!(tax_on(100, 88_750), 9);
+        // 5% of 10 cents is exactly half a cent.
+        assert_eq!(tax_on(10, 50_000), 1);
+        assert_eq!(tax_on(-10, 50_000), -1);
+        assert_eq!(tax_on(9, 50_000), 0);
+        assert_eq!(tax_on(i64::MAX / 2, PPM as i32), i64::MAX / 2);
+    }
+
+    #[test]
+    fn test_compound_rates_tax_earlier_taxes_and_lines_sum_exactly() {
+        let gst = rate("GST", 50_000, false, &[]);
+        let qst = rate("QST", 99_750, true, &["expense"]);
+        let lines = [
+            line(10_000, "consulting"),
+            line(333, "consulting"),
+            line(4_250, "expense"),
+        ];
+        let calc = calculate(&[gst.clone(), qst.clone()], &lines);
+
+        // $100.00: GST 500, QST on 10500 = 1047.375 -> 1047.
+        // $3.33: GST 16.65 -> 17, QST on 350 = 34.9125 -> 35.
+        // Expenses only carry GST: 212.5 -> 213.
+        assert_eq!(calc.line_tax_cents, vec![1547, 52, 213]);
+        assert_eq!(calc.tax_cents, 1812);
+        assert_eq!(calc.rates.len(), 2);
+        assert_eq!(calc.rates[0].taxable_cents, 14_583);
+        assert_eq!(calc.rates[0].tax_cents, 730);
+        assert_eq!(calc.rates[1].taxable_cents, 10_500 + 350);
+        assert_eq!(calc.rates[1].tax_cents, 1082);
+        assert_eq!(
+            calc.rates.iter().map(|r| r.tax_cents).sum::<i64>(),
+            calc.tax_cents
+        );
+
+        // A rate that taxes nothing is left out of the breakdown.
+        let calc = calculate(&[qst], &[line(100, "Expense")]);
+        assert_eq!(
+            calc,
+            Calculation {
+                line_tax_cents: vec![0],
+                rates: Vec::new(),
+                tax_cents: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use axum::{extract::Path, http::StatusCode, Json};
+use uuid::Uuid;
+
+use super::model::*;
+use crate::auth::Claims;
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::require_role;
+use crate::middleware::tenant::TenantTx;
+
+/// `percent` as parts per million, refusing more precision than a
+/// `rate_ppm` can hold.
+fn rate_ppm(percent: f64) -> AppResult<i32> {
+    if !percent.is_finite() || !(0.0..=100.0).contains(&percent) {
+        return Err(AppError::Validation(
+            "rate_percent must be between 0 and 100".to_string(),
+        ));
+    }
+    let ppm = (percent * 10_000.0).round();
+    if (ppm - percent * 10_000.0).abs() > 1e-6 {
+        return Err(AppError::Validation(
+            "rate_percent allows at most four decimal places".to_string(),
+        ));
+    }
+    Ok(ppm as i32)
+}
+
+fn clean_service_types(types: &[String]) -> Vec<String> {
+    types
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Blank jurisdictions apply everywhere and are stored as NULL.
+fn clean_jurisdiction(jurisdiction: &str) -> Option<String> {
+    Some(jurisdiction.trim().to_string()).filter(|j| !j.is_empty())
+}
+
+pub async fn list_tax_rates(claims: Claims, mut tx: TenantTx) -> AppResult<Json<Vec<TaxRate>>> {
+    let rates: Vec<TaxRate> = sqlx::query_as(&format!(
+        "SELECT {} FROM tax_rates r WHERE r.tenant_id = $1 \
+         ORDER BY r.is_active DESC, r.priority, r.created_at",
+        TAX_RATE_COLUMNS
+    ))
+    .bind(claims.tid)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(Json(rates))
+}
+
+pub async fn create_tax_rate(
+    claims: Claims,
+    mut tx: TenantTx,
+    Json(body): Json<CreateTaxRateRequest>,
+) -> AppResult<(StatusCode, Json<TaxRate>)> {
+    require_role(&claims, "admin")?;
+    if body.name.trim().is_empty() {
+        return Err(AppError::Validation("name is required".to_string()));
+    }
+    let rate_ppm = rate_ppm(body.rate_percent)?;
+
+    let rate: TaxRate = sqlx::query_as(&format!(
+        "WITH r AS ( \
+             INSERT INTO tax_rates (tenant_id, name, jurisdiction, rate_ppm, is_compound, priority, \
+                 exempt_service_types, exempt_client_ids, is_active) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING * \
+         ) SELECT {} FROM r",
+        TAX_RATE_COLUMNS
+    ))
+    .bind(claims.tid)
+    .bind(body.name.trim())
+    .bind(body.jurisdiction.as_deref().and_then(clean_jurisdiction))
+    .bind(rate_ppm)
+    .bind(body.is_compound.unwrap_or(false))
+    .bind(body.priority.unwrap_or(0))
+    .bind(clean_service_types(
+        body.exempt_service_types.as_deref().unwrap_or_default(),
+    ))
+    .bind(body.exempt_client_ids.unwrap_or_default())
+    .bind(body.is_active.unwrap_or(true))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(rate)))
+}
+
+/// Update a rate. Invoices already taxed keep the breakdown they were
+/// computed with until they are next edited.
+pub async fn update_tax_rate(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateTaxRateRequest>,
+) -> AppResult<Json<TaxRate>> {
+    require_role(&claims, "admin")?;
+    if body.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::Validation("name must not be empty".to_string()));
+    }
+    let rate_ppm = body.rate_percent.map(rate_ppm).transpose()?;
+
+    let rate: TaxRate = sqlx::query_as(&format!(
+        "WITH r AS ( \
+             UPDATE tax_rates SET \
+             name = COALESCE($3, name), \
+             jurisdiction = CASE WHEN $4 THEN $5 ELSE jurisdiction END, \
+             rate_ppm = COALESCE($6, rate_ppm), \
+             is_compound = COALESCE($7, is_compound), \
+             priority = COALESCE($8, priority), \
+             exempt_service_types = COALESCE($9, exempt_service_types), \
+             exempt_client_ids = COALESCE($10, exempt_client_ids), \
+             is_active = COALESCE($11, is_active), \
+             updated_at = NOW() \
+             WHERE id = $1 AND tenant_id = $2 RETURNING * \
+         ) SELECT {} FROM r",
+        TAX_RATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.tid)
+    .bind(body.name.as_deref().map(str::trim))
+    .bind(body.jurisdiction.is_some())
+    .bind(body.jurisdiction.as_deref().and_then(clean_jurisdiction))
+    .bind(rate_ppm)
+    .bind(body.is_compound)
+    .bind(body.priority)
+    .bind(
+        body.exempt_service_types
+            .as_deref()
+            .map(clean_service_types),
+    )
+    .bind(body.exempt_client_ids)
+    .bind(body.is_active)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Tax rate not found".to_string()))?;
+
+    Ok(Json(rate))
+}
+
+/// Delete a rate. Invoice breakdowns that used it keep their figures.
+pub async fn delete_tax_rate(
+    claims: Claims,
+    mut tx: TenantTx,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    require_role(&claims, "admin")?;
+    let result = sqlx::query("DELETE FROM tax_rates WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(claims.tid)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Tax rate not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,27 @@
+//! Tenant-configured tax rates and the [`engine`] that applies them to
+//! invoices.
+//!
+//! A rate applies to every client unless it names a jurisdiction, which is
+//! matched against the state in the client's address. Rates can exempt
+//! individual clients and service types; lines billed from expenses carry
+//! the service type `expense`.
+
+pub mod engine;
+pub mod handler;
+pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/tax-rates", get(handler::list_tax_rates))
+        .route("/tax-rates", post(handler::create_tax_rate))
+        .route("/tax-rates/{id}", put(handler::update_tax_rate))
+        .route("/tax-rates/{id}", delete(handler::delete_tax_rate))
+}
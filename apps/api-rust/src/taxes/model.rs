@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const TAX_RATE_COLUMNS: &str = "r.id, r.tenant_id, r.name, r.jurisdiction, r.rate_ppm, \
+    r.rate_ppm / 10000.0::float8 AS rate_percent, r.is_compound, r.priority, \
+    r.exempt_service_types, r.exempt_client_ids, r.is_active, r.created_at, r.updated_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TaxRate {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    /// State the rate applies in; `None` applies to every client.
+    pub jurisdiction: Option<String>,
+    /// Parts per million of the taxable amount: 8.875% is 88750.
+    pub rate_ppm: i32,
+    pub rate_percent: f64,
+    /// Also taxes the tax added by the rates applied before it.
+    pub is_compound: bool,
+    /// Rates apply in ascending priority.
+    pub priority: i32,
+    pub exempt_service_types: Vec<String>,
+    pub exempt_client_ids: Vec<Uuid>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTaxRateRequest {
+    pub name: String,
+    pub jurisdiction: Option<String>,
+    /// Up to four decimal places, e.g. 8.875.
+    pub rate_percent: f64,
+    pub is_compound: Option<bool>,
+    pub priority: Option<i32>,
+    pub exempt_service_types: Option<Vec<String>>,
+    pub exempt_client_ids: Option<Vec<Uuid>>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTaxRateRequest {
+    pub name: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub rate_percent: Option<f64>,
+    pub is_compound: Option<bool>,
+    pub priority: Option<i32>,
+    pub exempt_service_types: Option<Vec<String>>,
+    pub exempt_client_ids: Option<Vec<Uuid>>,
+    pub is_active: Option<bool>,
+}
+
+/// One rate's share of an invoice's tax.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InvoiceTax {
+    pub tax_rate_id: Option<Uuid>,
+    pub name: String,
+    pub jurisdiction: Option<String>,
+    pub rate_ppm: i32,
+    pub is_compound: bool,
+    pub taxable_cents: i64,
+    pub tax_cents: i64,
+}
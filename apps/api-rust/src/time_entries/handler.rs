@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -9,11 +9,11 @@ use validator::Validate;
 use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::time_entries::model::*;
-use crate::AppState;
 
 pub async fn list_time_entries(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListTimeEntriesQuery>,
 ) -> AppResult<Json<PaginatedResponse<TimeEntry>>> {
@@ -46,12 +46,12 @@ pub async fn list_time_entries(
         )
         .bind(claims.tid)
         .bind(pattern)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as("SELECT COUNT(*) FROM time_entries WHERE tenant_id = $1")
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
     };
 
@@ -63,7 +63,7 @@ pub async fn list_time_entries(
         .bind(pattern)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -72,7 +72,7 @@ pub async fn list_time_entries(
         .bind(claims.tid)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -90,7 +90,7 @@ pub async fn list_time_entries(
 }
 
 pub async fn create_time_entry(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateTimeEntryRequest>,
 ) -> AppResult<(StatusCode, Json<TimeEntry>)> {
@@ -134,14 +134,14 @@ pub async fn create_time_entry(
     .bind(is_running)
     .bind(started_at)
     .bind(date)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(entry)))
 }
 
 pub async fn stop_timer(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(entry_id): Path<Uuid>,
 ) -> AppResult<Json<TimeEntry>> {
@@ -150,7 +150,7 @@ pub async fn stop_timer(
     )
     .bind(entry_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Running timer not found".to_string()))?;
 
@@ -170,14 +170,14 @@ pub async fn stop_timer(
     .bind(claims.tid)
     .bind(now)
     .bind(elapsed_minutes)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(entry))
 }
 
 pub async fn update_time_entry(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(entry_id): Path<Uuid>,
     Json(payload): Json<UpdateTimeEntryRequest>,
@@ -209,7 +209,7 @@ pub async fn update_time_entry(
     .bind(payload.rate_cents)
     .bind(payload.is_billable)
     .bind(payload.date)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Time entry not found or already invoiced".to_string()))?;
 
@@ -217,7 +217,7 @@ pub async fn update_time_entry(
 }
 
 pub async fn delete_time_entry(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(entry_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -226,7 +226,7 @@ pub async fn delete_time_entry(
     )
     .bind(entry_id)
     .bind(claims.tid)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -1,2 +1,19 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/time-entries", get(handler::list_time_entries))
+        .route("/time-entries", post(handler::create_time_entry))
+        .route("/time-entries/{id}", put(handler::update_time_entry))
+        .route("/time-entries/{id}/stop", post(handler::stop_timer))
+        .route("/time-entries/{id}", delete(handler::delete_time_entry))
+}
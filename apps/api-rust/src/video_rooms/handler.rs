@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -7,8 +7,8 @@ use uuid::Uuid;
 
 use crate::auth::jwt::Claims;
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::video_rooms::model::*;
-use crate::AppState;
 
 /// Generate an 8-character alphanumeric room code.
 fn generate_room_code() -> String {
@@ -24,7 +24,7 @@ fn generate_room_code() -> String {
 }
 
 pub async fn create_room(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateRoomRequest>,
 ) -> AppResult<(StatusCode, Json<VideoRoom>)> {
@@ -47,14 +47,14 @@ pub async fn create_room(
     .bind(max_participants)
     .bind(recording_enabled)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(room)))
 }
 
 pub async fn list_rooms(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListRoomsQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
@@ -75,7 +75,7 @@ pub async fn list_rooms(
     .bind(per_page)
     .bind(offset)
     .bind(status_filter)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     let (total,): (i64,) = sqlx::query_as(
@@ -84,7 +84,7 @@ pub async fn list_rooms(
     )
     .bind(claims.tid)
     .bind(status_filter)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok(Json(serde_json::json!({
@@ -99,7 +99,7 @@ pub async fn list_rooms(
 }
 
 pub async fn get_room(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<VideoRoom>> {
@@ -111,7 +111,7 @@ pub async fn get_room(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Video room not found".to_string()))?;
 
@@ -119,7 +119,7 @@ pub async fn get_room(
 }
 
 pub async fn join_room(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -132,7 +132,7 @@ pub async fn join_room(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Video room not found".to_string()))?;
 
@@ -153,7 +153,7 @@ pub async fn join_room(
     .bind(claims.sub)
     .bind(&token_hash)
     .bind(expires_at)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // If room is in 'created' state, activate it
@@ -164,7 +164,7 @@ pub async fn join_room(
         )
         .bind(id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
     }
 
@@ -179,7 +179,7 @@ pub async fn join_room(
 }
 
 pub async fn end_room(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<VideoRoom>> {
@@ -192,7 +192,7 @@ pub async fn end_room(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Video room not found".to_string()))?;
 
@@ -208,7 +208,7 @@ pub async fn end_room(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Create session record
@@ -223,14 +223,14 @@ pub async fn end_room(
     .bind(room.ended_at)
     .bind(duration_seconds)
     .bind(participant_count as i32)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     Ok(Json(room))
 }
 
 pub async fn list_sessions(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<Vec<VideoSession>>> {
@@ -243,7 +243,7 @@ pub async fn list_sessions(
     )
     .bind(id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(sessions))
@@ -1,2 +1,22 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::subscriptions::entitlements::require_feature;
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/video-rooms", get(handler::list_rooms))
+        .route("/video-rooms", post(handler::create_room))
+        .route("/video-rooms/{id}", get(handler::get_room))
+        .route("/video-rooms/{id}/join", post(handler::join_room))
+        .route("/video-rooms/{id}/end", post(handler::end_room))
+        .route("/video-rooms/{id}/sessions", get(handler::list_sessions))
+        .route_layer(require_feature("video_interviews"))
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Query, State},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
@@ -9,27 +9,27 @@ use validator::Validate;
 use crate::auth::jwt::Claims;
 use crate::clients::model::{PaginatedResponse, PaginationMeta};
 use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantTx;
 use crate::workflows::model::*;
-use crate::AppState;
 
 // --- Templates ---
 
 pub async fn list_templates(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
 ) -> AppResult<Json<Vec<WorkflowTemplate>>> {
     let templates: Vec<WorkflowTemplate> = sqlx::query_as(
         "SELECT id, tenant_id, name, description, category, steps, is_active, created_by, created_at, updated_at FROM workflow_templates WHERE tenant_id = $1 AND is_active = true ORDER BY name",
     )
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(templates))
 }
 
 pub async fn create_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateTemplateRequest>,
 ) -> AppResult<(StatusCode, Json<WorkflowTemplate>)> {
@@ -46,7 +46,7 @@ pub async fn create_template(
     .bind(payload.category.as_deref())
     .bind(&payload.steps)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     Ok((StatusCode::CREATED, Json(template)))
@@ -55,7 +55,7 @@ pub async fn create_template(
 // --- Instances ---
 
 pub async fn list_instances(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Query(params): Query<ListWorkflowsQuery>,
 ) -> AppResult<Json<PaginatedResponse<WorkflowInstance>>> {
@@ -74,12 +74,12 @@ pub async fn list_instances(
         )
         .bind(claims.tid)
         .bind(pattern)
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await?
     } else {
         sqlx::query_as("SELECT COUNT(*) FROM workflow_instances WHERE tenant_id = $1")
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?
     };
 
@@ -91,7 +91,7 @@ pub async fn list_instances(
         .bind(pattern)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     } else {
         sqlx::query_as(
@@ -100,7 +100,7 @@ pub async fn list_instances(
         .bind(claims.tid)
         .bind(per_page)
         .bind(offset)
-        .fetch_all(&state.db)
+        .fetch_all(&mut *tx)
         .await?
     };
 
@@ -118,7 +118,7 @@ pub async fn list_instances(
 }
 
 pub async fn get_instance(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(instance_id): Path<Uuid>,
 ) -> AppResult<Json<WorkflowInstance>> {
@@ -127,7 +127,7 @@ pub async fn get_instance(
     )
     .bind(instance_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Workflow not found".to_string()))?;
 
@@ -135,7 +135,7 @@ pub async fn get_instance(
 }
 
 pub async fn create_instance(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<CreateInstanceRequest>,
 ) -> AppResult<(StatusCode, Json<WorkflowInstance>)> {
@@ -153,7 +153,7 @@ pub async fn create_instance(
     .bind(payload.due_date)
     .bind(payload.assigned_to)
     .bind(claims.sub)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Log the first step as started
@@ -162,7 +162,7 @@ pub async fn create_instance(
     )
     .bind(payload.template_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?;
 
     if let Some((steps_json,)) = steps {
@@ -179,7 +179,7 @@ pub async fn create_instance(
             .bind(instance.id)
             .bind(step_name)
             .bind(claims.sub)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
@@ -188,7 +188,7 @@ pub async fn create_instance(
 }
 
 pub async fn advance_step(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(instance_id): Path<Uuid>,
     Json(payload): Json<AdvanceStepRequest>,
@@ -208,7 +208,7 @@ pub async fn advance_step(
     )
     .bind(instance_id)
     .bind(claims.tid)
-    .fetch_optional(&state.db)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Workflow not found".to_string()))?;
 
@@ -221,7 +221,7 @@ pub async fn advance_step(
         sqlx::query_as("SELECT steps FROM workflow_templates WHERE id = $1 AND tenant_id = $2")
             .bind(instance.template_id)
             .bind(claims.tid)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await?;
 
     let empty_vec = vec![];
@@ -243,7 +243,7 @@ pub async fn advance_step(
     .bind(&payload.action)
     .bind(claims.sub)
     .bind(payload.notes.as_deref())
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     // Determine next step
@@ -263,7 +263,7 @@ pub async fn advance_step(
     .bind(claims.tid)
     .bind(next_index)
     .bind(new_status)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     // Log next step as started if not complete
@@ -282,7 +282,7 @@ pub async fn advance_step(
         .bind(next_index)
         .bind(next_step_name)
         .bind(claims.sub)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
     }
 
@@ -290,7 +290,7 @@ pub async fn advance_step(
 }
 
 pub async fn get_step_logs(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(instance_id): Path<Uuid>,
 ) -> AppResult<Json<Vec<WorkflowStepLog>>> {
@@ -299,14 +299,14 @@ pub async fn get_step_logs(
     )
     .bind(instance_id)
     .bind(claims.tid)
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await?;
 
     Ok(Json(logs))
 }
 
 pub async fn delete_instance(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(instance_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -314,13 +314,13 @@ pub async fn delete_instance(
     sqlx::query("DELETE FROM workflow_step_logs WHERE instance_id = $1 AND tenant_id = $2")
         .bind(instance_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     let result = sqlx::query("DELETE FROM workflow_instances WHERE id = $1 AND tenant_id = $2")
         .bind(instance_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -331,7 +331,7 @@ pub async fn delete_instance(
 }
 
 pub async fn delete_template(
-    State(state): State<AppState>,
+    mut tx: TenantTx,
     Extension(claims): Extension<Claims>,
     Path(template_id): Path<Uuid>,
 ) -> AppResult<StatusCode> {
@@ -341,7 +341,7 @@ pub async fn delete_template(
     )
     .bind(template_id)
     .bind(claims.tid)
-    .fetch_one(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
 
     if instance_count > 0 {
@@ -354,7 +354,7 @@ pub async fn delete_template(
     let result = sqlx::query("DELETE FROM workflow_templates WHERE id = $1 AND tenant_id = $2")
         .bind(template_id)
         .bind(claims.tid)
-        .execute(&state.db)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -1,2 +1,23 @@
 pub mod handler;
 pub mod model;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::AppState;
+
+/// Authenticated routes, nested under `/api/v1`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/workflow-templates", get(handler::list_templates))
+        .route("/workflow-templates", post(handler::create_template))
+        .route("/workflow-templates/{id}", delete(handler::delete_template))
+        .route("/workflows", get(handler::list_instances))
+        .route("/workflows", post(handler::create_instance))
+        .route("/workflows/{id}", get(handler::get_instance))
+        .route("/workflows/{id}", delete(handler::delete_instance))
+        .route("/workflows/{id}/advance", post(handler::advance_step))
+        .route("/workflows/{id}/logs", get(handler::get_step_logs))
+}
@@ -4,6 +4,8 @@ use axum::{
         Query, State,
     },
     response::Response,
+    routing::get,
+    Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -14,6 +16,11 @@ use tokio::time::{interval, Duration};
 use crate::auth::jwt::validate_token;
 use crate::AppState;
 
+/// WebSocket endpoint, nested under `/api/v1`. Authenticates via the `token` query parameter.
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/ws", get(ws_handler))
+}
+
 /// Shared broadcast channel for real-time events
 #[derive(Clone)]
 pub struct WsBroadcast {
@@ -21,6 +28,12 @@ pub struct WsBroadcast {
     rooms: Arc<RwLock<HashMap<String, HashSet<(uuid::Uuid, uuid::Uuid)>>>>,
 }
 
+impl Default for WsBroadcast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WsBroadcast {
     pub fn new() -> Self {
         let (tx, _) = broadcast::channel(1024);
@@ -119,6 +132,16 @@ pub enum WsEventPayload {
         invoice_number: String,
         amount_cents: i64,
     },
+    #[serde(rename = "application_moved")]
+    ApplicationMoved {
+        id: uuid::Uuid,
+        job_id: uuid::Uuid,
+        candidate_id: uuid::Uuid,
+        from_stage: String,
+        to_stage: String,
+        status: String,
+        moved_by: uuid::Uuid,
+    },
     #[serde(rename = "ping")]
     Ping,
 
@@ -0,0 +1,266 @@
+//! Multi-step approval chains and the offer gate. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::approvals::reminders;
+use cpa_backend::scheduler::Job;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    manager_id: Uuid,
+    partner_id: Uuid,
+    offer_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let manager_id = common::seed_user(db, tenant_id, "Mary", "manager").await;
+    let partner_id = common::seed_user(db, tenant_id, "Paul", "partner").await;
+    let candidate_user = common::seed_user(db, tenant_id, "Ada", "client").await;
+
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, status) \
+         VALUES ($1, 'Senior Accountant', 'open') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let application_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO applications (tenant_id, job_id, candidate_id, stage) \
+         VALUES ($1, $2, $3, 'onsite') RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(job_id)
+    .bind(candidate_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let offer_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO offers (tenant_id, application_id, job_id, candidate_id, status, title, \
+         base_salary_cents, created_by) \
+         VALUES ($1, $2, $3, $4, 'draft', 'Senior Accountant', 9000000, $5) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(application_id)
+    .bind(job_id)
+    .bind(candidate_id)
+    .bind(fixture.admin_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    Some(Scenario {
+        fixture,
+        manager_id,
+        partner_id,
+        offer_id,
+    })
+}
+
+impl Scenario {
+    /// Two steps: any manager, then the partner.
+    async fn offer_workflow(&self) {
+        let (status, workflow) = self
+            .request(
+                "POST",
+                "/api/v1/approval-workflows",
+                json!({
+                    "name": "Offer sign-off",
+                    "workflow_type": "offer",
+                    "steps": [
+                        {"name": "Hiring manager", "role": "manager"},
+                        {"name": "Partner", "user_ids": [self.partner_id], "quorum": "all"},
+                    ],
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", workflow);
+    }
+
+    async fn raise(&self) -> String {
+        let (status, request) = self
+            .request(
+                "POST",
+                "/api/v1/approvals",
+                json!({
+                    "request_type": "offer",
+                    "title": "Offer for Ada",
+                    "entity_id": self.offer_id,
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", request);
+        assert_eq!(request["entity_type"], "offer");
+        request["id"].as_str().unwrap().to_string()
+    }
+
+    async fn decide(
+        &self,
+        user_id: Uuid,
+        role: &str,
+        id: &str,
+        decision: &str,
+    ) -> (StatusCode, Value) {
+        self.request_as(
+            user_id,
+            role,
+            "POST",
+            &format!("/api/v1/approvals/{}/decide", id),
+            json!({"decision": decision, "comment": "ok"}),
+        )
+        .await
+    }
+
+    async fn send_offer(&self) -> StatusCode {
+        self.request(
+            "POST",
+            &format!("/api/v1/offers/{}/send", self.offer_id),
+            json!({}),
+        )
+        .await
+        .0
+    }
+
+    async fn notifications(&self, user_id: Uuid) -> i64 {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM notifications \
+             WHERE user_id = $1 AND type = 'approval_requested'",
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn offer_is_sent_only_after_every_step_approves() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    f.offer_workflow().await;
+
+    assert_eq!(f.send_offer().await, StatusCode::CONFLICT);
+
+    let id = f.raise().await;
+    // Partners outrank managers, so both may decide the first step.
+    assert_eq!(f.notifications(f.manager_id).await, 1);
+    assert_eq!(f.notifications(f.partner_id).await, 1);
+
+    // The requester cannot approve their own request.
+    let (status, _) = f.decide(f.admin_id, "admin", &id, "approved").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, request) = f.decide(f.manager_id, "manager", &id, "approved").await;
+    assert_eq!(status, StatusCode::OK, "{}", request);
+    assert_eq!(request["status"], "pending");
+    assert_eq!(request["current_step"], 1);
+    assert_eq!(f.notifications(f.partner_id).await, 2);
+    assert_eq!(f.send_offer().await, StatusCode::CONFLICT);
+
+    // The manager is not on the partner step.
+    let (status, _) = f.decide(f.manager_id, "manager", &id, "approved").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, request) = f.decide(f.partner_id, "partner", &id, "approved").await;
+    assert_eq!(status, StatusCode::OK, "{}", request);
+    assert_eq!(request["status"], "approved");
+    assert!(request["resolved_at"].is_string());
+
+    let (_, detail) = f
+        .request("GET", &format!("/api/v1/approvals/{}", id), json!({}))
+        .await;
+    assert_eq!(detail["decisions"].as_array().unwrap().len(), 2);
+
+    assert_eq!(f.send_offer().await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rejection_blocks_the_offer_and_stale_steps_are_reminded() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    f.offer_workflow().await;
+    let id = f.raise().await;
+
+    sqlx::query(
+        "UPDATE approval_requests SET step_started_at = NOW() - INTERVAL '2 days' WHERE id = $1",
+    )
+    .bind(Uuid::parse_str(&id).unwrap())
+    .execute(&f.db)
+    .await
+    .unwrap();
+    let job = Job {
+        id: Uuid::new_v4(),
+        tenant_id: None,
+        job_type: reminders::JOB_TYPE.to_string(),
+        payload: json!({}),
+        attempts: 0,
+        max_attempts: 1,
+        run_at: chrono::Utc::now(),
+    };
+    reminders::remind_approvers(f.state.clone(), job)
+        .await
+        .unwrap();
+    assert_eq!(f.notifications(f.manager_id).await, 2);
+
+    let (status, request) = f.decide(f.manager_id, "manager", &id, "rejected").await;
+    assert_eq!(status, StatusCode::OK, "{}", request);
+    assert_eq!(request["status"], "rejected");
+
+    let (status, _) = f.decide(f.partner_id, "partner", &id, "approved").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(f.send_offer().await, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn concurrent_sends_and_publishes_go_through_once() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (a, b) = tokio::join!(f.send_offer(), f.send_offer());
+    let mut statuses = [a, b];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST]);
+
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, status) VALUES ($1, 'Controller', 'draft') RETURNING id",
+    )
+    .bind(f.tenant_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    let uri = format!("/api/v1/jobs/{}/publish", job_id);
+    let (a, b) = tokio::join!(
+        f.request("POST", &uri, json!({})),
+        f.request("POST", &uri, json!({})),
+    );
+    let mut statuses = [a.0, b.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST]);
+}
@@ -0,0 +1,437 @@
+//! Automation rules run against real events. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::automations::{engine, events};
+use cpa_backend::scheduler::Job;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    candidate_id: Uuid,
+    application_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+/// A tenant with one candidate who has applied to one job.
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let candidate_user = common::seed_user(db, tenant_id, "Ada", "client").await;
+
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, description) \
+         VALUES ($1, 'Rust Engineer', 'Build the platform') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let application_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO applications (tenant_id, job_id, candidate_id) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(job_id)
+    .bind(candidate_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    Some(Scenario {
+        fixture,
+        candidate_id,
+        application_id,
+    })
+}
+
+impl Scenario {
+    async fn rule(&self, name: &str, trigger: &str, conditions: Value, actions: Value) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO automation_rules (tenant_id, name, trigger_event, conditions, actions, created_by) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .bind(name)
+        .bind(trigger)
+        .bind(conditions)
+        .bind(actions)
+        .bind(self.admin_id)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    /// Run queued automation jobs, including those they queue, until none
+    /// are left. Returns how many ran.
+    async fn drain(&self) -> usize {
+        let mut ran = 0;
+        loop {
+            let jobs: Vec<Job> = sqlx::query_as(
+                "UPDATE background_jobs SET status = 'succeeded' \
+                 WHERE tenant_id = $1 AND job_type = $2 AND status = 'queued' \
+                 RETURNING id, tenant_id, job_type, payload, attempts, max_attempts, run_at",
+            )
+            .bind(self.tenant_id)
+            .bind(events::JOB_TYPE)
+            .fetch_all(&self.db)
+            .await
+            .unwrap();
+            if jobs.is_empty() {
+                return ran;
+            }
+            for job in jobs {
+                engine::run(self.state.clone(), job).await.unwrap();
+                ran += 1;
+            }
+        }
+    }
+
+    async fn log(&self, rule_id: Uuid) -> Vec<(String, Option<String>, Value)> {
+        sqlx::query_as(
+            "SELECT status, error_message, actions_taken FROM automation_log \
+             WHERE rule_id = $1 ORDER BY executed_at",
+        )
+        .bind(rule_id)
+        .fetch_all(&self.db)
+        .await
+        .unwrap()
+    }
+
+    async fn execution_count(&self, rule_id: Uuid) -> i32 {
+        sqlx::query_scalar("SELECT execution_count FROM automation_rules WHERE id = $1")
+            .bind(rule_id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn stage_change_runs_matching_rules_and_stops_loops() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let advance = f
+        .rule(
+            "Fast-track screened candidates",
+            "application.stage_changed",
+            json!({"field": "to_stage", "op": "eq", "value": "screening"}),
+            json!([
+                {"type": "move_stage", "to_stage": "onsite"},
+                {"type": "create_task", "title": "Book onsite ({{from_stage}} -> {{to_stage}})", "priority": "high"},
+                {"type": "notify_user", "title": "Candidate fast-tracked"},
+            ]),
+        )
+        .await;
+    // Sends the application back, which would re-trigger the first rule.
+    let bounce = f
+        .rule(
+            "Send onsite back to screening",
+            "application.stage_changed",
+            json!({"all": [
+                {"field": "to_stage", "op": "eq", "value": "onsite"},
+                {"field": "from_stage", "op": "neq", "value": "phone_screen"},
+            ]}),
+            json!([{"type": "move_stage", "to_stage": "screening"}]),
+        )
+        .await;
+
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/stage", f.application_id),
+            json!({"to_stage": "screening"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // screening (user) -> onsite (first rule) -> screening (second rule),
+    // where the first rule is skipped because it already ran in the chain.
+    assert_eq!(f.drain().await, 3);
+
+    let stage: String = sqlx::query_scalar("SELECT stage FROM applications WHERE id = $1")
+        .bind(f.application_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(stage, "screening");
+
+    let log = f.log(advance).await;
+    let statuses: Vec<&str> = log.iter().map(|(s, ..)| s.as_str()).collect();
+    assert_eq!(statuses, ["success", "skipped"]);
+    assert!(log[1].1.as_deref().unwrap().contains("Loop detected"));
+    assert_eq!(log[0].2[0]["to_stage"], "onsite");
+    assert_eq!(f.execution_count(advance).await, 1);
+    assert_eq!(f.log(bounce).await.len(), 1);
+    assert_eq!(f.execution_count(bounce).await, 1);
+
+    let task: (String, String) = sqlx::query_as(
+        "SELECT title, priority FROM tasks WHERE tenant_id = $1 AND created_by = $2",
+    )
+    .bind(f.tenant_id)
+    .bind(f.admin_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(
+        task,
+        ("Book onsite (applied -> screening)".into(), "high".into())
+    );
+
+    let notified: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND resource_id = $2",
+    )
+    .bind(f.admin_id)
+    .bind(f.application_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(notified, 1);
+}
+
+#[tokio::test]
+async fn failed_action_rolls_back_its_rule_only() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let pool_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO talent_pools (tenant_id, name, created_by) VALUES ($1, 'Silver medalists', $2) \
+         RETURNING id",
+    )
+    .bind(f.tenant_id)
+    .bind(f.admin_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    let pool = f
+        .rule(
+            "Pool new candidates",
+            "candidate.created",
+            json!({}),
+            json!([{"type": "add_to_talent_pool", "pool_id": pool_id}]),
+        )
+        .await;
+    let broken = f
+        .rule(
+            "Welcome email",
+            "candidate.created",
+            json!({}),
+            json!([
+                {"type": "create_task", "title": "Say hello"},
+                {"type": "send_email_template", "template_id": Uuid::new_v4()},
+            ]),
+        )
+        .await;
+
+    let event = events::DomainEvent::CandidateCreated {
+        candidate_id: f.candidate_id,
+        headline: None,
+        location_city: None,
+        location_state: None,
+        availability_status: None,
+    };
+    events::emit(&f.db, f.tenant_id, f.admin_id, event.clone())
+        .await
+        .unwrap();
+    events::emit(&f.db, f.tenant_id, f.admin_id, event)
+        .await
+        .unwrap();
+    assert_eq!(f.drain().await, 2);
+
+    let members: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT candidate_name, candidate_email FROM talent_pool_members WHERE pool_id = $1",
+    )
+    .bind(pool_id)
+    .fetch_all(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(members.len(), 1, "a candidate joins a pool once");
+    assert_eq!(members[0].0, "Ada Test");
+    assert_eq!(f.execution_count(pool).await, 2);
+
+    let log = f.log(broken).await;
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].0, "failed");
+    assert!(log[0]
+        .1
+        .as_deref()
+        .unwrap()
+        .starts_with("Action 1 (send_email_template) failed"));
+    let tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE tenant_id = $1")
+        .bind(f.tenant_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(
+        tasks, 0,
+        "the failed rule's earlier actions are rolled back"
+    );
+}
+
+#[tokio::test]
+async fn rules_are_validated_with_field_paths() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/automations",
+            json!({
+                "name": "Typos",
+                "trigger_event": "application.stage_changed",
+                "conditions": {"field": "to_stage", "op": "equals", "value": "offer"},
+                "actions": [{"type": "move_stage", "to_stage": "intervew"}],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let paths: Vec<&str> = body["error"]["details"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["conditions.op", "actions[0].to_stage"]);
+
+    let (status, rule) = f
+        .request(
+            "POST",
+            "/api/v1/automations",
+            json!({
+                "name": "Offer follow-up",
+                "trigger_event": "application.stage_changed",
+                "conditions": {"field": "to_stage", "op": "eq", "value": "offer"},
+                "actions": [{"type": "create_task", "title": "Chase offer"}],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Updates are checked against the rule they produce.
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/automations/{}", rule["id"].as_str().unwrap()),
+            json!({"trigger_event": "invoice.payment_recorded"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["error"]["details"]["errors"][0]["path"],
+        "conditions.field"
+    );
+}
+
+#[tokio::test]
+async fn dry_run_reports_actions_without_taking_them() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let rule = f
+        .rule(
+            "Fast-track screened candidates",
+            "application.stage_changed",
+            json!({"field": "to_stage", "op": "eq", "value": "screening"}),
+            json!([
+                {"type": "move_stage", "to_stage": "onsite"},
+                {"type": "create_task", "title": "Book onsite ({{to_stage}})"},
+            ]),
+        )
+        .await;
+    let uri = format!("/api/v1/automations/{}/test", rule);
+
+    // No events yet, and no sample given.
+    let (status, _) = f.request("POST", &uri, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let sample = |to_stage: &str| {
+        json!({"event": {
+            "application_id": f.application_id,
+            "job_id": Uuid::nil(),
+            "candidate_id": f.candidate_id,
+            "from_stage": "applied",
+            "to_stage": to_stage,
+        }})
+    };
+    let (status, body) = f.request("POST", &uri, sample("screening")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["source"], "sample");
+    assert_eq!(body["matched"], true);
+    assert_eq!(
+        body["actions"],
+        json!([
+            {
+                "type": "move_stage",
+                "application_id": f.application_id,
+                "from_stage": "applied",
+                "to_stage": "onsite",
+            },
+            {
+                "type": "create_task",
+                "title": "Book onsite (screening)",
+                "description": null,
+                "assigned_to": null,
+                "priority": "medium",
+                "due_date": null,
+            },
+        ])
+    );
+
+    let (_, body) = f.request("POST", &uri, sample("hired")).await;
+    assert_eq!(body["matched"], false);
+    assert_eq!(body["actions"], json!([]));
+
+    let (status, body) = f
+        .request("POST", &uri, json!({"event": {"to_stage": "screening"}}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["details"]["errors"][0]["path"], "event");
+
+    // The most recent real event is used when no sample is given.
+    f.request(
+        "POST",
+        &format!("/api/v1/applications/{}/stage", f.application_id),
+        json!({"to_stage": "screening"}),
+    )
+    .await;
+    let (status, body) = f.request("POST", &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["source"], "recent");
+    assert_eq!(body["actions"][0]["from_stage"], "screening");
+
+    let stage: String = sqlx::query_scalar("SELECT stage FROM applications WHERE id = $1")
+        .bind(f.application_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(stage, "screening");
+    let tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks WHERE tenant_id = $1")
+        .bind(f.tenant_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(tasks, 0);
+    assert!(f.log(rule).await.is_empty());
+}
@@ -0,0 +1,208 @@
+//! Billing clients from unbilled time entries and expenses. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    /// Manager who logs the work and bills it.
+    user_id: Uuid,
+    client_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let user_id = common::seed_user(&fixture.db, fixture.tenant_id, "Grace", "manager").await;
+    let client_id = common::seed_client(&fixture.db, fixture.tenant_id).await;
+    Some(Scenario {
+        fixture,
+        user_id,
+        client_id,
+    })
+}
+
+impl Scenario {
+    async fn request(&self, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.request_as(self.user_id, "manager", method, uri, body)
+            .await
+    }
+
+    async fn time_entry(
+        &self,
+        service_type: &str,
+        minutes: i32,
+        date: &str,
+        billable: bool,
+    ) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO time_entries \
+             (tenant_id, user_id, client_id, description, service_type, duration_minutes, rate_cents, is_billable, date) \
+             VALUES ($1, $2, $3, 'Work', $4, $5, 15000, $6, $7::date) RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .bind(self.user_id)
+        .bind(self.client_id)
+        .bind(service_type)
+        .bind(minutes)
+        .bind(billable)
+        .bind(date)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    async fn expense(&self, amount_cents: i64, reimbursable: bool) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO expenses \
+             (tenant_id, client_id, user_id, category, description, amount_cents, date, is_reimbursable) \
+             VALUES ($1, $2, $3, 'travel', 'Mileage', $4, '2026-03-05', $5) RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .bind(self.client_id)
+        .bind(self.user_id)
+        .bind(amount_cents)
+        .bind(reimbursable)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    async fn billed_on(&self, table: &str, id: Uuid) -> Option<Uuid> {
+        sqlx::query_scalar(&format!("SELECT invoice_id FROM {} WHERE id = $1", table))
+            .bind(id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn bills_unbilled_work_once_and_releases_it_on_void() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let prep_a = f
+        .time_entry("tax_preparation", 80, "2026-03-02", true)
+        .await;
+    let prep_b = f
+        .time_entry("tax_preparation", 40, "2026-03-09", true)
+        .await;
+    let review = f.time_entry("review", 30, "2026-03-10", true).await;
+    let internal = f.time_entry("review", 60, "2026-03-10", false).await;
+    let outside = f.time_entry("review", 60, "2026-04-02", true).await;
+    let travel = f.expense(4250, true).await;
+    let personal = f.expense(999, false).await;
+
+    let range = json!({
+        "client_id": f.client_id,
+        "date_from": "2026-03-01",
+        "date_to": "2026-03-31",
+    });
+    let (status, preview) = f
+        .request(
+            "GET",
+            &format!(
+                "/api/v1/invoices/unbilled?client_id={}&date_from=2026-03-01&date_to=2026-03-31",
+                f.client_id
+            ),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", preview);
+    assert_eq!(preview["time_entries"], 3);
+    assert_eq!(preview["expenses"], 1);
+    // Two hours of prep and half an hour of review at $150/h, plus travel.
+    assert_eq!(preview["subtotal_cents"], 30000 + 7500 + 4250);
+
+    let (status, invoice) = f
+        .request("POST", "/api/v1/invoices/bill", range.clone())
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+    assert_eq!(invoice["status"], "draft");
+    assert_eq!(invoice["total_cents"], 41750);
+    assert_eq!(invoice["time_entries_billed"], 3);
+    assert_eq!(invoice["expenses_billed"], 1);
+    let invoice_id: Uuid = invoice["id"].as_str().unwrap().parse().unwrap();
+
+    let lines: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT description, total_cents FROM invoice_line_items WHERE invoice_id = $1 ORDER BY sort_order",
+    )
+    .bind(invoice_id)
+    .fetch_all(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(
+        lines,
+        vec![
+            ("Review (1 time entry)".to_string(), 7500),
+            ("Tax preparation (2 time entries)".to_string(), 30000),
+            ("Expenses: Travel (1 expense)".to_string(), 4250),
+        ]
+    );
+
+    for id in [prep_a, prep_b, review] {
+        assert_eq!(f.billed_on("time_entries", id).await, Some(invoice_id));
+    }
+    assert_eq!(f.billed_on("expenses", travel).await, Some(invoice_id));
+    for id in [internal, outside] {
+        assert_eq!(f.billed_on("time_entries", id).await, None);
+    }
+    assert_eq!(f.billed_on("expenses", personal).await, None);
+
+    // Nothing is left to bill in March.
+    let (status, _) = f
+        .request("POST", "/api/v1/invoices/bill", range.clone())
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = f
+        .request(
+            "PATCH",
+            &format!("/api/v1/invoices/{}/status", invoice_id),
+            json!({"status": "void"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(f.billed_on("time_entries", prep_a).await, None);
+    assert_eq!(f.billed_on("expenses", travel).await, None);
+
+    // Released work can be billed again, one line per entry this time.
+    let mut by_entry = range.clone();
+    by_entry["group_by"] = json!("entry");
+    by_entry["include_expenses"] = json!(false);
+    let (status, invoice) = f.request("POST", "/api/v1/invoices/bill", by_entry).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+    assert_eq!(invoice["time_entries_billed"], 3);
+    assert_eq!(invoice["expenses_billed"], 0);
+    let linked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM invoice_line_items WHERE invoice_id = $1 AND time_entry_id IS NOT NULL",
+    )
+    .bind(invoice["id"].as_str().unwrap().parse::<Uuid>().unwrap())
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(linked, 3);
+
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/invoices/bill",
+            json!({"client_id": f.client_id, "group_by": "month"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
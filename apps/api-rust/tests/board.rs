@@ -0,0 +1,262 @@
+//! Job boards and bulk moves. Skipped when `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::ws::WsEventPayload;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    job_id: Uuid,
+    /// Applications from Ada, Alan and Barbara, in that order.
+    applications: Vec<Uuid>,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+/// A tenant with one job and three applications in `applied`.
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, description) \
+         VALUES ($1, 'Rust Engineer', 'Build the platform') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    let mut applications = Vec::new();
+    for first in ["Ada", "Alan", "Barbara"] {
+        let user_id = common::seed_user(db, tenant_id, first, "client").await;
+        let application_id: Uuid = sqlx::query_scalar(
+            "WITH cp AS (INSERT INTO candidate_profiles (tenant_id, user_id, headline) \
+             VALUES ($1, $2, 'Engineer') RETURNING id) \
+             INSERT INTO applications (tenant_id, job_id, candidate_id) \
+             SELECT $1, $3, cp.id FROM cp RETURNING id",
+        )
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(job_id)
+        .fetch_one(db)
+        .await
+        .unwrap();
+        applications.push(application_id);
+    }
+
+    Some(Scenario {
+        fixture,
+        job_id,
+        applications,
+    })
+}
+
+impl Scenario {
+    async fn board(&self, query: &str) -> Value {
+        let (status, body) = self
+            .request(
+                "GET",
+                &format!("/api/v1/jobs/{}/board{}", self.job_id, query),
+                json!({}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        body
+    }
+
+    async fn stage_events(&self) -> i64 {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM application_stage_events WHERE application_id = ANY($1)",
+        )
+        .bind(&self.applications)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+}
+
+fn column<'a>(board: &'a Value, key: &str) -> &'a Value {
+    board["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["key"] == key)
+        .unwrap_or_else(|| panic!("no {} column in {}", key, board))
+}
+
+#[tokio::test]
+async fn board_groups_applications_by_pipeline_stage() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    // Ada spent two days in applied before screening.
+    sqlx::query(
+        "INSERT INTO application_stage_events \
+         (tenant_id, application_id, from_stage, to_stage, duration_hours, created_at) \
+         VALUES ($1, $2, 'applied', 'screening', 48, NOW() - INTERVAL '5 hours')",
+    )
+    .bind(f.tenant_id)
+    .bind(f.applications[0])
+    .execute(&f.db)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE applications SET stage = 'screening' WHERE id = $1")
+        .bind(f.applications[0])
+        .execute(&f.db)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE applications SET stage = 'rejected', status = 'rejected' WHERE id = $1")
+        .bind(f.applications[2])
+        .execute(&f.db)
+        .await
+        .unwrap();
+
+    let board = f.board("").await;
+    assert_eq!(board["job_title"], "Rust Engineer");
+    assert_eq!(board["total"], 3);
+    let keys: Vec<&str> = board["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["key"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "applied",
+            "screening",
+            "phone_screen",
+            "technical",
+            "onsite",
+            "offer",
+            "hired",
+            "rejected",
+            "withdrawn"
+        ]
+    );
+
+    let applied = column(&board, "applied");
+    assert_eq!(applied["count"], 1);
+    assert_eq!(applied["avg_hours_in_stage"], 48.0);
+    assert_eq!(applied["applications"][0]["candidate_name"], "Alan Test");
+
+    let screening = column(&board, "screening");
+    let card = &screening["applications"][0];
+    assert_eq!(card["id"], f.applications[0].to_string());
+    assert_eq!(card["candidate_name"], "Ada Test");
+    assert_eq!(card["candidate_headline"], "Engineer");
+    assert_eq!(card["hours_in_stage"], 5);
+
+    // Closed applications are counted but not listed unless asked for.
+    let rejected = column(&board, "rejected");
+    assert_eq!(rejected["count"], 1);
+    assert_eq!(rejected["applications"], json!([]));
+    let board = f.board("?include_closed=true").await;
+    assert_eq!(
+        column(&board, "rejected")["applications"][0]["candidate_name"],
+        "Barbara Test"
+    );
+}
+
+#[tokio::test]
+async fn bulk_moves_are_atomic_and_broadcast() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let mut rx = f.state.ws_broadcast.tx.subscribe();
+
+    // Barbara has withdrawn, so moving her fails and nobody moves.
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/withdraw", f.applications[2]),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    rx.recv().await.unwrap();
+    let before = f.stage_events().await;
+
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/applications/bulk-move",
+            json!({"application_ids": f.applications, "to_stage": "screening"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let errors = body["error"]["details"]["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["path"], "application_ids[2]");
+    assert_eq!(f.stage_events().await, before);
+    assert!(rx.try_recv().is_err());
+
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/applications/bulk-move",
+            json!({"application_ids": &f.applications[..2], "to_stage": "screening"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["moved"], 2);
+    assert_eq!(f.stage_events().await, before + 2);
+    for expected in &f.applications[..2] {
+        match rx.recv().await.unwrap().payload {
+            WsEventPayload::ApplicationMoved {
+                id,
+                from_stage,
+                to_stage,
+                moved_by,
+                ..
+            } => {
+                assert_eq!(id, *expected);
+                assert_eq!(
+                    (from_stage.as_str(), to_stage.as_str()),
+                    ("applied", "screening")
+                );
+                assert_eq!(moved_by, f.admin_id);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    // Bulk rejection records the reason on each application.
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/applications/bulk-move",
+            json!({
+                "application_ids": &f.applications[..2],
+                "to_stage": "rejected",
+                "notes": "Role filled",
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    for application in body["applications"].as_array().unwrap() {
+        assert_eq!(application["status"], "rejected");
+        assert_eq!(application["rejected_reason"], "Role filled");
+    }
+    let queued: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM background_jobs WHERE tenant_id = $1 AND job_type = $2",
+    )
+    .bind(f.tenant_id)
+    .bind(cpa_backend::automations::events::JOB_TYPE)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(queued, 5);
+}
@@ -0,0 +1,304 @@
+//! iCalendar invites, feeds and replies for meetings. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use cpa_backend::router;
+
+mod common;
+
+struct User {
+    id: Uuid,
+    email: String,
+    role: &'static str,
+}
+
+async fn user(db: &sqlx::PgPool, id: Uuid, role: &'static str) -> User {
+    let email = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap();
+    User { id, email, role }
+}
+
+struct Scenario {
+    fixture: common::Fixture,
+    admin: User,
+    interviewer: User,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let interviewer_id = common::seed_user(&fixture.db, fixture.tenant_id, "Nora", "manager").await;
+    let admin = user(&fixture.db, fixture.admin_id, "admin").await;
+    let interviewer = user(&fixture.db, interviewer_id, "manager").await;
+    Some(Scenario {
+        fixture,
+        admin,
+        interviewer,
+    })
+}
+
+impl Scenario {
+    async fn send(
+        &self,
+        user: Option<&User>,
+        method: &str,
+        uri: &str,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", content_type);
+        if let Some(user) = user {
+            request = request.header(
+                "authorization",
+                format!("Bearer {}", self.token(user.id, user.role)),
+            );
+        }
+        let response = router(self.state.clone())
+            .oneshot(request.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        if status == StatusCode::OK && uri.contains(".ics") {
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "text/calendar; charset=utf-8"
+            );
+        }
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    async fn json(&self, user: &User, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let (status, text) = self
+            .send(
+                Some(user),
+                method,
+                uri,
+                "application/json",
+                body.to_string(),
+            )
+            .await;
+        (status, serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+
+    async fn ics(&self, meeting_id: &str) -> (StatusCode, String) {
+        let text = self
+            .send(
+                Some(&self.admin),
+                "GET",
+                &format!("/api/v1/meetings/{}/ics", meeting_id),
+                "application/json",
+                String::new(),
+            )
+            .await;
+        (text.0, text.1.replace("\r\n ", ""))
+    }
+
+    async fn accept(&self, meeting_id: &str, at: &str) -> StatusCode {
+        self.json(
+            &self.admin,
+            "POST",
+            &format!("/api/v1/meetings/{}/accept", meeting_id),
+            json!({"accepted_time": at, "timezone": "America/New_York"}),
+        )
+        .await
+        .0
+    }
+
+    async fn reply(
+        &self,
+        user: &User,
+        meeting_id: &str,
+        sequence: i64,
+        partstat: &str,
+    ) -> (StatusCode, Value) {
+        let body = format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nMETHOD:REPLY\r\nBEGIN:VEVENT\r\n\
+             UID:{}@localhost\r\nSEQUENCE:{}\r\n\
+             ATTENDEE;PARTSTAT={}:mailto:{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            meeting_id,
+            sequence,
+            partstat,
+            self.interviewer.email.to_uppercase()
+        );
+        let (status, text) = self
+            .send(
+                Some(user),
+                "POST",
+                "/api/v1/meetings/ics-reply",
+                "text/calendar",
+                body,
+            )
+            .await;
+        (status, serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+}
+
+#[tokio::test]
+async fn invites_carry_sequence_and_replies_update_participants() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (status, meeting) = f
+        .json(
+            &f.admin,
+            "POST",
+            "/api/v1/meetings/request",
+            json!({
+                "title": "Partner review, round 2",
+                "participant_user_ids": [f.interviewer.id],
+                "duration_minutes": 45,
+                "proposed_times": [],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", meeting);
+    let id = meeting["id"].as_str().unwrap().to_string();
+
+    // No time agreed yet.
+    assert_eq!(f.ics(&id).await.0, StatusCode::CONFLICT);
+
+    assert_eq!(f.accept(&id, "2030-03-11T16:00:00Z").await, StatusCode::OK);
+
+    let (status, ics) = f.ics(&id).await;
+    assert_eq!(status, StatusCode::OK, "{}", ics);
+    assert!(ics.contains("METHOD:REQUEST\r\n"));
+    assert!(ics.contains(&format!("UID:{}@localhost\r\n", id)));
+    assert!(ics.contains("SEQUENCE:0\r\n"));
+    assert!(ics.contains("DTSTART:20300311T160000Z\r\nDTEND:20300311T164500Z\r\n"));
+    assert!(ics.contains("SUMMARY:Partner review\\, round 2\r\n"));
+    assert!(ics.contains(&format!(
+        "ORGANIZER;CN=\"Grace Test\":mailto:{}",
+        f.admin.email
+    )));
+    assert!(ics.contains(&format!(
+        "ATTENDEE;CN=\"Nora Test\";ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:{}",
+        f.interviewer.email
+    )));
+
+    let (status, body) = f
+        .json(
+            &f.admin,
+            "POST",
+            &format!("/api/v1/meetings/{}/reschedule", id),
+            json!({"proposed_times": ["2030-03-12T16:00:00Z"], "reason": "Clash"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(f.accept(&id, "2030-03-12T16:00:00Z").await, StatusCode::OK);
+    let (_, ics) = f.ics(&id).await;
+    assert!(ics.contains("SEQUENCE:1\r\n"));
+    assert!(ics.contains("DTSTART:20300312T160000Z\r\n"));
+
+    // Replies are admin-only, and must answer the latest version.
+    assert_eq!(
+        f.reply(&f.interviewer, &id, 1, "ACCEPTED").await.0,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        f.reply(&f.admin, &id, 0, "ACCEPTED").await.0,
+        StatusCode::CONFLICT
+    );
+    let (status, result) = f.reply(&f.admin, &id, 1, "TENTATIVE").await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["updated"][0]["user_id"], json!(f.interviewer.id));
+    assert_eq!(result["updated"][0]["response_status"], "tentative");
+    let (_, ics) = f.ics(&id).await;
+    assert!(ics.contains("PARTSTAT=TENTATIVE"));
+
+    let (status, _) = f
+        .json(
+            &f.admin,
+            "POST",
+            &format!("/api/v1/meetings/{}/cancel", id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, ics) = f.ics(&id).await;
+    assert!(ics.contains("METHOD:CANCEL\r\n"));
+    assert!(ics.contains("SEQUENCE:2\r\n"));
+    assert!(ics.contains("STATUS:CANCELLED\r\n"));
+}
+
+#[tokio::test]
+async fn calendar_feed_serves_upcoming_meetings_until_rotated() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let mut ids = Vec::new();
+    for (title, at) in [
+        ("Upcoming interview", "2030-03-11T16:00:00Z"),
+        ("Past meeting", "2020-03-11T16:00:00Z"),
+    ] {
+        let (_, meeting) = f
+            .json(
+                &f.admin,
+                "POST",
+                "/api/v1/meetings/request",
+                json!({"title": title, "participant_user_ids": [f.interviewer.id], "proposed_times": []}),
+            )
+            .await;
+        let id = meeting["id"].as_str().unwrap().to_string();
+        assert_eq!(f.accept(&id, at).await, StatusCode::OK);
+        ids.push(id);
+    }
+
+    let (status, feed) = f
+        .json(
+            &f.interviewer,
+            "POST",
+            "/api/v1/meetings/calendar-feed",
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", feed);
+    let url = feed["url"].as_str().unwrap();
+    assert!(feed["webcal_url"]
+        .as_str()
+        .unwrap()
+        .starts_with("webcal://"));
+    let path = &url[url.find("/api/v1/").unwrap()..];
+
+    let (status, ics) = f
+        .send(None, "GET", path, "text/calendar", String::new())
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", ics);
+    assert!(ics.contains("METHOD:PUBLISH\r\n"));
+    assert!(ics.contains(&format!("UID:{}@localhost", ids[0])));
+    assert!(!ics.contains(&ids[1]));
+
+    let (status, _) = f
+        .json(
+            &f.interviewer,
+            "POST",
+            "/api/v1/meetings/calendar-feed",
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = f
+        .send(None, "GET", path, "text/calendar", String::new())
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
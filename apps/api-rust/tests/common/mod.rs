@@ -0,0 +1,179 @@
+//! Shared setup for integration tests.
+
+#![allow(dead_code)] // not every test binary uses every helper
+
+use std::path::Path;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use cpa_backend::auth::jwt::create_access_token;
+use cpa_backend::{config::Config, mailer, middleware, router, search, ws, AppState};
+
+pub const STRIPE_WEBHOOK_SECRET: &str = "whsec_test";
+
+pub fn test_config(database_url: &str) -> Config {
+    serde_json::from_value(serde_json::json!({
+        "database_url": database_url,
+        "redis_url": "redis://127.0.0.1:1",
+        "jwt_secret": "test-secret-test-secret-test-secret",
+        "mail_transport": "memory",
+        "search_backend": "memory",
+        "stripe_webhook_secret": STRIPE_WEBHOOK_SECRET,
+    }))
+    .unwrap()
+}
+
+pub fn test_state(config: Config, db: sqlx::PgPool) -> AppState {
+    AppState {
+        db,
+        mailer: mailer::Mailer::from_config(&config).unwrap(),
+        search: search::SearchEngine::from_config(&config).unwrap(),
+        config,
+        ws_broadcast: ws::WsBroadcast::new(),
+        rate_limiter: middleware::rate_limit::RateLimiter::new(100, 60),
+        redis: None,
+    }
+}
+
+/// Connect to `TEST_DATABASE_URL` and bring it up to date with the
+/// migrations. Returns `None` (and the caller skips) when it is unset.
+pub async fn test_database() -> Option<(String, PgPool)> {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL not set; skipping database tests");
+        return None;
+    };
+
+    let db = PgPool::connect(&url).await.unwrap();
+    sqlx::migrate::Migrator::new(Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/migrations"
+    )))
+    .await
+    .unwrap()
+    .run(&db)
+    .await
+    .unwrap();
+    Some((url, db))
+}
+
+pub async fn seed_tenant(db: &PgPool) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query("INSERT INTO tenants (id, name, slug, kms_key_id) VALUES ($1, $2, $2, 'test')")
+        .bind(id)
+        .bind(format!("test-{}", id))
+        .execute(db)
+        .await
+        .unwrap();
+    id
+}
+
+/// A user in `tenant_id`. `name` is "First Last"; the last name defaults to
+/// "Test".
+pub async fn seed_user(db: &PgPool, tenant_id: Uuid, name: &str, role: &str) -> Uuid {
+    let (first, last) = name.split_once(' ').unwrap_or((name, "Test"));
+    sqlx::query_scalar(
+        "INSERT INTO users (tenant_id, email, password_hash, first_name, last_name, role) \
+         VALUES ($1, $2, 'x', $3, $4, $5) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(format!(
+        "{}.{}@example.com",
+        first.to_lowercase(),
+        Uuid::new_v4()
+    ))
+    .bind(first)
+    .bind(last)
+    .bind(role)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+pub async fn seed_client(db: &PgPool, tenant_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO clients (tenant_id, name, business_type) VALUES ($1, 'Acme', 'llc') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+/// A fresh tenant with an admin to call the API as.
+pub struct Fixture {
+    pub db: PgPool,
+    pub state: AppState,
+    pub tenant_id: Uuid,
+    pub admin_id: Uuid,
+}
+
+/// Returns `None` (and the caller skips) when `TEST_DATABASE_URL` is unset.
+pub async fn fixture() -> Option<Fixture> {
+    fixture_with(|_| {}).await
+}
+
+/// Like [`fixture`], with the config adjusted before the state is built.
+pub async fn fixture_with(configure: impl FnOnce(&mut Config)) -> Option<Fixture> {
+    let (url, db) = test_database().await?;
+    let mut config = test_config(&url);
+    configure(&mut config);
+    let state = test_state(config, db.clone());
+    let tenant_id = seed_tenant(&db).await;
+    let admin_id = seed_user(&db, tenant_id, "Grace", "admin").await;
+    Some(Fixture {
+        db,
+        state,
+        tenant_id,
+        admin_id,
+    })
+}
+
+impl Fixture {
+    pub fn token(&self, user_id: Uuid, role: &str) -> String {
+        create_access_token(user_id, self.tenant_id, role, &self.state.config.jwt_secret).unwrap()
+    }
+
+    pub async fn request_as(
+        &self,
+        user_id: Uuid,
+        role: &str,
+        method: &str,
+        uri: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(
+                "authorization",
+                format!("Bearer {}", self.token(user_id, role)),
+            )
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// Call the API as the admin.
+    pub async fn request(&self, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.request_as(self.admin_id, "admin", method, uri, body)
+            .await
+    }
+
+    /// Run `request` through the router. The body is `Null` when it is not
+    /// JSON.
+    pub async fn send(&self, request: Request<Body>) -> (StatusCode, Value) {
+        let response = router(self.state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+}
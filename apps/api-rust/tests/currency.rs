@@ -0,0 +1,235 @@
+//! Invoices in several currencies, exchange rates and base-currency
+//! reports. Skipped when `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    client_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let client_id = common::seed_client(&fixture.db, fixture.tenant_id).await;
+    Some(Scenario { fixture, client_id })
+}
+
+impl Scenario {
+    /// Send `body` as `content_type` on behalf of the admin.
+    async fn upload(
+        &self,
+        method: &str,
+        uri: &str,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(
+                "authorization",
+                format!("Bearer {}", self.token(self.admin_id, "admin")),
+            )
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// Issue an invoice for `unit_price` minor units and pay it in full.
+    async fn paid_invoice(&self, currency: Option<&str>, unit_price: i64) -> Value {
+        let mut body = json!({
+            "client_id": self.client_id,
+            "line_items": [{"description": "Advisory", "quantity": 1, "unit_price_cents": unit_price}],
+        });
+        if let Some(currency) = currency {
+            body["currency"] = json!(currency);
+        }
+        let (status, invoice) = self.request("POST", "/api/v1/invoices", body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+        let id = invoice["id"].as_str().unwrap();
+        let (status, body) = self
+            .request(
+                "PATCH",
+                &format!("/api/v1/invoices/{}/status", id),
+                json!({"status": "sent"}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (status, body) = self
+            .request(
+                "POST",
+                &format!("/api/v1/invoices/{}/payment", id),
+                json!({"amount_cents": unit_price, "method": "wire"}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        invoice
+    }
+}
+
+#[tokio::test]
+async fn reports_convert_into_the_base_currency_with_the_rates_used() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (status, firm) = f
+        .request(
+            "PUT",
+            "/api/v1/settings/firm",
+            json!({"base_currency": "usd", "locale": "de-de"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", firm);
+    assert_eq!(firm["base_currency"], "USD");
+    assert_eq!(firm["locale"], "de-DE");
+    let (status, _) = f
+        .request(
+            "PUT",
+            "/api/v1/settings/firm",
+            json!({"base_currency": "XXX"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A bad line rejects the whole file.
+    let (status, body) = f
+        .upload(
+            "POST",
+            "/api/v1/exchange-rates/import",
+            "text/csv",
+            "2026-03-01,EUR,USD,1.08\n2026-03-02,EUR,USD,-1\n".to_string(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, body) = f
+        .upload(
+            "POST",
+            "/api/v1/exchange-rates/import?source=ecb",
+            "text/csv",
+            "date,from,to,rate\n\
+             2026-01-01,EUR,USD,1.02\n\
+             2026-03-01,EUR,USD,1.0800\n\
+             2026-03-15,USD,JPY,150\n"
+                .to_string(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["imported"], 3);
+    // Restating a rate replaces it.
+    let (status, march) = f
+        .request(
+            "POST",
+            "/api/v1/exchange-rates",
+            json!({"rate_date": "2026-03-01", "from_currency": "eur", "to_currency": "USD", "rate": "1.0825"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", march);
+    assert_eq!(march["rate"], "1.0825");
+    let (status, rates) = f
+        .request("GET", "/api/v1/exchange-rates?from_currency=EUR", json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", rates);
+    let listed: Vec<(&str, &str)> = rates
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["rate_date"].as_str().unwrap(),
+                r["rate"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        listed,
+        vec![("2026-03-01", "1.0825"), ("2026-01-01", "1.02")]
+    );
+
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/invoices",
+            json!({
+                "client_id": f.client_id,
+                "currency": "DOGE",
+                "line_items": [{"description": "Advisory", "quantity": 1, "unit_price_cents": 100}],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Invoices default to the base currency.
+    let usd = f.paid_invoice(None, 5_000).await;
+    assert_eq!(usd["currency"], "USD");
+    // EUR 1,000.00 at 1.0825 is $1,082.50.
+    let eur = f.paid_invoice(Some("eur"), 100_000).await;
+    assert_eq!(eur["currency"], "EUR");
+    // 15,000 yen at 150 per dollar is $100.00.
+    f.paid_invoice(Some("JPY"), 15_000).await;
+
+    let today = chrono::Utc::now().date_naive();
+    let (status, report) = f
+        .request(
+            "GET",
+            &format!("/api/v1/reports/pl?start_date={}&end_date={}", today, today),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(report["base_currency"], "USD");
+    assert_eq!(report["revenue"]["total_cents"], 5_000 + 108_250 + 10_000);
+    let used: Vec<(&str, &str, &str)> = report["exchange_rates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["from_currency"].as_str().unwrap(),
+                r["to_currency"].as_str().unwrap(),
+                r["rate"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(used, vec![("EUR", "USD", "1.0825"), ("USD", "JPY", "150")]);
+
+    let (status, cash) = f
+        .request(
+            "GET",
+            &format!(
+                "/api/v1/reports/cashflow?start_date={}&end_date={}",
+                today, today
+            ),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", cash);
+    assert_eq!(cash["inflows"][0]["amount_cents"], 123_250);
+    assert_eq!(cash["exchange_rates"].as_array().unwrap().len(), 2);
+
+    // Without a GBP rate the report cannot be converted.
+    f.paid_invoice(Some("GBP"), 1_000).await;
+    let (status, body) = f
+        .request(
+            "GET",
+            &format!("/api/v1/reports/pl?start_date={}&end_date={}", today, today),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+}
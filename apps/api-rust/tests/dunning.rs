@@ -0,0 +1,252 @@
+//! Overdue invoices, dunning steps with late fees, and the receivables
+//! aging report. Skipped when `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use chrono::{Days, NaiveDate};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::invoices::dunning;
+use cpa_backend::scheduler::Job;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    client_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let client_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO clients (tenant_id, name, business_type, email) \
+         VALUES ($1, 'Acme', 'llc', 'ap@acme.example') RETURNING id",
+    )
+    .bind(fixture.tenant_id)
+    .fetch_one(&fixture.db)
+    .await
+    .unwrap();
+    Some(Scenario { fixture, client_id })
+}
+
+impl Scenario {
+    /// A sent invoice for `amount` minor units, due on `due_date`.
+    async fn sent_invoice(&self, currency: &str, amount: i64, due_date: NaiveDate) -> String {
+        let (status, invoice) = self
+            .request(
+                "POST",
+                "/api/v1/invoices",
+                json!({
+                    "client_id": self.client_id,
+                    "currency": currency,
+                    "due_date": due_date,
+                    "line_items": [{"description": "Advisory", "quantity": 1, "unit_price_cents": amount}],
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+        let id = invoice["id"].as_str().unwrap().to_string();
+        let (status, body) = self
+            .request(
+                "PATCH",
+                &format!("/api/v1/invoices/{}/status", id),
+                json!({"status": "sent"}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        id
+    }
+
+    async fn invoice(&self, id: &str) -> Value {
+        let (status, invoice) = self
+            .request("GET", &format!("/api/v1/invoices/{}", id), json!({}))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", invoice);
+        invoice
+    }
+
+    async fn run_job(&self) {
+        let job = Job {
+            id: Uuid::new_v4(),
+            tenant_id: None,
+            job_type: dunning::JOB_TYPE.to_string(),
+            payload: json!({}),
+            attempts: 0,
+            max_attempts: 1,
+            run_at: chrono::Utc::now(),
+        };
+        dunning::run_dunning(self.state.clone(), job).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn overdue_invoices_are_dunned_once_per_step_and_aged() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let today = chrono::Utc::now().date_naive();
+    let days_ago = |n: u64| today.checked_sub_days(Days::new(n)).unwrap();
+
+    for step in [
+        json!({"days_past_due": 3}),
+        json!({"days_past_due": 14, "late_fee_cents": 2_500, "late_fee_percent": 1.5, "message": "Please call us."}),
+        json!({"days_past_due": 30, "late_fee_cents": 2_500, "send_reminder": false}),
+    ] {
+        let (status, body) = f
+            .request("POST", "/api/v1/invoices/dunning-steps", step)
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+    }
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/invoices/dunning-steps",
+            json!({"days_past_due": 3}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/invoices/dunning-steps",
+            json!({"days_past_due": 7, "late_fee_percent": 1.23456}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, steps) = f
+        .request("GET", "/api/v1/invoices/dunning-steps", json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", steps);
+    assert_eq!(steps.as_array().unwrap().len(), 3);
+    assert_eq!(steps[1]["late_fee_ppm"], 15_000);
+
+    // $1,000.00, 20 days late: the 3-day step is skipped behind the 14-day
+    // one, which charges $25.00 plus 1.5%.
+    let late = f.sent_invoice("USD", 100_000, days_ago(20)).await;
+    let upcoming = f.sent_invoice("USD", 30_000, today + Days::new(10)).await;
+    // EUR 500.00, 40 days late: the flat $25.00 is EUR 20.00 at 1.25.
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/exchange-rates",
+            json!({"rate_date": days_ago(60), "from_currency": "EUR", "to_currency": "USD", "rate": "1.25"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let euro = f.sent_invoice("EUR", 50_000, days_ago(40)).await;
+
+    f.run_job().await;
+    // Running again changes nothing.
+    f.run_job().await;
+
+    let invoice = f.invoice(&late).await;
+    assert_eq!(invoice["status"], "overdue");
+    assert_eq!(invoice["total_cents"], 104_000);
+    let fee_lines: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT description, total_cents FROM invoice_line_items \
+         WHERE invoice_id = $1::uuid AND service_type = 'late_fee'",
+    )
+    .bind(&late)
+    .fetch_all(&f.state.db)
+    .await
+    .unwrap();
+    assert_eq!(
+        fee_lines,
+        vec![("Late fee (20 days past due)".to_string(), 4_000)]
+    );
+    let (status, events) = f
+        .request(
+            "GET",
+            &format!("/api/v1/invoices/{}/dunning", late),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", events);
+    let events: Vec<(bool, i64)> = events
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["skipped"].as_bool().unwrap(),
+                e["late_fee_cents"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(events, vec![(true, 0), (false, 4_000)]);
+
+    assert_eq!(f.invoice(&upcoming).await["status"], "sent");
+    let invoice = f.invoice(&euro).await;
+    assert_eq!(invoice["status"], "overdue");
+    assert_eq!(invoice["total_cents"], 52_000);
+
+    // Only the 14-day step emails; the 30-day one just charges.
+    let reminders: Vec<(String, String)> = sqlx::query_as(
+        "SELECT to_email, body_text FROM email_outbox WHERE tenant_id = $1 AND category = 'invoice'",
+    )
+    .bind(f.tenant_id)
+    .fetch_all(&f.state.db)
+    .await
+    .unwrap();
+    assert_eq!(reminders.len(), 1, "{:?}", reminders);
+    assert_eq!(reminders[0].0, "ap@acme.example");
+    assert!(
+        reminders[0].1.contains("20 days past due"),
+        "{}",
+        reminders[0].1
+    );
+    assert!(reminders[0].1.contains("$1,040.00"), "{}", reminders[0].1);
+    assert!(reminders[0].1.contains("Please call us."));
+
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/payment", late),
+            json!({"amount_cents": 4_000, "method": "wire"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, aging) = f
+        .request(
+            "GET",
+            &format!("/api/v1/reports/ar-aging?as_of={}", today),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", aging);
+    assert_eq!(aging["base_currency"], "USD");
+    let client = &aging["clients"][0];
+    assert_eq!(client["client_name"], "Acme");
+    assert_eq!(client["invoice_count"], 3);
+    assert_eq!(client["current_cents"], 30_000);
+    assert_eq!(client["days_0_30_cents"], 100_000);
+    // EUR 520.00 at 1.25
+    assert_eq!(client["days_31_60_cents"], 65_000);
+    assert_eq!(client["days_over_90_cents"], 0);
+    assert_eq!(aging["totals"]["total_cents"], 195_000);
+    assert_eq!(aging["exchange_rates"].as_array().unwrap().len(), 1);
+
+    // Aged 60 days later, everything has slipped two buckets.
+    let (status, aging) = f
+        .request(
+            "GET",
+            &format!("/api/v1/reports/ar-aging?as_of={}", today + Days::new(60)),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", aging);
+    assert_eq!(aging["totals"]["days_31_60_cents"], 30_000);
+    assert_eq!(aging["totals"]["days_61_90_cents"], 100_000);
+    assert_eq!(aging["totals"]["days_over_90_cents"], 65_000);
+}
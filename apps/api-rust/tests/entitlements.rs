@@ -0,0 +1,162 @@
+//! Plan limits and feature entitlements enforced through the router. Skipped
+//! when `TEST_DATABASE_URL` is unset.
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::scheduler::Job;
+use cpa_backend::subscriptions::trials;
+
+mod common;
+
+/// The free plan seeded by migration 017.
+const STARTER_PLAN: Uuid = Uuid::from_u128(1);
+
+/// A tenant subscribed to a paid plan allowing a single job and no talent
+/// pools.
+async fn subscribed() -> Option<common::Fixture> {
+    let f = common::fixture().await?;
+    let plan_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO plans (name, slug, price_monthly_cents, max_jobs, max_users, max_candidates, features) \
+         VALUES ('One job', $1, 1000, 1, -1, -1, '[\"job_posting\"]') RETURNING id",
+    )
+    .bind(format!("one-job-{}", f.tenant_id))
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/subscription",
+            json!({ "plan_id": plan_id }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    Some(f)
+}
+
+async fn create_job(f: &common::Fixture, title: &str) -> (StatusCode, Value) {
+    f.request("POST", "/api/v1/jobs", json!({ "title": title }))
+        .await
+}
+
+async fn meter(f: &common::Fixture, meter_type: &str) -> (i64, Option<i64>) {
+    sqlx::query_as(
+        "SELECT m.current_value, m.limit_value FROM usage_meters m \
+         JOIN subscriptions s ON s.id = m.subscription_id \
+         WHERE s.tenant_id = $1 AND m.meter_type = $2",
+    )
+    .bind(f.tenant_id)
+    .bind(meter_type)
+    .fetch_one(&f.db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn job_limit_is_enforced_and_freed_on_delete() {
+    let Some(f) = subscribed().await else { return };
+
+    let (status, job) = create_job(&f, "First role").await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = create_job(&f, "Second role").await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert_eq!(body["error"]["code"], "PLAN_LIMIT_REACHED");
+    assert_eq!(
+        body["error"]["details"],
+        json!({ "meter": "job_posts", "limit": 1, "current": 1 })
+    );
+    assert_eq!(meter(&f, "job_posts").await, (1, Some(1)));
+
+    let (status, _) = f
+        .request(
+            "DELETE",
+            &format!("/api/v1/jobs/{}", job["id"].as_str().unwrap()),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(meter(&f, "job_posts").await.0, 0);
+
+    let (status, _) = create_job(&f, "Second role").await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn concurrent_creates_cannot_exceed_the_limit() {
+    let Some(f) = subscribed().await else { return };
+
+    let (a, b) = tokio::join!(create_job(&f, "Role A"), create_job(&f, "Role B"));
+    let mut statuses = [a.0, b.0];
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        [StatusCode::CREATED, StatusCode::PAYMENT_REQUIRED]
+    );
+
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM job_posts WHERE tenant_id = $1")
+        .bind(f.tenant_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(jobs, 1);
+}
+
+#[tokio::test]
+async fn features_outside_the_plan_are_forbidden() {
+    let Some(f) = subscribed().await else { return };
+
+    let (status, body) = f.request("GET", "/api/v1/talent-pools", json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "FEATURE_NOT_ENTITLED");
+    assert_eq!(body["error"]["details"]["feature"], "talent_pools");
+
+    // An override grants the feature without changing plans.
+    sqlx::query(
+        "INSERT INTO entitlements (tenant_id, feature_key, source) VALUES ($1, 'talent_pools', 'override')",
+    )
+    .bind(f.tenant_id)
+    .execute(&f.db)
+    .await
+    .unwrap();
+    let (status, _) = f.request("GET", "/api/v1/talent-pools", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn expired_trials_move_to_the_free_plan() {
+    let Some(f) = subscribed().await else { return };
+    sqlx::query(
+        "UPDATE subscriptions SET trial_ends_at = NOW() - INTERVAL '1 day' WHERE tenant_id = $1",
+    )
+    .bind(f.tenant_id)
+    .execute(&f.db)
+    .await
+    .unwrap();
+
+    let job = Job {
+        id: Uuid::new_v4(),
+        tenant_id: None,
+        job_type: trials::JOB_TYPE.to_string(),
+        payload: json!({}),
+        attempts: 0,
+        max_attempts: 1,
+        run_at: chrono::Utc::now(),
+    };
+    trials::expire_trials(f.state.clone(), job).await.unwrap();
+
+    let (plan_id, status): (Uuid, String) =
+        sqlx::query_as("SELECT plan_id, status FROM subscriptions WHERE tenant_id = $1")
+            .bind(f.tenant_id)
+            .fetch_one(&f.db)
+            .await
+            .unwrap();
+    assert_eq!((plan_id, status.as_str()), (STARTER_PLAN, "active"));
+    // Starter allows two jobs and includes no talent pools.
+    assert_eq!(meter(&f, "job_posts").await, (0, Some(2)));
+    let (status, _) = f.request("GET", "/api/v1/talent-pools", json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,173 @@
+//! Document extraction. Skipped when `TEST_DATABASE_URL` is unset.
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cpa_backend::extraction::{self, worker, ExtractionJob, Target};
+use cpa_backend::ws::WsEventPayload;
+
+mod common;
+
+const RESUME: &str = "\
+Ada Lovelace
+Austin, TX | ada@example.com | 512-555-0100
+
+Experience
+Senior Accountant at Acme Corp, Jan 2020 - Present
+- Ran month-end close in NetSuite
+
+Education
+University of Texas, BBA in Accounting, 2016
+
+Skills
+Python, GAAP, Excel
+";
+
+#[tokio::test]
+async fn completed_candidate_uploads_are_parsed_into_the_profile() {
+    let Some(f) = common::fixture().await else {
+        return;
+    };
+    let (db, state, tenant_id, admin_id) = (&f.db, &f.state, f.tenant_id, f.admin_id);
+    let candidate_user = common::seed_user(db, tenant_id, "Ada Lovelace", "client").await;
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    // Already listed skills are not added again.
+    sqlx::query(
+        "INSERT INTO candidate_skills (tenant_id, candidate_id, skill_name) \
+         VALUES ($1, $2, 'python')",
+    )
+    .bind(tenant_id)
+    .bind(candidate_id)
+    .execute(db)
+    .await
+    .unwrap();
+
+    let (status, upload) = f
+        .request(
+            "POST",
+            &format!("/api/v1/candidates/{}/documents", candidate_id),
+            json!({
+                "document_type": "resume",
+                "filename": "ada.txt",
+                "mime_type": "text/plain",
+                "size_bytes": RESUME.len(),
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", upload);
+    let document_id: Uuid = upload["id"].as_str().unwrap().parse().unwrap();
+    let s3_key = upload["s3_key"].as_str().unwrap();
+    assert!(s3_key.ends_with(&format!("/documents/{}/ada.txt", document_id)));
+    assert!(upload["upload_url"].as_str().unwrap().contains(s3_key));
+
+    let (status, completed) = f
+        .request(
+            "POST",
+            &format!(
+                "/api/v1/candidates/{}/documents/{}/complete",
+                candidate_id, document_id
+            ),
+            json!({"upsert_skills": true}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", completed);
+    assert_eq!(completed["parsed_data"], json!({"status": "pending"}));
+
+    let payload: Value = sqlx::query_scalar(
+        "SELECT payload FROM background_jobs WHERE tenant_id = $1 AND job_type = $2",
+    )
+    .bind(tenant_id)
+    .bind(extraction::JOB_TYPE)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let job: ExtractionJob = serde_json::from_value(payload).unwrap();
+    assert_eq!(
+        job,
+        ExtractionJob {
+            target: Target::CandidateDocument,
+            id: document_id,
+            requested_by: admin_id,
+            upsert_skills: true,
+        }
+    );
+
+    let mut rx = state.ws_broadcast.tx.subscribe();
+    let stored = worker::process(state, tenant_id, &job, RESUME.as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(stored["status"], "parsed");
+    assert_eq!(stored["contact"]["name"], "Ada Lovelace");
+    assert_eq!(stored["contact"]["email"], "ada@example.com");
+    assert_eq!(stored["work_history"][0]["company"], "Acme Corp");
+    assert_eq!(stored["work_history"][0]["current"], true);
+    assert_eq!(stored["education"][0]["graduation_year"], 2016);
+    assert_eq!(stored["skills_added"], json!(["GAAP", "Excel", "NetSuite"]));
+
+    let parsed_data: Value =
+        sqlx::query_scalar("SELECT parsed_data FROM candidate_documents WHERE id = $1")
+            .bind(document_id)
+            .fetch_one(db)
+            .await
+            .unwrap();
+    assert_eq!(parsed_data, stored);
+    let skills: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT skill_name, category FROM candidate_skills \
+         WHERE candidate_id = $1 ORDER BY skill_name",
+    )
+    .bind(candidate_id)
+    .fetch_all(db)
+    .await
+    .unwrap();
+    assert_eq!(
+        skills,
+        [
+            ("Excel".to_string(), Some("tool".to_string())),
+            ("GAAP".to_string(), Some("domain".to_string())),
+            ("NetSuite".to_string(), Some("tool".to_string())),
+            ("python".to_string(), None),
+        ]
+    );
+
+    let event = rx.recv().await.unwrap();
+    assert_eq!(event.user_id, admin_id);
+    match event.payload {
+        WsEventPayload::DocumentProcessed {
+            id,
+            filename,
+            category,
+            confidence,
+        } => {
+            assert_eq!(id, document_id);
+            assert_eq!(filename, "ada.txt");
+            assert_eq!(category.as_deref(), Some("resume"));
+            assert_eq!(confidence, Some(1.0));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    // A file that cannot be read is recorded as failed rather than retried.
+    let failed = worker::process(state, tenant_id, &job, b"%PDF-1.4 truncated")
+        .await
+        .unwrap();
+    assert_eq!(failed["status"], "failed");
+    assert!(failed["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Could not read PDF"));
+    assert!(matches!(
+        rx.recv().await.unwrap().payload,
+        WsEventPayload::DocumentProcessed {
+            confidence: None,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,181 @@
+//! `Idempotency-Key` replay against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::{middleware as axum_mw, middleware::from_fn, routing::post, Json, Router};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use cpa_backend::auth::jwt::Claims;
+use cpa_backend::error::AppResult;
+use cpa_backend::middleware::{
+    auth::require_auth, idempotency::idempotency_check, tenant::TenantTx,
+};
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    app: Router,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct CreateProbe {
+    name: String,
+    #[serde(default)]
+    delay_ms: u64,
+}
+
+/// Records a client row per execution so double-runs are visible.
+async fn create_probe(
+    mut tx: TenantTx,
+    claims: Claims,
+    Json(body): Json<CreateProbe>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    tokio::time::sleep(Duration::from_millis(body.delay_ms)).await;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO clients (tenant_id, name, business_type) VALUES ($1, $2, 'llc') RETURNING id",
+    )
+    .bind(claims.tid)
+    .bind(&body.name)
+    .fetch_one(&mut *tx)
+    .await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": id }))))
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let app = Router::new()
+        .route(
+            "/probe",
+            post(create_probe).layer(from_fn(idempotency_check)),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            fixture.state.clone(),
+            require_auth,
+        ))
+        .with_state(fixture.state.clone());
+    Some(Scenario { fixture, app })
+}
+
+impl Scenario {
+    async fn post(
+        &self,
+        user_id: Uuid,
+        key: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, Option<String>, String) {
+        let token = self.token(user_id, "partner");
+        let request = Request::builder()
+            .method("POST")
+            .uri("/probe")
+            .header("authorization", format!("Bearer {}", token))
+            .header("content-type", "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let replayed = response
+            .headers()
+            .get("idempotent-replayed")
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn executions(&self, name: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM clients WHERE tenant_id = $1 AND name = $2")
+            .bind(self.tenant_id)
+            .bind(name)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn retry_replays_the_stored_response() {
+    let Some(f) = scenario().await else { return };
+    let user = Uuid::new_v4();
+    let key = Uuid::new_v4().to_string();
+    let name = format!("replay-{}", key);
+
+    let first = f
+        .post(user, &key, serde_json::json!({ "name": name }))
+        .await;
+    let second = f
+        .post(user, &key, serde_json::json!({ "name": name }))
+        .await;
+
+    assert_eq!(first.0, StatusCode::CREATED);
+    assert_eq!(first.1, None);
+    assert_eq!(second.0, StatusCode::CREATED);
+    assert_eq!(second.1.as_deref(), Some("true"));
+    assert_eq!(second.2, first.2);
+    assert_eq!(f.executions(&name).await, 1);
+}
+
+#[tokio::test]
+async fn key_reused_with_a_different_body_is_rejected() {
+    let Some(f) = scenario().await else { return };
+    let user = Uuid::new_v4();
+    let key = Uuid::new_v4().to_string();
+
+    let first = f.post(user, &key, serde_json::json!({ "name": "a" })).await;
+    let second = f.post(user, &key, serde_json::json!({ "name": "b" })).await;
+
+    assert_eq!(first.0, StatusCode::CREATED);
+    assert_eq!(second.0, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn keys_are_scoped_per_user() {
+    let Some(f) = scenario().await else { return };
+    let key = Uuid::new_v4().to_string();
+    let name = format!("scoped-{}", key);
+
+    let first = f
+        .post(Uuid::new_v4(), &key, serde_json::json!({ "name": name }))
+        .await;
+    let second = f
+        .post(Uuid::new_v4(), &key, serde_json::json!({ "name": name }))
+        .await;
+
+    assert_eq!(first.0, StatusCode::CREATED);
+    assert_eq!(second.0, StatusCode::CREATED);
+    assert_eq!(second.1, None);
+    assert_eq!(f.executions(&name).await, 2);
+}
+
+#[tokio::test]
+async fn concurrent_duplicate_waits_for_the_original() {
+    let Some(f) = scenario().await else { return };
+    let user = Uuid::new_v4();
+    let key = Uuid::new_v4().to_string();
+    let name = format!("concurrent-{}", key);
+    let body = serde_json::json!({ "name": name, "delay_ms": 300 });
+
+    let (a, b) = tokio::join!(f.post(user, &key, body.clone()), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        f.post(user, &key, body.clone()).await
+    });
+
+    assert_eq!(a.0, StatusCode::CREATED);
+    assert_eq!(b.0, StatusCode::CREATED);
+    assert_eq!(b.1.as_deref(), Some("true"));
+    assert_eq!(a.2, b.2);
+    assert_eq!(f.executions(&name).await, 1);
+}
@@ -0,0 +1,145 @@
+//! Invoice sending and deletion. Skipped when `TEST_DATABASE_URL` is unset.
+
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+
+/// A draft invoice for a client with an email address.
+async fn draft(f: &common::Fixture) -> String {
+    let client_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO clients (tenant_id, name, business_type, email) \
+         VALUES ($1, 'Acme', 'llc', 'ap@acme.example') RETURNING id",
+    )
+    .bind(f.tenant_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    let (status, invoice) = f
+        .request(
+            "POST",
+            "/api/v1/invoices",
+            json!({
+                "client_id": client_id,
+                "line_items": [{"description": "Advisory", "quantity": 1, "unit_price_cents": 50_000}],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+    invoice["id"].as_str().unwrap().to_string()
+}
+
+async fn queued_emails(f: &common::Fixture) -> i64 {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM email_outbox WHERE tenant_id = $1 AND category = 'invoice'",
+    )
+    .bind(f.tenant_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn bulk_send_emails_each_draft() {
+    let Some(f) = common::fixture().await else {
+        return;
+    };
+    let first = draft(&f).await;
+    let second = draft(&f).await;
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/send", first),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(queued_emails(&f).await, 1);
+
+    // Already sent and unknown invoices are skipped.
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/invoices/bulk-send",
+            json!({"ids": [first, second, Uuid::new_v4()]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body, json!({"sent": 1, "requested": 3}));
+    assert_eq!(queued_emails(&f).await, 2);
+}
+
+#[tokio::test]
+async fn line_totals_round_to_the_nearest_cent() {
+    let Some(f) = common::fixture().await else {
+        return;
+    };
+    let client_id = common::seed_client(&f.db, f.tenant_id).await;
+    let (status, invoice) = f
+        .request(
+            "POST",
+            "/api/v1/invoices",
+            json!({
+                "client_id": client_id,
+                "line_items": [
+                    {"description": "Review", "quantity": 1.5, "unit_price_cents": 3_333},
+                    {"description": "Filing", "quantity": 0.3333, "unit_price_cents": 10_000},
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+    assert_eq!(invoice["subtotal_cents"], 5_000 + 3_333);
+    assert_eq!(invoice["total_cents"], 5_000 + 3_333);
+
+    let lines: Vec<i64> = sqlx::query_scalar(
+        "SELECT total_cents FROM invoice_line_items WHERE invoice_id = $1::uuid ORDER BY sort_order",
+    )
+    .bind(invoice["id"].as_str().unwrap())
+    .fetch_all(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(lines, [5_000, 3_333]);
+    let stored: i64 = sqlx::query_scalar("SELECT subtotal_cents FROM invoices WHERE id = $1::uuid")
+        .bind(invoice["id"].as_str().unwrap())
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(stored, 5_000 + 3_333);
+}
+
+#[tokio::test]
+async fn only_drafts_can_be_deleted() {
+    let Some(f) = common::fixture().await else {
+        return;
+    };
+    let sent = draft(&f).await;
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/send", sent),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = f
+        .request("DELETE", &format!("/api/v1/invoices/{}", sent), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    let (status, _) = f
+        .request("GET", &format!("/api/v1/invoices/{}", sent), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let unsent = draft(&f).await;
+    let (status, _) = f
+        .request("DELETE", &format!("/api/v1/invoices/{}", unsent), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = f
+        .request("GET", &format!("/api/v1/invoices/{}", unsent), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,200 @@
+//! Match scoring and recommendations. Skipped when `TEST_DATABASE_URL` is
+//! unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    job_id: Uuid,
+    /// Ada knows Rust and lives in Austin; Alan knows neither.
+    ada: Uuid,
+    alan: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, status, work_mode, location_city, \
+         location_state, salary_min_cents, salary_max_cents, skills_required, skills_preferred) \
+         VALUES ($1, 'Rust Engineer', 'open', 'onsite', 'Austin', 'TX', 12000000, 15000000, \
+         '{Rust,PostgreSQL}', '{Kubernetes}') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    let mut candidates = Vec::new();
+    for (first, city, state, skill) in [
+        ("Ada", "Austin", "TX", "rust"),
+        ("Alan", "Boston", "MA", "Excel"),
+    ] {
+        let user_id = common::seed_user(db, tenant_id, first, "client").await;
+        let candidate_id: Uuid = sqlx::query_scalar(
+            "WITH cp AS (INSERT INTO candidate_profiles \
+             (tenant_id, user_id, location_city, location_state, remote_preference, \
+              desired_salary_min_cents) \
+             VALUES ($1, $2, $3, $4, 'onsite', 13000000) RETURNING id) \
+             INSERT INTO candidate_skills (tenant_id, candidate_id, skill_name) \
+             SELECT $1, cp.id, $5 FROM cp RETURNING candidate_id",
+        )
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(city)
+        .bind(state)
+        .bind(skill)
+        .fetch_one(db)
+        .await
+        .unwrap();
+        candidates.push(candidate_id);
+    }
+
+    Some(Scenario {
+        fixture,
+        job_id,
+        ada: candidates[0],
+        alan: candidates[1],
+    })
+}
+
+impl Scenario {
+    async fn match_score(&self, application_id: &str) -> f64 {
+        sqlx::query_scalar("SELECT match_score::float8 FROM applications WHERE id = $1::uuid")
+            .bind(application_id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn applications_are_scored_and_rescored_on_change() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+
+    let (status, application) = f
+        .request(
+            "POST",
+            "/api/v1/applications",
+            json!({"job_id": f.job_id, "candidate_id": f.ada}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", application);
+    // Half the required skills (20 of 40), none preferred (0 of 15), work
+    // mode, location and salary in full (45): 65 of 100.
+    assert_eq!(application["match_score"], 65.0);
+    let reasons = application["match_reasons"].as_array().unwrap();
+    assert_eq!(reasons[0]["factor"], "required_skills");
+    assert_eq!(reasons[0]["matched"], json!(["Rust"]));
+    assert_eq!(reasons[0]["missing"], json!(["PostgreSQL"]));
+    let application_id = application["id"].as_str().unwrap();
+
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/candidates/{}/skills", f.ada),
+            json!({"skill_name": "PostgreSQL"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(f.match_score(application_id).await, 85.0);
+
+    let (status, _) = f
+        .request(
+            "PATCH",
+            &format!("/api/v1/jobs/{}", f.job_id),
+            json!({"skills_preferred": []}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(f.match_score(application_id).await, 100.0);
+
+    let (status, _) = f
+        .request(
+            "PATCH",
+            &format!("/api/v1/candidates/{}", f.ada),
+            json!({"desired_salary_min_cents": 18750000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(f.match_score(application_id).await, 82.35);
+}
+
+#[tokio::test]
+async fn recommendations_rank_by_score() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+
+    let (status, body) = f
+        .request(
+            "GET",
+            &format!("/api/v1/jobs/{}/recommended-candidates", f.job_id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let ranked: Vec<&str> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["candidate_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(ranked, ["Ada Test", "Alan Test"]);
+    assert!(body[0]["match_score"].as_f64().unwrap() > body[1]["match_score"].as_f64().unwrap());
+
+    let (_, body) = f
+        .request(
+            "GET",
+            &format!(
+                "/api/v1/jobs/{}/recommended-candidates?min_score=50",
+                f.job_id
+            ),
+            json!({}),
+        )
+        .await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let (status, body) = f
+        .request(
+            "GET",
+            &format!("/api/v1/candidates/{}/recommended-jobs", f.alan),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body[0]["job_id"], f.job_id.to_string());
+    assert_eq!(body[0]["applied"], false);
+
+    // Jobs already applied to are left out unless asked for.
+    sqlx::query("INSERT INTO applications (tenant_id, job_id, candidate_id) VALUES ($1, $2, $3)")
+        .bind(f.tenant_id)
+        .bind(f.job_id)
+        .bind(f.alan)
+        .execute(&f.db)
+        .await
+        .unwrap();
+    let uri = format!("/api/v1/candidates/{}/recommended-jobs", f.alan);
+    let (_, body) = f.request("GET", &uri, json!({})).await;
+    assert_eq!(body, json!([]));
+    let (_, body) = f
+        .request("GET", &format!("{}?include_applied=true", uri), json!({}))
+        .await;
+    assert_eq!(body[0]["applied"], true);
+}
@@ -0,0 +1,269 @@
+//! Offer negotiation, acceptance and expiry. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+use cpa_backend::offers::expiry;
+use cpa_backend::scheduler::Job;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    application_id: Uuid,
+    job_id: Uuid,
+    candidate_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let candidate_user = common::seed_user(db, tenant_id, "Ada", "client").await;
+
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, status) \
+         VALUES ($1, 'Senior Accountant', 'open') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let application_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO applications (tenant_id, job_id, candidate_id, stage) \
+         VALUES ($1, $2, $3, 'onsite') RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(job_id)
+    .bind(candidate_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    Some(Scenario {
+        fixture,
+        application_id,
+        job_id,
+        candidate_id,
+    })
+}
+
+impl Scenario {
+    /// Create and send an offer; returns its id.
+    async fn sent_offer(&self, expiry_date: &str) -> String {
+        let (status, offer) = self
+            .request(
+                "POST",
+                "/api/v1/offers",
+                json!({
+                    "application_id": self.application_id,
+                    "job_id": self.job_id,
+                    "candidate_id": self.candidate_id,
+                    "title": "Senior Accountant",
+                    "base_salary_cents": 9_000_000,
+                    "equity_pct": 0.5,
+                    "expiry_date": expiry_date,
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", offer);
+        let id = offer["id"].as_str().unwrap().to_string();
+        let (status, _) = self
+            .request("POST", &format!("/api/v1/offers/{}/send", id), json!({}))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        id
+    }
+}
+
+#[tokio::test]
+async fn negotiated_offer_is_accepted_and_hires_the_candidate() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let id = f.sent_offer("2999-01-01").await;
+
+    let (status, negotiation) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/counter", id),
+            json!({"base_salary_cents": 10_000_000, "notes": "Competing offer"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", negotiation);
+    assert_eq!(negotiation["status"], "counter_offer");
+    assert_eq!(negotiation["candidate_name"], "Ada Test");
+    // The counter keeps the equity it did not mention.
+    assert_eq!(negotiation["current_offer"]["equity_pct"], 0.5);
+
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/counter", id),
+            json!({"base_salary_cents": 11_000_000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let (status, negotiation) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/negotiation/respond", id),
+            json!({"action": "revise", "base_salary_cents": 9_500_000, "final_offer": true}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", negotiation);
+    assert_eq!(negotiation["status"], "final_offer");
+
+    let (_, offer) = f
+        .request("GET", &format!("/api/v1/offers/{}", id), json!({}))
+        .await;
+    assert_eq!(offer["status"], "sent");
+    assert_eq!(offer["base_salary_cents"], 9_500_000);
+
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/counter", id),
+            json!({"base_salary_cents": 9_800_000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, offer) = f
+        .request("POST", &format!("/api/v1/offers/{}/accept", id), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", offer);
+    assert_eq!(offer["status"], "accepted");
+
+    let (_, negotiation) = f
+        .request(
+            "GET",
+            &format!("/api/v1/offers/{}/negotiation", id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(negotiation["status"], "accepted");
+    let history: Vec<(&str, &str, i64)> = negotiation["rounds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["proposed_by"].as_str().unwrap(),
+                r["action"].as_str().unwrap(),
+                r["base_salary_cents"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        history,
+        [
+            ("company", "offer", 9_000_000),
+            ("candidate", "counter", 10_000_000),
+            ("company", "revise", 9_500_000),
+            ("candidate", "accept", 9_500_000),
+        ]
+    );
+
+    let (stage, status, amount, accepted, hired): (String, String, Option<i64>, bool, bool) =
+        sqlx::query_as(
+            "SELECT stage, status, offer_amount_cents, offer_accepted_at IS NOT NULL, \
+             hired_at IS NOT NULL FROM applications WHERE id = $1",
+        )
+        .bind(f.application_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!((stage.as_str(), status.as_str()), ("hired", "hired"));
+    assert_eq!(amount, Some(9_500_000));
+    assert!(accepted && hired);
+
+    let events: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM application_stage_events \
+         WHERE application_id = $1 AND from_stage = 'onsite' AND to_stage = 'hired'",
+    )
+    .bind(f.application_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(events, 1);
+}
+
+#[tokio::test]
+async fn sent_offers_past_expiry_are_expired() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let id = f.sent_offer("2999-01-01").await;
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/counter", id),
+            json!({"start_date": "2999-02-01"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/offers/{}/negotiation/respond", id),
+            json!({"action": "reject"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    sqlx::query("UPDATE offers SET expiry_date = CURRENT_DATE - 1 WHERE id = $1::uuid")
+        .bind(&id)
+        .execute(&f.db)
+        .await
+        .unwrap();
+
+    let job = Job {
+        id: Uuid::new_v4(),
+        tenant_id: None,
+        job_type: expiry::JOB_TYPE.to_string(),
+        payload: json!({}),
+        attempts: 0,
+        max_attempts: 1,
+        run_at: chrono::Utc::now(),
+    };
+    expiry::expire_offers(f.state.clone(), job).await.unwrap();
+
+    let (_, offer) = f
+        .request("GET", &format!("/api/v1/offers/{}", id), json!({}))
+        .await;
+    assert_eq!(offer["status"], "expired");
+    let (_, negotiation) = f
+        .request(
+            "GET",
+            &format!("/api/v1/offers/{}/negotiation", id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(negotiation["status"], "expired");
+    assert_eq!(negotiation["rounds"][2]["action"], "reject");
+
+    let (status, _) = f
+        .request("POST", &format!("/api/v1/offers/{}/accept", id), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,49 @@
+//! Email outbox dispatch against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use cpa_backend::mailer::memory::MemoryMailer;
+use cpa_backend::mailer::{outbox, Mailer};
+
+mod common;
+
+/// A message claimed an hour ago by a dispatcher that never finished it.
+async fn stale_message(db: &PgPool, tenant_id: Uuid, attempts: i32, max_attempts: i32) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO email_outbox (tenant_id, to_email, subject, body_text, status, attempts, max_attempts, locked_at, next_attempt_at) \
+         VALUES ($1, 'client@example.com', 'Hello', 'Hi', 'sending', $2, $3, NOW() - INTERVAL '1 hour', NOW() - INTERVAL '1 hour') \
+         RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(attempts)
+    .bind(max_attempts)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn status(db: &PgPool, id: Uuid) -> (String, i32) {
+    sqlx::query_as("SELECT status, attempts FROM email_outbox WHERE id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn stale_messages_are_retried_until_out_of_attempts() {
+    let Some((_, db)) = common::test_database().await else {
+        return;
+    };
+    let tenant_id = common::seed_tenant(&db).await;
+    let retried = stale_message(&db, tenant_id, 2, 3).await;
+    let exhausted = stale_message(&db, tenant_id, 3, 3).await;
+
+    let mailer = Mailer::Memory(MemoryMailer::new());
+    outbox::dispatch_due(&db, &mailer).await.unwrap();
+
+    assert_eq!(status(&db, retried).await, ("sent".to_string(), 3));
+    assert_eq!(status(&db, exhausted).await, ("failed".to_string(), 3));
+}
@@ -0,0 +1,240 @@
+//! Payment allocation across invoices, client credit, credit notes,
+//! refunds and the invoice state machine. Skipped when `TEST_DATABASE_URL`
+//! is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use chrono::{Days, NaiveDate};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    client_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let client_id = common::seed_client(&fixture.db, fixture.tenant_id).await;
+    Some(Scenario { fixture, client_id })
+}
+
+impl Scenario {
+    /// A sent USD invoice for `amount` cents, due on `due_date`.
+    async fn sent_invoice(&self, amount: i64, due_date: NaiveDate) -> String {
+        let (status, invoice) = self
+            .request(
+                "POST",
+                "/api/v1/invoices",
+                json!({
+                    "client_id": self.client_id,
+                    "due_date": due_date,
+                    "line_items": [{"description": "Advisory", "quantity": 1, "unit_price_cents": amount}],
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+        let id = invoice["id"].as_str().unwrap().to_string();
+        self.set_status(&id, "sent", StatusCode::OK).await;
+        id
+    }
+
+    async fn set_status(&self, id: &str, status: &str, expected: StatusCode) -> Value {
+        let (code, body) = self
+            .request(
+                "PATCH",
+                &format!("/api/v1/invoices/{}/status", id),
+                json!({"status": status}),
+            )
+            .await;
+        assert_eq!(code, expected, "{}", body);
+        body
+    }
+
+    /// `(status, amount_paid_cents)`
+    async fn invoice(&self, id: &str) -> (String, i64) {
+        let (status, invoice) = self
+            .request("GET", &format!("/api/v1/invoices/{}", id), json!({}))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", invoice);
+        (
+            invoice["status"].as_str().unwrap().to_string(),
+            invoice["amount_paid_cents"].as_i64().unwrap(),
+        )
+    }
+
+    async fn credit(&self) -> Value {
+        let (status, credit) = self
+            .request(
+                "GET",
+                &format!("/api/v1/clients/{}/credit", self.client_id),
+                json!({}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", credit);
+        credit
+    }
+}
+
+#[tokio::test]
+async fn payments_are_allocated_refunded_and_credited() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let today = chrono::Utc::now().date_naive();
+    let older = f.sent_invoice(10_000, today + Days::new(5)).await;
+    let newer = f.sent_invoice(5_000, today + Days::new(20)).await;
+
+    // Invoices are paid by payments, not by hand.
+    let body = f.set_status(&older, "paid", StatusCode::BAD_REQUEST).await;
+    assert!(
+        body.to_string()
+            .contains("Allowed transitions: viewed, void"),
+        "{}",
+        body
+    );
+
+    // One payment across both invoices, oldest due first.
+    let (status, payment) = f
+        .request(
+            "POST",
+            "/api/v1/payments",
+            json!({"client_id": f.client_id, "amount_cents": 12_000, "method": "wire"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", payment);
+    let payment_id = payment["id"].as_str().unwrap().to_string();
+    assert_eq!(payment["allocations"].as_array().unwrap().len(), 2);
+    assert_eq!(payment["unallocated_cents"], 0);
+    assert_eq!(f.invoice(&older).await, ("paid".into(), 10_000));
+    assert_eq!(f.invoice(&newer).await, ("partial".into(), 2_000));
+
+    // Overpaying leaves credit.
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/payment", newer),
+            json!({"amount_cents": 4_000, "method": "check"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["invoice_status"], "paid");
+    assert_eq!(body["credit_cents"], 1_000);
+    assert_eq!(f.credit().await[0]["total_cents"], 1_000);
+
+    // A credit note against a third invoice pays it first; the client's
+    // credit covers more on request.
+    let third = f.sent_invoice(8_000, today + Days::new(30)).await;
+    let (status, note) = f
+        .request(
+            "POST",
+            "/api/v1/credit-notes",
+            json!({"invoice_id": third, "amount_cents": 2_000, "reason": "Discount"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", note);
+    assert_eq!(note["credit_note_number"], "CN-00001");
+    assert_eq!(note["unallocated_cents"], 0);
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/apply-credit", third),
+            json!({"amount_cents": 1_500}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, invoice) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/apply-credit", third),
+            json!({"amount_cents": 1_000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", invoice);
+    assert_eq!(invoice["status"], "partial");
+    assert_eq!(invoice["amount_paid_cents"], 3_000);
+    assert_eq!(f.credit().await, json!([]));
+
+    // Refunds come off the latest-due invoice first.
+    let (status, refunded) = f
+        .request(
+            "POST",
+            &format!("/api/v1/payments/{}/refund", payment_id),
+            json!({"amount_cents": 3_000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", refunded);
+    assert_eq!(refunded["refunded_cents"], 3_000);
+    assert_eq!(refunded["refunds"][0]["amount_cents"], -3_000);
+    assert_eq!(f.invoice(&newer).await, ("partial".into(), 3_000));
+    assert_eq!(f.invoice(&older).await, ("partial".into(), 9_000));
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/payments/{}/refund", payment_id),
+            json!({"amount_cents": 10_000}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Voiding an invoice returns what paid it to credit.
+    f.set_status(&third, "void", StatusCode::OK).await;
+    assert_eq!(f.invoice(&third).await, ("void".into(), 0));
+    let credit = f.credit().await;
+    assert_eq!(
+        (
+            &credit[0]["credit_notes_cents"],
+            &credit[0]["payments_cents"]
+        ),
+        (&json!(2_000), &json!(1_000))
+    );
+    f.set_status(&third, "sent", StatusCode::BAD_REQUEST).await;
+
+    // Credit can be allocated explicitly, but not past what is due.
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/payments/{}/allocate", payment_id),
+            json!({"allocations": [{"invoice_id": newer, "amount_cents": 2_500}]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, note) = f
+        .request(
+            "POST",
+            &format!("/api/v1/credit-notes/{}/void", note["id"].as_str().unwrap()),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", note);
+    assert_eq!(note["status"], "void");
+    assert_eq!(f.credit().await[0]["total_cents"], 1_000);
+
+    let (status, payments) = f
+        .request(
+            "GET",
+            &format!("/api/v1/payments?client_id={}", f.client_id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", payments);
+    let amounts: Vec<i64> = payments["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["amount_cents"].as_i64().unwrap())
+        .collect();
+    assert_eq!(amounts.len(), 3);
+    assert_eq!(amounts.iter().sum::<i64>(), 13_000);
+}
@@ -0,0 +1,436 @@
+//! Stage changes checked against a job's hiring pipeline. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    candidate_id: Uuid,
+    job_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+/// A tenant with one job and one candidate who has not applied yet.
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let candidate_user = common::seed_user(db, tenant_id, "Ada", "client").await;
+
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, description) \
+         VALUES ($1, 'Rust Engineer', 'Build the platform') RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    Some(Scenario {
+        fixture,
+        candidate_id,
+        job_id,
+    })
+}
+
+impl Scenario {
+    async fn template(&self, stages: Value) -> Uuid {
+        let (status, body) = self
+            .request(
+                "POST",
+                "/api/v1/pipeline-templates",
+                json!({"name": "Engineering", "stages": stages}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        body["id"].as_str().unwrap().parse().unwrap()
+    }
+
+    async fn apply(&self) -> (Uuid, String) {
+        let (status, body) = self
+            .request(
+                "POST",
+                "/api/v1/applications",
+                json!({"job_id": self.job_id, "candidate_id": self.candidate_id}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        (
+            body["id"].as_str().unwrap().parse().unwrap(),
+            body["stage"].as_str().unwrap().to_string(),
+        )
+    }
+
+    async fn move_to(&self, application_id: Uuid, stage: &str) -> (StatusCode, Value) {
+        self.request(
+            "POST",
+            &format!("/api/v1/applications/{}/stage", application_id),
+            json!({"to_stage": stage}),
+        )
+        .await
+    }
+
+    async fn submit_scorecard(&self, application_id: Uuid, stage: &str) {
+        sqlx::query(
+            "INSERT INTO scorecards (tenant_id, application_id, interviewer_id, interview_stage, \
+             overall_score, submitted_at) VALUES ($1, $2, $3, $4, 4, NOW())",
+        )
+        .bind(self.tenant_id)
+        .bind(application_id)
+        .bind(self.admin_id)
+        .bind(stage)
+        .execute(&self.db)
+        .await
+        .unwrap();
+    }
+}
+
+fn engineering_stages() -> Value {
+    json!([
+        {"key": "screening", "name": "Recruiter screen", "next": ["technical"]},
+        {"key": "technical", "name": "Technical", "next": ["offer"], "required_scorecards": 2},
+        {"key": "offer", "name": "Offer"},
+        {"key": "hired", "name": "Hired", "terminal": true},
+    ])
+}
+
+#[tokio::test]
+async fn transitions_follow_the_job_pipeline() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let template_id = f.template(engineering_stages()).await;
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/jobs/{}/pipeline", f.job_id),
+            json!({"template_id": template_id}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["stages"][0]["name"], "Recruiter screen");
+
+    let (application_id, stage) = f.apply().await;
+    assert_eq!(stage, "screening");
+
+    let (status, body) = f.move_to(application_id, "offer").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("allowed: technical, rejected, withdrawn"),
+        "{}",
+        body
+    );
+    let (status, _) = f.move_to(application_id, "onsite").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = f.move_to(application_id, "technical").await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Leaving technical needs two submitted scorecards for that stage.
+    f.submit_scorecard(application_id, "technical").await;
+    f.submit_scorecard(application_id, "screening").await;
+    let (status, body) = f.move_to(application_id, "offer").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("needs 2 submitted scorecard(s)"));
+    f.submit_scorecard(application_id, "technical").await;
+    let (status, _) = f.move_to(application_id, "offer").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = f.move_to(application_id, "hired").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/reject", application_id),
+            json!({"to_stage": "rejected", "notes": "Too late"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("terminal"));
+
+    let history: Vec<(Option<String>, String)> = sqlx::query_as(
+        "SELECT from_stage, to_stage FROM application_stage_events \
+         WHERE application_id = $1 ORDER BY created_at, id",
+    )
+    .bind(application_id)
+    .fetch_all(&f.db)
+    .await
+    .unwrap();
+    let to: Vec<&str> = history.iter().map(|(_, to)| to.as_str()).collect();
+    assert_eq!(to.len(), 4);
+    assert!(to.contains(&"hired"));
+}
+
+#[tokio::test]
+async fn reject_and_withdraw_go_through_the_state_machine() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    // No template: the built-in pipeline applies.
+    let (rejected, stage) = f.apply().await;
+    assert_eq!(stage, "applied");
+
+    // A gated stage does not hold up a rejection.
+    f.request(
+        "PUT",
+        &format!("/api/v1/jobs/{}/pipeline", f.job_id),
+        json!({"overrides": {"applied": {"required_scorecards": 1}}}),
+    )
+    .await;
+    let (status, _) = f.move_to(rejected, "screening").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/reject", rejected),
+            json!({"to_stage": "rejected", "notes": "Not enough Rust"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["stage"], "rejected");
+    assert_eq!(body["status"], "rejected");
+    assert_eq!(body["rejected_reason"], "Not enough Rust");
+
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/withdraw", rejected),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = f.move_to(rejected, "applied").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let events: Vec<(String, Value)> = sqlx::query_as(
+        "SELECT job_type, payload FROM background_jobs WHERE tenant_id = $1 AND job_type = $2",
+    )
+    .bind(f.tenant_id)
+    .bind(cpa_backend::automations::events::JOB_TYPE)
+    .fetch_all(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].1["event"]["to_stage"], "rejected");
+
+    let withdrawn: Uuid = sqlx::query_scalar(
+        "WITH job AS (INSERT INTO job_posts (tenant_id, title, description) \
+         VALUES ($1, 'Data Engineer', 'Pipelines') RETURNING id) \
+         INSERT INTO applications (tenant_id, job_id, candidate_id, stage) \
+         SELECT $1, job.id, $2, 'applied' FROM job RETURNING id",
+    )
+    .bind(f.tenant_id)
+    .bind(f.candidate_id)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/applications/{}/withdraw", withdrawn),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["status"], "withdrawn");
+    let from: Option<String> = sqlx::query_scalar(
+        "SELECT from_stage FROM application_stage_events \
+         WHERE application_id = $1 AND to_stage = 'withdrawn'",
+    )
+    .bind(withdrawn)
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(from.as_deref(), Some("applied"));
+}
+
+#[tokio::test]
+async fn templates_are_validated_reordered_and_protected() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/pipeline-templates",
+            json!({"name": "Broken", "stages": [
+                {"key": "applied", "name": "Applied", "next": ["offer"]},
+                {"key": "rejected", "name": "Rejected"},
+            ]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let paths: Vec<&str> = body["error"]["details"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["stages[0].next[0]", "stages[1].key"]);
+
+    let first = f.template(engineering_stages()).await;
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/pipeline-templates/{}", first),
+            json!({"is_default": true}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // New jobs pick up the default template.
+    let (status, job) = f
+        .request(
+            "POST",
+            "/api/v1/jobs",
+            json!({"title": "Platform Engineer"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", job);
+    let job_id = job["id"].as_str().unwrap();
+    let (_, pipeline) = f
+        .request(
+            "GET",
+            &format!("/api/v1/jobs/{}/pipeline", job_id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(pipeline["template_id"], first.to_string());
+
+    // Only one default per tenant.
+    let (status, second) = f
+        .request(
+            "POST",
+            "/api/v1/pipeline-templates",
+            json!({"name": "Sales", "is_default": true, "stages": [
+                {"key": "applied", "name": "Applied"},
+                {"key": "hired", "name": "Hired", "terminal": true},
+            ]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, templates) = f
+        .request("GET", "/api/v1/pipeline-templates", json!({}))
+        .await;
+    let defaults: Vec<&Value> = templates
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|t| t["is_default"] == true)
+        .collect();
+    assert_eq!(defaults.len(), 1);
+    assert_eq!(defaults[0]["id"], second["id"]);
+
+    // Reordering must list every stage, and keep the pipeline valid.
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/pipeline-templates/{}/reorder", first),
+            json!({"order": ["technical", "screening", "offer"]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = f
+        .request(
+            "POST",
+            &format!("/api/v1/pipeline-templates/{}/reorder", first),
+            json!({"order": ["hired", "screening", "technical", "offer"]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/pipeline-templates/{}/reorder", first),
+            json!({"order": ["technical", "screening", "offer", "hired"]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["stages"][0]["key"], "technical");
+
+    // Overrides are checked against the template.
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/jobs/{}/pipeline", job_id),
+            json!({"overrides": {"onsite": {"name": "Onsite"}}}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["error"]["details"]["errors"][0]["path"],
+        "overrides.onsite"
+    );
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/jobs/{}/pipeline", job_id),
+            json!({"overrides": {"technical": {"required_scorecards": 0, "name": "Take-home"}}}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["stages"][0]["name"], "Take-home");
+
+    // Template changes that would break a job's overrides are refused.
+    let (status, _) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/pipeline-templates/{}", first),
+            json!({"stages": [
+                {"key": "screening", "name": "Screen"},
+                {"key": "hired", "name": "Hired", "terminal": true},
+            ]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = f
+        .request(
+            "DELETE",
+            &format!("/api/v1/pipeline-templates/{}", first),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = f
+        .request(
+            "DELETE",
+            &format!(
+                "/api/v1/pipeline-templates/{}",
+                second["id"].as_str().unwrap()
+            ),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,105 @@
+//! Recurring invoice issuance against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use cpa_backend::invoices::recurring::issue_template;
+
+mod common;
+
+fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, day).unwrap()
+}
+
+/// A monthly template anchored on the 31st that has never issued.
+async fn template(db: &PgPool, tenant_id: Uuid, anchor: NaiveDate) -> Uuid {
+    let user_id = common::seed_user(db, tenant_id, "Ada", "admin").await;
+    let client_id = common::seed_client(db, tenant_id).await;
+    sqlx::query_scalar(
+        "INSERT INTO recurring_invoices (tenant_id, client_id, schedule, anchor_date, next_issue_date, line_items, created_by) \
+         VALUES ($1, $2, 'monthly', $3, $3, $4, $5) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(client_id)
+    .bind(anchor)
+    .bind(serde_json::json!([
+        {"description": "Bookkeeping", "quantity": 1.0, "unit_price_cents": 25_000}
+    ]))
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn issued(db: &PgPool, recurring_id: Uuid) -> Vec<(NaiveDate, String)> {
+    sqlx::query_as(
+        "SELECT issued_date, invoice_number FROM invoices \
+         WHERE recurring_invoice_id = $1 ORDER BY issued_date",
+    )
+    .bind(recurring_id)
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn catching_up_issues_each_period_once() {
+    let Some((_, db)) = common::test_database().await else {
+        return;
+    };
+    let tenant_id = common::seed_tenant(&db).await;
+    let recurring_id = template(&db, tenant_id, d(2026, 1, 31)).await;
+    let today = d(2026, 5, 15);
+
+    // Two instances picking up the same template: one issues the backlog,
+    // the other finds it locked.
+    let (a, b) = tokio::join!(
+        issue_template(&db, tenant_id, recurring_id, today),
+        issue_template(&db, tenant_id, recurring_id, today),
+    );
+    assert_eq!(a.unwrap() + b.unwrap(), 4);
+
+    let expected = vec![
+        (d(2026, 1, 31), "INV-00001".to_string()),
+        (d(2026, 2, 28), "INV-00002".to_string()),
+        (d(2026, 3, 31), "INV-00003".to_string()),
+        (d(2026, 4, 30), "INV-00004".to_string()),
+    ];
+    assert_eq!(issued(&db, recurring_id).await, expected);
+    let (next_issue_date, generated): (NaiveDate, i32) = sqlx::query_as(
+        "SELECT next_issue_date, invoices_generated FROM recurring_invoices WHERE id = $1",
+    )
+    .bind(recurring_id)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!((next_issue_date, generated), (d(2026, 5, 31), 4));
+
+    // Nothing is due until the next period.
+    assert_eq!(
+        issue_template(&db, tenant_id, recurring_id, today)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // A run that issued but never advanced the template is replayed
+    // without duplicating periods.
+    sqlx::query("UPDATE recurring_invoices SET next_issue_date = anchor_date WHERE id = $1")
+        .bind(recurring_id)
+        .execute(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        issue_template(&db, tenant_id, recurring_id, d(2026, 6, 1))
+            .await
+            .unwrap(),
+        1
+    );
+    let invoices = issued(&db, recurring_id).await;
+    assert_eq!(invoices.len(), 5);
+    assert_eq!(invoices[..4], expected[..]);
+    assert_eq!(invoices[4], (d(2026, 5, 31), "INV-00005".to_string()));
+}
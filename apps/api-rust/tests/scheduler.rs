@@ -0,0 +1,82 @@
+//! Background job runner against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use cpa_backend::scheduler::runner::purge_succeeded;
+use cpa_backend::scheduler::JobRunner;
+
+mod common;
+
+/// A job claimed an hour ago by a worker that never finished it.
+async fn stale_job(db: &PgPool, attempts: i32, max_attempts: i32) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO background_jobs (job_type, status, attempts, max_attempts, locked_at, locked_by) \
+         VALUES ('test.stale', 'running', $1, $2, NOW() - INTERVAL '1 hour', 'gone') RETURNING id",
+    )
+    .bind(attempts)
+    .bind(max_attempts)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn status(db: &PgPool, id: Uuid) -> (String, Option<String>) {
+    sqlx::query_as("SELECT status, locked_by FROM background_jobs WHERE id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn stale_jobs_are_requeued_until_out_of_attempts() {
+    let Some((_, db)) = common::test_database().await else {
+        return;
+    };
+    let retried = stale_job(&db, 2, 3).await;
+    let exhausted = stale_job(&db, 3, 3).await;
+
+    JobRunner::new().requeue_stale(&db).await.unwrap();
+
+    assert_eq!(status(&db, retried).await, ("queued".to_string(), None));
+    assert_eq!(status(&db, exhausted).await, ("dead".to_string(), None));
+}
+
+/// A job that finished `status` the given number of days ago.
+async fn finished_job(db: &PgPool, status: &str, days_ago: i32) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO background_jobs (job_type, status, completed_at) \
+         VALUES ('test.finished', $1, NOW() - make_interval(days => $2)) RETURNING id",
+    )
+    .bind(status)
+    .bind(days_ago)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn exists(db: &PgPool, id: Uuid) -> bool {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM background_jobs WHERE id = $1)")
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn old_succeeded_jobs_are_purged() {
+    let Some((_, db)) = common::test_database().await else {
+        return;
+    };
+    let old = finished_job(&db, "succeeded", 30).await;
+    let recent = finished_job(&db, "succeeded", 1).await;
+    let dead = finished_job(&db, "dead", 30).await;
+
+    purge_succeeded(&db).await.unwrap();
+
+    assert!(!exists(&db, old).await);
+    assert!(exists(&db, recent).await);
+    assert!(exists(&db, dead).await);
+}
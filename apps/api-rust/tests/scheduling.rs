@@ -0,0 +1,276 @@
+//! Slot finding across timezones and candidate self-scheduling. Skipped
+//! when `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    /// Works 09:00-17:00 in New York on Mondays.
+    new_york_id: Uuid,
+    /// Works 12:00-18:00 in London on Mondays.
+    london_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let f = Scenario {
+        new_york_id: common::seed_user(db, tenant_id, "Nora", "manager").await,
+        london_id: common::seed_user(db, tenant_id, "Liam", "senior_accountant").await,
+        fixture,
+    };
+    for (user_id, role, start, end, timezone) in [
+        (
+            f.new_york_id,
+            "manager",
+            "09:00",
+            "17:00",
+            "America/New_York",
+        ),
+        (
+            f.london_id,
+            "senior_accountant",
+            "12:00",
+            "18:00",
+            "Europe/London",
+        ),
+    ] {
+        let (status, body) = f
+            .request_as(
+                user_id,
+                role,
+                "PUT",
+                "/api/v1/meetings/availability",
+                json!({"blocks": [{
+                    "day_of_week": 1,
+                    "start_time": start,
+                    "end_time": end,
+                    "timezone": timezone,
+                }]}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    Some(f)
+}
+
+impl Scenario {
+    async fn public(&self, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        self.send(request).await
+    }
+
+    /// An accepted hour-long meeting for `user_id` at `at`.
+    async fn busy(&self, user_id: Uuid, at: &str) {
+        let meeting_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO meeting_requests \
+             (tenant_id, title, requested_by, duration_minutes, status, accepted_time) \
+             VALUES ($1, 'Busy', $2, 60, 'accepted', $3::timestamptz) RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .bind(user_id)
+        .bind(at)
+        .fetch_one(&self.db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO meeting_participants (tenant_id, meeting_id, user_id, response_status) \
+             VALUES ($1, $2, $3, 'accepted')",
+        )
+        .bind(self.tenant_id)
+        .bind(meeting_id)
+        .bind(user_id)
+        .execute(&self.db)
+        .await
+        .unwrap();
+    }
+}
+
+fn starts(slots: &Value) -> Vec<String> {
+    let mut starts: Vec<String> = slots
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["starts_at"].as_str().unwrap().to_string())
+        .collect();
+    starts.sort();
+    starts
+}
+
+#[tokio::test]
+async fn slots_intersect_participants_across_a_dst_change() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    f.busy(f.new_york_id, "2030-03-04T15:00:00Z").await;
+
+    // New York moves to daylight time on 2030-03-10; London does not until
+    // the end of the month.
+    let (status, slots) = f
+        .request(
+            "POST",
+            "/api/v1/meetings/slots",
+            json!({
+                "participant_user_ids": [f.new_york_id, f.london_id],
+                "duration_minutes": 60,
+                "from": "2030-03-03T00:00:00Z",
+                "to": "2030-03-13T00:00:00Z",
+                "step_minutes": 60,
+                "limit": 100,
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", slots);
+    assert_eq!(
+        starts(&slots),
+        vec![
+            // 09:00 EST is 14:00 UTC; 15:00 is taken.
+            "2030-03-04T14:00:00Z",
+            "2030-03-04T16:00:00Z",
+            "2030-03-04T17:00:00Z",
+            // 09:00 EDT is 13:00 UTC; London still ends at 18:00 UTC.
+            "2030-03-11T13:00:00Z",
+            "2030-03-11T14:00:00Z",
+            "2030-03-11T15:00:00Z",
+            "2030-03-11T16:00:00Z",
+            "2030-03-11T17:00:00Z",
+        ]
+    );
+
+    let (status, body) = f
+        .request(
+            "POST",
+            "/api/v1/meetings/slots",
+            json!({"participant_user_ids": [Uuid::new_v4()]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+}
+
+#[tokio::test]
+async fn candidate_books_a_slot_through_a_scheduling_link() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    f.busy(f.new_york_id, "2030-03-11T15:00:00Z").await;
+
+    let (status, meeting) = f
+        .request(
+            "POST",
+            "/api/v1/meetings/request",
+            json!({
+                "title": "Onsite interview",
+                "participant_user_ids": [f.new_york_id, f.london_id],
+                "duration_minutes": 60,
+                "proposed_times": [],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", meeting);
+    let meeting_id = meeting["id"].as_str().unwrap();
+
+    let (status, link) = f
+        .request(
+            "POST",
+            &format!("/api/v1/meetings/{}/scheduling-link", meeting_id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", link);
+    let token = link["token"].as_str().unwrap();
+    assert!(link["url"].as_str().unwrap().ends_with(token));
+
+    let (status, page) = f
+        .public(
+            "GET",
+            &format!("/api/v1/public/scheduling/{}", token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    assert_eq!(page["title"], "Onsite interview");
+    assert_eq!(page["organizer_name"], "Grace Test");
+    assert!(!page["slots"].as_array().unwrap().is_empty());
+
+    let book_uri = format!("/api/v1/public/scheduling/{}", token);
+    let book_uri = book_uri.as_str();
+    let book = |starts_at: &'static str| {
+        f.public(
+            "POST",
+            book_uri,
+            json!({"starts_at": starts_at, "timezone": "America/Chicago"}),
+        )
+    };
+    // An interviewer is already busy.
+    let (status, body) = book("2030-03-11T15:00:00Z").await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    // Outside London's hours.
+    let (status, body) = book("2030-03-11T18:00:00Z").await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let (status, booking) = book("2030-03-11T16:00:00Z").await;
+    assert_eq!(status, StatusCode::OK, "{}", booking);
+    assert_eq!(booking["ends_at"], "2030-03-11T17:00:00Z");
+
+    let (_, detail) = f
+        .request(
+            "GET",
+            &format!("/api/v1/meetings/{}", meeting_id),
+            json!({}),
+        )
+        .await;
+    assert_eq!(detail["meeting"]["status"], "accepted");
+    assert_eq!(detail["meeting"]["accepted_timezone"], "America/Chicago");
+    assert!(detail["participants"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|p| p["response_status"] == "accepted"));
+
+    let (status, _) = book("2030-03-11T13:00:00Z").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // The booked time now counts against the interviewers.
+    let (_, slots) = f
+        .request(
+            "POST",
+            "/api/v1/meetings/slots",
+            json!({
+                "participant_user_ids": [f.london_id],
+                "from": "2030-03-11T00:00:00Z",
+                "to": "2030-03-12T00:00:00Z",
+                "step_minutes": 60,
+            }),
+        )
+        .await;
+    assert_eq!(
+        starts(&slots),
+        vec![
+            "2030-03-11T12:00:00Z",
+            "2030-03-11T13:00:00Z",
+            "2030-03-11T14:00:00Z",
+            "2030-03-11T15:00:00Z",
+            "2030-03-11T17:00:00Z",
+        ]
+    );
+}
@@ -0,0 +1,249 @@
+//! Search indexing and querying against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use uuid::Uuid;
+
+use cpa_backend::scheduler::Job;
+use cpa_backend::search::{indexer, Entity, SearchEngine};
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    candidate_id: Uuid,
+    /// Private note by the admin.
+    note_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+/// A tenant with one candidate (Ada Lovelace: Python, Rust) who applied to a
+/// Rust Engineer job, plus a private note on her.
+async fn scenario(backend: &str) -> Option<Scenario> {
+    let fixture = common::fixture_with(|config| {
+        config.search_backend = Some(backend.to_string());
+    })
+    .await?;
+    let (db, tenant_id) = (&fixture.db, fixture.tenant_id);
+    let candidate_user = common::seed_user(db, tenant_id, "Ada Lovelace", "client").await;
+
+    let candidate_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_profiles (tenant_id, user_id, headline, location_city, location_state) \
+         VALUES ($1, $2, 'Analytical engine programmer', 'Austin', 'TX') RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_user)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    for skill in ["Python", "Rust"] {
+        sqlx::query(
+            "INSERT INTO candidate_skills (tenant_id, candidate_id, skill_name) VALUES ($1, $2, $3)",
+        )
+        .bind(tenant_id)
+        .bind(candidate_id)
+        .bind(skill)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO job_posts (tenant_id, title, description, location_city, location_state, skills_required) \
+         VALUES ($1, 'Rust Engineer', 'Build the platform', 'Austin', 'TX', ARRAY['Rust']) RETURNING id",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO applications (tenant_id, job_id, candidate_id, stage) VALUES ($1, $2, $3, 'onsite')",
+    )
+    .bind(tenant_id)
+    .bind(job_id)
+    .bind(candidate_id)
+    .execute(db)
+    .await
+    .unwrap();
+    let note_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO candidate_notes (tenant_id, candidate_id, author_id, content, is_private) \
+         VALUES ($1, $2, $3, 'Excellent compiler background', true) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(candidate_id)
+    .bind(fixture.admin_id)
+    .fetch_one(db)
+    .await
+    .unwrap();
+
+    Some(Scenario {
+        fixture,
+        candidate_id,
+        note_id,
+    })
+}
+
+impl Scenario {
+    async fn search(&self, user_id: Uuid, query: &str) -> serde_json::Value {
+        let request = Request::builder()
+            .uri(format!("/api/v1/search?{}", query))
+            .header(
+                "authorization",
+                format!("Bearer {}", self.token(user_id, "admin")),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = self.send(request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        body
+    }
+
+    fn memory(&self) -> &cpa_backend::search::memory::MemoryEngine {
+        match &self.state.search {
+            SearchEngine::Memory(engine) => engine,
+            _ => panic!("fixture is not using the memory engine"),
+        }
+    }
+}
+
+fn types(body: &serde_json::Value) -> Vec<&str> {
+    let mut types: Vec<&str> = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["type"].as_str().unwrap())
+        .collect();
+    types.sort();
+    types
+}
+
+#[tokio::test]
+async fn row_changes_are_synced_to_the_engine() {
+    let Some(f) = scenario("memory").await else {
+        return;
+    };
+    let job = Job {
+        id: Uuid::new_v4(),
+        tenant_id: None,
+        job_type: indexer::SYNC_JOB_TYPE.to_string(),
+        payload: serde_json::json!({}),
+        attempts: 0,
+        max_attempts: 1,
+        run_at: chrono::Utc::now(),
+    };
+    indexer::sync(f.state.clone(), job.clone()).await.unwrap();
+
+    let docs = f.memory().documents(f.tenant_id);
+    let candidate = docs.iter().find(|d| d.entity == Entity::Candidate).unwrap();
+    assert_eq!(candidate.entity_id, f.candidate_id);
+    assert_eq!(candidate.title, "Ada Lovelace");
+    assert_eq!(candidate.skills, vec!["Python", "Rust"]);
+    assert_eq!(candidate.location.as_deref(), Some("Austin, TX"));
+    assert_eq!(docs.len(), 4);
+
+    sqlx::query("DELETE FROM candidate_notes WHERE id = $1")
+        .bind(f.note_id)
+        .execute(&f.db)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE job_posts SET title = 'Staff Rust Engineer' WHERE tenant_id = $1")
+        .bind(f.tenant_id)
+        .execute(&f.db)
+        .await
+        .unwrap();
+    indexer::sync(f.state.clone(), job).await.unwrap();
+
+    let docs = f.memory().documents(f.tenant_id);
+    assert!(!docs.iter().any(|d| d.entity == Entity::Note));
+    let job = docs.iter().find(|d| d.entity == Entity::Job).unwrap();
+    assert_eq!(job.title, "Staff Rust Engineer");
+}
+
+#[tokio::test]
+async fn engine_search_tolerates_typos_with_facets_and_highlights() {
+    let Some(f) = scenario("memory").await else {
+        return;
+    };
+    indexer::reindex(&f.state, Some(f.tenant_id)).await.unwrap();
+
+    let body = f.search(f.admin_id, "q=pyhton&type=candidates").await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["results"][0]["title"], "Ada Lovelace");
+    assert_eq!(
+        body["results"][0]["url"],
+        format!("/talent/{}", f.candidate_id)
+    );
+
+    let body = f.search(f.admin_id, "q=rust").await;
+    assert_eq!(types(&body), ["application", "candidate", "job"]);
+    assert_eq!(
+        body["facets"]["stage"],
+        serde_json::json!([{ "value": "onsite", "count": 1 }])
+    );
+    assert_eq!(body["facets"]["skills"][0]["value"], "Rust");
+    assert_eq!(body["facets"]["skills"][0]["count"], 3);
+
+    let body = f.search(f.admin_id, "q=rust&stage=onsite").await;
+    assert_eq!(types(&body), ["application"]);
+    assert_eq!(body["results"][0]["subtitle"], "Rust Engineer");
+
+    let body = f.search(f.admin_id, "q=compiler").await;
+    assert_eq!(
+        body["results"][0]["highlight"],
+        "Excellent <mark>compiler</mark> background"
+    );
+    // The note is private to its author.
+    let body = f.search(Uuid::new_v4(), "q=compiler").await;
+    assert_eq!(body["total"], 0);
+}
+
+#[tokio::test]
+async fn postgres_fallback_uses_stored_vectors() {
+    let Some(f) = scenario("postgres").await else {
+        return;
+    };
+    indexer::reindex(&f.state, Some(f.tenant_id)).await.unwrap();
+
+    let body = f.search(f.admin_id, "q=engin").await;
+    assert_eq!(types(&body), ["application", "candidate", "job"]);
+    assert_eq!(
+        body["facets"]["entity"],
+        serde_json::json!([
+            { "value": "application", "count": 1 },
+            { "value": "candidate", "count": 1 },
+            { "value": "job", "count": 1 },
+        ])
+    );
+    let job = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["type"] == "job")
+        .unwrap();
+    assert_eq!(job["highlight"], "Rust <mark>Engineer</mark>");
+
+    // A misspelt name still finds the candidate by trigram similarity.
+    let body = f.search(f.admin_id, "q=lovlace&type=candidates").await;
+    assert_eq!(body["total"], 1);
+
+    let body = f.search(f.admin_id, "q=rust&skills=Python").await;
+    assert_eq!(types(&body), ["application", "candidate"]);
+
+    let body = f.search(Uuid::new_v4(), "q=compiler").await;
+    assert_eq!(body["total"], 0);
+    let body = f.search(f.admin_id, "q=compiler").await;
+    assert_eq!(types(&body), ["note"]);
+    assert!(body["results"][0]["highlight"]
+        .as_str()
+        .unwrap()
+        .contains("<mark>compiler</mark>"));
+}
@@ -0,0 +1,422 @@
+//! Signed Stripe webhook deliveries against a real database. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+//!
+//! Payloads come from `tests/fixtures/stripe`, with `{{name}}` placeholders
+//! filled in per test so runs do not collide.
+
+use std::ops::Deref;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use uuid::Uuid;
+
+use cpa_backend::payments::webhook;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    /// Unique per test; used to derive Stripe ids.
+    tag: String,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let tag = Uuid::new_v4().simple().to_string();
+    sqlx::query("UPDATE tenants SET stripe_customer_id = $2 WHERE id = $1")
+        .bind(fixture.tenant_id)
+        .bind(format!("cus_{}", tag))
+        .execute(&fixture.db)
+        .await
+        .unwrap();
+    Some(Scenario { fixture, tag })
+}
+
+impl Scenario {
+    fn id(&self, prefix: &str) -> String {
+        format!("{}_{}", prefix, self.tag)
+    }
+
+    fn render(&self, name: &str, vars: &[(&str, String)]) -> String {
+        let path = format!(
+            "{}/tests/fixtures/stripe/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let mut payload = std::fs::read_to_string(path).unwrap();
+        let defaults = [
+            ("tenant_id", self.tenant_id.to_string()),
+            ("customer", self.id("cus")),
+            ("payment_intent", self.id("pi")),
+            ("charge", self.id("ch")),
+            ("dispute", self.id("dp")),
+            ("subscription", self.id("sub")),
+            ("stripe_invoice", self.id("in")),
+            ("price", self.id("price")),
+        ];
+        for (key, value) in vars.iter().cloned().chain(defaults) {
+            payload = payload.replace(&format!("{{{{{}}}}}", key), &value);
+        }
+        payload
+    }
+
+    async fn deliver_signed(&self, payload: &str, signature: &str) -> StatusCode {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/webhooks/stripe")
+            .header("content-type", "application/json")
+            .header("stripe-signature", signature)
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+        self.send(request).await.0
+    }
+
+    async fn deliver(&self, payload: &str) -> StatusCode {
+        let signature = webhook::sign(
+            payload.as_bytes(),
+            common::STRIPE_WEBHOOK_SECRET,
+            chrono::Utc::now().timestamp(),
+        );
+        self.deliver_signed(payload, &signature).await
+    }
+
+    async fn seed_invoice(&self, total_cents: i64) -> Uuid {
+        let client_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO clients (tenant_id, name, business_type) VALUES ($1, 'Acme', 'llc') RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .fetch_one(&self.db)
+        .await
+        .unwrap();
+        sqlx::query_scalar(
+            "INSERT INTO invoices (tenant_id, client_id, invoice_number, status, subtotal_cents, \
+             total_cents, created_by) \
+             VALUES ($1, $2, $3, 'sent', $4, $4, $5) RETURNING id",
+        )
+        .bind(self.tenant_id)
+        .bind(client_id)
+        .bind(format!("INV-{}", &self.tag[..8]))
+        .bind(total_cents)
+        .bind(self.admin_id)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    async fn seed_plan(&self) -> Uuid {
+        sqlx::query_scalar(
+            "INSERT INTO plans (name, slug, price_monthly_cents, stripe_price_id_monthly) \
+             VALUES ('Test', $1, 4900, $2) RETURNING id",
+        )
+        .bind(self.id("plan"))
+        .bind(self.id("price"))
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    /// Pay `amount` towards `invoice_id` with a fresh payment intent.
+    async fn pay(&self, invoice_id: Uuid, amount: i64, n: u32) -> StatusCode {
+        let payload = self.render(
+            "payment_intent.succeeded",
+            &[
+                ("event_id", self.id(&format!("evt_pi{}", n))),
+                ("payment_intent", self.id(&format!("pi{}", n))),
+                ("invoice_id", invoice_id.to_string()),
+                ("amount", amount.to_string()),
+            ],
+        );
+        self.deliver(&payload).await
+    }
+
+    async fn invoice(&self, id: Uuid) -> (String, i64, Option<chrono::NaiveDate>) {
+        sqlx::query_as("SELECT status, amount_paid_cents, paid_date FROM invoices WHERE id = $1")
+            .bind(id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+
+    async fn payment_events(&self, event_type: &str) -> i64 {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM payment_events WHERE tenant_id = $1 AND event_type = $2",
+        )
+        .bind(self.tenant_id)
+        .bind(event_type)
+        .fetch_one(&self.db)
+        .await
+        .unwrap()
+    }
+
+    async fn event_status(&self, id: &str) -> (String, i32) {
+        sqlx::query_as("SELECT status, attempts FROM stripe_events WHERE id = $1")
+            .bind(id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn partial_payments_accumulate_until_paid() {
+    let Some(f) = scenario().await else { return };
+    let invoice_id = f.seed_invoice(10_000).await;
+
+    assert_eq!(f.pay(invoice_id, 4_000, 1).await, StatusCode::OK);
+    let (status, paid, paid_date) = f.invoice(invoice_id).await;
+    assert_eq!((status.as_str(), paid, paid_date), ("partial", 4_000, None));
+
+    assert_eq!(f.pay(invoice_id, 6_000, 2).await, StatusCode::OK);
+    let (status, paid, paid_date) = f.invoice(invoice_id).await;
+    assert_eq!((status.as_str(), paid), ("paid", 10_000));
+    assert!(paid_date.is_some());
+    assert_eq!(f.payment_events("charge_succeeded").await, 2);
+}
+
+#[tokio::test]
+async fn redelivered_events_are_applied_once() {
+    let Some(f) = scenario().await else { return };
+    let invoice_id = f.seed_invoice(10_000).await;
+
+    assert_eq!(f.pay(invoice_id, 4_000, 1).await, StatusCode::OK);
+    assert_eq!(f.pay(invoice_id, 4_000, 1).await, StatusCode::OK);
+
+    assert_eq!(f.invoice(invoice_id).await.1, 4_000);
+    let payments: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM payments WHERE invoice_id = $1")
+        .bind(invoice_id)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(payments, 1);
+    assert_eq!(
+        f.event_status(&f.id("evt_pi1")).await,
+        ("processed".into(), 1)
+    );
+}
+
+#[tokio::test]
+async fn bad_signatures_are_rejected_and_not_stored() {
+    let Some(f) = scenario().await else { return };
+    let invoice_id = f.seed_invoice(10_000).await;
+    let payload = f.render(
+        "payment_intent.succeeded",
+        &[
+            ("event_id", f.id("evt_forged")),
+            ("invoice_id", invoice_id.to_string()),
+            ("amount", "10000".into()),
+        ],
+    );
+    let now = chrono::Utc::now().timestamp();
+
+    let wrong_secret = webhook::sign(payload.as_bytes(), "whsec_other", now);
+    assert_eq!(
+        f.deliver_signed(&payload, &wrong_secret).await,
+        StatusCode::UNAUTHORIZED
+    );
+    let stale = webhook::sign(
+        payload.as_bytes(),
+        common::STRIPE_WEBHOOK_SECRET,
+        now - 3600,
+    );
+    assert_eq!(
+        f.deliver_signed(&payload, &stale).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        f.deliver_signed(&payload, "").await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let stored: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM stripe_events WHERE id = $1)")
+            .bind(f.id("evt_forged"))
+            .fetch_one(&f.db)
+            .await
+            .unwrap();
+    assert!(!stored);
+    assert_eq!(f.invoice(invoice_id).await.1, 0);
+}
+
+#[tokio::test]
+async fn refunds_reverse_the_invoice_payment() {
+    let Some(f) = scenario().await else { return };
+    let invoice_id = f.seed_invoice(10_000).await;
+    let payload = f.render(
+        "payment_intent.succeeded",
+        &[
+            ("event_id", f.id("evt_pi")),
+            ("invoice_id", invoice_id.to_string()),
+            ("amount", "10000".into()),
+        ],
+    );
+    assert_eq!(f.deliver(&payload).await, StatusCode::OK);
+
+    // Stripe reports the cumulative refunded amount on each event.
+    for (n, refunded) in [(1, 2_500), (2, 10_000)] {
+        let payload = f.render(
+            "charge.refunded",
+            &[
+                ("event_id", f.id(&format!("evt_refund{}", n))),
+                ("amount", "10000".into()),
+                ("amount_refunded", refunded.to_string()),
+            ],
+        );
+        assert_eq!(f.deliver(&payload).await, StatusCode::OK);
+    }
+
+    let (status, paid, paid_date) = f.invoice(invoice_id).await;
+    assert_eq!((status.as_str(), paid, paid_date), ("sent", 0, None));
+    let payment: (String, i64) =
+        sqlx::query_as("SELECT status, refunded_cents FROM payments WHERE invoice_id = $1")
+            .bind(invoice_id)
+            .fetch_one(&f.db)
+            .await
+            .unwrap();
+    assert_eq!(payment, ("refunded".into(), 10_000));
+    assert_eq!(f.payment_events("refund").await, 2);
+}
+
+#[tokio::test]
+async fn lost_disputes_reverse_the_payment_once() {
+    let Some(f) = scenario().await else { return };
+    let invoice_id = f.seed_invoice(10_000).await;
+    let payload = f.render(
+        "payment_intent.succeeded",
+        &[
+            ("event_id", f.id("evt_pi")),
+            ("invoice_id", invoice_id.to_string()),
+            ("amount", "10000".into()),
+        ],
+    );
+    assert_eq!(f.deliver(&payload).await, StatusCode::OK);
+
+    let dispute = f.render(
+        "charge.dispute.closed",
+        &[
+            ("event_id", f.id("evt_dispute")),
+            ("amount", "10000".into()),
+        ],
+    );
+    assert_eq!(f.deliver(&dispute).await, StatusCode::OK);
+    // A replay of the same closure must not take the money back twice.
+    assert_eq!(
+        webhook::replay(&f.state, &f.id("evt_dispute"))
+            .await
+            .unwrap(),
+        webhook::Outcome::Processed
+    );
+
+    let (status, paid, _) = f.invoice(invoice_id).await;
+    assert_eq!((status.as_str(), paid), ("sent", 0));
+    let dispute_status: Option<String> =
+        sqlx::query_scalar("SELECT dispute_status FROM payments WHERE invoice_id = $1")
+            .bind(invoice_id)
+            .fetch_one(&f.db)
+            .await
+            .unwrap();
+    assert_eq!(dispute_status.as_deref(), Some("lost"));
+    assert_eq!(f.payment_events("dispute").await, 1);
+}
+
+#[tokio::test]
+async fn subscription_lifecycle_syncs_billing() {
+    let Some(f) = scenario().await else { return };
+    let plan_id = f.seed_plan().await;
+
+    let created = f.render(
+        "customer.subscription.created",
+        &[("event_id", f.id("evt_sub_created"))],
+    );
+    assert_eq!(f.deliver(&created).await, StatusCode::OK);
+
+    let paid = f.render("invoice.paid", &[("event_id", f.id("evt_inv_paid"))]);
+    assert_eq!(f.deliver(&paid).await, StatusCode::OK);
+
+    let subscription: (Uuid, Uuid, String) = sqlx::query_as(
+        "SELECT id, plan_id, status FROM subscriptions WHERE stripe_subscription_id = $1",
+    )
+    .bind(f.id("sub"))
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(
+        (subscription.1, subscription.2.as_str()),
+        (plan_id, "trialing")
+    );
+
+    let invoice: (Uuid, i64, String, Option<String>) = sqlx::query_as(
+        "SELECT subscription_id, amount_cents, status, currency FROM billing_invoices \
+         WHERE stripe_invoice_id = $1",
+    )
+    .bind(f.id("in"))
+    .fetch_one(&f.db)
+    .await
+    .unwrap();
+    assert_eq!(
+        invoice,
+        (subscription.0, 4_900, "paid".into(), Some("USD".into()))
+    );
+
+    let deleted = f.render(
+        "customer.subscription.deleted",
+        &[("event_id", f.id("evt_sub_deleted"))],
+    );
+    assert_eq!(f.deliver(&deleted).await, StatusCode::OK);
+    let status: String = sqlx::query_scalar("SELECT status FROM subscriptions WHERE id = $1")
+        .bind(subscription.0)
+        .fetch_one(&f.db)
+        .await
+        .unwrap();
+    assert_eq!(status, "cancelled");
+
+    assert_eq!(f.payment_events("subscription_created").await, 1);
+    assert_eq!(f.payment_events("invoice_paid").await, 1);
+    assert_eq!(f.payment_events("subscription_cancelled").await, 1);
+}
+
+#[tokio::test]
+async fn failed_events_can_be_replayed() {
+    let Some(f) = scenario().await else { return };
+    f.seed_plan().await;
+
+    // The invoice arrives before its subscription is known locally.
+    let paid = f.render("invoice.paid", &[("event_id", f.id("evt_inv_paid"))]);
+    assert_eq!(f.deliver(&paid).await, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        f.event_status(&f.id("evt_inv_paid")).await,
+        ("failed".into(), 1)
+    );
+
+    let created = f.render(
+        "customer.subscription.created",
+        &[("event_id", f.id("evt_sub_created"))],
+    );
+    assert_eq!(f.deliver(&created).await, StatusCode::OK);
+
+    assert_eq!(
+        webhook::replay(&f.state, &f.id("evt_inv_paid"))
+            .await
+            .unwrap(),
+        webhook::Outcome::Processed
+    );
+    assert_eq!(
+        f.event_status(&f.id("evt_inv_paid")).await,
+        ("processed".into(), 2)
+    );
+    assert_eq!(f.payment_events("invoice_paid").await, 1);
+
+    // Once processed, a redelivery is acknowledged without reapplying.
+    assert_eq!(f.deliver(&paid).await, StatusCode::OK);
+    assert_eq!(
+        f.event_status(&f.id("evt_inv_paid")).await,
+        ("processed".into(), 2)
+    );
+}
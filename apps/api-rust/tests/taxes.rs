@@ -0,0 +1,208 @@
+//! Tax rates applied to invoices and reported as collected. Skipped when
+//! `TEST_DATABASE_URL` is unset.
+
+use std::ops::Deref;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+mod common;
+
+struct Scenario {
+    fixture: common::Fixture,
+    manager_id: Uuid,
+    client_id: Uuid,
+}
+
+impl Deref for Scenario {
+    type Target = common::Fixture;
+
+    fn deref(&self) -> &common::Fixture {
+        &self.fixture
+    }
+}
+
+async fn scenario() -> Option<Scenario> {
+    let fixture = common::fixture().await?;
+    let manager_id = common::seed_user(&fixture.db, fixture.tenant_id, "Grace", "manager").await;
+    let client_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO clients (tenant_id, name, business_type, address) \
+         VALUES ($1, 'Acme', 'llc', '{\"city\": \"Albany\", \"state\": \"NY\"}') RETURNING id",
+    )
+    .bind(fixture.tenant_id)
+    .fetch_one(&fixture.db)
+    .await
+    .unwrap();
+    Some(Scenario {
+        fixture,
+        manager_id,
+        client_id,
+    })
+}
+
+impl Scenario {
+    async fn rate(&self, body: Value) -> String {
+        let (status, rate) = self.request("POST", "/api/v1/tax-rates", body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", rate);
+        rate["id"].as_str().unwrap().to_string()
+    }
+}
+
+#[tokio::test]
+async fn invoices_are_taxed_per_line_and_reported_as_collected() {
+    let Some(f) = scenario().await else {
+        return;
+    };
+    let (status, _) = f
+        .request_as(
+            f.manager_id,
+            "manager",
+            "POST",
+            "/api/v1/tax-rates",
+            json!({"name": "Sneaky", "rate_percent": 1}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = f
+        .request(
+            "POST",
+            "/api/v1/tax-rates",
+            json!({"name": "Too precise", "rate_percent": 4.00001}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    f.rate(json!({"name": "NY State", "jurisdiction": "NY", "rate_percent": 4}))
+        .await;
+    f.rate(json!({"name": "NYC", "jurisdiction": "ny", "rate_percent": 4.5, "priority": 1}))
+        .await;
+    let excise = f
+        .rate(json!({
+            "name": "Excise",
+            "rate_percent": 1,
+            "is_compound": true,
+            "priority": 2,
+            "exempt_service_types": ["bookkeeping"],
+        }))
+        .await;
+    // Another state's tax does not apply.
+    f.rate(json!({"name": "CA", "jurisdiction": "CA", "rate_percent": 7.25}))
+        .await;
+
+    let (status, invoice) = f
+        .request(
+            "POST",
+            "/api/v1/invoices",
+            json!({
+                "client_id": f.client_id,
+                "line_items": [
+                    {"description": "Advisory", "quantity": 1, "unit_price_cents": 10001, "service_type": "consulting"},
+                    {"description": "Books", "quantity": 2, "unit_price_cents": 2500, "service_type": "bookkeeping"},
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invoice);
+    // Advisory: 400.04 + 450.045 + 1% of 10851 = 400 + 450 + 109.
+    // Books: 200 + 225, exempt from excise.
+    assert_eq!(invoice["subtotal_cents"], 15001);
+    assert_eq!(invoice["tax_cents"], 959 + 425);
+    assert_eq!(invoice["total_cents"], 15001 + 1384);
+    let id = invoice["id"].as_str().unwrap().to_string();
+
+    let (status, taxes) = f
+        .request("GET", &format!("/api/v1/invoices/{}/taxes", id), json!({}))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", taxes);
+    let breakdown: Vec<(String, i64, i64)> = taxes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            (
+                t["name"].as_str().unwrap().to_string(),
+                t["taxable_cents"].as_i64().unwrap(),
+                t["tax_cents"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        breakdown,
+        vec![
+            ("NY State".to_string(), 15001, 600),
+            ("NYC".to_string(), 15001, 675),
+            ("Excise".to_string(), 10851, 109),
+        ]
+    );
+
+    // Exempting the client from excise takes effect when the draft is edited.
+    let (status, body) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/tax-rates/{}", excise),
+            json!({"exempt_client_ids": [f.client_id]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, invoice) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/invoices/{}", id),
+            json!({"line_items": [
+                {"description": "Advisory", "quantity": 1, "unit_price_cents": 10001, "service_type": "consulting"},
+            ]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", invoice);
+    assert_eq!(invoice["subtotal_cents"], 10001);
+    assert_eq!(invoice["tax_cents"], 850);
+    assert_eq!(invoice["total_cents"], 10851);
+
+    let (code, body) = f
+        .request(
+            "PATCH",
+            &format!("/api/v1/invoices/{}/status", id),
+            json!({"status": "sent"}),
+        )
+        .await;
+    assert_eq!(code, StatusCode::OK, "{}", body);
+    let (code, body) = f
+        .request(
+            "POST",
+            &format!("/api/v1/invoices/{}/payment", id),
+            json!({"amount_cents": 10851, "method": "check"}),
+        )
+        .await;
+    assert_eq!(code, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["invoice_status"], "paid");
+    // Only drafts can be edited.
+    let (status, _) = f
+        .request(
+            "PUT",
+            &format!("/api/v1/invoices/{}", id),
+            json!({"notes": "late"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let today = chrono::Utc::now().date_naive();
+    let (status, report) = f
+        .request(
+            "GET",
+            &format!("/api/v1/reports/pl?start_date={}&end_date={}", today, today),
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(
+        report["sales_tax_collected"],
+        json!({
+            "total_cents": 850,
+            "items": [
+                {"label": "NYC (ny)", "amount_cents": 450},
+                {"label": "NY State (NY)", "amount_cents": 400},
+            ],
+        })
+    );
+}
@@ -0,0 +1,375 @@
+//! Tenant isolation enforced by Postgres row-level security.
+//!
+//! These tests connect through a role without `BYPASSRLS` (superusers and
+//! roles with that attribute skip every policy), so any row they can see or
+//! write has passed the tenant policies. They run against
+//! `TEST_DATABASE_URL` and are skipped when it is unset. Handlers not yet
+//! moved onto the tenant transaction are listed in `POOL_HANDLER_FILES`.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::{middleware as axum_mw, routing::post, Router};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use cpa_backend::auth::jwt::{create_access_token, Claims};
+use cpa_backend::error::{AppError, AppResult};
+use cpa_backend::middleware::{auth::require_auth, tenant::TenantTx};
+use cpa_backend::scheduler::tenant_tx;
+use cpa_backend::{router, AppState};
+
+mod common;
+
+const PROBE_ROLE: &str = "cpa_rls_probe";
+
+/// Two tenants, each with a client and a draft invoice.
+struct Tenants {
+    /// Connection as configured in `TEST_DATABASE_URL`, used for seeding.
+    admin: PgPool,
+    /// Same database, but every connection runs as the RLS-bound probe role.
+    app: PgPool,
+    state: AppState,
+    tenant_a: Uuid,
+    tenant_b: Uuid,
+    client_a: Uuid,
+    client_b: Uuid,
+    invoice_a: Uuid,
+    invoice_b: Uuid,
+}
+
+async fn tenants() -> Option<Tenants> {
+    let (url, admin) = common::test_database().await?;
+
+    // Tests run concurrently; serialise role setup.
+    let mut tx = admin.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(PROBE_ROLE)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    for stmt in [
+        format!(
+            "DO $$ BEGIN IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = '{0}') \
+             THEN CREATE ROLE {0} NOLOGIN NOBYPASSRLS; END IF; END $$",
+            PROBE_ROLE
+        ),
+        format!("GRANT {} TO CURRENT_USER", PROBE_ROLE),
+        format!("GRANT USAGE ON SCHEMA public TO {}", PROBE_ROLE),
+        format!(
+            "GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO {}",
+            PROBE_ROLE
+        ),
+    ] {
+        sqlx::query(&stmt).execute(&mut *tx).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let app = PgPoolOptions::new()
+        .max_connections(4)
+        .after_connect(|conn, _| {
+            Box::pin(async move {
+                sqlx::query(&format!("SET ROLE {}", PROBE_ROLE))
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(&url)
+        .await
+        .unwrap();
+
+    let tenant_a = common::seed_tenant(&admin).await;
+    let tenant_b = common::seed_tenant(&admin).await;
+    let client_a = seed_client(&admin, tenant_a).await;
+    let client_b = seed_client(&admin, tenant_b).await;
+    let invoice_a = seed_invoice(&admin, tenant_a, client_a).await;
+    let invoice_b = seed_invoice(&admin, tenant_b, client_b).await;
+
+    let state = common::test_state(common::test_config(&url), app.clone());
+    Some(Tenants {
+        admin,
+        app,
+        state,
+        tenant_a,
+        tenant_b,
+        client_a,
+        client_b,
+        invoice_a,
+        invoice_b,
+    })
+}
+
+async fn seed_client(db: &PgPool, tenant_id: Uuid) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO clients (id, tenant_id, name, business_type) VALUES ($1, $2, $3, 'llc')",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .bind(format!("Client of {}", tenant_id))
+    .execute(db)
+    .await
+    .unwrap();
+    id
+}
+
+async fn seed_invoice(db: &PgPool, tenant_id: Uuid, client_id: Uuid) -> Uuid {
+    let user_id = common::seed_user(db, tenant_id, "Ada", "admin").await;
+    sqlx::query_scalar(
+        "INSERT INTO invoices (tenant_id, client_id, invoice_number, created_by) \
+         VALUES ($1, $2, 'INV-00001', $3) RETURNING id",
+    )
+    .bind(tenant_id)
+    .bind(client_id)
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+async fn client_exists(db: &PgPool, id: Uuid) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM clients WHERE id = $1)")
+        .bind(id)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+fn bearer(f: &Tenants, tenant_id: Uuid) -> String {
+    let token = create_access_token(
+        Uuid::new_v4(),
+        tenant_id,
+        "partner",
+        &f.state.config.jwt_secret,
+    )
+    .unwrap();
+    format!("Bearer {}", token)
+}
+
+#[tokio::test]
+async fn other_tenants_rows_are_invisible() {
+    let Some(f) = tenants().await else { return };
+    let mut tx = tenant_tx(&f.app, f.tenant_a).await.unwrap();
+
+    // No tenant_id filter: the policy alone decides what is visible.
+    let own: Option<Uuid> = sqlx::query_scalar("SELECT id FROM clients WHERE id = $1")
+        .bind(f.client_a)
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(own, Some(f.client_a));
+
+    let other: Option<Uuid> = sqlx::query_scalar("SELECT id FROM clients WHERE id = $1")
+        .bind(f.client_b)
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(other, None);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM clients WHERE tenant_id = $1")
+        .bind(f.tenant_b)
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn cross_tenant_writes_are_rejected() {
+    let Some(f) = tenants().await else { return };
+    let mut tx = tenant_tx(&f.app, f.tenant_a).await.unwrap();
+
+    let updated = sqlx::query("UPDATE clients SET name = 'hijacked' WHERE id = $1")
+        .bind(f.client_b)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(updated.rows_affected(), 0);
+
+    let err = sqlx::query(
+        "INSERT INTO clients (tenant_id, name, business_type) VALUES ($1, 'planted', 'llc')",
+    )
+    .bind(f.tenant_b)
+    .execute(&mut *tx)
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_database_error().and_then(|e| e.code()).as_deref(),
+        Some("42501"),
+        "expected a row-level security violation, got {}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn queries_without_tenant_context_see_nothing() {
+    let Some(f) = tenants().await else { return };
+
+    // Depending on the connection's history the unset GUC is either NULL (no
+    // rows) or '' (uuid cast error); both keep the row out of reach.
+    let row: Result<Option<Uuid>, _> = sqlx::query_scalar("SELECT id FROM clients WHERE id = $1")
+        .bind(f.client_a)
+        .fetch_optional(&f.app)
+        .await;
+    assert!(!matches!(row, Ok(Some(_))));
+}
+
+#[tokio::test]
+async fn handlers_query_inside_the_tenant_transaction() {
+    let Some(f) = tenants().await else { return };
+    let app = router(f.state.clone());
+
+    for (n, (resource, id, expected)) in [
+        ("clients", f.client_a, StatusCode::OK),
+        ("clients", f.client_b, StatusCode::NOT_FOUND),
+        ("invoices", f.invoice_a, StatusCode::OK),
+        ("invoices", f.invoice_b, StatusCode::NOT_FOUND),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let uri = format!("/api/v1/{}/{}", resource, id);
+        let request = Request::builder()
+            .uri(&uri)
+            .header("authorization", bearer(&f, f.tenant_a))
+            .header("x-forwarded-for", format!("10.1.0.{}", n + 1))
+            .body(Body::empty())
+            .unwrap();
+        let status = app.clone().oneshot(request).await.unwrap().status();
+        assert_eq!(status, expected, "GET {}", uri);
+    }
+}
+
+async fn insert_then(
+    mut tx: TenantTx,
+    claims: Claims,
+    axum::extract::Path((id, fail)): axum::extract::Path<(Uuid, bool)>,
+) -> AppResult<StatusCode> {
+    sqlx::query(
+        "INSERT INTO clients (id, tenant_id, name, business_type) VALUES ($1, $2, 'probe', 'llc')",
+    )
+    .bind(id)
+    .bind(claims.tid)
+    .execute(&mut *tx)
+    .await?;
+    if fail {
+        return Err(AppError::Validation("rejected after write".to_string()));
+    }
+    Ok(StatusCode::CREATED)
+}
+
+#[tokio::test]
+async fn transaction_follows_the_response_status() {
+    let Some(f) = tenants().await else { return };
+    let app = Router::new()
+        .route("/probe/{id}/{fail}", post(insert_then))
+        .layer(axum_mw::from_fn_with_state(f.state.clone(), require_auth))
+        .with_state(f.state.clone());
+
+    for (fail, expected) in [
+        (false, StatusCode::CREATED),
+        (true, StatusCode::BAD_REQUEST),
+    ] {
+        let id = Uuid::new_v4();
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/probe/{}/{}", id, fail))
+            .header("authorization", bearer(&f, f.tenant_a))
+            .body(Body::empty())
+            .unwrap();
+        let status = app.clone().oneshot(request).await.unwrap().status();
+        assert_eq!(status, expected);
+        assert_eq!(client_exists(&f.admin, id).await, !fail);
+    }
+}
+
+#[tokio::test]
+async fn ledger_writes_pass_the_tenant_policies() {
+    let Some(f) = tenants().await else { return };
+    let app = router(f.state.clone());
+    sqlx::query("UPDATE invoices SET status = 'sent', subtotal_cents = 10000, total_cents = 10000 WHERE id = $1")
+        .bind(f.invoice_a)
+        .execute(&f.admin)
+        .await
+        .unwrap();
+    let manager = common::seed_user(&f.admin, f.tenant_a, "Ada", "manager").await;
+    let token =
+        create_access_token(manager, f.tenant_a, "manager", &f.state.config.jwt_secret).unwrap();
+
+    // A retry with the same key replays the response instead of paying twice.
+    for (n, (client_id, expected)) in [
+        (f.client_a, StatusCode::CREATED),
+        (f.client_a, StatusCode::CREATED),
+        (f.client_b, StatusCode::NOT_FOUND),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/payments")
+            .header("authorization", format!("Bearer {}", token))
+            .header("content-type", "application/json")
+            .header("idempotency-key", format!("payment-{}", client_id))
+            .header("x-forwarded-for", format!("10.2.0.{}", n + 1))
+            .body(Body::from(
+                serde_json::json!({"client_id": client_id, "amount_cents": 4000, "method": "wire"})
+                    .to_string(),
+            ))
+            .unwrap();
+        let status = app.clone().oneshot(request).await.unwrap().status();
+        assert_eq!(status, expected, "POST /payments for {}", client_id);
+    }
+
+    let (status, paid, allocations): (String, i64, i64) = sqlx::query_as(
+        "SELECT i.status, i.amount_paid_cents, \
+         (SELECT COUNT(*) FROM payment_allocations a WHERE a.invoice_id = i.id) \
+         FROM invoices i WHERE i.id = $1",
+    )
+    .bind(f.invoice_a)
+    .fetch_one(&f.admin)
+    .await
+    .unwrap();
+    assert_eq!((status.as_str(), paid, allocations), ("partial", 4000, 1));
+}
+
+/// Source files with authenticated handlers that still use the pool rather
+/// than [`TenantTx`], so RLS does not apply to those queries. Move a module
+/// onto `TenantTx` and drop it from this list; new handlers must not be
+/// added to it.
+///
+/// `auth/handler.rs` stays: `logout` records its security event through the
+/// pool, like the unauthenticated auth endpoints, so the event is written
+/// whatever happens to the request's transaction.
+const POOL_HANDLER_FILES: &[&str] = &["auth/handler.rs"];
+
+fn pool_handler_files(dir: &std::path::Path, root: &std::path::Path, found: &mut Vec<String>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            pool_handler_files(&path, root, found);
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).unwrap();
+        // A handler is a public async fn taking the caller's claims.
+        let on_pool = source.split("\npub async fn ").skip(1).any(|f| {
+            let body = f.split("\n}\n").next().unwrap_or(f);
+            let params = body.split('{').next().unwrap_or(body);
+            params.contains("Claims") && body.contains("state.db")
+        });
+        if on_pool {
+            let relative = path.strip_prefix(root).unwrap();
+            found.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+#[test]
+fn handlers_left_on_the_pool_are_tracked() {
+    let root = std::path::Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+    let mut found = Vec::new();
+    pool_handler_files(root, root, &mut found);
+    found.sort();
+    assert_eq!(found, POOL_HANDLER_FILES);
+}
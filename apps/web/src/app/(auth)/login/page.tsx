@@ -329,7 +329,7 @@ export default function LoginPage() {
   const handleMfaVerify = useCallback(async (code: string) => {
     setIsLoading(true);
     try {
-      const { data } = await api.post("/auth/mfa/verify", { mfa_token: mfaToken, code });
+      const { data } = await api.post("/auth/mfa/verify-login", { mfa_token: mfaToken, code });
       localStorage.setItem("access_token", data.access_token);
       localStorage.setItem("refresh_token", data.refresh_token);
       setUser(data.user);
@@ -1,16 +1,34 @@
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { api } from "@/lib/api";
 
+export interface ApprovalStep {
+  name: string;
+  role?: string | null;
+  user_ids?: string[];
+  quorum?: number | "all";
+}
+
+export interface ApprovalWorkflow {
+  id: string;
+  name: string;
+  workflow_type: string;
+  steps: ApprovalStep[];
+  is_active: boolean;
+  created_at: string;
+}
+
 export interface ApprovalRequest {
   id: string;
   workflow_id: string | null;
   request_type: string;
-  entity_id: string;
-  entity_type: string;
-  requested_by: string;
-  requester_name: string;
-  status: string;
+  entity_type: string | null;
+  entity_id: string | null;
+  title: string;
+  description: string | null;
+  requester_id: string;
+  status: "pending" | "approved" | "rejected" | "cancelled";
   current_step: number;
+  steps: ApprovalStep[];
   metadata: Record<string, unknown>;
   created_at: string;
   resolved_at: string | null;
@@ -18,26 +36,37 @@ export interface ApprovalRequest {
 
 export interface ApprovalDecision {
   id: string;
-  request_id: string;
   approver_id: string;
   approver_name: string;
-  decision: string;
-  comments: string | null;
+  decision: "approved" | "rejected";
+  comment: string | null;
   step_number: number;
   decided_at: string;
 }
 
+export interface ApprovalRequestDetail extends ApprovalRequest {
+  decisions: ApprovalDecision[];
+}
+
 export interface CreateApprovalPayload {
   request_type: string;
-  entity_id: string;
-  entity_type: string;
+  title: string;
+  description?: string;
+  entity_id?: string;
   workflow_id?: string;
   metadata?: Record<string, unknown>;
 }
 
 export interface DecideApprovalPayload {
-  decision: string;
-  comments?: string;
+  decision: "approved" | "rejected";
+  comment?: string;
+}
+
+export interface WorkflowPayload {
+  name: string;
+  workflow_type: string;
+  steps: ApprovalStep[];
+  is_active?: boolean;
 }
 
 export function useApprovalRequests(params?: {
@@ -55,6 +84,17 @@ export function useApprovalRequests(params?: {
   });
 }
 
+export function useApprovalRequest(id: string | undefined) {
+  return useQuery({
+    queryKey: ["approvals", id],
+    queryFn: async () => {
+      const { data } = await api.get<ApprovalRequestDetail>(`/approvals/${id}`);
+      return data;
+    },
+    enabled: !!id,
+  });
+}
+
 export function useCreateApproval() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -83,3 +123,45 @@ export function useDecideApproval(requestId: string) {
     },
   });
 }
+
+export function useApprovalWorkflows() {
+  return useQuery({
+    queryKey: ["approval-workflows"],
+    queryFn: async () => {
+      const { data } = await api.get<ApprovalWorkflow[]>("/approval-workflows");
+      return data;
+    },
+  });
+}
+
+export function useCreateApprovalWorkflow() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: WorkflowPayload) => {
+      const { data } = await api.post<ApprovalWorkflow>(
+        "/approval-workflows",
+        payload
+      );
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["approval-workflows"] });
+    },
+  });
+}
+
+export function useUpdateApprovalWorkflow(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: Partial<WorkflowPayload>) => {
+      const { data } = await api.put<ApprovalWorkflow>(
+        `/approval-workflows/${id}`,
+        payload
+      );
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["approval-workflows"] });
+    },
+  });
+}
@@ -0,0 +1,92 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { api } from "@/lib/api";
+import type { Invoice } from "./use-invoices";
+
+export interface CreditNote {
+  id: string;
+  tenant_id: string;
+  client_id: string;
+  invoice_id: string | null;
+  credit_note_number: string;
+  currency: string;
+  amount_cents: number;
+  /** Credit not yet applied to an invoice. */
+  unallocated_cents: number;
+  reason: string | null;
+  status: "issued" | "void";
+  created_by: string;
+  created_at: string;
+  voided_at: string | null;
+}
+
+export interface CreateCreditNotePayload {
+  /** Required unless `invoice_id` is given. */
+  client_id?: string;
+  /** The invoice credited; it takes the credit first. */
+  invoice_id?: string;
+  amount_cents: number;
+  currency?: string;
+  reason?: string;
+}
+
+export function useCreditNotes(params?: {
+  client_id?: string;
+  invoice_id?: string;
+  status?: "issued" | "void";
+}) {
+  return useQuery({
+    queryKey: ["credit-notes", params],
+    queryFn: async () => {
+      const { data } = await api.get<CreditNote[]>("/credit-notes", { params });
+      return data;
+    },
+  });
+}
+
+export function useCreateCreditNote() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: CreateCreditNotePayload) => {
+      const { data } = await api.post<CreditNote>("/credit-notes", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["credit-notes"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
+
+export function useVoidCreditNote() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (id: string) => {
+      const { data } = await api.post<CreditNote>(`/credit-notes/${id}/void`);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["credit-notes"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
+
+/** Pay an invoice from the client's unapplied credit. */
+export function useApplyCredit() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async ({ invoiceId, amount_cents }: { invoiceId: string; amount_cents?: number }) => {
+      const { data } = await api.post<Invoice>(`/invoices/${invoiceId}/apply-credit`, {
+        amount_cents,
+      });
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["credit-notes"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
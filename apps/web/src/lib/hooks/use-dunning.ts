@@ -0,0 +1,96 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { api } from "@/lib/api";
+
+export interface DunningStep {
+  id: string;
+  tenant_id: string;
+  days_past_due: number;
+  send_reminder: boolean;
+  /** Flat fee in minor units of the firm's base currency. */
+  late_fee_cents: number;
+  late_fee_ppm: number;
+  late_fee_percent: number;
+  message: string | null;
+  is_active: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface DunningStepPayload {
+  days_past_due: number;
+  send_reminder?: boolean;
+  late_fee_cents?: number;
+  late_fee_percent?: number;
+  message?: string | null;
+  is_active?: boolean;
+}
+
+export interface DunningEvent {
+  id: string;
+  invoice_id: string;
+  dunning_step_id: string | null;
+  days_past_due: number;
+  skipped: boolean;
+  late_fee_cents: number;
+  email_outbox_id: string | null;
+  created_at: string;
+}
+
+export function useDunningSteps() {
+  return useQuery({
+    queryKey: ["dunning-steps"],
+    queryFn: async () => {
+      const { data } = await api.get<DunningStep[]>("/invoices/dunning-steps");
+      return data;
+    },
+  });
+}
+
+export function useCreateDunningStep() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: DunningStepPayload) => {
+      const { data } = await api.post<DunningStep>("/invoices/dunning-steps", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["dunning-steps"] });
+    },
+  });
+}
+
+export function useUpdateDunningStep(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: Partial<DunningStepPayload>) => {
+      const { data } = await api.put<DunningStep>(`/invoices/dunning-steps/${id}`, payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["dunning-steps"] });
+    },
+  });
+}
+
+export function useDeleteDunningStep() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (id: string) => {
+      await api.delete(`/invoices/dunning-steps/${id}`);
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["dunning-steps"] });
+    },
+  });
+}
+
+export function useInvoiceDunning(invoiceId: string) {
+  return useQuery({
+    queryKey: ["invoices", invoiceId, "dunning"],
+    queryFn: async () => {
+      const { data } = await api.get<DunningEvent[]>(`/invoices/${invoiceId}/dunning`);
+      return data;
+    },
+    enabled: !!invoiceId,
+  });
+}
@@ -0,0 +1,102 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { api } from "@/lib/api";
+
+export interface Currency {
+  code: string;
+  minor_units: number;
+  symbol: string | null;
+  name: string;
+}
+
+export interface ExchangeRate {
+  id: string;
+  tenant_id: string;
+  rate_date: string;
+  from_currency: string;
+  to_currency: string;
+  /** Units of to_currency per unit of from_currency, as an exact decimal. */
+  rate: string;
+  source: string;
+  created_by: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ExchangeRateParams {
+  from_currency?: string;
+  to_currency?: string;
+  date_from?: string;
+  date_to?: string;
+}
+
+export interface ExchangeRatePayload {
+  rate_date: string;
+  from_currency: string;
+  to_currency: string;
+  rate: string | number;
+  source?: string;
+}
+
+export function useCurrencies() {
+  return useQuery({
+    queryKey: ["currencies"],
+    queryFn: async () => {
+      const { data } = await api.get<Currency[]>("/currencies");
+      return data;
+    },
+    staleTime: Infinity,
+  });
+}
+
+export function useExchangeRates(params: ExchangeRateParams = {}) {
+  return useQuery({
+    queryKey: ["exchange-rates", params],
+    queryFn: async () => {
+      const { data } = await api.get<ExchangeRate[]>("/exchange-rates", { params });
+      return data;
+    },
+  });
+}
+
+export function useUpsertExchangeRate() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: ExchangeRatePayload) => {
+      const { data } = await api.post<ExchangeRate>("/exchange-rates", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["exchange-rates"] });
+    },
+  });
+}
+
+/** Upload a `date,from,to,rate` CSV file of rates. */
+export function useImportExchangeRates() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async ({ file, source }: { file: File; source?: string }) => {
+      const { data } = await api.post<{ imported: number }>(
+        "/exchange-rates/import",
+        await file.text(),
+        { params: { source }, headers: { "Content-Type": "text/csv" } },
+      );
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["exchange-rates"] });
+    },
+  });
+}
+
+export function useDeleteExchangeRate() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (id: string) => {
+      await api.delete(`/exchange-rates/${id}`);
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["exchange-rates"] });
+    },
+  });
+}
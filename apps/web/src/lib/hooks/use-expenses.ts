@@ -14,6 +14,7 @@ export interface Expense {
   receipt_document_id: string | null;
   is_reimbursable: boolean;
   status: string;
+  invoice_id: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -2,12 +2,25 @@ import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { api } from "@/lib/api";
 import type { PaginatedResponse } from "./use-clients";
 
+/**
+ * `partial`, `overdue` and `paid` follow from payments; by hand an invoice
+ * can only be sent, marked viewed or voided.
+ */
+export type InvoiceStatus =
+  | "draft"
+  | "sent"
+  | "viewed"
+  | "partial"
+  | "overdue"
+  | "paid"
+  | "void";
+
 export interface Invoice {
   id: string;
   tenant_id: string;
   client_id: string;
   invoice_number: string;
-  status: string;
+  status: InvoiceStatus;
   subtotal_cents: number;
   tax_cents: number;
   total_cents: number;
@@ -29,14 +42,27 @@ export interface CreateInvoicePayload {
   client_id: string;
   due_date?: string;
   notes?: string;
+  /** ISO 4217 code; defaults to the firm's base currency. */
+  currency?: string;
   line_items: {
     description: string;
     quantity: number;
     unit_price_cents: number;
     time_entry_id?: string;
+    service_type?: string;
   }[];
 }
 
+export interface InvoiceTax {
+  tax_rate_id: string | null;
+  name: string;
+  jurisdiction: string | null;
+  rate_ppm: number;
+  is_compound: boolean;
+  taxable_cents: number;
+  tax_cents: number;
+}
+
 export function useInvoices(params?: {
   page?: number;
   per_page?: number;
@@ -106,6 +132,17 @@ export function useSendInvoice() {
   });
 }
 
+export interface RecordPaymentResponse {
+  payment_id: string;
+  invoice_id: string;
+  amount_cents: number;
+  method: string;
+  new_total_paid_cents: number;
+  /** Paid over the balance; kept as the client's credit. */
+  credit_cents: number;
+  invoice_status: InvoiceStatus;
+}
+
 export function useRecordPayment() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -120,7 +157,7 @@ export function useRecordPayment() {
       method: string;
       notes?: string;
     }) => {
-      const { data } = await api.post(`/invoices/${id}/payment`, {
+      const { data } = await api.post<RecordPaymentResponse>(`/invoices/${id}/payment`, {
         amount_cents,
         method,
         notes,
@@ -129,6 +166,7 @@ export function useRecordPayment() {
     },
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["payments"] });
     },
   });
 }
@@ -141,7 +179,7 @@ export function useUpdateInvoiceStatus() {
       status,
     }: {
       id: string;
-      status: string;
+      status: "sent" | "viewed" | "void";
     }) => {
       const { data } = await api.patch<Invoice>(`/invoices/${id}/status`, {
         status,
@@ -251,3 +289,89 @@ export function useDeleteRecurringInvoice() {
     },
   });
 }
+
+export interface BillingLine {
+  description: string;
+  quantity: number;
+  unit_price_cents: number;
+  total_cents: number;
+  time_entry_id: string | null;
+  expense_id: string | null;
+  service_type: string;
+}
+
+export interface UnbilledParams {
+  client_id: string;
+  date_from?: string;
+  date_to?: string;
+  group_by?: "service_type" | "entry";
+  include_expenses?: boolean;
+}
+
+export interface UnbilledSummary {
+  lines: BillingLine[];
+  subtotal_cents: number;
+  time_entries: number;
+  expenses: number;
+}
+
+export type BilledInvoice = Invoice & {
+  time_entries_billed: number;
+  expenses_billed: number;
+};
+
+export function useUnbilled(params?: UnbilledParams) {
+  return useQuery({
+    queryKey: ["invoices", "unbilled", params],
+    queryFn: async () => {
+      const { data } = await api.get<UnbilledSummary>("/invoices/unbilled", {
+        params,
+      });
+      return data;
+    },
+    enabled: !!params?.client_id,
+  });
+}
+
+export function useBillClient() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (
+      payload: UnbilledParams & { due_date?: string; notes?: string },
+    ) => {
+      const { data } = await api.post<BilledInvoice>("/invoices/bill", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["time-entries"] });
+      queryClient.invalidateQueries({ queryKey: ["expenses"] });
+    },
+  });
+}
+
+export function useUpdateInvoice(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (
+      payload: Partial<Omit<CreateInvoicePayload, "client_id">>,
+    ) => {
+      const { data } = await api.put<Invoice>(`/invoices/${id}`, payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+    },
+  });
+}
+
+export function useInvoiceTaxes(id: string) {
+  return useQuery({
+    queryKey: ["invoices", id, "taxes"],
+    queryFn: async () => {
+      const { data } = await api.get<InvoiceTax[]>(`/invoices/${id}/taxes`);
+      return data;
+    },
+    enabled: !!id,
+  });
+}
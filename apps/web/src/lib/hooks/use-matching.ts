@@ -1,4 +1,6 @@
 import { useMemo } from "react";
+import { useQuery } from "@tanstack/react-query";
+import { api } from "@/lib/api";
 import { useCandidates, type CandidateProfile, type CandidateSkill } from "./use-candidates";
 import { useJobs, useJob, type JobPost } from "./use-jobs";
 
@@ -282,3 +284,71 @@ function extractSkillKeywords(candidate: CandidateProfile): string[] {
   }
   return found;
 }
+
+export interface MatchReason {
+  factor: "required_skills" | "preferred_skills" | "work_mode" | "location" | "salary";
+  weight: number;
+  score: number;
+  detail: string;
+  matched?: string[];
+  missing?: string[];
+}
+
+export interface RecommendedCandidate {
+  candidate_id: string;
+  candidate_name: string;
+  headline: string | null;
+  location_city: string | null;
+  location_state: string | null;
+  availability_status: string | null;
+  applied: boolean;
+  match_score: number;
+  match_reasons: MatchReason[];
+}
+
+export interface RecommendedJob {
+  job_id: string;
+  title: string;
+  department: string | null;
+  location_city: string | null;
+  location_state: string | null;
+  work_mode: string | null;
+  employment_type: string | null;
+  applied: boolean;
+  match_score: number;
+  match_reasons: MatchReason[];
+}
+
+export interface RecommendationParams {
+  limit?: number;
+  min_score?: number;
+  include_applied?: boolean;
+}
+
+export function useRecommendedCandidates(jobId: string, params: RecommendationParams = {}) {
+  return useQuery({
+    queryKey: ["jobs", jobId, "recommended-candidates", params],
+    queryFn: async () => {
+      const { data } = await api.get<RecommendedCandidate[]>(
+        `/jobs/${jobId}/recommended-candidates`,
+        { params }
+      );
+      return data;
+    },
+    enabled: !!jobId,
+  });
+}
+
+export function useRecommendedJobs(candidateId: string, params: RecommendationParams = {}) {
+  return useQuery({
+    queryKey: ["candidates", candidateId, "recommended-jobs", params],
+    queryFn: async () => {
+      const { data } = await api.get<RecommendedJob[]>(
+        `/candidates/${candidateId}/recommended-jobs`,
+        { params }
+      );
+      return data;
+    },
+    enabled: !!candidateId,
+  });
+}
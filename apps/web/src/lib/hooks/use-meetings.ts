@@ -181,3 +181,152 @@ export function useCancelMeeting() {
     },
   });
 }
+
+export interface AvailabilityBlock {
+  id: string;
+  user_id: string;
+  /** 0 = Sunday. Null for one-off blocks. */
+  day_of_week: number | null;
+  start_time: string;
+  end_time: string;
+  timezone: string;
+  is_recurring: boolean;
+  specific_date: string | null;
+  is_blocked: boolean;
+}
+
+export interface AvailabilityBlockInput {
+  day_of_week?: number;
+  start_time: string;
+  end_time: string;
+  timezone: string;
+  specific_date?: string;
+  is_blocked?: boolean;
+}
+
+export interface Slot {
+  starts_at: string;
+  ends_at: string;
+  score: number;
+}
+
+export interface SlotWindow {
+  from?: string;
+  to?: string;
+  step_minutes?: number;
+  limit?: number;
+}
+
+export interface FindSlotsPayload extends SlotWindow {
+  participant_user_ids: string[];
+  duration_minutes?: number;
+}
+
+export interface SchedulingLink {
+  token: string;
+  url: string;
+  expires_at: string;
+}
+
+export function useAvailability() {
+  return useQuery({
+    queryKey: ["meetings", "availability"],
+    queryFn: async () => {
+      const { data } = await api.get<AvailabilityBlock[]>(
+        "/meetings/availability"
+      );
+      return data;
+    },
+  });
+}
+
+export function useSetAvailability() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (blocks: AvailabilityBlockInput[]) => {
+      const { data } = await api.put<AvailabilityBlock[]>(
+        "/meetings/availability",
+        { blocks }
+      );
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["meetings", "availability"] });
+    },
+  });
+}
+
+export function useFindSlots() {
+  return useMutation({
+    mutationFn: async (payload: FindSlotsPayload) => {
+      const { data } = await api.post<Slot[]>("/meetings/slots", payload);
+      return data;
+    },
+  });
+}
+
+export function useMeetingSlots(id: string, window?: SlotWindow) {
+  return useQuery({
+    queryKey: ["meetings", id, "slots", window],
+    queryFn: async () => {
+      const { data } = await api.get<Slot[]>(`/meetings/${id}/slots`, {
+        params: window,
+      });
+      return data;
+    },
+    enabled: !!id,
+  });
+}
+
+export function useCreateSchedulingLink() {
+  return useMutation({
+    mutationFn: async ({
+      id,
+      expires_in_days,
+    }: {
+      id: string;
+      expires_in_days?: number;
+    }) => {
+      const { data } = await api.post<SchedulingLink>(
+        `/meetings/${id}/scheduling-link`,
+        { expires_in_days }
+      );
+      return data;
+    },
+  });
+}
+
+export interface CalendarFeed {
+  url: string;
+  webcal_url: string;
+}
+
+/** Download the meeting's invite (or cancellation) as an .ics file. */
+export async function downloadMeetingIcs(id: string) {
+  const { data } = await api.get<Blob>(`/meetings/${id}/ics`, {
+    responseType: "blob",
+  });
+  const url = URL.createObjectURL(data);
+  const link = document.createElement("a");
+  link.href = url;
+  link.download = "meeting.ics";
+  link.click();
+  URL.revokeObjectURL(url);
+}
+
+export function useCreateCalendarFeed() {
+  return useMutation({
+    mutationFn: async () => {
+      const { data } = await api.post<CalendarFeed>("/meetings/calendar-feed");
+      return data;
+    },
+  });
+}
+
+export function useDeleteCalendarFeed() {
+  return useMutation({
+    mutationFn: async () => {
+      await api.delete("/meetings/calendar-feed");
+    },
+  });
+}
@@ -1,30 +1,53 @@
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { api } from "@/lib/api";
 
+export interface OfferTerms {
+  base_salary_cents: number | null;
+  equity_pct: number | null;
+  signing_bonus_cents: number | null;
+  start_date: string | null;
+}
+
+export interface NegotiationRound extends OfferTerms {
+  round: number;
+  proposed_by: "company" | "candidate";
+  action: "offer" | "counter" | "accept" | "reject" | "revise" | "decline";
+  notes: string | null;
+  user_id: string | null;
+  created_at: string;
+}
+
 export interface OfferNegotiation {
   id: string;
-  offer_id: string;
-  candidate_name: string;
-  job_title: string;
-  round_number: number;
-  initiated_by: string;
-  proposed_base_salary_cents: number | null;
-  proposed_equity_pct: number | null;
-  proposed_bonus_cents: number | null;
-  proposed_benefits: string | null;
-  candidate_response: string | null;
-  employer_response: string | null;
-  status: string;
-  resolved_at: string | null;
-  created_at: string;
+  offer_id: string | null;
+  application_id: string;
+  candidate_name: string | null;
+  job_title: string | null;
+  status:
+    | "initial_offer"
+    | "counter_offer"
+    | "revised_offer"
+    | "final_offer"
+    | "accepted"
+    | "declined"
+    | "expired";
+  rounds: NegotiationRound[];
+  current_offer: OfferTerms;
+  created_at: string | null;
+  updated_at: string | null;
+}
+
+export interface CounterOfferPayload extends Partial<OfferTerms> {
+  offerId: string;
+  notes?: string;
 }
 
-export interface CreateNegotiationPayload {
-  offer_id: string;
-  proposed_base_salary_cents?: number;
-  proposed_equity_pct?: number;
-  proposed_bonus_cents?: number;
-  proposed_benefits?: string;
+export interface RespondToCounterPayload extends Partial<OfferTerms> {
+  offerId: string;
+  action: "accept" | "reject" | "revise";
+  notes?: string;
+  final_offer?: boolean;
+  expiry_date?: string;
 }
 
 export function useNegotiations(params?: { status?: string }) {
@@ -40,12 +63,42 @@ export function useNegotiations(params?: { status?: string }) {
   });
 }
 
-export function useCreateNegotiation() {
+export function useOfferNegotiation(offerId: string) {
+  return useQuery({
+    queryKey: ["negotiations", "offer", offerId],
+    queryFn: async () => {
+      const { data } = await api.get<OfferNegotiation>(
+        `/offers/${offerId}/negotiation`
+      );
+      return data;
+    },
+    enabled: !!offerId,
+  });
+}
+
+export function useCounterOffer() {
   const queryClient = useQueryClient();
   return useMutation({
-    mutationFn: async (payload: CreateNegotiationPayload) => {
+    mutationFn: async ({ offerId, ...payload }: CounterOfferPayload) => {
       const { data } = await api.post<OfferNegotiation>(
-        "/offer-negotiations",
+        `/offers/${offerId}/counter`,
+        payload
+      );
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["negotiations"] });
+      queryClient.invalidateQueries({ queryKey: ["offers"] });
+    },
+  });
+}
+
+export function useRespondToCounter() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async ({ offerId, ...payload }: RespondToCounterPayload) => {
+      const { data } = await api.post<OfferNegotiation>(
+        `/offers/${offerId}/negotiation/respond`,
         payload
       );
       return data;
@@ -21,6 +21,7 @@ export interface Offer {
   accepted_at: string | null;
   declined_at: string | null;
   decline_reason: string | null;
+  letter_document_id: string | null;
   created_at: string;
 }
 
@@ -134,3 +135,19 @@ export function useAcceptOffer() {
     },
   });
 }
+
+export function useGenerateOfferLetter() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async ({ id, templateId }: { id: string; templateId?: string }) => {
+      const { data } = await api.post(`/offers/${id}/letter`, {
+        template_id: templateId,
+      });
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["offers"] });
+      queryClient.invalidateQueries({ queryKey: ["candidates"] });
+    },
+  });
+}
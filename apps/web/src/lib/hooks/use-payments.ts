@@ -1,5 +1,6 @@
-import { useMutation } from "@tanstack/react-query";
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { api } from "@/lib/api";
+import type { PaginatedResponse } from "./use-clients";
 
 export interface PaymentIntentResponse {
   client_secret: string;
@@ -18,3 +19,147 @@ export function useCreatePaymentIntent() {
     },
   });
 }
+
+export interface Payment {
+  id: string;
+  client_id: string;
+  /** The invoice the payment was taken against, if any. */
+  invoice_id: string | null;
+  /** Negative for refunds. */
+  amount_cents: number;
+  currency: string;
+  method: string;
+  status: string;
+  notes: string | null;
+  stripe_payment_id: string | null;
+  /** Set on refunds: the payment refunded. */
+  refund_of: string | null;
+  refunded_cents: number;
+  /** Credit left for other invoices. */
+  unallocated_cents: number;
+  created_by: string | null;
+  created_at: string;
+}
+
+export interface PaymentAllocation {
+  id: string;
+  invoice_id: string;
+  invoice_number: string;
+  payment_id: string | null;
+  credit_note_id: string | null;
+  /** Negative when money was taken back off the invoice. */
+  amount_cents: number;
+  created_at: string;
+}
+
+export interface PaymentDetail extends Payment {
+  allocations: PaymentAllocation[];
+  refunds: Payment[];
+}
+
+export interface AllocationPayload {
+  invoice_id: string;
+  amount_cents: number;
+}
+
+export interface CreatePaymentPayload {
+  client_id: string;
+  amount_cents: number;
+  /** ISO 4217 code; defaults to the firm's base currency. */
+  currency?: string;
+  method: string;
+  notes?: string;
+  /** Omit to pay the client's open invoices, oldest due first. */
+  allocations?: AllocationPayload[];
+}
+
+export interface ClientCredit {
+  currency: string;
+  payments_cents: number;
+  credit_notes_cents: number;
+  total_cents: number;
+}
+
+export function usePayments(params?: {
+  client_id?: string;
+  invoice_id?: string;
+  page?: number;
+  per_page?: number;
+}) {
+  return useQuery({
+    queryKey: ["payments", params],
+    queryFn: async () => {
+      const { data } = await api.get<PaginatedResponse<Payment>>("/payments", { params });
+      return data;
+    },
+  });
+}
+
+export function usePayment(id: string) {
+  return useQuery({
+    queryKey: ["payments", id],
+    queryFn: async () => {
+      const { data } = await api.get<PaymentDetail>(`/payments/${id}`);
+      return data;
+    },
+    enabled: !!id,
+  });
+}
+
+export function useCreatePayment() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: CreatePaymentPayload) => {
+      const { data } = await api.post<PaymentDetail>("/payments", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["payments"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
+
+export function useAllocatePayment(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (allocations?: AllocationPayload[]) => {
+      const { data } = await api.post<PaymentDetail>(`/payments/${id}/allocate`, {
+        allocations,
+      });
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["payments"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
+
+export function useRefundPayment(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: { amount_cents?: number; notes?: string }) => {
+      const { data } = await api.post<PaymentDetail>(`/payments/${id}/refund`, payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["payments"] });
+      queryClient.invalidateQueries({ queryKey: ["invoices"] });
+      queryClient.invalidateQueries({ queryKey: ["client-credit"] });
+    },
+  });
+}
+
+export function useClientCredit(clientId: string) {
+  return useQuery({
+    queryKey: ["client-credit", clientId],
+    queryFn: async () => {
+      const { data } = await api.get<ClientCredit[]>(`/clients/${clientId}/credit`);
+      return data;
+    },
+    enabled: !!clientId,
+  });
+}
@@ -1,11 +1,19 @@
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { api } from "@/lib/api";
 
+export interface PipelineStage {
+  key: string;
+  name: string;
+  next?: string[];
+  required_scorecards?: number;
+  terminal?: boolean;
+}
+
 export interface PipelineTemplate {
   id: string;
   name: string;
   description: string | null;
-  stages: Record<string, unknown>;
+  stages: PipelineStage[];
   is_default: boolean;
   created_at: string;
 }
@@ -13,7 +21,7 @@ export interface PipelineTemplate {
 export interface CreatePipelineTemplatePayload {
   name: string;
   description?: string;
-  stages: Record<string, unknown>;
+  stages: PipelineStage[];
   is_default?: boolean;
 }
 
@@ -1,20 +1,53 @@
 import { useQuery } from "@tanstack/react-query";
 import { api } from "@/lib/api";
+import type { ExchangeRate } from "./use-exchange-rates";
 
 export interface ProfitLossReport {
   period_start: string;
   period_end: string;
+  base_currency: string;
   revenue: { total_cents: number; items: { label: string; amount_cents: number }[] };
   expenses: { total_cents: number; items: { label: string; amount_cents: number }[] };
+  sales_tax_collected: {
+    total_cents: number;
+    items: { label: string; amount_cents: number }[];
+  };
   net_income_cents: number;
+  exchange_rates: ExchangeRate[];
 }
 
 export interface CashFlowReport {
   period_start: string;
   period_end: string;
+  base_currency: string;
   inflows: { month: string; amount_cents: number }[];
   outflows: { month: string; amount_cents: number }[];
   net_cash_flow_cents: number;
+  exchange_rates: ExchangeRate[];
+}
+
+export interface AgingBuckets {
+  /** Not yet due, or without a due date. */
+  current_cents: number;
+  days_0_30_cents: number;
+  days_31_60_cents: number;
+  days_61_90_cents: number;
+  days_over_90_cents: number;
+  total_cents: number;
+}
+
+export interface ArAgingClient extends AgingBuckets {
+  client_id: string;
+  client_name: string;
+  invoice_count: number;
+}
+
+export interface ArAgingReport {
+  as_of: string;
+  base_currency: string;
+  clients: ArAgingClient[];
+  totals: AgingBuckets;
+  exchange_rates: ExchangeRate[];
 }
 
 export interface TeamUtilization {
@@ -45,6 +78,16 @@ export function useCashFlow(params?: { start_date?: string; end_date?: string })
   });
 }
 
+export function useArAging(params?: { as_of?: string; client_id?: string }) {
+  return useQuery({
+    queryKey: ["reports", "ar-aging", params],
+    queryFn: async () => {
+      const { data } = await api.get<ArAgingReport>("/reports/ar-aging", { params });
+      return data;
+    },
+  });
+}
+
 export function useTeamUtilization() {
   return useQuery({
     queryKey: ["reports", "utilization"],
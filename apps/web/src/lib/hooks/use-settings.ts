@@ -8,6 +8,8 @@ export interface FirmSettings {
   tier: string;
   status: string;
   settings: Record<string, unknown>;
+  base_currency: string;
+  locale: string;
   created_at: string;
   updated_at: string;
 }
@@ -46,7 +48,12 @@ export function useFirmSettings() {
 export function useUpdateFirmSettings() {
   const queryClient = useQueryClient();
   return useMutation({
-    mutationFn: async (payload: { name?: string; settings?: Record<string, unknown> }) => {
+    mutationFn: async (payload: {
+      name?: string;
+      settings?: Record<string, unknown>;
+      base_currency?: string;
+      locale?: string;
+    }) => {
       const { data } = await api.put<FirmSettings>("/settings/firm", payload);
       return data;
     },
@@ -0,0 +1,77 @@
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { api } from "@/lib/api";
+
+export interface TaxRate {
+  id: string;
+  tenant_id: string;
+  name: string;
+  jurisdiction: string | null;
+  rate_ppm: number;
+  rate_percent: number;
+  is_compound: boolean;
+  priority: number;
+  exempt_service_types: string[];
+  exempt_client_ids: string[];
+  is_active: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface TaxRatePayload {
+  name: string;
+  jurisdiction?: string | null;
+  rate_percent: number;
+  is_compound?: boolean;
+  priority?: number;
+  exempt_service_types?: string[];
+  exempt_client_ids?: string[];
+  is_active?: boolean;
+}
+
+export function useTaxRates() {
+  return useQuery({
+    queryKey: ["tax-rates"],
+    queryFn: async () => {
+      const { data } = await api.get<TaxRate[]>("/tax-rates");
+      return data;
+    },
+  });
+}
+
+export function useCreateTaxRate() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: TaxRatePayload) => {
+      const { data } = await api.post<TaxRate>("/tax-rates", payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["tax-rates"] });
+    },
+  });
+}
+
+export function useUpdateTaxRate(id: string) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (payload: Partial<TaxRatePayload>) => {
+      const { data } = await api.put<TaxRate>(`/tax-rates/${id}`, payload);
+      return data;
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["tax-rates"] });
+    },
+  });
+}
+
+export function useDeleteTaxRate() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (id: string) => {
+      await api.delete(`/tax-rates/${id}`);
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["tax-rates"] });
+    },
+  });
+}
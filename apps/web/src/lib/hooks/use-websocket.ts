@@ -22,6 +22,7 @@ export type WsEventType =
   | "expense_update"
   | "time_entry_update"
   | "application_update"
+  | "application_moved"
   | "ping"
   | "pong"
   | "connected"
@@ -57,8 +58,9 @@ export function useWebSocket() {
           break;
         case "document_processed":
           queryClient.invalidateQueries({ queryKey: ["documents"] });
-          if (event.data?.name) {
-            toast.success(`Document "${event.data.name}" processed`);
+          queryClient.invalidateQueries({ queryKey: ["candidates"] });
+          if (event.data?.filename) {
+            toast.success(`Document "${event.data.filename}" processed`);
           }
           break;
         case "workflow_update":
@@ -85,6 +87,14 @@ export function useWebSocket() {
         case "time_entry_update":
           queryClient.invalidateQueries({ queryKey: ["time-entries"] });
           break;
+        case "application_moved":
+          queryClient.invalidateQueries({ queryKey: ["applications"] });
+          if (event.data?.job_id) {
+            queryClient.invalidateQueries({
+              queryKey: ["job-board", String(event.data.job_id)],
+            });
+          }
+          break;
         case "connected":
           // Reset reconnect delay on successful connection
           reconnectDelayRef.current = INITIAL_RECONNECT_DELAY;
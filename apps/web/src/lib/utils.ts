@@ -34,6 +34,20 @@ export function exportToCSV(data: Record<string, unknown>[], filename: string) {
   URL.revokeObjectURL(link.href);
 }
 
+/**
+ * Format an amount stored in minor units of `currency` (cents, or whole yen
+ * for JPY) for `locale`.
+ */
+export function formatMoney(minor: number, currency = "USD", locale = "en-US"): string {
+  try {
+    const format = new Intl.NumberFormat(locale, { style: "currency", currency });
+    const digits = format.resolvedOptions().maximumFractionDigits ?? 2;
+    return format.format(minor / 10 ** digits);
+  } catch {
+    return `${(minor / 100).toFixed(2)} ${currency}`;
+  }
+}
+
 export function downloadBlob(blob: Blob, filename: string) {
   const link = document.createElement("a");
   link.href = URL.createObjectURL(blob);
@@ -61,11 +75,13 @@ export function getStatusColor(status: string): string {
     case "cancelled":
       return "bg-red-100 text-red-800 dark:bg-red-900/30 dark:text-red-400";
     case "sent":
+    case "partial":
     case "review":
     case "under_review":
       return "bg-blue-100 text-blue-800 dark:bg-blue-900/30 dark:text-blue-400";
     case "inactive":
     case "archived":
+    case "void":
       return "bg-gray-100 text-gray-800 dark:bg-gray-900/30 dark:text-gray-400";
     default:
       return "bg-gray-100 text-gray-800 dark:bg-gray-900/30 dark:text-gray-400";
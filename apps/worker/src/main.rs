@@ -22,9 +22,8 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Talent OS Worker started");
 
-    // Run workers concurrently
+    // Run workers concurrently. Reminders are fired by the API's job runner.
     tokio::select! {
-        _ = reminder_worker(&db) => {},
         _ = notification_worker(&db) => {},
         _ = usage_meter_worker(&db) => {},
         _ = tokio::signal::ctrl_c() => {
@@ -35,42 +34,6 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Fires pending reminders that are past their fire_at time
-async fn reminder_worker(db: &sqlx::PgPool) {
-    let mut interval = tokio::time::interval(Duration::from_secs(30));
-    loop {
-        interval.tick().await;
-
-        match sqlx::query_as::<_, (uuid::Uuid, uuid::Uuid, String, Option<String>, Option<String>)>(
-            "UPDATE reminder_jobs SET fired = TRUE, fired_at = NOW() \
-             WHERE fired = FALSE AND fire_at <= NOW() \
-             RETURNING id, user_id, reminder_type, title, body"
-        )
-        .fetch_all(db)
-        .await
-        {
-            Ok(reminders) => {
-                for (id, user_id, reminder_type, title, body) in &reminders {
-                    tracing::info!(
-                        reminder_id = %id,
-                        user_id = %user_id,
-                        reminder_type = %reminder_type,
-                        "Fired reminder: {}",
-                        title.as_deref().unwrap_or("(no title)")
-                    );
-                    // In production: dispatch to notification channel (email, push, in-app)
-                }
-                if !reminders.is_empty() {
-                    tracing::info!("Processed {} reminders", reminders.len());
-                }
-            }
-            Err(e) => {
-                tracing::error!("Reminder worker error: {}", e);
-            }
-        }
-    }
-}
-
 /// Processes queued notification events
 async fn notification_worker(db: &sqlx::PgPool) {
     let mut interval = tokio::time::interval(Duration::from_secs(15));